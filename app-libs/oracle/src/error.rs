@@ -18,7 +18,7 @@
 #[cfg(all(not(feature = "std"), feature = "sgx"))]
 use crate::sgx_reexport_prelude::*;
 
-use crate::types::TradingPair;
+use crate::types::{Coordinate, TradingPair};
 use std::{boxed::Box, string::String};
 
 /// Exchange rate error
@@ -34,6 +34,12 @@ pub enum Error {
 	InvalidCryptoCurrencyId,
 	#[error("Invalid id for fiat currency")]
 	InvalidFiatCurrencyId,
+	#[error("Latitude {0} is out of range [-90, 90]")]
+	InvalidLatitude(Coordinate),
+	#[error("Longitude {0} is out of range [-180, 180]")]
+	InvalidLongitude(Coordinate),
+	#[error("Invalid time window [{0}, {1})")]
+	InvalidTimeWindow(u64, u64),
 	#[error(transparent)]
 	Other(#[from] Box<dyn std::error::Error + Sync + Send + 'static>),
 }
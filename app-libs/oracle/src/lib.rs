@@ -36,6 +36,8 @@ use crate::{error::Error, metrics_exporter::MetricsExporter};
 use itp_ocall_api::EnclaveMetricsOCallApi;
 use std::sync::Arc;
 
+pub use itc_rest_client::connection_pool::ConnectionPool;
+
 pub mod error;
 pub mod metrics_exporter;
 pub mod traits;
@@ -67,18 +69,33 @@ pub type OpenMeteoWeatherOracle<OCallApi> =
 
 pub fn create_coin_gecko_oracle<OCallApi: EnclaveMetricsOCallApi>(
 	ocall_api: Arc<OCallApi>,
+	connection_pool: Arc<ConnectionPool>,
 ) -> CoinGeckoExchangeRateOracle<OCallApi> {
-	ExchangeRateOracle::new(CoinGeckoSource {}, Arc::new(MetricsExporter::new(ocall_api)))
+	ExchangeRateOracle::new(
+		CoinGeckoSource {},
+		Arc::new(MetricsExporter::new(ocall_api)),
+		connection_pool,
+	)
 }
 
 pub fn create_coin_market_cap_oracle<OCallApi: EnclaveMetricsOCallApi>(
 	ocall_api: Arc<OCallApi>,
+	connection_pool: Arc<ConnectionPool>,
 ) -> CoinMarketCapExchangeRateOracle<OCallApi> {
-	ExchangeRateOracle::new(CoinMarketCapSource {}, Arc::new(MetricsExporter::new(ocall_api)))
+	ExchangeRateOracle::new(
+		CoinMarketCapSource {},
+		Arc::new(MetricsExporter::new(ocall_api)),
+		connection_pool,
+	)
 }
 
 pub fn create_open_meteo_weather_oracle<OCallApi: EnclaveMetricsOCallApi>(
 	ocall_api: Arc<OCallApi>,
+	connection_pool: Arc<ConnectionPool>,
 ) -> OpenMeteoWeatherOracle<OCallApi> {
-	WeatherOracle::new(WeatherOracleSource {}, Arc::new(MetricsExporter::new(ocall_api)))
+	WeatherOracle::new(
+		WeatherOracleSource {},
+		Arc::new(MetricsExporter::new(ocall_api)),
+		connection_pool,
+	)
 }
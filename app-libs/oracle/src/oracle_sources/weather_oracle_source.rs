@@ -21,15 +21,20 @@ use crate::sgx_reexport_prelude::*;
 use crate::{
 	error::Error,
 	traits::OracleSource,
-	types::{ExchangeRate, TradingPair, WeatherInfo},
+	types::{
+		Aggregation, ExchangeRate, TimeWindow, TradingPair, WeatherInfo, WeatherQuery,
+		WeatherReport, WeatherValue,
+	},
 };
 use itc_rest_client::{
 	http_client::{HttpClient, SendWithCertificateVerification},
 	rest_client::RestClient,
 	RestGet, RestPath,
 };
+use log::debug;
 use serde::{Deserialize, Serialize};
 use std::{
+	collections::BTreeMap,
 	string::{String, ToString},
 	time::Duration,
 	vec::Vec,
@@ -39,17 +44,26 @@ use url::Url;
 const WEATHER_URL: &str = "https://api.open-meteo.com";
 const WEATHER_PARAM_LONGITUDE: &str = "longitude";
 const WEATHER_PARAM_LATITUDE: &str = "latitude";
-// const WEATHER_PARAM_HOURLY: &str = "hourly"; // TODO: Add to Query
+const WEATHER_PARAM_HOURLY: &str = "hourly";
+const WEATHER_PARAM_START_DATE: &str = "start_date";
+const WEATHER_PARAM_END_DATE: &str = "end_date";
+const WEATHER_PARAM_TIME_FORMAT: &str = "timeformat";
+const WEATHER_TIME_FORMAT_UNIX: &str = "unixtime";
 const WEATHER_PATH: &str = "v1/forecast";
 const WEATHER_TIMEOUT: Duration = Duration::from_secs(3u64);
 const WEATHER_ROOT_CERTIFICATE: &str = include_str!("../certificates/open_meteo_root.pem");
 
-// TODO: Change f32 types to appropriate Substrate Fixed Type
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Open-Meteo weather oracle source.
+///
+/// Fetches the hourly samples of the queried variable for all days touched by the query's
+/// time window and aggregates the samples that lie within the window.
 #[derive(Default)]
 pub struct WeatherOracleSource;
 
 impl<OracleSourceInfo: Into<WeatherInfo>> OracleSource<OracleSourceInfo> for WeatherOracleSource {
-	type OracleRequestResult = Result<f32, Error>; // TODO: Change from f32 type
+	type OracleRequestResult = Result<WeatherReport, Error>;
 
 	fn metrics_id(&self) -> String {
 		"weather".to_string()
@@ -76,7 +90,6 @@ impl<OracleSourceInfo: Into<WeatherInfo>> OracleSource<OracleSourceInfo> for Wea
 		Err(Error::NoValidData("None".into(), "None".into()))
 	}
 
-	// TODO: Make this take a variant perhaps or a Closure so that it is more generic
 	fn execute_request(
 		rest_client: &mut RestClient<HttpClient<SendWithCertificateVerification>>,
 		source_info: OracleSourceInfo,
@@ -84,30 +97,108 @@ impl<OracleSourceInfo: Into<WeatherInfo>> OracleSource<OracleSourceInfo> for Wea
 		let weather_info: WeatherInfo = source_info.into();
 		let query = weather_info.weather_query;
 
-		// TODO:
-		// This part is opinionated towards a hard coded query need to make more generic
+		let latitude = query.latitude.to_string();
+		let longitude = query.longitude.to_string();
+		let (start_date, end_date) = query_dates(&query.time_window);
+
 		let response = rest_client
 			.get_with::<String, OpenMeteo>(
 				WEATHER_PATH.into(),
 				&[
-					(WEATHER_PARAM_LATITUDE, &query.latitude),
-					(WEATHER_PARAM_LONGITUDE, &query.longitude),
-					//(WEATHER_PARAM_HOURLY), &query.hourly),
+					(WEATHER_PARAM_LATITUDE, &latitude),
+					(WEATHER_PARAM_LONGITUDE, &longitude),
+					(WEATHER_PARAM_HOURLY, query.variable.open_meteo_id()),
+					(WEATHER_PARAM_START_DATE, &start_date),
+					(WEATHER_PARAM_END_DATE, &end_date),
+					(WEATHER_PARAM_TIME_FORMAT, WEATHER_TIME_FORMAT_UNIX),
 				],
 			)
 			.map_err(Error::RestClient)?;
 
-		let open_meteo_weather_struct = response.0;
+		debug!("open-meteo received response: {:?}", &response);
+		weather_report_from_response(query, response.0)
+	}
+}
+
+/// Extract the samples within the query's time window from the response and aggregate them.
+fn weather_report_from_response(
+	query: WeatherQuery,
+	response: OpenMeteoWeatherStruct,
+) -> Result<WeatherReport, Error> {
+	let no_valid_data = || Error::NoValidData(WEATHER_URL.to_string(), query.clone().key());
+
+	let values = response
+		.hourly
+		.values
+		.get(query.variable.open_meteo_id())
+		.ok_or_else(no_valid_data)?;
+
+	let window = query.time_window;
+	let samples = response
+		.hourly
+		.time
+		.iter()
+		.zip(values.iter())
+		.filter(|(time, _)| window.start <= **time && **time < window.end)
+		.filter_map(|(_, value)| value.and_then(WeatherValue::checked_from_num))
+		.collect::<Vec<_>>();
+
+	let value = aggregate(&samples, query.aggregation).ok_or_else(no_valid_data)?;
+
+	Ok(WeatherReport { weather_query: query, value, number_of_samples: samples.len() as u32 })
+}
 
-		Ok(open_meteo_weather_struct.longitude)
+/// Aggregate the samples, returns `None` if there are no samples or the result overflows.
+fn aggregate(samples: &[WeatherValue], aggregation: Aggregation) -> Option<WeatherValue> {
+	let (first, rest) = samples.split_first()?;
+	match aggregation {
+		Aggregation::Min => samples.iter().min().copied(),
+		Aggregation::Max => samples.iter().max().copied(),
+		Aggregation::Sum => rest.iter().try_fold(*first, |sum, v| sum.checked_add(*v)),
+		Aggregation::Mean => rest
+			.iter()
+			.try_fold(*first, |sum, v| sum.checked_add(*v))?
+			.checked_div(WeatherValue::checked_from_num(samples.len())?),
 	}
 }
 
+/// First and last day (`yyyy-mm-dd`, UTC) touched by the time window `[start, end)`.
+fn query_dates(time_window: &TimeWindow) -> (String, String) {
+	let last_second = time_window.end.saturating_sub(1).max(time_window.start);
+	(iso_date(time_window.start), iso_date(last_second))
+}
+
+/// Format a unix timestamp as an ISO 8601 date (`yyyy-mm-dd`, UTC).
+///
+/// Based on the `civil_from_days` algorithm: http://howardhinnant.github.io/date_algorithms.html
+fn iso_date(unix_timestamp: u64) -> String {
+	let days = unix_timestamp / SECONDS_PER_DAY + 719_468;
+	let era = days / 146_097;
+	let day_of_era = days - era * 146_097;
+	let year_of_era =
+		(day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let mp = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * mp + 2) / 5 + 1;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 };
+	let year = year_of_era + era * 400 + u64::from(month <= 2);
+	format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct OpenMeteoHourly {
+	/// Unix timestamps of the samples.
+	time: Vec<u64>,
+	/// Samples, keyed by the Open-Meteo variable id. Missing samples are `null`.
+	#[serde(flatten)]
+	values: BTreeMap<String, Vec<Option<f64>>>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 struct OpenMeteoWeatherStruct {
 	latitude: f32,
 	longitude: f32,
-	//hourly: String,
+	hourly: OpenMeteoHourly,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -118,3 +209,88 @@ impl RestPath<String> for OpenMeteo {
 		Ok(path)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::types::{Coordinate, WeatherVariable};
+	use core::assert_matches::assert_matches;
+
+	// 2023-06-01T00:00:00Z
+	const JUNE_FIRST: u64 = 1_685_577_600;
+	const HOUR: u64 = 60 * 60;
+
+	fn weather_query(aggregation: Aggregation, time_window: TimeWindow) -> WeatherQuery {
+		WeatherQuery::new(
+			Coordinate::from_num(52.52),
+			Coordinate::from_num(13.41),
+			WeatherVariable::Temperature,
+			time_window,
+			aggregation,
+		)
+		.unwrap()
+	}
+
+	fn response(samples: Vec<Option<f64>>) -> OpenMeteoWeatherStruct {
+		let time = (0..samples.len() as u64).map(|h| JUNE_FIRST + h * HOUR).collect();
+		let values = BTreeMap::from([("temperature_2m".to_string(), samples)]);
+		OpenMeteoWeatherStruct {
+			latitude: 52.52,
+			longitude: 13.41,
+			hourly: OpenMeteoHourly { time, values },
+		}
+	}
+
+	#[test]
+	fn iso_date_works() {
+		assert_eq!(iso_date(0), "1970-01-01");
+		assert_eq!(iso_date(JUNE_FIRST), "2023-06-01");
+		assert_eq!(iso_date(JUNE_FIRST - 1), "2023-05-31");
+		// leap day
+		assert_eq!(iso_date(1_709_164_800), "2024-02-29");
+	}
+
+	#[test]
+	fn query_dates_excludes_end_of_window() {
+		let window = TimeWindow { start: JUNE_FIRST, end: JUNE_FIRST + SECONDS_PER_DAY };
+		assert_eq!(query_dates(&window), ("2023-06-01".to_string(), "2023-06-01".to_string()));
+	}
+
+	#[test]
+	fn aggregate_works() {
+		let samples: Vec<WeatherValue> =
+			[1, -3, 8].iter().map(|v| WeatherValue::from_num(*v)).collect();
+
+		assert_eq!(aggregate(&samples, Aggregation::Min), Some(WeatherValue::from_num(-3)));
+		assert_eq!(aggregate(&samples, Aggregation::Max), Some(WeatherValue::from_num(8)));
+		assert_eq!(aggregate(&samples, Aggregation::Sum), Some(WeatherValue::from_num(6)));
+		assert_eq!(aggregate(&samples, Aggregation::Mean), Some(WeatherValue::from_num(2)));
+		assert_eq!(aggregate(&[], Aggregation::Mean), None);
+	}
+
+	#[test]
+	fn weather_report_only_aggregates_samples_within_window() {
+		let window = TimeWindow { start: JUNE_FIRST + HOUR, end: JUNE_FIRST + 3 * HOUR };
+		let query = weather_query(Aggregation::Sum, window);
+
+		let report = weather_report_from_response(
+			query.clone(),
+			response(vec![Some(100.0), Some(1.5), None, Some(2.5)]),
+		)
+		.unwrap();
+
+		assert_eq!(report.weather_query, query);
+		assert_eq!(report.value, WeatherValue::from_num(1.5));
+		assert_eq!(report.number_of_samples, 1);
+	}
+
+	#[test]
+	fn weather_report_without_samples_fails() {
+		let window = TimeWindow { start: JUNE_FIRST + 10 * HOUR, end: JUNE_FIRST + 11 * HOUR };
+		let query = weather_query(Aggregation::Mean, window);
+
+		let result = weather_report_from_response(query, response(vec![Some(1.0), Some(2.0)]));
+
+		assert_matches!(result, Err(Error::NoValidData(_, _)));
+	}
+}
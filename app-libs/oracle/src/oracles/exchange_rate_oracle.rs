@@ -20,13 +20,13 @@ use crate::sgx_reexport_prelude::*;
 
 use crate::{
 	metrics_exporter::ExportMetrics,
+	oracles::create_send,
 	traits::OracleSource,
-	types::{ExchangeRate, TradingInfo, TradingPair},
+	types::{ExchangeRate, ResponseTranscript, TradingInfo, TradingPair},
 	Error,
 };
 use itc_rest_client::{
-	http_client::{HttpClient, SendWithCertificateVerification},
-	rest_client::RestClient,
+	connection_pool::ConnectionPool, http_client::HttpClient, rest_client::RestClient,
 };
 use log::*;
 use std::{
@@ -40,17 +40,34 @@ use url::Url;
 pub struct ExchangeRateOracle<OracleSourceType, MetricsExporter> {
 	oracle_source: OracleSourceType,
 	metrics_exporter: Arc<MetricsExporter>,
+	/// Keep-alive connections, shared with the other oracles.
+	connection_pool: Arc<ConnectionPool>,
 }
 
-impl<OracleSourceType, MetricsExporter> ExchangeRateOracle<OracleSourceType, MetricsExporter> {
-	pub fn new(oracle_source: OracleSourceType, metrics_exporter: Arc<MetricsExporter>) -> Self {
-		ExchangeRateOracle { oracle_source, metrics_exporter }
+impl<OracleSourceType, MetricsExporter> ExchangeRateOracle<OracleSourceType, MetricsExporter>
+where
+	OracleSourceType: OracleSource<TradingInfo>,
+{
+	pub fn new(
+		oracle_source: OracleSourceType,
+		metrics_exporter: Arc<MetricsExporter>,
+		connection_pool: Arc<ConnectionPool>,
+	) -> Self {
+		ExchangeRateOracle { oracle_source, metrics_exporter, connection_pool }
+	}
+
+	pub fn get_base_url(&self) -> Result<Url, Error> {
+		self.oracle_source.base_url()
 	}
 }
 
 pub trait GetExchangeRate {
-	/// Get the cryptocurrency/fiat_currency exchange rate
-	fn get_exchange_rate(&self, trading_pair: TradingPair) -> Result<(ExchangeRate, Url), Error>;
+	/// Get the cryptocurrency/fiat_currency exchange rate, together with the source URL
+	/// and the transcript of the response the rate was taken from.
+	fn get_exchange_rate(
+		&self,
+		trading_pair: TradingPair,
+	) -> Result<(ExchangeRate, Url, Option<ResponseTranscript>), Error>;
 }
 
 impl<OracleSourceType, MetricsExporter> GetExchangeRate
@@ -59,7 +76,10 @@ where
 	OracleSourceType: OracleSource<TradingInfo>,
 	MetricsExporter: ExportMetrics<TradingInfo>,
 {
-	fn get_exchange_rate(&self, trading_pair: TradingPair) -> Result<(ExchangeRate, Url), Error> {
+	fn get_exchange_rate(
+		&self,
+		trading_pair: TradingPair,
+	) -> Result<(ExchangeRate, Url, Option<ResponseTranscript>), Error> {
 		let source_id = self.oracle_source.metrics_id();
 		self.metrics_exporter.increment_number_requests(source_id.clone());
 
@@ -70,7 +90,7 @@ where
 		debug!("Get exchange rate from URI: {}, trading pair: {:?}", base_url, trading_pair);
 
 		let http_client = HttpClient::new(
-			SendWithCertificateVerification::new(root_certificates),
+			create_send(root_certificates, self.connection_pool.clone()),
 			true,
 			request_timeout,
 			None,
@@ -100,7 +120,7 @@ where
 					);
 
 					debug!("Successfully executed exchange rate request");
-					break Ok((exchange_rate, base_url))
+					break Ok((exchange_rate, base_url, rest_client.response_transcript()))
 				},
 				Err(e) =>
 					if tries < number_of_tries {
@@ -139,7 +159,8 @@ mod tests {
 
 		let trading_pair =
 			TradingPair { crypto_currency: "BTC".to_string(), fiat_currency: "USD".to_string() };
-		let _bit_usd = test_client.get_exchange_rate(trading_pair.clone()).unwrap();
+		let (_bit_usd, _, transcript) =
+			test_client.get_exchange_rate(trading_pair.clone()).unwrap();
 
 		assert_eq!(1, metrics_exporter.get_number_request());
 		assert_eq!(1, metrics_exporter.get_response_times().len());
@@ -150,5 +171,7 @@ mod tests {
 
 		assert_eq!(trading_pair, metric_trading_pair);
 		assert_eq!(ExchangeRate::from_num(42.3f32), exchange_rate);
+		// The mocked source does not send any request.
+		assert!(transcript.is_none());
 	}
 }
@@ -18,27 +18,38 @@
 #[cfg(all(not(feature = "std"), feature = "sgx"))]
 use crate::sgx_reexport_prelude::*;
 
-use crate::{metrics_exporter::ExportMetrics, traits::OracleSource, types::WeatherInfo, Error};
+use crate::{
+	metrics_exporter::ExportMetrics,
+	oracles::create_send,
+	traits::OracleSource,
+	types::{ResponseTranscript, WeatherInfo, WeatherReport},
+	Error,
+};
 use itc_rest_client::{
-	http_client::{HttpClient, SendWithCertificateVerification},
-	rest_client::RestClient,
+	connection_pool::ConnectionPool, http_client::HttpClient, rest_client::RestClient,
 };
 use log::*;
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 use url::Url;
 
 #[allow(unused)]
 pub struct WeatherOracle<OracleSourceType, MetricsExporter> {
 	oracle_source: OracleSourceType,
 	metrics_exporter: Arc<MetricsExporter>,
+	/// Keep-alive connections, shared with the other oracles.
+	connection_pool: Arc<ConnectionPool>,
 }
 
 impl<OracleSourceType, MetricsExporter> WeatherOracle<OracleSourceType, MetricsExporter>
 where
 	OracleSourceType: OracleSource<WeatherInfo>,
 {
-	pub fn new(oracle_source: OracleSourceType, metrics_exporter: Arc<MetricsExporter>) -> Self {
-		WeatherOracle { oracle_source, metrics_exporter }
+	pub fn new(
+		oracle_source: OracleSourceType,
+		metrics_exporter: Arc<MetricsExporter>,
+		connection_pool: Arc<ConnectionPool>,
+	) -> Self {
+		WeatherOracle { oracle_source, metrics_exporter, connection_pool }
 	}
 
 	pub fn get_base_url(&self) -> Result<Url, Error> {
@@ -46,38 +57,53 @@ where
 	}
 }
 
-pub trait GetLongitude {
-	type LongitudeResult;
-	fn get_longitude(&self, weather_info: WeatherInfo) -> Self::LongitudeResult;
+pub trait GetWeather {
+	/// Get the queried weather variable, aggregated over the query's time window,
+	/// together with the transcript of the response the report was computed from.
+	///
+	/// The query is expected to have been checked with `WeatherQuery::validate`.
+	fn get_weather(
+		&self,
+		weather_info: WeatherInfo,
+	) -> Result<(WeatherReport, Option<ResponseTranscript>), Error>;
 }
 
-impl<OracleSourceType, MetricsExporter> GetLongitude
+impl<OracleSourceType, MetricsExporter> GetWeather
 	for WeatherOracle<OracleSourceType, MetricsExporter>
 where
-	OracleSourceType: OracleSource<WeatherInfo, OracleRequestResult = Result<f32, Error>>,
+	OracleSourceType: OracleSource<WeatherInfo, OracleRequestResult = Result<WeatherReport, Error>>,
 	MetricsExporter: ExportMetrics<WeatherInfo>,
 {
-	type LongitudeResult = Result<f32, Error>;
-
-	fn get_longitude(&self, weather_info: WeatherInfo) -> Self::LongitudeResult {
-		let query = weather_info.weather_query.clone();
+	fn get_weather(
+		&self,
+		weather_info: WeatherInfo,
+	) -> Result<(WeatherReport, Option<ResponseTranscript>), Error> {
+		let source_id = self.oracle_source.metrics_id();
+		self.metrics_exporter.increment_number_requests(source_id.clone());
 
 		let base_url = self.oracle_source.base_url()?;
 		let root_certificates = self.oracle_source.root_certificates_content();
 
-		debug!("Get longitude from URI: {}, query: {:?}", base_url, query);
+		debug!("Get weather from URI: {}, query: {:?}", base_url, weather_info.weather_query);
 
 		let http_client = HttpClient::new(
-			SendWithCertificateVerification::new(root_certificates),
+			create_send(root_certificates, self.connection_pool.clone()),
 			true,
 			self.oracle_source.request_timeout(),
 			None,
 			None,
 		);
 		let mut rest_client = RestClient::new(http_client, base_url);
-		<OracleSourceType as OracleSource<WeatherInfo>>::execute_request(
+
+		let timer_start = Instant::now();
+		let report = <OracleSourceType as OracleSource<WeatherInfo>>::execute_request(
 			&mut rest_client,
-			weather_info,
-		)
+			weather_info.clone(),
+		)?;
+
+		self.metrics_exporter.record_response_time(source_id.clone(), timer_start);
+		self.metrics_exporter.update_weather(source_id, weather_info);
+
+		Ok((report, rest_client.response_transcript()))
 	}
 }
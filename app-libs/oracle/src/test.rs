@@ -27,13 +27,20 @@ use crate::{
 	},
 	oracles::{
 		exchange_rate_oracle::{ExchangeRateOracle, GetExchangeRate},
-		weather_oracle::{GetLongitude, WeatherOracle},
+		weather_oracle::{GetWeather, WeatherOracle},
 	},
 	traits::OracleSource,
-	types::{TradingInfo, TradingPair, WeatherInfo, WeatherQuery},
+	types::{
+		Aggregation, Coordinate, TimeWindow, TradingInfo, TradingPair, WeatherInfo, WeatherQuery,
+		WeatherVariable,
+	},
 };
 use core::assert_matches::assert_matches;
-use std::sync::Arc;
+use itc_rest_client::connection_pool::ConnectionPool;
+use std::{
+	sync::Arc,
+	time::{SystemTime, UNIX_EPOCH},
+};
 use substrate_fixed::transcendental::ZERO;
 
 type TestOracle<OracleSource> = ExchangeRateOracle<OracleSource, MetricsExporterMock>;
@@ -52,16 +59,40 @@ fn get_exchange_rate_from_coin_gecko_works() {
 }
 
 #[test]
-fn get_longitude_from_open_meteo_works() {
+fn get_temperature_from_open_meteo_works() {
 	let oracle = create_weather_oracle::<WeatherOracleSource>();
-	let weather_query =
-		WeatherQuery { latitude: "52.52".into(), longitude: "13.41".into(), hourly: "none".into() };
-	// Todo: hourly param is temperature_2m to get temp or relativehumidity_2m to get humidity
-	let weather_info = WeatherInfo { weather_query };
-	let expected_longitude = 13.41f32;
-	let response_longitude =
-		oracle.get_longitude(weather_info).expect("Can grab longitude from oracle");
-	assert!((response_longitude - expected_longitude) < 0.5);
+	let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+	let one_day = 24 * 60 * 60;
+	let weather_query = WeatherQuery::new(
+		Coordinate::from_num(52.52),
+		Coordinate::from_num(13.41),
+		WeatherVariable::Temperature,
+		TimeWindow { start: now - one_day, end: now },
+		Aggregation::Mean,
+	)
+	.unwrap();
+	let weather_info = WeatherInfo { weather_query: weather_query.clone() };
+
+	let (report, transcript) =
+		oracle.get_weather(weather_info).expect("Can grab temperature from oracle");
+
+	assert_eq!(report.weather_query, weather_query);
+	assert!(transcript.unwrap().server_certificate_fingerprint.is_some());
+	assert!(report.number_of_samples > 0);
+	assert!(report.value > -60 && report.value < 60);
+}
+
+#[test]
+fn weather_query_with_invalid_latitude_is_invalid() {
+	let weather_query = WeatherQuery {
+		latitude: Coordinate::from_num(91),
+		longitude: Coordinate::from_num(13.41),
+		variable: WeatherVariable::Precipitation,
+		time_window: TimeWindow { start: 0, end: 3600 },
+		aggregation: Aggregation::Sum,
+	};
+
+	assert_matches!(weather_query.validate(), Err(Error::InvalidLatitude(_)));
 }
 
 #[test]
@@ -77,13 +108,21 @@ fn get_exchange_rate_for_undefined_coin_gecko_crypto_currency_fails() {
 fn create_weather_oracle<OracleSourceType: OracleSource<WeatherInfo>>(
 ) -> TestWeatherOracle<OracleSourceType> {
 	let oracle_source = OracleSourceType::default();
-	WeatherOracle::new(oracle_source, Arc::new(MetricsExporterMock::default()))
+	WeatherOracle::new(
+		oracle_source,
+		Arc::new(MetricsExporterMock::default()),
+		Arc::new(ConnectionPool::default()),
+	)
 }
 
 fn create_exchange_rate_oracle<OracleSourceType: OracleSource<TradingInfo>>(
 ) -> TestOracle<OracleSourceType> {
 	let oracle_source = OracleSourceType::default();
-	ExchangeRateOracle::new(oracle_source, Arc::new(MetricsExporterMock::default()))
+	ExchangeRateOracle::new(
+		oracle_source,
+		Arc::new(MetricsExporterMock::default()),
+		Arc::new(ConnectionPool::default()),
+	)
 }
 
 fn get_exchange_rate_for_undefined_crypto_currency_fails<
@@ -15,28 +15,114 @@
 
 */
 
+use crate::error::Error;
 use codec::{Decode, Encode};
 use std::string::String;
-use substrate_fixed::types::U32F32;
+use substrate_fixed::types::{I32F32, U32F32};
+
+pub use itc_rest_client::transcript::ResponseTranscript;
+
+/// Maximal length of a weather query time window, in seconds (31 days).
+pub const MAX_WEATHER_TIME_WINDOW: u64 = 31 * 24 * 60 * 60;
 
 #[derive(Debug, Clone, Encode, Decode, Eq, PartialEq)]
 pub struct WeatherInfo {
 	pub weather_query: WeatherQuery,
 }
 
+/// Weather variables that can be queried from the weather oracle.
+#[derive(Debug, Clone, Copy, Encode, Decode, Eq, PartialEq)]
+pub enum WeatherVariable {
+	/// Air temperature 2m above ground in [°C].
+	Temperature,
+	/// Sum of rain, showers and snow of the preceding hour in [mm].
+	Precipitation,
+	/// Wind speed 10m above ground in [km/h].
+	WindSpeed,
+}
+
+impl WeatherVariable {
+	/// Identifier of the hourly variable in the Open-Meteo API.
+	pub fn open_meteo_id(&self) -> &'static str {
+		match self {
+			WeatherVariable::Temperature => "temperature_2m",
+			WeatherVariable::Precipitation => "precipitation",
+			WeatherVariable::WindSpeed => "windspeed_10m",
+		}
+	}
+}
+
+/// How the hourly samples of a time window are combined into a single value.
+#[derive(Debug, Clone, Copy, Encode, Decode, Eq, PartialEq)]
+pub enum Aggregation {
+	Mean,
+	Min,
+	Max,
+	Sum,
+}
+
+/// Time window `[start, end)`, given in unix timestamps [s].
+#[derive(Debug, Clone, Copy, Encode, Decode, Eq, PartialEq)]
+pub struct TimeWindow {
+	pub start: u64,
+	pub end: u64,
+}
+
 #[derive(Debug, Clone, Encode, Decode, Eq, PartialEq)]
 pub struct WeatherQuery {
-	pub longitude: String,
-	pub latitude: String,
-	pub hourly: String,
+	pub latitude: Coordinate,
+	pub longitude: Coordinate,
+	pub variable: WeatherVariable,
+	pub time_window: TimeWindow,
+	pub aggregation: Aggregation,
 }
 
 impl WeatherQuery {
+	pub fn new(
+		latitude: Coordinate,
+		longitude: Coordinate,
+		variable: WeatherVariable,
+		time_window: TimeWindow,
+		aggregation: Aggregation,
+	) -> Result<Self, Error> {
+		let query = WeatherQuery { latitude, longitude, variable, time_window, aggregation };
+		query.validate()?;
+		Ok(query)
+	}
+
+	/// Check the coordinate ranges and the time window.
+	///
+	/// Needs to be called explicitly on queries that were decoded, e.g. when received over the FFI.
+	pub fn validate(&self) -> Result<(), Error> {
+		if self.latitude < Coordinate::from_num(-90) || self.latitude > Coordinate::from_num(90) {
+			return Err(Error::InvalidLatitude(self.latitude))
+		}
+		if self.longitude < Coordinate::from_num(-180) || self.longitude > Coordinate::from_num(180)
+		{
+			return Err(Error::InvalidLongitude(self.longitude))
+		}
+		let window = self.time_window;
+		if window.start >= window.end || window.end - window.start > MAX_WEATHER_TIME_WINDOW {
+			return Err(Error::InvalidTimeWindow(window.start, window.end))
+		}
+		Ok(())
+	}
+
 	pub fn key(self) -> String {
-		format!("{}/{}", self.latitude, self.longitude)
+		format!("{}/{}/{}", self.latitude, self.longitude, self.variable.open_meteo_id())
 	}
 }
 
+/// Aggregated weather data, as it is reported to the parentchain.
+#[derive(Debug, Clone, Encode, Decode, Eq, PartialEq)]
+pub struct WeatherReport {
+	pub weather_query: WeatherQuery,
+	/// Aggregated value, in the unit of the queried [`WeatherVariable`].
+	pub value: WeatherValue,
+	/// Number of hourly samples that were aggregated.
+	pub number_of_samples: u32,
+}
+
 #[derive(Debug, Clone, Encode, Decode, Eq, PartialEq)]
 pub struct TradingInfo {
 	pub trading_pair: TradingPair,
@@ -53,9 +139,24 @@ impl TradingPair {
 	pub fn key(self) -> String {
 		format!("{}/{}", self.crypto_currency, self.fiat_currency)
 	}
+
+	/// Inverse of [`TradingPair::key`].
+	pub fn from_key(key: &str) -> Option<Self> {
+		let (crypto_currency, fiat_currency) = key.split_once('/')?;
+		if crypto_currency.is_empty() || fiat_currency.is_empty() || fiat_currency.contains('/') {
+			return None
+		}
+		Some(TradingPair {
+			crypto_currency: crypto_currency.into(),
+			fiat_currency: fiat_currency.into(),
+		})
+	}
 }
 
 /// TODO Fix https://github.com/integritee-network/pallets/issues/71 and get it from https://github.com/integritee-network/pallets.git
 /// Teeracle types
 pub type ExchangeRate = U32F32;
-// pub type Coordinate = U32F32;
+/// Coordinate in decimal degrees.
+pub type Coordinate = I32F32;
+/// Value of a weather variable, see [`WeatherVariable`] for the units.
+pub type WeatherValue = I32F32;
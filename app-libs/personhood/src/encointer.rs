@@ -0,0 +1,331 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Personhood from Encointer ceremonies.
+//!
+//! Participants of an Encointer ceremony attest each other's personhood in physical meetups.
+//! Each attended ceremony yields a verified `ParticipantReputation` for the participant's
+//! account in the community of the meetup.
+
+use crate::{Confidence, PersonhoodAssessment, VerifiedAt, VerifiedStorage};
+use core::cmp::min;
+use encointer_primitives::{
+	ceremonies::Reputation,
+	communities::{CommunityIdentifier, CommunityMetadata},
+	scheduler::CeremonyIndexType,
+};
+use itp_storage::{storage_double_map_key, storage_map_key, storage_value_key, StorageHasher};
+use itp_types::{
+	personhood::{
+		ENCOINTER_CEREMONIES_PALLET, ENCOINTER_COMMUNITIES_PALLET, ENCOINTER_SCHEDULER_PALLET,
+	},
+	AccountId, H256,
+};
+use std::{string::String, vec, vec::Vec};
+
+/// Number of past ceremonies whose reputations are taken into account.
+pub const REPUTATION_LOOKBACK: CeremonyIndexType = 5;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncointerSubject {
+	pub cid: CommunityIdentifier,
+	/// Current ceremony index, the reputations of the ceremonies before it are considered.
+	/// Assessments use the index read from the source's verified storage instead.
+	pub cindex: CeremonyIndexType,
+	pub account: AccountId,
+}
+
+impl EncointerSubject {
+	/// The subject at the ceremony with index `cindex`.
+	pub fn at_ceremony(&self, cindex: CeremonyIndexType) -> Self {
+		Self { cindex, ..self.clone() }
+	}
+
+	/// Ceremonies whose reputations are considered, the newest first.
+	pub fn ceremony_indexes(&self) -> Vec<CeremonyIndexType> {
+		(1..=min(REPUTATION_LOOKBACK, self.cindex)).map(|i| self.cindex - i).collect()
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncointerEvidence {
+	/// Genesis hash of the parentchain the community is registered on. A community identifier
+	/// is only unique per chain, so badges name the chain they have been issued for.
+	pub source: Option<H256>,
+	/// A community that is not registered can't attest personhood.
+	pub community_registered: bool,
+	/// Reputations of the subject's ceremonies, in the order of
+	/// [`EncointerSubject::ceremony_indexes`].
+	pub reputations: Vec<(CeremonyIndexType, Reputation)>,
+}
+
+/// Reads the Encointer pallets of a parentchain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncointerCeremonies {
+	communities_pallet: String,
+	ceremonies_pallet: String,
+	scheduler_pallet: String,
+}
+
+impl Default for EncointerCeremonies {
+	fn default() -> Self {
+		Self::new(
+			ENCOINTER_COMMUNITIES_PALLET.into(),
+			ENCOINTER_CEREMONIES_PALLET.into(),
+			ENCOINTER_SCHEDULER_PALLET.into(),
+		)
+	}
+}
+
+impl EncointerCeremonies {
+	/// Reads the Encointer pallets under the names they have in the parentchain's runtime.
+	pub fn new(
+		communities_pallet: String,
+		ceremonies_pallet: String,
+		scheduler_pallet: String,
+	) -> Self {
+		Self { communities_pallet, ceremonies_pallet, scheduler_pallet }
+	}
+
+	pub fn current_ceremony_index_key(&self) -> Vec<u8> {
+		storage_value_key(&self.scheduler_pallet, "CurrentCeremonyIndex")
+	}
+
+	pub fn community_metadata_key(&self, cid: &CommunityIdentifier) -> Vec<u8> {
+		storage_map_key(
+			&self.communities_pallet,
+			"CommunityMetadata",
+			cid,
+			&StorageHasher::Blake2_128Concat,
+		)
+	}
+
+	pub fn reputation_key(
+		&self,
+		cid: CommunityIdentifier,
+		cindex: CeremonyIndexType,
+		account: &AccountId,
+	) -> Vec<u8> {
+		storage_double_map_key(
+			&self.ceremonies_pallet,
+			"ParticipantReputation",
+			&(cid, cindex),
+			&StorageHasher::Blake2_128Concat,
+			account,
+			&StorageHasher::Blake2_128Concat,
+		)
+	}
+
+	/// Metadata of the community, `None` if it is not registered.
+	pub fn read_community_metadata<S: VerifiedStorage>(
+		&self,
+		storage: &S,
+		cid: &CommunityIdentifier,
+	) -> Result<(Option<CommunityMetadata>, VerifiedAt), S::Error> {
+		let (mut metadata, verified_at) =
+			storage.read_verified(vec![self.community_metadata_key(cid)])?;
+		Ok((metadata.pop().flatten(), verified_at))
+	}
+
+	/// Index of the current ceremony, `None` if the scheduler has no ceremony index.
+	pub fn read_current_ceremony_index<S: VerifiedStorage>(
+		&self,
+		storage: &S,
+	) -> Result<(Option<CeremonyIndexType>, VerifiedAt), S::Error> {
+		let (mut cindex, verified_at) =
+			storage.read_verified(vec![self.current_ceremony_index_key()])?;
+		Ok((cindex.pop().flatten(), verified_at))
+	}
+
+	/// Reputations of `account` in the given ceremonies of the community.
+	pub fn read_reputations<S: VerifiedStorage>(
+		&self,
+		storage: &S,
+		cid: CommunityIdentifier,
+		cindexes: &[CeremonyIndexType],
+		account: &AccountId,
+	) -> Result<(Vec<Reputation>, VerifiedAt), S::Error> {
+		let storage_keys = cindexes
+			.iter()
+			.map(|cindex| self.reputation_key(cid, *cindex, account))
+			.collect();
+		let (reputations, verified_at) = storage.read_verified::<Reputation>(storage_keys)?;
+		// `ParticipantReputation` is a `ValueQuery`, a missing entry is the default.
+		Ok((
+			reputations
+				.into_iter()
+				.map(|reputation| reputation.unwrap_or(Reputation::Unverified))
+				.collect(),
+			verified_at,
+		))
+	}
+
+	/// Assesses the evidence read for the subject. Does not access storage, the subject's
+	/// ceremony index must have been read with [`Self::read_current_ceremony_index`].
+	pub fn assess(
+		&self,
+		subject: &EncointerSubject,
+		evidence: &EncointerEvidence,
+	) -> PersonhoodAssessment {
+		let verified_cindexes: Vec<CeremonyIndexType> = evidence
+			.reputations
+			.iter()
+			.filter(|(_, reputation)| {
+				evidence.source.is_some()
+					&& evidence.community_registered
+					&& reputation.is_verified()
+			})
+			.map(|(cindex, _)| *cindex)
+			.collect();
+		let attestations = verified_cindexes.len() as u32;
+
+		PersonhoodAssessment {
+			is_attested: attestations > 0,
+			evidence_age: verified_cindexes
+				.iter()
+				.max()
+				.map(|newest| subject.cindex.saturating_sub(*newest)),
+			attestations,
+			confidence: match attestations {
+				0 => Confidence::None,
+				1 => Confidence::Low,
+				2 | 3 => Confidence::Medium,
+				_ => Confidence::High,
+			},
+			source: evidence.source,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::{Decode, Encode};
+
+	fn subject(cindex: CeremonyIndexType) -> EncointerSubject {
+		EncointerSubject {
+			cid: CommunityIdentifier::default(),
+			cindex,
+			account: AccountId::new([1u8; 32]),
+		}
+	}
+
+	/// Storage with a single value, verified at block 42.
+	struct SingleValueStorage {
+		key: Vec<u8>,
+		value: Vec<u8>,
+	}
+
+	impl VerifiedStorage for SingleValueStorage {
+		type Error = codec::Error;
+
+		fn read_verified<V: Decode>(
+			&self,
+			storage_keys: Vec<Vec<u8>>,
+		) -> Result<(Vec<Option<V>>, VerifiedAt), Self::Error> {
+			let values = storage_keys
+				.iter()
+				.map(|key| match key == &self.key {
+					true => V::decode(&mut self.value.as_slice()).map(Some),
+					false => Ok(None),
+				})
+				.collect::<Result<_, _>>()?;
+			Ok((values, 42))
+		}
+
+		fn genesis_hash(&self) -> Result<H256, Self::Error> {
+			Ok(H256::repeat_byte(1))
+		}
+	}
+
+	#[test]
+	fn current_ceremony_index_is_read_from_the_scheduler_pallet() {
+		let ceremonies =
+			EncointerCeremonies::new("Communities".into(), "Ceremonies".into(), "Scheduler".into());
+		let storage = SingleValueStorage {
+			key: storage_value_key("Scheduler", "CurrentCeremonyIndex"),
+			value: 10u32.encode(),
+		};
+
+		assert_eq!(ceremonies.read_current_ceremony_index(&storage).unwrap(), (Some(10), 42));
+		assert_eq!(
+			EncointerCeremonies::default().read_current_ceremony_index(&storage).unwrap(),
+			(None, 42)
+		);
+	}
+
+	#[test]
+	fn ceremony_indexes_are_bounded_by_lookback_and_first_ceremony() {
+		assert_eq!(subject(10).ceremony_indexes(), vec![9, 8, 7, 6, 5]);
+		assert_eq!(subject(3).ceremony_indexes(), vec![2, 1, 0]);
+	}
+
+	#[test]
+	fn verified_reputations_attest_personhood() {
+		let evidence = EncointerEvidence {
+			source: Some(H256::repeat_byte(1)),
+			community_registered: true,
+			reputations: vec![
+				(9, Reputation::Unverified),
+				(8, Reputation::VerifiedUnlinked),
+				(7, Reputation::VerifiedUnlinked),
+			],
+		};
+
+		let assessment = EncointerCeremonies::default().assess(&subject(10), &evidence);
+
+		assert_eq!(
+			assessment,
+			PersonhoodAssessment {
+				is_attested: true,
+				evidence_age: Some(2),
+				attestations: 2,
+				confidence: Confidence::Medium,
+				source: Some(H256::repeat_byte(1)),
+			}
+		);
+	}
+
+	#[test]
+	fn unverified_reputations_do_not_attest_personhood() {
+		let evidence = EncointerEvidence {
+			source: Some(H256::repeat_byte(1)),
+			community_registered: true,
+			reputations: vec![(9, Reputation::Unverified), (8, Reputation::UnverifiedReputable)],
+		};
+
+		let assessment = EncointerCeremonies::default().assess(&subject(10), &evidence);
+
+		assert!(!assessment.is_attested);
+		assert_eq!(assessment.evidence_age, None);
+		assert_eq!(assessment.confidence, Confidence::None);
+	}
+
+	#[test]
+	fn unregistered_community_does_not_attest_personhood() {
+		let evidence = EncointerEvidence {
+			source: None,
+			community_registered: false,
+			reputations: vec![(9, Reputation::VerifiedUnlinked)],
+		};
+
+		let assessment = EncointerCeremonies::default().assess(&subject(10), &evidence);
+
+		assert!(!assessment.is_attested);
+		assert_eq!(assessment.attestations, 0);
+	}
+}
@@ -0,0 +1,122 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Proof-of-personhood backends of the personhood oracle.
+//!
+//! A [`PersonhoodSource`] reads evidence of a subject's personhood from verified parentchain
+//! storage and assesses it in terms that are common to all backends: whether the subject's
+//! personhood is attested, how fresh the evidence is and how confident the source is. Badges and
+//! attestations are issued based on that assessment only, so that further backends, e.g.
+//! identity registrars with judgements or graph attestations, can be added without changing
+//! the issuance.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(all(feature = "std", feature = "sgx"))]
+compile_error!("feature \"std\" and feature \"sgx\" cannot be enabled at the same time");
+
+#[cfg(all(not(feature = "std"), feature = "sgx"))]
+#[macro_use]
+extern crate sgx_tstd as std;
+
+use codec::{Decode, Encode};
+use core::fmt::Debug;
+use itp_types::{AccountId, H256};
+use std::{string::String, vec::Vec};
+
+pub mod encointer;
+
+pub use encointer::{EncointerCeremonies, EncointerEvidence, EncointerSubject};
+
+/// Number of the parentchain block that storage values have been verified against.
+pub type VerifiedAt = u64;
+
+/// Storage of a parentchain, read with a storage proof against a finalized header.
+pub trait VerifiedStorage {
+	type Error: Debug;
+
+	/// Reads the values at `storage_keys`, `None` for a key without value. Returns the number
+	/// of the header the values have been verified against.
+	fn read_verified<V: Decode>(
+		&self,
+		storage_keys: Vec<Vec<u8>>,
+	) -> Result<(Vec<Option<V>>, VerifiedAt), Self::Error>;
+
+	/// Genesis hash of the parentchain, which identifies the chain the values are read from.
+	fn genesis_hash(&self) -> Result<H256, Self::Error>;
+}
+
+/// Whose personhood is checked, together with the context a source needs to look it up.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PersonhoodSubject {
+	/// A participant of the ceremonies of an Encointer community.
+	Encointer(EncointerSubject),
+}
+
+impl PersonhoodSubject {
+	pub fn account(&self) -> &AccountId {
+		match self {
+			PersonhoodSubject::Encointer(subject) => &subject.account,
+		}
+	}
+}
+
+#[derive(Debug)]
+pub enum Error {
+	/// The source can't check the personhood of this kind of subject.
+	UnsupportedSubject,
+	/// The source's storage could not be read.
+	Storage(String),
+}
+
+/// A proof-of-personhood backend, which reads the evidence from the storage it has been set up
+/// with. The oracle holds its backends as trait objects and issues badges based on the
+/// assessment only.
+pub trait PersonhoodSource {
+	/// Whether the subject is known to the source, e.g. whether the subject's community is
+	/// registered on the source's parentchain.
+	fn knows(&self, subject: &PersonhoodSubject) -> Result<bool, Error>;
+
+	/// Reads the evidence of the subject's personhood and assesses it.
+	fn assess(&self, subject: &PersonhoodSubject) -> Result<PersonhoodAssessment, Error>;
+}
+
+/// Outcome of a personhood check, comparable across sources.
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PersonhoodAssessment {
+	/// The source attests the subject's personhood, e.g. by the subject's attendance of a
+	/// ceremony. Sources can't tell whether a person holds further accounts, so this does not
+	/// imply that the subject is unique.
+	pub is_attested: bool,
+	/// Age of the newest attestation, in periods of the source, e.g. ceremonies. `None` if
+	/// there is no attestation.
+	pub evidence_age: Option<u32>,
+	/// Number of independent attestations the assessment is based on.
+	pub attestations: u32,
+	pub confidence: Confidence,
+	/// Genesis hash of the parentchain the evidence has been read from, `None` if the source
+	/// has no evidence of the subject.
+	pub source: Option<H256>,
+}
+
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Confidence {
+	None,
+	Low,
+	Medium,
+	High,
+}
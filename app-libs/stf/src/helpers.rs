@@ -73,6 +73,30 @@ pub fn get_storage_by_key_hash<V: Decode>(key: Vec<u8>) -> Option<V> {
 	}
 }
 
+/// Whether the value of the state `key` is public, i.e. it may be proven to anyone, e.g. with
+/// the `state_getReadProof` RPC. All other state is only accessible with trusted getters.
+///
+/// Public are a few storage values and all entries of the storage maps that hold account and
+/// balance data, so a client can prove the balance of an account against a sidechain header.
+pub fn is_public_state_key(key: &[u8]) -> bool {
+	let public_values = [
+		storage_value_key("System", "Number"),
+		storage_value_key("Timestamp", "Now"),
+		storage_value_key("Balances", "TotalIssuance"),
+		storage_value_key("Balances", "ExistentialDeposit"),
+	];
+	let public_map_prefixes = [
+		storage_value_key("System", "Account"),
+		storage_value_key("Balances", "Locks"),
+		storage_value_key("Balances", "Reserves"),
+	];
+
+	public_values.iter().any(|public_key| public_key.as_slice() == key)
+		|| public_map_prefixes
+			.iter()
+			.any(|prefix| key.len() > prefix.len() && key.starts_with(prefix))
+}
+
 /// Get the AccountInfo key where the account is stored.
 pub fn account_key_hash<AccountId: Encode>(account: &AccountId) -> Vec<u8> {
 	storage_map_key("System", "Account", account, &StorageHasher::Blake2_128Concat)
@@ -102,3 +126,20 @@ pub fn ensure_enclave_signer_account<AccountId: Encode + Decode + PartialEq>(
 pub fn set_block_number(block_number: u32) {
 	sp_io::storage::set(&storage_value_key("System", "Number"), &block_number.encode());
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn account_keys_are_public() {
+		assert!(is_public_state_key(&account_key_hash(&[1u8; 32])));
+		assert!(is_public_state_key(&storage_value_key("Balances", "TotalIssuance")));
+	}
+
+	#[test]
+	fn map_prefixes_and_other_keys_are_not_public() {
+		assert!(!is_public_state_key(&storage_value_key("System", "Account")));
+		assert!(!is_public_state_key(&storage_value_key("Sudo", ENCLAVE_ACCOUNT_KEY)));
+	}
+}
@@ -54,6 +54,7 @@ pub fn shield_funds_increments_signer_account_nonce() {
 			500u128,
 		),
 		0,
+		0,
 		Signature::Ed25519(Ed25519Signature([0u8; 64])),
 	);
 
@@ -113,6 +113,26 @@ impl TrustedCall {
 		}
 	}
 
+	/// Tip defined by the call itself, for preferred inclusion.
+	///
+	/// EVM calls tip their maximum priority fee for the entire gas limit, which the EVM charges.
+	/// Any call can tip in addition with [`TrustedCall::sign_with_tip`].
+	#[cfg_attr(not(feature = "evm"), allow(clippy::match_single_binding))]
+	pub fn tip(&self) -> Balance {
+		match self {
+			#[cfg(feature = "evm")]
+			TrustedCall::evm_call(_, _, _, _, _, gas_limit, _, max_priority_fee_per_gas, ..) =>
+				evm_tip(*gas_limit, max_priority_fee_per_gas),
+			#[cfg(feature = "evm")]
+			TrustedCall::evm_create(_, _, _, _, gas_limit, _, max_priority_fee_per_gas, ..) =>
+				evm_tip(*gas_limit, max_priority_fee_per_gas),
+			#[cfg(feature = "evm")]
+			TrustedCall::evm_create2(_, _, _, _, _, gas_limit, _, max_priority_fee_per_gas, ..) =>
+				evm_tip(*gas_limit, max_priority_fee_per_gas),
+			_ => 0,
+		}
+	}
+
 	pub fn sign(
 		&self,
 		pair: &KeyPair,
@@ -120,12 +140,52 @@ impl TrustedCall {
 		mrenclave: &[u8; 32],
 		shard: &ShardIdentifier,
 	) -> TrustedCallSigned {
-		let mut payload = self.encode();
-		payload.append(&mut nonce.encode());
-		payload.append(&mut mrenclave.encode());
-		payload.append(&mut shard.encode());
+		self.sign_with_tip(pair, nonce, 0, mrenclave, shard)
+	}
+
+	/// Sign the call with a `tip`, which is charged to the sender when the call is executed.
+	pub fn sign_with_tip(
+		&self,
+		pair: &KeyPair,
+		nonce: Index,
+		tip: Balance,
+		mrenclave: &[u8; 32],
+		shard: &ShardIdentifier,
+	) -> TrustedCallSigned {
+		let payload = signing_payload(self, nonce, tip, mrenclave, shard);
+		TrustedCallSigned {
+			call: self.clone(),
+			nonce,
+			tip,
+			signature: pair.sign(payload.as_slice()),
+		}
+	}
+}
+
+fn signing_payload(
+	call: &TrustedCall,
+	nonce: Index,
+	tip: Balance,
+	mrenclave: &[u8; 32],
+	shard: &ShardIdentifier,
+) -> Vec<u8> {
+	let mut payload = call.encode();
+	payload.append(&mut nonce.encode());
+	payload.append(&mut tip.encode());
+	payload.append(&mut mrenclave.encode());
+	payload.append(&mut shard.encode());
+	payload
+}
 
-		TrustedCallSigned { call: self.clone(), nonce, signature: pair.sign(payload.as_slice()) }
+#[cfg(feature = "evm")]
+fn evm_tip(gas_limit: u64, max_priority_fee_per_gas: &Option<U256>) -> Balance {
+	let tip = max_priority_fee_per_gas
+		.unwrap_or_default()
+		.saturating_mul(U256::from(gas_limit));
+	if tip > U256::from(Balance::MAX) {
+		Balance::MAX
+	} else {
+		tip.low_u128()
 	}
 }
 
@@ -133,22 +193,26 @@ impl TrustedCall {
 pub struct TrustedCallSigned {
 	pub call: TrustedCall,
 	pub nonce: Index,
+	/// Tip paid by the sender on execution, for preferred inclusion.
+	pub tip: Balance,
 	pub signature: Signature,
 }
 
 impl TrustedCallSigned {
-	pub fn new(call: TrustedCall, nonce: Index, signature: Signature) -> Self {
-		TrustedCallSigned { call, nonce, signature }
+	pub fn new(call: TrustedCall, nonce: Index, tip: Balance, signature: Signature) -> Self {
+		TrustedCallSigned { call, nonce, tip, signature }
 	}
 
 	pub fn verify_signature(&self, mrenclave: &[u8; 32], shard: &ShardIdentifier) -> bool {
-		let mut payload = self.call.encode();
-		payload.append(&mut self.nonce.encode());
-		payload.append(&mut mrenclave.encode());
-		payload.append(&mut shard.encode());
+		let payload = signing_payload(&self.call, self.nonce, self.tip, mrenclave, shard);
 		self.signature.verify(payload.as_slice(), self.call.sender_account())
 	}
 
+	/// Total tip of the call, used by the trusted operation pool to order calls.
+	pub fn tip(&self) -> Balance {
+		self.tip.saturating_add(self.call.tip())
+	}
+
 	pub fn into_trusted_operation(self, direct: bool) -> TrustedOperation {
 		match direct {
 			true => TrustedOperation::direct_call(self),
@@ -189,6 +253,10 @@ where
 		// so it should be considered as valid
 		System::inc_account_nonce(&sender);
 
+		if self.tip > 0 {
+			pay_tip(sender, self.tip)?;
+		}
+
 		match self.call {
 			TrustedCall::balance_set_balance(root, who, free_balance, reserved_balance) => {
 				ensure!(is_root::<Runtime, AccountId>(&root), Self::Error::MissingPrivileges(root));
@@ -415,6 +483,12 @@ fn unshield_funds(account: AccountId, amount: u128) -> Result<(), StfError> {
 	Ok(())
 }
 
+/// Burn the tip of a call from the free balance of its sender.
+fn pay_tip(account: AccountId, tip: Balance) -> Result<(), StfError> {
+	debug!("pay_tip({}, {})", account_id_to_string(&account), tip);
+	unshield_funds(account, tip)
+}
+
 fn shield_funds(account: AccountId, amount: u128) -> Result<(), StfError> {
 	let account_info = System::account(&account);
 	ita_sgx_runtime::BalancesCall::<Runtime>::force_set_balance {
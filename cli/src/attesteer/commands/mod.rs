@@ -17,7 +17,9 @@
 
 mod send_dcap_quote;
 mod send_ias_attestation;
+mod verify_enclave_attestation;
 
 pub use self::{
 	send_dcap_quote::SendDcapQuoteCmd, send_ias_attestation::SendIasAttestationReportCmd,
+	verify_enclave_attestation::VerifyEnclaveAttestationCmd,
 };
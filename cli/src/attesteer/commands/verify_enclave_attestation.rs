@@ -0,0 +1,154 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::{
+	command_utils::{get_chain_api, get_worker_api_direct, mrenclave_from_base58},
+	Cli,
+};
+use base58::ToBase58;
+use codec::{Decode, Encode};
+use itc_rpc_client::direct_client::DirectApi;
+use itp_attestation_handler::{
+	verify_enclave_attestation, EnclaveAttestation, EnclaveRegistration,
+};
+use itp_node_api::api_client::PalletTeerexApi;
+use itp_rpc::{RpcRequest, RpcResponse, RpcReturnValue};
+use itp_types::{AccountId, DirectRequestStatus, MultiEnclave};
+use itp_utils::FromHexPrefixed;
+use log::*;
+use nostr::prelude::{ToBech32, XOnlyPublicKey};
+use std::fs;
+
+/// Verify that the worker runs in a genuine enclave, which controls the attested keys.
+#[derive(Debug, Clone, Parser)]
+pub struct VerifyEnclaveAttestationCmd {
+	/// Verify the SCALE encoded attestation in this file, instead of fetching it from the worker.
+	#[clap(long)]
+	from_file: Option<String>,
+
+	/// Write the fetched attestation to this file, to verify it offline later on.
+	#[clap(long)]
+	save_to: Option<String>,
+
+	/// Base58 encoded MRENCLAVE the worker is expected to run.
+	#[clap(long)]
+	mrenclave: Option<String>,
+
+	/// Do not check the registration of the enclave on the parentchain. Only possible for IAS
+	/// attestations, as the signature of a DCAP quote is only checked upon registration.
+	#[clap(long)]
+	offline: bool,
+}
+
+impl VerifyEnclaveAttestationCmd {
+	pub fn run(&self, cli: &Cli) {
+		let attestation = match &self.from_file {
+			Some(path) => {
+				let encoded = fs::read(path).expect("Failed to read the attestation file");
+				EnclaveAttestation::decode(&mut encoded.as_slice())
+					.expect("Failed to decode the attestation file")
+			},
+			None => match get_enclave_attestation(cli) {
+				Ok(attestation) => attestation,
+				Err(e) => {
+					error!("Fetching the enclave attestation failed: {}", e);
+					return
+				},
+			},
+		};
+
+		if let Some(path) = &self.save_to {
+			fs::write(path, attestation.encode()).expect("Failed to write the attestation file");
+			println!("Attestation written to '{}'", path);
+		}
+
+		let signer = AccountId::from(attestation.keys.signing_key);
+		let registration = if self.offline {
+			None
+		} else {
+			let registered_enclave = get_chain_api(cli)
+				.enclave(&signer, None)
+				.expect("Failed to query the enclave registry");
+			let registered_enclave = match registered_enclave {
+				Some(enclave) => enclave,
+				None => {
+					error!("Enclave {} is not registered on the parentchain", signer);
+					return
+				},
+			};
+			let mrenclave = registered_enclave.fingerprint().0;
+			let MultiEnclave::Sgx(sgx_enclave) = registered_enclave;
+			Some(EnclaveRegistration { mrenclave, report_data: sgx_enclave.report_data.encode() })
+		};
+
+		let verified = match verify_enclave_attestation(&attestation, registration.as_ref()) {
+			Ok(verified) => verified,
+			Err(e) => {
+				error!("Attestation verification failed: {}", e);
+				return
+			},
+		};
+		if registration.is_some() {
+			println!("Enclave is registered on the parentchain");
+		}
+
+		if let Some(expected) = &self.mrenclave {
+			if mrenclave_from_base58(expected) != verified.mrenclave {
+				error!(
+					"Enclave runs MRENCLAVE {}, not {}",
+					verified.mrenclave.to_base58(),
+					expected
+				);
+				return
+			}
+		}
+
+		println!("MRENCLAVE:         {}", verified.mrenclave.to_base58());
+		println!("MRSIGNER:          {}", hex::encode(verified.mrsigner));
+		println!("ISV SVN:           {}", verified.isv_svn);
+		if let Some(status) = &verified.ias_quote_status {
+			println!("IAS quote status:  {}", status);
+		}
+		println!("Signing key:       {}", signer);
+		println!("Shielding key:     {}", String::from_utf8_lossy(&verified.keys.shielding_key));
+		match XOnlyPublicKey::from_slice(&verified.keys.nostr_issuer_key) {
+			Ok(key) => println!("Nostr issuer key:  {}", key.to_bech32().unwrap()),
+			Err(e) => error!("Invalid Nostr issuer key: {:?}", e),
+		}
+	}
+}
+
+fn get_enclave_attestation(cli: &Cli) -> Result<EnclaveAttestation, String> {
+	let direct_api = get_worker_api_direct(cli);
+
+	let rpc_method = "attesteer_getEnclaveAttestation".to_owned();
+	let jsonrpc_call: String = RpcRequest::compose_jsonrpc_call(rpc_method, vec![]).unwrap();
+
+	let rpc_response_str = direct_api.get(&jsonrpc_call).map_err(|e| format!("{:?}", e))?;
+	let rpc_response = serde_json::from_str::<RpcResponse>(&rpc_response_str)
+		.map_err(|e| format!("Can't parse RPC response '{}': {:?}", rpc_response_str, e))?;
+	let rpc_return_value = RpcReturnValue::from_hex(&rpc_response.result)
+		.map_err(|e| format!("Failed to decode RpcReturnValue: {:?}", e))?;
+
+	match rpc_return_value.status {
+		DirectRequestStatus::Ok =>
+			EnclaveAttestation::decode(&mut rpc_return_value.value.as_slice())
+				.map_err(|e| format!("Failed to decode the attestation: {:?}", e)),
+		_ => Err(String::decode(&mut rpc_return_value.value.as_slice())
+			.unwrap_or_else(|_| "Unknown error".to_owned())),
+	}
+}
@@ -17,7 +17,7 @@
 
 use crate::Cli;
 
-use self::commands::{SendDcapQuoteCmd, SendIasAttestationReportCmd};
+use self::commands::{SendDcapQuoteCmd, SendIasAttestationReportCmd, VerifyEnclaveAttestationCmd};
 
 mod commands;
 
@@ -29,6 +29,9 @@ pub enum AttesteerCommand {
 
 	/// Forward IAS attestation report for verification.
 	SendIASAttestationReport(SendIasAttestationReportCmd),
+
+	/// Verify the attestation of the worker and the keys bound to it.
+	VerifyEnclaveAttestation(VerifyEnclaveAttestationCmd),
 }
 
 impl AttesteerCommand {
@@ -36,6 +39,7 @@ impl AttesteerCommand {
 		match self {
 			AttesteerCommand::SendDCAPQuote(cmd) => cmd.run(cli),
 			AttesteerCommand::SendIASAttestationReport(cmd) => cmd.run(cli),
+			AttesteerCommand::VerifyEnclaveAttestation(cmd) => cmd.run(cli),
 		}
 	}
 }
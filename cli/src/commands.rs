@@ -22,7 +22,7 @@ use clap::Subcommand;
 #[cfg(feature = "teeracle")]
 use crate::personhood_oracle::PersonhoodOracleCommand;
 
-use crate::attesteer::AttesteerCommand;
+use crate::{attesteer::AttesteerCommand, sidechain::SidechainCommand};
 
 #[derive(Subcommand)]
 pub enum Commands {
@@ -37,6 +37,10 @@ pub enum Commands {
 	#[clap(subcommand)]
 	Attesteer(AttesteerCommand),
 
+	/// Subcommand for the sidechain block explorer.
+	#[clap(subcommand)]
+	Sidechain(SidechainCommand),
+
 	/// Subcommand for the personhood oracle.
 	#[cfg(feature = "teeracle")]
 	#[clap(subcommand)]
@@ -48,6 +52,10 @@ pub fn match_command(cli: &Cli) -> CliResult {
 		Commands::Base(cmd) => cmd.run(cli),
 		Commands::Trusted(trusted_cli) => trusted_cli.run(cli),
 		Commands::Attesteer(_) => Ok(CliResultOk::None),
+		Commands::Sidechain(cmd) => {
+			cmd.run(cli);
+			Ok(CliResultOk::None)
+		},
 		#[cfg(feature = "teeracle")]
 		Commands::PersonhoodOracle(cmd) => {
 			cmd.run(cli);
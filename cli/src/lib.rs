@@ -36,6 +36,7 @@ mod error;
 mod evm;
 #[cfg(feature = "teeracle")]
 mod personhood_oracle;
+mod sidechain;
 mod trusted_base_cli;
 mod trusted_cli;
 mod trusted_command_utils;
@@ -0,0 +1,163 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::{
+	sidechain::explorer_client::{get_untrusted_worker_api, request, shard_from_base58},
+	Cli,
+};
+use codec::Decode;
+use ita_stf::{StateDiffType, StatePayload};
+use itp_sgx_crypto::{Aes, ShieldingCryptoDecrypt, StateCrypto};
+use its_primitives::{
+	traits::{Block as BlockTrait, SignedBlock as SignedBlockTrait},
+	types::{block_data::AuditorStateDiff, header::SidechainHeader, BlockNumber, SignedBlock},
+};
+use its_rpc_handler::constants::{
+	RPC_METHOD_NAME_GET_BLOCK_BY_NUMBER, RPC_METHOD_NAME_GET_LATEST_HEADER,
+};
+use log::*;
+use serde_json::json;
+use sgx_crypto_helper::{rsa3072::Rsa3072KeyPair, RsaKeyPair};
+use std::fs;
+
+/// Create an auditor key pair and print its public key, to be registered as shard auditor.
+#[derive(Debug, Clone, Parser)]
+pub struct CreateAuditorKeyCmd {
+	/// File the JSON encoded key pair is written to.
+	key_file: String,
+}
+
+impl CreateAuditorKeyCmd {
+	pub fn run(&self) {
+		let key_pair = Rsa3072KeyPair::new().expect("Failed to create auditor key pair");
+		let public_key = key_pair.export_pubkey().expect("Failed to export auditor public key");
+
+		let key_pair_json = serde_json::to_vec(&key_pair).expect("Failed to serialize key pair");
+		fs::write(&self.key_file, key_pair_json).expect("Failed to write auditor key pair");
+		info!("Wrote auditor key pair to {}", self.key_file);
+
+		// This is the value that has to be registered as shard auditor on the parentchain.
+		println!("{}", serde_json::to_string(&public_key).expect("Failed to serialize public key"));
+	}
+}
+
+/// Decrypt and print the state diffs of sidechain blocks with the key of the shard auditor.
+#[derive(Debug, Clone, Parser)]
+pub struct DecryptStateDiffsCmd {
+	/// File containing the JSON encoded auditor key pair.
+	key_file: String,
+
+	/// Shard identifier, base58 encoded.
+	shard: String,
+
+	/// Number of the first block to decrypt.
+	from: BlockNumber,
+
+	/// Number of the last block to decrypt, defaults to the latest block.
+	to: Option<BlockNumber>,
+}
+
+impl DecryptStateDiffsCmd {
+	pub fn run(&self, cli: &Cli) {
+		let key_pair_json = fs::read(&self.key_file).expect("Failed to read auditor key pair");
+		let key_pair: Rsa3072KeyPair =
+			serde_json::from_slice(&key_pair_json).expect("Invalid auditor key pair");
+		let shard = shard_from_base58(&self.shard);
+
+		let api = match get_untrusted_worker_api(cli) {
+			Ok(api) => api,
+			Err(e) => {
+				error!("Connecting to the worker failed: {}", e);
+				return
+			},
+		};
+
+		let to = match self.to {
+			Some(to) => to,
+			None => match request::<Option<SidechainHeader>>(
+				&api,
+				RPC_METHOD_NAME_GET_LATEST_HEADER,
+				vec![json!(shard)],
+			) {
+				Ok(Some(header)) => header.block_number,
+				Ok(None) => {
+					println!("No sidechain block found for shard {}", self.shard);
+					return
+				},
+				Err(e) => {
+					error!("Fetching the latest sidechain header failed: {}", e);
+					return
+				},
+			},
+		};
+
+		for number in self.from..=to {
+			match request::<Option<SignedBlock>>(
+				&api,
+				RPC_METHOD_NAME_GET_BLOCK_BY_NUMBER,
+				vec![json!(shard), json!(number)],
+			) {
+				Ok(Some(block)) => print_state_diff(&block, &key_pair),
+				Ok(None) => println!("No sidechain block #{} found", number),
+				Err(e) => error!("Fetching sidechain block #{} failed: {}", number, e),
+			}
+		}
+	}
+}
+
+fn print_state_diff(signed_block: &SignedBlock, key_pair: &Rsa3072KeyPair) {
+	let block = signed_block.block();
+	println!("block #{} {:?}", block.header().block_number, block.header().hash());
+
+	let auditor_state_diff = match &block.block_data().auditor_state_diff {
+		Some(diff) => diff,
+		None => {
+			println!("  no state diff for the auditor");
+			return
+		},
+	};
+
+	match decrypt_state_payload(auditor_state_diff, key_pair) {
+		Ok(payload) => {
+			println!("  state hash apriori:    {:?}", payload.state_hash_apriori());
+			println!("  state hash aposteriori: {:?}", payload.state_hash_aposteriori());
+			println!("  state diff entries:    {}", payload.state_update().len());
+			for (key, value) in payload.state_update().iter() {
+				match value {
+					Some(value) =>
+						println!("    0x{} = 0x{}", hex::encode(key), hex::encode(value)),
+					None => println!("    0x{} removed", hex::encode(key)),
+				}
+			}
+		},
+		Err(e) => error!("  decrypting the state diff failed: {}", e),
+	}
+}
+
+fn decrypt_state_payload(
+	auditor_state_diff: &AuditorStateDiff,
+	key_pair: &Rsa3072KeyPair,
+) -> Result<StatePayload<StateDiffType>, String> {
+	let state_key_bytes = key_pair
+		.decrypt(&auditor_state_diff.encrypted_key)
+		.map_err(|e| format!("{:?}", e))?;
+	let state_key = Aes::decode(&mut state_key_bytes.as_slice()).map_err(|e| e.to_string())?;
+
+	let mut payload = auditor_state_diff.encrypted_state_diff.clone();
+	state_key.decrypt(&mut payload).map_err(|e| format!("{:?}", e))?;
+	StatePayload::decode(&mut payload.as_slice()).map_err(|e| e.to_string())
+}
@@ -0,0 +1,90 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::{
+	sidechain::explorer_client::{
+		get_untrusted_worker_api, print_block, request, shard_from_base58,
+	},
+	Cli,
+};
+use its_primitives::types::{
+	finality::FinalityStatus, header::SidechainHeader, BlockHash, SignedBlock,
+};
+use its_rpc_handler::constants::{
+	RPC_METHOD_NAME_GET_BLOCK_BY_HASH, RPC_METHOD_NAME_GET_FINALITY_STATUS,
+	RPC_METHOD_NAME_GET_FINALIZED_HEADER,
+};
+use log::*;
+use serde_json::json;
+use std::str::FromStr;
+
+/// Print the latest finalized sidechain block of a shard.
+#[derive(Debug, Clone, Parser)]
+pub struct GetFinalizedHeaderCmd {
+	/// Shard identifier, base58 encoded.
+	shard: String,
+}
+
+impl GetFinalizedHeaderCmd {
+	pub fn run(&self, cli: &Cli) {
+		let shard = shard_from_base58(&self.shard);
+		let result = get_untrusted_worker_api(cli).and_then(|api| {
+			let maybe_header: Option<SidechainHeader> =
+				request(&api, RPC_METHOD_NAME_GET_FINALIZED_HEADER, vec![json!(shard)])?;
+			match maybe_header {
+				Some(header) => request::<Option<SignedBlock>>(
+					&api,
+					RPC_METHOD_NAME_GET_BLOCK_BY_HASH,
+					vec![json!(header.hash())],
+				),
+				None => Ok(None),
+			}
+		});
+
+		match result {
+			Ok(Some(block)) => print_block(&block),
+			Ok(None) => println!("No finalized sidechain block found for shard {}", self.shard),
+			Err(e) => error!("Fetching the finalized sidechain block failed: {}", e),
+		}
+	}
+}
+
+/// Print whether the sidechain block with the given hash is final.
+#[derive(Debug, Clone, Parser)]
+pub struct GetFinalityStatusCmd {
+	/// Block hash, hex encoded.
+	hash: String,
+}
+
+impl GetFinalityStatusCmd {
+	pub fn run(&self, cli: &Cli) {
+		let block_hash = BlockHash::from_str(&self.hash).expect("block hash has to be hex encoded");
+		let result = get_untrusted_worker_api(cli).and_then(|api| {
+			request::<Option<FinalityStatus>>(
+				&api,
+				RPC_METHOD_NAME_GET_FINALITY_STATUS,
+				vec![json!(block_hash)],
+			)
+		});
+
+		match result {
+			Ok(Some(status)) => println!("{:?}", status),
+			Ok(None) => println!("No sidechain block found with hash {:?}", block_hash),
+			Err(e) => error!("Fetching the finality status failed: {}", e),
+		}
+	}
+}
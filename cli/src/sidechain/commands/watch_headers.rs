@@ -0,0 +1,86 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::{
+	sidechain::explorer_client::{
+		compose_request, get_untrusted_worker_api, print_block, print_header, request,
+		shard_from_base58,
+	},
+	Cli,
+};
+use itc_rpc_client::direct_client::DirectApi;
+use its_primitives::types::{header::SidechainHeader, SignedBlock};
+use its_rpc_handler::constants::{
+	RPC_METHOD_NAME_GET_BLOCK_BY_HASH, RPC_METHOD_NAME_SUBSCRIBE_NEW_HEADS,
+};
+use log::*;
+use serde_json::{json, Value};
+use std::sync::mpsc::channel;
+
+/// Print new sidechain blocks of a shard as they are produced.
+#[derive(Debug, Clone, Parser)]
+pub struct WatchHeadersCmd {
+	/// Shard identifier, base58 encoded.
+	shard: String,
+}
+
+impl WatchHeadersCmd {
+	pub fn run(&self, cli: &Cli) {
+		let shard = shard_from_base58(&self.shard);
+		let api = match get_untrusted_worker_api(cli) {
+			Ok(api) => api,
+			Err(e) => {
+				error!("Failed to connect to the untrusted worker RPC server: {}", e);
+				return
+			},
+		};
+
+		let (sender, receiver) = channel();
+		let request_str = compose_request(RPC_METHOD_NAME_SUBSCRIBE_NEW_HEADS, vec![json!(shard)]);
+		let _watch_handle = api.watch(request_str, sender);
+
+		while let Ok(message) = receiver.recv() {
+			let notification: Value = match serde_json::from_str(&message) {
+				Ok(value) => value,
+				Err(e) => {
+					error!("Can't parse subscription message '{}': {}", message, e);
+					continue
+				},
+			};
+
+			// The first message only confirms the subscription.
+			let maybe_header = notification
+				.get("params")
+				.and_then(|params| params.get("result"))
+				.cloned()
+				.map(serde_json::from_value::<SidechainHeader>);
+
+			match maybe_header {
+				Some(Ok(header)) => match request::<Option<SignedBlock>>(
+					&api,
+					RPC_METHOD_NAME_GET_BLOCK_BY_HASH,
+					vec![json!(header.hash())],
+				) {
+					Ok(Some(block)) => print_block(&block),
+					_ => print_header(&header),
+				},
+				Some(Err(e)) => error!("Unexpected header in subscription: {}", e),
+				None => println!("Watching sidechain blocks of shard {}", self.shard),
+			}
+		}
+	}
+}
@@ -0,0 +1,92 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Access to the sidechain explorer RPC methods of the untrusted worker RPC server.
+
+use crate::{command_utils::get_worker_api_direct, Cli};
+use base58::{FromBase58, ToBase58};
+use itc_rpc_client::direct_client::{DirectApi, DirectClient};
+use its_primitives::{
+	traits::{Block as BlockTrait, SignedBlock as SignedBlockTrait},
+	types::{header::SidechainHeader, ShardIdentifier, SignedBlock},
+};
+use log::*;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use sp_core::crypto::Ss58Codec;
+
+/// Client for the untrusted RPC server of the worker, where the sidechain blocks are stored.
+pub(crate) fn get_untrusted_worker_api(cli: &Cli) -> Result<DirectClient, String> {
+	let untrusted_url = get_worker_api_direct(cli)
+		.get_untrusted_worker_url()
+		.map_err(|e| e.to_string())?;
+	info!("Connecting to the untrusted worker RPC server on '{}'", untrusted_url);
+	Ok(DirectClient::new(untrusted_url))
+}
+
+pub(crate) fn compose_request(method: &str, params: Vec<Value>) -> String {
+	json!({ "jsonrpc": "2.0", "method": method, "params": params, "id": 1 }).to_string()
+}
+
+/// Send a request and deserialize the JSON result.
+pub(crate) fn request<R: DeserializeOwned>(
+	api: &DirectClient,
+	method: &str,
+	params: Vec<Value>,
+) -> Result<R, String> {
+	let response_str = api.get(&compose_request(method, params)).map_err(|e| e.to_string())?;
+	let response: Value = serde_json::from_str(&response_str)
+		.map_err(|e| format!("Can't parse RPC response '{}': {}", response_str, e))?;
+
+	if let Some(error) = response.get("error") {
+		return Err(format!("RPC error: {}", error))
+	}
+	let result = response.get("result").cloned().unwrap_or(Value::Null);
+	serde_json::from_value(result).map_err(|e| format!("Unexpected RPC result: {}", e))
+}
+
+pub(crate) fn shard_from_base58(shard: &str) -> ShardIdentifier {
+	ShardIdentifier::from_slice(&shard.from_base58().expect("shard has to be base58 encoded"))
+}
+
+pub(crate) fn print_header(header: &SidechainHeader) {
+	println!("block #{} {:?}", header.block_number, header.hash());
+	println!("  parent hash:       {:?}", header.parent_hash);
+	println!("  shard:             {}", header.shard_id.0.to_base58());
+	println!("  state root:        {:?}", header.state_root);
+	println!("  block data hash:   {:?}", header.block_data_hash);
+	println!("  next finalization: {}", header.next_finalization_block_number);
+}
+
+pub(crate) fn print_block(signed_block: &SignedBlock) {
+	let block = signed_block.block();
+	let block_data = block.block_data();
+
+	print_header(block.header());
+	println!("  layer one head:    {:?}", block_data.layer_one_head);
+	println!("  timestamp:         {}", block_data.timestamp);
+	println!("  author:            {}", block_data.block_author.to_ss58check());
+	println!("  state diff size:   {} bytes", block_data.encrypted_state_diff.len());
+	match &block_data.auditor_state_diff {
+		Some(diff) => println!("  auditor diff size: {} bytes", diff.encrypted_state_diff.len()),
+		None => println!("  auditor diff:      none"),
+	}
+	println!("  signed TOP hashes: {}", block_data.signed_top_hashes.len());
+	for top_hash in block_data.signed_top_hashes.iter() {
+		println!("    {:?}", top_hash);
+	}
+}
@@ -0,0 +1,69 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::Cli;
+
+use self::commands::{
+	CreateAuditorKeyCmd, DecryptStateDiffsCmd, GetBlockByHashCmd, GetBlockByNumberCmd,
+	GetFinalityStatusCmd, GetFinalizedHeaderCmd, GetLatestHeaderCmd, WatchHeadersCmd,
+};
+
+mod commands;
+mod explorer_client;
+
+/// Sidechain block explorer subcommands for the CLI.
+#[derive(Debug, clap::Subcommand)]
+pub enum SidechainCommand {
+	/// Print the latest sidechain block of a shard.
+	LatestHeader(GetLatestHeaderCmd),
+
+	/// Print the sidechain block with the given number.
+	BlockByNumber(GetBlockByNumberCmd),
+
+	/// Print the sidechain block with the given hash.
+	BlockByHash(GetBlockByHashCmd),
+
+	/// Print new sidechain blocks of a shard as they are produced.
+	WatchHeaders(WatchHeadersCmd),
+
+	/// Print the latest finalized sidechain block of a shard.
+	FinalizedHeader(GetFinalizedHeaderCmd),
+
+	/// Print whether the sidechain block with the given hash is final or tentative.
+	FinalityStatus(GetFinalityStatusCmd),
+
+	/// Create a key pair for the auditor of a shard.
+	CreateAuditorKey(CreateAuditorKeyCmd),
+
+	/// Decrypt and print the state diffs of sidechain blocks with the shard auditor key.
+	DecryptStateDiffs(DecryptStateDiffsCmd),
+}
+
+impl SidechainCommand {
+	pub fn run(&self, cli: &Cli) {
+		match self {
+			SidechainCommand::LatestHeader(cmd) => cmd.run(cli),
+			SidechainCommand::BlockByNumber(cmd) => cmd.run(cli),
+			SidechainCommand::BlockByHash(cmd) => cmd.run(cli),
+			SidechainCommand::WatchHeaders(cmd) => cmd.run(cli),
+			SidechainCommand::FinalizedHeader(cmd) => cmd.run(cli),
+			SidechainCommand::FinalityStatus(cmd) => cmd.run(cli),
+			SidechainCommand::CreateAuditorKey(cmd) => cmd.run(),
+			SidechainCommand::DecryptStateDiffs(cmd) => cmd.run(cli),
+		}
+	}
+}
@@ -30,9 +30,14 @@
 #[cfg(all(not(feature = "std"), feature = "sgx"))]
 use crate::sgx_reexport_prelude::*;
 
-use crate::{cert, Error as EnclaveError, Error, Result as EnclaveResult};
+use crate::{
+	cert,
+	enclave_attestation::{AttestationEvidence, AttestedKeys, EnclaveAttestation},
+	tcb_status::DcapQuoteStatus,
+	Error as EnclaveError, Error, Result as EnclaveResult,
+};
 use codec::Encode;
-use core::{convert::TryInto, default::Default};
+use core::default::Default;
 use itertools::Itertools;
 use itp_ocall_api::EnclaveAttestationOCallApi;
 use itp_settings::{
@@ -41,7 +46,6 @@ use itp_settings::{
 };
 use itp_sgx_crypto::key_repository::AccessKey;
 use itp_sgx_io as io;
-use itp_time_utils::now_as_secs;
 use log::*;
 use sgx_rand::{os, Rng};
 use sgx_tcrypto::{rsgx_sha256_slice, SgxEccHandle};
@@ -59,7 +63,7 @@ use std::{
 	prelude::v1::*,
 	println, str,
 	string::{String, ToString},
-	sync::Arc,
+	sync::{Arc, SgxRwLock as RwLock},
 	vec::Vec,
 };
 
@@ -92,6 +96,15 @@ pub trait AttestationHandler {
 		skip_ra: bool,
 	) -> EnclaveResult<(Vec<u8>, Vec<u8>, Vec<u8>)>;
 
+	/// Create a DCAP attested certificate for TLS, whose quote is bound to the certificate key.
+	/// Returns a pair consisting of (private key DER, certificate DER)
+	fn create_dcap_ra_report_and_signature(
+		&self,
+		quoting_enclave_target_info: Option<&sgx_target_info_t>,
+		quote_size: Option<&u32>,
+		skip_ra: bool,
+	) -> EnclaveResult<(Vec<u8>, Vec<u8>)>;
+
 	/// Get the measurement register value of the enclave
 	fn get_mrenclave(&self) -> EnclaveResult<[u8; MR_ENCLAVE_SIZE]>;
 
@@ -112,11 +125,23 @@ pub trait AttestationHandler {
 		sign_type: sgx_quote_sign_type_t,
 		skip_ra: bool,
 	) -> EnclaveResult<(Vec<u8>, Vec<u8>)>;
+
+	/// Public keys of the enclave, as bound into the report data of its attestations.
+	fn attested_keys(&self) -> EnclaveResult<AttestedKeys>;
+
+	/// MRENCLAVE and attested keys of the enclave, with the evidence of its latest remote
+	/// attestation, to be verified by relying parties.
+	fn enclave_attestation(&self) -> EnclaveResult<EnclaveAttestation>;
 }
 
 pub struct IntelAttestationHandler<OCallApi, SigningKeyRepo> {
 	pub(crate) ocall_api: Arc<OCallApi>,
 	pub(crate) signing_key_repo: Arc<SigningKeyRepo>,
+	/// JSON encoded shielding public key, bound into the report data.
+	pub(crate) shielding_key: Vec<u8>,
+	/// Nostr issuer public key, bound into the report data.
+	pub(crate) nostr_issuer_key: [u8; 32],
+	pub(crate) latest_evidence: RwLock<Option<AttestationEvidence>>,
 }
 
 impl<OCallApi, AccessSigningKey> IntelAttestationHandler<OCallApi, AccessSigningKey>
@@ -126,26 +151,116 @@ where
 {
 	fn create_payload_epid(
 		&self,
-		pub_k: &[u8; 32],
+		report_data: &sgx_report_data_t,
 		sign_type: sgx_quote_sign_type_t,
 	) -> EnclaveResult<String> {
 		info!("    [Enclave] Create attestation report");
-		let (attn_report, sig, cert) = match self.create_epid_attestation_report(&pub_k, sign_type)
-		{
-			Ok(r) => r,
-			Err(e) => {
-				error!("    [Enclave] Error in create_attestation_report: {:?}", e);
-				return Err(e.into())
-			},
-		};
+		let (attn_report, sig, cert) =
+			match self.create_epid_attestation_report(report_data, sign_type) {
+				Ok(r) => r,
+				Err(e) => {
+					error!("    [Enclave] Error in create_attestation_report: {:?}", e);
+					return Err(e.into())
+				},
+			};
 		println!("    [Enclave] Create attestation report successful");
 		debug!("              attn_report = {:?}", attn_report);
 		debug!("              sig         = {:?}", sig);
 		debug!("              cert        = {:?}", cert);
 
+		self.set_latest_evidence(AttestationEvidence::Ias {
+			report: attn_report.as_bytes().to_vec(),
+			signature: base64::decode(&sig).map_err(|e| EnclaveError::Other(e.into()))?,
+			signing_cert: base64::decode(&cert).map_err(|e| EnclaveError::Other(e.into()))?,
+		});
+
 		// concat the information
 		Ok(attn_report + "|" + &sig + "|" + &cert)
 	}
+
+	/// DCAP attested certificate. If `bind_cert_key` is set, the quote is bound to the key of
+	/// the certificate, for use in TLS. Otherwise it attests our keys, for the registration.
+	fn dcap_ra_cert(
+		&self,
+		quoting_enclave_target_info: Option<&sgx_target_info_t>,
+		quote_size: Option<&u32>,
+		skip_ra: bool,
+		bind_cert_key: bool,
+	) -> EnclaveResult<(Vec<u8>, Vec<u8>, Vec<u8>)> {
+		if !skip_ra && quoting_enclave_target_info.is_none() && quote_size.is_none() {
+			error!("Enclave Attestation] remote attestation not skipped, but Quoting Enclave (QE) data is not available");
+			return Err(EnclaveError::Sgx(sgx_status_t::SGX_ERROR_UNEXPECTED))
+		}
+		let chain_signer = self.signing_key_repo.retrieve_key()?;
+		info!("[Enclave Attestation] Ed25519 signer pub key: {:?}", chain_signer.public().0);
+
+		let ecc_handle = SgxEccHandle::new();
+		let _result = ecc_handle.open();
+		let (prv_k, pub_k) = ecc_handle.create_key_pair()?;
+		info!("Enclave Attestation] Generated ephemeral ECDSA keypair:");
+
+		let report_data = if bind_cert_key {
+			sgx_report_data_t {
+				d: cert::tls_cert_report_data(
+					&chain_signer.public().0,
+					&cert::cert_public_key(&pub_k),
+				),
+			}
+		} else {
+			self.report_data()?
+		};
+
+		let qe_quote = if !skip_ra {
+			let qe_quote = match self.retrieve_qe_dcap_quote(
+				&report_data,
+				quoting_enclave_target_info.unwrap(),
+				*quote_size.unwrap(),
+			) {
+				Ok(quote) => quote,
+				Err(e) => {
+					error!("[Enclave] Error in create_dcap_attestation_report: {:?}", e);
+					return Err(e.into())
+				},
+			};
+			if !bind_cert_key {
+				self.set_latest_evidence(AttestationEvidence::Dcap(qe_quote.clone()));
+			}
+			qe_quote
+		} else {
+			Default::default()
+		};
+
+		let qe_quote_base_64 = base64::encode(&qe_quote[..]);
+		// generate an ECC certificate
+		debug!("[Enclave] Generate ECC Certificate");
+		let (key_der, cert_der) =
+			match cert::gen_ecc_cert(&qe_quote_base_64, &prv_k, &pub_k, &ecc_handle) {
+				Ok(r) => r,
+				Err(e) => {
+					error!("[Enclave] gen_ecc_cert failed: {:?}", e);
+					return Err(e.into())
+				},
+			};
+
+		let _ = ecc_handle.close();
+
+		debug!("[Enclave] Generated ECC cert info:");
+		trace!("[Enclave] Generated ECC cert info: key_der={:#?}", &key_der);
+		trace!("[Enclave] Generated ECC cert info: cert_der={:#?}", &cert_der);
+		trace!("[Enclave] Generated ECC cert info: qe_quote={:#?}", &qe_quote);
+		Ok((key_der, cert_der, qe_quote))
+	}
+
+	fn report_data(&self) -> EnclaveResult<sgx_report_data_t> {
+		Ok(sgx_report_data_t { d: self.attested_keys()?.report_data() })
+	}
+
+	fn set_latest_evidence(&self, evidence: AttestationEvidence) {
+		match self.latest_evidence.write() {
+			Ok(mut latest_evidence) => *latest_evidence = Some(evidence),
+			Err(e) => error!("Failed to cache the attestation evidence: {:?}", e),
+		}
+	}
 }
 
 impl<OCallApi, AccessSigningKey> AttestationHandler
@@ -242,7 +357,7 @@ where
 		debug!("     pubkey Y is {:02x}", pub_k.gy.iter().format(""));
 
 		let payload = if !skip_ra {
-			self.create_payload_epid(&chain_signer.public().0, sign_type)?
+			self.create_payload_epid(&self.report_data()?, sign_type)?
 		} else {
 			Default::default()
 		};
@@ -268,60 +383,56 @@ where
 		quote_size: Option<&u32>,
 		skip_ra: bool,
 	) -> EnclaveResult<(Vec<u8>, Vec<u8>, Vec<u8>)> {
-		if !skip_ra && quoting_enclave_target_info.is_none() && quote_size.is_none() {
-			error!("Enclave Attestation] remote attestation not skipped, but Quoting Enclave (QE) data is not available");
-			return Err(EnclaveError::Sgx(sgx_status_t::SGX_ERROR_UNEXPECTED))
-		}
-		let chain_signer = self.signing_key_repo.retrieve_key()?;
-		info!("[Enclave Attestation] Ed25519 signer pub key: {:?}", chain_signer.public().0);
-
-		let ecc_handle = SgxEccHandle::new();
-		let _result = ecc_handle.open();
-		let (prv_k, pub_k) = ecc_handle.create_key_pair()?;
-		info!("Enclave Attestation] Generated ephemeral ECDSA keypair:");
-
-		let qe_quote = if !skip_ra {
-			let qe_quote = match self.retrieve_qe_dcap_quote(
-				&chain_signer.public().0,
-				quoting_enclave_target_info.unwrap(),
-				*quote_size.unwrap(),
-			) {
-				Ok(quote) => quote,
-				Err(e) => {
-					error!("[Enclave] Error in create_dcap_attestation_report: {:?}", e);
-					return Err(e.into())
-				},
-			};
-			qe_quote
-		} else {
-			Default::default()
-		};
+		self.dcap_ra_cert(quoting_enclave_target_info, quote_size, skip_ra, false)
+	}
 
-		let qe_quote_base_64 = base64::encode(&qe_quote[..]);
-		// generate an ECC certificate
-		debug!("[Enclave] Generate ECC Certificate");
-		let (key_der, cert_der) =
-			match cert::gen_ecc_cert(&qe_quote_base_64, &prv_k, &pub_k, &ecc_handle) {
-				Ok(r) => r,
-				Err(e) => {
-					error!("[Enclave] gen_ecc_cert failed: {:?}", e);
-					return Err(e.into())
-				},
-			};
+	fn create_dcap_ra_report_and_signature(
+		&self,
+		quoting_enclave_target_info: Option<&sgx_target_info_t>,
+		quote_size: Option<&u32>,
+		skip_ra: bool,
+	) -> EnclaveResult<(Vec<u8>, Vec<u8>)> {
+		let (key_der, cert_der, _qe_quote) =
+			self.dcap_ra_cert(quoting_enclave_target_info, quote_size, skip_ra, true)?;
+		Ok((key_der, cert_der))
+	}
 
-		let _ = ecc_handle.close();
+	fn attested_keys(&self) -> EnclaveResult<AttestedKeys> {
+		Ok(AttestedKeys {
+			signing_key: self.signing_key_repo.retrieve_key()?.public().0,
+			shielding_key: self.shielding_key.clone(),
+			nostr_issuer_key: self.nostr_issuer_key,
+		})
+	}
 
-		debug!("[Enclave] Generated ECC cert info:");
-		trace!("[Enclave] Generated ECC cert info: key_der={:#?}", &key_der);
-		trace!("[Enclave] Generated ECC cert info: cert_der={:#?}", &cert_der);
-		trace!("[Enclave] Generated ECC cert info: qe_quote={:#?}", &qe_quote);
-		Ok((key_der, cert_der, qe_quote))
+	fn enclave_attestation(&self) -> EnclaveResult<EnclaveAttestation> {
+		let evidence = self
+			.latest_evidence
+			.read()
+			.map_err(|e| EnclaveError::Other(format!("{:?}", e).into()))?
+			.clone();
+		Ok(EnclaveAttestation {
+			mrenclave: self.get_mrenclave()?,
+			keys: self.attested_keys()?,
+			evidence,
+		})
 	}
 }
 
 impl<OCallApi, AccessSigningKey> IntelAttestationHandler<OCallApi, AccessSigningKey> {
-	pub fn new(ocall_api: Arc<OCallApi>, signing_key_repo: Arc<AccessSigningKey>) -> Self {
-		Self { ocall_api, signing_key_repo }
+	pub fn new(
+		ocall_api: Arc<OCallApi>,
+		signing_key_repo: Arc<AccessSigningKey>,
+		shielding_key: Vec<u8>,
+		nostr_issuer_key: [u8; 32],
+	) -> Self {
+		Self {
+			ocall_api,
+			signing_key_repo,
+			shielding_key,
+			nostr_issuer_key,
+			latest_evidence: RwLock::new(None),
+		}
 	}
 }
 
@@ -539,7 +650,7 @@ where
 
 	fn create_epid_attestation_report(
 		&self,
-		pub_k: &[u8; 32],
+		report_data: &sgx_report_data_t,
 		sign_type: sgx_quote_sign_type_t,
 	) -> SgxResult<(String, String, String)> {
 		// Workflow:
@@ -567,10 +678,7 @@ where
 		let sigrl_vec: Vec<u8> = self.get_sigrl_from_intel(ias_socket, eg_num)?;
 
 		// (2) Generate the report
-		let mut report_data: sgx_report_data_t = sgx_report_data_t::default();
-		report_data.d[..32].clone_from_slice(&pub_k[..]);
-
-		let report = match rsgx_create_report(&target_info, &report_data) {
+		let report = match rsgx_create_report(&target_info, report_data) {
 			Ok(r) => {
 				debug!(
 					"    [Enclave] Report creation successful. mr_signer.m = {:x?}",
@@ -678,108 +786,23 @@ where
 			.map_err(|e| EnclaveError::Other(e.into()))
 	}
 
-	/// Returns Ok if the verification of the quote by the quote verification enclave (QVE) was successful
-	pub fn ecdsa_quote_verification(&self, quote: Vec<u8>) -> SgxResult<()> {
-		let mut app_enclave_target_info: sgx_target_info_t = unsafe { std::mem::zeroed() };
-		let quote_collateral: sgx_ql_qve_collateral_t = unsafe { std::mem::zeroed() };
-		let mut qve_report_info: sgx_ql_qe_report_info_t = unsafe { std::mem::zeroed() };
-		let supplemental_data_size = std::mem::size_of::<sgx_ql_qv_supplemental_t>() as u32;
-
-		// Get target info of the app enclave. QvE will target the generated report to this enclave.
-		let ret_val =
-			unsafe { sgx_self_target(&mut app_enclave_target_info as *mut sgx_target_info_t) };
-		if ret_val != sgx_status_t::SGX_SUCCESS {
-			error!("sgx_self_target returned: {:?}", ret_val);
-			return Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
-		}
-
-		// Set current time, which is needed to check against the expiration date of the certificate.
-		let current_time: i64 = now_as_secs().try_into().unwrap_or_else(|e| {
-			panic!("Could not convert SystemTime from u64 into i64: {:?}", e);
-		});
-
-		// Set random nonce.
-		let mut rand_nonce = vec![0u8; 16];
-		let ret_val = unsafe { sgx_read_rand(rand_nonce.as_mut_ptr(), rand_nonce.len()) };
-		if ret_val != sgx_status_t::SGX_SUCCESS {
-			error!("sgx_read_rand returned: {:?}", ret_val);
-			return Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
-		}
-		debug!("Retrieved random nonce {:?}", rand_nonce);
-		qve_report_info.nonce.rand.copy_from_slice(rand_nonce.as_slice());
-		qve_report_info.app_enclave_target_info = app_enclave_target_info;
-
-		// Ocall to call Quote verification Enclave (QvE), which verifies the generated quote.
-		let (
-			collateral_expiration_status,
-			quote_verification_result,
-			qve_report_info_return_value,
-			supplemental_data,
-		) = self.ocall_api.get_qve_report_on_quote(
-			quote.clone(),
-			current_time,
-			quote_collateral,
-			qve_report_info,
-			supplemental_data_size,
-		)?;
-
-		// Check nonce of qve report to protect against replay attacks, as the qve report
-		// is coming from the untrusted side.
-		if qve_report_info_return_value.nonce.rand != qve_report_info.nonce.rand {
-			error!(
-				"Nonce of input value and return value are not matching. Input: {:?}, Output: {:?}",
-				qve_report_info.nonce.rand, qve_report_info_return_value.nonce.rand
-			);
-			return Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
-		}
-
-		// Set the threshold of QvE ISV SVN. The ISV SVN of QvE used to verify quote must be greater or equal to this threshold
-		// e.g. You can check latest QvE ISVSVN from QvE configuration file on Github
-		// https://github.com/intel/SGXDataCenterAttestationPrimitives/blob/master/QuoteVerification/QvE/Enclave/linux/config.xml#L4
-		// or you can get latest QvE ISVSVN in QvE Identity JSON file from
-		// https://api.trustedservices.intel.com/sgx/certification/v3/qve/identity
-		// Make sure you are using trusted & latest QvE ISV SVN as threshold
-		// Warning: The function may return erroneous result if QvE ISV SVN has been modified maliciously.
-		let qve_isvsvn_threshold: sgx_isv_svn_t = 6;
-
-		// Verify the qve report to validate that it is coming from a legit quoting verification enclave
-		// and has not been tampered with.
-		let ret_val = unsafe {
-			sgx_tvl_verify_qve_report_and_identity(
-				quote.as_ptr(),
-				quote.len() as u32,
-				&qve_report_info_return_value as *const sgx_ql_qe_report_info_t,
-				current_time,
-				collateral_expiration_status,
-				quote_verification_result,
-				supplemental_data.as_ptr(),
-				supplemental_data_size,
-				qve_isvsvn_threshold,
-			)
-		};
-
-		if ret_val != sgx_quote3_error_t::SGX_QL_SUCCESS {
-			error!("sgx_tvl_verify_qve_report_and_identity returned: {:?}", ret_val);
-			return Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
-		}
-
-		Ok(())
+	/// Verifies a DCAP quote with the quote verification enclave (QvE) and returns the TCB
+	/// status of the platform that generated it.
+	pub fn ecdsa_quote_verification(&self, quote: Vec<u8>) -> SgxResult<DcapQuoteStatus> {
+		cert::verify_dcap_quote(&quote, self.ocall_api.as_ref())
 	}
 
 	pub fn retrieve_qe_dcap_quote(
 		&self,
-		pub_k: &[u8; 32],
+		report_data: &sgx_report_data_t,
 		quoting_enclave_target_info: &sgx_target_info_t,
 		quote_size: u32,
 	) -> SgxResult<Vec<u8>> {
-		// Generate app enclave report and include the enclave public key.
+		// Generate app enclave report and include the enclave public keys.
 		// The quote will be generated on top of this report and validate that the
-		// report as well as the public key inside it are coming from a legit
+		// report as well as the public keys inside it are coming from a legit
 		// intel sgx enclave.
-		let mut report_data: sgx_report_data_t = sgx_report_data_t::default();
-		report_data.d[..32].clone_from_slice(&pub_k[..]);
-
-		let app_report = match rsgx_create_report(quoting_enclave_target_info, &report_data) {
+		let app_report = match rsgx_create_report(quoting_enclave_target_info, report_data) {
 			Ok(report) => {
 				debug!(
 					"rsgx_create_report creation successful. mr_signer: {:?}",
@@ -28,6 +28,7 @@ use serde_json::Value;
 use sgx_types::{
 	sgx_platform_info_t, sgx_quote_t, sgx_status_t, SgxResult, SGX_PLATFORM_INFO_SIZE,
 };
+use sp_core::hashing::blake2_256;
 use std::{
 	io::BufReader,
 	ptr, str,
@@ -54,22 +55,58 @@ static SUPPORTED_SIG_ALGS: SignatureAlgorithms = &[
 pub const CERTEXPIRYDAYS: i64 = 90i64;
 pub const IAS_REPORT_CA: &[u8] = include_bytes!("../AttestationReportSigningCACert.pem");
 
+/// Layout of a DCAP quote (`sgx_quote3_t`).
+#[cfg(feature = "sgx")]
+const DCAP_QUOTE_HEADER_SIZE: usize = 48;
+#[cfg(feature = "sgx")]
+const DCAP_REPORT_BODY_SIZE: usize = 384;
+#[cfg(feature = "sgx")]
+const DCAP_MR_ENCLAVE_OFFSET: usize = 64;
+#[cfg(feature = "sgx")]
+const DCAP_REPORT_DATA_OFFSET: usize = 320;
+
+/// Report data of the DCAP quote embedded in a TLS certificate: the enclave signer, followed by
+/// the blake2-256 hash of the certificate key, so that the quote cannot be replayed in a
+/// certificate of another key.
+pub fn tls_cert_report_data(signer: &[u8; 32], cert_pub_key: &[u8]) -> [u8; 64] {
+	let mut report_data = [0u8; 64];
+	report_data[..32].copy_from_slice(signer);
+	report_data[32..].copy_from_slice(&blake2_256(cert_pub_key));
+	report_data
+}
+
 #[cfg(feature = "sgx")]
 pub use sgx::*;
 
 #[cfg(feature = "sgx")]
 pub mod sgx {
 	use super::*;
+	use crate::tcb_status::DcapQuoteStatus;
 	use bit_vec::BitVec;
 	use chrono::{Duration, TimeZone, Utc as TzUtc};
+	use core::convert::TryInto;
+	use itp_time_utils::now_as_secs;
 	use num_bigint::BigUint;
 	use sgx_tcrypto::SgxEccHandle;
-	use sgx_types::{sgx_ec256_private_t, sgx_ec256_public_t};
+	use sgx_types::{
+		sgx_ec256_private_t, sgx_ec256_public_t, sgx_isv_svn_t, sgx_ql_qe_report_info_t,
+		sgx_ql_qv_supplemental_t, sgx_ql_qve_collateral_t, sgx_quote3_error_t, sgx_read_rand,
+		sgx_self_target, sgx_target_info_t, sgx_tvl_verify_qve_report_and_identity,
+	};
 	use yasna::models::ObjectIdentifier;
 
 	const ISSUER: &str = "Integritee";
 	const SUBJECT: &str = "Integritee ephemeral";
 
+	/// Public key as it is encoded in the certificate, without the uncompressed point tag.
+	pub fn cert_public_key(pub_k: &sgx_ec256_public_t) -> Vec<u8> {
+		let mut pk_gx = pub_k.gx;
+		pk_gx.reverse();
+		let mut pk_gy = pub_k.gy;
+		pk_gy.reverse();
+		[pk_gx, pk_gy].concat()
+	}
+
 	/// `payload` must be a valid a string, not just arbitrary data.
 	pub fn gen_ecc_cert(
 		payload: &str,
@@ -79,12 +116,7 @@ pub mod sgx {
 	) -> Result<(Vec<u8>, Vec<u8>), sgx_status_t> {
 		// Generate public key bytes since both DER will use it
 		let mut pub_key_bytes: Vec<u8> = vec![4];
-		let mut pk_gx = pub_k.gx;
-		pk_gx.reverse();
-		let mut pk_gy = pub_k.gy;
-		pk_gy.reverse();
-		pub_key_bytes.extend_from_slice(&pk_gx);
-		pub_key_bytes.extend_from_slice(&pk_gy);
+		pub_key_bytes.extend_from_slice(&cert_public_key(pub_k));
 
 		// Generate Certificate DER
 		let cert_der = yasna::construct_der(|writer| {
@@ -218,6 +250,102 @@ pub mod sgx {
 
 		Ok((key_der, cert_der))
 	}
+
+	/// Verifies a DCAP quote with the quote verification enclave (QvE) and returns the TCB
+	/// status of the platform that generated it. The untrusted side provides the collateral,
+	/// which is fine, as it is signed by Intel and checked by the QvE.
+	pub fn verify_dcap_quote<A>(quote: &[u8], attestation_ocall: &A) -> SgxResult<DcapQuoteStatus>
+	where
+		A: EnclaveAttestationOCallApi,
+	{
+		let mut app_enclave_target_info: sgx_target_info_t = unsafe { std::mem::zeroed() };
+		let quote_collateral: sgx_ql_qve_collateral_t = unsafe { std::mem::zeroed() };
+		let mut qve_report_info: sgx_ql_qe_report_info_t = unsafe { std::mem::zeroed() };
+		let supplemental_data_size = std::mem::size_of::<sgx_ql_qv_supplemental_t>() as u32;
+
+		// Get target info of the app enclave. QvE will target the generated report to this enclave.
+		let ret_val =
+			unsafe { sgx_self_target(&mut app_enclave_target_info as *mut sgx_target_info_t) };
+		if ret_val != sgx_status_t::SGX_SUCCESS {
+			error!("sgx_self_target returned: {:?}", ret_val);
+			return Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
+		}
+
+		// Set current time, which is needed to check against the expiration date of the certificate.
+		let current_time: i64 = now_as_secs().try_into().unwrap_or_else(|e| {
+			panic!("Could not convert SystemTime from u64 into i64: {:?}", e);
+		});
+
+		// Set random nonce.
+		let mut rand_nonce = vec![0u8; 16];
+		let ret_val = unsafe { sgx_read_rand(rand_nonce.as_mut_ptr(), rand_nonce.len()) };
+		if ret_val != sgx_status_t::SGX_SUCCESS {
+			error!("sgx_read_rand returned: {:?}", ret_val);
+			return Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
+		}
+		debug!("Retrieved random nonce {:?}", rand_nonce);
+		qve_report_info.nonce.rand.copy_from_slice(rand_nonce.as_slice());
+		qve_report_info.app_enclave_target_info = app_enclave_target_info;
+
+		// Ocall to call Quote verification Enclave (QvE), which verifies the generated quote.
+		let (
+			collateral_expiration_status,
+			quote_verification_result,
+			qve_report_info_return_value,
+			supplemental_data,
+		) = attestation_ocall.get_qve_report_on_quote(
+			quote.to_vec(),
+			current_time,
+			quote_collateral,
+			qve_report_info,
+			supplemental_data_size,
+		)?;
+
+		// Check nonce of qve report to protect against replay attacks, as the qve report
+		// is coming from the untrusted side.
+		if qve_report_info_return_value.nonce.rand != qve_report_info.nonce.rand {
+			error!(
+				"Nonce of input value and return value are not matching. Input: {:?}, Output: {:?}",
+				qve_report_info.nonce.rand, qve_report_info_return_value.nonce.rand
+			);
+			return Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
+		}
+
+		// Set the threshold of QvE ISV SVN. The ISV SVN of QvE used to verify quote must be greater or equal to this threshold
+		// e.g. You can check latest QvE ISVSVN from QvE configuration file on Github
+		// https://github.com/intel/SGXDataCenterAttestationPrimitives/blob/master/QuoteVerification/QvE/Enclave/linux/config.xml#L4
+		// or you can get latest QvE ISVSVN in QvE Identity JSON file from
+		// https://api.trustedservices.intel.com/sgx/certification/v3/qve/identity
+		// Make sure you are using trusted & latest QvE ISV SVN as threshold
+		// Warning: The function may return erroneous result if QvE ISV SVN has been modified maliciously.
+		let qve_isvsvn_threshold: sgx_isv_svn_t = 6;
+
+		// Verify the qve report to validate that it is coming from a legit quoting verification enclave
+		// and has not been tampered with.
+		let ret_val = unsafe {
+			sgx_tvl_verify_qve_report_and_identity(
+				quote.as_ptr(),
+				quote.len() as u32,
+				&qve_report_info_return_value as *const sgx_ql_qe_report_info_t,
+				current_time,
+				collateral_expiration_status,
+				quote_verification_result,
+				supplemental_data.as_ptr(),
+				supplemental_data_size,
+				qve_isvsvn_threshold,
+			)
+		};
+
+		if ret_val != sgx_quote3_error_t::SGX_QL_SUCCESS {
+			error!("sgx_tvl_verify_qve_report_and_identity returned: {:?}", ret_val);
+			return Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
+		}
+
+		Ok(DcapQuoteStatus {
+			tcb_status: quote_verification_result.into(),
+			collateral_expired: collateral_expiration_status != 0,
+		})
+	}
 }
 
 pub fn percent_decode(orig: String) -> EnclaveResult<String> {
@@ -235,10 +363,15 @@ pub fn percent_decode(orig: String) -> EnclaveResult<String> {
 }
 
 // FIXME: This code is redundant with the host call of the integritee-node
+/// Verifies the remote attestation in the TLS certificate of a peer enclave.
+///
+/// The peer has to run the same enclave as we do, or one of the `accepted_mrenclaves`, e.g.
+/// the enclaves authorized for a shard during an upgrade.
 pub fn verify_mra_cert<A>(
 	cert_der: &[u8],
 	is_payload_base64_encoded: bool,
 	is_dcap: bool,
+	accepted_mrenclaves: &[[u8; 32]],
 	attestation_ocall: &A,
 ) -> SgxResult<()>
 where
@@ -298,61 +431,143 @@ where
 		let sig_cert_raw = iter.next().ok_or(sgx_status_t::SGX_ERROR_UNEXPECTED)?;
 		let sig_cert_dec = base64::decode_config(sig_cert_raw, base64::STANDARD)
 			.map_err(|e| EnclaveError::Other(e.into()))?;
-		let sig_cert = webpki::EndEntityCert::from(&sig_cert_dec).expect("Bad DER");
-
-		// Verify if the signing cert is issued by Intel CA
-		let mut ias_ca_stripped = IAS_REPORT_CA.to_vec();
-		ias_ca_stripped.retain(|&x| x != b'\r' && x != b'\n');
-		let head_len = "-----BEGIN CERTIFICATE-----".len();
-		let tail_len = "-----END CERTIFICATE-----".len();
-		let full_len = ias_ca_stripped.len();
-		let ias_ca_core: &[u8] = &ias_ca_stripped[head_len..full_len - tail_len];
-		let ias_cert_dec = base64::decode_config(ias_ca_core, base64::STANDARD)
-			.map_err(|e| EnclaveError::Other(e.into()))?;
+		verify_ias_report_signature(attn_report_raw, &sig, &sig_cert_dec)?;
 
-		let mut ca_reader = BufReader::new(IAS_REPORT_CA);
+		verify_attn_report(attn_report_raw, pub_k, accepted_mrenclaves, attestation_ocall)
+	} else {
+		verify_dcap_mra_quote(&payload, &pub_k, accepted_mrenclaves, attestation_ocall)
+	}
+}
 
-		let mut root_store = rustls::RootCertStore::empty();
-		root_store.add_pem_file(&mut ca_reader).expect("Failed to add CA");
+/// Verifies the DCAP quote of a peer enclave, that it was issued for the key of its TLS
+/// certificate `cert_pub_key`, and reports its TCB status.
+#[cfg(feature = "sgx")]
+fn verify_dcap_mra_quote<A>(
+	quote: &[u8],
+	cert_pub_key: &[u8],
+	accepted_mrenclaves: &[[u8; 32]],
+	attestation_ocall: &A,
+) -> SgxResult<()>
+where
+	A: EnclaveAttestationOCallApi,
+{
+	if quote.len() < DCAP_QUOTE_HEADER_SIZE + DCAP_REPORT_BODY_SIZE {
+		error!("DCAP quote of the peer is too short: {} bytes", quote.len());
+		return Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
+	}
+	let report_body = &quote[DCAP_QUOTE_HEADER_SIZE..];
+	let mr_enclave = &report_body[DCAP_MR_ENCLAVE_OFFSET..DCAP_MR_ENCLAVE_OFFSET + 32];
+	let report_data = &report_body[DCAP_REPORT_DATA_OFFSET..DCAP_REPORT_DATA_OFFSET + 64];
+	let signer = &report_data[..32];
 
-		let trust_anchors: Vec<webpki::TrustAnchor> =
-			root_store.roots.iter().map(|cert| cert.to_trust_anchor()).collect();
+	ensure_mrenclave_is_accepted(mr_enclave, accepted_mrenclaves, attestation_ocall)?;
 
-		let now_func = webpki::Time::try_from(SystemTime::now());
+	if report_data[32..] != blake2_256(cert_pub_key) {
+		error!("DCAP quote of the peer was not issued for the key of its TLS certificate");
+		return Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
+	}
 
-		match sig_cert.verify_is_valid_tls_server_cert(
-			SUPPORTED_SIG_ALGS,
-			&webpki::TLSServerTrustAnchors(&trust_anchors),
-			&[ias_cert_dec.as_slice()],
-			now_func.map_err(|_e| EnclaveError::Time)?,
-		) {
-			Ok(_) => info!("Cert is good"),
-			Err(e) => {
-				error!("Cert verification error {:?}", e);
-				return Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
-			},
-		}
+	let status = verify_dcap_quote(quote, attestation_ocall)?;
+	info!(
+		"Peer enclave {:02x}: TCB status {:?}, collateral expired: {}",
+		signer.iter().format(""),
+		status.tcb_status,
+		status.collateral_expired
+	);
+	status.check()
+}
 
-		// Verify the signature against the signing cert
-		match sig_cert.verify_signature(&webpki::RSA_PKCS1_2048_8192_SHA256, attn_report_raw, &sig)
-		{
-			Ok(_) => info!("Signature good"),
-			Err(e) => {
-				error!("Signature verification error {:?}", e);
-				return Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
-			},
-		}
+#[cfg(not(feature = "sgx"))]
+fn verify_dcap_mra_quote<A>(
+	_quote: &[u8],
+	_cert_pub_key: &[u8],
+	_accepted_mrenclaves: &[[u8; 32]],
+	_attestation_ocall: &A,
+) -> SgxResult<()>
+where
+	A: EnclaveAttestationOCallApi,
+{
+	error!("DCAP quotes can only be verified inside the enclave");
+	Err(sgx_status_t::SGX_ERROR_FEATURE_NOT_SUPPORTED)
+}
 
-		verify_attn_report(attn_report_raw, pub_k, attestation_ocall)
-	} else {
-		// TODO Refactor state provisioning to not use MURA #1385
-		Ok(())
+/// Ensures that the peer enclave's `mr_enclave` is our own or one of the `accepted_mrenclaves`.
+fn ensure_mrenclave_is_accepted<A>(
+	mr_enclave: &[u8],
+	accepted_mrenclaves: &[[u8; 32]],
+	attestation_ocall: &A,
+) -> SgxResult<()>
+where
+	A: EnclaveAttestationOCallApi,
+{
+	let ti = attestation_ocall.get_mrenclave_of_self()?;
+	if mr_enclave == ti.m || accepted_mrenclaves.iter().any(|accepted| mr_enclave == accepted) {
+		return Ok(())
 	}
+	error!("mr_enclave {:02x} is neither equal to self nor accepted", mr_enclave.iter().format(""));
+	Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
+}
+
+/// Verify that the IAS attestation `report` is signed with `signing_cert` (DER encoded),
+/// and that this certificate has been issued by the Intel attestation report CA.
+pub fn verify_ias_report_signature(
+	report: &[u8],
+	signature: &[u8],
+	signing_cert: &[u8],
+) -> SgxResult<()> {
+	let sig_cert = webpki::EndEntityCert::from(signing_cert).map_err(|e| {
+		error!("Invalid IAS signing cert {:?}", e);
+		sgx_status_t::SGX_ERROR_UNEXPECTED
+	})?;
+
+	// Verify if the signing cert is issued by Intel CA
+	let mut ias_ca_stripped = IAS_REPORT_CA.to_vec();
+	ias_ca_stripped.retain(|&x| x != b'\r' && x != b'\n');
+	let head_len = "-----BEGIN CERTIFICATE-----".len();
+	let tail_len = "-----END CERTIFICATE-----".len();
+	let full_len = ias_ca_stripped.len();
+	let ias_ca_core: &[u8] = &ias_ca_stripped[head_len..full_len - tail_len];
+	let ias_cert_dec = base64::decode_config(ias_ca_core, base64::STANDARD)
+		.map_err(|e| EnclaveError::Other(e.into()))?;
+
+	let mut ca_reader = BufReader::new(IAS_REPORT_CA);
+
+	let mut root_store = rustls::RootCertStore::empty();
+	root_store.add_pem_file(&mut ca_reader).expect("Failed to add CA");
+
+	let trust_anchors: Vec<webpki::TrustAnchor> =
+		root_store.roots.iter().map(|cert| cert.to_trust_anchor()).collect();
+
+	let now_func = webpki::Time::try_from(SystemTime::now());
+
+	match sig_cert.verify_is_valid_tls_server_cert(
+		SUPPORTED_SIG_ALGS,
+		&webpki::TLSServerTrustAnchors(&trust_anchors),
+		&[ias_cert_dec.as_slice()],
+		now_func.map_err(|_e| EnclaveError::Time)?,
+	) {
+		Ok(_) => info!("Cert is good"),
+		Err(e) => {
+			error!("Cert verification error {:?}", e);
+			return Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
+		},
+	}
+
+	// Verify the signature against the signing cert
+	match sig_cert.verify_signature(&webpki::RSA_PKCS1_2048_8192_SHA256, report, signature) {
+		Ok(_) => info!("Signature good"),
+		Err(e) => {
+			error!("Signature verification error {:?}", e);
+			return Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
+		},
+	}
+	Ok(())
 }
 
 pub fn verify_attn_report<A>(
 	report_raw: &[u8],
 	pub_k: Vec<u8>,
+	accepted_mrenclaves: &[[u8; 32]],
 	attestation_ocall: &A,
 ) -> SgxResult<()>
 where
@@ -431,14 +646,11 @@ where
 		// TODO: lack security check here
 		let sgx_quote: sgx_quote_t = unsafe { ptr::read(quote.as_ptr() as *const _) };
 
-		let ti = attestation_ocall.get_mrenclave_of_self()?;
-		if sgx_quote.report_body.mr_enclave.m != ti.m {
-			error!(
-				"mr_enclave is not equal to self {:?} != {:?}",
-				sgx_quote.report_body.mr_enclave.m, ti.m
-			);
-			return Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
-		}
+		ensure_mrenclave_is_accepted(
+			&sgx_quote.report_body.mr_enclave.m,
+			accepted_mrenclaves,
+			attestation_ocall,
+		)?;
 
 		// ATTENTION
 		// DO SECURITY CHECK ON DEMAND
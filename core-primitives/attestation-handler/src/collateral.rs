@@ -15,14 +15,33 @@
 
 */
 #[cfg(all(not(feature = "std"), feature = "sgx"))]
-use crate::sgx_reexport_prelude::serde_json;
-use sgx_types::sgx_ql_qve_collateral_t;
+use crate::sgx_reexport_prelude::{base64, chrono, serde_json};
+use chrono::DateTime;
+use codec::{Decode, Encode};
+use sgx_types::{c_char, sgx_ql_qve_collateral_t};
 use std::{io::Write, string::String, vec::Vec};
 
+/// Family-model-stepping-platform-custom SKU of an SGX platform, for which Intel issues
+/// the TCB info.
+pub type Fmspc = [u8; 6];
+
+/// Layout of a DCAP quote (`sgx_quote3_t`), up to its certification data.
+const QUOTE_SIGNATURE_DATA_OFFSET: usize = 48 + 384 + 4;
+const QUOTE_ECDSA_SIGNATURE_DATA_SIZE: usize = 64 + 64 + 384 + 64;
+const PCK_CERT_CHAIN_CERTIFICATION_DATA_TYPE: u16 = 5;
+
+const PEM_CERT_BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+const PEM_CERT_END: &str = "-----END CERTIFICATE-----";
+/// OID 1.2.840.113741.1.13.1.4 of the FMSPC extension in a PCK certificate, followed by
+/// the header of the octet string containing the FMSPC.
+const FMSPC_OID_AND_HEADER: [u8; 14] =
+	[0x06, 0x0A, 0x2A, 0x86, 0x48, 0x86, 0xF8, 0x4D, 0x01, 0x0D, 0x01, 0x04, 0x04, 0x06];
+
 /// This is a rust-ified version of the type sgx_ql_qve_collateral_t.
 /// See Appendix A.3 in the document
 /// "Intel® Software Guard Extensions (Intel® SGX) Data Center Attestation Primitives: ECDSA Quote Library API"
 /// https://download.01.org/intel-sgx/latest/dcap-latest/linux/docs/Intel_SGX_ECDSA_QuoteLibReference_DCAP_API.pdf
+#[derive(Encode, Decode, Clone, Debug, Default, PartialEq, Eq)]
 pub struct SgxQlQveCollateral {
 	pub version: u32, // version = 1.  PCK Cert chain is in the Quote.
 	/* intel DCAP 1.13 */
@@ -80,6 +99,37 @@ impl SgxQlQveCollateral {
 		}
 	}
 
+	/// Returns the C type pointing into the buffers of `self`, to hand the collateral to the
+	/// quote verification library. It must not outlive `self`, and must not be written to.
+	pub fn as_c_type(&self) -> sgx_ql_qve_collateral_t {
+		sgx_ql_qve_collateral_t {
+			version: self.version,
+			tee_type: self.tee_type,
+			pck_crl_issuer_chain: self.pck_crl_issuer_chain.as_ptr() as *mut c_char,
+			pck_crl_issuer_chain_size: self.pck_crl_issuer_chain.len() as u32,
+			root_ca_crl: self.root_ca_crl.as_ptr() as *mut c_char,
+			root_ca_crl_size: self.root_ca_crl.len() as u32,
+			pck_crl: self.pck_crl.as_ptr() as *mut c_char,
+			pck_crl_size: self.pck_crl.len() as u32,
+			tcb_info_issuer_chain: self.tcb_info_issuer_chain.as_ptr() as *mut c_char,
+			tcb_info_issuer_chain_size: self.tcb_info_issuer_chain.len() as u32,
+			tcb_info: self.tcb_info.as_ptr() as *mut c_char,
+			tcb_info_size: self.tcb_info.len() as u32,
+			qe_identity_issuer_chain: self.qe_identity_issuer_chain.as_ptr() as *mut c_char,
+			qe_identity_issuer_chain_size: self.qe_identity_issuer_chain.len() as u32,
+			qe_identity: self.qe_identity.as_ptr() as *mut c_char,
+			qe_identity_size: self.qe_identity.len() as u32,
+		}
+	}
+
+	/// Unix time in seconds, at which Intel issues the next TCB info or QE identity,
+	/// whichever comes first. The collateral is considered expired after that.
+	pub fn next_update(&self) -> Option<u64> {
+		let tcb_info_next_update = Self::next_update_of("tcbInfo", &self.tcb_info)?;
+		let qe_identity_next_update = Self::next_update_of("enclaveIdentity", &self.qe_identity)?;
+		Some(tcb_info_next_update.min(qe_identity_next_update))
+	}
+
 	pub fn dump_to_disk(&self) {
 		Self::write_data_to_disk("pck_crl_issuer_chain", &self.pck_crl_issuer_chain);
 		Self::write_data_to_disk("root_ca_crl", &self.root_ca_crl);
@@ -127,12 +177,53 @@ impl SgxQlQveCollateral {
 		Some((data_json, signature))
 	}
 
+	fn next_update_of(data_name: &str, data: &[u8]) -> Option<u64> {
+		let json = String::from_utf8_lossy(data);
+		let json = json.trim_matches(char::from(0));
+		let value: serde_json::Value = serde_json::from_str(json).ok()?;
+		let next_update = value[data_name]["nextUpdate"].as_str()?;
+		let timestamp = DateTime::parse_from_rfc3339(next_update).ok()?.timestamp();
+		u64::try_from(timestamp).ok()
+	}
+
 	fn write_data_to_disk(filename: &str, contents: &[u8]) {
 		let mut file = std::fs::File::create(filename).unwrap();
 		file.write_all(contents).unwrap();
 	}
 }
 
+/// Extracts the FMSPC from the PCK certificate embedded in a DCAP quote, to look up the
+/// collateral the quote has to be verified with.
+pub fn fmspc_from_quote(quote: &[u8]) -> Option<Fmspc> {
+	let mut offset = QUOTE_SIGNATURE_DATA_OFFSET + QUOTE_ECDSA_SIGNATURE_DATA_SIZE;
+	let auth_data_size = u16::from_le_bytes(quote.get(offset..offset + 2)?.try_into().ok()?);
+	offset += 2 + auth_data_size as usize;
+
+	let certification_data_type =
+		u16::from_le_bytes(quote.get(offset..offset + 2)?.try_into().ok()?);
+	if certification_data_type != PCK_CERT_CHAIN_CERTIFICATION_DATA_TYPE {
+		return None
+	}
+	let certification_data_size =
+		u32::from_le_bytes(quote.get(offset + 2..offset + 6)?.try_into().ok()?) as usize;
+	offset += 6;
+	let cert_chain = quote.get(offset..offset + certification_data_size)?;
+
+	// The PCK certificate is the first one of the chain.
+	let cert_chain = String::from_utf8_lossy(cert_chain);
+	let begin = cert_chain.find(PEM_CERT_BEGIN)? + PEM_CERT_BEGIN.len();
+	let end = begin + cert_chain[begin..].find(PEM_CERT_END)?;
+	let pem_body: String =
+		cert_chain[begin..end].chars().filter(|c| !c.is_ascii_whitespace()).collect();
+	let pck_cert = base64::decode(pem_body).ok()?;
+
+	let position = pck_cert
+		.windows(FMSPC_OID_AND_HEADER.len())
+		.position(|window| window == FMSPC_OID_AND_HEADER)?;
+	let fmspc_offset = position + FMSPC_OID_AND_HEADER.len();
+	pck_cert.get(fmspc_offset..fmspc_offset + 6)?.try_into().ok()
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -155,4 +246,46 @@ mod tests {
 		let json = br#"{"tcbInfo":{"id":"SGX"},"signature":""#;
 		assert!(SgxQlQveCollateral::separate_json_data_and_signature("tcbInfo", json).is_none());
 	}
+
+	#[test]
+	fn next_update_is_the_earlier_one_of_tcb_info_and_qe_identity() {
+		let collateral = SgxQlQveCollateral {
+			tcb_info: br#"{"tcbInfo":{"nextUpdate":"2023-06-14T10:22:11Z"},"signature":"00"}"#
+				.to_vec(),
+			qe_identity:
+				b"{\"enclaveIdentity\":{\"nextUpdate\":\"2023-06-13T00:00:00Z\"},\"signature\":\"00\"}\0"
+					.to_vec(),
+			..Default::default()
+		};
+		assert_eq!(collateral.next_update(), Some(1686614400));
+
+		let collateral = SgxQlQveCollateral { qe_identity: vec![], ..collateral };
+		assert_eq!(collateral.next_update(), None);
+	}
+
+	#[test]
+	fn fmspc_is_extracted_from_the_pck_certificate_of_a_quote() {
+		let fmspc = [0x00, 0x90, 0x6e, 0xa1, 0x00, 0x00];
+		let mut pck_cert = vec![0x30, 0x82, 0x01, 0x00];
+		pck_cert.extend_from_slice(&FMSPC_OID_AND_HEADER);
+		pck_cert.extend_from_slice(&fmspc);
+		let cert_chain = format!(
+			"{}\n{}\n{}\n{}\nMIIB\n{}\n",
+			PEM_CERT_BEGIN,
+			base64::encode(&pck_cert),
+			PEM_CERT_END,
+			PEM_CERT_BEGIN,
+			PEM_CERT_END
+		);
+
+		let mut quote = vec![0u8; QUOTE_SIGNATURE_DATA_OFFSET + QUOTE_ECDSA_SIGNATURE_DATA_SIZE];
+		quote.extend_from_slice(&2u16.to_le_bytes());
+		quote.extend_from_slice(&[1, 2]);
+		quote.extend_from_slice(&PCK_CERT_CHAIN_CERTIFICATION_DATA_TYPE.to_le_bytes());
+		quote.extend_from_slice(&(cert_chain.len() as u32).to_le_bytes());
+		quote.extend_from_slice(cert_chain.as_bytes());
+
+		assert_eq!(fmspc_from_quote(&quote), Some(fmspc));
+		assert_eq!(fmspc_from_quote(&quote[..quote.len() - 10]), None);
+	}
 }
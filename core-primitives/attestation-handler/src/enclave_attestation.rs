@@ -0,0 +1,385 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Attestation of an enclave towards relying parties, e.g. the receivers of its Nostr badges.
+//!
+//! The enclave binds the public keys it controls into the report data of its remote
+//! attestation. The first 32 bytes are the enclave signing key, as checked by the teerex
+//! pallet upon registration, the last 32 bytes are the blake2-256 hash of the shielding key
+//! and the Nostr issuer key. [`verify_enclave_attestation`] checks an [`EnclaveAttestation`]
+//! without access to the enclave.
+//!
+//! Only the signature of an IAS report can be verified offline. The signature of a DCAP quote
+//! is verified by the teerex pallet upon registration, which stores the report data of the
+//! quote. A DCAP attestation is therefore only verified against the [`EnclaveRegistration`]
+//! of the enclave, never on its own.
+
+#[cfg(all(not(feature = "std"), feature = "sgx"))]
+use crate::sgx_reexport_prelude::*;
+
+use crate::cert::verify_ias_report_signature;
+use codec::{Decode, Encode};
+use serde_json::Value;
+use sp_core::hashing::blake2_256;
+use std::{format, string::String, vec::Vec};
+
+pub const REPORT_DATA_SIZE: usize = 64;
+
+/// Header size of an EPID quote (`sgx_quote_t`) as well as a DCAP quote (`sgx_quote3_t`).
+const QUOTE_HEADER_SIZE: usize = 48;
+const REPORT_BODY_SIZE: usize = 384;
+const MR_ENCLAVE_OFFSET: usize = 64;
+const MR_SIGNER_OFFSET: usize = 128;
+const ISV_SVN_OFFSET: usize = 258;
+const REPORT_DATA_OFFSET: usize = 320;
+const DCAP_QUOTE_VERSION: u16 = 3;
+
+/// IAS quote statuses with which the enclave is considered genuine. Anything but `OK`
+/// means that the platform lacks updates, which is up to the relying party to judge.
+const ACCEPTED_IAS_QUOTE_STATUSES: [&str; 4] =
+	["OK", "SW_HARDENING_NEEDED", "GROUP_OUT_OF_DATE", "CONFIGURATION_NEEDED"];
+
+/// Public keys an enclave binds into the report data of its attestation.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct AttestedKeys {
+	/// Ed25519 enclave signing key, i.e. the enclave account on the parentchain.
+	pub signing_key: [u8; 32],
+	/// JSON encoded RSA3072 shielding public key.
+	pub shielding_key: Vec<u8>,
+	/// X-only secp256k1 public key, with which the enclave signs Nostr events.
+	pub nostr_issuer_key: [u8; 32],
+}
+
+impl AttestedKeys {
+	pub fn report_data(&self) -> [u8; REPORT_DATA_SIZE] {
+		let mut report_data = [0u8; REPORT_DATA_SIZE];
+		report_data[..32].copy_from_slice(&self.signing_key);
+		report_data[32..]
+			.copy_from_slice(&blake2_256(&(&self.shielding_key, self.nostr_issuer_key).encode()));
+		report_data
+	}
+}
+
+/// Evidence of a remote attestation, as produced by the attestation handler.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub enum AttestationEvidence {
+	/// Raw DCAP quote.
+	Dcap(Vec<u8>),
+	/// IAS attestation report, its signature and the DER encoded certificate it is signed with.
+	Ias { report: Vec<u8>, signature: Vec<u8>, signing_cert: Vec<u8> },
+}
+
+/// Attestation of an enclave, as returned by the `attesteer_getEnclaveAttestation` RPC.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct EnclaveAttestation {
+	pub mrenclave: [u8; 32],
+	pub keys: AttestedKeys,
+	/// Evidence of the latest remote attestation of the enclave. `None` if it has not been
+	/// attested since it started, e.g. because it runs with `--skip-ra`.
+	pub evidence: Option<AttestationEvidence>,
+}
+
+/// Registration of an enclave on the parentchain. The teerex pallet only registers an enclave
+/// after verifying the signature of its quote.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnclaveRegistration {
+	pub mrenclave: [u8; 32],
+	/// Report data of the quote the enclave registered with.
+	pub report_data: Vec<u8>,
+}
+
+/// Outcome of a successful [`verify_enclave_attestation`]: the evidence is signed by Intel,
+/// either checked directly or by the teerex pallet upon registration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifiedEnclave {
+	pub mrenclave: [u8; 32],
+	pub mrsigner: [u8; 32],
+	pub isv_svn: u16,
+	pub keys: AttestedKeys,
+	/// Quote status reported by IAS, `None` for DCAP.
+	pub ias_quote_status: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum VerificationError {
+	#[error("The enclave has not been remote attested")]
+	MissingEvidence,
+	#[error("Malformed quote: {0}")]
+	MalformedQuote(String),
+	#[error("Unsupported DCAP quote version: {0}")]
+	UnsupportedQuoteVersion(u16),
+	#[error("Malformed IAS report: {0}")]
+	MalformedReport(String),
+	#[error("IAS report is not signed by Intel")]
+	InvalidReportSignature,
+	#[error("Unaccepted IAS quote status: {0}")]
+	UnacceptedQuoteStatus(String),
+	#[error("MRENCLAVE of the quote does not match the claimed one")]
+	MrenclaveMismatch,
+	#[error("Report data of the quote does not match the claimed keys")]
+	ReportDataMismatch,
+	#[error("The signature of a DCAP quote can only be verified by the enclave registration")]
+	MissingRegistration,
+	#[error("The enclave is registered with a different MRENCLAVE or report data")]
+	RegistrationMismatch,
+}
+
+/// Verify that the evidence of `attestation` is signed by Intel and attests the claimed
+/// MRENCLAVE and keys.
+///
+/// An IAS report is verified on its own, a DCAP quote only against the `registration` of the
+/// enclave. If a `registration` is given, the attestation must match it in any case.
+pub fn verify_enclave_attestation(
+	attestation: &EnclaveAttestation,
+	registration: Option<&EnclaveRegistration>,
+) -> Result<VerifiedEnclave, VerificationError> {
+	let (quote, ias_quote_status) =
+		match attestation.evidence.as_ref().ok_or(VerificationError::MissingEvidence)? {
+			AttestationEvidence::Dcap(quote) => {
+				if registration.is_none() {
+					return Err(VerificationError::MissingRegistration)
+				}
+				let version = quote_version(quote)?;
+				if version != DCAP_QUOTE_VERSION {
+					return Err(VerificationError::UnsupportedQuoteVersion(version))
+				}
+				(quote.clone(), None)
+			},
+			AttestationEvidence::Ias { report, signature, signing_cert } => {
+				verify_ias_report_signature(report, signature, signing_cert)
+					.map_err(|_| VerificationError::InvalidReportSignature)?;
+				let (quote, status) = parse_ias_report(report)?;
+				(quote, Some(status))
+			},
+		};
+
+	let report_body = quote
+		.get(QUOTE_HEADER_SIZE..QUOTE_HEADER_SIZE + REPORT_BODY_SIZE)
+		.ok_or_else(|| VerificationError::MalformedQuote(format!("length {}", quote.len())))?;
+
+	if report_body[MR_ENCLAVE_OFFSET..MR_ENCLAVE_OFFSET + 32] != attestation.mrenclave {
+		return Err(VerificationError::MrenclaveMismatch)
+	}
+	if report_body[REPORT_DATA_OFFSET..REPORT_DATA_OFFSET + REPORT_DATA_SIZE]
+		!= attestation.keys.report_data()
+	{
+		return Err(VerificationError::ReportDataMismatch)
+	}
+	if let Some(registration) = registration {
+		if registration.mrenclave != attestation.mrenclave
+			|| registration.report_data != attestation.keys.report_data()
+		{
+			return Err(VerificationError::RegistrationMismatch)
+		}
+	}
+
+	let mut mrsigner = [0u8; 32];
+	mrsigner.copy_from_slice(&report_body[MR_SIGNER_OFFSET..MR_SIGNER_OFFSET + 32]);
+
+	Ok(VerifiedEnclave {
+		mrenclave: attestation.mrenclave,
+		mrsigner,
+		isv_svn: u16::from_le_bytes([report_body[ISV_SVN_OFFSET], report_body[ISV_SVN_OFFSET + 1]]),
+		keys: attestation.keys.clone(),
+		ias_quote_status,
+	})
+}
+
+fn quote_version(quote: &[u8]) -> Result<u16, VerificationError> {
+	match quote {
+		[low, high, ..] => Ok(u16::from_le_bytes([*low, *high])),
+		_ => Err(VerificationError::MalformedQuote(format!("length {}", quote.len()))),
+	}
+}
+
+/// Returns the quote and the quote status of an IAS attestation report.
+fn parse_ias_report(report: &[u8]) -> Result<(Vec<u8>, String), VerificationError> {
+	let report: Value = serde_json::from_slice(report)
+		.map_err(|e| VerificationError::MalformedReport(format!("{:?}", e)))?;
+
+	let status = match &report["isvEnclaveQuoteStatus"] {
+		Value::String(status) => status.clone(),
+		_ => return Err(VerificationError::MalformedReport("missing quote status".into())),
+	};
+	if !ACCEPTED_IAS_QUOTE_STATUSES.contains(&status.as_str()) {
+		return Err(VerificationError::UnacceptedQuoteStatus(status))
+	}
+
+	let quote = match &report["isvEnclaveQuoteBody"] {
+		Value::String(quote) => base64::decode(quote)
+			.map_err(|e| VerificationError::MalformedReport(format!("{:?}", e)))?,
+		_ => return Err(VerificationError::MalformedReport("missing quote body".into())),
+	};
+	Ok((quote, status))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn attested_keys() -> AttestedKeys {
+		AttestedKeys {
+			signing_key: [1u8; 32],
+			shielding_key: b"{\"n\":[1],\"e\":[1]}".to_vec(),
+			nostr_issuer_key: [2u8; 32],
+		}
+	}
+
+	fn dcap_quote(mrenclave: [u8; 32], report_data: [u8; REPORT_DATA_SIZE]) -> Vec<u8> {
+		let mut quote = vec![0u8; QUOTE_HEADER_SIZE + REPORT_BODY_SIZE + 4];
+		quote[..2].copy_from_slice(&DCAP_QUOTE_VERSION.to_le_bytes());
+		let body = QUOTE_HEADER_SIZE;
+		quote[body + MR_ENCLAVE_OFFSET..body + MR_ENCLAVE_OFFSET + 32].copy_from_slice(&mrenclave);
+		quote[body + MR_SIGNER_OFFSET..body + MR_SIGNER_OFFSET + 32].copy_from_slice(&[9u8; 32]);
+		quote[body + ISV_SVN_OFFSET..body + ISV_SVN_OFFSET + 2]
+			.copy_from_slice(&7u16.to_le_bytes());
+		quote[body + REPORT_DATA_OFFSET..body + REPORT_DATA_OFFSET + REPORT_DATA_SIZE]
+			.copy_from_slice(&report_data);
+		quote
+	}
+
+	fn attestation(quote: Vec<u8>) -> EnclaveAttestation {
+		EnclaveAttestation {
+			mrenclave: [3u8; 32],
+			keys: attested_keys(),
+			evidence: Some(AttestationEvidence::Dcap(quote)),
+		}
+	}
+
+	#[test]
+	fn report_data_starts_with_signing_key() {
+		let report_data = attested_keys().report_data();
+
+		assert_eq!(report_data[..32], [1u8; 32]);
+		assert_ne!(report_data[32..], [0u8; 32]);
+	}
+
+	#[test]
+	fn report_data_binds_every_key() {
+		let mut other_nostr_key = attested_keys();
+		other_nostr_key.nostr_issuer_key = [4u8; 32];
+		let mut other_shielding_key = attested_keys();
+		other_shielding_key.shielding_key = b"{}".to_vec();
+
+		assert_ne!(attested_keys().report_data(), other_nostr_key.report_data());
+		assert_ne!(attested_keys().report_data(), other_shielding_key.report_data());
+	}
+
+	fn registration() -> EnclaveRegistration {
+		EnclaveRegistration {
+			mrenclave: [3u8; 32],
+			report_data: attested_keys().report_data().to_vec(),
+		}
+	}
+
+	#[test]
+	fn verifying_registered_dcap_quote_with_matching_keys_works() {
+		let quote = dcap_quote([3u8; 32], attested_keys().report_data());
+
+		let verified =
+			verify_enclave_attestation(&attestation(quote), Some(&registration())).unwrap();
+
+		assert_eq!(verified.mrsigner, [9u8; 32]);
+		assert_eq!(verified.isv_svn, 7);
+		assert_eq!(verified.keys, attested_keys());
+	}
+
+	#[test]
+	fn verifying_dcap_quote_without_registration_fails() {
+		let quote = dcap_quote([3u8; 32], attested_keys().report_data());
+
+		assert_eq!(
+			verify_enclave_attestation(&attestation(quote), None),
+			Err(VerificationError::MissingRegistration)
+		);
+	}
+
+	#[test]
+	fn verifying_dcap_quote_of_otherwise_registered_enclave_fails() {
+		let quote = dcap_quote([3u8; 32], attested_keys().report_data());
+		let mut other_keys = attested_keys();
+		other_keys.nostr_issuer_key = [4u8; 32];
+		let registration = EnclaveRegistration {
+			mrenclave: [3u8; 32],
+			report_data: other_keys.report_data().to_vec(),
+		};
+
+		assert_eq!(
+			verify_enclave_attestation(&attestation(quote), Some(&registration)),
+			Err(VerificationError::RegistrationMismatch)
+		);
+	}
+
+	#[test]
+	fn verifying_dcap_quote_with_other_keys_fails() {
+		let mut other_keys = attested_keys();
+		other_keys.nostr_issuer_key = [4u8; 32];
+		let quote = dcap_quote([3u8; 32], other_keys.report_data());
+
+		assert_eq!(
+			verify_enclave_attestation(&attestation(quote), Some(&registration())),
+			Err(VerificationError::ReportDataMismatch)
+		);
+	}
+
+	#[test]
+	fn verifying_dcap_quote_of_other_enclave_fails() {
+		let quote = dcap_quote([5u8; 32], attested_keys().report_data());
+
+		assert_eq!(
+			verify_enclave_attestation(&attestation(quote), Some(&registration())),
+			Err(VerificationError::MrenclaveMismatch)
+		);
+	}
+
+	#[test]
+	fn verifying_truncated_quote_fails() {
+		let mut quote = dcap_quote([3u8; 32], attested_keys().report_data());
+		quote.truncate(QUOTE_HEADER_SIZE + 100);
+
+		assert!(matches!(
+			verify_enclave_attestation(&attestation(quote), Some(&registration())),
+			Err(VerificationError::MalformedQuote(_))
+		));
+	}
+
+	#[test]
+	fn verifying_without_evidence_fails() {
+		let mut attestation = attestation(vec![]);
+		attestation.evidence = None;
+
+		assert_eq!(
+			verify_enclave_attestation(&attestation, None),
+			Err(VerificationError::MissingEvidence)
+		);
+	}
+
+	#[test]
+	fn unsigned_ias_report_is_rejected() {
+		let mut attestation = attestation(vec![]);
+		attestation.evidence = Some(AttestationEvidence::Ias {
+			report: b"{}".to_vec(),
+			signature: vec![1u8; 256],
+			signing_cert: vec![2u8; 32],
+		});
+
+		assert_eq!(
+			verify_enclave_attestation(&attestation, None),
+			Err(VerificationError::InvalidReportSignature)
+		);
+	}
+}
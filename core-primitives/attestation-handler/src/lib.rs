@@ -43,13 +43,22 @@ pub mod collateral;
 
 pub mod cert;
 
+pub mod enclave_attestation;
+
 pub mod error;
 
+pub mod tcb_status;
+
 #[cfg(all(not(feature = "std"), feature = "sgx"))]
 pub use attestation_handler::{AttestationHandler, IntelAttestationHandler, DEV_HOSTNAME};
-pub use collateral::SgxQlQveCollateral;
+pub use collateral::{fmspc_from_quote, Fmspc, SgxQlQveCollateral};
+pub use enclave_attestation::{
+	verify_enclave_attestation, AttestationEvidence, AttestedKeys, EnclaveAttestation,
+	EnclaveRegistration, VerificationError, VerifiedEnclave,
+};
 
 pub use error::{Error, Result};
+pub use tcb_status::{DcapQuoteStatus, TcbStatus};
 
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub enum RemoteAttestationType {
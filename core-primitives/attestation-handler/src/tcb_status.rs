@@ -0,0 +1,112 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! TCB status of a DCAP quote, as reported by the quote verification enclave (QvE).
+
+use codec::{Decode, Encode};
+use sgx_types::{sgx_ql_qv_result_t, sgx_status_t};
+
+/// TCB level of the platform that generated a quote, judged by the TCB info collateral.
+#[derive(Encode, Decode, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TcbStatus {
+	UpToDate,
+	SwHardeningNeeded,
+	ConfigurationNeeded,
+	ConfigurationAndSwHardeningNeeded,
+	OutOfDate,
+	OutOfDateConfigurationNeeded,
+	Revoked,
+	InvalidSignature,
+	Unspecified,
+}
+
+impl From<sgx_ql_qv_result_t> for TcbStatus {
+	fn from(result: sgx_ql_qv_result_t) -> Self {
+		match result {
+			sgx_ql_qv_result_t::SGX_QL_QV_RESULT_OK => TcbStatus::UpToDate,
+			sgx_ql_qv_result_t::SGX_QL_QV_RESULT_SW_HARDENING_NEEDED =>
+				TcbStatus::SwHardeningNeeded,
+			sgx_ql_qv_result_t::SGX_QL_QV_RESULT_CONFIG_NEEDED => TcbStatus::ConfigurationNeeded,
+			sgx_ql_qv_result_t::SGX_QL_QV_RESULT_CONFIG_AND_SW_HARDENING_NEEDED =>
+				TcbStatus::ConfigurationAndSwHardeningNeeded,
+			sgx_ql_qv_result_t::SGX_QL_QV_RESULT_OUT_OF_DATE => TcbStatus::OutOfDate,
+			sgx_ql_qv_result_t::SGX_QL_QV_RESULT_OUT_OF_DATE_CONFIG_NEEDED =>
+				TcbStatus::OutOfDateConfigurationNeeded,
+			sgx_ql_qv_result_t::SGX_QL_QV_RESULT_REVOKED => TcbStatus::Revoked,
+			sgx_ql_qv_result_t::SGX_QL_QV_RESULT_INVALID_SIGNATURE => TcbStatus::InvalidSignature,
+			_ => TcbStatus::Unspecified,
+		}
+	}
+}
+
+/// Outcome of the verification of a DCAP quote.
+#[derive(Encode, Decode, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DcapQuoteStatus {
+	pub tcb_status: TcbStatus,
+	/// The collateral the quote has been verified with has passed its next update date.
+	pub collateral_expired: bool,
+}
+
+impl DcapQuoteStatus {
+	/// Maps the status to the result of a remote attestation, like for IAS reports:
+	/// a platform lacking updates, or expired collateral, yields `SGX_ERROR_UPDATE_NEEDED`,
+	/// which the caller may choose to accept.
+	pub fn check(&self) -> Result<(), sgx_status_t> {
+		match self.tcb_status {
+			TcbStatus::Revoked | TcbStatus::InvalidSignature | TcbStatus::Unspecified =>
+				Err(sgx_status_t::SGX_ERROR_UNEXPECTED),
+			TcbStatus::ConfigurationNeeded
+			| TcbStatus::ConfigurationAndSwHardeningNeeded
+			| TcbStatus::OutOfDate
+			| TcbStatus::OutOfDateConfigurationNeeded => Err(sgx_status_t::SGX_ERROR_UPDATE_NEEDED),
+			TcbStatus::UpToDate | TcbStatus::SwHardeningNeeded if self.collateral_expired =>
+				Err(sgx_status_t::SGX_ERROR_UPDATE_NEEDED),
+			TcbStatus::UpToDate | TcbStatus::SwHardeningNeeded => Ok(()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn up_to_date_platform_with_valid_collateral_is_accepted() {
+		let status = DcapQuoteStatus { tcb_status: TcbStatus::UpToDate, collateral_expired: false };
+		assert_eq!(status.check(), Ok(()));
+
+		let status =
+			DcapQuoteStatus { tcb_status: TcbStatus::SwHardeningNeeded, collateral_expired: false };
+		assert_eq!(status.check(), Ok(()));
+	}
+
+	#[test]
+	fn expired_collateral_or_outdated_platform_needs_update() {
+		let status = DcapQuoteStatus { tcb_status: TcbStatus::UpToDate, collateral_expired: true };
+		assert_eq!(status.check(), Err(sgx_status_t::SGX_ERROR_UPDATE_NEEDED));
+
+		let status =
+			DcapQuoteStatus { tcb_status: TcbStatus::OutOfDate, collateral_expired: false };
+		assert_eq!(status.check(), Err(sgx_status_t::SGX_ERROR_UPDATE_NEEDED));
+	}
+
+	#[test]
+	fn revoked_platform_is_rejected() {
+		let status = DcapQuoteStatus { tcb_status: TcbStatus::Revoked, collateral_expired: false };
+		assert_eq!(status.check(), Err(sgx_status_t::SGX_ERROR_UNEXPECTED));
+	}
+}
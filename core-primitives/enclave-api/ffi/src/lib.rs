@@ -64,8 +64,17 @@ extern "C" {
 		parentchain_id_size: u32,
 	) -> sgx_status_t;
 
+	pub fn set_personhood_sources(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		personhood_sources: *const u8,
+		personhood_sources_size: u32,
+	) -> sgx_status_t;
+
 	pub fn execute_trusted_calls(eid: sgx_enclave_id_t, retval: *mut sgx_status_t) -> sgx_status_t;
 
+	pub fn seal_top_pool(eid: sgx_enclave_id_t, retval: *mut sgx_status_t) -> sgx_status_t;
+
 	pub fn sync_parentchain(
 		eid: sgx_enclave_id_t,
 		retval: *mut sgx_status_t,
@@ -197,10 +206,8 @@ extern "C" {
 	pub fn update_market_data_xt(
 		eid: sgx_enclave_id_t,
 		retval: *mut sgx_status_t,
-		crypto_currency: *const u8,
-		crypto_currency_size: u32,
-		fiat_currency: *const u8,
-		fiat_currency_size: u32,
+		candidates: *const u8,
+		candidates_size: u32,
 		unchecked_extrinsic: *mut u8,
 		unchecked_extrinsic_size: u32,
 	) -> sgx_status_t;
@@ -208,10 +215,8 @@ extern "C" {
 	pub fn update_weather_data_xt(
 		eid: sgx_enclave_id_t,
 		retval: *mut sgx_status_t,
-		weather_info_longitude: *const u8,
-		weather_info_longitude_size: u32,
-		weather_info_latitude: *const u8,
-		weather_info_latitude_size: u32,
+		weather_query: *const u8,
+		weather_query_size: u32,
 		unchecked_extrinsic: *mut u8,
 		unchecked_extrinsic_size: u32,
 	) -> sgx_status_t;
@@ -235,7 +240,72 @@ extern "C" {
 		quote_size: Option<&u32>,
 		shard: *const u8,
 		shard_size: u32,
+		items: *const u8,
+		items_size: u32,
+		legacy_framing: c_int,
 		skip_ra: c_int,
 	) -> sgx_status_t;
 
+	pub fn write_state_import_key(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		import_key_path: *const u8,
+		import_key_path_size: u32,
+	) -> sgx_status_t;
+
+	pub fn export_state_snapshot_to_file(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		shard: *const u8,
+		shard_size: u32,
+		maybe_state_hash: *const u8,
+		maybe_state_hash_size: u32,
+		import_key_path: *const u8,
+		import_key_path_size: u32,
+		export_path: *const u8,
+		export_path_size: u32,
+	) -> sgx_status_t;
+
+	pub fn import_state_snapshot_from_file(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		snapshot_path: *const u8,
+		snapshot_path_size: u32,
+	) -> sgx_status_t;
+
+	pub fn export_sealed_secrets_to_file(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		shard: *const u8,
+		shard_size: u32,
+		import_key_path: *const u8,
+		import_key_path_size: u32,
+		export_path: *const u8,
+		export_path_size: u32,
+	) -> sgx_status_t;
+
+	pub fn import_sealed_secrets_from_file(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		secrets_path: *const u8,
+		secrets_path_size: u32,
+	) -> sgx_status_t;
+
+	pub fn get_light_client_backups(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		parentchain_id: *const u8,
+		parentchain_id_size: u32,
+		backups: *mut u8,
+		backups_size: u32,
+	) -> sgx_status_t;
+
+	pub fn restore_light_client_backup(
+		eid: sgx_enclave_id_t,
+		retval: *mut sgx_status_t,
+		parentchain_id: *const u8,
+		parentchain_id_size: u32,
+		generation: u64,
+	) -> sgx_status_t;
+
 }
@@ -25,6 +25,7 @@ use itp_enclave_api_ffi as ffi;
 use itp_settings::worker::{
 	HEADER_MAX_SIZE, MR_ENCLAVE_SIZE, SHIELDING_KEY_SIZE, SIGNING_KEY_SIZE,
 };
+use itp_types::personhood::PersonhoodSourceConfig;
 use log::*;
 use sgx_crypto_helper::rsa3072::Rsa3072PubKey;
 use sgx_types::*;
@@ -69,6 +70,10 @@ pub trait EnclaveBase: Send + Sync + 'static {
 		parentchain_id: ParentchainId,
 	) -> EnclaveResult<()>;
 
+	/// Set the target parentchains the personhood oracle reads from, in the order they are
+	/// consulted.
+	fn set_personhood_sources(&self, sources: &[PersonhoodSourceConfig]) -> EnclaveResult<()>;
+
 	fn get_rsa_shielding_pubkey(&self) -> EnclaveResult<Rsa3072PubKey>;
 
 	fn get_ecc_signing_pubkey(&self) -> EnclaveResult<ed25519::Public>;
@@ -233,6 +238,26 @@ impl EnclaveBase for Enclave {
 		Ok(())
 	}
 
+	fn set_personhood_sources(&self, sources: &[PersonhoodSourceConfig]) -> EnclaveResult<()> {
+		let mut retval = sgx_status_t::SGX_SUCCESS;
+
+		let sources_enc = sources.encode();
+
+		let result = unsafe {
+			ffi::set_personhood_sources(
+				self.eid,
+				&mut retval,
+				sources_enc.as_ptr(),
+				sources_enc.len() as u32,
+			)
+		};
+
+		ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+		ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+		Ok(())
+	}
+
 	fn get_rsa_shielding_pubkey(&self) -> EnclaveResult<Rsa3072PubKey> {
 		let mut retval = sgx_status_t::SGX_SUCCESS;
 
@@ -20,8 +20,11 @@ pub mod direct_request;
 pub mod enclave_base;
 pub mod enclave_test;
 pub mod error;
+pub mod light_client_backup;
 pub mod remote_attestation;
+pub mod secret_migration;
 pub mod sidechain;
+pub mod state_snapshot;
 pub mod teeracle_api;
 pub mod utils;
 
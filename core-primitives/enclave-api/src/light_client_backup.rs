@@ -0,0 +1,93 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::{error::Error, Enclave, EnclaveResult};
+use codec::{Decode, Encode};
+use frame_support::ensure;
+use itc_parentchain::{light_client::backup::LightClientBackupInfo, primitives::ParentchainId};
+use itp_enclave_api_ffi as ffi;
+use sgx_types::sgx_status_t;
+
+/// Upper bound of the encoded backup list, which holds a handful of fixed-size entries.
+const BACKUP_INFOS_MAX_SIZE: usize = 1024;
+
+/// Inspection and rollback of the sealed light client backups.
+pub trait LightClientBackups: Send + Sync + 'static {
+	/// List the backup slots of the light client of `parentchain_id`.
+	fn list_light_client_backups(
+		&self,
+		parentchain_id: &ParentchainId,
+	) -> EnclaveResult<Vec<LightClientBackupInfo>>;
+
+	/// Replace the light client db of `parentchain_id` with the backup of `generation`.
+	fn restore_light_client_backup(
+		&self,
+		parentchain_id: &ParentchainId,
+		generation: u64,
+	) -> EnclaveResult<()>;
+}
+
+impl LightClientBackups for Enclave {
+	fn list_light_client_backups(
+		&self,
+		parentchain_id: &ParentchainId,
+	) -> EnclaveResult<Vec<LightClientBackupInfo>> {
+		let mut retval = sgx_status_t::SGX_SUCCESS;
+		let parentchain_id_enc = parentchain_id.encode();
+		let mut backups = vec![0u8; BACKUP_INFOS_MAX_SIZE];
+
+		let result = unsafe {
+			ffi::get_light_client_backups(
+				self.eid,
+				&mut retval,
+				parentchain_id_enc.as_ptr(),
+				parentchain_id_enc.len() as u32,
+				backups.as_mut_ptr(),
+				backups.len() as u32,
+			)
+		};
+
+		ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+		ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+		Ok(Decode::decode(&mut backups.as_slice())?)
+	}
+
+	fn restore_light_client_backup(
+		&self,
+		parentchain_id: &ParentchainId,
+		generation: u64,
+	) -> EnclaveResult<()> {
+		let mut retval = sgx_status_t::SGX_SUCCESS;
+		let parentchain_id_enc = parentchain_id.encode();
+
+		let result = unsafe {
+			ffi::restore_light_client_backup(
+				self.eid,
+				&mut retval,
+				parentchain_id_enc.as_ptr(),
+				parentchain_id_enc.len() as u32,
+				generation,
+			)
+		};
+
+		ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+		ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+		Ok(())
+	}
+}
@@ -34,6 +34,34 @@ const ID_ENCLAVE: &str = "libsgx_id_enclave.signed.so.1";
 const LIBDCAP_QUOTEPROV: &str = "libdcap_quoteprov.so.1";
 const QVE_ENCLAVE: &str = "libsgx_qve.signed.so.1";
 
+/// Returned by the enclave if the provisioning server only speaks the legacy protocol, which
+/// requires to reconnect with `legacy_framing`.
+pub const LEGACY_PROVISIONING_SERVER_STATUS: sgx_status_t =
+	sgx_status_t::SGX_ERROR_UNSUPPORTED_FEATURE;
+
+/// Item of the state provisioning, with the opcode the enclave uses for it.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ProvisioningItem {
+	ShieldingKey = 0,
+	StateKey = 1,
+	State = 2,
+	LightClient = 3,
+}
+
+impl std::str::FromStr for ProvisioningItem {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"shielding-key" => Ok(ProvisioningItem::ShieldingKey),
+			"state-key" => Ok(ProvisioningItem::StateKey),
+			"state" => Ok(ProvisioningItem::State),
+			"light-client" => Ok(ProvisioningItem::LightClient),
+			_ => Err(format!("Unknown provisioning item: {}", s)),
+		}
+	}
+}
+
 /// Struct that unites all relevant data reported by the QVE
 pub struct QveReport {
 	pub supplemental_data: Vec<u8>,
@@ -127,6 +155,8 @@ pub trait TlsRemoteAttestation {
 		quoting_enclave_target_info: Option<&sgx_target_info_t>,
 		quote_size: Option<&u32>,
 		shard: &ShardIdentifier,
+		items: &[ProvisioningItem],
+		legacy_framing: bool,
 		skip_ra: bool,
 	) -> EnclaveResult<()>;
 }
@@ -718,11 +748,14 @@ impl TlsRemoteAttestation for Enclave {
 		quoting_enclave_target_info: Option<&sgx_target_info_t>,
 		quote_size: Option<&u32>,
 		shard: &ShardIdentifier,
+		items: &[ProvisioningItem],
+		legacy_framing: bool,
 		skip_ra: bool,
 	) -> EnclaveResult<()> {
 		let mut retval = sgx_status_t::SGX_SUCCESS;
 
 		let encoded_shard = shard.encode();
+		let opcodes: Vec<u8> = items.iter().map(|item| *item as u8).collect();
 
 		let result = unsafe {
 			ffi::request_state_provisioning(
@@ -734,6 +767,9 @@ impl TlsRemoteAttestation for Enclave {
 				quote_size,
 				encoded_shard.as_ptr(),
 				encoded_shard.len() as u32,
+				opcodes.as_ptr(),
+				opcodes.len() as u32,
+				legacy_framing.into(),
 				skip_ra.into(),
 			)
 		};
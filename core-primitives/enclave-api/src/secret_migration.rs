@@ -0,0 +1,85 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::{error::Error, Enclave, EnclaveResult};
+use frame_support::ensure;
+use itp_enclave_api_ffi as ffi;
+use itp_types::ShardIdentifier;
+use sgx_types::sgx_status_t;
+
+/// Hand-over of the sealed secrets to an upgraded enclave with a different MRENCLAVE.
+pub trait SecretMigration: Send + Sync + 'static {
+	/// Export the shielding, state and signing key, encrypted to the enclave that wrote the
+	/// import key at `import_key_path`. Fails unless that enclave is authorized for `shard`
+	/// on the Integritee parentchain.
+	fn export_sealed_secrets(
+		&self,
+		shard: &ShardIdentifier,
+		import_key_path: &str,
+		export_path: &str,
+	) -> EnclaveResult<()>;
+
+	/// Import and seal secrets that were exported to this enclave.
+	fn import_sealed_secrets(&self, secrets_path: &str) -> EnclaveResult<()>;
+}
+
+impl SecretMigration for Enclave {
+	fn export_sealed_secrets(
+		&self,
+		shard: &ShardIdentifier,
+		import_key_path: &str,
+		export_path: &str,
+	) -> EnclaveResult<()> {
+		let mut retval = sgx_status_t::SGX_SUCCESS;
+
+		let result = unsafe {
+			ffi::export_sealed_secrets_to_file(
+				self.eid,
+				&mut retval,
+				shard.as_ptr(),
+				shard.as_bytes().len() as u32,
+				import_key_path.as_ptr(),
+				import_key_path.len() as u32,
+				export_path.as_ptr(),
+				export_path.len() as u32,
+			)
+		};
+
+		ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+		ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+		Ok(())
+	}
+
+	fn import_sealed_secrets(&self, secrets_path: &str) -> EnclaveResult<()> {
+		let mut retval = sgx_status_t::SGX_SUCCESS;
+
+		let result = unsafe {
+			ffi::import_sealed_secrets_from_file(
+				self.eid,
+				&mut retval,
+				secrets_path.as_ptr(),
+				secrets_path.len() as u32,
+			)
+		};
+
+		ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+		ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+		Ok(())
+	}
+}
@@ -38,6 +38,9 @@ pub trait Sidechain: Send + Sync + 'static {
 	) -> EnclaveResult<()>;
 
 	fn execute_trusted_calls(&self) -> EnclaveResult<()>;
+
+	/// Seal the pending trusted operations, so they can be restored after a restart.
+	fn seal_top_pool(&self) -> EnclaveResult<()>;
 }
 
 impl Sidechain for Enclave {
@@ -85,4 +88,15 @@ impl Sidechain for Enclave {
 
 		Ok(())
 	}
+
+	fn seal_top_pool(&self) -> EnclaveResult<()> {
+		let mut retval = sgx_status_t::SGX_SUCCESS;
+
+		let result = unsafe { ffi::seal_top_pool(self.eid, &mut retval) };
+
+		ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+		ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+		Ok(())
+	}
 }
@@ -0,0 +1,111 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::{error::Error, Enclave, EnclaveResult};
+use codec::Encode;
+use frame_support::ensure;
+use itp_enclave_api_ffi as ffi;
+use itp_types::{ShardIdentifier, H256};
+use sgx_types::sgx_status_t;
+
+/// Export and import of encrypted state snapshots, independent of any online peer.
+pub trait StateSnapshot: Send + Sync + 'static {
+	/// Write the signed shielding key of the enclave, to which a peer can export a snapshot.
+	fn write_state_import_key(&self, import_key_path: &str) -> EnclaveResult<()>;
+
+	/// Export the state of `shard` at `maybe_state_hash` (or the current state, if `None`),
+	/// encrypted to the enclave that wrote the import key at `import_key_path`.
+	fn export_state_snapshot(
+		&self,
+		shard: &ShardIdentifier,
+		maybe_state_hash: Option<H256>,
+		import_key_path: &str,
+		export_path: &str,
+	) -> EnclaveResult<()>;
+
+	/// Import a state snapshot that was exported to this enclave.
+	fn import_state_snapshot(&self, snapshot_path: &str) -> EnclaveResult<()>;
+}
+
+impl StateSnapshot for Enclave {
+	fn write_state_import_key(&self, import_key_path: &str) -> EnclaveResult<()> {
+		let mut retval = sgx_status_t::SGX_SUCCESS;
+
+		let result = unsafe {
+			ffi::write_state_import_key(
+				self.eid,
+				&mut retval,
+				import_key_path.as_ptr(),
+				import_key_path.len() as u32,
+			)
+		};
+
+		ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+		ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+		Ok(())
+	}
+
+	fn export_state_snapshot(
+		&self,
+		shard: &ShardIdentifier,
+		maybe_state_hash: Option<H256>,
+		import_key_path: &str,
+		export_path: &str,
+	) -> EnclaveResult<()> {
+		let mut retval = sgx_status_t::SGX_SUCCESS;
+		let maybe_state_hash_enc = maybe_state_hash.encode();
+
+		let result = unsafe {
+			ffi::export_state_snapshot_to_file(
+				self.eid,
+				&mut retval,
+				shard.as_ptr(),
+				shard.as_bytes().len() as u32,
+				maybe_state_hash_enc.as_ptr(),
+				maybe_state_hash_enc.len() as u32,
+				import_key_path.as_ptr(),
+				import_key_path.len() as u32,
+				export_path.as_ptr(),
+				export_path.len() as u32,
+			)
+		};
+
+		ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+		ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+		Ok(())
+	}
+
+	fn import_state_snapshot(&self, snapshot_path: &str) -> EnclaveResult<()> {
+		let mut retval = sgx_status_t::SGX_SUCCESS;
+
+		let result = unsafe {
+			ffi::import_state_snapshot_from_file(
+				self.eid,
+				&mut retval,
+				snapshot_path.as_ptr(),
+				snapshot_path.len() as u32,
+			)
+		};
+
+		ensure!(result == sgx_status_t::SGX_SUCCESS, Error::Sgx(result));
+		ensure!(retval == sgx_status_t::SGX_SUCCESS, Error::Sgx(retval));
+
+		Ok(())
+	}
+}
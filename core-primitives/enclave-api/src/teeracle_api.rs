@@ -19,46 +19,41 @@ use crate::{error::Error, Enclave, EnclaveResult};
 use codec::Encode;
 use frame_support::ensure;
 use itp_enclave_api_ffi as ffi;
+use itp_settings::teeracle::{EXCHANGE_RATE_EXTRINSIC_MAX_SIZE, MAX_EXCHANGE_RATE_FEEDS};
+use itp_teeracle_storage::DataSource;
 use log::*;
 use sgx_types::*;
 
 pub trait TeeracleApi: Send + Sync + 'static {
-	/// Update the currency market data for the token oracle.
-	fn update_market_data_xt(
-		&self,
-		crypto_currency: &str,
-		fiat_currency: &str,
-	) -> EnclaveResult<Vec<u8>>;
+	/// Update the currency market data of all feeds governed by the Teeracle pallet.
+	///
+	/// `candidate_sources` are the whitelisted data sources read from the parentchain. The enclave
+	/// verifies each of them against its light client.
+	fn update_market_data_xt(&self, candidate_sources: &[DataSource]) -> EnclaveResult<Vec<u8>>;
 
-	/// Update weather data for the corresponding coordinates.
-	fn update_weather_data_xt(&self, longitude: &str, latitude: &str) -> EnclaveResult<Vec<u8>>;
+	/// Update weather data for a SCALE encoded weather query (`ita_oracle::types::WeatherQuery`).
+	fn update_weather_data_xt(&self, encoded_weather_query: &[u8]) -> EnclaveResult<Vec<u8>>;
 }
 
 impl TeeracleApi for Enclave {
-	fn update_market_data_xt(
-		&self,
-		crypto_currency: &str,
-		fiat_currency: &str,
-	) -> EnclaveResult<Vec<u8>> {
+	fn update_market_data_xt(&self, candidate_sources: &[DataSource]) -> EnclaveResult<Vec<u8>> {
+		let candidates = candidate_sources.encode();
 		info!(
-			"TeeracleApi update_market_data_xt in with crypto {} and fiat {}",
-			crypto_currency, fiat_currency
+			"TeeracleApi update_market_data_xt in with candidate sources: 0x{}",
+			hex::encode(&candidates)
 		);
 		let mut retval = sgx_status_t::SGX_SUCCESS;
-		let response_len = 8192;
+		// At most one extrinsic per served feed, plus room for the length prefix.
+		let response_len =
+			(EXCHANGE_RATE_EXTRINSIC_MAX_SIZE * (MAX_EXCHANGE_RATE_FEEDS + 1)) as u32;
 		let mut response: Vec<u8> = vec![0u8; response_len as usize];
 
-		let crypto_curr = crypto_currency.encode();
-		let fiat_curr = fiat_currency.encode();
-
 		let res = unsafe {
 			ffi::update_market_data_xt(
 				self.eid,
 				&mut retval,
-				crypto_curr.as_ptr(),
-				crypto_curr.len() as u32,
-				fiat_curr.as_ptr(),
-				fiat_curr.len() as u32,
+				candidates.as_ptr(),
+				candidates.len() as u32,
 				response.as_mut_ptr(),
 				response_len,
 			)
@@ -69,26 +64,22 @@ impl TeeracleApi for Enclave {
 
 		Ok(response)
 	}
-	fn update_weather_data_xt(&self, longitude: &str, latitude: &str) -> EnclaveResult<Vec<u8>> {
+
+	fn update_weather_data_xt(&self, encoded_weather_query: &[u8]) -> EnclaveResult<Vec<u8>> {
 		info!(
-			"TeeracleApi update_weather_data_xt in with latitude: {}, longitude: {}",
-			latitude, longitude
+			"TeeracleApi update_weather_data_xt in with query: 0x{}",
+			hex::encode(encoded_weather_query)
 		);
 		let mut retval = sgx_status_t::SGX_SUCCESS;
 		let response_len = 8192;
 		let mut response: Vec<u8> = vec![0u8; response_len as usize];
 
-		let longitude_encoded: Vec<u8> = longitude.encode();
-		let latitude_encoded: Vec<u8> = latitude.encode();
-
 		let res = unsafe {
 			ffi::update_weather_data_xt(
 				self.eid,
 				&mut retval,
-				longitude_encoded.as_ptr(),
-				longitude_encoded.len() as u32,
-				latitude_encoded.as_ptr(),
-				latitude_encoded.len() as u32,
+				encoded_weather_query.as_ptr(),
+				encoded_weather_query.len() as u32,
 				response.as_mut_ptr(),
 				response_len,
 			)
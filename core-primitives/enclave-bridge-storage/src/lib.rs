@@ -22,10 +22,25 @@ impl StoragePrefix for EnclaveBridgeStorage {
 
 pub trait EnclaveBridgeStorageKeys {
 	fn shard_status<T: Encode>(shard: T) -> Vec<u8>;
+	fn shard_auditor<T: Encode>(shard: T) -> Vec<u8>;
+	fn shard_config_registry<T: Encode>(shard: T) -> Vec<u8>;
 }
 
 impl<S: StoragePrefix> EnclaveBridgeStorageKeys for S {
 	fn shard_status<T: Encode>(shard: T) -> Vec<u8> {
 		storage_map_key(Self::prefix(), "ShardStatus", &shard, &StorageHasher::Blake2_128Concat)
 	}
+
+	fn shard_auditor<T: Encode>(shard: T) -> Vec<u8> {
+		storage_map_key(Self::prefix(), "ShardAuditor", &shard, &StorageHasher::Blake2_128Concat)
+	}
+
+	fn shard_config_registry<T: Encode>(shard: T) -> Vec<u8> {
+		storage_map_key(
+			Self::prefix(),
+			"ShardConfigRegistry",
+			&shard,
+			&StorageHasher::Blake2_128Concat,
+		)
+	}
 }
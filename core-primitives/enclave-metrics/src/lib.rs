@@ -37,6 +37,7 @@ pub enum EnclaveMetric {
 	TopPoolSizeIncrement,
 	TopPoolSizeDecrement,
 	ExchangeRateOracle(ExchangeRateOracleMetric),
+	PersonhoodOracle(PersonhoodOracleMetric),
 	// OracleMetric(OracleMetric<MetricsInfo>),
 }
 
@@ -50,6 +51,114 @@ pub enum ExchangeRateOracleMetric {
 	NumberRequestsIncrement(String),
 }
 
+/// Personhood oracle metrics.
+///
+/// These leave the enclave, so they must never contain anything that could identify a user,
+/// e.g. account ids, community identifiers or Nostr keys.
+#[derive(Encode, Decode, Debug)]
+pub enum PersonhoodOracleMetric {
+	/// Increment the number of reputation lookups and cache lookups by those of one request
+	ReputationLookups(ReputationLookupCounts),
+	/// Increment the number of failed personhood verifications (Reason)
+	VerificationFailure(VerificationFailureReason),
+	/// Increment the number of issued badges (Tier, i.e. number of verified reputations)
+	BadgeIssued(u32),
+	/// Result of publishing events to a Nostr relay. (Relay host or "other", Success)
+	RelayPublish(String, bool),
+	/// Latency of a personhood RPC call in [ms]. (RPC method, Latency)
+	RpcLatency(String, u128),
+}
+
+#[derive(Encode, Decode, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReputationLookupResult {
+	Verified,
+	Unverified,
+	Failed,
+}
+
+impl ReputationLookupResult {
+	pub fn label(&self) -> &'static str {
+		match self {
+			ReputationLookupResult::Verified => "verified",
+			ReputationLookupResult::Unverified => "unverified",
+			ReputationLookupResult::Failed => "failed",
+		}
+	}
+}
+
+/// Reputation lookups of one request, reported together to need a single OCall.
+#[derive(Encode, Decode, Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ReputationLookupCounts {
+	pub verified: u32,
+	pub unverified: u32,
+	pub failed: u32,
+	pub cache_hits: u32,
+	pub cache_misses: u32,
+}
+
+impl ReputationLookupCounts {
+	pub fn record_lookup(&mut self, result: ReputationLookupResult) {
+		match result {
+			ReputationLookupResult::Verified => self.verified += 1,
+			ReputationLookupResult::Unverified => self.unverified += 1,
+			ReputationLookupResult::Failed => self.failed += 1,
+		}
+	}
+
+	pub fn record_cache_lookup(&mut self, hit: bool) {
+		match hit {
+			true => self.cache_hits += 1,
+			false => self.cache_misses += 1,
+		}
+	}
+
+	/// Number of lookups per result.
+	pub fn lookups(&self) -> [(ReputationLookupResult, u32); 3] {
+		[
+			(ReputationLookupResult::Verified, self.verified),
+			(ReputationLookupResult::Unverified, self.unverified),
+			(ReputationLookupResult::Failed, self.failed),
+		]
+	}
+}
+
+#[derive(Encode, Decode, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VerificationFailureReason {
+	/// The request parameters could not be decoded.
+	InvalidParameters,
+	/// The subject has no verified reputation.
+	NoReputation,
+	/// The supplied Nostr public key is invalid.
+	InvalidNostrKey,
+	/// The badge events could not be created or published.
+	PublishFailed,
+}
+
+impl VerificationFailureReason {
+	pub fn label(&self) -> &'static str {
+		match self {
+			VerificationFailureReason::InvalidParameters => "invalid_parameters",
+			VerificationFailureReason::NoReputation => "no_reputation",
+			VerificationFailureReason::InvalidNostrKey => "invalid_nostr_key",
+			VerificationFailureReason::PublishFailed => "publish_failed",
+		}
+	}
+}
+
+/// Metric label of a badge tier. A tier is the number of verified reputations within the
+/// reputation lookback, so only a few values are expected. Any other tier shares one label.
+pub fn badge_tier_label(tier: u32) -> &'static str {
+	match tier {
+		0 => "0",
+		1 => "1",
+		2 => "2",
+		3 => "3",
+		4 => "4",
+		5 => "5",
+		_ => "other",
+	}
+}
+
 #[derive(Encode, Decode, Debug)]
 pub enum OracleMetric<MetricsInfo> {
 	OracleSpecificMetric(MetricsInfo),
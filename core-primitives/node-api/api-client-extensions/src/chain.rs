@@ -16,20 +16,37 @@
 */
 
 use crate::{ApiClientError, ApiResult};
+use codec::{Decode, Encode};
 use itp_api_client_types::{Block, SignedBlock};
 use itp_types::{
 	parentchain::{BlockNumber, Hash, Header, StorageProof},
 	H256,
 };
-use sp_consensus_grandpa::{AuthorityList, VersionedAuthorityList, GRANDPA_AUTHORITIES_KEY};
+use sp_consensus_grandpa::{AuthorityList, SetId, VersionedAuthorityList, GRANDPA_AUTHORITIES_KEY};
+use sp_core::Bytes;
 use sp_runtime::traits::GetRuntimeBlockType;
 use substrate_api_client::{
-	rpc::Request, serde_impls::StorageKey, storage_key, Api, ExtrinsicParams, FrameSystemConfig,
-	GetBlock, GetHeader, GetStorage,
+	rpc::Request, rpc_params, serde_impls::StorageKey, storage_key, Api, ExtrinsicParams,
+	FrameSystemConfig, GetBlock, GetHeader, GetStorage,
 };
 
 type RawEvents = Vec<u8>;
 
+pub type ParaId = u32;
+
+/// Finality proof as returned by the `grandpa_proveFinality` RPC.
+///
+/// Mirrors `sc_consensus_grandpa::FinalityProof`, which we can't depend on here.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct GrandpaFinalityProof {
+	/// Hash of the block finalized by the justification.
+	pub block: Hash,
+	/// Encoded GRANDPA justification of the `block`.
+	pub justification: Vec<u8>,
+	/// Headers between the requested block and the `block`, if requested.
+	pub unknown_headers: Vec<Header>,
+}
+
 /// ApiClient extension that simplifies chain data access.
 pub trait ChainApi {
 	fn last_finalized_block(&self) -> ApiResult<Option<SignedBlock>>;
@@ -45,6 +62,20 @@ pub trait ChainApi {
 	fn grandpa_authorities_proof(&self, hash: Option<H256>) -> ApiResult<StorageProof>;
 	fn get_events_value_proof(&self, block_hash: Option<H256>) -> ApiResult<StorageProof>;
 	fn get_events_for_block(&self, block_hash: Option<H256>) -> ApiResult<RawEvents>;
+	fn grandpa_set_id(&self, hash: Option<H256>) -> ApiResult<SetId>;
+	/// Proof of the GRANDPA authorities and their set id.
+	fn grandpa_authorities_and_set_id_proof(&self, hash: Option<H256>) -> ApiResult<StorageProof>;
+	/// Proof of the finality of the given block, or of the first descendant that has a stored
+	/// GRANDPA justification. Returns `None` if there is no such block yet.
+	fn grandpa_finality_proof(
+		&self,
+		block_number: BlockNumber,
+	) -> ApiResult<Option<GrandpaFinalityProof>>;
+	/// Own para id, if this is a parachain.
+	fn parachain_id(&self) -> ApiResult<Option<ParaId>>;
+	/// Encoded head of the parachain with the given id, read from a relay chain.
+	fn para_head(&self, para_id: ParaId, hash: Option<H256>) -> ApiResult<Option<Vec<u8>>>;
+	fn para_head_proof(&self, para_id: ParaId, hash: Option<H256>) -> ApiResult<StorageProof>;
 }
 
 impl<Signer, Client, Params, Runtime> ChainApi for Api<Signer, Client, Params, Runtime>
@@ -120,4 +151,46 @@ where
 		let key = storage_key("System", "Events");
 		Ok(self.get_opaque_storage_by_key_hash(key, block_hash)?.unwrap_or_default())
 	}
+
+	fn grandpa_set_id(&self, hash: Option<H256>) -> ApiResult<SetId> {
+		Ok(self.get_storage_value("Grandpa", "CurrentSetId", hash)?.unwrap_or_default())
+	}
+
+	fn grandpa_authorities_and_set_id_proof(&self, hash: Option<H256>) -> ApiResult<StorageProof> {
+		let keys = vec![
+			StorageKey(GRANDPA_AUTHORITIES_KEY.to_vec()),
+			storage_key("Grandpa", "CurrentSetId"),
+		];
+		Ok(self
+			.get_storage_proof_by_keys(keys, hash)?
+			.map(|read_proof| read_proof.proof.into_iter().map(|bytes| bytes.0).collect())
+			.unwrap_or_default())
+	}
+
+	fn grandpa_finality_proof(
+		&self,
+		block_number: BlockNumber,
+	) -> ApiResult<Option<GrandpaFinalityProof>> {
+		let encoded_proof: Option<Bytes> =
+			self.client().request("grandpa_proveFinality", rpc_params![block_number])?;
+		encoded_proof
+			.map(|proof| GrandpaFinalityProof::decode(&mut proof.0.as_slice()))
+			.transpose()
+			.map_err(Into::into)
+	}
+
+	fn parachain_id(&self) -> ApiResult<Option<ParaId>> {
+		self.get_storage_value("ParachainInfo", "ParachainId", None)
+	}
+
+	fn para_head(&self, para_id: ParaId, hash: Option<H256>) -> ApiResult<Option<Vec<u8>>> {
+		self.get_storage_map("Paras", "Heads", para_id, hash)
+	}
+
+	fn para_head_proof(&self, para_id: ParaId, hash: Option<H256>) -> ApiResult<StorageProof> {
+		Ok(self
+			.get_storage_map_proof("Paras", "Heads", para_id, hash)?
+			.map(|read_proof| read_proof.proof.into_iter().map(|bytes| bytes.0).collect())
+			.unwrap_or_default())
+	}
 }
@@ -15,5 +15,43 @@
 
 */
 
+use crate::ApiResult;
+use itp_teeracle_storage::{
+	decode_whitelist_key, DataSource, TeeracleStorage, TeeracleStorageKeys,
+};
+use itp_types::parentchain::Hash;
+use sp_core::storage::StorageKey;
+use substrate_api_client::{
+	log::error, rpc::Request, Api, ExtrinsicParams, FrameSystemConfig, GetStorage,
+};
+
 pub const TEERACLE: &str = "Teeracle";
 pub const ADD_TO_WHITELIST: &str = "add_to_whitelist";
+
+/// ApiClient extension that enables communication with the `teeracle` pallet.
+///
+/// Note: the results are not verified, the enclave has to check them with a storage proof.
+pub trait PalletTeeracleApi {
+	/// All data sources that have a whitelist entry.
+	fn whitelisted_data_sources(&self, at_block: Option<Hash>) -> ApiResult<Vec<DataSource>>;
+}
+
+impl<Signer, Client, Params, Runtime> PalletTeeracleApi for Api<Signer, Client, Params, Runtime>
+where
+	Client: Request,
+	Runtime: FrameSystemConfig<Hash = Hash>,
+	Params: ExtrinsicParams<Runtime::Index, Runtime::Hash>,
+{
+	fn whitelisted_data_sources(&self, at_block: Option<Hash>) -> ApiResult<Vec<DataSource>> {
+		let key_prefix = StorageKey(TeeracleStorage::whitelists_prefix());
+		//fixme: solve this properly with infinite elements
+		let max_keys = 1000;
+		let storage_keys =
+			self.get_storage_keys_paged(Some(key_prefix), max_keys, None, at_block)?;
+
+		if storage_keys.len() == max_keys as usize {
+			error!("results can be wrong because max keys reached for query")
+		}
+		Ok(storage_keys.iter().filter_map(|key| decode_whitelist_key(&key.0)).collect())
+	}
+}
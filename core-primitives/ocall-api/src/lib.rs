@@ -26,7 +26,7 @@ use derive_more::{Display, From};
 use itp_storage::Error as StorageError;
 use itp_types::{
 	parentchain::ParentchainId, storage::StorageEntryVerified, BlockHash, ShardIdentifier,
-	TrustedOperationStatus, WorkerRequest, WorkerResponse,
+	SidechainBlockConfirmation, TrustedOperationStatus, WorkerRequest, WorkerResponse,
 };
 use sgx_types::*;
 use sp_core::H256;
@@ -138,6 +138,13 @@ pub trait EnclaveSidechainOCallApi: Clone + Send + Sync {
 		maybe_until_block_hash: Option<BlockHash>,
 		shard_identifier: ShardIdentifier,
 	) -> SgxResult<Vec<SignedSidechainBlock>>;
+
+	/// Report the latest sidechain block of `shard` that became final.
+	fn finalize_sidechain_block(
+		&self,
+		shard_identifier: ShardIdentifier,
+		finalized_block: SidechainBlockConfirmation,
+	) -> SgxResult<()>;
 }
 
 /// Newtype for IPFS CID
@@ -36,6 +36,17 @@ pub mod files {
 	pub static ENCLAVE_FILE: &str = "enclave.signed.so";
 	pub static SHIELDING_KEY_FILE: &str = "enclave-shielding-pubkey.json";
 	pub static SIGNING_KEY_FILE: &str = "enclave-signing-pubkey.bin";
+	/// Signed shielding key, to which a peer can export a state snapshot.
+	pub static STATE_IMPORT_KEY_FILE: &str = "enclave-state-import-key.bin";
+	/// Default file name of an exported (and encrypted) state snapshot.
+	pub static STATE_SNAPSHOT_EXPORT_FILE: &str = "state-snapshot-export.bin";
+	/// Default file name of the sealed secrets, exported to a successor enclave.
+	pub static SECRETS_EXPORT_FILE: &str = "enclave-secrets-export.bin";
+	/// Directory of the cached DCAP quote verification collateral, one file per FMSPC.
+	pub static DCAP_COLLATERAL_CACHE_PATH: &str = "dcap_collateral_cache";
+	/// Interval after which cached DCAP collateral is fetched again, in seconds. Until its
+	/// next update date, the cached collateral is used if fetching fails.
+	pub static DCAP_COLLATERAL_REFRESH_INTERVAL: u64 = 3600;
 	/// sidechain database path
 	pub static SIDECHAIN_STORAGE_PATH: &str = "sidechain_db";
 	pub static SIDECHAIN_PURGE_INTERVAL: u64 = 7200; // purge sidechain every .. s
@@ -53,6 +64,9 @@ pub mod files {
 
 	pub const RA_DUMP_CERT_DER_FILE: &str = "ra_dump_cert.der";
 
+	/// Sealed pending trusted operations, restored on enclave startup.
+	pub const TOP_POOL_SEALED_FILE: &str = "top_pool_sealed.bin";
+
 	// used by worker and enclave
 	pub const SHARDS_PATH: &str = "shards";
 
@@ -92,12 +106,30 @@ pub mod worker {
 	// Should be set to a value that ensures that at least 2 sidechain blocks are finalized per
 	// parentchain block.
 	pub const BLOCK_NUMBER_FINALIZATION_DIFF: u64 = 20;
+	// Interval in which the pending trusted operations are sealed to disk, in seconds.
+	pub const TOP_POOL_SEAL_INTERVAL: u64 = 30;
+	// Number of finalized parentchain blocks between two backups of a light client state.
+	pub const LIGHT_CLIENT_BACKUP_INTERVAL: u32 = 1_000;
 }
 
 pub mod sidechain {
 	use core::time::Duration;
 
 	pub static SLOT_DURATION: Duration = Duration::from_millis(1000);
+
+	/// Number of blocks behind the best block for which we still track competing forks.
+	///
+	/// The state after each block in the fork tree is kept in memory to switch forks, so this
+	/// bounds that memory, independent of [`super::files::STATE_SNAPSHOTS_CACHE_SIZE`].
+	pub const MAX_FORK_DEPTH: u64 = 4;
+
+	/// Maximum number of trusted calls that are executed in parallel within a slot.
+	///
+	/// Parallel calls run on worker threads of the STF executor, which are kept alive between
+	/// state updates, so each of them permanently binds a TCS. `TCSNum` in the enclave configs
+	/// therefore reserves this many TCS on top of the 8 needed by the untrusted threads calling
+	/// into the enclave, change both together. 1 means sequential execution.
+	pub const MAX_PARALLEL_TRUSTED_CALLS: usize = 4;
 }
 
 /// Settings concerning the enclave
@@ -112,4 +144,30 @@ pub mod teeracle {
 	pub static ONE_DAY: Duration = Duration::from_secs(86400);
 
 	pub static THIRTY_MINUTES: Duration = Duration::from_secs(1800);
+
+	/// Maximum size of an extrinsic that reports a single exchange rate.
+	pub const EXCHANGE_RATE_EXTRINSIC_MAX_SIZE: usize = 1024;
+
+	/// Maximum number of (trading pair, data source) feeds served per market data update.
+	pub const MAX_EXCHANGE_RATE_FEEDS: usize = 64;
+}
+
+/// Settings for the personhood oracle
+pub mod personhood_oracle {
+	use core::time::Duration;
+
+	/// Maximum number of verified reputations, i.e. (community, ceremony index, account)
+	/// entries, that are cached inside the enclave.
+	pub const REPUTATION_CACHE_SIZE: usize = 10_000;
+	/// Maximum number of communities whose verified metadata is cached inside the enclave.
+	pub const COMMUNITY_CACHE_SIZE: usize = 256;
+	/// Maximum number of reputation or community changes an Encointer watcher keeps track of
+	/// within a ceremony phase. Beyond that, everything verified before is considered outdated.
+	pub const MAX_TRACKED_ENCOINTER_CHANGES: usize = 100_000;
+	/// Maximum number of issued Nostr badges the oracle keeps up to date. Beyond that, the
+	/// oldest badges are no longer refreshed and are left to expire.
+	pub const MAX_MAINTAINED_BADGES: usize = 10_000;
+	/// Nostr badge awards expire after this duration (NIP-40). Badges are renewed with every
+	/// Encointer ceremony, which takes 10 days, so this leaves some margin.
+	pub static BADGE_VALIDITY: Duration = Duration::from_secs(14 * 86400);
 }
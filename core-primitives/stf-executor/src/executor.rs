@@ -17,6 +17,9 @@
 
 use crate::{
 	error::{Error, Result},
+	parallel_execution::{
+		conflict_free_prefix_len, event_log, SpeculationPool, SpeculativeExecution,
+	},
 	traits::{StatePostProcessing, StateUpdateProposer, StfUpdateState},
 	BatchExecutionResult, ExecutedOperation,
 };
@@ -28,7 +31,7 @@ use ita_stf::{
 };
 use itp_node_api::metadata::{provider::AccessNodeMetadata, NodeMetadataTrait};
 use itp_ocall_api::{EnclaveAttestationOCallApi, EnclaveOnChainOCallApi};
-use itp_sgx_externalities::{SgxExternalitiesTrait, StateHash};
+use itp_sgx_externalities::{record_state_access, SgxExternalitiesTrait, StateHash};
 use itp_stf_interface::{
 	parentchain_pallet::ParentchainPalletInterface, StateCallInterface, UpdateState,
 };
@@ -39,13 +42,30 @@ use itp_types::{parentchain::ParentchainId, storage::StorageEntryVerified, Opaqu
 use log::*;
 use sp_runtime::traits::Header as HeaderTrait;
 use std::{
-	collections::BTreeMap, fmt::Debug, marker::PhantomData, sync::Arc, time::Duration, vec::Vec,
+	cmp::min,
+	collections::{BTreeMap, BTreeSet},
+	fmt::Debug,
+	marker::PhantomData,
+	sync::{Arc, SgxMutex as Mutex},
+	time::Duration,
+	vec::Vec,
 };
 
-pub struct StfExecutor<OCallApi, StateHandler, NodeMetadataRepository, Stf> {
+/// Workers executing the trusted calls of a shard in parallel.
+type ShardSpeculationPool<State> =
+	(ShardIdentifier, SpeculationPool<State, TrustedOperation, ExecutedOperation>);
+
+pub struct StfExecutor<OCallApi, StateHandler, NodeMetadataRepository, Stf>
+where
+	StateHandler: HandleState,
+{
 	ocall_api: Arc<OCallApi>,
 	state_handler: Arc<StateHandler>,
 	node_metadata_repo: Arc<NodeMetadataRepository>,
+	max_parallel_calls: usize,
+	/// Kept from one state update to the next, so the state replicas of the workers only
+	/// need to be cloned once.
+	speculation_pool: Mutex<Option<ShardSpeculationPool<StateHandler::StateT>>>,
 	_phantom: PhantomData<Stf>,
 }
 
@@ -70,7 +90,25 @@ where
 		state_handler: Arc<StateHandler>,
 		node_metadata_repo: Arc<NodeMetadataRepository>,
 	) -> Self {
-		StfExecutor { ocall_api, state_handler, node_metadata_repo, _phantom: PhantomData }
+		StfExecutor {
+			ocall_api,
+			state_handler,
+			node_metadata_repo,
+			max_parallel_calls: 1,
+			speculation_pool: Mutex::new(None),
+			_phantom: PhantomData,
+		}
+	}
+
+	/// Execute up to `max_parallel_calls` trusted calls in parallel when proposing a state update.
+	///
+	/// Each parallel call is executed by a worker thread with its own replica of the state, so
+	/// this trades memory for throughput. The workers and their replicas are created with the
+	/// first state update of a shard and kept for the following ones.
+	/// A value of 0 or 1 executes all calls sequentially (default).
+	pub fn with_max_parallel_calls(mut self, max_parallel_calls: usize) -> Self {
+		self.max_parallel_calls = max_parallel_calls;
+		self
 	}
 
 	/// Execute a trusted call on the STF
@@ -93,41 +131,20 @@ where
 		debug!("query mrenclave of self");
 		let mrenclave = self.ocall_api.get_mrenclave_of_self()?;
 
-		let top_or_hash = TrustedOperationOrHash::from_top(trusted_operation.clone());
-
-		let trusted_call = match trusted_operation.to_call().ok_or(Error::InvalidTrustedCallType) {
-			Ok(c) => c,
-			Err(e) => {
-				error!("Error: {:?}", e);
-				return Ok(ExecutedOperation::failed(top_or_hash))
-			},
-		};
-
-		if let false = trusted_call.verify_signature(&mrenclave.m, &shard) {
-			error!("TrustedCallSigned: bad signature");
-			return Ok(ExecutedOperation::failed(top_or_hash))
-		}
-
-		debug!("execute on STF, call with nonce {}", trusted_call.nonce);
-		let mut extrinsic_call_backs: Vec<OpaqueCall> = Vec::new();
-		if let Err(e) = Stf::execute_call(
+		let executed_operation = execute_trusted_operation::<Stf, _, _>(
 			state,
-			trusted_call.clone(),
-			&mut extrinsic_call_backs,
+			trusted_operation,
+			&mrenclave.m,
+			shard,
 			self.node_metadata_repo.clone(),
-		) {
-			error!("Stf execute failed: {:?}", e);
-			return Ok(ExecutedOperation::failed(top_or_hash))
-		}
+		);
 
-		let operation_hash = trusted_operation.hash();
-		debug!("Operation hash {:?}", operation_hash);
-
-		if let StatePostProcessing::Prune = post_processing {
+		if let (StatePostProcessing::Prune, true) = (post_processing, executed_operation.is_success())
+		{
 			state.prune_state_diff();
 		}
 
-		Ok(ExecutedOperation::success(operation_hash, top_or_hash, extrinsic_call_backs))
+		Ok(executed_operation)
 	}
 }
 
@@ -265,6 +282,10 @@ where
 	<StateHandler::StateT as SgxExternalitiesTrait>::SgxExternalitiesDiffType:
 		From<BTreeMap<Vec<u8>, Option<Vec<u8>>>>,
 	<Stf as StateCallInterface<TrustedCallSigned, StateHandler::StateT, NodeMetadataRepository>>::Error: Debug,
+	Stf: 'static,
+	StateHandler::StateT: Clone + Send + 'static,
+	<StateHandler::StateT as SgxExternalitiesTrait>::SgxExternalitiesDiffType: Clone,
+	NodeMetadataRepository: Send + Sync + 'static,
 {
 	type Externalities = StateHandler::StateT;
 
@@ -286,6 +307,17 @@ where
 
 		// Execute any pre-processing steps.
 		let mut state = prepare_state_function(state);
+
+		if self.max_parallel_calls > 1 {
+			let executed_and_failed_calls =
+				self.execute_in_parallel(&mut state, trusted_calls, shard, ends_at);
+			return Ok(BatchExecutionResult {
+				executed_operations: executed_and_failed_calls,
+				state_hash_before_execution,
+				state_after_execution: state,
+			})
+		}
+
 		let mut executed_and_failed_calls = Vec::<ExecutedOperation>::new();
 
 		// Iterate through all calls until time is over.
@@ -320,6 +352,251 @@ where
 	}
 }
 
+impl<OCallApi, StateHandler, NodeMetadataRepository, Stf>
+	StfExecutor<OCallApi, StateHandler, NodeMetadataRepository, Stf>
+where
+	OCallApi: EnclaveAttestationOCallApi,
+	StateHandler: HandleState<HashType = H256>,
+	StateHandler::StateT: SgxExternalitiesTrait + Clone + Send + 'static,
+	NodeMetadataRepository: AccessNodeMetadata + Send + Sync + 'static,
+	NodeMetadataRepository::MetadataType: NodeMetadataTrait,
+	Stf: UpdateState<
+			StateHandler::StateT,
+			<StateHandler::StateT as SgxExternalitiesTrait>::SgxExternalitiesDiffType,
+		> + StateCallInterface<TrustedCallSigned, StateHandler::StateT, NodeMetadataRepository>
+		+ 'static,
+	<StateHandler::StateT as SgxExternalitiesTrait>::SgxExternalitiesDiffType: Clone
+		+ IntoIterator<Item = (Vec<u8>, Option<Vec<u8>>)>
+		+ From<BTreeMap<Vec<u8>, Option<Vec<u8>>>>,
+	<Stf as StateCallInterface<TrustedCallSigned, StateHandler::StateT, NodeMetadataRepository>>::Error: Debug,
+{
+	/// Execute the trusted calls in windows of up to `max_parallel_calls` calls.
+	///
+	/// All calls of a window are executed in parallel by a [`SpeculationPool`], whose workers
+	/// each keep a replica of the current state. Then the longest conflict free prefix of the
+	/// window is committed in the original call order, see [`crate::parallel_execution`]. The
+	/// next window starts with the first conflicting call.
+	///
+	/// The committed state is the same as with sequential execution. The deadline is checked
+	/// before committing each call, so the speculative results of a window that are committed
+	/// after the deadline are dropped. If no worker is left, the remaining calls are executed
+	/// sequentially.
+	fn execute_in_parallel(
+		&self,
+		state: &mut StateHandler::StateT,
+		trusted_calls: &[TrustedOperation],
+		shard: &ShardIdentifier,
+		ends_at: Duration,
+	) -> Vec<ExecutedOperation> {
+		let mut executed_and_failed_calls = Vec::<ExecutedOperation>::new();
+		if trusted_calls.is_empty() {
+			return executed_and_failed_calls
+		}
+
+		let mrenclave = match self.ocall_api.get_mrenclave_of_self() {
+			Ok(mrenclave) => mrenclave.m,
+			Err(e) => {
+				error!("Fatal Error. Failed to attempt call execution: {:?}", e);
+				return executed_and_failed_calls
+			},
+		};
+
+		let mut speculation_pool = match self.speculation_pool.lock() {
+			Ok(speculation_pool) => speculation_pool,
+			Err(e) => {
+				error!("Fatal Error. Speculation pool lock is poisoned: {:?}", e);
+				return executed_and_failed_calls
+			},
+		};
+		let mut pool = match speculation_pool.take() {
+			Some((pool_shard, mut pool)) if pool_shard == *shard && pool.size() > 0 => {
+				pool.rebase(state);
+				pool
+			},
+			_ => start_speculation_pool::<Stf, _, _>(
+				state,
+				self.max_parallel_calls,
+				mrenclave,
+				*shard,
+				self.node_metadata_repo.clone(),
+			),
+		};
+
+		let mut pending_calls = trusted_calls;
+		while !pending_calls.is_empty() {
+			// Break if allowed time window is over.
+			if ends_at < duration_now() {
+				info!("Aborting execution of trusted calls because slot time is up");
+				break
+			}
+
+			if pool.size() == 0 {
+				warn!("No thread left for parallel execution, executing trusted call sequentially");
+				executed_and_failed_calls.push(execute_trusted_operation::<Stf, _, _>(
+					state,
+					&pending_calls[0],
+					&mrenclave,
+					shard,
+					self.node_metadata_repo.clone(),
+				));
+				pending_calls = &pending_calls[1..];
+				continue
+			}
+
+			let window = &pending_calls[..min(pool.size(), pending_calls.len())];
+			let executions = pool.execute(window, |top| {
+				ExecutedOperation::failed(TrustedOperationOrHash::from_top(top.clone()))
+			});
+
+			let number_of_committed_calls = conflict_free_prefix_len(&executions);
+			debug!(
+				"Committing {} of {} speculatively executed trusted calls",
+				number_of_committed_calls,
+				window.len()
+			);
+
+			let mut committed_writes = BTreeSet::new();
+			let mut number_of_executed_calls = 0;
+			for execution in executions.into_iter().take(number_of_committed_calls) {
+				if ends_at < duration_now() {
+					info!("Aborting execution of trusted calls because slot time is up");
+					break
+				}
+				committed_writes.extend(execution.access_set.writes().iter().cloned());
+				let state_diff: BTreeMap<Vec<u8>, Option<Vec<u8>>> =
+					execution.state_diff.into_iter().collect();
+				Stf::apply_state_diff(state, state_diff.into());
+				if let Some(appended_events) = execution.appended_events {
+					appended_events.append_to(state);
+				}
+				executed_and_failed_calls.push(execution.outcome);
+				number_of_executed_calls += 1;
+			}
+			pool.sync(state, &committed_writes);
+
+			if number_of_executed_calls < number_of_committed_calls {
+				break
+			}
+			pending_calls = &pending_calls[number_of_committed_calls..];
+		}
+
+		*speculation_pool = Some((*shard, pool));
+		executed_and_failed_calls
+	}
+}
+
+/// Verify the signature of a trusted call and execute it on the given state.
+///
+/// An invalid trusted call results in a failed `ExecutedOperation`. State changes a call made
+/// before it failed are not reverted, the same as with the regular call execution.
+fn execute_trusted_operation<Stf, State, NodeMetadataRepository>(
+	state: &mut State,
+	trusted_operation: &TrustedOperation,
+	mrenclave: &[u8; 32],
+	shard: &ShardIdentifier,
+	node_metadata_repo: Arc<NodeMetadataRepository>,
+) -> ExecutedOperation
+where
+	Stf: StateCallInterface<TrustedCallSigned, State, NodeMetadataRepository>,
+	Stf::Error: Debug,
+	NodeMetadataRepository: AccessNodeMetadata,
+	NodeMetadataRepository::MetadataType: NodeMetadataTrait,
+{
+	let top_or_hash = TrustedOperationOrHash::from_top(trusted_operation.clone());
+
+	let trusted_call = match trusted_operation.to_call().ok_or(Error::InvalidTrustedCallType) {
+		Ok(c) => c,
+		Err(e) => {
+			error!("Error: {:?}", e);
+			return ExecutedOperation::failed(top_or_hash)
+		},
+	};
+
+	if let false = trusted_call.verify_signature(mrenclave, shard) {
+		error!("TrustedCallSigned: bad signature");
+		return ExecutedOperation::failed(top_or_hash)
+	}
+
+	debug!("execute on STF, call with nonce {}", trusted_call.nonce);
+	let mut extrinsic_call_backs: Vec<OpaqueCall> = Vec::new();
+	if let Err(e) = Stf::execute_call(
+		state,
+		trusted_call.clone(),
+		&mut extrinsic_call_backs,
+		node_metadata_repo,
+	) {
+		error!("Stf execute failed: {:?}", e);
+		return ExecutedOperation::failed(top_or_hash)
+	}
+
+	let operation_hash = trusted_operation.hash();
+	debug!("Operation hash {:?}", operation_hash);
+
+	ExecutedOperation::success(operation_hash, top_or_hash, extrinsic_call_backs)
+}
+
+/// Start a pool of `size` workers that execute trusted operations on replicas of `state`.
+///
+/// A free function, so that the worker closure does not depend on the executor's type
+/// parameters, which are not necessarily `'static`.
+fn start_speculation_pool<Stf, State, NodeMetadataRepository>(
+	state: &State,
+	size: usize,
+	mrenclave: [u8; 32],
+	shard: ShardIdentifier,
+	node_metadata_repo: Arc<NodeMetadataRepository>,
+) -> SpeculationPool<State, TrustedOperation, ExecutedOperation>
+where
+	Stf: StateCallInterface<TrustedCallSigned, State, NodeMetadataRepository> + 'static,
+	Stf::Error: Debug,
+	State: SgxExternalitiesTrait + Clone + Send + 'static,
+	State::SgxExternalitiesDiffType: Clone + IntoIterator<Item = (Vec<u8>, Option<Vec<u8>>)>,
+	NodeMetadataRepository: AccessNodeMetadata + Send + Sync + 'static,
+	NodeMetadataRepository::MetadataType: NodeMetadataTrait,
+{
+	SpeculationPool::start(state, size, move |replica: &mut State, top: &TrustedOperation| {
+		execute_on_replica::<Stf, _, _>(
+			replica,
+			top,
+			&mrenclave,
+			&shard,
+			node_metadata_repo.clone(),
+		)
+	})
+}
+
+/// Execute a trusted operation on a state replica, recording the accessed keys.
+fn execute_on_replica<Stf, State, NodeMetadataRepository>(
+	state: &mut State,
+	trusted_operation: &TrustedOperation,
+	mrenclave: &[u8; 32],
+	shard: &ShardIdentifier,
+	node_metadata_repo: Arc<NodeMetadataRepository>,
+) -> SpeculativeExecution<ExecutedOperation>
+where
+	Stf: StateCallInterface<TrustedCallSigned, State, NodeMetadataRepository>,
+	Stf::Error: Debug,
+	State: SgxExternalitiesTrait,
+	State::SgxExternalitiesDiffType: Clone + IntoIterator<Item = (Vec<u8>, Option<Vec<u8>>)>,
+	NodeMetadataRepository: AccessNodeMetadata,
+	NodeMetadataRepository::MetadataType: NodeMetadataTrait,
+{
+	// Only the changes of this call must end up in the diff.
+	state.prune_state_diff();
+	let event_log_before = event_log(state);
+	let (executed_operation, access) = record_state_access(|| {
+		execute_trusted_operation::<Stf, _, _>(
+			state,
+			trusted_operation,
+			mrenclave,
+			shard,
+			node_metadata_repo,
+		)
+	});
+	let state_diff = state.state_diff().clone().into_iter().collect();
+	SpeculativeExecution::new(executed_operation, access, state_diff, event_log_before.as_deref())
+}
+
 fn into_map(
 	storage_entries: Vec<StorageEntryVerified<Vec<u8>>>,
 ) -> BTreeMap<Vec<u8>, Option<Vec<u8>>> {
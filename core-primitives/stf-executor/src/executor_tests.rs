@@ -15,12 +15,14 @@
 
 */
 
-use crate::{executor::StfExecutor, traits::StateUpdateProposer};
+use crate::{executor::StfExecutor, traits::StateUpdateProposer, BatchExecutionResult};
 use codec::Encode;
 use ita_stf::{
 	stf_sgx_tests::StfState,
-	test_genesis::{endowed_account, test_genesis_setup},
-	State, TrustedCall,
+	test_genesis::{
+		endowed_account, second_endowed_account, test_genesis_setup, unendowed_account,
+	},
+	State, TrustedCall, TrustedOperation,
 };
 use itc_parentchain_test::ParentchainHeaderBuilder;
 use itp_node_api::metadata::{metadata_mocks::NodeMetadataMock, provider::NodeMetadataRepository};
@@ -174,6 +176,143 @@ pub fn propose_state_update_executes_no_trusted_calls_given_no_time() {
 	assert_eq!(batch_execution_result.get_executed_operation_hashes(), vec![]);
 }
 
+pub fn propose_state_update_in_parallel_results_in_same_state_as_sequential_execution() {
+	// given
+	let (sequential_executor, ocall_api, sequential_state_handler) = stf_executor();
+	let (parallel_executor, _, parallel_state_handler) = stf_executor();
+	let parallel_executor = parallel_executor.with_max_parallel_calls(3);
+	let mrenclave = ocall_api.get_mrenclave_of_self().unwrap().m;
+	let (_, shard) = init_state_and_shard_with_state_handler(sequential_state_handler.as_ref());
+	let _ = init_state_and_shard_with_state_handler(parallel_state_handler.as_ref());
+
+	let sender_1 = endowed_account();
+	let sender_2 = second_endowed_account();
+	let receiver = unendowed_account();
+	// The third call depends on the first one (nonce), so it has to be executed after it.
+	let trusted_operations = vec![
+		TrustedCall::balance_transfer(sender_1.public().into(), receiver.public().into(), 42)
+			.sign(&sender_1.clone().into(), 0, &mrenclave, &shard)
+			.into_trusted_operation(true),
+		TrustedCall::balance_transfer(sender_2.public().into(), sender_2.public().into(), 100)
+			.sign(&sender_2.clone().into(), 0, &mrenclave, &shard)
+			.into_trusted_operation(true),
+		TrustedCall::balance_transfer(sender_1.public().into(), sender_1.public().into(), 7)
+			.sign(&sender_1.clone().into(), 1, &mrenclave, &shard)
+			.into_trusted_operation(true),
+	];
+
+	// when
+	let sequential_result = sequential_executor
+		.propose_state_update(
+			&trusted_operations,
+			&ParentchainHeaderBuilder::default().build(),
+			&shard,
+			Duration::from_secs(1000),
+			|state| state,
+		)
+		.unwrap();
+	let parallel_result = parallel_executor
+		.propose_state_update(
+			&trusted_operations,
+			&ParentchainHeaderBuilder::default().build(),
+			&shard,
+			Duration::from_secs(1000),
+			|state| state,
+		)
+		.unwrap();
+
+	// then
+	assert_eq!(parallel_result.executed_operations.len(), 3);
+	assert_eq!(
+		parallel_result.get_executed_operation_hashes(),
+		sequential_result.get_executed_operation_hashes()
+	);
+	assert_eq!(parallel_result.state_after_execution, sequential_result.state_after_execution);
+}
+
+pub fn propose_state_update_in_parallel_with_conflicting_calls_results_in_same_state_as_sequential_execution(
+) {
+	// given
+	let (sequential_executor, ocall_api, sequential_state_handler) = stf_executor();
+	let (parallel_executor, _, parallel_state_handler) = stf_executor();
+	let parallel_executor = parallel_executor.with_max_parallel_calls(4);
+	let mrenclave = ocall_api.get_mrenclave_of_self().unwrap().m;
+	let (_, shard) = init_state_and_shard_with_state_handler(sequential_state_handler.as_ref());
+	let _ = init_state_and_shard_with_state_handler(parallel_state_handler.as_ref());
+
+	let sender_1 = endowed_account();
+	let sender_2 = second_endowed_account();
+	let receiver = unendowed_account();
+	let transfer = |from: &sp_core::ed25519::Pair, to: &sp_core::ed25519::Pair, nonce| {
+		TrustedCall::balance_transfer(from.public().into(), to.public().into(), 10)
+			.sign(&from.clone().into(), nonce, &mrenclave, &shard)
+			.into_trusted_operation(true)
+	};
+	// Calls of the same sender, to the same receiver and calls that fail, each of which
+	// conflicts with a call in the same window.
+	let trusted_operations = vec![
+		transfer(&sender_1, &receiver, 0),
+		transfer(&sender_1, &sender_2, 1),
+		transfer(&sender_2, &receiver, 0),
+		transfer(&sender_1, &receiver, 2),
+		transfer(&sender_2, &sender_1, 5),
+		transfer(&receiver, &sender_1, 0),
+		transfer(&sender_2, &receiver, 1),
+		transfer(&sender_1, &sender_1, 3),
+	];
+
+	// when
+	let sequential_result = propose_state_update(&sequential_executor, &trusted_operations, &shard);
+	let parallel_result = propose_state_update(&parallel_executor, &trusted_operations, &shard);
+
+	// then
+	assert_eq!(parallel_result.executed_operations.len(), trusted_operations.len());
+	assert_eq!(parallel_result.executed_operations, sequential_result.executed_operations);
+	assert_eq!(parallel_result.state_after_execution, sequential_result.state_after_execution);
+}
+
+pub fn propose_state_update_in_parallel_follows_the_state_across_updates() {
+	// given
+	let (sequential_executor, ocall_api, sequential_state_handler) = stf_executor();
+	let (parallel_executor, _, parallel_state_handler) = stf_executor();
+	let parallel_executor = parallel_executor.with_max_parallel_calls(2);
+	let mrenclave = ocall_api.get_mrenclave_of_self().unwrap().m;
+	let (_, shard) = init_state_and_shard_with_state_handler(sequential_state_handler.as_ref());
+	let _ = init_state_and_shard_with_state_handler(parallel_state_handler.as_ref());
+
+	let sender_1 = endowed_account();
+	let sender_2 = second_endowed_account();
+	let transfer = |from: &sp_core::ed25519::Pair, nonce| {
+		TrustedCall::balance_transfer(from.public().into(), from.public().into(), 10)
+			.sign(&from.clone().into(), nonce, &mrenclave, &shard)
+			.into_trusted_operation(true)
+	};
+
+	for nonce in 0..3 {
+		let trusted_operations = vec![transfer(&sender_1, nonce), transfer(&sender_2, nonce)];
+
+		// when
+		let sequential_result =
+			propose_state_update(&sequential_executor, &trusted_operations, &shard);
+		let parallel_result = propose_state_update(&parallel_executor, &trusted_operations, &shard);
+		write_state(
+			sequential_state_handler.as_ref(),
+			sequential_result.state_after_execution.clone(),
+			&shard,
+		);
+		write_state(
+			parallel_state_handler.as_ref(),
+			parallel_result.state_after_execution.clone(),
+			&shard,
+		);
+
+		// then
+		assert_eq!(parallel_result.executed_operations, sequential_result.executed_operations);
+		assert_eq!(parallel_result.get_executed_operation_hashes().len(), trusted_operations.len());
+		assert_eq!(parallel_result.state_after_execution, sequential_result.state_after_execution);
+	}
+}
+
 pub fn propose_state_update_always_executes_preprocessing_step() {
 	// given
 	let shard = ShardIdentifier::default();
@@ -220,6 +359,32 @@ fn stf_executor() -> (
 	(executor, ocall_api, state_handler)
 }
 
+fn propose_state_update(
+	stf_executor: &StfExecutor<
+		OnchainMock,
+		HandleStateMock,
+		NodeMetadataRepository<NodeMetadataMock>,
+		StfState,
+	>,
+	trusted_operations: &[TrustedOperation],
+	shard: &ShardIdentifier,
+) -> BatchExecutionResult<State> {
+	stf_executor
+		.propose_state_update(
+			trusted_operations,
+			&ParentchainHeaderBuilder::default().build(),
+			shard,
+			Duration::from_secs(1000),
+			|state| state,
+		)
+		.unwrap()
+}
+
+fn write_state(state_handler: &HandleStateMock, state: State, shard: &ShardIdentifier) {
+	let (lock, _) = state_handler.load_for_mutation(shard).unwrap();
+	state_handler.write_after_mutation(state, lock, shard).unwrap();
+}
+
 /// Returns a test setup initialized `State` with the corresponding `ShardIdentifier`.
 pub(crate) fn init_state_and_shard_with_state_handler<S: HandleState<StateT = State>>(
 	state_handler: &S,
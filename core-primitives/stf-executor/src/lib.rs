@@ -38,6 +38,7 @@ pub mod sgx_reexport_prelude {
 
 pub mod error;
 pub mod getter_executor;
+pub mod parallel_execution;
 pub mod state_getter;
 pub mod traits;
 
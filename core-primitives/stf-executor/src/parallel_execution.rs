@@ -0,0 +1,655 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Conflict detection for executing trusted calls in parallel.
+//!
+//! A window of trusted calls is executed speculatively, each call on its own replica of the same
+//! state, see [`SpeculationPool`]. Afterwards, the calls are committed in their original order, until the first call
+//! accessed a key that has been written by a previously committed call of the same window.
+//! That call (and all following ones) is executed again in the next window, on top of the
+//! updated state.
+//!
+//! The committed result is therefore always the same as if the calls had been executed one
+//! after another, which guarantees that every validateer ends up with the same state.
+//!
+//! Nearly every call deposits an event, so the `System` pallet's event log is treated
+//! separately: a call that only appended events to the log does not conflict because of that.
+//! Its events are appended to the committed log in call order instead, see [`AppendedEvents`].
+
+use codec::{Compact, Decode, Encode};
+use itp_sgx_externalities::{SgxExternalitiesTrait, StateAccess};
+use itp_storage::storage_value_key;
+use log::*;
+use std::{
+	collections::BTreeSet,
+	sync::{
+		mpsc::{channel, Receiver, Sender},
+		Arc,
+	},
+	thread,
+	vec::Vec,
+};
+
+/// Keys a trusted call has read and written during its (speculative) execution.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateAccessSet {
+	reads: BTreeSet<Vec<u8>>,
+	writes: BTreeSet<Vec<u8>>,
+	iterated: bool,
+	appends_events: bool,
+}
+
+impl StateAccessSet {
+	pub fn new(
+		access: StateAccess,
+		writes: impl IntoIterator<Item = Vec<u8>>,
+		appends_events: bool,
+	) -> Self {
+		StateAccessSet {
+			reads: access.reads,
+			writes: writes.into_iter().collect(),
+			iterated: access.iterated,
+			appends_events,
+		}
+	}
+
+	/// Returns true if the call accessed (read or wrote) any of the `written` keys.
+	///
+	/// A call that iterated over the state may have read any key, so it conflicts with
+	/// every non-empty set of written keys.
+	pub fn conflicts_with(&self, written: &BTreeSet<Vec<u8>>) -> bool {
+		if written.is_empty() {
+			return false
+		}
+		self.iterated
+			|| self.reads.iter().any(|key| written.contains(key))
+			|| self.writes.iter().any(|key| written.contains(key))
+	}
+
+	/// Returns true if the call accessed the event log other than by only appending events.
+	///
+	/// Such a call conflicts with every previous call of the window that appended events.
+	pub fn depends_on_event_log(&self) -> bool {
+		!self.appends_events
+			&& (self.iterated
+				|| self.reads.iter().chain(self.writes.iter()).any(|key| is_event_log_key(key)))
+	}
+
+	/// All keys the call wrote, including the event log.
+	pub fn writes(&self) -> &BTreeSet<Vec<u8>> {
+		&self.writes
+	}
+
+	/// Keys that following calls must not access to be committed after this call.
+	///
+	/// Appending events does not conflict, so the event log keys are left out for a call
+	/// that only appended events.
+	pub fn conflicting_writes(&self) -> impl Iterator<Item = &Vec<u8>> {
+		self.writes.iter().filter(|key| !(self.appends_events && is_event_log_key(key)))
+	}
+}
+
+/// Events a trusted call appended to the `System` pallet's event log of its state replica.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AppendedEvents {
+	count: u32,
+	/// The encoded events, without the length prefix of the event log.
+	encoded: Vec<u8>,
+}
+
+impl AppendedEvents {
+	/// Events by which the event log `after` extends the event log `before`.
+	///
+	/// Returns `None` if `after` does not extend `before`, e.g. because the log was reset.
+	fn between(before: Option<&[u8]>, after: &[u8]) -> Option<Self> {
+		let (count_before, events_before) = match before {
+			Some(before) => split_event_log(before)?,
+			None => (0, &[][..]),
+		};
+		let (count_after, events_after) = split_event_log(after)?;
+		Some(AppendedEvents {
+			count: count_after.checked_sub(count_before)?,
+			encoded: events_after.strip_prefix(events_before)?.to_vec(),
+		})
+	}
+
+	/// Append the events to the event log of `state` and add them to its event count.
+	pub fn append_to<State: SgxExternalitiesTrait>(&self, state: &mut State) {
+		let (count, events) = state
+			.get(&events_key())
+			.and_then(|event_log| split_event_log(event_log))
+			.unwrap_or((0, &[][..]));
+		let mut event_log = Compact(count.saturating_add(self.count)).encode();
+		event_log.extend_from_slice(events);
+		event_log.extend_from_slice(&self.encoded);
+
+		let event_count = state
+			.get(&event_count_key())
+			.and_then(|count| u32::decode(&mut count.as_slice()).ok())
+			.unwrap_or_default();
+
+		state.insert(events_key(), event_log);
+		state.insert(event_count_key(), event_count.saturating_add(self.count).encode());
+	}
+}
+
+/// The encoded event log of `state`.
+pub fn event_log<State: SgxExternalitiesTrait>(state: &State) -> Option<Vec<u8>> {
+	state.get(&events_key()).cloned()
+}
+
+fn events_key() -> Vec<u8> {
+	storage_value_key("System", "Events")
+}
+
+fn event_count_key() -> Vec<u8> {
+	storage_value_key("System", "EventCount")
+}
+
+/// Prefix of the `System::EventTopics` map, which refers to events by their index in the log.
+fn event_topics_prefix() -> Vec<u8> {
+	storage_value_key("System", "EventTopics")
+}
+
+fn is_event_log_key(key: &[u8]) -> bool {
+	key == events_key().as_slice()
+		|| key == event_count_key().as_slice()
+		|| key.starts_with(&event_topics_prefix())
+}
+
+/// Number of events and the encoded events of a SCALE encoded event log.
+fn split_event_log(event_log: &[u8]) -> Option<(u32, &[u8])> {
+	let mut events = event_log;
+	let count = Compact::<u32>::decode(&mut events).ok()?.0;
+	Some((count, events))
+}
+
+/// Outcome and state diff of a trusted call that has been executed on a copy of the state.
+///
+/// If the call only appended events to the event log, the event log is not part of the state
+/// diff, the events are in `appended_events` instead.
+pub struct SpeculativeExecution<Outcome> {
+	pub outcome: Outcome,
+	pub access_set: StateAccessSet,
+	pub state_diff: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+	pub appended_events: Option<AppendedEvents>,
+}
+
+impl<Outcome> SpeculativeExecution<Outcome> {
+	/// `event_log_before` is the event log of the state the call has been executed on.
+	pub fn new(
+		outcome: Outcome,
+		access: StateAccess,
+		mut state_diff: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+		event_log_before: Option<&[u8]>,
+	) -> Self {
+		let writes: Vec<_> = state_diff.iter().map(|(key, _)| key.clone()).collect();
+		let appended_events = appended_events(event_log_before, &state_diff);
+		if appended_events.is_some() {
+			state_diff.retain(|(key, _)| !is_event_log_key(key));
+		}
+		let access_set = StateAccessSet::new(access, writes, appended_events.is_some());
+		SpeculativeExecution { outcome, access_set, state_diff, appended_events }
+	}
+}
+
+/// The events a call appended, if it changed the event log in no other way.
+///
+/// Event topics refer to events by their index, which depends on the events of the previous
+/// calls, so a call that deposited events with topics is not treated as only appending.
+fn appended_events(
+	event_log_before: Option<&[u8]>,
+	state_diff: &[(Vec<u8>, Option<Vec<u8>>)],
+) -> Option<AppendedEvents> {
+	let topics_prefix = event_topics_prefix();
+	if state_diff.iter().any(|(key, _)| key.starts_with(&topics_prefix)) {
+		return None
+	}
+	let events_key = events_key();
+	let (_, event_log_after) = state_diff.iter().find(|(key, _)| *key == events_key)?;
+	AppendedEvents::between(event_log_before, event_log_after.as_ref()?)
+}
+
+/// Number of leading speculative executions that can be committed in their given order.
+///
+/// The first execution never conflicts, so the result is at least 1 for a non-empty slice.
+pub fn conflict_free_prefix_len<Outcome>(executions: &[SpeculativeExecution<Outcome>]) -> usize {
+	let mut written = BTreeSet::new();
+	let mut events_appended = false;
+	for (index, execution) in executions.iter().enumerate() {
+		let access_set = &execution.access_set;
+		if access_set.conflicts_with(&written)
+			|| (events_appended && access_set.depends_on_event_log())
+		{
+			return index
+		}
+		written.extend(access_set.conflicting_writes().cloned());
+		events_appended |= execution.appended_events.is_some();
+	}
+	executions.len()
+}
+
+/// Executes the calls of a window on long-lived worker threads.
+///
+/// Every worker owns a replica of the state, which is cloned once when the pool is started.
+/// After a window has been committed, [`SpeculationPool::sync`] brings the replicas up to date
+/// by sending them only the entries that changed, instead of copying the whole state per call.
+/// The pool can be kept from one state update to the next, see [`SpeculationPool::rebase`].
+pub struct SpeculationPool<State, Call, Outcome> {
+	workers: Vec<Worker<Call, Outcome>>,
+	/// The committed state, which the replicas are synced to.
+	reference: State,
+}
+
+struct Worker<Call, Outcome> {
+	tasks: Sender<WorkerTask<Call>>,
+	executions: Receiver<SpeculativeExecution<Outcome>>,
+	/// Entries to apply to the replica before executing the next call.
+	pending_updates: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+	/// Keys the last call wrote to the replica, whether it has been committed or not.
+	speculative_writes: BTreeSet<Vec<u8>>,
+}
+
+struct WorkerTask<Call> {
+	updates: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+	call: Call,
+}
+
+impl<State, Call, Outcome> SpeculationPool<State, Call, Outcome>
+where
+	State: SgxExternalitiesTrait + Clone + Send + 'static,
+	Call: Clone + Send + 'static,
+	Outcome: Send + 'static,
+{
+	/// Start up to `size` workers, each with its own replica of `state`.
+	///
+	/// `execute` must leave only the changes of the executed call in the state diff of the
+	/// replica it is given. Workers that cannot be spawned (e.g. no free TCS in the enclave)
+	/// are skipped, so the pool may be smaller than requested, or even empty.
+	pub fn start<F>(state: &State, size: usize, execute: F) -> Self
+	where
+		F: Fn(&mut State, &Call) -> SpeculativeExecution<Outcome> + Send + Sync + 'static,
+	{
+		let execute = Arc::new(execute);
+		let mut workers = Vec::with_capacity(size);
+		for _ in 0..size {
+			let (task_sender, task_receiver) = channel::<WorkerTask<Call>>();
+			let (execution_sender, execution_receiver) = channel();
+			let mut replica = state.clone();
+			let execute = execute.clone();
+			let spawn_result = thread::Builder::new().spawn(move || {
+				while let Ok(task) = task_receiver.recv() {
+					for (key, value) in task.updates {
+						match value {
+							Some(value) => {
+								replica.insert(key, value);
+							},
+							None => {
+								replica.remove(&key);
+							},
+						}
+					}
+					let execution = execute(&mut replica, &task.call);
+					if execution_sender.send(execution).is_err() {
+						break
+					}
+				}
+			});
+			match spawn_result {
+				Ok(_) => workers.push(Worker {
+					tasks: task_sender,
+					executions: execution_receiver,
+					pending_updates: Vec::new(),
+					speculative_writes: BTreeSet::new(),
+				}),
+				Err(e) => {
+					warn!("Failed to spawn thread for trusted call execution: {:?}", e);
+					break
+				},
+			}
+		}
+		let mut reference = state.clone();
+		reference.prune_state_diff();
+		SpeculationPool { workers, reference }
+	}
+
+	/// Number of workers, i.e. the maximum number of calls that can be executed per window.
+	pub fn size(&self) -> usize {
+		self.workers.len()
+	}
+
+	/// Execute each call of `window` on a worker of its own.
+	///
+	/// The window must not contain more calls than there are workers. A call whose worker
+	/// panicked results in `failed(call)`, it is not executed again. The worker is removed
+	/// from the pool, as its replica is gone.
+	pub fn execute(
+		&mut self,
+		window: &[Call],
+		failed: impl Fn(&Call) -> Outcome,
+	) -> Vec<SpeculativeExecution<Outcome>> {
+		debug_assert!(window.len() <= self.workers.len());
+
+		let mut dispatched = Vec::with_capacity(window.len());
+		for (worker, call) in self.workers.iter_mut().zip(window) {
+			let updates = std::mem::take(&mut worker.pending_updates);
+			dispatched.push(worker.tasks.send(WorkerTask { updates, call: call.clone() }).is_ok());
+		}
+
+		let mut crashed_workers = Vec::new();
+		let mut executions = Vec::with_capacity(window.len());
+		for (index, (call, sent)) in window.iter().zip(dispatched).enumerate() {
+			let worker = &mut self.workers[index];
+			match sent.then(|| worker.executions.recv().ok()).flatten() {
+				Some(execution) => {
+					worker.speculative_writes = execution.access_set.writes().clone();
+					executions.push(execution);
+				},
+				None => {
+					error!("Thread executing a trusted call panicked, marking the call as failed");
+					crashed_workers.push(index);
+					executions.push(SpeculativeExecution::new(
+						failed(call),
+						StateAccess::default(),
+						Vec::new(),
+						None,
+					));
+				},
+			}
+		}
+
+		for index in crashed_workers.into_iter().rev() {
+			self.workers.remove(index);
+		}
+		executions
+	}
+
+	/// Bring the replicas up to date after the calls writing `committed_writes` have been
+	/// committed to `state`.
+	///
+	/// Besides the committed entries, a replica gets back the committed value of every entry
+	/// its last call wrote, so that the changes of calls that have not been committed are
+	/// reverted.
+	pub fn sync(&mut self, state: &State, committed_writes: &BTreeSet<Vec<u8>>) {
+		for key in committed_writes {
+			match state.get(key) {
+				Some(value) => self.reference.insert(key.clone(), value.clone()),
+				None => self.reference.remove(key),
+			};
+		}
+		self.reference.prune_state_diff();
+		self.revert_speculative_writes(committed_writes.iter().cloned().collect());
+	}
+
+	/// Bring the replicas up to date with `state`, which may differ in any entry from the state
+	/// the pool has been synced to last, e.g. at the start of the next state update.
+	///
+	/// Only the entries that differ are sent to the workers.
+	pub fn rebase(&mut self, state: &State) {
+		let changes = state.changes_since(&self.reference);
+		for (key, value) in changes.iter() {
+			match value {
+				Some(value) => self.reference.insert(key.clone(), value.clone()),
+				None => self.reference.remove(key),
+			};
+		}
+		self.reference.prune_state_diff();
+		self.revert_speculative_writes(changes.into_iter().map(|(key, _)| key).collect());
+	}
+
+	/// Queue the reference values of the `changed_keys` and of the keys written by the last
+	/// call of each worker.
+	fn revert_speculative_writes(&mut self, changed_keys: BTreeSet<Vec<u8>>) {
+		for worker in self.workers.iter_mut() {
+			let speculative_writes = std::mem::take(&mut worker.speculative_writes);
+			// Appended to updates not yet sent, so later values take precedence.
+			worker.pending_updates.extend(
+				changed_keys
+					.union(&speculative_writes)
+					.map(|key| (key.clone(), self.reference.get(key).cloned())),
+			);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use itp_sgx_externalities::SgxExternalities;
+	use std::{
+		sync::atomic::{AtomicUsize, Ordering},
+		vec,
+	};
+
+	#[test]
+	fn independent_executions_are_all_conflict_free() {
+		let executions = vec![
+			execution(&[b"a"], &[b"a"], false),
+			execution(&[b"b"], &[b"b"], false),
+			execution(&[b"c"], &[], false),
+		];
+
+		assert_eq!(conflict_free_prefix_len(&executions), 3);
+	}
+
+	#[test]
+	fn reading_a_previously_written_key_conflicts() {
+		let executions = vec![
+			execution(&[], &[b"a"], false),
+			execution(&[b"b"], &[b"b"], false),
+			execution(&[b"a"], &[b"c"], false),
+			execution(&[b"d"], &[b"d"], false),
+		];
+
+		assert_eq!(conflict_free_prefix_len(&executions), 2);
+	}
+
+	#[test]
+	fn writing_a_previously_written_key_conflicts() {
+		let executions = vec![execution(&[], &[b"a"], false), execution(&[], &[b"a"], false)];
+
+		assert_eq!(conflict_free_prefix_len(&executions), 1);
+	}
+
+	#[test]
+	fn writing_a_previously_read_key_does_not_conflict() {
+		let executions = vec![execution(&[b"a"], &[], false), execution(&[], &[b"a"], false)];
+
+		assert_eq!(conflict_free_prefix_len(&executions), 2);
+	}
+
+	#[test]
+	fn iterating_conflicts_only_after_a_write() {
+		let executions = vec![
+			execution(&[], &[], true),
+			execution(&[b"a"], &[], false),
+			execution(&[], &[b"b"], false),
+			execution(&[], &[], true),
+		];
+
+		assert_eq!(conflict_free_prefix_len(&executions), 3);
+	}
+
+	#[test]
+	fn first_execution_never_conflicts() {
+		let executions = vec![execution(&[b"a"], &[b"a"], true)];
+
+		assert_eq!(conflict_free_prefix_len(&executions), 1);
+		assert_eq!(conflict_free_prefix_len::<()>(&[]), 0);
+	}
+
+	#[test]
+	fn appending_events_does_not_conflict() {
+		let executions = vec![
+			appending_execution(&[1], &[b"a"]),
+			appending_execution(&[2, 3], &[b"b"]),
+			appending_execution(&[4], &[b"a"]),
+		];
+
+		assert!(executions.iter().all(|execution| execution.appended_events.is_some()));
+		assert!(executions[0].state_diff.iter().all(|(key, _)| !is_event_log_key(key)));
+		assert_eq!(conflict_free_prefix_len(&executions), 2);
+	}
+
+	#[test]
+	fn accessing_the_event_log_otherwise_conflicts_with_appended_events() {
+		let reading = execution(&[events_key().as_slice()], &[], false);
+		let writing_topics =
+			execution(&[], &[[event_topics_prefix(), vec![1]].concat().as_slice()], false);
+
+		assert!(reading.appended_events.is_none());
+		assert!(writing_topics.appended_events.is_none());
+		assert_eq!(conflict_free_prefix_len(&[appending_execution(&[1], &[]), reading]), 1);
+		assert_eq!(conflict_free_prefix_len(&[appending_execution(&[1], &[]), writing_topics]), 1);
+	}
+
+	#[test]
+	fn resetting_the_event_log_is_not_appending() {
+		let state_diff = vec![(events_key(), Some(vec![5u32].encode()))];
+		let execution = SpeculativeExecution::new(
+			(),
+			StateAccess::default(),
+			state_diff,
+			Some(vec![0u32].encode().as_slice()),
+		);
+
+		assert!(execution.appended_events.is_none());
+		assert_eq!(conflict_free_prefix_len(&[appending_execution(&[1], &[]), execution]), 1);
+	}
+
+	#[test]
+	fn appended_events_are_added_to_the_event_log_in_call_order() {
+		let mut state = SgxExternalities::default();
+		state.insert(events_key(), vec![0u32].encode());
+		state.insert(event_count_key(), 1u32.encode());
+
+		for execution in [appending_execution(&[1], &[]), appending_execution(&[2, 3], &[])] {
+			execution.appended_events.unwrap().append_to(&mut state);
+		}
+
+		assert_eq!(state.get(&events_key()), Some(&vec![0u32, 1, 2, 3].encode()));
+		assert_eq!(state.get(&event_count_key()), Some(&4u32.encode()));
+	}
+
+	#[test]
+	fn replicas_follow_the_committed_state() {
+		let state = SgxExternalities::default();
+		let mut pool = SpeculationPool::start(&state, 2, increment);
+		let mut state = state;
+
+		// Only the first call is committed, the write of the second one must be reverted.
+		let executions = pool.execute(&[&b"a"[..], b"b"], |_| None);
+		commit(&mut pool, &mut state, executions.into_iter().take(1));
+
+		let outcomes = pool.execute(&[&b"a"[..], b"b"], |_| None);
+		let outcomes: Vec<_> = outcomes.into_iter().map(|e| e.outcome).collect();
+
+		assert_eq!(outcomes, vec![Some(1), Some(0)]);
+	}
+
+	#[test]
+	fn replicas_follow_a_rebased_state() {
+		let mut state = SgxExternalities::default();
+		state.insert(b"a".to_vec(), vec![5]);
+		state.insert(b"b".to_vec(), vec![5]);
+		let mut pool = SpeculationPool::start(&state, 2, increment);
+		// Leave a speculative write on the second replica.
+		pool.execute(&[&b"c"[..], b"c"], |_| None);
+
+		let mut rebased_state = SgxExternalities::default();
+		rebased_state.insert(b"a".to_vec(), vec![7]);
+		pool.rebase(&rebased_state);
+
+		let outcomes = pool.execute(&[&b"a"[..], b"b"], |_| None);
+		let outcomes: Vec<_> = outcomes.into_iter().map(|e| e.outcome).collect();
+		assert_eq!(outcomes, vec![Some(8), Some(0)]);
+		let outcomes = pool.execute(&[&b"c"[..], b"c"], |_| None);
+		let outcomes: Vec<_> = outcomes.into_iter().map(|e| e.outcome).collect();
+		assert_eq!(outcomes, vec![Some(0), Some(0)]);
+	}
+
+	#[test]
+	fn panicking_call_fails_without_being_executed_again() {
+		let executed_calls = Arc::new(AtomicUsize::new(0));
+		let counter = executed_calls.clone();
+		let mut pool =
+			SpeculationPool::start(&SgxExternalities::default(), 2, move |state, call| {
+				counter.fetch_add(1, Ordering::SeqCst);
+				if *call == &b"panic"[..] {
+					panic!("call panicked");
+				}
+				increment(state, call)
+			});
+
+		let executions = pool.execute(&[&b"a"[..], b"panic"], |_| Some(u8::MAX));
+		let outcomes: Vec<_> = executions.iter().map(|e| e.outcome).collect();
+
+		assert_eq!(outcomes, vec![Some(0), Some(u8::MAX)]);
+		assert!(executions[1].state_diff.is_empty());
+		assert_eq!(executed_calls.load(Ordering::SeqCst), 2);
+		assert_eq!(pool.size(), 1);
+	}
+
+	/// Write the successor of the value at `key`, starting with 0, and return the written value.
+	fn increment(state: &mut SgxExternalities, key: &&[u8]) -> SpeculativeExecution<Option<u8>> {
+		state.prune_state_diff();
+		let value = state.get(key).map(|value| value[0] + 1).unwrap_or_default();
+		state.insert(key.to_vec(), vec![value]);
+		let state_diff = state.state_diff().clone().into_iter().collect();
+		SpeculativeExecution::new(Some(value), StateAccess::default(), state_diff, None)
+	}
+
+	fn commit(
+		pool: &mut SpeculationPool<SgxExternalities, &'static [u8], Option<u8>>,
+		state: &mut SgxExternalities,
+		executions: impl Iterator<Item = SpeculativeExecution<Option<u8>>>,
+	) {
+		let mut committed_writes = BTreeSet::new();
+		for execution in executions {
+			committed_writes.extend(execution.access_set.writes().iter().cloned());
+			for (key, value) in execution.state_diff {
+				match value {
+					Some(value) => state.insert(key, value),
+					None => state.remove(&key),
+				};
+			}
+		}
+		pool.sync(state, &committed_writes);
+	}
+
+	fn execution(reads: &[&[u8]], writes: &[&[u8]], iterated: bool) -> SpeculativeExecution<()> {
+		let access =
+			StateAccess { reads: reads.iter().map(|key| key.to_vec()).collect(), iterated };
+		let state_diff = writes.iter().map(|key| (key.to_vec(), Some(vec![1u8]))).collect();
+		SpeculativeExecution::new((), access, state_diff, None)
+	}
+
+	/// A call that appends `events` to the event log `[0]`, besides writing `writes`.
+	fn appending_execution(events: &[u32], writes: &[&[u8]]) -> SpeculativeExecution<()> {
+		let access = StateAccess {
+			reads: [events_key(), event_count_key()].into_iter().collect(),
+			iterated: false,
+		};
+		let mut event_log = vec![0u32];
+		event_log.extend_from_slice(events);
+		let mut state_diff: Vec<_> =
+			writes.iter().map(|key| (key.to_vec(), Some(vec![1u8]))).collect();
+		state_diff.push((events_key(), Some(event_log.encode())));
+		state_diff.push((event_count_key(), Some((event_log.len() as u32).encode())));
+		SpeculativeExecution::new((), access, state_diff, Some(vec![0u32].encode().as_slice()))
+	}
+}
@@ -43,6 +43,12 @@ pub enum Error {
 	StateNotFoundInRepository(String),
 	#[error("State observer error: {0}")]
 	StateObserver(#[from] itp_stf_state_observer::error::Error),
+	#[error("Hash of the imported state snapshot does not match: expected {0}, computed {1}")]
+	SnapshotHashMismatch(String, String),
+	#[error("Invalid signature of the exporter of the state snapshot")]
+	InvalidSnapshotSignature,
+	#[error("State snapshot is not ahead of the current state: {0}")]
+	OutdatedSnapshot(String),
 	#[error("Cache size for registry is zero")]
 	ZeroCacheSize,
 	#[error("Could not acquire lock, lock is poisoned")]
@@ -72,4 +72,13 @@ pub trait HandleState {
 	///
 	/// Use in cases where the previous state is of no interest. Otherwise use `load_for_mutation` and `write_after_mutation`.
 	fn reset(&self, state: Self::StateT, shard: &ShardIdentifier) -> Result<Self::HashType>;
+
+	/// Load a clone of an earlier (or the current) state snapshot, identified by its state hash.
+	///
+	/// The current state and the snapshot history remain unchanged.
+	fn load_snapshot(
+		&self,
+		shard: &ShardIdentifier,
+		state_hash: &Self::HashType,
+	) -> Result<Self::StateT>;
 }
@@ -227,6 +227,17 @@ where
 		let state_write_lock = self.states_map_lock.write().map_err(|_| Error::LockPoisoning)?;
 		self.write_after_mutation(state, state_write_lock, shard)
 	}
+
+	fn load_snapshot(
+		&self,
+		shard: &ShardIdentifier,
+		state_hash: &Self::HashType,
+	) -> Result<Self::StateT> {
+		self.state_snapshot_repository
+			.read()
+			.map_err(|_| Error::LockPoisoning)?
+			.load_snapshot(shard, state_hash)
+	}
 }
 
 impl<Repository, StateObserver, StateInitializer> QueryShardState
@@ -0,0 +1,413 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Export and import of shard state snapshots, for shard migration and cold restores.
+//!
+//! A snapshot is exported at a given state hash and encrypted with a fresh transport key,
+//! which itself is encrypted to the shielding key of the importing enclave. The importing
+//! enclave announces its shielding key in a [`StateImportKey`], signed with the enclave
+//! signing key that it registered (i.e. remote attested) on the parentchain. The exporting
+//! enclave in turn signs the [`StateSnapshotExport`] with its registered signing key, so that
+//! the importing enclave can check where the snapshot comes from.
+
+use crate::{
+	error::{Error, Result},
+	handle_state::HandleState,
+	query_shard_state::QueryShardState,
+};
+use codec::{Decode, Encode};
+use itp_hashing::Hash;
+use itp_sgx_crypto::{Aes, ShieldingCryptoDecrypt, ShieldingCryptoEncrypt, StateCrypto};
+use itp_sgx_externalities::SgxExternalitiesTrait;
+use itp_types::{ShardIdentifier, H256};
+use log::*;
+use sp_core::{blake2_256, ed25519, Pair};
+use std::{format, vec::Vec};
+
+/// Signing context of a [`StateImportKey`], to prevent the signature from being replayed elsewhere.
+const STATE_IMPORT_KEY_CONTEXT: &[u8] = b"integritee/state-import-key";
+
+/// Signing context of a [`StateSnapshotExport`].
+const STATE_SNAPSHOT_EXPORT_CONTEXT: &[u8] = b"integritee/state-snapshot-export";
+
+/// Shielding key of an enclave that wants to import a state snapshot.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct StateImportKey {
+	/// Signing key of the importing enclave, as registered on the parentchain.
+	pub enclave_signer: ed25519::Public,
+	/// JSON encoded RSA shielding public key of the importing enclave.
+	pub shielding_key: Vec<u8>,
+	/// Signature of the `enclave_signer` over the `shielding_key`.
+	pub signature: ed25519::Signature,
+}
+
+impl StateImportKey {
+	pub fn new_signed(enclave_signer: &ed25519::Pair, shielding_key: Vec<u8>) -> Self {
+		let signature = enclave_signer.sign(&Self::signing_payload(&shielding_key));
+		Self { enclave_signer: enclave_signer.public(), shielding_key, signature }
+	}
+
+	/// Verify that the shielding key was signed by the enclave signer.
+	///
+	/// Does not check whether the signer is a registered enclave, that is up to the caller.
+	pub fn verify_signature(&self) -> bool {
+		ed25519::Pair::verify(
+			&self.signature,
+			Self::signing_payload(&self.shielding_key),
+			&self.enclave_signer,
+		)
+	}
+
+	fn signing_payload(shielding_key: &[u8]) -> Vec<u8> {
+		(STATE_IMPORT_KEY_CONTEXT, shielding_key).encode()
+	}
+}
+
+/// Encrypted state snapshot of a shard, as written to the export file.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct StateSnapshotExport {
+	pub shard: ShardIdentifier,
+	/// Hash of the exported state, checked on import.
+	pub state_hash: H256,
+	/// Signing key of the enclave the snapshot is encrypted for.
+	pub target_enclave_signer: ed25519::Public,
+	/// Transport key, encrypted with the shielding key of the target enclave.
+	pub encrypted_transport_key: Vec<u8>,
+	/// Encoded state, encrypted with the transport key.
+	pub encrypted_state: Vec<u8>,
+	/// Signing key of the exporting enclave, as registered on the parentchain.
+	pub exporter_enclave_signer: ed25519::Public,
+	/// Signature of the `exporter_enclave_signer` over all the other fields.
+	pub signature: ed25519::Signature,
+}
+
+impl StateSnapshotExport {
+	/// Verify that the snapshot was signed by the exporter enclave signer.
+	///
+	/// Does not check whether the signer is a registered enclave, that is up to the caller.
+	pub fn verify_signature(&self) -> bool {
+		ed25519::Pair::verify(
+			&self.signature,
+			self.signing_payload(),
+			&self.exporter_enclave_signer,
+		)
+	}
+
+	fn signing_payload(&self) -> Vec<u8> {
+		(
+			STATE_SNAPSHOT_EXPORT_CONTEXT,
+			self.shard,
+			self.state_hash,
+			self.target_enclave_signer,
+			blake2_256(&self.encrypted_transport_key),
+			blake2_256(&self.encrypted_state),
+			self.exporter_enclave_signer,
+		)
+			.encode()
+	}
+}
+
+/// Export the state snapshot of `shard` with the given `state_hash`.
+///
+/// The snapshot is encrypted with `transport_key`, which should be freshly generated for
+/// each export. The transport key in turn is encrypted with `target_shielding_key`, the
+/// (previously verified) shielding key of the target enclave. The export is signed with
+/// `exporter_signer`, our own enclave signing key.
+pub fn export_state_snapshot<StateHandler, ShieldingKey>(
+	state_handler: &StateHandler,
+	shard: &ShardIdentifier,
+	state_hash: &H256,
+	exporter_signer: &ed25519::Pair,
+	target_enclave_signer: ed25519::Public,
+	target_shielding_key: &ShieldingKey,
+	transport_key: Aes,
+) -> Result<StateSnapshotExport>
+where
+	StateHandler: HandleState<HashType = H256>,
+	StateHandler::StateT: SgxExternalitiesTrait,
+	<StateHandler::StateT as SgxExternalitiesTrait>::SgxExternalitiesType: Encode,
+	ShieldingKey: ShieldingCryptoEncrypt,
+{
+	let state = state_handler.load_snapshot(shard, state_hash)?;
+
+	let mut encrypted_state = state.state().encode();
+	transport_key.encrypt(&mut encrypted_state)?;
+
+	let encrypted_transport_key = target_shielding_key
+		.encrypt(&transport_key.encode())
+		.map_err(|e| Error::Other(format!("{:?}", e).into()))?;
+
+	let mut snapshot = StateSnapshotExport {
+		shard: *shard,
+		state_hash: *state_hash,
+		target_enclave_signer,
+		encrypted_transport_key,
+		encrypted_state,
+		exporter_enclave_signer: exporter_signer.public(),
+		signature: ed25519::Signature::from_raw([0u8; 64]),
+	};
+	snapshot.signature = exporter_signer.sign(&snapshot.signing_payload());
+
+	info!("Exported state snapshot {:?} of shard {:?}", state_hash, shard);
+	Ok(snapshot)
+}
+
+/// Import a state snapshot, decrypting it with our own `shielding_key`.
+///
+/// The signature of the exporter is checked here, whether the exporter is a registered
+/// enclave is up to the caller. The decrypted state is only written, if its hash matches
+/// the hash of the exported state, and if the shard does not exist yet or
+/// `ensure_is_ahead(snapshot_state, current_state)` succeeds. Returns the hash of the
+/// imported state.
+pub fn import_state_snapshot<StateHandler, ShieldingKey, EnsureIsAhead>(
+	state_handler: &StateHandler,
+	snapshot: StateSnapshotExport,
+	shielding_key: &ShieldingKey,
+	ensure_is_ahead: EnsureIsAhead,
+) -> Result<H256>
+where
+	StateHandler: HandleState<HashType = H256> + QueryShardState,
+	StateHandler::StateT: SgxExternalitiesTrait + Hash<H256>,
+	<StateHandler::StateT as SgxExternalitiesTrait>::SgxExternalitiesType: Decode,
+	ShieldingKey: ShieldingCryptoDecrypt,
+	EnsureIsAhead: FnOnce(&StateHandler::StateT, &StateHandler::StateT) -> Result<()>,
+{
+	if !snapshot.verify_signature() {
+		return Err(Error::InvalidSnapshotSignature)
+	}
+
+	let transport_key = shielding_key
+		.decrypt(&snapshot.encrypted_transport_key)
+		.map_err(|e| Error::Other(format!("{:?}", e).into()))
+		.and_then(|key| Ok(Aes::decode(&mut key.as_slice())?))?;
+
+	let mut state_bytes = snapshot.encrypted_state;
+	transport_key.decrypt(&mut state_bytes)?;
+
+	let state = <StateHandler::StateT as SgxExternalitiesTrait>::new(Decode::decode(
+		&mut state_bytes.as_slice(),
+	)?);
+	let computed_hash = state.hash();
+	if computed_hash != snapshot.state_hash {
+		return Err(Error::SnapshotHashMismatch(
+			format!("{:?}", snapshot.state_hash),
+			format!("{:?}", computed_hash),
+		))
+	}
+
+	if state_handler.shard_exists(&snapshot.shard)? {
+		let (current_state, _) = state_handler.load_cloned(&snapshot.shard)?;
+		ensure_is_ahead(&state, &current_state)?;
+	}
+
+	let state_hash = state_handler.reset(state, &snapshot.shard)?;
+	info!("Imported state snapshot {:?} into shard {:?}", state_hash, snapshot.shard);
+	Ok(state_hash)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		state_handler::StateHandler,
+		test::mocks::{
+			initialize_state_mock::InitializeStateMock,
+			versioned_state_access_mock::VersionedStateAccessMock,
+		},
+	};
+	use itp_sgx_externalities::{SgxExternalities, SgxExternalitiesType};
+	use itp_stf_state_observer::mock::UpdateStateMock;
+	use std::{convert::Infallible, sync::Arc};
+
+	type TestState = SgxExternalities;
+	type TestStateHandler = StateHandler<
+		VersionedStateAccessMock<TestState, H256>,
+		UpdateStateMock<TestState>,
+		InitializeStateMock<TestState>,
+	>;
+
+	/// Reverses the bytes, so that encrypted data is distinguishable from plain data.
+	#[derive(Default)]
+	struct TestShieldingKey;
+
+	impl ShieldingCryptoEncrypt for TestShieldingKey {
+		type Error = Infallible;
+
+		fn encrypt(&self, data: &[u8]) -> core::result::Result<Vec<u8>, Self::Error> {
+			Ok(data.iter().rev().copied().collect())
+		}
+	}
+
+	impl ShieldingCryptoDecrypt for TestShieldingKey {
+		type Error = Infallible;
+
+		fn decrypt(&self, data: &[u8]) -> core::result::Result<Vec<u8>, Self::Error> {
+			Ok(data.iter().rev().copied().collect())
+		}
+	}
+
+	#[test]
+	fn import_key_signature_verifies() {
+		let signer = ed25519::Pair::from_seed(&[1u8; 32]);
+		let import_key = StateImportKey::new_signed(&signer, vec![1, 2, 3]);
+
+		assert!(import_key.verify_signature());
+
+		let tampered = StateImportKey { shielding_key: vec![1, 2, 4], ..import_key };
+		assert!(!tampered.verify_signature());
+	}
+
+	#[test]
+	fn exported_snapshot_can_be_imported_in_other_state_handler() {
+		let shard = ShardIdentifier::random();
+		let source = state_handler();
+		let state_hash = source.reset(state_with_value(42), &shard).unwrap();
+
+		let snapshot = export_snapshot(&source, &shard, &state_hash);
+		assert_ne!(
+			source.load_cloned(&shard).unwrap().0.state().encode(),
+			snapshot.encrypted_state
+		);
+
+		let target = state_handler();
+		let imported_hash =
+			import_state_snapshot(&target, snapshot, &TestShieldingKey, ensure_value_is_larger)
+				.unwrap();
+
+		assert_eq!(state_hash, imported_hash);
+		assert_eq!(source.load_cloned(&shard).unwrap(), target.load_cloned(&shard).unwrap());
+	}
+
+	#[test]
+	fn import_fails_on_invalid_signature_and_leaves_state_untouched() {
+		let shard = ShardIdentifier::random();
+		let source = state_handler();
+		let state_hash = source.reset(state_with_value(42), &shard).unwrap();
+
+		let mut snapshot = export_snapshot(&source, &shard, &state_hash);
+		snapshot.state_hash = H256::random();
+
+		let target = state_handler();
+		let result =
+			import_state_snapshot(&target, snapshot, &TestShieldingKey, ensure_value_is_larger);
+
+		assert!(matches!(result, Err(Error::InvalidSnapshotSignature)));
+		assert!(target.load_cloned(&shard).is_err());
+	}
+
+	#[test]
+	fn import_fails_on_tampered_state_and_leaves_state_untouched() {
+		let shard = ShardIdentifier::random();
+		let source = state_handler();
+		let state_hash = source.reset(state_with_value(42), &shard).unwrap();
+
+		let mut snapshot = export_snapshot(&source, &shard, &state_hash);
+		snapshot.encrypted_state[0] ^= 1;
+
+		let target = state_handler();
+		let result =
+			import_state_snapshot(&target, snapshot, &TestShieldingKey, ensure_value_is_larger);
+
+		assert!(matches!(result, Err(Error::InvalidSnapshotSignature)));
+		assert!(target.load_cloned(&shard).is_err());
+	}
+
+	#[test]
+	fn import_fails_on_hash_mismatch_of_signed_snapshot() {
+		let shard = ShardIdentifier::random();
+		let source = state_handler();
+		let state_hash = source.reset(state_with_value(42), &shard).unwrap();
+
+		let exporter = ed25519::Pair::from_seed(&[1u8; 32]);
+		let mut snapshot = export_snapshot(&source, &shard, &state_hash);
+		snapshot.state_hash = H256::random();
+		snapshot.signature = exporter.sign(&snapshot.signing_payload());
+
+		let target = state_handler();
+		let result =
+			import_state_snapshot(&target, snapshot, &TestShieldingKey, ensure_value_is_larger);
+
+		assert!(matches!(result, Err(Error::SnapshotHashMismatch(_, _))));
+		assert!(target.load_cloned(&shard).is_err());
+	}
+
+	#[test]
+	fn import_into_existing_shard_fails_if_snapshot_is_not_ahead() {
+		let shard = ShardIdentifier::random();
+		let source = state_handler();
+		let state_hash = source.reset(state_with_value(42), &shard).unwrap();
+		let snapshot = export_snapshot(&source, &shard, &state_hash);
+
+		let target = state_handler();
+		let current_hash = target.reset(state_with_value(42), &shard).unwrap();
+		let result = import_state_snapshot(
+			&target,
+			snapshot.clone(),
+			&TestShieldingKey,
+			ensure_value_is_larger,
+		);
+
+		assert!(matches!(result, Err(Error::OutdatedSnapshot(_))));
+		assert_eq!(target.load_cloned(&shard).unwrap().1, current_hash);
+
+		target.reset(state_with_value(41), &shard).unwrap();
+		let imported_hash =
+			import_state_snapshot(&target, snapshot, &TestShieldingKey, ensure_value_is_larger)
+				.unwrap();
+
+		assert_eq!(imported_hash, state_hash);
+	}
+
+	fn ensure_value_is_larger(snapshot_state: &TestState, current_state: &TestState) -> Result<()> {
+		if snapshot_state.get(b"key") > current_state.get(b"key") {
+			Ok(())
+		} else {
+			Err(Error::OutdatedSnapshot("value is not larger".into()))
+		}
+	}
+
+	fn export_snapshot(
+		state_handler: &TestStateHandler,
+		shard: &ShardIdentifier,
+		state_hash: &H256,
+	) -> StateSnapshotExport {
+		export_state_snapshot(
+			state_handler,
+			shard,
+			state_hash,
+			&ed25519::Pair::from_seed(&[1u8; 32]),
+			ed25519::Public::from_raw([2u8; 32]),
+			&TestShieldingKey,
+			Aes::new([3u8; 16], [4u8; 16]),
+		)
+		.unwrap()
+	}
+
+	fn state_with_value(value: u64) -> TestState {
+		let mut state = TestState::new(SgxExternalitiesType::default());
+		state.insert(b"key".to_vec(), value.encode());
+		state
+	}
+
+	fn state_handler() -> TestStateHandler {
+		TestStateHandler::new(
+			Default::default(),
+			Arc::new(UpdateStateMock::default()),
+			Arc::new(InitializeStateMock::new(Default::default())),
+		)
+	}
+}
@@ -44,6 +44,13 @@ pub trait VersionedStateAccess {
 		state_hash: Self::HashType,
 	) -> Result<()>;
 
+	/// Load the state version identified by a state hash, without changing the history.
+	fn load_snapshot(
+		&self,
+		shard_identifier: &ShardIdentifier,
+		state_hash: &Self::HashType,
+	) -> Result<Self::StateType>;
+
 	/// Reverts the state of a given shard to a state version identified by a state hash.
 	fn revert_to(
 		&mut self,
@@ -238,6 +245,20 @@ where
 		Ok(())
 	}
 
+	fn load_snapshot(
+		&self,
+		shard_identifier: &ShardIdentifier,
+		state_hash: &Self::HashType,
+	) -> Result<Self::StateType> {
+		let snapshot_metadata = self
+			.get_snapshot_history(shard_identifier)?
+			.iter()
+			.find(|fmd| fmd.state_hash == *state_hash)
+			.ok_or_else(|| Error::StateNotFoundInRepository(format!("{:?}", state_hash)))?;
+
+		self.load_state(shard_identifier, snapshot_metadata)
+	}
+
 	fn revert_to(
 		&mut self,
 		shard_identifier: &ShardIdentifier,
@@ -437,6 +458,32 @@ mod tests {
 		assert_eq!(3, file_io.get_states_for_shard(&shard_id).unwrap().len());
 	}
 
+	#[test]
+	fn load_snapshot_returns_older_version_and_keeps_history() {
+		let shard_id = ShardIdentifier::random();
+		let (_, mut state_snapshot_repository) = create_state_snapshot_repository(&[shard_id], 6);
+
+		let state_hashes = [1u64, 2u64, 3u64]
+			.into_iter()
+			.map(TestState)
+			.map(|state| {
+				let state_hash = state.hash();
+				state_snapshot_repository.update(&shard_id, &state, state_hash).unwrap();
+				state_hash
+			})
+			.collect::<Vec<_>>();
+
+		let snapshot =
+			state_snapshot_repository.load_snapshot(&shard_id, &state_hashes[0]).unwrap();
+
+		assert_eq!(TestState(1u64), snapshot);
+		assert_eq!(4, state_snapshot_repository.snapshot_history.get(&shard_id).unwrap().len());
+		assert_eq!(TestState(3u64), state_snapshot_repository.load_latest(&shard_id).unwrap());
+		assert!(state_snapshot_repository
+			.load_snapshot(&shard_id, &TestState(4u64).hash())
+			.is_err());
+	}
+
 	#[test]
 	fn initializing_new_shard_works() {
 		let (_, mut state_snapshot_repository) = create_state_snapshot_repository(&[], 2);
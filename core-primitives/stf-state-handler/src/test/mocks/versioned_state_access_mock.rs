@@ -69,6 +69,14 @@ where
 		Ok(())
 	}
 
+	fn load_snapshot(
+		&self,
+		shard_identifier: &ShardIdentifier,
+		_state_hash: &Self::HashType,
+	) -> Result<Self::StateType> {
+		self.load_latest(shard_identifier)
+	}
+
 	fn revert_to(
 		&mut self,
 		shard_identifier: &ShardIdentifier,
@@ -0,0 +1,53 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Records which state keys are read while executing a closure.
+//!
+//! Writes do not need to be recorded here, they end up in the state diff anyway.
+
+use std::{collections::BTreeSet, vec::Vec};
+
+/// State accesses recorded by [`record_state_access`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateAccess {
+	/// Keys that have been read.
+	pub reads: BTreeSet<Vec<u8>>,
+	/// True if the state has been iterated over (e.g. `next_storage_key` or `clear_prefix`),
+	/// in which case the set of read keys is unbounded.
+	pub iterated: bool,
+}
+
+environmental::environmental!(state_access: StateAccess);
+
+/// Execute the given closure while recording all state reads going through the externalities.
+///
+/// The recording is only active for the same thread this function was called from.
+pub fn record_state_access<F: FnOnce() -> R, R>(f: F) -> (R, StateAccess) {
+	let mut access = StateAccess::default();
+	let result = state_access::using(&mut access, f);
+	(result, access)
+}
+
+pub(crate) fn record_read(key: &[u8]) {
+	state_access::with(|access| {
+		access.reads.insert(key.to_vec());
+	});
+}
+
+pub(crate) fn record_iteration() {
+	state_access::with(|access| access.iterated = true);
+}
@@ -22,13 +22,14 @@ compile_error!("feature \"std\" and feature \"sgx\" cannot be enabled at the sam
 extern crate sgx_tstd as std;
 
 use codec::{Decode, Encode, EncodeAppend};
-use core::ops::Bound;
+use core::{cmp::Ordering, ops::Bound};
 use derive_more::{Deref, DerefMut, From, IntoIterator};
 use itp_hashing::Hash;
 use serde::{Deserialize, Serialize};
 use sp_core::{hashing::blake2_256, H256};
 use std::{collections::BTreeMap, vec, vec::Vec};
 
+pub use access_recorder::{record_state_access, StateAccess};
 pub use scope_limited::{set_and_run_with_externalities, with_externalities};
 
 // Unfortunately we cannot use `serde_with::serde_as` to serialize our map (which would be very convenient)
@@ -37,6 +38,7 @@ pub use scope_limited::{set_and_run_with_externalities, with_externalities};
 // directly into this code base.
 //use serde_with::serde_as;
 
+mod access_recorder;
 mod codec_impl;
 mod scope_limited;
 // These are used to serialize a map with keys that are not string.
@@ -116,6 +118,12 @@ pub trait SgxExternalitiesTrait {
 	/// Prunes the state diff.
 	fn prune_state_diff(&mut self);
 
+	/// Entries in which the state differs from the state of `previous`, `None` for removed keys.
+	///
+	/// Applying them to `previous` yields the same state as `self`. Neither the state diffs
+	/// nor the accessed keys are taken into account.
+	fn changes_since(&self, previous: &Self) -> Vec<(Vec<u8>, Option<Vec<u8>>)>;
+
 	/// Execute the given closure while `self` is set as externalities.
 	///
 	/// Returns the result of the given closure.
@@ -148,6 +156,7 @@ where
 	}
 
 	fn append(&mut self, key: Vec<u8>, value: Vec<u8>) {
+		access_recorder::record_read(&key);
 		let current = self.state.entry(key.clone()).or_default();
 		let updated_value = StorageAppend::new(current).append(value);
 		self.state_diff.insert(key, Some(updated_value));
@@ -159,14 +168,17 @@ where
 	}
 
 	fn get(&self, key: &[u8]) -> Option<&Vec<u8>> {
+		access_recorder::record_read(key);
 		self.state.get(key)
 	}
 
 	fn contains_key(&self, key: &[u8]) -> bool {
+		access_recorder::record_read(key);
 		self.state.contains_key(key)
 	}
 
 	fn next_storage_key(&self, key: &[u8]) -> Option<Vec<u8>> {
+		access_recorder::record_iteration();
 		let range = (Bound::Excluded(key), Bound::Unbounded);
 		self.state.range::<[u8], _>(range).next().map(|(k, _v)| k.to_vec()) // directly return k as _v is never None in our case
 	}
@@ -175,7 +187,46 @@ where
 		self.state_diff.clear();
 	}
 
+	fn changes_since(&self, previous: &Self) -> Vec<(Vec<u8>, Option<Vec<u8>>)> {
+		let mut changes = Vec::new();
+		let mut current_entries = self.state.iter().peekable();
+		let mut previous_entries = previous.state.iter().peekable();
+		loop {
+			match (current_entries.peek(), previous_entries.peek()) {
+				(None, None) => break,
+				(Some((key, value)), None) => {
+					changes.push(((*key).clone(), Some((*value).clone())));
+					current_entries.next();
+				},
+				(None, Some((key, _))) => {
+					changes.push(((*key).clone(), None));
+					previous_entries.next();
+				},
+				(Some((key, value)), Some((previous_key, previous_value))) =>
+					match key.cmp(previous_key) {
+						Ordering::Less => {
+							changes.push(((*key).clone(), Some((*value).clone())));
+							current_entries.next();
+						},
+						Ordering::Greater => {
+							changes.push(((*previous_key).clone(), None));
+							previous_entries.next();
+						},
+						Ordering::Equal => {
+							if value != previous_value {
+								changes.push(((*key).clone(), Some((*value).clone())));
+							}
+							current_entries.next();
+							previous_entries.next();
+						},
+					},
+			}
+		}
+		changes
+	}
+
 	fn clear_prefix(&mut self, key_prefix: &[u8], _maybe_limit: Option<u32>) -> u32 {
+		access_recorder::record_iteration();
 		// Inspired by Substrate https://github.com/paritytech/substrate/blob/c8653447fc8ef8d95a92fe164c96dffb37919e85/primitives/state-machine/src/basic.rs#L242-L254
 		let to_remove = self
 			.state
@@ -384,4 +435,77 @@ pub mod tests {
 		});
 		assert!(stored_value.is_some());
 	}
+
+	#[test]
+	fn record_state_access_records_reads_but_not_writes() {
+		let mut ext = SgxExternalities::default();
+		ext.insert(b"existing".to_vec(), b"value".to_vec());
+		ext.prune_state_diff();
+
+		let (_, access) = record_state_access(|| {
+			ext.execute_with(|| {
+				with_externalities(|e| {
+					e.get(b"existing");
+					e.contains_key(b"missing");
+					e.insert(b"written".to_vec(), b"value".to_vec());
+				})
+				.unwrap()
+			})
+		});
+
+		let expected_reads: std::collections::BTreeSet<Vec<u8>> =
+			[b"existing".to_vec(), b"missing".to_vec()].into_iter().collect();
+		assert_eq!(access.reads, expected_reads);
+		assert!(!access.iterated);
+		assert_eq!(ext.state_diff.len(), 1);
+	}
+
+	#[test]
+	fn record_state_access_flags_iteration() {
+		let mut ext = SgxExternalities::default();
+
+		let (_, access) = record_state_access(|| ext.clear_prefix(b"house", None));
+
+		assert!(access.iterated);
+	}
+
+	#[test]
+	fn state_access_is_not_recorded_outside_of_recording_scope() {
+		let ext = SgxExternalities::default();
+		ext.get(b"key");
+
+		let (_, access) = record_state_access(|| ());
+
+		assert_eq!(access, StateAccess::default());
+	}
+
+	#[test]
+	fn changes_since_yields_the_entries_to_get_from_the_previous_state() {
+		let mut previous = SgxExternalities::default();
+		previous.insert(b"changed".to_vec(), b"old".to_vec());
+		previous.insert(b"removed".to_vec(), b"value".to_vec());
+		previous.insert(b"unchanged".to_vec(), b"value".to_vec());
+		let mut current = previous.clone();
+		current.insert(b"added".to_vec(), b"value".to_vec());
+		current.insert(b"changed".to_vec(), b"new".to_vec());
+		current.remove(b"removed");
+
+		let changes = current.changes_since(&previous);
+
+		assert_eq!(
+			changes,
+			vec![
+				(b"added".to_vec(), Some(b"value".to_vec())),
+				(b"changed".to_vec(), Some(b"new".to_vec())),
+				(b"removed".to_vec(), None),
+			]
+		);
+		for (key, value) in changes {
+			match value {
+				Some(value) => previous.insert(key, value),
+				None => previous.remove(&key),
+			};
+		}
+		assert_eq!(previous.state, current.state);
+	}
 }
@@ -0,0 +1,113 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Storage keys of the Teeracle pallet, used to read the on-chain feed configuration.
+
+use codec::Decode;
+#[cfg(test)]
+use codec::Encode;
+use itp_storage::{storage_map_key, storage_value_key, StorageHasher};
+use sp_std::prelude::Vec;
+
+/// Market data source, i.e. the base URL of the source (`BoundedVec<u8>` in the pallet).
+pub type DataSource = Vec<u8>;
+/// Trading pair in the form `CRYPTO/FIAT`, e.g. `DOT/USD` (`BoundedVec<u8>` in the pallet).
+pub type TradingPairString = Vec<u8>;
+pub type MrEnclave = [u8; 32];
+/// A trading pair reported by a specific data source.
+pub type ExchangeRateFeed = (TradingPairString, DataSource);
+
+/// Length of a storage map prefix: twox_128(pallet) ++ twox_128(storage).
+const PREFIX_LEN: usize = 32;
+/// Length of the hash part of a `Blake2_128Concat` hashed key.
+const BLAKE2_128_LEN: usize = 16;
+
+pub struct TeeracleStorage;
+
+pub trait StoragePrefix {
+	fn prefix() -> &'static str;
+}
+
+impl StoragePrefix for TeeracleStorage {
+	fn prefix() -> &'static str {
+		"Teeracle"
+	}
+}
+
+pub trait TeeracleStorageKeys {
+	/// Key of the MRENCLAVEs whitelisted for `data_source`.
+	fn whitelist(data_source: &DataSource) -> Vec<u8>;
+	/// Prefix of all whitelist entries.
+	fn whitelists_prefix() -> Vec<u8>;
+	/// Key of the trading pairs governance configured `data_source` to serve.
+	fn trading_pairs(data_source: &DataSource) -> Vec<u8>;
+}
+
+impl<S: StoragePrefix> TeeracleStorageKeys for S {
+	fn whitelist(data_source: &DataSource) -> Vec<u8> {
+		storage_map_key(Self::prefix(), "Whitelists", data_source, &StorageHasher::Blake2_128Concat)
+	}
+
+	fn whitelists_prefix() -> Vec<u8> {
+		storage_value_key(Self::prefix(), "Whitelists")
+	}
+
+	fn trading_pairs(data_source: &DataSource) -> Vec<u8> {
+		storage_map_key(
+			Self::prefix(),
+			"TradingPairs",
+			data_source,
+			&StorageHasher::Blake2_128Concat,
+		)
+	}
+}
+
+/// Recovers the data source from a full `Whitelists` storage key.
+pub fn decode_whitelist_key(key: &[u8]) -> Option<DataSource> {
+	let mut input = key.get(PREFIX_LEN..)?;
+	decode_blake2_128_concat(&mut input)
+}
+
+fn decode_blake2_128_concat<T: Decode>(input: &mut &[u8]) -> Option<T> {
+	*input = input.get(BLAKE2_128_LEN..)?;
+	T::decode(input).ok()
+}
+
+/// Encoded form of a trading pair, as configured in `TradingPairs`.
+pub fn encode_trading_pair(crypto_currency: &str, fiat_currency: &str) -> TradingPairString {
+	let mut trading_pair = crypto_currency.as_bytes().to_vec();
+	trading_pair.push(b'/');
+	trading_pair.extend_from_slice(fiat_currency.as_bytes());
+	trading_pair
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn whitelist_key_round_trips() {
+		let data_source = b"https://api.coingecko.com/".to_vec();
+
+		let key = TeeracleStorage::whitelist(&data_source);
+
+		assert!(key.starts_with(&TeeracleStorage::whitelists_prefix()));
+		assert_eq!(decode_whitelist_key(&key), Some(data_source));
+	}
+
+	#[test]
+	fn trading_pairs_key_is_not_a_whitelist_key() {
+		let data_source = b"https://api.coingecko.com/".to_vec();
+
+		let key = TeeracleStorage::trading_pairs(&data_source);
+
+		assert!(!key.starts_with(&TeeracleStorage::whitelists_prefix()));
+		assert!(key.ends_with(&data_source.encode()));
+	}
+
+	#[test]
+	fn decoding_truncated_key_fails() {
+		let key = TeeracleStorage::whitelist(&b"https://api.coingecko.com/".to_vec());
+
+		assert_eq!(decode_whitelist_key(&key[..40]), None);
+	}
+}
@@ -36,11 +36,13 @@ use std::{collections::HashMap, format, vec::Vec};
 #[derive(Default)]
 pub struct HandleStateMock {
 	state_map: RwLock<HashMap<ShardIdentifier, StfState>>,
+	/// All states ever written, oldest first, to support `load_snapshot` and `revert_to`.
+	state_history: RwLock<HashMap<ShardIdentifier, Vec<StfState>>>,
 }
 
 impl HandleStateMock {
 	pub fn from_shard(shard: ShardIdentifier) -> Result<Self> {
-		let state_handler = HandleStateMock { state_map: Default::default() };
+		let state_handler = HandleStateMock::default();
 		state_handler.initialize_shard(shard)?;
 		Ok(state_handler)
 	}
@@ -96,6 +98,12 @@ impl HandleState for HandleStateMock {
 		shard: &ShardIdentifier,
 	) -> Result<Self::HashType> {
 		state_lock.insert(*shard, state.clone());
+		self.state_history
+			.write()
+			.unwrap()
+			.entry(*shard)
+			.or_default()
+			.push(state.clone());
 		Ok(state.hash())
 	}
 
@@ -103,6 +111,23 @@ impl HandleState for HandleStateMock {
 		let write_lock = self.state_map.write().unwrap();
 		self.write_after_mutation(state, write_lock, shard)
 	}
+
+	fn load_snapshot(
+		&self,
+		shard: &ShardIdentifier,
+		state_hash: &Self::HashType,
+	) -> Result<Self::StateT> {
+		self.state_history
+			.read()
+			.unwrap()
+			.get(shard)
+			.ok_or_else(|| Error::Other(format!("shard is not initialized {:?}", shard).into()))?
+			.iter()
+			.rev()
+			.find(|state| state.hash() == *state_hash)
+			.cloned()
+			.ok_or_else(|| Error::Other(format!("no state with hash {:?}", state_hash).into()))
+	}
 }
 
 impl QueryShardState for HandleStateMock {
@@ -26,7 +26,8 @@ use itp_ocall_api::{
 use itp_storage::Error::StorageValueUnavailable;
 use itp_types::{
 	parentchain::ParentchainId, storage::StorageEntryVerified, AccountId, BlockHash,
-	EnclaveFingerprint, ShardIdentifier, ShardSignerStatus, WorkerRequest, WorkerResponse,
+	EnclaveFingerprint, ShardIdentifier, ShardSignerStatus, SidechainBlockConfirmation,
+	WorkerRequest, WorkerResponse,
 };
 use sgx_types::*;
 use sp_core::H256;
@@ -167,6 +168,14 @@ impl EnclaveSidechainOCallApi for OnchainMock {
 	) -> SgxResult<Vec<SignedSidechainBlock>> {
 		Ok(Vec::new())
 	}
+
+	fn finalize_sidechain_block(
+		&self,
+		_shard_identifier: ShardIdentifier,
+		_finalized_block: SidechainBlockConfirmation,
+	) -> SgxResult<()> {
+		Ok(())
+	}
 }
 
 impl EnclaveMetricsOCallApi for OnchainMock {
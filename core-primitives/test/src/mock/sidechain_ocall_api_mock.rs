@@ -24,7 +24,7 @@ use std::sync::RwLock;
 use codec::{Decode, Encode};
 use core::marker::PhantomData;
 use itp_ocall_api::EnclaveSidechainOCallApi;
-use itp_types::{BlockHash, ShardIdentifier};
+use itp_types::{BlockHash, ShardIdentifier, SidechainBlockConfirmation};
 use sgx_types::{sgx_status_t, SgxResult};
 use std::vec::Vec;
 
@@ -107,4 +107,12 @@ where
 			None => Err(sgx_status_t::SGX_ERROR_UNEXPECTED),
 		}
 	}
+
+	fn finalize_sidechain_block(
+		&self,
+		_shard_identifier: ShardIdentifier,
+		_finalized_block: SidechainBlockConfirmation,
+	) -> SgxResult<()> {
+		Ok(())
+	}
 }
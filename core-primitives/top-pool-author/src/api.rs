@@ -20,7 +20,10 @@
 #[cfg(all(not(feature = "std"), feature = "sgx"))]
 use crate::sgx_reexport_prelude::*;
 
-use crate::error;
+use crate::{
+	error,
+	priority::{PrioritizeOperation, StfTipPriority},
+};
 use codec::Encode;
 use ita_stf::{Getter, TrustedCallSigned, TrustedOperation as StfTrustedOperation};
 use itp_stf_primitives::types::ShardIdentifier;
@@ -44,22 +47,43 @@ use std::{boxed::Box, marker::PhantomData, pin::Pin, vec, vec::Vec};
 pub type Result<T> = core::result::Result<T, ()>;
 
 /// The operation pool logic for full client.
-pub struct SidechainApi<Block> {
+pub struct SidechainApi<Block, PriorityProvider = StfTipPriority> {
+	priority_provider: PriorityProvider,
 	_marker: PhantomData<Block>,
 }
 
 impl<Block> SidechainApi<Block> {
-	/// Create new operation pool logic.
+	/// Create new operation pool logic, prioritizing operations by their STF defined tip.
 	pub fn new() -> Self {
-		SidechainApi { _marker: Default::default() }
+		Self::with_priority_provider(StfTipPriority)
+	}
+}
+
+impl<Block, PriorityProvider> SidechainApi<Block, PriorityProvider>
+where
+	PriorityProvider: PrioritizeOperation,
+{
+	/// Create new operation pool logic with a custom priority provider.
+	pub fn with_priority_provider(priority_provider: PriorityProvider) -> Self {
+		SidechainApi { priority_provider, _marker: Default::default() }
 	}
 
-	fn validate_trusted_call(trusted_call_signed: TrustedCallSigned) -> ValidTransaction {
+	fn validate_trusted_call(
+		&self,
+		operation: &StfTrustedOperation,
+		trusted_call_signed: &TrustedCallSigned,
+	) -> ValidTransaction {
 		let from = trusted_call_signed.call.sender_account();
 		let requires = vec![];
 		let provides = vec![(from, trusted_call_signed.nonce).encode()];
 
-		ValidTransaction { priority: 1 << 20, requires, provides, longevity: 64, propagate: true }
+		ValidTransaction {
+			priority: self.priority_provider.priority_of(operation),
+			requires,
+			provides,
+			longevity: 64,
+			propagate: true,
+		}
 	}
 }
 
@@ -69,9 +93,10 @@ impl<Block> Default for SidechainApi<Block> {
 	}
 }
 
-impl<Block> ChainApi for SidechainApi<Block>
+impl<Block, PriorityProvider> ChainApi for SidechainApi<Block, PriorityProvider>
 where
 	Block: BlockT,
+	PriorityProvider: PrioritizeOperation + Send + Sync,
 {
 	type Block = Block;
 	type Error = error::Error;
@@ -85,18 +110,18 @@ where
 		uxt: StfTrustedOperation,
 		_shard: ShardIdentifier,
 	) -> Self::ValidationFuture {
-		let operation = match uxt {
+		let operation = match &uxt {
 			StfTrustedOperation::direct_call(signed_call) =>
-				Self::validate_trusted_call(signed_call),
+				self.validate_trusted_call(&uxt, signed_call),
 			StfTrustedOperation::indirect_call(signed_call) =>
-				Self::validate_trusted_call(signed_call),
+				self.validate_trusted_call(&uxt, signed_call),
 			StfTrustedOperation::get(getter) => match getter {
 				Getter::public(_) =>
 					return Box::pin(ready(Ok(Err(TransactionValidityError::Unknown(
 						UnknownTransaction::CannotLookup,
 					))))),
 				Getter::trusted(trusted_getter) => ValidTransaction {
-					priority: 1 << 20,
+					priority: self.priority_provider.priority_of(&uxt),
 					requires: vec![],
 					provides: vec![trusted_getter.signature.encode()],
 					longevity: 64,
@@ -184,6 +209,31 @@ mod tests {
 		assert!(validation.is_err());
 	}
 
+	#[test]
+	fn validated_priority_is_taken_from_priority_provider() {
+		let chain_api =
+			SidechainApi::<ParentchainBlock, _>::with_priority_provider(ConstantPriority(42));
+		let operation = create_indirect_trusted_operation();
+
+		let validation = executor::block_on(chain_api.validate_transaction(
+			TrustedOperationSource::Local,
+			operation,
+			ShardIdentifier::default(),
+		))
+		.unwrap()
+		.unwrap();
+
+		assert_eq!(validation.priority, 42);
+	}
+
+	struct ConstantPriority(u64);
+
+	impl PrioritizeOperation for ConstantPriority {
+		fn priority_of(&self, _operation: &TrustedOperation) -> u64 {
+			self.0
+		}
+	}
+
 	fn create_indirect_trusted_operation() -> TrustedOperation {
 		let trusted_call_signed = TrustedCall::balance_transfer(
 			AccountKeyring::Alice.public().into(),
@@ -40,7 +40,10 @@ use itp_top_pool::{
 };
 use itp_types::{BlockHash as SidechainBlockHash, ShardIdentifier};
 use jsonrpc_core::{
-	futures::future::{ready, TryFutureExt},
+	futures::{
+		executor::block_on,
+		future::{ready, TryFutureExt},
+	},
 	Error as RpcError,
 };
 use log::*;
@@ -65,6 +68,12 @@ pub type AuthorTopFilter = crate::top_filter::CallsOnlyFilter;
 /// some unique operations via RPC and have them included in the pool.
 const TX_SOURCE: TrustedOperationSource = TrustedOperationSource::External;
 
+/// Default maximum number of direct calls a single sender can have pending in the pool of a shard.
+///
+/// Ensures fairness among senders: a single account cannot crowd out everybody else
+/// during load spikes. Indirect calls are not limited, they have been paid for on the parentchain.
+pub const DEFAULT_MAX_PENDING_CALLS_PER_SENDER: usize = 64;
+
 /// Authoring API for RPC calls
 ///
 ///
@@ -81,6 +90,7 @@ where
 	state_facade: Arc<StateFacade>,
 	shielding_key_repo: Arc<ShieldingKeyRepository>,
 	ocall_api: Arc<OCallApi>,
+	max_pending_calls_per_sender: usize,
 }
 
 impl<TopPool, TopFilter, StateFacade, ShieldingKeyRepository, OCallApi>
@@ -101,7 +111,20 @@ where
 		encryption_key: Arc<ShieldingKeyRepository>,
 		ocall_api: Arc<OCallApi>,
 	) -> Self {
-		Author { top_pool, top_filter, state_facade, shielding_key_repo: encryption_key, ocall_api }
+		Author {
+			top_pool,
+			top_filter,
+			state_facade,
+			shielding_key_repo: encryption_key,
+			ocall_api,
+			max_pending_calls_per_sender: DEFAULT_MAX_PENDING_CALLS_PER_SENDER,
+		}
+	}
+
+	/// Set the maximum number of direct calls a single sender can have pending per shard.
+	pub fn with_max_pending_calls_per_sender(mut self, max_pending_calls: usize) -> Self {
+		self.max_pending_calls_per_sender = max_pending_calls;
+		self
 	}
 }
 
@@ -126,15 +149,6 @@ where
 		shard: ShardIdentifier,
 		submission_mode: TopSubmissionMode,
 	) -> PoolFuture<TxHash<TopPool>, RpcError> {
-		// check if shard exists
-		match self.state_facade.shard_exists(&shard) {
-			Err(_) => return Box::pin(ready(Err(ClientError::InvalidShard.into()))),
-			Ok(shard_exists) =>
-				if !shard_exists {
-					return Box::pin(ready(Err(ClientError::InvalidShard.into())))
-				},
-		};
-
 		// decrypt call
 		let shielding_key = match self.shielding_key_repo.retrieve_key() {
 			Ok(k) => k,
@@ -150,12 +164,40 @@ where
 			Err(_) => return Box::pin(ready(Err(ClientError::BadFormat.into()))),
 		};
 
+		self.process_trusted_operation(trusted_operation, shard, submission_mode)
+	}
+
+	fn process_trusted_operation(
+		&self,
+		trusted_operation: TrustedOperation,
+		shard: ShardIdentifier,
+		submission_mode: TopSubmissionMode,
+	) -> PoolFuture<TxHash<TopPool>, RpcError> {
+		// check if shard exists
+		match self.state_facade.shard_exists(&shard) {
+			Err(_) => return Box::pin(ready(Err(ClientError::InvalidShard.into()))),
+			Ok(shard_exists) =>
+				if !shard_exists {
+					return Box::pin(ready(Err(ClientError::InvalidShard.into())))
+				},
+		};
+
 		// apply top filter - return error if this specific type of trusted operation
 		// is not allowed by the filter
 		if !self.top_filter.filter(&trusted_operation) {
 			return Box::pin(ready(Err(ClientError::UnsupportedOperation.into())))
 		}
 
+		// enforce per-sender fairness limit on direct calls
+		if let TrustedOperation::direct_call(ref trusted_call_signed) = trusted_operation {
+			let sender = trusted_call_signed.call.sender_account();
+			if self.get_pending_trusted_calls_for(shard, sender).len()
+				>= self.max_pending_calls_per_sender
+			{
+				return Box::pin(ready(Err(ClientError::TooManyPendingCalls.into())))
+			}
+		}
+
 		//let best_block_hash = self.client.info().best_hash;
 		// dummy block hash
 		let best_block_hash = Default::default();
@@ -274,6 +316,14 @@ where
 		self.process_top(ext, shard, TopSubmissionMode::Submit)
 	}
 
+	fn submit_trusted_operation(
+		&self,
+		operation: TrustedOperation,
+		shard: ShardIdentifier,
+	) -> PoolFuture<TxHash<TopPool>, RpcError> {
+		self.process_trusted_operation(operation, shard, TopSubmissionMode::Submit)
+	}
+
 	/// Get hash of TrustedOperation
 	fn hash_of(&self, xt: &TrustedOperation) -> TxHash<TopPool> {
 		self.top_pool.hash_of(xt)
@@ -347,6 +397,39 @@ where
 	) -> PoolFuture<TxHash<TopPool>, RpcError> {
 		self.process_top(ext, shard, TopSubmissionMode::SubmitWatch)
 	}
+
+	fn resubmit_operations(&self, shard: ShardIdentifier, operations: Vec<TrustedOperation>) {
+		let results = match block_on(self.top_pool.submit_at(
+			&generic::BlockId::hash(Default::default()),
+			TX_SOURCE,
+			operations,
+			shard,
+		)) {
+			Ok(results) => results,
+			Err(e) => {
+				warn!("Failed to resubmit trusted operations: {:?}", map_top_error::<TopPool>(e));
+				return
+			},
+		};
+
+		for result in results {
+			match result {
+				Ok(hash) => {
+					debug!("Resubmitted trusted operation {:?} to TOP pool", hash);
+					if let Err(e) =
+						self.ocall_api.update_metric(EnclaveMetric::TopPoolSizeIncrement)
+					{
+						warn!("Failed to update metric for top pool size: {:?}", e);
+					}
+				},
+				// Operations that became invalid in the meantime are dropped.
+				Err(e) => debug!(
+					"Could not resubmit trusted operation: {:?}",
+					map_top_error::<TopPool>(e)
+				),
+			}
+		}
+	}
 }
 
 impl<TopPool, TopFilter, StateFacade, ShieldingKeyRepository, OCallApi> OnBlockImported
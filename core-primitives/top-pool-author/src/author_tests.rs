@@ -17,6 +17,7 @@
 
 use crate::{
 	author::Author,
+	client_error::Error as ClientError,
 	test_fixtures::{
 		create_indirect_trusted_operation, shard_id, trusted_call_signed, trusted_getter_signed,
 	},
@@ -123,6 +124,24 @@ fn submitting_indirect_call_works() {
 	assert_eq!(1, author.get_pending_trusted_calls(shard_id()).len());
 }
 
+#[test]
+fn submitting_direct_calls_beyond_sender_limit_returns_error() {
+	let (author, top_pool, shielding_key) = create_author_with_filter(AllowAllTopsFilter);
+	let author = author.with_max_pending_calls_per_sender(1);
+	let first_operation = TrustedOperation::direct_call(trusted_call_signed());
+	let mut second_call = trusted_call_signed();
+	second_call.nonce += 1;
+	let second_operation = TrustedOperation::direct_call(second_call);
+
+	submit_operation_to_top_pool(&author, &first_operation, &shielding_key, shard_id()).unwrap();
+	let submit_response =
+		submit_operation_to_top_pool(&author, &second_operation, &shielding_key, shard_id());
+
+	assert_eq!(submit_response, Err(ClientError::TooManyPendingCalls.into()));
+	let submitted = top_pool.get_last_submitted_transactions();
+	assert_eq!(submitted.get(&shard_id()).unwrap().xts, vec![first_operation]);
+}
+
 fn create_author_with_filter<F: Filter<Value = TrustedOperation>>(
 	filter: F,
 ) -> (TestAuthor<F>, Arc<TrustedOperationPoolMock>, ShieldingCryptoMock) {
@@ -68,6 +68,9 @@ pub enum Error {
 	/// Unsupported trusted operation (in case we allow only certain types of operations, using filters)
 	#[display(fmt = "Unsupported operation type")]
 	UnsupportedOperation,
+	/// Sender has reached the maximum number of pending trusted calls.
+	#[display(fmt = "Too many pending trusted calls of sender")]
+	TooManyPendingCalls,
 }
 
 impl std::error::Error for Error {
@@ -104,6 +107,8 @@ const POOL_CYCLE_DETECTED: i64 = POOL_INVALID_TX + 5;
 const POOL_IMMEDIATELY_DROPPED: i64 = POOL_INVALID_TX + 6;
 /// The key type crypto is not known.
 const UNSUPPORTED_KEY_TYPE: i64 = POOL_INVALID_TX + 7;
+/// The sender has reached the limit of pending operations in the pool.
+const POOL_SENDER_LIMIT_REACHED: i64 = POOL_INVALID_TX + 8;
 
 impl From<Error> for rpc_core::Error {
 	fn from(e: Error) -> Self {
@@ -165,6 +170,11 @@ impl From<Error> for rpc_core::Error {
 				message: "Immediately Dropped".into(),
 				data: Some("The Trusted Operation couldn't enter the pool because of the limit".into()),
 			},
+			Error::TooManyPendingCalls => rpc_core::Error {
+				code: rpc_core::ErrorCode::ServerError(POOL_SENDER_LIMIT_REACHED),
+				message: "Too many pending trusted calls".into(),
+				data: Some("The sender has reached the limit of pending trusted calls in the pool, retry once some of them have been executed.".into()),
+			},
 			Error::UnsupportedKeyType => rpc_core::Error {
 				code: rpc_core::ErrorCode::ServerError(UNSUPPORTED_KEY_TYPE),
 				message: "Unknown key type crypto" .into(),
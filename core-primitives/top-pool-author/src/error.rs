@@ -65,6 +65,9 @@ pub enum Error {
 
 	#[display(fmt = "Codec error: {}", _0)]
 	CodecError(codec::Error),
+
+	#[display(fmt = "IO error: {}", _0)]
+	Io(std::io::Error),
 }
 
 impl error::Error for Error {
@@ -36,6 +36,8 @@ pub mod api;
 pub mod author;
 pub mod client_error;
 pub mod error;
+pub mod persistence;
+pub mod priority;
 pub mod top_filter;
 pub mod traits;
 
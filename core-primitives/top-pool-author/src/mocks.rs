@@ -28,7 +28,7 @@ use crate::{
 	error::Result,
 	traits::{AuthorApi, OnBlockImported},
 };
-use codec::Decode;
+use codec::{Decode, Encode};
 use ita_stf::{
 	hash::{Hash, TrustedOperationOrHash},
 	Getter, TrustedGetterSigned, TrustedOperation,
@@ -98,6 +98,18 @@ impl AuthorApi<H256, H256> for AuthorApiMock<H256, H256> {
 		Box::pin(ready(Ok(H256::default())))
 	}
 
+	fn submit_trusted_operation(
+		&self,
+		operation: TrustedOperation,
+		shard: ShardIdentifier,
+	) -> PoolFuture<H256, RpcError> {
+		let hash = operation.hash();
+		let mut write_lock = self.tops.write().unwrap();
+		let extrinsics = write_lock.entry(shard).or_default();
+		extrinsics.push(operation.encode());
+		Box::pin(ready(Ok(hash)))
+	}
+
 	fn hash_of(&self, xt: &TrustedOperation) -> H256 {
 		xt.hash()
 	}
@@ -193,6 +205,12 @@ impl AuthorApi<H256, H256> for AuthorApiMock<H256, H256> {
 	fn watch_top(&self, _ext: Vec<u8>, _shard: ShardIdentifier) -> PoolFuture<H256, RpcError> {
 		todo!()
 	}
+
+	fn resubmit_operations(&self, shard: ShardIdentifier, operations: Vec<TrustedOperation>) {
+		let mut write_lock = self.tops.write().unwrap();
+		let extrinsics = write_lock.entry(shard).or_default();
+		extrinsics.extend(operations.iter().map(|operation| operation.encode()));
+	}
 }
 
 impl OnBlockImported for AuthorApiMock<H256, H256> {
@@ -0,0 +1,418 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Persistence of the trusted operation pool across enclave restarts.
+//!
+//! Pending operations are sealed to disk and restored on startup. Restored calls are
+//! revalidated against the current account nonces and submitted through the author, like
+//! any other operation. The connections of their watchers did not survive the restart, so the
+//! final status of the operations that could not be restored is kept for clients to query.
+
+#[cfg(all(not(feature = "std"), feature = "sgx"))]
+use crate::sgx_reexport_prelude::*;
+
+#[cfg(feature = "sgx")]
+use std::sync::SgxRwLock as RwLock;
+
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+
+use crate::{error::Result, traits::AuthorApi};
+use codec::{Decode, Encode};
+use ita_stf::{Index, TrustedOperation};
+use itp_sgx_io::SealedIO;
+use itp_stf_interface::system_pallet::SystemPalletAccountInterface;
+use itp_stf_primitives::types::AccountId;
+use itp_stf_state_observer::traits::ObserveState;
+use itp_top_pool::primitives::{BlockHash, InPoolOperation, TrustedOperationPool, TxHash};
+use itp_types::{ShardIdentifier, TrustedOperationStatus};
+use jsonrpc_core::futures::executor::block_on;
+use log::*;
+use std::{collections::HashMap, marker::PhantomData, sync::Arc, vec::Vec};
+
+/// Pending trusted operations of all shards, as they are sealed to disk.
+#[derive(Encode, Decode, Default, Debug, Clone, PartialEq)]
+pub struct PersistedTopPool {
+	pub shards: Vec<(ShardIdentifier, Vec<TrustedOperation>)>,
+}
+
+impl PersistedTopPool {
+	pub fn number_of_operations(&self) -> usize {
+		self.shards.iter().map(|(_, operations)| operations.len()).sum()
+	}
+}
+
+/// Seal and restore the pending operations of the trusted operation pool.
+pub trait PersistTopPool {
+	type Hash;
+
+	/// Seal all pending operations, returns the number of sealed operations.
+	fn seal_pending_operations(&self) -> Result<usize>;
+
+	/// Restore the sealed operations into the pool, returns the number of restored operations.
+	fn restore_pending_operations(&self) -> Result<usize>;
+
+	/// Final status of a sealed operation that could not be restored, if any.
+	fn status_of_unrestored_operation(&self, hash: &Self::Hash) -> Option<TrustedOperationStatus>;
+}
+
+pub struct TopPoolPersistence<TopPool, Author, Seal, StateObserver, Stf>
+where
+	TopPool: TrustedOperationPool,
+{
+	top_pool: Arc<TopPool>,
+	author: Arc<Author>,
+	seal: Seal,
+	state_observer: Arc<StateObserver>,
+	unrestored_operations: RwLock<HashMap<TxHash<TopPool>, TrustedOperationStatus>>,
+	_phantom: PhantomData<Stf>,
+}
+
+impl<TopPool, Author, Seal, StateObserver, Stf>
+	TopPoolPersistence<TopPool, Author, Seal, StateObserver, Stf>
+where
+	TopPool: TrustedOperationPool,
+	Author: AuthorApi<TxHash<TopPool>, BlockHash<TopPool>>,
+	StateObserver: ObserveState,
+	Stf: SystemPalletAccountInterface<StateObserver::StateType, AccountId, Index = Index>,
+{
+	pub fn new(
+		top_pool: Arc<TopPool>,
+		author: Arc<Author>,
+		seal: Seal,
+		state_observer: Arc<StateObserver>,
+	) -> Self {
+		TopPoolPersistence {
+			top_pool,
+			author,
+			seal,
+			state_observer,
+			unrestored_operations: Default::default(),
+			_phantom: Default::default(),
+		}
+	}
+
+	/// Revalidate a sealed operation. Returns the final status of the operation,
+	/// if it cannot be restored into the pool.
+	fn revalidate(
+		&self,
+		shard: &ShardIdentifier,
+		operation: &TrustedOperation,
+	) -> Option<TrustedOperationStatus> {
+		// The response to a getter goes to the connection it was submitted on,
+		// which did not survive the restart.
+		let trusted_call_signed = match operation.to_call() {
+			Some(call) => call,
+			None => return Some(TrustedOperationStatus::Dropped),
+		};
+
+		let sender = trusted_call_signed.call.sender_account();
+		match self
+			.state_observer
+			.observe_state(shard, |state| Stf::get_account_nonce(state, sender))
+		{
+			Ok(nonce) if trusted_call_signed.nonce < nonce => Some(TrustedOperationStatus::Invalid),
+			Ok(_) => None,
+			Err(e) => {
+				warn!("Failed to get nonce of shard {:?} to revalidate operation: {:?}", shard, e);
+				Some(TrustedOperationStatus::Dropped)
+			},
+		}
+	}
+
+	fn record_unrestored(&self, hash: TxHash<TopPool>, status: TrustedOperationStatus) {
+		match self.unrestored_operations.write() {
+			Ok(mut unrestored) => {
+				unrestored.insert(hash, status);
+			},
+			Err(e) => error!("Failed to record status of unrestored operation: {:?}", e),
+		}
+	}
+}
+
+impl<TopPool, Author, Seal, StateObserver, Stf> PersistTopPool
+	for TopPoolPersistence<TopPool, Author, Seal, StateObserver, Stf>
+where
+	TopPool: TrustedOperationPool,
+	Author: AuthorApi<TxHash<TopPool>, BlockHash<TopPool>>,
+	Seal: SealedIO<Unsealed = PersistedTopPool>,
+	crate::error::Error: From<Seal::Error>,
+	StateObserver: ObserveState,
+	Stf: SystemPalletAccountInterface<StateObserver::StateType, AccountId, Index = Index>,
+{
+	type Hash = TxHash<TopPool>;
+
+	fn seal_pending_operations(&self) -> Result<usize> {
+		let persisted_pool = PersistedTopPool {
+			shards: self
+				.top_pool
+				.shards()
+				.into_iter()
+				.map(|shard| {
+					let mut operations: Vec<TrustedOperation> =
+						self.top_pool.ready(shard).map(|op| op.data().clone()).collect();
+					operations.append(&mut self.top_pool.futures(shard));
+					(shard, operations)
+				})
+				.collect(),
+		};
+
+		self.seal.seal(&persisted_pool)?;
+
+		let number_of_operations = persisted_pool.number_of_operations();
+		debug!("Sealed {} pending trusted operations", number_of_operations);
+		Ok(number_of_operations)
+	}
+
+	fn restore_pending_operations(&self) -> Result<usize> {
+		let persisted_pool = self.seal.unseal()?;
+
+		let mut restored = 0usize;
+		for (shard, operations) in persisted_pool.shards {
+			for operation in operations {
+				let hash = self.author.hash_of(&operation);
+
+				if let Some(status) = self.revalidate(&shard, &operation) {
+					info!("Not restoring trusted operation {:?}: {:?}", hash, status);
+					self.record_unrestored(hash, status);
+					continue
+				}
+
+				match block_on(self.author.submit_trusted_operation(operation, shard)) {
+					Ok(_) => restored += 1,
+					Err(e) => {
+						warn!("Failed to restore trusted operation {:?}: {:?}", hash, e);
+						self.record_unrestored(hash, TrustedOperationStatus::Dropped);
+					},
+				}
+			}
+		}
+
+		info!("Restored {} sealed trusted operations", restored);
+		Ok(restored)
+	}
+
+	fn status_of_unrestored_operation(&self, hash: &Self::Hash) -> Option<TrustedOperationStatus> {
+		self.unrestored_operations.read().ok()?.get(hash).cloned()
+	}
+}
+
+#[cfg(feature = "sgx")]
+pub use sgx::*;
+
+#[cfg(feature = "sgx")]
+pub mod sgx {
+	use super::*;
+	use crate::error::Error;
+	use itp_sgx_io::{seal, unseal};
+	use std::path::PathBuf;
+
+	/// Seals the pending operations to a file.
+	#[derive(Clone, Debug)]
+	pub struct TopPoolSeal {
+		path: PathBuf,
+	}
+
+	impl TopPoolSeal {
+		pub fn new(path: PathBuf) -> Self {
+			Self { path }
+		}
+	}
+
+	impl SealedIO for TopPoolSeal {
+		type Error = Error;
+		type Unsealed = PersistedTopPool;
+
+		/// Returns an empty pool if nothing has been sealed yet.
+		fn unseal(&self) -> Result<Self::Unsealed> {
+			if !self.path.exists() {
+				info!("No sealed trusted operations found at {}", self.path.display());
+				return Ok(PersistedTopPool::default())
+			}
+			let bytes = unseal(&self.path)?;
+			Ok(Decode::decode(&mut bytes.as_slice())?)
+		}
+
+		fn seal(&self, unsealed: &Self::Unsealed) -> Result<()> {
+			Ok(seal(&unsealed.encode(), &self.path)?)
+		}
+	}
+}
+
+#[cfg(test)]
+pub mod tests {
+	use super::*;
+	use crate::{
+		author::Author,
+		error::Error,
+		test_fixtures::{create_indirect_trusted_operation, shard_id, trusted_getter_signed},
+		top_filter::{AllowAllTopsFilter, Filter, GettersOnlyFilter},
+	};
+	use itp_sgx_crypto::mocks::KeyRepositoryMock;
+	use itp_stf_state_handler::handle_state::HandleState;
+	use itp_stf_state_observer::mock::ObserveStateMock;
+	use itp_test::mock::{
+		handle_state_mock::HandleStateMock, metrics_ocall_mock::MetricsOCallMock,
+		shielding_crypto_mock::ShieldingCryptoMock,
+	};
+	use itp_top_pool::{
+		mocks::trusted_operation_pool_mock::TrustedOperationPoolMock,
+		primitives::TrustedOperationSource,
+	};
+	use sp_runtime::generic::BlockId;
+	use std::sync::RwLock;
+
+	type TestAuthor<F> = Author<
+		TrustedOperationPoolMock,
+		F,
+		HandleStateMock,
+		KeyRepositoryMock<ShieldingCryptoMock>,
+		MetricsOCallMock,
+	>;
+
+	type TestPersistence<F> = TopPoolPersistence<
+		TrustedOperationPoolMock,
+		TestAuthor<F>,
+		TopPoolSealMock,
+		ObserveStateMock<Index>,
+		NonceStfMock,
+	>;
+
+	#[test]
+	fn sealed_operations_are_restored() {
+		let sealing_pool = Arc::new(TrustedOperationPoolMock::default());
+		let operation = create_indirect_trusted_operation();
+		block_on(sealing_pool.submit_one(
+			&BlockId::number(0),
+			TrustedOperationSource::External,
+			operation.clone(),
+			shard_id(),
+		))
+		.unwrap();
+
+		let seal = TopPoolSealMock::default();
+		let persistence = create_persistence(sealing_pool, AllowAllTopsFilter, seal.clone(), 0);
+		assert_eq!(1, persistence.seal_pending_operations().unwrap());
+
+		let restored_pool = Arc::new(TrustedOperationPoolMock::default());
+		let persistence = create_persistence(restored_pool.clone(), AllowAllTopsFilter, seal, 0);
+		assert_eq!(1, persistence.restore_pending_operations().unwrap());
+
+		let restored = restored_pool.get_last_submitted_transactions();
+		assert_eq!(vec![operation.clone()], restored.get(&shard_id()).unwrap().xts);
+		assert_eq!(
+			None,
+			persistence.status_of_unrestored_operation(&restored_pool.hash_of(&operation))
+		);
+	}
+
+	#[test]
+	fn outdated_calls_and_getters_are_not_restored() {
+		let call = create_indirect_trusted_operation();
+		let getter = TrustedOperation::get(trusted_getter_signed());
+		let seal = seal_operations(vec![call.clone(), getter.clone()]);
+
+		// The call has nonce 1, which has already been used in the current state.
+		let top_pool = Arc::new(TrustedOperationPoolMock::default());
+		let persistence = create_persistence(top_pool.clone(), AllowAllTopsFilter, seal, 2);
+
+		assert_eq!(0, persistence.restore_pending_operations().unwrap());
+		assert!(top_pool.get_last_submitted_transactions().is_empty());
+		assert_eq!(
+			Some(TrustedOperationStatus::Invalid),
+			persistence.status_of_unrestored_operation(&top_pool.hash_of(&call))
+		);
+		assert_eq!(
+			Some(TrustedOperationStatus::Dropped),
+			persistence.status_of_unrestored_operation(&top_pool.hash_of(&getter))
+		);
+	}
+
+	#[test]
+	fn restored_operations_are_subject_to_the_top_filter() {
+		let call = create_indirect_trusted_operation();
+		let seal = seal_operations(vec![call.clone()]);
+
+		let top_pool = Arc::new(TrustedOperationPoolMock::default());
+		let persistence = create_persistence(top_pool.clone(), GettersOnlyFilter, seal, 0);
+
+		assert_eq!(0, persistence.restore_pending_operations().unwrap());
+		assert!(top_pool.get_last_submitted_transactions().is_empty());
+		assert_eq!(
+			Some(TrustedOperationStatus::Dropped),
+			persistence.status_of_unrestored_operation(&top_pool.hash_of(&call))
+		);
+	}
+
+	fn seal_operations(operations: Vec<TrustedOperation>) -> TopPoolSealMock {
+		let seal = TopPoolSealMock::default();
+		seal.seal(&PersistedTopPool { shards: vec![(shard_id(), operations)] }).unwrap();
+		seal
+	}
+
+	fn create_persistence<F: Filter<Value = TrustedOperation>>(
+		top_pool: Arc<TrustedOperationPoolMock>,
+		filter: F,
+		seal: TopPoolSealMock,
+		account_nonce: Index,
+	) -> TestPersistence<F> {
+		let state_facade = HandleStateMock::from_shard(shard_id()).unwrap();
+		state_facade.load_cloned(&shard_id()).unwrap();
+		let author = Arc::new(Author::new(
+			top_pool.clone(),
+			filter,
+			Arc::new(state_facade),
+			Arc::new(KeyRepositoryMock::new(ShieldingCryptoMock::default())),
+			Arc::new(MetricsOCallMock::default()),
+		));
+
+		TestPersistence::new(top_pool, author, seal, Arc::new(ObserveStateMock::new(account_nonce)))
+	}
+
+	#[derive(Default, Clone)]
+	struct TopPoolSealMock {
+		sealed: Arc<RwLock<PersistedTopPool>>,
+	}
+
+	impl SealedIO for TopPoolSealMock {
+		type Error = Error;
+		type Unsealed = PersistedTopPool;
+
+		fn unseal(&self) -> Result<Self::Unsealed> {
+			Ok(self.sealed.read().unwrap().clone())
+		}
+
+		fn seal(&self, unsealed: &Self::Unsealed) -> Result<()> {
+			*self.sealed.write().unwrap() = unsealed.clone();
+			Ok(())
+		}
+	}
+
+	/// Returns the state itself as the nonce of every account.
+	struct NonceStfMock;
+
+	impl SystemPalletAccountInterface<Index, AccountId> for NonceStfMock {
+		type Index = Index;
+		type AccountData = ();
+
+		fn get_account_nonce(state: &mut Index, _account_id: &AccountId) -> Self::Index {
+			*state
+		}
+
+		fn get_account_data(_state: &mut Index, _account_id: &AccountId) -> Self::AccountData {}
+	}
+}
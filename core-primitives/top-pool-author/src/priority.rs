@@ -0,0 +1,147 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Priority providers, determining the order in which trusted operations are
+//! taken from the pool and which ones are evicted first when the pool is full.
+
+use ita_stf::{TrustedCallSigned, TrustedOperation};
+use sp_runtime::transaction_validity::TransactionPriority;
+
+/// Priority of trusted getters, the lowest of all operations.
+///
+/// Getters do not change the state and are not paid for, so they must not crowd out calls
+/// and are evicted first when the pool is full.
+pub const GETTER_PRIORITY: TransactionPriority = 0;
+
+/// Base priority of a direct trusted call that does not pay any tip.
+pub const BASE_CALL_PRIORITY: TransactionPriority = 1 << 20;
+
+/// Base priority of indirect calls, which have already been paid for on the parentchain.
+pub const PREFERRED_PRIORITY: TransactionPriority = 1 << 40;
+
+/// Provides the priority of a trusted operation.
+pub trait PrioritizeOperation {
+	fn priority_of(&self, operation: &TrustedOperation) -> TransactionPriority;
+}
+
+/// Default priority provider, using the tip defined by the STF on each trusted call.
+///
+/// Trusted calls are prioritized by their tip on top of a base priority, which is higher for
+/// indirect calls. Trusted getters get the lowest priority.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct StfTipPriority;
+
+impl StfTipPriority {
+	fn call_priority(
+		base_priority: TransactionPriority,
+		trusted_call_signed: &TrustedCallSigned,
+	) -> TransactionPriority {
+		let tip = trusted_call_signed.tip();
+		let tip = TransactionPriority::try_from(tip).unwrap_or(TransactionPriority::MAX);
+		base_priority.saturating_add(tip)
+	}
+}
+
+impl PrioritizeOperation for StfTipPriority {
+	fn priority_of(&self, operation: &TrustedOperation) -> TransactionPriority {
+		match operation {
+			TrustedOperation::direct_call(trusted_call_signed) =>
+				Self::call_priority(BASE_CALL_PRIORITY, trusted_call_signed),
+			TrustedOperation::indirect_call(trusted_call_signed) =>
+				Self::call_priority(PREFERRED_PRIORITY, trusted_call_signed),
+			TrustedOperation::get(_) => GETTER_PRIORITY,
+		}
+	}
+}
+
+/// Priority provider that assigns the same priority to every trusted operation.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct FlatPriority;
+
+impl PrioritizeOperation for FlatPriority {
+	fn priority_of(&self, _operation: &TrustedOperation) -> TransactionPriority {
+		BASE_CALL_PRIORITY
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ita_stf::{Getter, TrustedCall, TrustedGetter};
+	use itp_stf_primitives::types::{KeyPair, ShardIdentifier};
+	use sp_core::{ed25519, Pair};
+	use sp_keyring::AccountKeyring;
+
+	#[test]
+	fn indirect_calls_are_preferred_over_direct_calls_and_getters() {
+		let priority = StfTipPriority;
+		let call = signed_transfer(0);
+
+		let direct_priority = priority.priority_of(&TrustedOperation::direct_call(call.clone()));
+		let indirect_priority = priority.priority_of(&TrustedOperation::indirect_call(call));
+		let getter_priority = priority.priority_of(&trusted_getter());
+
+		assert_eq!(direct_priority, BASE_CALL_PRIORITY);
+		assert_eq!(indirect_priority, PREFERRED_PRIORITY);
+		assert_eq!(getter_priority, GETTER_PRIORITY);
+		assert!(getter_priority < direct_priority);
+	}
+
+	#[test]
+	fn tip_of_signed_call_is_added_to_base_priority() {
+		let priority = StfTipPriority;
+
+		assert_eq!(
+			priority.priority_of(&TrustedOperation::direct_call(signed_transfer(100))),
+			BASE_CALL_PRIORITY + 100
+		);
+	}
+
+	#[test]
+	fn flat_priority_is_equal_for_all_operations() {
+		let priority = FlatPriority;
+
+		assert_eq!(
+			priority.priority_of(&TrustedOperation::direct_call(signed_transfer(0))),
+			priority.priority_of(&trusted_getter())
+		);
+	}
+
+	fn signed_transfer(tip: u128) -> TrustedCallSigned {
+		TrustedCall::balance_transfer(
+			AccountKeyring::Alice.public().into(),
+			AccountKeyring::Bob.public().into(),
+			1000u128,
+		)
+		.sign_with_tip(
+			&KeyPair::Ed25519(Box::new(signer())),
+			1,
+			tip,
+			&[1u8; 32],
+			&ShardIdentifier::default(),
+		)
+	}
+
+	fn trusted_getter() -> TrustedOperation {
+		let getter = TrustedGetter::free_balance(AccountKeyring::Alice.public().into());
+		TrustedOperation::get(Getter::trusted(getter.sign(&KeyPair::Ed25519(Box::new(signer())))))
+	}
+
+	fn signer() -> ed25519::Pair {
+		ed25519::Pair::from_seed(b"12345678901234567890123456789012")
+	}
+}
@@ -34,6 +34,15 @@ pub trait AuthorApi<Hash, BlockHash> {
 	/// Submit encoded extrinsic for inclusion in block.
 	fn submit_top(&self, extrinsic: Vec<u8>, shard: ShardIdentifier) -> PoolFuture<Hash, RpcError>;
 
+	/// Submit an already decrypted trusted operation for inclusion in block.
+	///
+	/// The operation passes the same checks as the ones submitted with [`Self::submit_top`].
+	fn submit_trusted_operation(
+		&self,
+		operation: TrustedOperation,
+		shard: ShardIdentifier,
+	) -> PoolFuture<Hash, RpcError>;
+
 	/// Return hash of Trusted Operation
 	fn hash_of(&self, xt: &TrustedOperation) -> Hash;
 
@@ -72,6 +81,10 @@ pub trait AuthorApi<Hash, BlockHash> {
 	/// See [`TrustedOperationStatus`](sp_transaction_pool::TrustedOperationStatus) for details on transaction
 	/// life cycle.
 	fn watch_top(&self, ext: Vec<u8>, shard: ShardIdentifier) -> PoolFuture<Hash, RpcError>;
+
+	/// Submit already decrypted trusted operations again, e.g. the ones of sidechain blocks
+	/// that were retracted in a re-org. Operations that are no longer valid are dropped.
+	fn resubmit_operations(&self, shard: ShardIdentifier, operations: Vec<TrustedOperation>);
 }
 
 /// Trait to notify listeners/observer of a newly created block
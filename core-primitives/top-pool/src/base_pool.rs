@@ -417,7 +417,8 @@ impl<Hash: hash::Hash + Member + Ord, Ex: fmt::Debug> BasePool<Hash, Ex> {
 	///
 	/// Removes and returns worst operations from the queues and all operations that depend on them.
 	/// Technically the worst operation should be evaluated by computing the entire pending set.
-	/// We use a simplified approach to remove the operation that occupies the pool for the longest time.
+	/// We use a simplified approach and remove the operation with the lowest priority. Among
+	/// operations of equal priority, the one that occupies the pool for the longest time is removed.
 	pub fn enforce_limits(
 		&mut self,
 		ready: &Limit,
@@ -433,7 +434,10 @@ impl<Hash: hash::Hash + Member + Ord, Ex: fmt::Debug> BasePool<Hash, Ex> {
 					let operation = &current.operation;
 					match minimal {
 						None => Some(operation.clone()),
-						Some(ref tx) if tx.insertion_id > operation.insertion_id =>
+						Some(ref tx)
+							if tx.operation.priority > operation.operation.priority
+								|| (tx.operation.priority == operation.operation.priority
+									&& tx.insertion_id > operation.insertion_id) =>
 							Some(operation.clone()),
 						other => other,
 					}
@@ -451,14 +455,14 @@ impl<Hash: hash::Hash + Member + Ord, Ex: fmt::Debug> BasePool<Hash, Ex> {
 		while future.is_exceeded(self.future.len(shard), self.future.bytes(shard)) {
 			// find the worst operation
 			let minimal = self.future.fold(
-				|minimal, current| {
-					match minimal {
-						None => Some(current.clone()),
-						/*Some(ref tx) if tx.imported_at > current.imported_at => {
-							Some(current.clone())
-						},*/
-						other => other,
-					}
+				|minimal, current| match minimal {
+					None => Some(current.clone()),
+					Some(ref tx)
+						if tx.operation.priority > current.operation.priority
+							|| (tx.operation.priority == current.operation.priority
+								&& tx.imported_at > current.imported_at) =>
+						Some(current.clone()),
+					other => other,
 				},
 				shard,
 			);
@@ -1379,4 +1383,41 @@ source: External, requires: [03,02], provides: [04], data: [4]}"
 		assert!(pool.reject_future_operations);
 		assert_eq!(pool.future.len(shard), 1);
 	}
+
+	#[test]
+	pub fn test_should_evict_lowest_priority_operations_when_limits_are_exceeded() {
+		// given
+		let mut pool = test_pool();
+		let shard = ShardIdentifier::default();
+		let operation = |hash: u64, priority: u64, requires: Vec<Tag>| TrustedOperation {
+			data: vec![hash as u8],
+			bytes: 1,
+			hash,
+			priority,
+			valid_till: 64u64,
+			requires,
+			provides: vec![vec![hash as u8]],
+			propagate: true,
+			source: Source::External,
+		};
+		pool.import(operation(1, 10u64, vec![]), shard).unwrap();
+		pool.import(operation(2, 1u64, vec![]), shard).unwrap();
+		pool.import(operation(3, 5u64, vec![]), shard).unwrap();
+		pool.import(operation(4, 1u64, vec![vec![0]]), shard).unwrap();
+		pool.import(operation(5, 7u64, vec![vec![0]]), shard).unwrap();
+
+		// when
+		let removed = pool.enforce_limits(
+			&Limit { count: 2, total_bytes: 1024 },
+			&Limit { count: 1, total_bytes: 1024 },
+			shard,
+		);
+
+		// then
+		let removed_hashes: Vec<u64> = removed.iter().map(|op| op.hash).collect();
+		assert_eq!(removed_hashes, vec![2, 4]);
+		assert_eq!(pool.ready.len(shard), 2);
+		assert_eq!(pool.future.len(shard), 1);
+		assert!(pool.future.contains(&5, shard));
+	}
 }
@@ -205,6 +205,10 @@ where
 		Box::new(self.pool.validated_pool().ready(shard))
 	}
 
+	fn futures(&self, shard: ShardIdentifier) -> Vec<StfTrustedOperation> {
+		self.pool.validated_pool().futures(shard)
+	}
+
 	fn shards(&self) -> Vec<ShardIdentifier> {
 		self.pool.validated_pool().shards()
 	}
@@ -164,6 +164,10 @@ impl TrustedOperationPool for TrustedOperationPoolMock {
 		Box::new(ready_transactions.into_iter())
 	}
 
+	fn futures(&self, _shard: ShardIdentifier) -> Vec<StfTrustedOperation> {
+		Vec::new()
+	}
+
 	fn shards(&self) -> Vec<ShardIdentifier> {
 		let transactions = self.submitted_transactions.read().unwrap();
 		transactions.iter().map(|(shard, _)| *shard).collect()
@@ -642,7 +642,7 @@ pub mod tests {
 
 		let signature = pair.sign(msg);
 		let multi_sig = MultiSignature::from(signature);
-		TrustedCallSigned::new(call, nonce, multi_sig).into_trusted_operation(true)
+		TrustedCallSigned::new(call, nonce, 0, multi_sig).into_trusted_operation(true)
 	}
 
 	fn test_pool() -> Pool<TestApi, RpcResponderMock<H256>> {
@@ -224,6 +224,9 @@ pub trait TrustedOperationPool: Send + Sync {
 		shard: ShardIdentifier,
 	) -> Box<dyn Iterator<Item = Arc<Self::InPoolOperation>> + Send>;
 
+	/// Get all operations of the future queue, i.e. operations with unsatisfied requirements.
+	fn futures(&self, shard: ShardIdentifier) -> Vec<StfTrustedOperation>;
+
 	/// Get an iterator over all shards.
 	fn shards(&self) -> Vec<ShardIdentifier>;
 
@@ -655,6 +655,11 @@ where
 		self.pool.read().unwrap().ready(shard)
 	}
 
+	/// Get all operations of the future queue
+	pub fn futures(&self, shard: ShardIdentifier) -> Vec<StfTrustedOperation> {
+		self.pool.read().unwrap().futures(shard).map(|op| op.data.clone()).collect()
+	}
+
 	/// Get an iterator for all shards
 	pub fn shards(&self) -> Vec<ShardIdentifier> {
 		let mut shards = vec![];
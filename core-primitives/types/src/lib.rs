@@ -25,6 +25,7 @@ use sgx_tstd as std;
 use sp_std::vec::Vec;
 
 pub mod parentchain;
+pub mod personhood;
 pub mod storage;
 
 /// Substrate runtimes provide no string type. Hence, for arbitrary data of varying length the
@@ -50,6 +51,11 @@ pub type CallWorkerFn = ([u8; 2], Request);
 use enclave_bridge_primitives::ShardSignerStatus as ShardSignerStatusGen;
 pub type ShardSignerStatus = ShardSignerStatusGen<AccountId, BlockNumber>;
 pub type ShardStatus = Vec<ShardSignerStatus>;
+use enclave_bridge_primitives::{
+	ShardConfig as ShardConfigGen, UpgradableShardConfig as UpgradableShardConfigGen,
+};
+pub type ShardConfig = ShardConfigGen<AccountId>;
+pub type UpgradableShardConfig = UpgradableShardConfigGen<AccountId, BlockNumber>;
 pub use enclave_bridge_primitives::Request;
 pub use teerex_primitives::{
 	EnclaveFingerprint, MultiEnclave, SgxBuildMode, SgxEnclave, SgxReportData, SgxStatus,
@@ -73,6 +79,13 @@ impl Encode for OpaqueCall {
 	}
 }
 
+/// Latest confirmed sidechain block of a shard, as stored by the sidechain pallet.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Encode, Decode)]
+pub struct SidechainBlockConfirmation {
+	pub block_number: SidechainBlockNumber,
+	pub block_header_hash: BlockHash,
+}
+
 #[derive(Debug, Clone, PartialEq, Encode, Decode)]
 pub enum DirectRequestStatus {
 	/// Direct request was successfully executed
@@ -0,0 +1,71 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Configuration of the parentchains the personhood oracle reads its data from.
+
+use crate::parentchain::ParentchainId;
+use codec::{Decode, Encode};
+use sp_std::vec::Vec;
+
+/// Pallet names of Encointer as deployed on the Encointer network.
+pub const ENCOINTER_COMMUNITIES_PALLET: &str = "EncointerCommunities";
+pub const ENCOINTER_CEREMONIES_PALLET: &str = "EncointerCeremonies";
+pub const ENCOINTER_SCHEDULER_PALLET: &str = "EncointerScheduler";
+
+/// Where the personhood data is stored on a parentchain.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub enum PersonhoodStorageLayout {
+	/// The Encointer pallets under their default names.
+	Encointer,
+	/// The Encointer pallets, deployed under different names in the runtime.
+	RenamedEncointerPallets {
+		communities_pallet: Vec<u8>,
+		ceremonies_pallet: Vec<u8>,
+		scheduler_pallet: Vec<u8>,
+	},
+}
+
+impl PersonhoodStorageLayout {
+	/// UTF-8 names of the communities, the ceremonies and the scheduler pallet.
+	pub fn pallet_names(&self) -> (&[u8], &[u8], &[u8]) {
+		match self {
+			PersonhoodStorageLayout::Encointer => (
+				ENCOINTER_COMMUNITIES_PALLET.as_bytes(),
+				ENCOINTER_CEREMONIES_PALLET.as_bytes(),
+				ENCOINTER_SCHEDULER_PALLET.as_bytes(),
+			),
+			PersonhoodStorageLayout::RenamedEncointerPallets {
+				communities_pallet,
+				ceremonies_pallet,
+				scheduler_pallet,
+			} => (communities_pallet, ceremonies_pallet, scheduler_pallet),
+		}
+	}
+}
+
+/// A target parentchain the personhood oracle reads verified personhood data from.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct PersonhoodSourceConfig {
+	pub parentchain_id: ParentchainId,
+	pub layout: PersonhoodStorageLayout,
+}
+
+impl PersonhoodSourceConfig {
+	pub fn encointer(parentchain_id: ParentchainId) -> Self {
+		Self { parentchain_id, layout: PersonhoodStorageLayout::Encointer }
+	}
+}
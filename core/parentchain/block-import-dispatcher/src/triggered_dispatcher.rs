@@ -17,6 +17,12 @@
 
 //! A block import dispatcher that retains all blocks in a queue until import is triggered.
 
+#[cfg(feature = "sgx")]
+use std::sync::SgxRwLock as RwLock;
+
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+
 use crate::{
 	error::{Error, Result},
 	DispatchBlockImport,
@@ -24,10 +30,13 @@ use crate::{
 use itc_parentchain_block_importer::ImportParentchainBlocks;
 use itp_import_queue::{PeekQueue, PopFromQueue, PushToQueue};
 use log::trace;
-use std::vec::Vec;
+use std::{collections::VecDeque, format, vec::Vec};
 
 pub type RawEventsPerBlock = Vec<u8>;
 
+/// Number of imported blocks that are kept, so their state effects can be re-applied.
+pub const IMPORTED_BLOCKS_HISTORY_SIZE: usize = 64;
+
 /// Trait to specifically trigger the import of parentchain blocks.
 pub trait TriggerParentchainBlockImport {
 	type SignedBlockType;
@@ -57,14 +66,32 @@ pub trait TriggerParentchainBlockImport {
 
 	/// Peek the latest block in the import queue. Returns None if queue is empty.
 	fn peek_latest(&self) -> Result<Option<Self::SignedBlockType>>;
+
+	/// Re-apply the state effects of the imported blocks after the one matching `is_applied`,
+	/// up to **and including** the one matching `predicate`, e.g. after the state was reset for a
+	/// sidechain re-org. If `predicate` matches no imported block, the remaining blocks up to it
+	/// are imported from the queue.
+	///
+	/// Fails if the block matching `is_applied` is no longer in the history of imported blocks.
+	/// Returns the latest block imported from the queue (if any).
+	fn reapply_until(
+		&self,
+		is_applied: impl Fn(&Self::SignedBlockType) -> bool,
+		predicate: impl Fn(&Self::SignedBlockType) -> bool,
+	) -> Result<Option<Self::SignedBlockType>>;
 }
 
 /// Dispatcher for block imports that retains blocks until the import is triggered, using the
 /// `TriggerParentchainBlockImport` trait implementation.
-pub struct TriggeredDispatcher<BlockImporter, BlockImportQueue, EventsImportQueue> {
+pub struct TriggeredDispatcher<BlockImporter, BlockImportQueue, EventsImportQueue>
+where
+	BlockImporter: ImportParentchainBlocks,
+{
 	block_importer: BlockImporter,
 	import_queue: BlockImportQueue,
 	events_queue: EventsImportQueue,
+	/// Most recently imported blocks with their events, oldest first.
+	imported_history: RwLock<VecDeque<(BlockImporter::SignedBlockType, RawEventsPerBlock)>>,
 }
 
 impl<BlockImporter, BlockImportQueue, EventsImportQueue>
@@ -84,8 +111,35 @@ where
 			block_importer,
 			import_queue: block_import_queue,
 			events_queue: events_import_queue,
+			imported_history: RwLock::new(VecDeque::new()),
 		}
 	}
+
+	/// Import blocks and add them to the history of imported blocks.
+	fn import_and_record(
+		&self,
+		blocks_to_import: Vec<BlockImporter::SignedBlockType>,
+		events_to_import: Vec<RawEventsPerBlock>,
+	) -> Result<()> {
+		let imported = blocks_to_import
+			.iter()
+			.cloned()
+			.zip(events_to_import.iter().cloned())
+			.collect::<Vec<_>>();
+
+		self.block_importer
+			.import_parentchain_blocks(blocks_to_import, events_to_import)
+			.map_err(Error::BlockImport)?;
+
+		let mut history = self.imported_history.write().map_err(|e| {
+			Error::Other(format!("Failed to lock imported blocks history: {:?}", e).into())
+		})?;
+		history.extend(imported);
+		while history.len() > IMPORTED_BLOCKS_HISTORY_SIZE {
+			history.pop_front();
+		}
+		Ok(())
+	}
 }
 
 impl<BlockImporter, BlockImportQueue, SignedBlockType, EventsImportQueue>
@@ -137,9 +191,7 @@ where
 			events_to_import.len()
 		);
 
-		self.block_importer
-			.import_parentchain_blocks(blocks_to_import, events_to_import)
-			.map_err(Error::BlockImport)?;
+		self.import_and_record(blocks_to_import, events_to_import)?;
 
 		Ok(latest_imported_block)
 	}
@@ -154,9 +206,7 @@ where
 			events_to_import.len()
 		);
 
-		self.block_importer
-			.import_parentchain_blocks(blocks_to_import, events_to_import)
-			.map_err(Error::BlockImport)
+		self.import_and_record(blocks_to_import, events_to_import)
 	}
 
 	fn import_until(
@@ -179,9 +229,7 @@ where
 			events_to_import.len(),
 		);
 
-		self.block_importer
-			.import_parentchain_blocks(blocks_to_import, events_to_import)
-			.map_err(Error::BlockImport)?;
+		self.import_and_record(blocks_to_import, events_to_import)?;
 
 		Ok(latest_imported_block)
 	}
@@ -204,6 +252,50 @@ where
 		);
 		self.import_queue.peek_last().map_err(Error::ImportQueue)
 	}
+
+	fn reapply_until(
+		&self,
+		is_applied: impl Fn(&BlockImporter::SignedBlockType) -> bool,
+		predicate: impl Fn(&BlockImporter::SignedBlockType) -> bool,
+	) -> Result<Option<BlockImporter::SignedBlockType>> {
+		let (blocks_to_reapply, events_to_reapply, is_reapplied_until_target) = {
+			let history = self.imported_history.read().map_err(|e| {
+				Error::Other(format!("Failed to lock imported blocks history: {:?}", e).into())
+			})?;
+			let first_to_reapply = history
+				.iter()
+				.position(|(block, _)| is_applied(block))
+				.map(|index| index + 1)
+				.ok_or_else(|| {
+					Error::Other("Applied block is not in the imported blocks history".into())
+				})?;
+
+			let mut blocks = Vec::new();
+			let mut events = Vec::new();
+			let mut is_target_found = false;
+			for (block, block_events) in history.iter().skip(first_to_reapply) {
+				blocks.push(block.clone());
+				events.push(block_events.clone());
+				if predicate(block) {
+					is_target_found = true;
+					break
+				}
+			}
+			(blocks, events, is_target_found)
+		};
+
+		trace!("Re-applying {} imported parentchain blocks", blocks_to_reapply.len());
+		if !blocks_to_reapply.is_empty() {
+			self.block_importer
+				.reapply_parentchain_blocks(blocks_to_reapply, events_to_reapply)
+				.map_err(Error::BlockImport)?;
+		}
+
+		if is_reapplied_until_target {
+			return Ok(None)
+		}
+		self.import_until(predicate)
+	}
 }
 
 #[cfg(test)]
@@ -326,6 +418,40 @@ mod tests {
 		assert_eq!(dispatcher.import_queue.pop_all().unwrap(), vec![5]);
 	}
 
+	#[test]
+	fn reapply_until_reapplies_imported_blocks_and_imports_the_rest() {
+		let dispatcher = test_fixtures();
+
+		dispatcher
+			.dispatch_import(vec![1, 2, 3, 4, 5], vec![vec![1], vec![2], vec![3], vec![4], vec![5]])
+			.unwrap();
+		dispatcher.import_until(|i: &SignedBlockType| i == &3).unwrap();
+
+		let latest_imported = dispatcher
+			.reapply_until(|i: &SignedBlockType| i == &1, |i: &SignedBlockType| i == &4)
+			.unwrap();
+
+		assert_eq!(latest_imported, Some(4));
+		assert_eq!(dispatcher.block_importer.get_all_reapplied_blocks(), vec![2, 3]);
+		assert_eq!(dispatcher.block_importer.get_all_imported_blocks(), vec![1, 2, 3, 4]);
+		assert_eq!(dispatcher.import_queue.pop_all().unwrap(), vec![5]);
+	}
+
+	#[test]
+	fn reapply_until_fails_if_applied_block_is_not_in_history() {
+		let dispatcher = test_fixtures();
+
+		dispatcher
+			.dispatch_import(vec![1, 2, 3], vec![vec![1], vec![2], vec![3]])
+			.unwrap();
+		dispatcher.import_all().unwrap();
+
+		assert!(dispatcher
+			.reapply_until(|i: &SignedBlockType| i == &0, |i: &SignedBlockType| i == &3)
+			.is_err());
+		assert!(dispatcher.block_importer.get_all_reapplied_blocks().is_empty());
+	}
+
 	fn test_fixtures() -> TestDispatcher {
 		let events_import_queue = ImportQueue::<RawEventsPerBlock>::default();
 		let import_queue = ImportQueue::<SignedBlockType>::default();
@@ -165,4 +165,39 @@ impl<
 
 		Ok(())
 	}
+
+	fn reapply_parentchain_blocks(
+		&self,
+		blocks_to_reapply: Vec<Self::SignedBlockType>,
+		events_to_reapply: Vec<Vec<u8>>,
+	) -> Result<()> {
+		let id = self.validator_accessor.parentchain_id();
+
+		for (signed_block, raw_events) in
+			blocks_to_reapply.into_iter().zip(events_to_reapply.into_iter())
+		{
+			let block = signed_block.block;
+			if let Err(e) = self.stf_executor.update_states(block.header(), &id) {
+				error!("[{:?}] Error performing state updates upon block re-application", id);
+				return Err(e.into())
+			}
+
+			// The confirmation calls have been sent when the block was imported, so we drop them.
+			if self
+				.indirect_calls_executor
+				.execute_indirect_calls_in_extrinsics(&block, &raw_events)
+				.is_err()
+			{
+				error!("[{:?}] Error executing relevant extrinsics again", id);
+			}
+
+			info!(
+				"[{:?}] Re-applied parentchain block (number: {}, hash: {})",
+				id,
+				block.header().number,
+				block.header().hash()
+			);
+		}
+		Ok(())
+	}
 }
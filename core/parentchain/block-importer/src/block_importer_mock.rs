@@ -29,6 +29,7 @@ use std::{sync::RwLock, vec::Vec};
 #[derive(Default)]
 pub struct ParentchainBlockImporterMock<SignedBlockT> {
 	imported_blocks: RwLock<Vec<SignedBlockT>>,
+	reapplied_blocks: RwLock<Vec<SignedBlockT>>,
 }
 
 impl<SignedBlockT> ParentchainBlockImporterMock<SignedBlockT>
@@ -39,6 +40,11 @@ where
 		let imported_blocks_lock = self.imported_blocks.read().unwrap();
 		(*imported_blocks_lock).clone()
 	}
+
+	pub fn get_all_reapplied_blocks(&self) -> Vec<SignedBlockT> {
+		let reapplied_blocks_lock = self.reapplied_blocks.read().unwrap();
+		(*reapplied_blocks_lock).clone()
+	}
 }
 
 impl<SignedBlockT> ImportParentchainBlocks for ParentchainBlockImporterMock<SignedBlockT>
@@ -58,4 +64,16 @@ where
 		imported_blocks_lock.extend(blocks_to_import);
 		Ok(())
 	}
+
+	fn reapply_parentchain_blocks(
+		&self,
+		blocks_to_reapply: Vec<Self::SignedBlockType>,
+		_events: Vec<Vec<u8>>,
+	) -> Result<()> {
+		let mut reapplied_blocks_lock = self.reapplied_blocks.write().map_err(|e| {
+			Error::Other(format!("failed to acquire lock for reapplied blocks vec: {:?}", e).into())
+		})?;
+		reapplied_blocks_lock.extend(blocks_to_reapply);
+		Ok(())
+	}
 }
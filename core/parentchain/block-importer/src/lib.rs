@@ -55,4 +55,14 @@ pub trait ImportParentchainBlocks {
 		blocks_to_import: Vec<Self::SignedBlockType>,
 		events_to_import: Vec<Vec<u8>>,
 	) -> Result<()>;
+
+	/// Re-apply the state effects of blocks that have already been imported, e.g. after the state
+	/// was reset for a sidechain re-org:
+	/// * Performs the state updates and executes the relevant extrinsics again
+	/// * Does not touch the light-client and does not send any extrinsics
+	fn reapply_parentchain_blocks(
+		&self,
+		blocks_to_reapply: Vec<Self::SignedBlockType>,
+		events_to_reapply: Vec<Vec<u8>>,
+	) -> Result<()>;
 }
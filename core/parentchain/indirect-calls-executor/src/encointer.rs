@@ -0,0 +1,202 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Watch the Encointer pallets of a parentchain.
+//!
+//! The watched events change what the personhood oracle reports: a ceremony phase change
+//! moves the window of relevant reputations, `ParticipantReputation` entries are written
+//! upon registration and reward issuance and communities can be registered, updated
+//! or purged.
+
+use codec::{Decode, Encode};
+use encointer_primitives::{
+	ceremonies::{MeetupIndexType, MeetupParticipantIndexType, ParticipantType},
+	communities::CommunityIdentifier,
+	scheduler::CeremonyPhaseType,
+};
+use itp_api_client_types::StaticEvent;
+use itp_sgx_runtime_primitives::types::AccountId;
+use itp_types::H256;
+use std::vec::Vec;
+
+/// Pallet names:
+const ENCOINTER_SCHEDULER: &str = "EncointerScheduler";
+const ENCOINTER_CEREMONIES: &str = "EncointerCeremonies";
+const ENCOINTER_COMMUNITIES: &str = "EncointerCommunities";
+
+/// A change on the Encointer parentchain that is relevant for the personhood oracle.
+#[derive(Encode, Decode, Debug, Clone, PartialEq, Eq)]
+pub enum EncointerEvent {
+	/// The ceremony phase changed. A change to `Registering` starts a new ceremony index.
+	PhaseChanged(CeremonyPhaseType),
+	/// `ParticipantReputation` entries of a community have been written.
+	///
+	/// Contains the account if the event tells whose reputation changed, `None` if potentially
+	/// several participants of the community are affected.
+	ReputationChanged(CommunityIdentifier, Option<AccountId>),
+	/// A community has been registered or its metadata has been updated.
+	CommunityChanged(CommunityIdentifier),
+	/// A community has been removed from the registry.
+	CommunityPurged(CommunityIdentifier),
+}
+
+/// Handles the Encointer events found in an imported parentchain block.
+pub trait HandleEncointerEvents {
+	fn handle_encointer_events(
+		&self,
+		block_number: u64,
+		block_hash: H256,
+		events: Vec<EncointerEvent>,
+	);
+}
+
+/// Ignores all Encointer events, used for parentchains without Encointer pallets.
+impl HandleEncointerEvents for () {
+	fn handle_encointer_events(&self, _: u64, _: H256, _: Vec<EncointerEvent>) {}
+}
+
+#[derive(Encode, Decode, Debug)]
+pub struct PhaseChangedTo(pub CeremonyPhaseType);
+
+impl StaticEvent for PhaseChangedTo {
+	const PALLET: &'static str = ENCOINTER_SCHEDULER;
+	const EVENT: &'static str = "PhaseChangedTo";
+}
+
+#[derive(Encode, Decode, Debug)]
+pub struct ParticipantRegistered(pub CommunityIdentifier, pub ParticipantType, pub AccountId);
+
+impl StaticEvent for ParticipantRegistered {
+	const PALLET: &'static str = ENCOINTER_CEREMONIES;
+	const EVENT: &'static str = "ParticipantRegistered";
+}
+
+#[derive(Encode, Decode, Debug)]
+pub struct RewardsIssued(
+	pub CommunityIdentifier,
+	pub MeetupIndexType,
+	pub MeetupParticipantIndexType,
+);
+
+impl StaticEvent for RewardsIssued {
+	const PALLET: &'static str = ENCOINTER_CEREMONIES;
+	const EVENT: &'static str = "RewardsIssued";
+}
+
+#[derive(Encode, Decode, Debug)]
+pub struct CommunityRegistered(pub CommunityIdentifier);
+
+impl StaticEvent for CommunityRegistered {
+	const PALLET: &'static str = ENCOINTER_COMMUNITIES;
+	const EVENT: &'static str = "CommunityRegistered";
+}
+
+#[derive(Encode, Decode, Debug)]
+pub struct MetadataUpdated(pub CommunityIdentifier);
+
+impl StaticEvent for MetadataUpdated {
+	const PALLET: &'static str = ENCOINTER_COMMUNITIES;
+	const EVENT: &'static str = "MetadataUpdated";
+}
+
+#[derive(Encode, Decode, Debug)]
+pub struct CommunityPurged(pub CommunityIdentifier);
+
+impl StaticEvent for CommunityPurged {
+	const PALLET: &'static str = ENCOINTER_COMMUNITIES;
+	const EVENT: &'static str = "CommunityPurged";
+}
+
+impl From<PhaseChangedTo> for EncointerEvent {
+	fn from(event: PhaseChangedTo) -> Self {
+		EncointerEvent::PhaseChanged(event.0)
+	}
+}
+
+impl ParticipantRegistered {
+	/// Registering as reputable links the reputation of a past ceremony, all other
+	/// registrations leave the reputations untouched.
+	pub fn into_encointer_event(self) -> Option<EncointerEvent> {
+		match self.1 {
+			ParticipantType::Reputable =>
+				Some(EncointerEvent::ReputationChanged(self.0, Some(self.2))),
+			_ => None,
+		}
+	}
+}
+
+impl From<RewardsIssued> for EncointerEvent {
+	fn from(event: RewardsIssued) -> Self {
+		// Rewards issuance verifies the reputation of all attested meetup participants.
+		EncointerEvent::ReputationChanged(event.0, None)
+	}
+}
+
+impl From<CommunityRegistered> for EncointerEvent {
+	fn from(event: CommunityRegistered) -> Self {
+		EncointerEvent::CommunityChanged(event.0)
+	}
+}
+
+impl From<MetadataUpdated> for EncointerEvent {
+	fn from(event: MetadataUpdated) -> Self {
+		EncointerEvent::CommunityChanged(event.0)
+	}
+}
+
+impl From<CommunityPurged> for EncointerEvent {
+	fn from(event: CommunityPurged) -> Self {
+		EncointerEvent::CommunityPurged(event.0)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn reputable_registration_changes_reputation_of_participant() {
+		let cid = CommunityIdentifier::default();
+		let account = AccountId::new([1u8; 32]);
+
+		let event = ParticipantRegistered(cid, ParticipantType::Reputable, account.clone())
+			.into_encointer_event();
+
+		assert_eq!(event, Some(EncointerEvent::ReputationChanged(cid, Some(account))));
+	}
+
+	#[test]
+	fn newbie_registration_is_ignored() {
+		let event = ParticipantRegistered(
+			CommunityIdentifier::default(),
+			ParticipantType::Newbie,
+			AccountId::new([1u8; 32]),
+		)
+		.into_encointer_event();
+
+		assert_eq!(event, None);
+	}
+
+	#[test]
+	fn rewards_issuance_changes_reputation_of_community() {
+		let cid = CommunityIdentifier::default();
+
+		let event: EncointerEvent = RewardsIssued(cid, 1, 3).into();
+
+		assert_eq!(event, EncointerEvent::ReputationChanged(cid, None));
+	}
+}
@@ -16,9 +16,15 @@
 */
 //! Various way to filter Parentchain events
 
-use crate::error::Result;
+use crate::{
+	encointer::{
+		CommunityPurged, CommunityRegistered, EncointerEvent, MetadataUpdated,
+		ParticipantRegistered, PhaseChangedTo, RewardsIssued,
+	},
+	error::Result,
+};
 use codec::{Decode, Encode};
-use itp_api_client_types::{Events, StaticEvent};
+use itp_api_client_types::{EventDetails, Events, StaticEvent};
 use itp_sgx_runtime_primitives::types::{AccountId, Balance};
 use itp_types::H256;
 use itp_utils::stringify::account_id_to_string;
@@ -74,6 +80,10 @@ pub trait FilterEvents {
 	fn get_extrinsic_statuses(&self) -> Result<Vec<ExtrinsicStatus>>;
 
 	fn get_transfer_events(&self) -> Result<Vec<BalanceTransfer>>;
+
+	/// Returns the events of the Encointer pallets that are relevant for the personhood oracle,
+	/// in the order they have been emitted.
+	fn get_encointer_events(&self) -> Result<Vec<EncointerEvent>>;
 }
 
 impl FilterEvents for Events<H256> {
@@ -116,6 +126,40 @@ impl FilterEvents for Events<H256> {
 			})
 			.collect())
 	}
+
+	fn get_encointer_events(&self) -> Result<Vec<EncointerEvent>> {
+		Ok(self
+			.iter()
+			.flatten() // flatten filters out the nones
+			.filter_map(|ev| as_encointer_event(&ev))
+			.collect())
+	}
+}
+
+fn as_encointer_event(event: &EventDetails) -> Option<EncointerEvent> {
+	if let Some(e) = decode_event::<PhaseChangedTo>(event) {
+		return Some(e.into())
+	}
+	if let Some(e) = decode_event::<ParticipantRegistered>(event) {
+		return e.into_encointer_event()
+	}
+	if let Some(e) = decode_event::<RewardsIssued>(event) {
+		return Some(e.into())
+	}
+	if let Some(e) = decode_event::<CommunityRegistered>(event) {
+		return Some(e.into())
+	}
+	if let Some(e) = decode_event::<MetadataUpdated>(event) {
+		return Some(e.into())
+	}
+	decode_event::<CommunityPurged>(event).map(Into::into)
+}
+
+fn decode_event<E: StaticEvent + Decode>(event: &EventDetails) -> Option<E> {
+	event.as_event::<E>().unwrap_or_else(|e| {
+		log::error!("Could not decode {}::{} event: {:?}", E::PALLET, E::EVENT, e);
+		None
+	})
 }
 
 pub struct MockEvents;
@@ -133,4 +177,8 @@ impl FilterEvents for MockEvents {
 		};
 		Ok(Vec::from([transfer]))
 	}
+
+	fn get_encointer_events(&self) -> Result<Vec<EncointerEvent>> {
+		Ok(Vec::new())
+	}
 }
@@ -20,6 +20,7 @@
 use crate::sgx_reexport_prelude::*;
 
 use crate::{
+	encointer::HandleEncointerEvents,
 	error::{Error, Result},
 	event_filter::{ExtrinsicStatus, FilterEvents},
 	filter_metadata::{EventsFromMetadata, FilterIntoDataFrom},
@@ -40,7 +41,10 @@ use itp_top_pool_author::traits::AuthorApi;
 use itp_types::{OpaqueCall, ShardIdentifier, H256};
 use log::*;
 use sp_core::blake2_256;
-use sp_runtime::traits::{Block as ParentchainBlockTrait, Header, Keccak256};
+use sp_runtime::{
+	traits::{Block as ParentchainBlockTrait, Header, Keccak256},
+	SaturatedConversion,
+};
 use std::{fmt::Debug, sync::Arc, vec::Vec};
 
 pub struct IndirectCallsExecutor<
@@ -50,11 +54,13 @@ pub struct IndirectCallsExecutor<
 	NodeMetadataProvider,
 	IndirectCallsFilter,
 	EventCreator,
+	EncointerEventHandler,
 > {
 	pub(crate) shielding_key_repo: Arc<ShieldingKeyRepository>,
 	pub(crate) stf_enclave_signer: Arc<StfEnclaveSigner>,
 	pub(crate) top_pool_author: Arc<TopPoolAuthor>,
 	pub(crate) node_meta_data_provider: Arc<NodeMetadataProvider>,
+	pub(crate) encointer_event_handler: Arc<EncointerEventHandler>,
 	_phantom: PhantomData<(IndirectCallsFilter, EventCreator)>,
 }
 impl<
@@ -64,6 +70,7 @@ impl<
 		NodeMetadataProvider,
 		IndirectCallsFilter,
 		EventCreator,
+		EncointerEventHandler,
 	>
 	IndirectCallsExecutor<
 		ShieldingKeyRepository,
@@ -72,6 +79,7 @@ impl<
 		NodeMetadataProvider,
 		IndirectCallsFilter,
 		EventCreator,
+		EncointerEventHandler,
 	>
 {
 	pub fn new(
@@ -79,12 +87,14 @@ impl<
 		stf_enclave_signer: Arc<StfEnclaveSigner>,
 		top_pool_author: Arc<TopPoolAuthor>,
 		node_meta_data_provider: Arc<NodeMetadataProvider>,
+		encointer_event_handler: Arc<EncointerEventHandler>,
 	) -> Self {
 		IndirectCallsExecutor {
 			shielding_key_repo,
 			stf_enclave_signer,
 			top_pool_author,
 			node_meta_data_provider,
+			encointer_event_handler,
 			_phantom: Default::default(),
 		}
 	}
@@ -97,6 +107,7 @@ impl<
 		NodeMetadataProvider,
 		FilterIndirectCalls,
 		EventCreator,
+		EncointerEventHandler,
 	> ExecuteIndirectCalls
 	for IndirectCallsExecutor<
 		ShieldingKeyRepository,
@@ -105,6 +116,7 @@ impl<
 		NodeMetadataProvider,
 		FilterIndirectCalls,
 		EventCreator,
+		EncointerEventHandler,
 	> where
 	ShieldingKeyRepository: AccessKey,
 	<ShieldingKeyRepository as AccessKey>::KeyType: ShieldingCryptoDecrypt<Error = itp_sgx_crypto::Error>
@@ -116,6 +128,7 @@ impl<
 	NodeMetadataProvider::MetadataType: NodeMetadataTrait + Clone,
 	FilterIndirectCalls::Output: IndirectDispatch<Self> + Encode + Debug,
 	EventCreator: EventsFromMetadata<NodeMetadataProvider::MetadataType>,
+	EncointerEventHandler: HandleEncointerEvents,
 {
 	fn execute_indirect_calls_in_extrinsics<ParentchainBlock>(
 		&self,
@@ -147,6 +160,19 @@ impl<
 			events.iter().for_each(|event| info!("Found transfer_event: {:?}", event))
 		}
 
+		match events.get_encointer_events() {
+			Ok(encointer_events) if !encointer_events.is_empty() => {
+				debug!("Found {} Encointer events", encointer_events.len());
+				self.encointer_event_handler.handle_encointer_events(
+					block_number.saturated_into(),
+					block_hash,
+					encointer_events,
+				);
+			},
+			Ok(_) => {},
+			Err(e) => warn!("Could not filter Encointer events: {:?}", e),
+		}
+
 		// This would be catastrophic but should never happen
 		if xt_statuses.len() != block.extrinsics().len() {
 			return Err(Error::Other("Extrinsic Status and Extrinsic count not equal".into()))
@@ -214,6 +240,7 @@ impl<
 		NodeMetadataProvider,
 		FilterIndirectCalls,
 		EventFilter,
+		EncointerEventHandler,
 	> IndirectExecutor
 	for IndirectCallsExecutor<
 		ShieldingKeyRepository,
@@ -222,6 +249,7 @@ impl<
 		NodeMetadataProvider,
 		FilterIndirectCalls,
 		EventFilter,
+		EncointerEventHandler,
 	> where
 	ShieldingKeyRepository: AccessKey,
 	<ShieldingKeyRepository as AccessKey>::KeyType: ShieldingCryptoDecrypt<Error = itp_sgx_crypto::Error>
@@ -308,6 +336,7 @@ mod test {
 		TestNodeMetadataRepository,
 		ShieldFundsAndInvokeFilter<ParentchainExtrinsicParser>,
 		TestEventCreator,
+		(),
 	>;
 
 	type Seed = [u8; 32];
@@ -481,6 +510,7 @@ mod test {
 			stf_enclave_signer,
 			top_pool_author.clone(),
 			node_metadata_repo,
+			Arc::new(()),
 		);
 
 		(executor, top_pool_author, shielding_key_repo)
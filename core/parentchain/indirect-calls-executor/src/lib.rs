@@ -41,11 +41,13 @@ mod event_filter;
 mod executor;
 mod traits;
 
+pub mod encointer;
 pub mod error;
 pub mod filter_metadata;
 pub mod indirect_calls;
 pub mod parentchain_parser;
 
+pub use encointer::{EncointerEvent, HandleEncointerEvents};
 pub use error::{Error, Result};
 pub use executor::IndirectCallsExecutor;
 pub use traits::{ExecuteIndirectCalls, IndirectDispatch, IndirectExecutor};
@@ -0,0 +1,62 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Backups of the sealed light client state.
+//!
+//! Whenever the sealed state has advanced by a configured number of finalized blocks, a copy
+//! is sealed into one of a fixed number of rotating backup slots, together with its generation.
+//! If the light client db can't be unsealed, the newest intact backup is restored. The
+//! integrity of a backup is ensured by the MAC of its sealed file.
+
+use codec::{Decode, Encode};
+use itp_types::parentchain::BlockNumber;
+use std::vec::Vec;
+
+/// Content of a backup slot.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct LightClientBackup {
+	/// Incremented with each backup, the highest one is the newest backup.
+	pub generation: u64,
+	/// Latest finalized parentchain block of the backed up state.
+	pub latest_finalized_block: BlockNumber,
+	/// Encoded light client state.
+	pub state: Vec<u8>,
+}
+
+impl LightClientBackup {
+	pub fn new(generation: u64, latest_finalized_block: BlockNumber, state: Vec<u8>) -> Self {
+		Self { generation, latest_finalized_block, state }
+	}
+}
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub enum LightClientBackupStatus {
+	Valid {
+		generation: u64,
+		/// Latest finalized parentchain block of the backed up state.
+		latest_finalized_block: BlockNumber,
+	},
+	/// The slot can't be unsealed or decoded.
+	Corrupted,
+}
+
+/// Summary of a backup slot, to inspect the backups from the untrusted side.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct LightClientBackupInfo {
+	pub slot: u32,
+	pub status: LightClientBackupStatus,
+}
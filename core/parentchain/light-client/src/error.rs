@@ -18,6 +18,7 @@
 use std::{boxed::Box, string::String};
 
 use sgx_types::sgx_status_t;
+use sp_core::H256;
 #[cfg(all(not(feature = "std"), feature = "sgx"))]
 use thiserror_sgx as thiserror;
 
@@ -53,6 +54,16 @@ pub enum Error {
 	PoisonedLock,
 	#[error("No Justification found")]
 	NoJustificationFound,
+	#[error("Parachain head in the relay chain state does not match the header")]
+	ParachainHeadMismatch,
+	#[error("Relay chain genesis {0:?} does not match the sealed relay chain anchor")]
+	RelayChainMismatch(H256),
+	#[error("The relay chain authorities have already been initialized")]
+	RelayChainAlreadyAnchored,
+	#[error("Neither the light client db nor any of its backups is valid")]
+	NoValidBackup,
+	#[error("No valid light client backup of generation {0}")]
+	BackupNotFound(u64),
 	#[error(transparent)]
 	Other(#[from] Box<dyn std::error::Error + Sync + Send + 'static>),
 }
@@ -18,18 +18,22 @@
 //! Finality for determination of the light client validation.
 
 use crate::{
-	error::Result,
+	error::{JustificationError, Result},
 	grandpa_log,
 	justification::GrandpaJustification,
+	parachain_inclusion::{
+		check_para_head_proof, ParaId, ParachainInclusionProof, PARACHAIN_INCLUSION_ENGINE_ID,
+	},
 	state::{RelayState, ScheduledChangeAtBlock},
-	AuthorityList, Error, NumberFor,
+	AuthorityList, Error, HashingFor, NumberFor,
 };
+use codec::Decode;
 use finality_grandpa::voter_set::VoterSet;
 use log::*;
 pub use sp_consensus_grandpa::SetId;
-use sp_consensus_grandpa::{AuthorityId, ScheduledChange, GRANDPA_ENGINE_ID};
+use sp_consensus_grandpa::{AuthorityId, ConsensusLog, ScheduledChange, GRANDPA_ENGINE_ID};
 use sp_runtime::{
-	generic::Digest,
+	generic::{Digest, OpaqueDigestItemId},
 	traits::{Block as ParentchainBlockTrait, Header as HeaderTrait},
 	EncodedJustification, Justifications,
 };
@@ -40,6 +44,22 @@ pub struct GrandpaFinality;
 #[derive(Default)]
 pub struct ParachainFinality;
 
+/// Finality of a parachain, derived from the GRANDPA finality of its relay chain.
+///
+/// A parachain header is final if it is the parachain head (`Paras::Heads`) in the state of a
+/// relay chain block finalized by GRANDPA, which is proven by a [`ParachainInclusionProof`] in
+/// the header's justifications. Headers without such a proof are only kept as unjustified
+/// headers, and get justified by the next proven descendant. Unlike on a solochain, they don't
+/// become the last finalized header, so that storage is only ever read from proven headers.
+///
+/// The GRANDPA authority set of the relay chain is tracked in the validator set fields of the
+/// [`RelayState`], which are unused for parachains otherwise. It follows the scheduled and
+/// forced changes signalled by verified relay chain headers. The relay chain is expected to
+/// use the same header type as the parachain.
+pub struct RelayChainFinality {
+	para_id: ParaId,
+}
+
 pub trait Finality<Block: ParentchainBlockTrait> {
 	fn validate(
 		&self,
@@ -67,6 +87,125 @@ where
 	}
 }
 
+impl<Block> Finality<Block> for RelayChainFinality
+where
+	Block: ParentchainBlockTrait,
+	NumberFor<Block>: finality_grandpa::BlockNumberOps,
+{
+	fn validate(
+		&self,
+		header: Block::Header,
+		_validator_set: &AuthorityList,
+		_validator_set_id: SetId,
+		justifications: Option<Justifications>,
+		relay: &mut RelayState<Block>,
+	) -> Result<()> {
+		let inclusion_proof = match justifications
+			.and_then(|just| just.into_justification(PARACHAIN_INCLUSION_ENGINE_ID))
+		{
+			Some(encoded) =>
+				ParachainInclusionProof::<Block::Header>::decode(&mut encoded.as_slice())?,
+			None => {
+				relay.push_unjustified_header(header.hash());
+
+				debug!(
+					"Syncing parachain block without relay chain inclusion proof. Amount of unjustified headers: {}",
+					relay.unjustified_headers.len()
+				);
+				return Err(Error::NoJustificationFound)
+			},
+		};
+
+		for (relay_header, justification) in inclusion_proof.authority_set_changes {
+			// Changes we have already applied can't be verified with the current authority set
+			// anymore. Skipping them is safe, as only verified headers change the set.
+			if let Err(e) = Self::verify_relay_header::<Block>(relay, &relay_header, justification)
+			{
+				debug!(
+					"Skipping relay chain authority set change of block {:?}: {:?}",
+					relay_header.number(),
+					e
+				);
+			}
+		}
+
+		Self::verify_relay_header::<Block>(
+			relay,
+			&inclusion_proof.relay_header,
+			inclusion_proof.relay_justification,
+		)?;
+
+		let para_head_hash = check_para_head_proof::<HashingFor<Block>>(
+			inclusion_proof.relay_header.state_root(),
+			inclusion_proof.para_head_proof,
+			self.para_id,
+		)?;
+		if para_head_hash != header.hash() {
+			return Err(Error::ParachainHeadMismatch)
+		}
+
+		Ok(())
+	}
+}
+
+impl RelayChainFinality {
+	pub fn new(para_id: ParaId) -> Self {
+		Self { para_id }
+	}
+
+	/// Verifies that the relay chain header has been finalized by the authority set that is
+	/// current at its height, and schedules the authority set change it signals, if any.
+	///
+	/// The relay state is only changed after the justification has been verified.
+	fn verify_relay_header<Block>(
+		relay: &mut RelayState<Block>,
+		relay_header: &Block::Header,
+		justification: EncodedJustification,
+	) -> Result<()>
+	where
+		Block: ParentchainBlockTrait,
+		NumberFor<Block>: finality_grandpa::BlockNumberOps,
+	{
+		// We don't see every relay chain header, so a scheduled change is due as soon as we
+		// see a header after the one that enacted it.
+		let due_change = relay
+			.scheduled_change
+			.as_ref()
+			.filter(|change| *relay_header.number() > change.at_block)
+			.map(|change| change.next_authority_list.clone());
+		let (authorities, set_id) = match &due_change {
+			Some(next_authorities) =>
+				(next_authorities.clone(), relay.current_validator_set_id + 1),
+			None => (relay.current_validator_set.clone(), relay.current_validator_set_id),
+		};
+
+		let voter_set = VoterSet::new(authorities.into_iter())
+			.ok_or(JustificationError::InvalidAuthoritiesSet)?;
+
+		GrandpaFinality::verify_grandpa_proof::<Block>(
+			justification,
+			relay_header.hash(),
+			*relay_header.number(),
+			set_id,
+			&voter_set,
+		)?;
+
+		if let Some(next_authorities) = due_change {
+			relay.scheduled_change = None;
+			relay.current_validator_set = next_authorities;
+			relay.current_validator_set_id = set_id;
+		}
+
+		if let Some(change) = authority_set_change::<Block>(relay_header.digest()) {
+			relay.scheduled_change = Some(ScheduledChangeAtBlock {
+				at_block: change.delay + *relay_header.number(),
+				next_authority_list: change.next_authorities,
+			})
+		}
+		Ok(())
+	}
+}
+
 impl<Block> Finality<Block> for GrandpaFinality
 where
 	Block: ParentchainBlockTrait,
@@ -105,7 +244,7 @@ where
 					// FIXME: Printing error upon invalid justification, but this will need a better fix
 					// see issue #353
 					error!("Block {:?} contained invalid justification: {:?}", block_num, err);
-					relay.unjustified_headers.push(block_hash);
+					relay.push_unjustified_header(block_hash);
 					relay.set_last_finalized_block_header(header);
 					return Err(err)
 				}
@@ -114,7 +253,7 @@ where
 				Ok(())
 			},
 			None => {
-				relay.unjustified_headers.push(block_hash);
+				relay.push_unjustified_header(block_hash);
 				relay.set_last_finalized_block_header(header);
 
 				debug!(
@@ -185,3 +324,157 @@ fn pending_change<Block: ParentchainBlockTrait>(
 ) -> Option<ScheduledChange<NumberFor<Block>>> {
 	grandpa_log::<Block>(digest).and_then(|log| log.try_into_change())
 }
+
+/// Authority set change signalled in the digest of a finalized header, forced or scheduled.
+///
+/// A forced change is enacted `delay` blocks after the signalling block without waiting for
+/// its finality. As the signalling header has been finalized here, it is enacted like a
+/// scheduled change. A forced change whose header has never been finalized by the previous
+/// authority set can't be verified, and the light client has to be re-initialized.
+fn authority_set_change<Block: ParentchainBlockTrait>(
+	digest: &Digest,
+) -> Option<ScheduledChange<NumberFor<Block>>> {
+	let id = OpaqueDigestItemId::Consensus(&GRANDPA_ENGINE_ID);
+	let mut scheduled_change = None;
+	for log in digest
+		.logs()
+		.iter()
+		.filter_map(|log| log.try_to::<ConsensusLog<NumberFor<Block>>>(id))
+	{
+		match log {
+			ConsensusLog::ForcedChange(_, change) => {
+				warn!("Following forced relay chain authority set change: {:?}", change);
+				return Some(change)
+			},
+			ConsensusLog::ScheduledChange(change) => scheduled_change = Some(change),
+			_ => {},
+		}
+	}
+	scheduled_change
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::state::MAX_UNJUSTIFIED_HEADERS;
+	use codec::Encode;
+	use itc_parentchain_test::{Block, Header, ParentchainHeaderBuilder};
+	use sp_core::ed25519;
+	use sp_runtime::{DigestItem, OpaqueExtrinsic};
+
+	type TestBlock = Block<Header, OpaqueExtrinsic>;
+
+	#[test]
+	fn parachain_header_without_inclusion_proof_is_imported_unjustified() {
+		let genesis = ParentchainHeaderBuilder::default().build();
+		let header = ParentchainHeaderBuilder::default()
+			.with_number(1)
+			.with_parent_hash(genesis.hash())
+			.build();
+		let mut relay = RelayState::<TestBlock>::new(genesis.clone(), vec![]);
+
+		let result =
+			RelayChainFinality::new(7).validate(header.clone(), &vec![], 0, None, &mut relay);
+
+		assert!(matches!(result, Err(Error::NoJustificationFound)));
+		assert_eq!(relay.unjustified_headers, vec![header.hash()]);
+		assert_eq!(relay.last_finalized_block_header, genesis);
+		assert_eq!(relay.last_imported_header_hash(), header.hash());
+	}
+
+	#[test]
+	fn unjustified_parachain_headers_are_bounded() {
+		let genesis = ParentchainHeaderBuilder::default().build();
+		let mut relay = RelayState::<TestBlock>::new(genesis, vec![]);
+		let headers: Vec<_> = (1..=MAX_UNJUSTIFIED_HEADERS as u32 + 2)
+			.map(|number| ParentchainHeaderBuilder::default().with_number(number).build())
+			.collect();
+
+		for header in headers.iter() {
+			let _ =
+				RelayChainFinality::new(7).validate(header.clone(), &vec![], 0, None, &mut relay);
+		}
+
+		assert_eq!(relay.unjustified_headers.len(), MAX_UNJUSTIFIED_HEADERS);
+		assert_eq!(relay.unjustified_headers[0], headers[2].hash());
+		assert_eq!(relay.last_imported_header_hash(), headers.last().unwrap().hash());
+	}
+
+	#[test]
+	fn forced_authority_set_change_takes_precedence() {
+		let scheduled = ScheduledChange { next_authorities: authorities(1), delay: 5 };
+		let forced = ScheduledChange { next_authorities: authorities(2), delay: 0 };
+		let digest = Digest {
+			logs: vec![
+				grandpa_digest_item(ConsensusLog::ScheduledChange(scheduled.clone())),
+				grandpa_digest_item(ConsensusLog::ForcedChange(3, forced.clone())),
+			],
+		};
+
+		assert_eq!(authority_set_change::<TestBlock>(&digest), Some(forced));
+		assert_eq!(
+			authority_set_change::<TestBlock>(&Digest {
+				logs: vec![grandpa_digest_item(ConsensusLog::ScheduledChange(scheduled.clone()))]
+			}),
+			Some(scheduled)
+		);
+	}
+
+	fn authorities(seed: u8) -> AuthorityList {
+		vec![(AuthorityId::from(ed25519::Public::from_raw([seed; 32])), 1)]
+	}
+
+	fn grandpa_digest_item(log: ConsensusLog<NumberFor<TestBlock>>) -> DigestItem {
+		DigestItem::Consensus(GRANDPA_ENGINE_ID, log.encode())
+	}
+
+	#[test]
+	fn inclusion_proof_is_rejected_without_relay_chain_authorities() {
+		let genesis = ParentchainHeaderBuilder::default().build();
+		let header = ParentchainHeaderBuilder::default()
+			.with_number(1)
+			.with_parent_hash(genesis.hash())
+			.build();
+		let mut relay = RelayState::<TestBlock>::new(genesis.clone(), vec![]);
+		let inclusion_proof = ParachainInclusionProof {
+			authority_set_changes: vec![],
+			relay_header: ParentchainHeaderBuilder::default().build(),
+			relay_justification: vec![],
+			para_head_proof: vec![],
+		};
+
+		let result = RelayChainFinality::new(7).validate(
+			header,
+			&vec![],
+			0,
+			Some(inclusion_proof.append_to(None)),
+			&mut relay,
+		);
+
+		assert!(matches!(
+			result,
+			Err(Error::InvalidFinalityProof(JustificationError::InvalidAuthoritiesSet))
+		));
+		assert_eq!(relay.last_finalized_block_header, genesis);
+	}
+
+	#[test]
+	fn due_authority_set_change_is_not_applied_with_invalid_justification() {
+		let genesis = ParentchainHeaderBuilder::default().build();
+		let current_authorities =
+			vec![(AuthorityId::from(ed25519::Public::from_raw([1u8; 32])), 1)];
+		let next_authorities = vec![(AuthorityId::from(ed25519::Public::from_raw([2u8; 32])), 1)];
+		let mut relay = RelayState::<TestBlock>::new(genesis, current_authorities.clone());
+		relay.scheduled_change =
+			Some(ScheduledChangeAtBlock { at_block: 5, next_authority_list: next_authorities });
+		let relay_header = ParentchainHeaderBuilder::default().with_number(10).build();
+
+		let result =
+			RelayChainFinality::verify_relay_header::<TestBlock>(&mut relay, &relay_header, vec![]);
+
+		assert!(result.is_err());
+		assert!(relay.scheduled_change.is_some());
+		assert_eq!(relay.current_validator_set, current_authorities);
+		assert_eq!(relay.current_validator_set_id, 0);
+	}
+}
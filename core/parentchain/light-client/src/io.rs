@@ -16,26 +16,37 @@
 */
 
 use crate::{
+	backup::LightClientBackup,
 	error::{Error, Result},
-	finality::{Finality, GrandpaFinality, ParachainFinality},
-	light_client_init_params::{GrandpaParams, SimpleParams},
-	light_validation::{check_validator_set_proof, LightValidation},
+	finality::{Finality, GrandpaFinality, ParachainFinality, RelayChainFinality},
+	light_client_init_params::{
+		GrandpaParams, RelayChainAnchor, RelayChainBackedParams, SimpleParams,
+	},
+	light_validation::{
+		check_validator_set_and_id_proof, check_validator_set_proof, LightValidation,
+	},
+	parachain_inclusion::ParaId,
 	state::RelayState,
 	LightClientSealing, LightClientState, LightValidationState, NumberFor, Validator,
 };
 use codec::{Decode, Encode};
-use core::{fmt::Debug, marker::PhantomData};
+use core::{
+	fmt::Debug,
+	marker::PhantomData,
+	sync::atomic::{AtomicU32, AtomicU64, Ordering},
+};
 use itp_ocall_api::EnclaveOnChainOCallApi;
 use itp_sgx_io::{seal, unseal};
-use itp_types::parentchain::{IdentifyParentchain, ParentchainId};
+use itp_types::parentchain::{BlockNumber, IdentifyParentchain, ParentchainId};
 use log::*;
-use sp_runtime::traits::{Block, Header};
+use sp_runtime::traits::{Block, Header, UniqueSaturatedInto};
 use std::{
 	boxed::Box,
-	fs,
+	format,
 	path::{Path, PathBuf},
 	sgxfs::SgxFile,
 	sync::Arc,
+	vec::Vec,
 };
 
 #[cfg(feature = "sgx")]
@@ -46,26 +57,46 @@ use std::sync::RwLock;
 
 pub const DB_FILE: &str = "db.bin";
 pub const BACKUP_FILE: &str = "db.bin.backup";
+/// Sealed [`RelayChainAnchor`] of a relay chain backed light client.
+pub const RELAY_CHAIN_ANCHOR_FILE: &str = "relay_chain_anchor.bin";
+/// Number of rotating backups of the light client state.
+pub const BACKUP_COUNT: u32 = 5;
 
 #[derive(Clone, Debug)]
 pub struct LightClientStateSeal<B, LightClientState> {
 	base_path: PathBuf,
 	db_path: PathBuf,
-	backup_path: PathBuf,
 	parentchain_id: ParentchainId,
+	/// Number of finalized blocks between two backups.
+	backup_interval: BlockNumber,
+	/// Generation of the newest backup, 0 if there is none.
+	latest_backup_generation: Arc<AtomicU64>,
+	/// The state is backed up once its latest finalized block reaches this block.
+	next_backup_block: Arc<AtomicU32>,
 	_phantom: PhantomData<(B, LightClientState)>,
 }
 
 impl<B, L> LightClientStateSeal<B, L> {
-	pub fn new(base_path: PathBuf, parentchain_id: ParentchainId) -> Result<Self> {
+	pub fn new(
+		base_path: PathBuf,
+		parentchain_id: ParentchainId,
+		backup_interval: BlockNumber,
+	) -> Result<Self> {
 		std::fs::create_dir_all(&base_path)?;
-		Ok(Self {
+		let seal = Self {
 			base_path: base_path.clone(),
-			db_path: base_path.clone().join(DB_FILE),
-			backup_path: base_path.join(BACKUP_FILE),
+			db_path: base_path.join(DB_FILE),
 			parentchain_id,
+			backup_interval,
+			latest_backup_generation: Arc::new(AtomicU64::new(0)),
+			next_backup_block: Arc::new(AtomicU32::new(0)),
 			_phantom: Default::default(),
-		})
+		};
+		if let Some(backup) = seal.newest_backup() {
+			seal.latest_backup_generation.store(backup.generation, Ordering::SeqCst);
+			seal.schedule_next_backup(backup.latest_finalized_block);
+		}
+		Ok(seal)
 	}
 
 	pub fn base_path(&self) -> &Path {
@@ -76,18 +107,86 @@ impl<B, L> LightClientStateSeal<B, L> {
 		&self.db_path
 	}
 
-	pub fn backup_path(&self) -> &Path {
-		&self.backup_path
+	pub fn backup_path(&self, slot: u32) -> PathBuf {
+		self.base_path.join(format!("{}.{}", BACKUP_FILE, slot))
 	}
 
-	pub fn backup(&self) -> Result<()> {
-		if self.db_path().exists() {
-			let _bytes = fs::copy(self.db_path(), self.backup_path())?;
-		} else {
-			info!("{} does not exist yet, skipping backup...", self.db_path().display())
-		}
+	/// Seals `encoded_state` into the slot of the next backup generation, overwriting the
+	/// oldest backup once all slots are in use.
+	pub fn backup(&self, encoded_state: &[u8], latest_finalized_block: BlockNumber) -> Result<()> {
+		let generation = self.latest_backup_generation.load(Ordering::SeqCst) + 1;
+		let backup =
+			LightClientBackup::new(generation, latest_finalized_block, encoded_state.to_vec());
+		seal(&backup.encode(), self.backup_path(Self::slot(generation)))?;
+		self.latest_backup_generation.store(generation, Ordering::SeqCst);
+		self.schedule_next_backup(latest_finalized_block);
+		Ok(())
+	}
+
+	/// Whether a state with the given latest finalized block is due to be backed up.
+	pub fn is_backup_due(&self, latest_finalized_block: BlockNumber) -> bool {
+		latest_finalized_block >= self.next_backup_block.load(Ordering::SeqCst)
+	}
+
+	fn schedule_next_backup(&self, latest_backed_up_block: BlockNumber) {
+		self.next_backup_block
+			.store(latest_backed_up_block.saturating_add(self.backup_interval), Ordering::SeqCst);
+	}
+
+	/// Backups of all occupied slots. `None` for a slot that is corrupted.
+	pub fn backups(&self) -> Vec<(u32, Option<LightClientBackup>)> {
+		(0..BACKUP_COUNT)
+			.filter(|slot| self.backup_path(*slot).exists())
+			.map(|slot| (slot, self.read_backup(slot)))
+			.collect()
+	}
+
+	/// The intact backup with the highest generation.
+	pub fn newest_backup(&self) -> Option<LightClientBackup> {
+		self.backups()
+			.into_iter()
+			.filter_map(|(_, backup)| backup)
+			.max_by_key(|backup| backup.generation)
+	}
+
+	/// The intact backup of the given generation.
+	pub fn backup_of_generation(&self, generation: u64) -> Result<LightClientBackup> {
+		self.read_backup(Self::slot(generation))
+			.filter(|backup| backup.generation == generation)
+			.ok_or(Error::BackupNotFound(generation))
+	}
+
+	/// Replaces the light client db with the backup of the given generation.
+	pub fn restore_backup(&self, generation: u64) -> Result<()> {
+		let backup = self.backup_of_generation(generation)?;
+		info!(
+			"[{:?}] Restoring light client state from backup generation {}",
+			self.parentchain_id, generation
+		);
+		seal(&backup.state, self.db_path())?;
+		self.schedule_next_backup(backup.latest_finalized_block);
 		Ok(())
 	}
+
+	fn read_backup(&self, slot: u32) -> Option<LightClientBackup> {
+		let backup = unseal(self.backup_path(slot))
+			.map_err(Error::from)
+			.and_then(|encoded| Ok(LightClientBackup::decode(&mut encoded.as_slice())?));
+		match backup {
+			Ok(backup) => Some(backup),
+			Err(e) => {
+				warn!(
+					"[{:?}] Could not read light client backup slot {}: {:?}",
+					self.parentchain_id, slot, e
+				);
+				None
+			},
+		}
+	}
+
+	fn slot(generation: u64) -> u32 {
+		(generation % BACKUP_COUNT as u64) as u32
+	}
 }
 
 impl<B, L> IdentifyParentchain for LightClientStateSeal<B, L> {
@@ -96,39 +195,61 @@ impl<B, L> IdentifyParentchain for LightClientStateSeal<B, L> {
 	}
 }
 
-impl<B: Block, LightClientState: Decode + Encode + Debug> LightClientSealing
-	for LightClientStateSeal<B, LightClientState>
+impl<B, LightClientState> LightClientSealing for LightClientStateSeal<B, LightClientState>
+where
+	B: Block,
+	LightClientState: Decode + Encode + Debug + crate::LightClientState<B>,
 {
 	type LightClientState = LightClientState;
 
 	fn seal(&self, unsealed: &LightClientState) -> Result<()> {
-		trace!(
-			"[{:?}] Backup light client state to {}",
-			self.parentchain_id,
-			self.backup_path().display()
-		);
-
-		if let Err(e) = self.backup() {
-			warn!(
-				"[{:?}] Could not backup previous light client state: Error: {}",
-				self.parentchain_id, e
-			);
-		};
-
 		trace!(
 			"[{:?}] Seal light client State. Current state: {:?}",
 			self.parentchain_id,
 			unsealed
 		);
-		Ok(unsealed.using_encoded(|bytes| seal(bytes, self.db_path()))?)
+		let encoded = unsealed.encode();
+		seal(&encoded, self.db_path())?;
+
+		let latest_finalized_block: BlockNumber =
+			(*unsealed.latest_finalized_header()?.number()).unique_saturated_into();
+		if self.is_backup_due(latest_finalized_block) {
+			if let Err(e) = self.backup(&encoded, latest_finalized_block) {
+				warn!(
+					"[{:?}] Could not backup light client state: Error: {}",
+					self.parentchain_id, e
+				);
+			}
+		}
+		Ok(())
 	}
 
+	/// Unseals the light client db. Falls back to the newest intact backup, and restores it,
+	/// if the db is corrupted.
 	fn unseal(&self) -> Result<LightClientState> {
-		Ok(unseal(self.db_path()).map(|b| Decode::decode(&mut b.as_slice()))??)
+		let db_state = unseal(self.db_path())
+			.map_err(Error::from)
+			.and_then(|encoded| Ok(LightClientState::decode(&mut encoded.as_slice())?));
+		let error = match db_state {
+			Ok(state) => return Ok(state),
+			Err(e) => e,
+		};
+
+		error!(
+			"[{:?}] Could not unseal light client db {}: {:?}. Falling back to the newest backup",
+			self.parentchain_id,
+			self.db_path().display(),
+			error
+		);
+		let backup = self.newest_backup().ok_or(Error::NoValidBackup)?;
+		let state = LightClientState::decode(&mut backup.state.as_slice())?;
+		self.restore_backup(backup.generation)?;
+		Ok(state)
 	}
 
+	/// The db exists, or it can be restored from a backup.
 	fn exists(&self) -> bool {
-		SgxFile::open(self.db_path()).is_ok()
+		SgxFile::open(self.db_path()).is_ok() || self.newest_backup().is_some()
 	}
 
 	fn path(&self) -> &Path {
@@ -145,22 +266,45 @@ pub struct LightClientStateSealSync<B, LightClientState> {
 }
 
 impl<B, LightClientState> LightClientStateSealSync<B, LightClientState> {
-	pub fn new(base_path: PathBuf, parentchain_id: ParentchainId) -> Result<Self> {
+	pub fn new(
+		base_path: PathBuf,
+		parentchain_id: ParentchainId,
+		backup_interval: BlockNumber,
+	) -> Result<Self> {
 		Ok(Self {
-			seal: LightClientStateSeal::new(base_path, parentchain_id)?,
+			seal: LightClientStateSeal::new(base_path, parentchain_id, backup_interval)?,
 			_rw_lock: RwLock::new(()),
 		})
 	}
 }
 
+impl<B, LightClientState> LightClientStateSealSync<B, LightClientState> {
+	pub fn backups(&self) -> Result<Vec<(u32, Option<LightClientBackup>)>> {
+		let _lock = self._rw_lock.read().map_err(|_| Error::PoisonedLock)?;
+		Ok(self.seal.backups())
+	}
+
+	pub fn backup_of_generation(&self, generation: u64) -> Result<LightClientBackup> {
+		let _lock = self._rw_lock.read().map_err(|_| Error::PoisonedLock)?;
+		self.seal.backup_of_generation(generation)
+	}
+
+	pub fn restore_backup(&self, generation: u64) -> Result<()> {
+		let _lock = self._rw_lock.write().map_err(|_| Error::PoisonedLock)?;
+		self.seal.restore_backup(generation)
+	}
+}
+
 impl<B, LightClientState> IdentifyParentchain for LightClientStateSealSync<B, LightClientState> {
 	fn parentchain_id(&self) -> ParentchainId {
 		self.seal.parentchain_id
 	}
 }
 
-impl<B: Block, LightClientState: Decode + Encode + Debug> LightClientSealing
-	for LightClientStateSealSync<B, LightClientState>
+impl<B, LightClientState> LightClientSealing for LightClientStateSealSync<B, LightClientState>
+where
+	B: Block,
+	LightClientState: Decode + Encode + Debug + crate::LightClientState<B>,
 {
 	type LightClientState = LightClientState;
 
@@ -289,6 +433,156 @@ where
 	Ok(validator)
 }
 
+/// Initializes a relay chain backed light client.
+///
+/// The relay chain authorities are initialized from the `params` only once, which seals the
+/// [`RelayChainAnchor`] next to the light client db. Afterwards, they are only changed by
+/// verified relay chain headers, and a start with another relay chain is refused.
+pub fn read_or_init_relay_chain_backed_validator<B, OCallApi, LightClientSeal>(
+	params: RelayChainBackedParams<B::Header>,
+	ocall_api: Arc<OCallApi>,
+	seal: &LightClientSeal,
+	parentchain_id: ParentchainId,
+) -> Result<LightValidation<B, OCallApi>>
+where
+	B: Block,
+	NumberFor<B>: finality_grandpa::BlockNumberOps,
+	OCallApi: EnclaveOnChainOCallApi,
+	LightClientSeal:
+		LightClientSealing<LightClientState = LightValidationState<B>> + IdentifyParentchain,
+{
+	let anchor_path = relay_chain_anchor_path(seal.path());
+	let anchor = unseal_relay_chain_anchor(&anchor_path)?;
+	if let Some(anchor) = &anchor {
+		if anchor.genesis_hash != params.relay_genesis_hash {
+			return Err(Error::RelayChainMismatch(params.relay_genesis_hash))
+		}
+	}
+
+	let validation_state = if seal.exists() {
+		let validation_state = seal.unseal()?;
+		let genesis_hash = validation_state.genesis_hash()?;
+		if genesis_hash != params.genesis_header.hash() {
+			info!(
+				"Previous light client db belongs to another parentchain genesis. Creating new: {:?}",
+				genesis_hash
+			);
+			None
+		} else if validation_state.get_relay().current_validator_set.is_empty() {
+			// A light client that has been following the parachain without relay chain so far.
+			info!(
+				"[{:?}] Found already initialized light client without relay chain authorities",
+				seal.parentchain_id()
+			);
+			Some(validation_state)
+		} else {
+			info!(
+				"[{:?}] Found already initialized light client with Genesis Hash: {:?}",
+				seal.parentchain_id(),
+				genesis_hash
+			);
+			if anchor.is_none() {
+				seal_relay_chain_anchor(
+					&anchor_path,
+					&RelayChainAnchor { genesis_hash: params.relay_genesis_hash },
+				)?;
+			}
+			return init_and_seal_relay_chain_backed_validator(
+				ocall_api,
+				validation_state,
+				params.para_id,
+				seal,
+				parentchain_id,
+			)
+		}
+	} else {
+		info!(
+			"[{:?}] ChainRelay DB not found, creating new! {}",
+			seal.parentchain_id(),
+			seal.path().display()
+		);
+		None
+	};
+
+	// The relay chain authorities are initialized from the params.
+	if anchor.is_some() {
+		return Err(Error::RelayChainAlreadyAnchored)
+	}
+	check_validator_set_and_id_proof::<B>(
+		params.relay_header.state_root(),
+		params.relay_authority_proof,
+		&params.relay_authorities,
+		params.relay_authority_set_id,
+	)?;
+	info!(
+		"[{:?}] Start following the relay chain from block {:?}",
+		seal.parentchain_id(),
+		params.relay_header.number()
+	);
+	let mut validation_state = validation_state
+		.unwrap_or_else(|| RelayState::new(params.genesis_header.clone(), vec![]).into());
+	let relay = validation_state.get_relay_mut();
+	relay.current_validator_set = params.relay_authorities;
+	relay.current_validator_set_id = params.relay_authority_set_id;
+	relay.scheduled_change = None;
+
+	let validator = init_and_seal_relay_chain_backed_validator(
+		ocall_api,
+		validation_state,
+		params.para_id,
+		seal,
+		parentchain_id,
+	)?;
+	seal_relay_chain_anchor(
+		&anchor_path,
+		&RelayChainAnchor { genesis_hash: params.relay_genesis_hash },
+	)?;
+	Ok(validator)
+}
+
+fn init_and_seal_relay_chain_backed_validator<B, OCallApi, LightClientSeal>(
+	ocall_api: Arc<OCallApi>,
+	state: LightValidationState<B>,
+	para_id: ParaId,
+	seal: &LightClientSeal,
+	parentchain_id: ParentchainId,
+) -> Result<LightValidation<B, OCallApi>>
+where
+	B: Block,
+	NumberFor<B>: finality_grandpa::BlockNumberOps,
+	OCallApi: EnclaveOnChainOCallApi,
+	LightClientSeal:
+		LightClientSealing<LightClientState = LightValidationState<B>> + IdentifyParentchain,
+{
+	let validator = init_relay_chain_backed_validator::<B, OCallApi>(
+		ocall_api,
+		state,
+		para_id,
+		parentchain_id,
+	)?;
+	info!("[{:?}] light client state: {:?}", seal.parentchain_id(), validator);
+
+	seal.seal(validator.get_state())?;
+	Ok(validator)
+}
+
+fn relay_chain_anchor_path(db_path: &Path) -> PathBuf {
+	db_path.with_file_name(RELAY_CHAIN_ANCHOR_FILE)
+}
+
+fn unseal_relay_chain_anchor(path: &Path) -> Result<Option<RelayChainAnchor>> {
+	if SgxFile::open(path).is_err() {
+		return Ok(None)
+	}
+	let encoded = unseal(path)?;
+	Ok(Some(RelayChainAnchor::decode(&mut encoded.as_slice())?))
+}
+
+fn seal_relay_chain_anchor(path: &Path, anchor: &RelayChainAnchor) -> Result<()> {
+	info!("Sealing relay chain anchor {:?}", anchor);
+	Ok(seal(&anchor.encode(), path)?)
+}
+
 fn init_grandpa_validator<B, OCallApi>(
 	ocall_api: Arc<OCallApi>,
 	state: LightValidationState<B>,
@@ -324,30 +618,68 @@ where
 	Ok(validator)
 }
 
+fn init_relay_chain_backed_validator<B, OCallApi>(
+	ocall_api: Arc<OCallApi>,
+	state: LightValidationState<B>,
+	para_id: ParaId,
+	parentchain_id: ParentchainId,
+) -> Result<LightValidation<B, OCallApi>>
+where
+	B: Block,
+	NumberFor<B>: finality_grandpa::BlockNumberOps,
+	OCallApi: EnclaveOnChainOCallApi,
+{
+	let finality: Arc<Box<dyn Finality<B> + Sync + Send + 'static>> =
+		Arc::new(Box::new(RelayChainFinality::new(para_id)));
+
+	let validator = LightValidation::<B, OCallApi>::new(ocall_api, finality, state, parentchain_id);
+	Ok(validator)
+}
+
 #[cfg(feature = "test")]
 pub mod sgx_tests {
-	use super::{read_or_init_parachain_validator, Arc, LightClientStateSeal, RelayState};
+	use super::{
+		read_or_init_parachain_validator, read_or_init_relay_chain_backed_validator,
+		relay_chain_anchor_path, seal_relay_chain_anchor, unseal, Arc, LightClientStateSeal,
+		RelayState, BACKUP_COUNT,
+	};
 	use crate::{
-		light_client_init_params::SimpleParams, LightClientSealing, LightClientState,
-		LightValidationState,
+		error::Error,
+		light_client_init_params::{RelayChainAnchor, RelayChainBackedParams, SimpleParams},
+		LightClientSealing, LightClientState, LightValidationState,
 	};
+	use codec::Encode;
 	use itc_parentchain_test::{Block, Header, ParentchainHeaderBuilder};
 	use itp_sgx_temp_dir::TempDir;
 	use itp_test::mock::onchain_mock::OnchainMock;
-	use itp_types::parentchain::ParentchainId;
+	use itp_types::parentchain::{BlockNumber, ParentchainId};
+	use sp_core::H256;
 	use sp_runtime::OpaqueExtrinsic;
+	use std::fs;
 
 	type TestBlock = Block<Header, OpaqueExtrinsic>;
-	type TestSeal = LightClientStateSeal<TestBlock, LightValidationState<TestBlock>>;
+	type TestState = LightValidationState<TestBlock>;
+	type TestSeal = LightClientStateSeal<TestBlock, TestState>;
+
+	const BACKUP_INTERVAL: BlockNumber = 10;
 
 	fn default_simple_params() -> SimpleParams<Header> {
 		SimpleParams { genesis_header: ParentchainHeaderBuilder::default().build() }
 	}
 
+	fn test_seal(temp_dir: &TempDir, parentchain_id: ParentchainId) -> TestSeal {
+		TestSeal::new(temp_dir.path().to_path_buf(), parentchain_id, BACKUP_INTERVAL).unwrap()
+	}
+
+	fn state_at(number: BlockNumber) -> TestState {
+		let header = ParentchainHeaderBuilder::default().with_number(number).build();
+		RelayState::new(header, Default::default()).into()
+	}
+
 	pub fn init_parachain_light_client_works() {
 		let parachain_params = default_simple_params();
 		let temp_dir = TempDir::with_prefix("init_parachain_light_client_works").unwrap();
-		let seal = TestSeal::new(temp_dir.path().to_path_buf(), ParentchainId::Integritee).unwrap();
+		let seal = test_seal(&temp_dir, ParentchainId::Integritee);
 
 		let validator = read_or_init_parachain_validator::<TestBlock, OnchainMock, _>(
 			parachain_params.clone(),
@@ -367,19 +699,139 @@ pub mod sgx_tests {
 	}
 
 	pub fn sealing_creates_backup() {
-		let params = default_simple_params();
 		let temp_dir = TempDir::with_prefix("sealing_creates_backup").unwrap();
-		let seal = TestSeal::new(temp_dir.path().to_path_buf(), ParentchainId::Integritee).unwrap();
-		let state = RelayState::new(params.genesis_header, Default::default()).into();
+		let seal = test_seal(&temp_dir, ParentchainId::Integritee);
+		let state = state_at(3);
 
 		seal.seal(&state).unwrap();
 		let unsealed = seal.unseal().unwrap();
 
 		assert_eq!(state, unsealed);
+		assert!(seal.backup_path(1).exists());
+		let backup = seal.newest_backup().unwrap();
+		assert_eq!(backup.state, state.encode());
+		assert_eq!(backup.latest_finalized_block, 3);
+	}
+
+	pub fn backups_are_created_at_block_interval() {
+		let temp_dir = TempDir::with_prefix("backups_are_created_at_block_interval").unwrap();
+		let seal = test_seal(&temp_dir, ParentchainId::Integritee);
+
+		seal.seal(&state_at(1)).unwrap();
+		seal.seal(&state_at(BACKUP_INTERVAL)).unwrap();
+		assert_eq!(seal.newest_backup().unwrap().generation, 1);
+
+		seal.seal(&state_at(BACKUP_INTERVAL + 1)).unwrap();
+		let backup = seal.newest_backup().unwrap();
+		assert_eq!(backup.generation, 2);
+		assert_eq!(backup.latest_finalized_block, BACKUP_INTERVAL + 1);
+
+		// The interval continues after a restart.
+		let seal = test_seal(&temp_dir, ParentchainId::Integritee);
+		seal.seal(&state_at(2 * BACKUP_INTERVAL)).unwrap();
+		assert_eq!(seal.newest_backup().unwrap().generation, 2);
+	}
+
+	pub fn backups_rotate_through_slots() {
+		let temp_dir = TempDir::with_prefix("backups_rotate_through_slots").unwrap();
+		let seal = test_seal(&temp_dir, ParentchainId::Integritee);
+
+		for i in 0..BACKUP_COUNT + 2 {
+			seal.seal(&state_at(i * BACKUP_INTERVAL)).unwrap();
+		}
+
+		let backups = seal.backups();
+		assert_eq!(backups.len(), BACKUP_COUNT as usize);
+		let oldest = backups.iter().filter_map(|(_, b)| b.as_ref()).map(|b| b.generation).min();
+		assert_eq!(oldest, Some(3));
+		assert_eq!(seal.newest_backup().unwrap().generation, BACKUP_COUNT as u64 + 2);
 
-		// The first seal operation doesn't create a backup, as there is nothing to backup.
-		seal.seal(&unsealed).unwrap();
-		assert!(seal.backup_path().exists())
+		// The generation continues after a restart.
+		let seal = test_seal(&temp_dir, ParentchainId::Integritee);
+		seal.seal(&state_at((BACKUP_COUNT + 2) * BACKUP_INTERVAL)).unwrap();
+		assert_eq!(seal.newest_backup().unwrap().generation, BACKUP_COUNT as u64 + 3);
+	}
+
+	pub fn corrupted_db_falls_back_to_newest_backup() {
+		let temp_dir = TempDir::with_prefix("corrupted_db_falls_back_to_newest_backup").unwrap();
+		let seal = test_seal(&temp_dir, ParentchainId::Integritee);
+		let state = state_at(0);
+		seal.seal(&state).unwrap();
+
+		fs::write(seal.db_path(), b"corrupted").unwrap();
+
+		assert!(seal.exists());
+		assert_eq!(seal.unseal().unwrap(), state);
+		// The backup has been restored to the db.
+		assert_eq!(unseal(seal.db_path()).unwrap(), state.encode());
+	}
+
+	pub fn restore_backup_rolls_back_state() {
+		let temp_dir = TempDir::with_prefix("restore_backup_rolls_back_state").unwrap();
+		let seal = test_seal(&temp_dir, ParentchainId::Integritee);
+		let old_state = state_at(0);
+		let mut new_state = state_at(BACKUP_INTERVAL);
+		new_state.get_relay_mut().current_validator_set_id = 1;
+
+		seal.seal(&old_state).unwrap();
+		seal.seal(&new_state).unwrap();
+		assert_eq!(seal.unseal().unwrap(), new_state);
+
+		seal.restore_backup(1).unwrap();
+		assert_eq!(seal.unseal().unwrap(), old_state);
+		assert!(seal.restore_backup(3).is_err());
+	}
+
+	pub fn relay_chain_backed_light_client_refuses_another_relay_chain() {
+		let temp_dir =
+			TempDir::with_prefix("relay_chain_backed_light_client_refuses_another_relay_chain")
+				.unwrap();
+		let seal = test_seal(&temp_dir, ParentchainId::TargetA);
+		let anchor = RelayChainAnchor { genesis_hash: H256::repeat_byte(1) };
+		seal_relay_chain_anchor(&relay_chain_anchor_path(seal.path()), &anchor).unwrap();
+
+		let result = read_or_init_relay_chain_backed_validator::<TestBlock, OnchainMock, _>(
+			relay_chain_backed_params(H256::repeat_byte(2)),
+			Arc::new(OnchainMock::default()),
+			&seal,
+			ParentchainId::TargetA,
+		);
+
+		assert!(matches!(result, Err(Error::RelayChainMismatch(_))));
+	}
+
+	pub fn relay_chain_authorities_are_only_initialized_once() {
+		let temp_dir =
+			TempDir::with_prefix("relay_chain_authorities_are_only_initialized_once").unwrap();
+		let seal = test_seal(&temp_dir, ParentchainId::TargetA);
+		let params = relay_chain_backed_params(H256::repeat_byte(1));
+		// The relay chain authorities of the sealed light client are missing.
+		let state: TestState = RelayState::new(params.genesis_header.clone(), vec![]).into();
+		seal.seal(&state).unwrap();
+		let anchor = RelayChainAnchor { genesis_hash: params.relay_genesis_hash };
+		seal_relay_chain_anchor(&relay_chain_anchor_path(seal.path()), &anchor).unwrap();
+
+		let result = read_or_init_relay_chain_backed_validator::<TestBlock, OnchainMock, _>(
+			params,
+			Arc::new(OnchainMock::default()),
+			&seal,
+			ParentchainId::TargetA,
+		);
+
+		assert!(matches!(result, Err(Error::RelayChainAlreadyAnchored)));
+		assert_eq!(seal.unseal().unwrap(), state);
+	}
+
+	fn relay_chain_backed_params(relay_genesis_hash: H256) -> RelayChainBackedParams<Header> {
+		RelayChainBackedParams::new(
+			ParentchainHeaderBuilder::default().build(),
+			7,
+			relay_genesis_hash,
+			ParentchainHeaderBuilder::default().with_number(100).build(),
+			vec![],
+			0,
+			vec![],
+		)
 	}
 
 	// Todo #1293: add a unit test for the grandpa validator, but this needs a little effort for
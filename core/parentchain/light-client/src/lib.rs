@@ -40,6 +40,7 @@ use sp_runtime::{
 };
 use std::{path::Path, vec::Vec};
 
+pub mod backup;
 pub mod concurrent_access;
 pub mod error;
 pub mod finality;
@@ -47,6 +48,7 @@ pub mod justification;
 pub mod light_client_init_params;
 pub mod light_validation;
 pub mod light_validation_state;
+pub mod parachain_inclusion;
 pub mod state;
 
 #[cfg(all(not(feature = "std"), feature = "sgx"))]
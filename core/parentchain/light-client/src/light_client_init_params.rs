@@ -16,8 +16,10 @@
 
 */
 
+use crate::parachain_inclusion::ParaId;
 use codec::{Decode, Encode};
-use sp_consensus_grandpa::AuthorityList;
+use sp_consensus_grandpa::{AuthorityList, SetId};
+use sp_core::H256;
 use std::vec::Vec;
 
 #[derive(Encode, Decode, Clone)]
@@ -47,3 +49,52 @@ impl<Header> SimpleParams<Header> {
 		Self { genesis_header }
 	}
 }
+
+/// Parameters of a parachain light client that follows the GRANDPA finality of the relay chain.
+///
+/// The relay chain is followed from the finalized `relay_header` on. Its GRANDPA authorities
+/// and their set id are checked against the `relay_authority_proof` of that header's state.
+/// They are only taken from the parameters once, see [`RelayChainAnchor`].
+#[derive(Encode, Decode, Clone)]
+pub struct RelayChainBackedParams<Header> {
+	pub genesis_header: Header,
+	pub para_id: ParaId,
+	pub relay_genesis_hash: H256,
+	pub relay_header: Header,
+	pub relay_authorities: AuthorityList,
+	pub relay_authority_set_id: SetId,
+	pub relay_authority_proof: Vec<Vec<u8>>,
+}
+
+impl<Header> RelayChainBackedParams<Header> {
+	pub fn new(
+		genesis_header: Header,
+		para_id: ParaId,
+		relay_genesis_hash: H256,
+		relay_header: Header,
+		relay_authorities: AuthorityList,
+		relay_authority_set_id: SetId,
+		relay_authority_proof: Vec<Vec<u8>>,
+	) -> Self {
+		Self {
+			genesis_header,
+			para_id,
+			relay_genesis_hash,
+			relay_header,
+			relay_authorities,
+			relay_authority_set_id,
+			relay_authority_proof,
+		}
+	}
+}
+
+/// Relay chain a relay chain backed light client follows.
+///
+/// It is sealed when the relay chain authorities are initialized from the
+/// [`RelayChainBackedParams`]. Later starts must follow the same relay chain, and the
+/// authorities are not initialized from the parameters again, so that the host can't swap in
+/// a relay chain header and authority set of its own.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct RelayChainAnchor {
+	pub genesis_hash: H256,
+}
@@ -19,12 +19,13 @@
 
 use crate::{
 	error::Error, finality::Finality, light_validation_state::LightValidationState,
-	AuthorityListRef, ExtrinsicSender, HashFor, HashingFor, LightClientState, NumberFor, Validator,
+	AuthorityListRef, ExtrinsicSender, HashFor, HashingFor, LightClientState, NumberFor, SetId,
+	Validator,
 };
 use codec::Encode;
 use core::iter::Iterator;
 use itp_ocall_api::EnclaveOnChainOCallApi;
-use itp_storage::{Error as StorageError, StorageProof, StorageProofChecker};
+use itp_storage::{storage_value_key, Error as StorageError, StorageProof, StorageProofChecker};
 use itp_types::parentchain::{IdentifyParentchain, ParentchainId};
 use log::*;
 use sp_runtime::{
@@ -99,8 +100,7 @@ impl<Block: ParentchainBlockTrait, OcallApi: EnclaveOnChainOCallApi>
 		let validator_set_id = relay.current_validator_set_id;
 
 		// Check that the new header is a descendant of the old header
-		let last_header = &relay.last_finalized_block_header;
-		Self::verify_ancestry(ancestry_proof, last_header.hash(), &header)?;
+		Self::verify_ancestry(ancestry_proof, relay.last_imported_header_hash(), &header)?;
 
 		if let Err(e) = self.finality.validate(
 			header.clone(),
@@ -153,8 +153,13 @@ where
 
 		let relay = self.light_validation_state.get_relay_mut();
 
-		if relay.last_finalized_block_header.hash() != *header.parent_hash() {
-			return Err(Error::HeaderAncestryMismatch)
+		if relay.last_imported_header_hash() != *header.parent_hash() {
+			if relay.last_finalized_block_header.hash() != *header.parent_hash() {
+				return Err(Error::HeaderAncestryMismatch)
+			}
+			// The unjustified headers on top of the last finalized header are submitted again,
+			// e.g. after a restart, as they don't advance the finalized header on a parachain.
+			relay.unjustified_headers.clear();
 		}
 
 		self.submit_finalized_headers(header.clone(), vec![], justifications)
@@ -278,3 +283,24 @@ pub fn check_validator_set_proof<Block: ParentchainBlockTrait>(
 		Err(Error::ValidatorSetMismatch)
 	}
 }
+
+/// Checks the GRANDPA authorities and their set id against the storage proof of a block's state.
+pub fn check_validator_set_and_id_proof<Block: ParentchainBlockTrait>(
+	state_root: &HashFor<Block>,
+	proof: StorageProof,
+	validator_set: AuthorityListRef,
+	validator_set_id: SetId,
+) -> Result<(), Error> {
+	check_validator_set_proof::<Block>(state_root, proof.clone(), validator_set)?;
+
+	let checker = StorageProofChecker::<HashingFor<Block>>::new(*state_root, proof)?;
+	let actual_validator_set_id = checker
+		.read_value(&storage_value_key("Grandpa", "CurrentSetId"))?
+		.ok_or(StorageError::StorageValueUnavailable)?;
+
+	if validator_set_id.encode() == actual_validator_set_id {
+		Ok(())
+	} else {
+		Err(Error::ValidatorSetMismatch)
+	}
+}
@@ -0,0 +1,134 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Proof that a parachain header has been included in a finalized relay chain block.
+//!
+//! The host attaches the proof to the justifications of the proven parachain block, so it
+//! reaches the light client through the regular block import.
+
+use crate::error::Result;
+use codec::{Decode, Encode};
+use itp_storage::{
+	storage_map_key, Error as StorageError, StorageHasher, StorageProof, StorageProofChecker,
+};
+use sp_runtime::{
+	traits::Hash as HashTrait, ConsensusEngineId, EncodedJustification, Justifications,
+};
+use std::vec::Vec;
+
+/// Engine id of the [`ParachainInclusionProof`] within the justifications of a parachain block.
+pub const PARACHAIN_INCLUSION_ENGINE_ID: ConsensusEngineId = *b"PINC";
+
+pub type ParaId = u32;
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct ParachainInclusionProof<Header> {
+	/// Relay chain headers that signal a GRANDPA authority set change since the previously
+	/// proven relay chain block, in ascending order and each with its GRANDPA justification.
+	pub authority_set_changes: Vec<(Header, EncodedJustification)>,
+	/// Relay chain header whose state contains the parachain header as `Paras::Heads`.
+	pub relay_header: Header,
+	/// GRANDPA justification of the `relay_header`.
+	pub relay_justification: EncodedJustification,
+	/// Storage proof of `Paras::Heads(para_id)` in the state of the `relay_header`.
+	pub para_head_proof: StorageProof,
+}
+
+impl<Header: Encode> ParachainInclusionProof<Header> {
+	/// Adds the proof to the (possibly absent) justifications of the proven parachain block.
+	pub fn append_to(&self, justifications: Option<Justifications>) -> Justifications {
+		let justification = (PARACHAIN_INCLUSION_ENGINE_ID, self.encode());
+		match justifications {
+			Some(mut justifications) => {
+				justifications.append(justification);
+				justifications
+			},
+			None => Justifications::from(justification),
+		}
+	}
+}
+
+pub fn para_head_storage_key(para_id: ParaId) -> Vec<u8> {
+	storage_map_key("Paras", "Heads", &para_id, &StorageHasher::Twox64Concat)
+}
+
+/// Returns the hash of the parachain head stored in the relay chain state with the given root.
+pub fn check_para_head_proof<Hashing: HashTrait>(
+	state_root: &Hashing::Output,
+	proof: StorageProof,
+	para_id: ParaId,
+) -> Result<Hashing::Output> {
+	let checker = StorageProofChecker::<Hashing>::new(*state_root, proof)?;
+	let head_data = checker
+		.read_value(&para_head_storage_key(para_id))?
+		.ok_or(StorageError::StorageValueUnavailable)?;
+
+	// `HeadData` is the SCALE encoded header, wrapped into a `Vec<u8>`.
+	let encoded_header = Vec::<u8>::decode(&mut head_data.as_slice())?;
+	Ok(Hashing::hash(&encoded_header))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use itc_parentchain_test::ParentchainHeaderBuilder;
+	use sp_core::{Blake2Hasher, H256};
+	use sp_runtime::traits::{BlakeTwo256, Header as HeaderTrait};
+	use sp_state_machine::{backend::Backend, new_in_mem, prove_read};
+	use sp_trie::HashKey;
+
+	#[test]
+	fn para_head_proof_returns_hash_of_included_header() {
+		let para_header = ParentchainHeaderBuilder::default().with_number(42).build();
+		let (state_root, proof) = relay_state_with_para_head(7, para_header.encode().encode());
+
+		let head_hash = check_para_head_proof::<BlakeTwo256>(&state_root, proof, 7).unwrap();
+
+		assert_eq!(head_hash, para_header.hash());
+	}
+
+	#[test]
+	fn para_head_proof_of_other_parachain_fails() {
+		let para_header = ParentchainHeaderBuilder::default().build();
+		let (state_root, proof) = relay_state_with_para_head(7, para_header.encode().encode());
+
+		assert!(check_para_head_proof::<BlakeTwo256>(&state_root, proof, 8).is_err());
+	}
+
+	#[test]
+	fn inclusion_proof_is_appended_to_justifications() {
+		let proof = ParachainInclusionProof {
+			authority_set_changes: vec![],
+			relay_header: ParentchainHeaderBuilder::default().build(),
+			relay_justification: vec![1, 2, 3],
+			para_head_proof: vec![vec![4, 5]],
+		};
+		let justifications = proof.append_to(Some(Justifications::from((*b"FRNK", vec![0u8]))));
+
+		let encoded = justifications.into_justification(PARACHAIN_INCLUSION_ENGINE_ID).unwrap();
+		assert_eq!(ParachainInclusionProof::decode(&mut encoded.as_slice()).unwrap(), proof);
+	}
+
+	fn relay_state_with_para_head(para_id: ParaId, head_data: Vec<u8>) -> (H256, StorageProof) {
+		let key = para_head_storage_key(para_id);
+		let mut backend = new_in_mem::<Blake2Hasher, HashKey<Blake2Hasher>>();
+		backend.insert(vec![(None, vec![(key.clone(), Some(head_data))])], Default::default());
+		let root = backend.storage_root(std::iter::empty(), Default::default()).0;
+		let proof = prove_read(backend, &[key.as_slice()]).unwrap().iter_nodes().cloned().collect();
+		(root, proof)
+	}
+}
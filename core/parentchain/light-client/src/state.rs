@@ -26,6 +26,10 @@ use std::{collections::VecDeque, fmt, vec::Vec};
 /// Defines the amount of parentchain headers to keep.
 pub const PARENTCHAIN_HEADER_PRUNING: u64 = 1000;
 
+/// Defines the amount of unjustified headers to keep. Only the newest of them end up in the
+/// kept header hashes once justified anyway.
+pub const MAX_UNJUSTIFIED_HEADERS: usize = PARENTCHAIN_HEADER_PRUNING as usize;
+
 #[derive(Encode, Decode, Clone, Eq, PartialEq)]
 pub struct RelayState<Block: BlockT> {
 	pub genesis_hash: Block::Hash,
@@ -48,6 +52,15 @@ impl<Block: BlockT> RelayState<Block> {
 		}
 	}
 
+	pub fn push_unjustified_header(&mut self, header: Block::Hash) {
+		self.unjustified_headers.push(header);
+
+		if self.unjustified_headers.len() > MAX_UNJUSTIFIED_HEADERS {
+			let excess = self.unjustified_headers.len() - MAX_UNJUSTIFIED_HEADERS;
+			self.unjustified_headers.drain(..excess);
+		}
+	}
+
 	pub fn justify_headers(&mut self) {
 		self.header_hashes.extend(&mut self.unjustified_headers.iter());
 		self.unjustified_headers.clear();
@@ -60,6 +73,14 @@ impl<Block: BlockT> RelayState<Block> {
 	pub fn header_hashes(&self) -> &VecDeque<Block::Hash> {
 		&self.header_hashes
 	}
+
+	/// Hash of the last imported header, which is the last unjustified header, if there is any.
+	pub fn last_imported_header_hash(&self) -> Block::Hash {
+		self.unjustified_headers
+			.last()
+			.copied()
+			.unwrap_or_else(|| self.last_finalized_block_header.hash())
+	}
 }
 
 #[derive(Encode, Decode, Clone, Eq, PartialEq)]
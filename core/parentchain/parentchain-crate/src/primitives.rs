@@ -17,7 +17,9 @@
 
 extern crate alloc;
 
-use crate::light_client::light_client_init_params::{GrandpaParams, SimpleParams};
+use crate::light_client::light_client_init_params::{
+	GrandpaParams, RelayChainBackedParams, SimpleParams,
+};
 use codec::{Decode, Encode};
 
 use sp_runtime::traits::Block;
@@ -28,13 +30,25 @@ pub type SolochainHeader = HeaderFor<SolochainBlock>;
 pub type ParachainHeader = HeaderFor<ParachainBlock>;
 pub type SolochainParams = GrandpaParams<SolochainHeader>;
 pub type ParachainParams = SimpleParams<ParachainHeader>;
+pub type RelayChainBackedParachainParams = RelayChainBackedParams<ParachainHeader>;
 
 /// Initialization primitives, used by both service and enclave.
 /// Allows to use a single E-call for the initialization of different parentchain types.
 #[derive(Encode, Decode, Clone)]
 pub enum ParentchainInitParams {
-	Solochain { id: ParentchainId, params: SolochainParams },
-	Parachain { id: ParentchainId, params: ParachainParams },
+	Solochain {
+		id: ParentchainId,
+		params: SolochainParams,
+	},
+	Parachain {
+		id: ParentchainId,
+		params: ParachainParams,
+	},
+	/// Parachain whose finality is derived from the GRANDPA finality of its relay chain.
+	RelayChainBackedParachain {
+		id: ParentchainId,
+		params: RelayChainBackedParachainParams,
+	},
 }
 
 impl ParentchainInitParams {
@@ -42,6 +56,7 @@ impl ParentchainInitParams {
 		match self {
 			Self::Solochain { id, .. } => id,
 			Self::Parachain { id, .. } => id,
+			Self::RelayChainBackedParachain { id, .. } => id,
 		}
 	}
 }
@@ -57,3 +72,9 @@ impl From<(ParentchainId, ParachainParams)> for ParentchainInitParams {
 		Self::Parachain { id: value.0, params: value.1 }
 	}
 }
+
+impl From<(ParentchainId, RelayChainBackedParachainParams)> for ParentchainInitParams {
+	fn from(value: (ParentchainId, RelayChainBackedParachainParams)) -> Self {
+		Self::RelayChainBackedParachain { id: value.0, params: value.1 }
+	}
+}
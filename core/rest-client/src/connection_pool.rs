@@ -0,0 +1,273 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Pool of idle keep-alive connections, so consecutive requests to the same server
+//! do not need a new TCP connection and TLS handshake each.
+
+#[cfg(all(not(feature = "std"), feature = "sgx"))]
+use crate::sgx_reexport_prelude::*;
+
+#[cfg(feature = "sgx")]
+use std::sync::SgxMutex as Mutex;
+
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+
+use crate::{tls_connection::DerCertificate, transcript::Sha256Hash};
+use itp_time_utils::now_as_millis;
+use log::*;
+use rustls::{ClientSession, Session, StreamOwned};
+use std::{
+	collections::HashMap,
+	io::{Read, Result as IoResult, Write},
+	net::TcpStream,
+	string::String,
+	time::Duration,
+	vec::Vec,
+};
+
+/// Default number of idle connections kept per server.
+pub const DEFAULT_MAX_IDLE_CONNECTIONS_PER_HOST: usize = 2;
+
+/// Default time an idle connection is kept before it is discarded.
+///
+/// Most servers close idle connections after somewhere between 5 and 60 seconds.
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An open connection to a server, either plain or TLS protected.
+pub enum Connection {
+	Plain(TcpStream),
+	Tls(Box<StreamOwned<ClientSession, TcpStream>>),
+}
+
+impl Connection {
+	/// Set the read and write timeout of the underlying socket.
+	pub fn set_timeout(&self, timeout: Option<Duration>) -> IoResult<()> {
+		let socket = match self {
+			Connection::Plain(stream) => stream,
+			Connection::Tls(stream) => &stream.sock,
+		};
+		socket.set_read_timeout(timeout)?;
+		socket.set_write_timeout(timeout)
+	}
+
+	/// DER encoded (leaf) certificate the server presented during the TLS handshake.
+	pub fn server_certificate(&self) -> Option<DerCertificate> {
+		match self {
+			Connection::Plain(_) => None,
+			Connection::Tls(stream) => stream
+				.sess
+				.get_peer_certificates()
+				.and_then(|chain| chain.into_iter().next())
+				.map(|certificate| certificate.0),
+		}
+	}
+}
+
+impl Read for Connection {
+	fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+		match self {
+			Connection::Plain(stream) => stream.read(buf),
+			Connection::Tls(stream) => stream.read(buf),
+		}
+	}
+}
+
+impl Write for Connection {
+	fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+		match self {
+			Connection::Plain(stream) => stream.write(buf),
+			Connection::Tls(stream) => stream.write(buf),
+		}
+	}
+
+	fn flush(&mut self) -> IoResult<()> {
+		match self {
+			Connection::Plain(stream) => stream.flush(),
+			Connection::Tls(stream) => stream.flush(),
+		}
+	}
+}
+
+/// Identifies the connections that can be used for a request.
+///
+/// Besides the server, the key contains everything the connection was established with:
+/// a connection verified against one set of root certificates must never be handed out
+/// to a client that trusts a different set.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ConnectionKey {
+	pub scheme: String,
+	pub host: String,
+	pub port: u16,
+	/// Hash over the root certificates the server certificate was verified with.
+	pub trust_anchors: Sha256Hash,
+	/// Proxy the connection is tunneled through, if any.
+	pub proxy: Option<(String, u16)>,
+}
+
+struct IdleConnection<C> {
+	connection: C,
+	/// Unix timestamp in milliseconds.
+	idle_since: u64,
+}
+
+/// Thread-safe pool of idle connections.
+///
+/// Share it between clients with an `Arc` to reuse connections across clients.
+pub struct ConnectionPool<C = Connection> {
+	idle_connections: Mutex<HashMap<ConnectionKey, Vec<IdleConnection<C>>>>,
+	max_idle_per_host: usize,
+	idle_timeout: Duration,
+}
+
+impl<C> Default for ConnectionPool<C> {
+	fn default() -> Self {
+		ConnectionPool::new(DEFAULT_MAX_IDLE_CONNECTIONS_PER_HOST, DEFAULT_IDLE_TIMEOUT)
+	}
+}
+
+impl<C> ConnectionPool<C> {
+	pub fn new(max_idle_per_host: usize, idle_timeout: Duration) -> Self {
+		ConnectionPool {
+			idle_connections: Mutex::new(HashMap::new()),
+			max_idle_per_host,
+			idle_timeout,
+		}
+	}
+
+	/// Takes the most recently used idle connection for `key` out of the pool.
+	///
+	/// Connections that have been idle for longer than the idle timeout are discarded.
+	pub fn checkout(&self, key: &ConnectionKey) -> Option<C> {
+		self.checkout_at(key, now_as_millis())
+	}
+
+	/// Returns a connection to the pool, after its response has been read completely.
+	pub fn checkin(&self, key: ConnectionKey, connection: C) {
+		self.checkin_at(key, connection, now_as_millis())
+	}
+
+	/// Number of idle connections currently held by the pool.
+	pub fn idle_connections(&self) -> usize {
+		self.lock().values().map(|connections| connections.len()).sum()
+	}
+
+	/// Closes all idle connections.
+	pub fn clear(&self) {
+		self.lock().clear()
+	}
+
+	fn checkout_at(&self, key: &ConnectionKey, now: u64) -> Option<C> {
+		let idle_timeout = self.idle_timeout.as_millis() as u64;
+		let mut idle_connections = self.lock();
+		let connections = idle_connections.get_mut(key)?;
+		connections.retain(|idle| now.saturating_sub(idle.idle_since) < idle_timeout);
+
+		let connection = connections.pop().map(|idle| idle.connection);
+		if connections.is_empty() {
+			idle_connections.remove(key);
+		}
+		if connection.is_some() {
+			trace!("Reusing connection to {}:{}", key.host, key.port);
+		}
+		connection
+	}
+
+	fn checkin_at(&self, key: ConnectionKey, connection: C, now: u64) {
+		if self.max_idle_per_host == 0 {
+			return
+		}
+		let mut idle_connections = self.lock();
+		let connections = idle_connections.entry(key).or_insert_with(Vec::new);
+		if connections.len() >= self.max_idle_per_host {
+			// Drop the connection that has been idle the longest.
+			connections.remove(0);
+		}
+		connections.push(IdleConnection { connection, idle_since: now });
+	}
+
+	fn lock(
+		&self,
+	) -> impl core::ops::DerefMut<Target = HashMap<ConnectionKey, Vec<IdleConnection<C>>>> + '_ {
+		// A poisoned pool only contains idle connections, which are safe to keep using.
+		self.idle_connections.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::string::ToString;
+
+	#[test]
+	fn checked_in_connection_is_reused_for_same_key() {
+		let pool = ConnectionPool::<u32>::default();
+
+		pool.checkin_at(key("api.coingecko.com"), 1, 1_000);
+
+		assert_eq!(pool.checkout_at(&key("api.coingecko.com"), 2_000), Some(1));
+		assert_eq!(pool.checkout_at(&key("api.coingecko.com"), 2_000), None);
+		assert_eq!(pool.idle_connections(), 0);
+	}
+
+	#[test]
+	fn connection_is_not_shared_between_different_keys() {
+		let pool = ConnectionPool::<u32>::default();
+		let mut other_trust_anchors = key("api.coingecko.com");
+		other_trust_anchors.trust_anchors = [1u8; 32];
+
+		pool.checkin_at(key("api.coingecko.com"), 1, 1_000);
+
+		assert_eq!(pool.checkout_at(&key("pro-api.coinmarketcap.com"), 2_000), None);
+		assert_eq!(pool.checkout_at(&other_trust_anchors, 2_000), None);
+		assert_eq!(pool.idle_connections(), 1);
+	}
+
+	#[test]
+	fn expired_connections_are_discarded() {
+		let pool = ConnectionPool::<u32>::new(2, Duration::from_secs(30));
+
+		pool.checkin_at(key("api.coingecko.com"), 1, 1_000);
+		pool.checkin_at(key("api.coingecko.com"), 2, 20_000);
+
+		assert_eq!(pool.checkout_at(&key("api.coingecko.com"), 40_000), Some(2));
+		assert_eq!(pool.checkout_at(&key("api.coingecko.com"), 40_000), None);
+	}
+
+	#[test]
+	fn oldest_connection_is_dropped_if_pool_is_full() {
+		let pool = ConnectionPool::<u32>::new(2, Duration::from_secs(30));
+
+		pool.checkin_at(key("api.coingecko.com"), 1, 1_000);
+		pool.checkin_at(key("api.coingecko.com"), 2, 2_000);
+		pool.checkin_at(key("api.coingecko.com"), 3, 3_000);
+
+		assert_eq!(pool.idle_connections(), 2);
+		assert_eq!(pool.checkout_at(&key("api.coingecko.com"), 4_000), Some(3));
+		assert_eq!(pool.checkout_at(&key("api.coingecko.com"), 4_000), Some(2));
+	}
+
+	fn key(host: &str) -> ConnectionKey {
+		ConnectionKey {
+			scheme: "https".to_string(),
+			host: host.to_string(),
+			port: 443,
+			trust_anchors: [0u8; 32],
+			proxy: None,
+		}
+	}
+}
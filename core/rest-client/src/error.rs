@@ -55,4 +55,13 @@ pub enum Error {
 
 	#[error("Invalid parameter value")]
 	InvalidValue,
+
+	#[error("TLS error: {0}")]
+	TlsError(String),
+
+	#[error("Malformed HTTP response: {0}")]
+	InvalidResponse(String),
+
+	#[error("HTTP response exceeds the limit of {0} bytes")]
+	ResponseTooLarge(usize),
 }
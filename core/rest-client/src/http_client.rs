@@ -18,7 +18,15 @@
 #[cfg(all(not(feature = "std"), feature = "sgx"))]
 use crate::sgx_reexport_prelude::*;
 
-use crate::{error::Error, Query, RestPath};
+use crate::{
+	connection_pool::ConnectionPool,
+	error::Error,
+	proxy::HttpProxy,
+	tls_connection::{send_with_root_certificates, DerCertificate},
+	transcript::ResponseTranscript,
+	Query, RestPath,
+};
+use core::cell::RefCell;
 use http::{
 	header::{HeaderName, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, USER_AGENT},
 	HeaderValue,
@@ -26,15 +34,16 @@ use http::{
 use http_req::{
 	request::{Method, Request},
 	response::{Headers, Response},
-	tls::Config,
 	uri::Uri,
 };
+use itp_time_utils::now_as_millis;
 use log::*;
 use std::{
 	collections::HashMap,
 	convert::TryFrom,
 	str::FromStr,
 	string::{String, ToString},
+	sync::Arc,
 	time::Duration,
 	vec::Vec,
 };
@@ -54,15 +63,33 @@ pub trait SendHttpRequest {
 	) -> Result<(Response, EncodedBody), Error>
 	where
 		T: RestPath<U>;
+
+	/// Transcript of the response received for the previous request, if available.
+	fn response_transcript(&self) -> Option<ResponseTranscript> {
+		None
+	}
 }
 
-/// Send trait used by the http client to send HTTP request, based on `http_req`.
+/// A fully assembled HTTP request, as handed over to the `Send` implementation.
+#[derive(Debug)]
+pub struct HttpRequest<'a> {
+	pub url: &'a Url,
+	pub method: Method,
+	pub headers: Headers,
+	pub body: Option<&'a [u8]>,
+	pub timeout: Option<Duration>,
+}
+
+/// Send trait used by the http client to send HTTP request.
+///
+/// Returns the response and, for TLS connections where it is known,
+/// the DER encoded certificate the server presented.
 pub trait Send {
 	fn execute_send_request(
 		&self,
-		request: &mut Request,
+		request: HttpRequest,
 		writer: &mut Vec<u8>,
-	) -> Result<Response, Error>;
+	) -> Result<(Response, Option<DerCertificate>), Error>;
 }
 
 /// HTTP client implementation
@@ -74,6 +101,7 @@ pub struct HttpClient<SendType> {
 	timeout: Option<Duration>,
 	headers: Headers,
 	authorization: Option<String>,
+	response_transcript: RefCell<Option<ResponseTranscript>>,
 }
 
 /// Default send method.
@@ -85,47 +113,84 @@ pub struct DefaultSend;
 impl Send for DefaultSend {
 	fn execute_send_request(
 		&self,
-		request: &mut Request,
+		request: HttpRequest,
 		writer: &mut Vec<u8>,
-	) -> Result<Response, Error> {
-		request.send(writer).map_err(Error::HttpReqError)
+	) -> Result<(Response, Option<DerCertificate>), Error> {
+		let uri = Uri::try_from(request.url.as_str()).map_err(Error::HttpReqError)?;
+
+		let mut http_request = Request::new(&uri);
+		http_request
+			.method(request.method)
+			.headers(HashMap::from(request.headers))
+			.timeout(request.timeout)
+			.connect_timeout(request.timeout)
+			.read_timeout(request.timeout)
+			.write_timeout(request.timeout);
+
+		if let Some(body) = request.body {
+			http_request.body(body); // takes body non-owned (!)
+		}
+
+		let response = http_request.send(writer).map_err(Error::HttpReqError)?;
+		Ok((response, None))
 	}
 }
 
 /// Sends a HTTPs request with the server's root certificate(s).
 /// The connection will only be established if one of the supplied certificates
 /// matches the server's root certificate.
+///
+/// Uses its own TLS transport, so that the server's certificate ends up in the response transcript.
+/// Optionally, connections are tunneled through an HTTP proxy and kept alive in a connection pool.
+/// Certificates are verified end to end in both cases.
 pub struct SendWithCertificateVerification {
 	root_certificates: Vec<String>,
+	proxy: Option<HttpProxy>,
+	connection_pool: Option<Arc<ConnectionPool>>,
 }
 
 impl SendWithCertificateVerification {
 	pub fn new(root_certificates: Vec<String>) -> Self {
-		SendWithCertificateVerification { root_certificates }
+		SendWithCertificateVerification { root_certificates, proxy: None, connection_pool: None }
+	}
+
+	/// Tunnel all connections through the given HTTP proxy.
+	///
+	/// default is none
+	pub fn with_proxy(mut self, proxy: HttpProxy) -> Self {
+		self.proxy = Some(proxy);
+		self
+	}
+
+	/// Keep connections alive and reuse them from the given pool.
+	///
+	/// default is a new connection for every request
+	pub fn with_connection_pool(mut self, connection_pool: Arc<ConnectionPool>) -> Self {
+		self.connection_pool = Some(connection_pool);
+		self
 	}
 }
 
 impl Send for SendWithCertificateVerification {
 	fn execute_send_request(
 		&self,
-		request: &mut Request,
+		request: HttpRequest,
 		writer: &mut Vec<u8>,
-	) -> Result<Response, Error> {
-		let mut cnf = Config::empty_root_store();
-		for cert in self.root_certificates.iter() {
-			cnf.add_root_cert_content_pem_file(cert)?;
-		}
-
-		match request.send_with_config(writer, Some(&cnf)) {
-			Ok(response) => Ok(response),
-			Err(e) => {
-				error!(
-					"SendWithCertificateVerification::execute_send_request received error: {:#?}",
-					&e
-				);
-				Err(Error::HttpReqError(e))
-			},
-		}
+	) -> Result<(Response, Option<DerCertificate>), Error> {
+		send_with_root_certificates(
+			&self.root_certificates,
+			self.proxy.as_ref(),
+			self.connection_pool.as_deref(),
+			&request,
+			writer,
+		)
+		.map_err(|e| {
+			error!(
+				"SendWithCertificateVerification::execute_send_request received error: {:#?}",
+				&e
+			);
+			e
+		})
 	}
 }
 
@@ -146,6 +211,7 @@ where
 			timeout,
 			headers: headers.unwrap_or_else(Headers::new),
 			authorization,
+			response_transcript: RefCell::new(None),
 		}
 	}
 
@@ -195,10 +261,8 @@ where
 
 		trace!("uri: {:?}", uri);
 
-		let mut request = Request::new(&uri);
-		request.method(method);
-
 		let mut request_headers = Headers::default_http(&uri);
+		let mut request_body = None;
 
 		if let Some(body) = maybe_body.as_ref() {
 			if self.send_null_body || body != "null" {
@@ -214,7 +278,7 @@ where
 				);
 
 				trace!("set request body: {}", body);
-				request.body(body.as_bytes()); // takes body non-owned (!)
+				request_body = Some(body.as_bytes());
 			}
 		} else {
 			debug!("no body to send");
@@ -242,22 +306,35 @@ where
 				.map_err(|_| Error::RequestError)?,
 		);
 
-		request.headers(HashMap::from(request_headers));
-
-		request
-			.timeout(self.timeout)
-			.connect_timeout(self.timeout)
-			.read_timeout(self.timeout)
-			.write_timeout(self.timeout);
+		let request = HttpRequest {
+			url: &url,
+			method,
+			headers: request_headers,
+			body: request_body,
+			timeout: self.timeout,
+		};
 
 		trace!("request is: {:?}", request);
 
 		let mut writer = Vec::new();
 
-		let response = self.send.execute_send_request(&mut request, &mut writer)?;
+		let (response, server_certificate) =
+			self.send.execute_send_request(request, &mut writer)?;
+
+		*self.response_transcript.borrow_mut() = Some(ResponseTranscript::new(
+			url.to_string(),
+			now_as_millis(),
+			u16::from(response.status_code()),
+			server_certificate.as_deref(),
+			&writer,
+		));
 
 		Ok((response, writer))
 	}
+
+	fn response_transcript(&self) -> Option<ResponseTranscript> {
+		self.response_transcript.borrow().clone()
+	}
 }
 
 fn join_url(base_url: Url, path: &str, params: Option<&Query>) -> Result<Url, Error> {
@@ -529,6 +606,49 @@ mod tests {
 		assert!(response.status_code().is_success());
 		assert!(!response_body.url.is_empty());
 		assert_eq!(response_body.method.as_str(), "GET");
+
+		let transcript = http_client.response_transcript().unwrap();
+		assert!(transcript.server_certificate_fingerprint.is_some());
+		assert!(transcript.matches_body(&encoded_body));
+	}
+
+	#[test]
+	#[ignore = "depends on external web-service that proved to be unreliable for CI"]
+	fn authenticated_get_reuses_pooled_connection() {
+		#[derive(Serialize, Deserialize, Debug)]
+		struct HttpBinAnything {
+			pub method: String,
+		}
+
+		impl RestPath<()> for HttpBinAnything {
+			fn get_path(_: ()) -> Result<String, Error> {
+				Ok("anything".to_string())
+			}
+		}
+		let base_url = Url::parse("https://httpbin.org").unwrap();
+		let connection_pool = Arc::new(ConnectionPool::default());
+
+		let http_client = HttpClient::new(
+			SendWithCertificateVerification::new(vec![HTTPBIN_ROOT_CERT.to_string()])
+				.with_connection_pool(connection_pool.clone()),
+			true,
+			Some(Duration::from_secs(3u64)),
+			None,
+			None,
+		);
+
+		for _ in 0..2 {
+			let (response, _) = http_client
+				.send_request::<(), HttpBinAnything>(base_url.clone(), Method::GET, (), None, None)
+				.unwrap();
+			assert!(response.status_code().is_success());
+			assert_eq!(connection_pool.idle_connections(), 1);
+		}
+		assert!(http_client
+			.response_transcript()
+			.unwrap()
+			.server_certificate_fingerprint
+			.is_some());
 	}
 
 	#[test]
@@ -562,7 +682,7 @@ mod tests {
 
 		let result =
 			http_client.send_request::<(), HttpBinAnything>(base_url, Method::GET, (), None, None);
-		assert_matches!(result, Err(Error::HttpReqError(_)));
+		assert_matches!(result, Err(Error::TlsError(_)));
 		let msg = format!("error {:?}", result.err());
 		assert!(msg.contains("UnknownIssuer"));
 	}
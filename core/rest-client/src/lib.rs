@@ -32,14 +32,20 @@ extern crate sgx_tstd as std;
 pub mod sgx_reexport_prelude {
 	pub use http_req_sgx as http_req;
 	pub use http_sgx as http;
+	pub use rustls_sgx as rustls;
 	pub use thiserror_sgx as thiserror;
 	pub use url_sgx as url;
+	pub use webpki_sgx as webpki;
 }
 
+pub mod connection_pool;
 pub mod error;
 pub mod http_client;
 pub mod http_client_builder;
+pub mod proxy;
 pub mod rest_client;
+pub mod tls_connection;
+pub mod transcript;
 
 #[cfg(test)]
 pub mod mocks;
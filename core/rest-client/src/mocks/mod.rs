@@ -16,3 +16,4 @@
 */
 
 pub mod http_client_mock;
+pub mod stream_mock;
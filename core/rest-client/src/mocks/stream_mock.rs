@@ -0,0 +1,59 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use std::io::{Cursor, Error as IoError, ErrorKind, Read, Result as IoResult, Write};
+
+/// In-memory stream: reads from a fixed input and records everything written to it.
+pub struct StreamMock {
+	input: Cursor<Vec<u8>>,
+	pub output: Vec<u8>,
+	/// Fail reads at the end of the input like a TLS stream closed without close_notify.
+	unclean_close: bool,
+}
+
+impl StreamMock {
+	pub fn new(input: &[u8]) -> Self {
+		StreamMock { input: Cursor::new(input.to_vec()), output: Vec::new(), unclean_close: false }
+	}
+
+	pub fn with_unclean_close(mut self) -> Self {
+		self.unclean_close = true;
+		self
+	}
+}
+
+impl Read for StreamMock {
+	fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+		match self.input.read(buf)? {
+			0 if self.unclean_close && !buf.is_empty() => Err(IoError::new(
+				ErrorKind::UnexpectedEof,
+				"peer closed connection without close_notify",
+			)),
+			read => Ok(read),
+		}
+	}
+}
+
+impl Write for StreamMock {
+	fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+		self.output.write(buf)
+	}
+
+	fn flush(&mut self) -> IoResult<()> {
+		Ok(())
+	}
+}
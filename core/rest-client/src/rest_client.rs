@@ -22,8 +22,8 @@ pub use http_req::{request::Method, response::Headers};
 pub use url::Url;
 
 use crate::{
-	error::Error, http_client::SendHttpRequest, Query, RestDelete, RestGet, RestPatch, RestPath,
-	RestPost, RestPut,
+	error::Error, http_client::SendHttpRequest, transcript::ResponseTranscript, Query, RestDelete,
+	RestGet, RestPatch, RestPath, RestPost, RestPut,
 };
 
 use log::*;
@@ -63,6 +63,11 @@ where
 		&self.response_headers
 	}
 
+	/// Transcript of the response to the previous request, if the HTTP client provides one
+	pub fn response_transcript(&self) -> Option<ResponseTranscript> {
+		self.http_client.response_transcript()
+	}
+
 	fn post_or_put<U, T>(&mut self, method: Method, params: U, data: &T) -> Result<(), Error>
 	where
 		T: serde::Serialize + RestPath<U>,
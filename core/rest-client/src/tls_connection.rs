@@ -0,0 +1,620 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Minimal HTTP/1.1 transport on top of `rustls`.
+//!
+//! `http_req` does not expose the TLS session of a request. Sending the request ourselves
+//! gives access to the certificate the server presented during the handshake. It also allows
+//! keeping connections alive in a `ConnectionPool` and tunneling them through an `HttpProxy`.
+
+#[cfg(all(not(feature = "std"), feature = "sgx"))]
+use crate::sgx_reexport_prelude::*;
+
+use crate::{
+	connection_pool::{Connection, ConnectionKey, ConnectionPool},
+	error::Error,
+	http_client::HttpRequest,
+	proxy::{connect_tunnel, HttpProxy},
+	transcript::sha256,
+};
+use http_req::{request::Method, response::Response};
+use log::*;
+use rustls::{ClientConfig, ClientSession, StreamOwned};
+use std::{
+	io::{ErrorKind, Read, Write},
+	net::{TcpStream, ToSocketAddrs},
+	string::{String, ToString},
+	sync::Arc,
+	time::Duration,
+	vec::Vec,
+};
+use url::Url;
+
+const CRLF: &[u8] = b"\r\n";
+pub(crate) const HEAD_END: &[u8] = b"\r\n\r\n";
+const READ_CHUNK_SIZE: usize = 4096;
+/// Limit for the response head, and for each chunk size line or trailer of a chunked body.
+pub(crate) const MAX_HEAD_SIZE: usize = 64 * 1024;
+/// Limit for the response body, the responses of the APIs we query are much smaller.
+pub(crate) const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// DER encoded X.509 certificate.
+pub type DerCertificate = Vec<u8>;
+
+/// Sends `request` and writes the response body into `writer`.
+///
+/// HTTPS connections only trust the given `root_certificates`. In that case the server's
+/// (leaf) certificate is returned alongside the response. With a `proxy`, the connection is
+/// tunneled through it; the TLS session is still established with the server itself.
+/// With a `connection_pool`, idle keep-alive connections are reused and returned to it.
+pub(crate) fn send_with_root_certificates(
+	root_certificates: &[String],
+	proxy: Option<&HttpProxy>,
+	connection_pool: Option<&ConnectionPool>,
+	request: &HttpRequest,
+	writer: &mut Vec<u8>,
+) -> Result<(Response, Option<DerCertificate>), Error> {
+	let key = connection_key(root_certificates, proxy, request.url)?;
+	let keep_alive = connection_pool.is_some() && !requests_close(request);
+
+	if let Some(connection) = connection_pool.and_then(|pool| pool.checkout(&key)) {
+		let mut body = Vec::new();
+		match send_on(connection, request, keep_alive, &mut body) {
+			Ok((response, server_certificate, reusable_connection)) => {
+				writer.extend_from_slice(&body);
+				return_to_pool(connection_pool, key, reusable_connection);
+				return Ok((response, server_certificate))
+			},
+			// The server may have closed the idle connection in the meantime.
+			Err(e) if is_idempotent(&request.method) => debug!(
+				"Request on reused connection to {} failed ({:?}), retrying on a new connection",
+				key.host, e
+			),
+			Err(e) => return Err(e),
+		}
+	}
+
+	let connection = open_connection(root_certificates, proxy, &key, request.timeout)?;
+	let (response, server_certificate, reusable_connection) =
+		send_on(connection, request, keep_alive, writer)?;
+	return_to_pool(connection_pool, key, reusable_connection);
+
+	Ok((response, server_certificate))
+}
+
+fn connection_key(
+	root_certificates: &[String],
+	proxy: Option<&HttpProxy>,
+	url: &Url,
+) -> Result<ConnectionKey, Error> {
+	Ok(ConnectionKey {
+		scheme: url.scheme().to_string(),
+		host: url.host_str().ok_or(Error::UrlError)?.to_string(),
+		port: url.port_or_known_default().ok_or(Error::UrlError)?,
+		trust_anchors: sha256(root_certificates.concat().as_bytes()),
+		proxy: proxy.map(|proxy| (proxy.host().to_string(), proxy.port())),
+	})
+}
+
+fn open_connection(
+	root_certificates: &[String],
+	proxy: Option<&HttpProxy>,
+	key: &ConnectionKey,
+	timeout: Option<Duration>,
+) -> Result<Connection, Error> {
+	let socket = match proxy {
+		Some(proxy) => {
+			let mut socket = connect(proxy.host(), proxy.port(), timeout)?;
+			connect_tunnel(&mut socket, proxy, &key.host, key.port)?;
+			socket
+		},
+		None => connect(&key.host, key.port, timeout)?,
+	};
+
+	if key.scheme != "https" {
+		return Ok(Connection::Plain(socket))
+	}
+
+	let config = Arc::new(client_config(root_certificates)?);
+	let dns_name = webpki::DNSNameRef::try_from_ascii_str(&key.host)
+		.map_err(|e| Error::TlsError(format!("Invalid server name {}: {:?}", key.host, e)))?;
+	let session = ClientSession::new(&config, dns_name);
+	Ok(Connection::Tls(Box::new(StreamOwned::new(session, socket))))
+}
+
+/// Sends the request on `connection`. The connection is handed back if it can be reused.
+fn send_on(
+	mut connection: Connection,
+	request: &HttpRequest,
+	keep_alive: bool,
+	writer: &mut Vec<u8>,
+) -> Result<(Response, Option<DerCertificate>, Option<Connection>), Error> {
+	connection.set_timeout(request.timeout).map_err(Error::IoError)?;
+	let (response, reusable) = exchange(&mut connection, request, keep_alive, writer)?;
+	let server_certificate = connection.server_certificate();
+	Ok((response, server_certificate, if reusable { Some(connection) } else { None }))
+}
+
+fn return_to_pool(
+	connection_pool: Option<&ConnectionPool>,
+	key: ConnectionKey,
+	connection: Option<Connection>,
+) {
+	if let (Some(pool), Some(connection)) = (connection_pool, connection) {
+		pool.checkin(key, connection);
+	}
+}
+
+fn client_config(root_certificates: &[String]) -> Result<ClientConfig, Error> {
+	let mut config = ClientConfig::new();
+	for certificate in root_certificates {
+		match config.root_store.add_pem_file(&mut certificate.as_bytes()) {
+			Ok((valid, _)) if valid > 0 => {},
+			_ => return Err(Error::TlsError("Failed to parse root certificate".to_string())),
+		}
+	}
+	Ok(config)
+}
+
+fn connect(host: &str, port: u16, timeout: Option<Duration>) -> Result<TcpStream, Error> {
+	let socket = match timeout {
+		Some(timeout) => {
+			let address = (host, port)
+				.to_socket_addrs()
+				.map_err(Error::IoError)?
+				.next()
+				.ok_or(Error::UrlError)?;
+			TcpStream::connect_timeout(&address, timeout)
+		},
+		None => TcpStream::connect((host, port)),
+	}
+	.map_err(map_io_error)?;
+
+	socket.set_read_timeout(timeout).map_err(Error::IoError)?;
+	socket.set_write_timeout(timeout).map_err(Error::IoError)?;
+	Ok(socket)
+}
+
+/// Writes the request to the stream and reads back the response.
+///
+/// Returns whether the connection can be used for another request.
+fn exchange<S: Read + Write>(
+	stream: &mut S,
+	request: &HttpRequest,
+	keep_alive: bool,
+	writer: &mut Vec<u8>,
+) -> Result<(Response, bool), Error> {
+	stream.write_all(&request_message(request, keep_alive)).map_err(map_io_error)?;
+	stream.flush().map_err(map_io_error)?;
+
+	let mut buffer = Vec::new();
+	let head_len = read_until(stream, &mut buffer, 0, HEAD_END, MAX_HEAD_SIZE)? + HEAD_END.len();
+	let response = Response::from_head(&buffer[..head_len]).map_err(Error::HttpReqError)?;
+	let received_body = buffer.split_off(head_len);
+
+	let body_is_delimited = if matches!(request.method, Method::HEAD) {
+		received_body.is_empty()
+	} else {
+		read_body(stream, &response, received_body, writer)?
+	};
+
+	let server_keeps_alive = response.version() == "HTTP/1.1"
+		&& !has_connection_close(response.headers().get("Connection"));
+
+	Ok((response, keep_alive && server_keeps_alive && body_is_delimited))
+}
+
+fn request_message(request: &HttpRequest, keep_alive: bool) -> Vec<u8> {
+	let mut resource = request.url.path().to_string();
+	if let Some(query) = request.url.query() {
+		resource.push('?');
+		resource.push_str(query);
+	}
+
+	let mut head = format!("{} {} HTTP/1.1\r\n", request.method, resource);
+	for (name, value) in request.headers.iter() {
+		head.push_str(&format!("{}: {}\r\n", name, value));
+	}
+	if request.headers.get("Connection").is_none() {
+		head.push_str(if keep_alive {
+			"Connection: keep-alive\r\n"
+		} else {
+			"Connection: close\r\n"
+		});
+	}
+	head.push_str("\r\n");
+
+	let mut message = head.into_bytes();
+	if let Some(body) = request.body {
+		message.extend_from_slice(body);
+	}
+	message
+}
+
+fn requests_close(request: &HttpRequest) -> bool {
+	has_connection_close(request.headers.get("Connection"))
+}
+
+fn has_connection_close(connection_header: Option<&String>) -> bool {
+	connection_header
+		.map(|value| value.split(',').any(|option| option.trim().eq_ignore_ascii_case("close")))
+		.unwrap_or(false)
+}
+
+/// Requests that can safely be sent a second time, see RFC 7231, section 4.2.2.
+fn is_idempotent(method: &Method) -> bool {
+	matches!(
+		method,
+		Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS | Method::TRACE
+	)
+}
+
+/// Reads the response body into `writer`.
+///
+/// Returns whether the end of the body was determined by the response itself, i.e. the
+/// connection is positioned at the start of the next response and may be reused.
+fn read_body<S: Read>(
+	stream: &mut S,
+	response: &Response,
+	mut received: Vec<u8>,
+	writer: &mut Vec<u8>,
+) -> Result<bool, Error> {
+	let status_code = u16::from(response.status_code());
+	if status_code == 204 || status_code == 304 {
+		return Ok(received.is_empty())
+	}
+
+	let is_chunked = response
+		.headers()
+		.get("Transfer-Encoding")
+		.map(|encoding| encoding.to_ascii_lowercase().contains("chunked"))
+		.unwrap_or(false);
+	if is_chunked {
+		return read_chunked_body(stream, received, writer)
+	}
+
+	let is_delimited = match response.headers().get("Content-Length") {
+		Some(length) => {
+			let length = length.trim().parse::<usize>().map_err(|_| {
+				Error::InvalidResponse(format!("Invalid content length {}", length))
+			})?;
+			if length > MAX_BODY_SIZE {
+				return Err(Error::ResponseTooLarge(MAX_BODY_SIZE))
+			}
+			fill(stream, &mut received, length)?;
+			let is_delimited = received.len() == length;
+			received.truncate(length);
+			is_delimited
+		},
+		None => {
+			// Without a length, the body ends when the server closes the connection.
+			let mut chunk = [0u8; READ_CHUNK_SIZE];
+			loop {
+				if received.len() > MAX_BODY_SIZE {
+					return Err(Error::ResponseTooLarge(MAX_BODY_SIZE))
+				}
+				let read = read_some(stream, &mut chunk)?;
+				if read == 0 {
+					break
+				}
+				received.extend_from_slice(&chunk[..read]);
+			}
+			false
+		},
+	};
+
+	writer.extend_from_slice(&received);
+	Ok(is_delimited)
+}
+
+fn read_chunked_body<S: Read>(
+	stream: &mut S,
+	mut buffer: Vec<u8>,
+	writer: &mut Vec<u8>,
+) -> Result<bool, Error> {
+	let mut position = 0;
+	let mut body_size: usize = 0;
+	loop {
+		let line_end = read_until(stream, &mut buffer, position, CRLF, MAX_HEAD_SIZE)?;
+		let size_line = String::from_utf8_lossy(&buffer[position..line_end]).to_string();
+		let size_hex = size_line.split(';').next().unwrap_or_default().trim();
+		let size = usize::from_str_radix(size_hex, 16)
+			.map_err(|_| Error::InvalidResponse(format!("Invalid chunk size {}", size_line)))?;
+		position = line_end + CRLF.len();
+
+		// Trailers after the last chunk are ignored, up to the empty line ending them.
+		if size == 0 {
+			loop {
+				let line_end = read_until(stream, &mut buffer, position, CRLF, MAX_HEAD_SIZE)?;
+				let is_last_line = line_end == position;
+				position = line_end + CRLF.len();
+				if is_last_line {
+					return Ok(position == buffer.len())
+				}
+			}
+		}
+
+		body_size = body_size
+			.checked_add(size)
+			.filter(|body_size| *body_size <= MAX_BODY_SIZE)
+			.ok_or(Error::ResponseTooLarge(MAX_BODY_SIZE))?;
+		let chunk_end = position + size;
+
+		fill(stream, &mut buffer, chunk_end + CRLF.len())?;
+		writer.extend_from_slice(&buffer[position..chunk_end]);
+		position = chunk_end + CRLF.len();
+
+		// Drop the chunks already written, so the buffer does not hold the body twice.
+		buffer.drain(..position);
+		position = 0;
+	}
+}
+
+/// Reads from the stream until `pattern` occurs in `buffer[from..]`, returns its position.
+///
+/// Fails if `buffer[from..]` exceeds `max_len` bytes without containing `pattern`.
+pub(crate) fn read_until<S: Read>(
+	stream: &mut S,
+	buffer: &mut Vec<u8>,
+	from: usize,
+	pattern: &[u8],
+	max_len: usize,
+) -> Result<usize, Error> {
+	let mut chunk = [0u8; READ_CHUNK_SIZE];
+	loop {
+		let searched = &buffer[from.min(buffer.len())..];
+		if let Some(position) = find(searched, pattern) {
+			return Ok(from + position)
+		}
+		if searched.len() > max_len {
+			return Err(Error::ResponseTooLarge(max_len))
+		}
+		let read = read_some(stream, &mut chunk)?;
+		if read == 0 {
+			return Err(Error::InvalidResponse("Connection closed unexpectedly".to_string()))
+		}
+		buffer.extend_from_slice(&chunk[..read]);
+	}
+}
+
+/// Reads from the stream until `buffer` holds at least `len` bytes.
+fn fill<S: Read>(stream: &mut S, buffer: &mut Vec<u8>, len: usize) -> Result<(), Error> {
+	let mut chunk = [0u8; READ_CHUNK_SIZE];
+	while buffer.len() < len {
+		let read = read_some(stream, &mut chunk)?;
+		if read == 0 {
+			return Err(Error::InvalidResponse("Response body is incomplete".to_string()))
+		}
+		buffer.extend_from_slice(&chunk[..read]);
+	}
+	Ok(())
+}
+
+fn read_some<S: Read>(stream: &mut S, chunk: &mut [u8]) -> Result<usize, Error> {
+	loop {
+		match stream.read(chunk) {
+			Ok(read) => return Ok(read),
+			Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+			// Only a TLS close_notify alert ends the stream cleanly. Without it, the response may
+			// have been truncated by anyone on the path.
+			Err(e)
+				if e.kind() == ErrorKind::UnexpectedEof
+					|| e.kind() == ErrorKind::ConnectionAborted =>
+				return Err(Error::InvalidResponse(
+					"Connection closed without TLS close_notify".to_string(),
+				)),
+			Err(e) => return Err(map_io_error(e)),
+		}
+	}
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+	haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+pub(crate) fn map_io_error(e: std::io::Error) -> Error {
+	match e.kind() {
+		ErrorKind::TimedOut | ErrorKind::WouldBlock => Error::TimeoutError,
+		// rustls reports handshake and certificate failures as invalid data.
+		ErrorKind::InvalidData => Error::TlsError(e.to_string()),
+		_ => Error::IoError(e),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mocks::stream_mock::StreamMock;
+	use core::assert_matches::assert_matches;
+	use http_req::response::Headers;
+
+	#[test]
+	fn request_message_contains_method_resource_headers_and_body() {
+		let url = Url::parse("https://example.com/api/v1?order=desc").unwrap();
+		let mut headers = Headers::new();
+		headers.insert("Host", "example.com");
+		let request = HttpRequest {
+			url: &url,
+			method: Method::POST,
+			headers,
+			body: Some(b"{}"),
+			timeout: None,
+		};
+
+		let message = String::from_utf8(request_message(&request, false)).unwrap();
+
+		assert!(message.starts_with("POST /api/v1?order=desc HTTP/1.1\r\n"));
+		assert!(message.contains("Host: example.com\r\n"));
+		assert!(message.contains("Connection: close\r\n"));
+		assert!(message.ends_with("\r\n\r\n{}"));
+	}
+
+	#[test]
+	fn request_message_asks_for_keep_alive() {
+		let url = Url::parse("https://example.com/status").unwrap();
+
+		let message = String::from_utf8(request_message(&get_request(&url), true)).unwrap();
+
+		assert!(message.contains("Connection: keep-alive\r\n"));
+	}
+
+	#[test]
+	fn exchange_reads_body_with_content_length() {
+		let mut stream =
+			StreamMock::new(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello, ignored");
+
+		let (response, body, reusable) = exchange_with(&mut stream, true);
+
+		assert!(response.status_code().is_success());
+		assert_eq!(body, b"hello");
+		assert!(!reusable);
+		assert!(stream.output.starts_with(b"GET /status HTTP/1.1\r\n"));
+	}
+
+	#[test]
+	fn exchange_reads_chunked_body() {
+		let mut stream = StreamMock::new(
+			b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nWiki\r\n6;ext=1\r\npedia \r\n0\r\nExpires: never\r\n\r\n",
+		);
+
+		let (_, body, reusable) = exchange_with(&mut stream, true);
+
+		assert_eq!(body, b"Wikipedia ");
+		assert!(reusable);
+	}
+
+	#[test]
+	fn exchange_reads_body_until_connection_is_closed() {
+		let mut stream = StreamMock::new(b"HTTP/1.1 200 OK\r\n\r\nuntil the end");
+
+		let (_, body, reusable) = exchange_with(&mut stream, true);
+
+		assert_eq!(body, b"until the end");
+		assert!(!reusable);
+	}
+
+	#[test]
+	fn exchange_fails_for_truncated_body() {
+		let mut stream = StreamMock::new(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nshort");
+		let url = Url::parse("http://example.com/status").unwrap();
+		let request = get_request(&url);
+
+		let result = exchange(&mut stream, &request, true, &mut Vec::new());
+
+		assert_matches!(result, Err(Error::InvalidResponse(_)));
+	}
+
+	#[test]
+	fn exchange_fails_for_body_until_close_without_close_notify() {
+		let mut stream =
+			StreamMock::new(b"HTTP/1.1 200 OK\r\n\r\nmaybe truncated").with_unclean_close();
+
+		assert_matches!(exchange_result(&mut stream), Err(Error::InvalidResponse(_)));
+	}
+
+	#[test]
+	fn exchange_accepts_delimited_body_without_close_notify() {
+		let mut stream = StreamMock::new(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello")
+			.with_unclean_close();
+
+		let (_, body, _) = exchange_with(&mut stream, true);
+
+		assert_eq!(body, b"hello");
+	}
+
+	#[test]
+	fn exchange_fails_for_content_length_above_limit() {
+		let head = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", MAX_BODY_SIZE + 1);
+
+		let result = exchange_result(&mut StreamMock::new(head.as_bytes()));
+
+		assert_matches!(result, Err(Error::ResponseTooLarge(MAX_BODY_SIZE)));
+	}
+
+	#[test]
+	fn exchange_fails_for_chunk_sizes_above_limit() {
+		let too_large = format!(
+			"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n",
+			MAX_BODY_SIZE + 1
+		);
+		let overflowing = format!(
+			"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n1\r\na\r\n{:x}\r\n",
+			usize::MAX
+		);
+
+		assert_matches!(
+			exchange_result(&mut StreamMock::new(too_large.as_bytes())),
+			Err(Error::ResponseTooLarge(MAX_BODY_SIZE))
+		);
+		assert_matches!(
+			exchange_result(&mut StreamMock::new(overflowing.as_bytes())),
+			Err(Error::ResponseTooLarge(MAX_BODY_SIZE))
+		);
+	}
+
+	#[test]
+	fn exchange_fails_for_unterminated_head_above_limit() {
+		let mut response = b"HTTP/1.1 200 OK\r\nX-Padding: ".to_vec();
+		response.resize(MAX_HEAD_SIZE + READ_CHUNK_SIZE, b'a');
+
+		let result = exchange_result(&mut StreamMock::new(&response));
+
+		assert_matches!(result, Err(Error::ResponseTooLarge(MAX_HEAD_SIZE)));
+	}
+
+	#[test]
+	fn connection_is_reusable_only_if_both_sides_keep_it_alive() {
+		let keep_alive_response = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+		let close_response = b"HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 2\r\n\r\nok";
+		let http_1_0_response = b"HTTP/1.0 200 OK\r\nContent-Length: 2\r\n\r\nok";
+
+		assert!(exchange_with(&mut StreamMock::new(keep_alive_response), true).2);
+		assert!(!exchange_with(&mut StreamMock::new(keep_alive_response), false).2);
+		assert!(!exchange_with(&mut StreamMock::new(close_response), true).2);
+		assert!(!exchange_with(&mut StreamMock::new(http_1_0_response), true).2);
+	}
+
+	#[test]
+	fn connection_key_separates_trust_anchors_and_proxies() {
+		let url = Url::parse("https://api.coingecko.com/api/v3").unwrap();
+		let proxy = HttpProxy::new("proxy.internal".to_string(), 3128);
+
+		let key = connection_key(&["root a".to_string()], None, &url).unwrap();
+
+		assert_eq!(key.host, "api.coingecko.com");
+		assert_eq!(key.port, 443);
+		assert_ne!(key, connection_key(&["root b".to_string()], None, &url).unwrap());
+		assert_ne!(key, connection_key(&["root a".to_string()], Some(&proxy), &url).unwrap());
+	}
+
+	fn exchange_with(stream: &mut StreamMock, keep_alive: bool) -> (Response, Vec<u8>, bool) {
+		let url = Url::parse("http://example.com/status").unwrap();
+		let request = get_request(&url);
+		let mut body = Vec::new();
+		let (response, reusable) = exchange(stream, &request, keep_alive, &mut body).unwrap();
+		(response, body, reusable)
+	}
+
+	fn exchange_result(stream: &mut StreamMock) -> Result<(Response, bool), Error> {
+		let url = Url::parse("http://example.com/status").unwrap();
+		exchange(stream, &get_request(&url), true, &mut Vec::new())
+	}
+
+	fn get_request(url: &Url) -> HttpRequest {
+		HttpRequest { url, method: Method::GET, headers: Headers::new(), body: None, timeout: None }
+	}
+}
@@ -0,0 +1,121 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Transcript of an HTTP response, binding a result to the server it was fetched from.
+
+use codec::{Decode, Encode};
+use sha2::{Digest, Sha256};
+use std::string::String;
+
+pub type Sha256Hash = [u8; 32];
+
+/// Summary of a single HTTP request/response exchange.
+///
+/// Contains everything a third party needs to check where a result came from:
+/// the requested URL, when it was requested, the fingerprint of the TLS certificate
+/// the server presented (`None` for plain HTTP) and the hash of the response body.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct ResponseTranscript {
+	pub url: String,
+	/// Unix timestamp in milliseconds.
+	pub timestamp: u64,
+	pub status_code: u16,
+	/// SHA-256 of the DER encoded server (leaf) certificate.
+	pub server_certificate_fingerprint: Option<Sha256Hash>,
+	/// SHA-256 of the raw response body.
+	pub body_hash: Sha256Hash,
+}
+
+impl ResponseTranscript {
+	pub fn new(
+		url: String,
+		timestamp: u64,
+		status_code: u16,
+		server_certificate: Option<&[u8]>,
+		body: &[u8],
+	) -> Self {
+		ResponseTranscript {
+			url,
+			timestamp,
+			status_code,
+			server_certificate_fingerprint: server_certificate.map(sha256),
+			body_hash: sha256(body),
+		}
+	}
+
+	/// Checks whether `body` is the response body this transcript was created for.
+	pub fn matches_body(&self, body: &[u8]) -> bool {
+		self.body_hash == sha256(body)
+	}
+}
+
+pub fn sha256(data: &[u8]) -> Sha256Hash {
+	Sha256::digest(data).into()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn sha256_of_empty_input_is_correct() {
+		assert_eq!(
+			sha256(&[]).to_vec(),
+			hex_literal("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+		);
+	}
+
+	#[test]
+	fn transcript_hashes_body_and_certificate() {
+		let transcript = ResponseTranscript::new(
+			"https://example.com/api".to_string(),
+			1_700_000_000_000,
+			200,
+			Some(b"certificate"),
+			b"{\"rate\":42}",
+		);
+
+		assert_eq!(transcript.server_certificate_fingerprint, Some(sha256(b"certificate")));
+		assert!(transcript.matches_body(b"{\"rate\":42}"));
+		assert!(!transcript.matches_body(b"{\"rate\":43}"));
+	}
+
+	#[test]
+	fn transcript_without_tls_has_no_fingerprint() {
+		let transcript =
+			ResponseTranscript::new("http://example.com".to_string(), 0, 200, None, &[]);
+
+		assert!(transcript.server_certificate_fingerprint.is_none());
+	}
+
+	#[test]
+	fn transcript_encoding_round_trips() {
+		let transcript =
+			ResponseTranscript::new("https://example.com".to_string(), 5, 404, Some(&[1, 2]), &[3]);
+
+		let decoded = ResponseTranscript::decode(&mut transcript.encode().as_slice()).unwrap();
+
+		assert_eq!(decoded, transcript);
+	}
+
+	fn hex_literal(hex: &str) -> Vec<u8> {
+		(0..hex.len())
+			.step_by(2)
+			.map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+			.collect()
+	}
+}
@@ -15,13 +15,14 @@
 
 */
 
+use crate::sidechain_explorer::SidechainExplorerModuleBuilder;
 use itp_enclave_api::direct_request::DirectRequest;
 use itp_rpc::RpcRequest;
 use itp_utils::ToHexPrefixed;
 use its_peer_fetch::block_fetch_server::BlockFetchServerModuleBuilder;
 use its_primitives::types::block::SignedBlock;
 use its_rpc_handler::constants::RPC_METHOD_NAME_IMPORT_BLOCKS;
-use its_storage::interface::FetchBlocks;
+use its_storage::{interface::FetchBlocks, SubscribeHeads};
 use jsonrpsee::{
 	types::error::CallError,
 	ws_server::{RpcModule, WsServerBuilder},
@@ -30,6 +31,11 @@ use log::debug;
 use std::{net::SocketAddr, sync::Arc};
 use tokio::net::ToSocketAddrs;
 
+pub mod sidechain_explorer;
+
+/// Maximal number of concurrent client connections.
+pub const MAX_RPC_CONNECTIONS: u64 = 100;
+
 #[cfg(test)]
 mod mock;
 #[cfg(test)]
@@ -42,9 +48,13 @@ pub async fn run_server<Enclave, FetchSidechainBlocks>(
 ) -> anyhow::Result<SocketAddr>
 where
 	Enclave: DirectRequest,
-	FetchSidechainBlocks: FetchBlocks<SignedBlock> + Send + Sync + 'static,
+	FetchSidechainBlocks:
+		FetchBlocks<SignedBlock> + SubscribeHeads<SignedBlock> + Send + Sync + 'static,
 {
-	let mut server = WsServerBuilder::default().build(addr).await?;
+	let mut server = WsServerBuilder::default()
+		.max_connections(MAX_RPC_CONNECTIONS)
+		.build(addr)
+		.await?;
 
 	// FIXME: import block should be moved to trusted side.
 	let mut import_sidechain_block_module = RpcModule::new(enclave);
@@ -66,11 +76,16 @@ where
 	)?;
 	server.register_module(import_sidechain_block_module).unwrap();
 
-	let fetch_sidechain_blocks_module = BlockFetchServerModuleBuilder::new(sidechain_block_fetcher)
-		.build()
-		.map_err(|e| CallError::Failed(e.to_string().into()))?; // `to_string` necessary due to no all errors implementing Send + Sync.
+	let fetch_sidechain_blocks_module =
+		BlockFetchServerModuleBuilder::new(sidechain_block_fetcher.clone())
+			.build()
+			.map_err(|e| CallError::Failed(e.to_string().into()))?; // `to_string` necessary due to no all errors implementing Send + Sync.
 	server.register_module(fetch_sidechain_blocks_module).unwrap();
 
+	let sidechain_explorer_module =
+		SidechainExplorerModuleBuilder::new(sidechain_block_fetcher).build()?;
+	server.register_module(sidechain_explorer_module).unwrap();
+
 	let socket_addr = server.local_addr()?;
 	tokio::spawn(async move { server.start().await });
 
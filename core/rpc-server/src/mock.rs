@@ -15,15 +15,23 @@
 
 */
 
+use crate::sidechain_explorer::SendHeader;
 use itp_enclave_api::{direct_request::DirectRequest, EnclaveResult};
 use itp_rpc::RpcResponse;
 use itp_utils::ToHexPrefixed;
 use its_primitives::{
 	traits::ShardIdentifierFor,
-	types::{BlockHash, SignedBlock, SignedBlock as SignedSidechainBlock},
+	types::{
+		header::SidechainHeader, BlockHash, BlockNumber, SignedBlock,
+		SignedBlock as SignedSidechainBlock,
+	},
 };
-use its_storage::interface::FetchBlocks;
+use its_storage::{interface::FetchBlocks, HeadNotification, SubscribeHeads};
 use parity_scale_codec::Encode;
+use std::sync::{
+	mpsc::{channel, Receiver},
+	Arc, Mutex,
+};
 
 pub struct TestEnclave;
 
@@ -52,4 +60,70 @@ impl FetchBlocks<SignedSidechainBlock> for MockSidechainBlockFetcher {
 	) -> its_storage::Result<Vec<SignedBlock>> {
 		Ok(Vec::new())
 	}
+
+	fn fetch_block_by_hash(
+		&self,
+		_block_hash: &BlockHash,
+	) -> its_storage::Result<Option<SignedBlock>> {
+		Ok(None)
+	}
+
+	fn fetch_block_by_number(
+		&self,
+		_shard_identifier: &ShardIdentifierFor<SignedBlock>,
+		_block_number: BlockNumber,
+	) -> its_storage::Result<Option<SignedBlock>> {
+		Ok(None)
+	}
+
+	fn fetch_latest_block(
+		&self,
+		_shard_identifier: &ShardIdentifierFor<SignedBlock>,
+	) -> its_storage::Result<Option<SignedBlock>> {
+		Ok(None)
+	}
+
+	fn fetch_finalized_block(
+		&self,
+		_shard_identifier: &ShardIdentifierFor<SignedBlock>,
+	) -> its_storage::Result<Option<SignedBlock>> {
+		Ok(None)
+	}
+
+	fn is_retracted_block(&self, _block_hash: &BlockHash) -> its_storage::Result<bool> {
+		Ok(false)
+	}
+}
+
+impl SubscribeHeads<SignedSidechainBlock> for MockSidechainBlockFetcher {
+	fn subscribe_heads(&self) -> Receiver<HeadNotification<SignedSidechainBlock>> {
+		channel().1
+	}
+}
+
+/// Head subscription sink, recording the sent headers until it is closed.
+#[derive(Clone, Default)]
+pub struct SendHeaderMock {
+	sent_headers: Arc<Mutex<Vec<SidechainHeader>>>,
+	is_closed: Arc<Mutex<bool>>,
+}
+
+impl SendHeaderMock {
+	pub fn close(&self) {
+		*self.is_closed.lock().unwrap() = true;
+	}
+
+	pub fn sent_headers(&self) -> Vec<SidechainHeader> {
+		self.sent_headers.lock().unwrap().clone()
+	}
+}
+
+impl SendHeader for SendHeaderMock {
+	fn send_header(&mut self, header: &SidechainHeader) -> Result<(), String> {
+		if *self.is_closed.lock().unwrap() {
+			return Err("Subscription closed".into())
+		}
+		self.sent_headers.lock().unwrap().push(*header);
+		Ok(())
+	}
 }
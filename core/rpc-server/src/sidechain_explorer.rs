@@ -0,0 +1,397 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Public RPC methods to explore the sidechain blocks in our storage.
+
+use its_primitives::{
+	traits::{Block as BlockTrait, SignedBlock as SignedBlockTrait},
+	types::{
+		finality::FinalityStatus, header::SidechainHeader as Header, BlockHash, BlockNumber,
+		ShardIdentifier, SignedBlock,
+	},
+};
+use its_rpc_handler::constants::{
+	RPC_METHOD_NAME_GET_BLOCK_BY_HASH, RPC_METHOD_NAME_GET_BLOCK_BY_NUMBER,
+	RPC_METHOD_NAME_GET_FINALITY_STATUS, RPC_METHOD_NAME_GET_FINALIZED_HEADER,
+	RPC_METHOD_NAME_GET_LATEST_HEADER, RPC_METHOD_NAME_SUBSCRIBE_FINALIZED_HEADS,
+	RPC_METHOD_NAME_SUBSCRIBE_NEW_HEADS, RPC_METHOD_NAME_UNSUBSCRIBE_FINALIZED_HEADS,
+	RPC_METHOD_NAME_UNSUBSCRIBE_NEW_HEADS,
+};
+use its_storage::{interface::FetchBlocks, HeadNotification, SubscribeHeads};
+use jsonrpsee::{
+	types::error::{CallError, Error as RpcError},
+	ws_server::{RpcModule, SubscriptionSink},
+};
+use log::*;
+use std::{
+	sync::{mpsc::RecvTimeoutError, Arc, Mutex, MutexGuard},
+	thread,
+	time::{Duration, Instant},
+};
+
+/// Maximal number of concurrent subscriptions to sidechain heads.
+pub const MAX_HEAD_SUBSCRIPTIONS: usize = 1024;
+
+/// Time after which an idle head subscription is checked for being closed.
+pub const HEAD_SUBSCRIPTION_KEEP_ALIVE: Duration = Duration::from_secs(30);
+
+/// Context of the sidechain explorer RPC methods.
+pub struct SidechainExplorerContext<FetchSidechainBlocks> {
+	sidechain_block_fetcher: Arc<FetchSidechainBlocks>,
+	heads_notifier: Arc<HeadsNotifier>,
+}
+
+/// RPC server module builder for the sidechain block explorer methods.
+pub struct SidechainExplorerModuleBuilder<FetchSidechainBlocks> {
+	sidechain_block_fetcher: Arc<FetchSidechainBlocks>,
+}
+
+impl<FetchSidechainBlocks> SidechainExplorerModuleBuilder<FetchSidechainBlocks>
+where
+	FetchSidechainBlocks:
+		FetchBlocks<SignedBlock> + SubscribeHeads<SignedBlock> + Send + Sync + 'static,
+{
+	pub fn new(sidechain_block_fetcher: Arc<FetchSidechainBlocks>) -> Self {
+		SidechainExplorerModuleBuilder { sidechain_block_fetcher }
+	}
+
+	pub fn build(
+		self,
+	) -> Result<RpcModule<SidechainExplorerContext<FetchSidechainBlocks>>, RpcError> {
+		let heads_notifier = HeadsNotifier::start(self.sidechain_block_fetcher.as_ref());
+		let mut explorer_module = RpcModule::new(SidechainExplorerContext {
+			sidechain_block_fetcher: self.sidechain_block_fetcher,
+			heads_notifier,
+		});
+
+		explorer_module.register_method(
+			RPC_METHOD_NAME_GET_BLOCK_BY_NUMBER,
+			|params, context| {
+				debug!("{}: {:?}", RPC_METHOD_NAME_GET_BLOCK_BY_NUMBER, params);
+				let (shard, block_number) = params.parse::<(ShardIdentifier, BlockNumber)>()?;
+				context
+					.sidechain_block_fetcher
+					.fetch_block_by_number(&shard, block_number)
+					.map_err(|e| CallError::Failed(e.into()))
+			},
+		)?;
+
+		explorer_module.register_method(RPC_METHOD_NAME_GET_BLOCK_BY_HASH, |params, context| {
+			debug!("{}: {:?}", RPC_METHOD_NAME_GET_BLOCK_BY_HASH, params);
+			let block_hash = params.one::<BlockHash>()?;
+			context
+				.sidechain_block_fetcher
+				.fetch_block_by_hash(&block_hash)
+				.map_err(|e| CallError::Failed(e.into()))
+		})?;
+
+		explorer_module.register_method(RPC_METHOD_NAME_GET_LATEST_HEADER, |params, context| {
+			debug!("{}: {:?}", RPC_METHOD_NAME_GET_LATEST_HEADER, params);
+			let shard = params.one::<ShardIdentifier>()?;
+			latest_header(context.sidechain_block_fetcher.as_ref(), &shard)
+				.map_err(|e| CallError::Failed(e.into()))
+		})?;
+
+		explorer_module.register_method(
+			RPC_METHOD_NAME_GET_FINALIZED_HEADER,
+			|params, context| {
+				debug!("{}: {:?}", RPC_METHOD_NAME_GET_FINALIZED_HEADER, params);
+				let shard = params.one::<ShardIdentifier>()?;
+				context
+					.sidechain_block_fetcher
+					.fetch_finalized_block(&shard)
+					.map(|maybe_block| maybe_block.map(|b| *b.block().header()))
+					.map_err(|e| CallError::Failed(e.into()))
+			},
+		)?;
+
+		explorer_module.register_method(
+			RPC_METHOD_NAME_GET_FINALITY_STATUS,
+			|params, context| {
+				debug!("{}: {:?}", RPC_METHOD_NAME_GET_FINALITY_STATUS, params);
+				let block_hash = params.one::<BlockHash>()?;
+				finality_status(context.sidechain_block_fetcher.as_ref(), &block_hash)
+					.map_err(|e| CallError::Failed(e.into()))
+			},
+		)?;
+
+		explorer_module.register_subscription(
+			RPC_METHOD_NAME_SUBSCRIBE_NEW_HEADS,
+			RPC_METHOD_NAME_UNSUBSCRIBE_NEW_HEADS,
+			|params, sink, context| {
+				let shard = params.one::<ShardIdentifier>()?;
+				info!("New subscription to sidechain headers of shard {:?}", shard);
+
+				// Subscriptions to unknown shards would never receive a header, so we could not
+				// tell when they are closed.
+				let latest_header =
+					known_shard_latest_header(context.sidechain_block_fetcher.as_ref(), &shard)?;
+				context.heads_notifier.subscribe(
+					shard,
+					HeadKind::New,
+					sink,
+					Some(latest_header),
+				)?;
+				Ok(())
+			},
+		)?;
+
+		explorer_module.register_subscription(
+			RPC_METHOD_NAME_SUBSCRIBE_FINALIZED_HEADS,
+			RPC_METHOD_NAME_UNSUBSCRIBE_FINALIZED_HEADS,
+			|params, sink, context| {
+				let shard = params.one::<ShardIdentifier>()?;
+				info!("New subscription to finalized sidechain headers of shard {:?}", shard);
+
+				known_shard_latest_header(context.sidechain_block_fetcher.as_ref(), &shard)?;
+				let finalized_header = context
+					.sidechain_block_fetcher
+					.fetch_finalized_block(&shard)
+					.map(|maybe_block| maybe_block.map(|b| *b.block().header()))
+					.map_err(|e| CallError::Failed(e.into()))?;
+				context.heads_notifier.subscribe(
+					shard,
+					HeadKind::Finalized,
+					sink,
+					finalized_header,
+				)?;
+				Ok(())
+			},
+		)?;
+
+		Ok(explorer_module)
+	}
+}
+
+/// Which heads a subscription receives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum HeadKind {
+	New,
+	Finalized,
+}
+
+/// Sink of a head subscription.
+///
+/// Sending fails once the client unsubscribed or its connection is closed.
+pub(crate) trait SendHeader {
+	fn send_header(&mut self, header: &Header) -> Result<(), String>;
+}
+
+impl SendHeader for SubscriptionSink {
+	fn send_header(&mut self, header: &Header) -> Result<(), String> {
+		self.send(header).map_err(|e| format!("{:?}", e))
+	}
+}
+
+struct HeadSubscription<Sink> {
+	shard: ShardIdentifier,
+	kind: HeadKind,
+	sink: Sink,
+	/// Last header sent, re-sent to check whether the subscription is still alive.
+	last_header: Option<Header>,
+	last_sent: Instant,
+}
+
+impl<Sink: SendHeader> HeadSubscription<Sink> {
+	/// Send `header`, returns false if the subscription is closed.
+	fn send(&mut self, header: &Header) -> bool {
+		match self.sink.send_header(header) {
+			Ok(_) => {
+				self.last_header = Some(*header);
+				self.last_sent = Instant::now();
+				true
+			},
+			Err(e) => {
+				debug!("Closing sidechain header subscription: {}", e);
+				false
+			},
+		}
+	}
+}
+
+/// Sends the heads announced by the storage to all subscriptions, from a single thread.
+///
+/// The sink of a subscription only tells it is closed, by the client unsubscribing or
+/// disconnecting, when we send to it. So subscriptions idle for [`HEAD_SUBSCRIPTION_KEEP_ALIVE`]
+/// get their last header again, and are removed if that fails.
+pub(crate) struct HeadsNotifier<Sink = SubscriptionSink> {
+	subscriptions: Mutex<Vec<HeadSubscription<Sink>>>,
+}
+
+impl<Sink> Default for HeadsNotifier<Sink> {
+	fn default() -> Self {
+		HeadsNotifier { subscriptions: Default::default() }
+	}
+}
+
+impl<Sink: SendHeader + Send + 'static> HeadsNotifier<Sink> {
+	/// Create a notifier, fed by the head notifications of `storage`.
+	pub(crate) fn start<SubscribeSidechainHeads>(storage: &SubscribeSidechainHeads) -> Arc<Self>
+	where
+		SubscribeSidechainHeads: SubscribeHeads<SignedBlock>,
+	{
+		let heads_notifier = Arc::new(HeadsNotifier::default());
+		let head_notifications = storage.subscribe_heads();
+
+		let notifier = heads_notifier.clone();
+		thread::spawn(move || loop {
+			match head_notifications.recv_timeout(HEAD_SUBSCRIPTION_KEEP_ALIVE) {
+				Ok(head_notification) => notifier.notify(&head_notification),
+				Err(RecvTimeoutError::Timeout) => {},
+				Err(RecvTimeoutError::Disconnected) => {
+					debug!("Sidechain head notifications ended");
+					return
+				},
+			}
+			notifier.remove_closed_subscriptions(HEAD_SUBSCRIPTION_KEEP_ALIVE);
+		});
+		heads_notifier
+	}
+
+	/// Add a subscription, starting with the `current_head` if there is one.
+	pub(crate) fn subscribe(
+		&self,
+		shard: ShardIdentifier,
+		kind: HeadKind,
+		sink: Sink,
+		current_head: Option<Header>,
+	) -> Result<(), CallError> {
+		let mut subscriptions = self.lock_subscriptions()?;
+		if subscriptions.len() >= MAX_HEAD_SUBSCRIPTIONS {
+			return Err(CallError::Failed(
+				format!("Limit of {} head subscriptions is reached", MAX_HEAD_SUBSCRIPTIONS).into(),
+			))
+		}
+
+		let mut subscription =
+			HeadSubscription { shard, kind, sink, last_header: None, last_sent: Instant::now() };
+		if let Some(header) = current_head {
+			if !subscription.send(&header) {
+				return Ok(())
+			}
+		}
+		subscriptions.push(subscription);
+		Ok(())
+	}
+
+	pub(crate) fn number_of_subscriptions(&self) -> usize {
+		self.subscriptions.lock().map(|s| s.len()).unwrap_or_default()
+	}
+
+	fn notify(&self, head_notification: &HeadNotification<SignedBlock>) {
+		let (kind, block) = match head_notification {
+			HeadNotification::New(block) => (HeadKind::New, block),
+			HeadNotification::Finalized(block) => (HeadKind::Finalized, block),
+		};
+		let header = block.block().header();
+
+		match self.lock_subscriptions() {
+			Ok(mut subscriptions) => subscriptions.retain_mut(|subscription| {
+				if subscription.kind != kind || subscription.shard != header.shard_id {
+					return true
+				}
+				subscription.send(header)
+			}),
+			Err(e) => error!("{:?}", e),
+		}
+	}
+
+	/// Re-send the last header to subscriptions idle for `max_idle`, and remove the closed ones.
+	///
+	/// Subscriptions that did not receive any header yet have nothing to re-send. They are
+	/// removed with the first head of their shard if they are closed.
+	pub(crate) fn remove_closed_subscriptions(&self, max_idle: Duration) {
+		match self.lock_subscriptions() {
+			Ok(mut subscriptions) =>
+				subscriptions.retain_mut(|subscription| match subscription.last_header {
+					Some(header) if subscription.last_sent.elapsed() >= max_idle =>
+						subscription.send(&header),
+					_ => true,
+				}),
+			Err(e) => error!("{:?}", e),
+		}
+	}
+
+	fn lock_subscriptions(&self) -> Result<MutexGuard<'_, Vec<HeadSubscription<Sink>>>, CallError> {
+		self.subscriptions
+			.lock()
+			.map_err(|_| CallError::Failed("Head subscriptions lock is poisoned".into()))
+	}
+}
+
+fn latest_header<FetchSidechainBlocks>(
+	sidechain_block_fetcher: &FetchSidechainBlocks,
+	shard: &ShardIdentifier,
+) -> its_storage::Result<Option<Header>>
+where
+	FetchSidechainBlocks: FetchBlocks<SignedBlock>,
+{
+	sidechain_block_fetcher
+		.fetch_latest_block(shard)
+		.map(|maybe_block| maybe_block.map(|b| *b.block().header()))
+}
+
+/// Latest header of `shard`, fails if we have no block of it.
+fn known_shard_latest_header<FetchSidechainBlocks>(
+	sidechain_block_fetcher: &FetchSidechainBlocks,
+	shard: &ShardIdentifier,
+) -> Result<Header, CallError>
+where
+	FetchSidechainBlocks: FetchBlocks<SignedBlock>,
+{
+	latest_header(sidechain_block_fetcher, shard)
+		.map_err(|e| CallError::Failed(e.into()))?
+		.ok_or_else(|| CallError::Failed(format!("Unknown shard {:?}", shard).into()))
+}
+
+/// Finality status of the block with `block_hash`, `None` if the block is unknown.
+///
+/// A block is final if it is on our chain and not newer than the finalized block of its shard,
+/// and retracted if a re-org dropped it from our chain.
+pub(crate) fn finality_status<FetchSidechainBlocks>(
+	sidechain_block_fetcher: &FetchSidechainBlocks,
+	block_hash: &BlockHash,
+) -> its_storage::Result<Option<FinalityStatus>>
+where
+	FetchSidechainBlocks: FetchBlocks<SignedBlock>,
+{
+	let block = match sidechain_block_fetcher.fetch_block_by_hash(block_hash)? {
+		Some(block) => block,
+		None if sidechain_block_fetcher.is_retracted_block(block_hash)? =>
+			return Ok(Some(FinalityStatus::Retracted)),
+		None => return Ok(None),
+	};
+	let header = block.block().header();
+
+	let is_on_chain = sidechain_block_fetcher
+		.fetch_block_by_number(&header.shard_id, header.block_number)?
+		.map(|b| b.hash() == *block_hash)
+		.unwrap_or(false);
+	if !is_on_chain {
+		return Ok(Some(FinalityStatus::Retracted))
+	}
+
+	let is_final = sidechain_block_fetcher
+		.fetch_finalized_block(&header.shard_id)?
+		.map(|finalized_block| header.block_number <= finalized_block.block().header().block_number)
+		.unwrap_or(false);
+	if is_final {
+		Ok(Some(FinalityStatus::Final))
+	} else {
+		Ok(Some(FinalityStatus::Tentative))
+	}
+}
@@ -16,10 +16,26 @@
 */
 
 use super::*;
-use crate::mock::MockSidechainBlockFetcher;
+use crate::{
+	mock::{MockSidechainBlockFetcher, SendHeaderMock},
+	sidechain_explorer::{finality_status, HeadKind, HeadsNotifier, MAX_HEAD_SUBSCRIPTIONS},
+};
 use itp_rpc::RpcResponse;
-use its_rpc_handler::constants::RPC_METHOD_NAME_IMPORT_BLOCKS;
-use its_test::sidechain_block_builder::{SidechainBlockBuilder, SidechainBlockBuilderTrait};
+use its_primitives::{
+	traits::SignedBlock as SignedBlockTrait,
+	types::{
+		finality::FinalityStatus, header::SidechainHeader, BlockHash, ShardIdentifier,
+		SignedBlock as SignedSidechainBlock,
+	},
+};
+use its_rpc_handler::constants::{
+	RPC_METHOD_NAME_GET_LATEST_HEADER, RPC_METHOD_NAME_IMPORT_BLOCKS,
+};
+use its_storage::fetch_blocks_mock::FetchBlocksMock;
+use its_test::{
+	sidechain_block_builder::{SidechainBlockBuilder, SidechainBlockBuilderTrait},
+	sidechain_header_builder::SidechainHeaderBuilder,
+};
 use jsonrpsee::{
 	types::{to_json_value, traits::Client},
 	ws_client::WsClientBuilder,
@@ -27,6 +43,7 @@ use jsonrpsee::{
 use log::info;
 use mock::TestEnclave;
 use parity_scale_codec::Decode;
+use std::time::Duration;
 
 fn init() {
 	let _ = env_logger::builder().is_test(true).try_init();
@@ -54,3 +71,144 @@ async fn test_client_calls() {
 
 	assert!(RpcResponse::decode(&mut response.as_slice()).is_ok());
 }
+
+#[tokio::test]
+async fn get_latest_header_returns_none_for_unknown_shard() {
+	init();
+	let addr =
+		run_server("127.0.0.1:0", Arc::new(TestEnclave), Arc::new(MockSidechainBlockFetcher))
+			.await
+			.unwrap();
+
+	let url = format!("ws://{}", addr);
+	let client = WsClientBuilder::default().build(&url).await.unwrap();
+	let response: Option<SidechainHeader> = client
+		.request(
+			RPC_METHOD_NAME_GET_LATEST_HEADER,
+			vec![to_json_value(ShardIdentifier::default()).unwrap()].into(),
+		)
+		.await
+		.unwrap();
+
+	assert!(response.is_none());
+}
+
+fn block_on(parent: Option<&SignedSidechainBlock>) -> SignedSidechainBlock {
+	let (parent_hash, number) = match parent {
+		Some(parent) => (parent.hash(), parent.block.header.block_number + 1),
+		None => (BlockHash::default(), 1),
+	};
+	let header = SidechainHeaderBuilder::default()
+		.with_parent_hash(parent_hash)
+		.with_block_number(number)
+		.build();
+	SidechainBlockBuilder::default().with_header(header).build_signed()
+}
+
+#[test]
+fn finality_status_is_none_for_unknown_block() {
+	let fetcher = FetchBlocksMock::default().with_blocks(vec![block_on(None)]);
+
+	assert!(finality_status(&fetcher, &BlockHash::repeat_byte(1)).unwrap().is_none());
+}
+
+#[test]
+fn finality_status_is_final_up_to_finalized_block() {
+	let block_1 = block_on(None);
+	let block_2 = block_on(Some(&block_1));
+	let block_3 = block_on(Some(&block_2));
+	let fetcher = FetchBlocksMock::default()
+		.with_blocks(vec![block_1.clone(), block_2.clone(), block_3.clone()])
+		.with_finalized_block_number(2);
+
+	assert_eq!(finality_status(&fetcher, &block_1.hash()).unwrap(), Some(FinalityStatus::Final));
+	assert_eq!(finality_status(&fetcher, &block_2.hash()).unwrap(), Some(FinalityStatus::Final));
+	assert_eq!(
+		finality_status(&fetcher, &block_3.hash()).unwrap(),
+		Some(FinalityStatus::Tentative)
+	);
+}
+
+#[test]
+fn finality_status_is_tentative_without_finalized_block() {
+	let block_1 = block_on(None);
+	let fetcher = FetchBlocksMock::default().with_blocks(vec![block_1.clone()]);
+
+	assert_eq!(
+		finality_status(&fetcher, &block_1.hash()).unwrap(),
+		Some(FinalityStatus::Tentative)
+	);
+}
+
+#[test]
+fn finality_status_is_retracted_for_block_dropped_by_reorg() {
+	let block_1 = block_on(None);
+	let mut block_2a = block_on(Some(&block_1));
+	block_2a.block.header.state_root = BlockHash::repeat_byte(1);
+	let block_2b = block_on(Some(&block_1));
+	let fetcher = FetchBlocksMock::default()
+		.with_blocks(vec![block_1, block_2b.clone()])
+		.with_retracted_blocks(vec![block_2a.hash()]);
+
+	assert_eq!(
+		finality_status(&fetcher, &block_2a.hash()).unwrap(),
+		Some(FinalityStatus::Retracted)
+	);
+	assert_eq!(
+		finality_status(&fetcher, &block_2b.hash()).unwrap(),
+		Some(FinalityStatus::Tentative)
+	);
+}
+
+#[test]
+fn closed_head_subscription_is_removed_when_idle() {
+	let notifier = HeadsNotifier::<SendHeaderMock>::default();
+	let open_sink = SendHeaderMock::default();
+	let closed_sink = SendHeaderMock::default();
+	let header = block_on(None).block.header;
+
+	notifier
+		.subscribe(ShardIdentifier::default(), HeadKind::New, open_sink.clone(), Some(header))
+		.unwrap();
+	notifier
+		.subscribe(ShardIdentifier::default(), HeadKind::New, closed_sink.clone(), Some(header))
+		.unwrap();
+	closed_sink.close();
+
+	notifier.remove_closed_subscriptions(Duration::ZERO);
+
+	assert_eq!(notifier.number_of_subscriptions(), 1);
+	assert_eq!(open_sink.sent_headers(), vec![header, header]);
+}
+
+#[test]
+fn head_subscription_is_not_added_if_sink_is_closed() {
+	let notifier = HeadsNotifier::<SendHeaderMock>::default();
+	let sink = SendHeaderMock::default();
+	sink.close();
+
+	notifier
+		.subscribe(
+			ShardIdentifier::default(),
+			HeadKind::New,
+			sink,
+			Some(block_on(None).block.header),
+		)
+		.unwrap();
+
+	assert_eq!(notifier.number_of_subscriptions(), 0);
+}
+
+#[test]
+fn head_subscriptions_are_limited() {
+	let notifier = HeadsNotifier::<SendHeaderMock>::default();
+	for _ in 0..MAX_HEAD_SUBSCRIPTIONS {
+		notifier
+			.subscribe(ShardIdentifier::default(), HeadKind::New, SendHeaderMock::default(), None)
+			.unwrap();
+	}
+
+	assert!(notifier
+		.subscribe(ShardIdentifier::default(), HeadKind::New, SendHeaderMock::default(), None)
+		.is_err());
+}
@@ -26,10 +26,8 @@ pub extern "C" fn test_main_entrance() -> sgx_types::size_t {
 #[cfg(not(feature = "teeracle"))]
 #[no_mangle]
 pub unsafe extern "C" fn update_market_data_xt(
-	_crypto_currency_ptr: *const u8,
-	_crypto_currency_size: u32,
-	_fiat_currency_ptr: *const u8,
-	_fiat_currency_size: u32,
+	_candidate_feeds: *const u8,
+	_candidate_feeds_size: u32,
 	_unchecked_extrinsic: *mut u8,
 	_unchecked_extrinsic_size: u32,
 ) -> sgx_types::sgx_status_t {
@@ -40,10 +38,8 @@ pub unsafe extern "C" fn update_market_data_xt(
 #[cfg(not(feature = "teeracle"))]
 #[no_mangle]
 pub unsafe extern "C" fn update_weather_data_xt(
-	_weather_info_longitude: *const u8,
-	_weather_info_longitude_size: u32,
-	_weather_info_latitude: *const u8,
-	_weather_info_latitude_size: u32,
+	_weather_query: *const u8,
+	_weather_query_size: u32,
 	_unchecked_extrinsic: *mut u8,
 	_unchecked_extrinsic_size: u32,
 ) -> sgx_types::sgx_status_t {
@@ -51,6 +51,7 @@ pub enum Error {
 	Attestation(itp_attestation_handler::error::Error),
 	Metadata(itp_node_api_metadata::error::Error),
 	BufferError(itp_utils::buffer::BufferError),
+	Provisioning(crate::tls_ra::protocol::ProvisioningError),
 	Other(Box<dyn std::error::Error>),
 }
 
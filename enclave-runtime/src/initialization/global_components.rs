@@ -28,9 +28,18 @@ use crate::{
 		target_b_parachain::TargetBParachainHandler, target_b_solochain::TargetBSolochainHandler,
 	},
 	ocall::OcallApi,
-	rpc::rpc_response_channel::RpcResponseChannel,
+	rpc::{
+		encointer_watcher::EncointerWatcher,
+		issued_badges::{IssuedBadges, PersonhoodOracleEventHandler},
+		personhood_sources::ReputationSource,
+		rpc_response_channel::RpcResponseChannel,
+		worker_api_direct::NostrBadgePublisher,
+	},
+	sidechain_auditor::ParentchainAuditorEncryption,
 	tls_ra::seal_handler::SealHandler,
 };
+#[cfg(feature = "teeracle")]
+use ita_oracle::ConnectionPool;
 use ita_sgx_runtime::Runtime;
 use ita_stf::{Getter, State as StfState, Stf, TrustedCallSigned};
 use itc_direct_rpc_server::{
@@ -82,6 +91,7 @@ use itp_top_pool::basic_pool::BasicPool;
 use itp_top_pool_author::{
 	api::SidechainApi,
 	author::{Author, AuthorTopFilter},
+	persistence::{TopPoolPersistence, TopPoolSeal},
 };
 use itp_types::{Block as ParentchainBlock, SignedBlock as SignedParentchainBlock};
 use its_primitives::{
@@ -91,7 +101,10 @@ use its_primitives::{
 use its_sidechain::{
 	aura::block_importer::BlockImporter as SidechainBlockImporter,
 	block_composer::BlockComposer,
-	consensus_common::{BlockImportConfirmationHandler, BlockImportQueueWorker, PeerBlockSync},
+	consensus_common::{
+		AppliedSidechainBlocks, BlockImportConfirmationHandler, BlockImportQueueWorker,
+		PeerBlockSync, SidechainBlockStates, SidechainFinalityTracker,
+	},
 };
 use lazy_static::lazy_static;
 use sgx_crypto_helper::rsa3072::Rsa3072KeyPair;
@@ -146,14 +159,16 @@ pub type EnclaveExtrinsicsFactory =
 /// The enclave's generic indirect executor type.
 ///
 /// The `IndirectCallsFilter` calls filter can be configured per parentchain.
-pub type EnclaveIndirectCallsExecutor<IndirectCallsFilter> = IndirectCallsExecutor<
-	EnclaveShieldingKeyRepository,
-	EnclaveStfEnclaveSigner,
-	EnclaveTopPoolAuthor,
-	EnclaveNodeMetadataRepository,
-	IndirectCallsFilter,
-	EventCreator,
->;
+pub type EnclaveIndirectCallsExecutor<IndirectCallsFilter, EncointerEventHandler = ()> =
+	IndirectCallsExecutor<
+		EnclaveShieldingKeyRepository,
+		EnclaveStfEnclaveSigner,
+		EnclaveTopPoolAuthor,
+		EnclaveNodeMetadataRepository,
+		IndirectCallsFilter,
+		EventCreator,
+		EncointerEventHandler,
+	>;
 
 pub type EnclaveValidatorAccessor = ValidatorAccessor<
 	LightValidation<ParentchainBlock, EnclaveOCallApi>,
@@ -195,16 +210,24 @@ pub type IntegriteeParentchainBlockImportDispatcher = BlockImportDispatcher<
 	IntegriteeParentchainImmediateBlockImportDispatcher,
 >;
 
+/// Keeps the personhood oracle's watcher and the issued Nostr badges of a target parentchain
+/// up to date.
+pub type EnclavePersonhoodOracleEventHandler = PersonhoodOracleEventHandler<NostrBadgePublisher>;
+
 // Stuff for the Target A parentchain
 
 /// IndirectCalls executor instance of the Target A parentchain.
 ///
-/// **Note**: The filter here is purely used for demo purposes.
+/// **Note**: The filter here is purely used for demo purposes. The Encointer events of the
+/// Target A parentchain are forwarded to the personhood oracle, see
+/// [`EnclavePersonhoodOracleEventHandler`].
 ///
 /// Also note that the extrinsic parser must be changed if the signed extra contains the
 /// `AssetTxPayment`.
-pub type TargetAParentchainIndirectExecutor =
-	EnclaveIndirectCallsExecutor<TransferToAliceShieldsFundsFilter<ParentchainExtrinsicParser>>;
+pub type TargetAParentchainIndirectExecutor = EnclaveIndirectCallsExecutor<
+	TransferToAliceShieldsFundsFilter<ParentchainExtrinsicParser>,
+	EnclavePersonhoodOracleEventHandler,
+>;
 
 pub type TargetAParentchainBlockImporter = ParentchainBlockImporter<
 	ParentchainBlock,
@@ -232,12 +255,16 @@ pub type TargetAParentchainBlockImportDispatcher = BlockImportDispatcher<
 
 /// IndirectCalls executor instance of the Target B parentchain.
 ///
-/// **Note**: The filter here is purely used for demo purposes.
+/// **Note**: The filter here is purely used for demo purposes. The Encointer events of the
+/// Target B parentchain are forwarded to the personhood oracle, see
+/// [`EnclavePersonhoodOracleEventHandler`].
 ///
 /// Also note that the extrinsic parser must be changed if the signed extra contains the
 /// `AssetTxPayment`.
-pub type TargetBParentchainIndirectExecutor =
-	EnclaveIndirectCallsExecutor<TransferToAliceShieldsFundsFilter<ParentchainExtrinsicParser>>;
+pub type TargetBParentchainIndirectExecutor = EnclaveIndirectCallsExecutor<
+	TransferToAliceShieldsFundsFilter<ParentchainExtrinsicParser>,
+	EnclavePersonhoodOracleEventHandler,
+>;
 
 pub type TargetBParentchainBlockImporter = ParentchainBlockImporter<
 	ParentchainBlock,
@@ -271,8 +298,21 @@ pub type EnclaveTopPoolAuthor = Author<
 	EnclaveShieldingKeyRepository,
 	EnclaveOCallApi,
 >;
-pub type EnclaveSidechainBlockComposer =
-	BlockComposer<ParentchainBlock, SignedSidechainBlock, Pair, EnclaveStateKeyRepository>;
+pub type EnclaveTopPoolPersistence = TopPoolPersistence<
+	EnclaveTopPool,
+	EnclaveTopPoolAuthor,
+	TopPoolSeal,
+	EnclaveStateObserver,
+	EnclaveStf,
+>;
+pub type EnclaveAuditorEncryption = ParentchainAuditorEncryption<EnclaveOCallApi>;
+pub type EnclaveSidechainBlockComposer = BlockComposer<
+	ParentchainBlock,
+	SignedSidechainBlock,
+	Pair,
+	EnclaveStateKeyRepository,
+	EnclaveAuditorEncryption,
+>;
 pub type EnclaveSidechainBlockImporter = SidechainBlockImporter<
 	Pair,
 	ParentchainBlock,
@@ -299,6 +339,8 @@ pub type EnclaveSidechainBlockSyncer = PeerBlockSync<
 	EnclaveOCallApi,
 	EnclaveBlockImportConfirmationHandler,
 >;
+pub type EnclaveSidechainFinalityTracker = SidechainFinalityTracker<EnclaveOCallApi>;
+pub type EnclaveSidechainBlockStates = SidechainBlockStates<StfState>;
 pub type EnclaveSidechainBlockImportQueueWorker = BlockImportQueueWorker<
 	ParentchainBlock,
 	SignedSidechainBlock,
@@ -373,6 +415,10 @@ pub static GLOBAL_STATE_OBSERVER_COMPONENT: ComponentContainer<EnclaveStateObser
 pub static GLOBAL_TOP_POOL_AUTHOR_COMPONENT: ComponentContainer<EnclaveTopPoolAuthor> =
 	ComponentContainer::new("top_pool_author");
 
+/// TOP pool persistence, seals and restores the pending operations.
+pub static GLOBAL_TOP_POOL_PERSISTENCE_COMPONENT: ComponentContainer<EnclaveTopPoolPersistence> =
+	ComponentContainer::new("top_pool_persistence");
+
 /// attestation handler
 pub static GLOBAL_ATTESTATION_HANDLER_COMPONENT: ComponentContainer<EnclaveAttestationHandler> =
 	ComponentContainer::new("Attestation handler");
@@ -442,3 +488,46 @@ pub static GLOBAL_SIDECHAIN_BLOCK_COMPOSER_COMPONENT: ComponentContainer<
 pub static GLOBAL_SIDECHAIN_BLOCK_SYNCER_COMPONENT: ComponentContainer<
 	EnclaveSidechainBlockSyncer,
 > = ComponentContainer::new("sidechain_block_syncer");
+
+/// Sidechain finality tracker.
+pub static GLOBAL_SIDECHAIN_FINALITY_TRACKER_COMPONENT: ComponentContainer<
+	EnclaveSidechainFinalityTracker,
+> = ComponentContainer::new("sidechain_finality_tracker");
+
+/// Sidechain blocks applied to the state since the finalized block, shared by block import and
+/// block production.
+pub static GLOBAL_APPLIED_SIDECHAIN_BLOCKS_COMPONENT: ComponentContainer<AppliedSidechainBlocks> =
+	ComponentContainer::new("applied_sidechain_blocks");
+
+/// States after recent sidechain blocks, shared by block import and block production.
+pub static GLOBAL_SIDECHAIN_BLOCK_STATES_COMPONENT: ComponentContainer<
+	EnclaveSidechainBlockStates,
+> = ComponentContainer::new("sidechain_block_states");
+
+// Teeracle component instances
+//-------------------------------------------------------------------------------------------------
+
+/// Keep-alive connections of the Teeracle's oracles, reused across updates.
+#[cfg(feature = "teeracle")]
+pub static GLOBAL_ORACLE_CONNECTION_POOL_COMPONENT: ComponentContainer<ConnectionPool> =
+	ComponentContainer::new("oracle_connection_pool");
+
+// Personhood oracle component instances
+//-------------------------------------------------------------------------------------------------
+
+/// Watcher of the Encointer pallets on the Target A parentchain.
+pub static GLOBAL_TARGET_A_ENCOINTER_WATCHER_COMPONENT: ComponentContainer<EncointerWatcher> =
+	ComponentContainer::new("target_a_encointer_watcher");
+
+/// Watcher of the Encointer pallets on the Target B parentchain.
+pub static GLOBAL_TARGET_B_ENCOINTER_WATCHER_COMPONENT: ComponentContainer<EncointerWatcher> =
+	ComponentContainer::new("target_b_encointer_watcher");
+
+/// Parentchains the personhood oracle reads verified personhood data from, in the order they
+/// are consulted.
+pub static GLOBAL_PERSONHOOD_SOURCES_COMPONENT: ComponentContainer<Vec<ReputationSource>> =
+	ComponentContainer::new("personhood_sources");
+
+/// Nostr badges issued by the personhood oracle since the enclave has been started.
+pub static GLOBAL_ISSUED_BADGES_COMPONENT: ComponentContainer<IssuedBadges> =
+	ComponentContainer::new("issued_badges");
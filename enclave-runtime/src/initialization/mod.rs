@@ -18,28 +18,41 @@
 pub mod global_components;
 pub mod parentchain;
 
+#[cfg(feature = "teeracle")]
+use crate::initialization::global_components::GLOBAL_ORACLE_CONNECTION_POOL_COMPONENT;
 use crate::{
 	error::{Error, Result as EnclaveResult},
 	initialization::global_components::{
-		EnclaveBlockImportConfirmationHandler, EnclaveGetterExecutor, EnclaveLightClientSeal,
-		EnclaveOCallApi, EnclaveRpcConnectionRegistry, EnclaveRpcResponder,
+		EnclaveAuditorEncryption, EnclaveBlockImportConfirmationHandler, EnclaveGetterExecutor,
+		EnclaveLightClientSeal, EnclaveOCallApi, EnclaveRpcResponder,
 		EnclaveShieldingKeyRepository, EnclaveSidechainApi, EnclaveSidechainBlockImportQueue,
 		EnclaveSidechainBlockImportQueueWorker, EnclaveSidechainBlockImporter,
-		EnclaveSidechainBlockSyncer, EnclaveStateFileIo, EnclaveStateHandler,
-		EnclaveStateInitializer, EnclaveStateObserver, EnclaveStateSnapshotRepository,
-		EnclaveStfEnclaveSigner, EnclaveTopPool, EnclaveTopPoolAuthor,
+		EnclaveSidechainBlockStates, EnclaveSidechainBlockSyncer, EnclaveSidechainFinalityTracker,
+		EnclaveStateFileIo, EnclaveStateHandler, EnclaveStateInitializer, EnclaveStateObserver,
+		EnclaveStateSnapshotRepository, EnclaveStfEnclaveSigner, EnclaveTopPool,
+		EnclaveTopPoolAuthor, EnclaveTopPoolPersistence, GLOBAL_APPLIED_SIDECHAIN_BLOCKS_COMPONENT,
 		GLOBAL_ATTESTATION_HANDLER_COMPONENT, GLOBAL_INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_SEAL,
-		GLOBAL_OCALL_API_COMPONENT, GLOBAL_RPC_WS_HANDLER_COMPONENT,
+		GLOBAL_ISSUED_BADGES_COMPONENT, GLOBAL_OCALL_API_COMPONENT,
+		GLOBAL_PERSONHOOD_SOURCES_COMPONENT, GLOBAL_RPC_WS_HANDLER_COMPONENT,
 		GLOBAL_SHIELDING_KEY_REPOSITORY_COMPONENT, GLOBAL_SIDECHAIN_BLOCK_COMPOSER_COMPONENT,
-		GLOBAL_SIDECHAIN_BLOCK_SYNCER_COMPONENT, GLOBAL_SIDECHAIN_IMPORT_QUEUE_COMPONENT,
+		GLOBAL_SIDECHAIN_BLOCK_STATES_COMPONENT, GLOBAL_SIDECHAIN_BLOCK_SYNCER_COMPONENT,
+		GLOBAL_SIDECHAIN_FINALITY_TRACKER_COMPONENT, GLOBAL_SIDECHAIN_IMPORT_QUEUE_COMPONENT,
 		GLOBAL_SIDECHAIN_IMPORT_QUEUE_WORKER_COMPONENT, GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT,
 		GLOBAL_STATE_HANDLER_COMPONENT, GLOBAL_STATE_KEY_REPOSITORY_COMPONENT,
-		GLOBAL_STATE_OBSERVER_COMPONENT, GLOBAL_TARGET_A_PARENTCHAIN_LIGHT_CLIENT_SEAL,
+		GLOBAL_STATE_OBSERVER_COMPONENT, GLOBAL_TARGET_A_ENCOINTER_WATCHER_COMPONENT,
+		GLOBAL_TARGET_A_PARENTCHAIN_LIGHT_CLIENT_SEAL, GLOBAL_TARGET_B_ENCOINTER_WATCHER_COMPONENT,
 		GLOBAL_TARGET_B_PARENTCHAIN_LIGHT_CLIENT_SEAL, GLOBAL_TOP_POOL_AUTHOR_COMPONENT,
-		GLOBAL_WEB_SOCKET_SERVER_COMPONENT,
+		GLOBAL_TOP_POOL_PERSISTENCE_COMPONENT, GLOBAL_WEB_SOCKET_SERVER_COMPONENT,
 	},
 	ocall::OcallApi,
-	rpc::{rpc_response_channel::RpcResponseChannel, worker_api_direct::public_api_rpc_handler},
+	rpc::{
+		encointer_watcher::EncointerWatcher,
+		issued_badges::IssuedBadges,
+		nostr_utils::nostr_issuer_keys,
+		personhood_sources::ReputationSource,
+		rpc_response_channel::RpcResponseChannel,
+		worker_api_direct::{prove_read_inner, public_api_rpc_handler, StateTrieCache},
+	},
 	utils::{
 		get_extrinsic_factory_from_solo_or_parachain,
 		get_node_metadata_repository_from_integritee_solo_or_parachain,
@@ -50,6 +63,8 @@ use crate::{
 };
 use base58::ToBase58;
 use codec::Encode;
+#[cfg(feature = "teeracle")]
+use ita_oracle::ConnectionPool;
 use itc_direct_rpc_server::{
 	create_determine_watch, rpc_connection_registry::ConnectionRegistry,
 	rpc_ws_handler::RpcWsHandler,
@@ -61,12 +76,18 @@ use itc_tls_websocket_server::{
 use itp_attestation_handler::IntelAttestationHandler;
 use itp_component_container::{ComponentGetter, ComponentInitializer};
 use itp_primitives_cache::GLOBAL_PRIMITIVES_CACHE;
-use itp_settings::files::{
-	INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_DB_PATH, STATE_SNAPSHOTS_CACHE_SIZE,
-	TARGET_A_PARENTCHAIN_LIGHT_CLIENT_DB_PATH, TARGET_B_PARENTCHAIN_LIGHT_CLIENT_DB_PATH,
+use itp_settings::{
+	files::{
+		INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_DB_PATH, STATE_SNAPSHOTS_CACHE_SIZE,
+		TARGET_A_PARENTCHAIN_LIGHT_CLIENT_DB_PATH, TARGET_B_PARENTCHAIN_LIGHT_CLIENT_DB_PATH,
+		TOP_POOL_SEALED_FILE,
+	},
+	sidechain::MAX_FORK_DEPTH,
+	worker::LIGHT_CLIENT_BACKUP_INTERVAL,
 };
 use itp_sgx_crypto::{
-	get_aes_repository, get_ed25519_repository, get_rsa3072_repository, key_repository::AccessKey,
+	get_aes_repository, get_ed25519_repository, get_rsa3072_repository,
+	key_repository::{AccessKey, AccessPubkey},
 };
 use itp_stf_state_handler::{
 	file_io::StateDir, handle_state::HandleState, query_shard_state::QueryShardState,
@@ -74,12 +95,15 @@ use itp_stf_state_handler::{
 	state_snapshot_repository_loader::StateSnapshotRepositoryLoader, StateHandler,
 };
 use itp_top_pool::pool::Options as PoolOptions;
-use itp_top_pool_author::author::AuthorTopFilter;
-use itp_types::{parentchain::ParentchainId, ShardIdentifier};
-use its_sidechain::block_composer::BlockComposer;
+use itp_top_pool_author::{
+	author::AuthorTopFilter,
+	persistence::{PersistTopPool, TopPoolSeal},
+};
+use itp_types::{parentchain::ParentchainId, personhood::PersonhoodSourceConfig, ShardIdentifier};
+use its_sidechain::{block_composer::BlockComposer, consensus_common::AppliedSidechainBlocks};
 use log::*;
 use sp_core::crypto::Pair;
-use std::{collections::HashMap, path::PathBuf, string::String, sync::Arc};
+use std::{collections::HashMap, path::PathBuf, string::String, sync::Arc, vec::Vec};
 
 pub(crate) fn init_enclave(
 	mu_ra_url: String,
@@ -102,23 +126,26 @@ pub(crate) fn init_enclave(
 	let integritee_light_client_seal = Arc::new(EnclaveLightClientSeal::new(
 		base_dir.join(INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_DB_PATH),
 		ParentchainId::Integritee,
+		LIGHT_CLIENT_BACKUP_INTERVAL,
 	)?);
 	GLOBAL_INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_SEAL.initialize(integritee_light_client_seal);
 
 	let target_a_light_client_seal = Arc::new(EnclaveLightClientSeal::new(
 		base_dir.join(TARGET_A_PARENTCHAIN_LIGHT_CLIENT_DB_PATH),
 		ParentchainId::TargetA,
+		LIGHT_CLIENT_BACKUP_INTERVAL,
 	)?);
 	GLOBAL_TARGET_A_PARENTCHAIN_LIGHT_CLIENT_SEAL.initialize(target_a_light_client_seal);
 
 	let target_b_light_client_seal = Arc::new(EnclaveLightClientSeal::new(
 		base_dir.join(TARGET_B_PARENTCHAIN_LIGHT_CLIENT_DB_PATH),
 		ParentchainId::TargetB,
+		LIGHT_CLIENT_BACKUP_INTERVAL,
 	)?);
 	GLOBAL_TARGET_B_PARENTCHAIN_LIGHT_CLIENT_SEAL.initialize(target_b_light_client_seal);
 
 	let state_file_io =
-		Arc::new(EnclaveStateFileIo::new(state_key_repository, StateDir::new(base_dir)));
+		Arc::new(EnclaveStateFileIo::new(state_key_repository, StateDir::new(base_dir.clone())));
 	let state_initializer =
 		Arc::new(EnclaveStateInitializer::new(shielding_key_repository.clone()));
 	let state_snapshot_repository_loader = StateSnapshotRepositoryLoader::<
@@ -142,6 +169,17 @@ pub(crate) fn init_enclave(
 	let ocall_api = Arc::new(OcallApi);
 	GLOBAL_OCALL_API_COMPONENT.initialize(ocall_api.clone());
 
+	#[cfg(feature = "teeracle")]
+	GLOBAL_ORACLE_CONNECTION_POOL_COMPONENT.initialize(Arc::new(ConnectionPool::default()));
+
+	GLOBAL_TARGET_A_ENCOINTER_WATCHER_COMPONENT.initialize(Arc::new(EncointerWatcher::default()));
+	GLOBAL_TARGET_B_ENCOINTER_WATCHER_COMPONENT.initialize(Arc::new(EncointerWatcher::default()));
+	GLOBAL_ISSUED_BADGES_COMPONENT.initialize(Arc::new(IssuedBadges::default()));
+	// Until the untrusted worker configures the personhood sources, TargetA is the only one.
+	let default_personhood_source =
+		ReputationSource::new(PersonhoodSourceConfig::encointer(ParentchainId::TargetA))?;
+	GLOBAL_PERSONHOOD_SOURCES_COMPONENT.initialize(Arc::new(vec![default_personhood_source]));
+
 	// For debug purposes, list shards. no problem to panic if fails.
 	let shards = state_handler.list_shards().unwrap();
 	debug!("found the following {} shards on disk:", shards.len());
@@ -167,25 +205,61 @@ pub(crate) fn init_enclave(
 	// validateer completely breaking (IO PipeError).
 	// Corresponding GH issues are #545 and #600.
 
+	let response_channel = Arc::new(RpcResponseChannel::default());
+	let rpc_responder =
+		Arc::new(EnclaveRpcResponder::new(connection_registry.clone(), response_channel));
+	let top_pool = create_top_pool(rpc_responder);
+
 	let top_pool_author = create_top_pool_author(
-		connection_registry.clone(),
+		top_pool.clone(),
 		state_handler,
 		ocall_api.clone(),
 		shielding_key_repository.clone(),
 	);
 	GLOBAL_TOP_POOL_AUTHOR_COMPONENT.initialize(top_pool_author.clone());
 
+	// Restore the operations that were pending when the enclave was shut down. They are
+	// submitted through the author, so they pass the same checks as newly submitted operations.
+	let top_pool_persistence = Arc::new(EnclaveTopPoolPersistence::new(
+		top_pool,
+		top_pool_author.clone(),
+		TopPoolSeal::new(base_dir.join(TOP_POOL_SEALED_FILE)),
+		state_observer.clone(),
+	));
+	if let Err(e) = top_pool_persistence.restore_pending_operations() {
+		error!("Failed to restore sealed trusted operations: {:?}", e);
+	}
+	GLOBAL_TOP_POOL_PERSISTENCE_COMPONENT.initialize(top_pool_persistence);
+
+	let read_proof_state_observer = state_observer.clone();
+	let state_trie_cache = StateTrieCache::default();
+	let prove_read = move |shard: &ShardIdentifier, keys: &[Vec<u8>]| {
+		prove_read_inner(read_proof_state_observer.as_ref(), &state_trie_cache, shard, keys)
+	};
 	let getter_executor = Arc::new(EnclaveGetterExecutor::new(state_observer));
-	let io_handler =
-		public_api_rpc_handler(top_pool_author, getter_executor, shielding_key_repository);
+	let shielding_pubkey_json = serde_json::to_vec(&shielding_key_repository.retrieve_pubkey()?)
+		.map_err(|e| Error::Other(e.into()))?;
+	let io_handler = public_api_rpc_handler(
+		top_pool_author,
+		getter_executor,
+		shielding_key_repository,
+		prove_read,
+	);
 	let rpc_handler = Arc::new(RpcWsHandler::new(io_handler, watch_extractor, connection_registry));
 	GLOBAL_RPC_WS_HANDLER_COMPONENT.initialize(rpc_handler);
 
 	let sidechain_block_import_queue = Arc::new(EnclaveSidechainBlockImportQueue::default());
 	GLOBAL_SIDECHAIN_IMPORT_QUEUE_COMPONENT.initialize(sidechain_block_import_queue);
 
-	let attestation_handler =
-		Arc::new(IntelAttestationHandler::new(ocall_api, signing_key_repository));
+	let nostr_issuer_key = nostr_issuer_keys(&signing_key_repository.retrieve_key()?)
+		.map_err(|e| Error::Other(e.into()))?
+		.public_key();
+	let attestation_handler = Arc::new(IntelAttestationHandler::new(
+		ocall_api,
+		signing_key_repository,
+		shielding_pubkey_json,
+		nostr_issuer_key.serialize(),
+	));
 	GLOBAL_ATTESTATION_HANDLER_COMPONENT.initialize(attestation_handler);
 
 	Ok(())
@@ -216,12 +290,24 @@ pub(crate) fn init_enclave_sidechain_components() -> EnclaveResult<()> {
 
 	let signer = GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT.get()?.retrieve_key()?;
 
+	let applied_sidechain_blocks = Arc::new(AppliedSidechainBlocks::new());
+	GLOBAL_APPLIED_SIDECHAIN_BLOCKS_COMPONENT.initialize(applied_sidechain_blocks.clone());
+
+	let sidechain_finality_tracker =
+		Arc::new(EnclaveSidechainFinalityTracker::new(ocall_api.clone(), applied_sidechain_blocks));
+	GLOBAL_SIDECHAIN_FINALITY_TRACKER_COMPONENT.initialize(sidechain_finality_tracker.clone());
+
+	let sidechain_block_states = Arc::new(EnclaveSidechainBlockStates::new(MAX_FORK_DEPTH));
+	GLOBAL_SIDECHAIN_BLOCK_STATES_COMPONENT.initialize(sidechain_block_states.clone());
+
 	let sidechain_block_importer = Arc::new(EnclaveSidechainBlockImporter::new(
 		state_handler,
 		state_key_repository.clone(),
 		top_pool_author,
 		parentchain_block_import_dispatcher,
 		ocall_api.clone(),
+		sidechain_block_states,
+		sidechain_finality_tracker,
 	));
 
 	let sidechain_block_import_queue = GLOBAL_SIDECHAIN_IMPORT_QUEUE_COMPONENT.get()?;
@@ -238,7 +324,7 @@ pub(crate) fn init_enclave_sidechain_components() -> EnclaveResult<()> {
 
 	let sidechain_block_syncer = Arc::new(EnclaveSidechainBlockSyncer::new(
 		sidechain_block_importer,
-		ocall_api,
+		ocall_api.clone(),
 		sidechain_block_import_confirmation_handler,
 	));
 	GLOBAL_SIDECHAIN_BLOCK_SYNCER_COMPONENT.initialize(sidechain_block_syncer.clone());
@@ -250,7 +336,11 @@ pub(crate) fn init_enclave_sidechain_components() -> EnclaveResult<()> {
 		));
 	GLOBAL_SIDECHAIN_IMPORT_QUEUE_WORKER_COMPONENT.initialize(sidechain_block_import_queue_worker);
 
-	let block_composer = Arc::new(BlockComposer::new(signer, state_key_repository));
+	let block_composer = Arc::new(BlockComposer::with_auditor_encryption(
+		signer,
+		state_key_repository,
+		EnclaveAuditorEncryption::new(ocall_api),
+	));
 	GLOBAL_SIDECHAIN_BLOCK_COMPOSER_COMPONENT.initialize(block_composer);
 
 	Ok(())
@@ -289,20 +379,19 @@ pub(crate) fn init_shard(shard: ShardIdentifier) -> EnclaveResult<()> {
 	Ok(())
 }
 
+/// Initialize the TOP pool.
+pub fn create_top_pool(rpc_responder: Arc<EnclaveRpcResponder>) -> Arc<EnclaveTopPool> {
+	let side_chain_api = Arc::new(EnclaveSidechainApi::new());
+	Arc::new(EnclaveTopPool::create(PoolOptions::default(), side_chain_api, rpc_responder))
+}
+
 /// Initialize the TOP pool author component.
 pub fn create_top_pool_author(
-	connection_registry: Arc<EnclaveRpcConnectionRegistry>,
+	top_pool: Arc<EnclaveTopPool>,
 	state_handler: Arc<EnclaveStateHandler>,
 	ocall_api: Arc<EnclaveOCallApi>,
 	shielding_key_repository: Arc<EnclaveShieldingKeyRepository>,
 ) -> Arc<EnclaveTopPoolAuthor> {
-	let response_channel = Arc::new(RpcResponseChannel::default());
-	let rpc_responder = Arc::new(EnclaveRpcResponder::new(connection_registry, response_channel));
-
-	let side_chain_api = Arc::new(EnclaveSidechainApi::new());
-	let top_pool =
-		Arc::new(EnclaveTopPool::create(PoolOptions::default(), side_chain_api, rpc_responder));
-
 	Arc::new(EnclaveTopPoolAuthor::new(
 		top_pool,
 		AuthorTopFilter {},
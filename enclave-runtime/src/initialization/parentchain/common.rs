@@ -30,16 +30,20 @@ use crate::{
 			TargetAParentchainImmediateBlockImportDispatcher, TargetAParentchainIndirectExecutor,
 			TargetBParentchainBlockImportDispatcher, TargetBParentchainBlockImporter,
 			TargetBParentchainImmediateBlockImportDispatcher, TargetBParentchainIndirectExecutor,
-			GLOBAL_OCALL_API_COMPONENT, GLOBAL_SHIELDING_KEY_REPOSITORY_COMPONENT,
-			GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT, GLOBAL_STATE_HANDLER_COMPONENT,
-			GLOBAL_STATE_OBSERVER_COMPONENT, GLOBAL_TOP_POOL_AUTHOR_COMPONENT,
+			GLOBAL_ISSUED_BADGES_COMPONENT, GLOBAL_OCALL_API_COMPONENT,
+			GLOBAL_SHIELDING_KEY_REPOSITORY_COMPONENT, GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT,
+			GLOBAL_STATE_HANDLER_COMPONENT, GLOBAL_STATE_OBSERVER_COMPONENT,
+			GLOBAL_TARGET_A_ENCOINTER_WATCHER_COMPONENT,
+			GLOBAL_TARGET_B_ENCOINTER_WATCHER_COMPONENT, GLOBAL_TOP_POOL_AUTHOR_COMPONENT,
 		},
 		EnclaveStfEnclaveSigner,
 	},
+	rpc::{issued_badges::PersonhoodOracleEventHandler, worker_api_direct::NostrBadgePublisher},
 };
 use itp_component_container::ComponentGetter;
 use itp_nonce_cache::NonceCache;
 use itp_sgx_crypto::key_repository::AccessKey;
+use itp_types::parentchain::ParentchainId;
 use log::*;
 use sp_core::H256;
 use std::sync::Arc;
@@ -66,6 +70,7 @@ pub(crate) fn create_integritee_parentchain_block_importer(
 		stf_enclave_signer,
 		top_pool_author,
 		node_metadata_repository,
+		Arc::new(()),
 	));
 	Ok(IntegriteeParentchainBlockImporter::new(
 		validator_access,
@@ -85,6 +90,12 @@ pub(crate) fn create_target_a_parentchain_block_importer(
 	let top_pool_author = GLOBAL_TOP_POOL_AUTHOR_COMPONENT.get()?;
 	let shielding_key_repository = GLOBAL_SHIELDING_KEY_REPOSITORY_COMPONENT.get()?;
 	let ocall_api = GLOBAL_OCALL_API_COMPONENT.get()?;
+	let personhood_oracle_event_handler = Arc::new(PersonhoodOracleEventHandler::new(
+		ParentchainId::TargetA,
+		GLOBAL_TARGET_A_ENCOINTER_WATCHER_COMPONENT.get()?,
+		GLOBAL_ISSUED_BADGES_COMPONENT.get()?,
+		NostrBadgePublisher,
+	));
 
 	let stf_enclave_signer = Arc::new(EnclaveStfEnclaveSigner::new(
 		state_observer,
@@ -92,11 +103,13 @@ pub(crate) fn create_target_a_parentchain_block_importer(
 		shielding_key_repository.clone(),
 		top_pool_author.clone(),
 	));
+
 	let indirect_calls_executor = Arc::new(TargetAParentchainIndirectExecutor::new(
 		shielding_key_repository,
 		stf_enclave_signer,
 		top_pool_author,
 		node_metadata_repository,
+		personhood_oracle_event_handler,
 	));
 	Ok(TargetAParentchainBlockImporter::new(
 		validator_access,
@@ -116,6 +129,12 @@ pub(crate) fn create_target_b_parentchain_block_importer(
 	let top_pool_author = GLOBAL_TOP_POOL_AUTHOR_COMPONENT.get()?;
 	let shielding_key_repository = GLOBAL_SHIELDING_KEY_REPOSITORY_COMPONENT.get()?;
 	let ocall_api = GLOBAL_OCALL_API_COMPONENT.get()?;
+	let personhood_oracle_event_handler = Arc::new(PersonhoodOracleEventHandler::new(
+		ParentchainId::TargetB,
+		GLOBAL_TARGET_B_ENCOINTER_WATCHER_COMPONENT.get()?,
+		GLOBAL_ISSUED_BADGES_COMPONENT.get()?,
+		NostrBadgePublisher,
+	));
 
 	let stf_enclave_signer = Arc::new(EnclaveStfEnclaveSigner::new(
 		state_observer,
@@ -128,6 +147,7 @@ pub(crate) fn create_target_b_parentchain_block_importer(
 		stf_enclave_signer,
 		top_pool_author,
 		node_metadata_repository,
+		personhood_oracle_event_handler,
 	));
 	Ok(TargetBParentchainBlockImporter::new(
 		validator_access,
@@ -35,7 +35,10 @@ use crate::{
 };
 use itc_parentchain::light_client::{concurrent_access::ValidatorAccess, LightClientState};
 use itp_component_container::ComponentGetter;
-use itp_settings::worker_mode::{ProvideWorkerMode, WorkerMode};
+use itp_settings::{
+	sidechain::MAX_PARALLEL_TRUSTED_CALLS,
+	worker_mode::{ProvideWorkerMode, WorkerMode},
+};
 use itp_types::parentchain::ParentchainId;
 use std::{path::PathBuf, sync::Arc};
 
@@ -79,11 +82,10 @@ impl IntegriteeSolochainHandler {
 			node_metadata_repository.clone(),
 		)?;
 
-		let stf_executor = Arc::new(EnclaveStfExecutor::new(
-			ocall_api,
-			state_handler,
-			node_metadata_repository.clone(),
-		));
+		let stf_executor = Arc::new(
+			EnclaveStfExecutor::new(ocall_api, state_handler, node_metadata_repository.clone())
+				.with_max_parallel_calls(MAX_PARALLEL_TRUSTED_CALLS),
+		);
 
 		let block_importer = create_integritee_parentchain_block_importer(
 			validator_accessor.clone(),
@@ -16,7 +16,7 @@
 */
 
 use crate::{
-	error::Result,
+	error::{Error, Result},
 	initialization::{
 		global_components::{
 			GLOBAL_INTEGRITEE_PARACHAIN_HANDLER_COMPONENT,
@@ -87,6 +87,21 @@ pub(crate) fn init_parentchain_components<WorkerModeProvider: ProvideWorkerMode>
 				Ok(header.encode())
 			},
 		},
+		ParentchainInitParams::RelayChainBackedParachain { id, params } => match id {
+			ParentchainId::TargetA => {
+				let handler = TargetAParachainHandler::init_relay_chain_backed::<WorkerModeProvider>(
+					base_path, params,
+				)?;
+				let header = handler
+					.validator_accessor
+					.execute_on_validator(|v| v.latest_finalized_header())?;
+				GLOBAL_TARGET_A_PARACHAIN_HANDLER_COMPONENT.initialize(handler.into());
+				Ok(header.encode())
+			},
+			_ => Err(Error::Other(
+				format!("Relay chain backed light client is not supported for {:?}", id).into(),
+			)),
+		},
 		ParentchainInitParams::Solochain { id, params } => match id {
 			ParentchainId::Integritee => {
 				let handler =
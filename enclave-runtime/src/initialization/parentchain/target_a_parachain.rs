@@ -42,7 +42,9 @@ use itp_settings::worker_mode::{ProvideWorkerMode, WorkerMode};
 use itp_types::parentchain::ParentchainId;
 use std::{path::PathBuf, sync::Arc};
 
-pub use itc_parentchain::primitives::{ParachainBlock, ParachainHeader, ParachainParams};
+pub use itc_parentchain::primitives::{
+	ParachainBlock, ParachainHeader, ParachainParams, RelayChainBackedParachainParams,
+};
 
 #[derive(Clone)]
 pub struct TargetAParachainHandler {
@@ -60,9 +62,6 @@ impl TargetAParachainHandler {
 		params: ParachainParams,
 	) -> Result<Self> {
 		let ocall_api = GLOBAL_OCALL_API_COMPONENT.get()?;
-		let state_handler = GLOBAL_STATE_HANDLER_COMPONENT.get()?;
-		let node_metadata_repository = Arc::new(EnclaveNodeMetadataRepository::default());
-
 		let genesis_header = params.genesis_header.clone();
 
 		let light_client_seal = GLOBAL_TARGET_A_PARENTCHAIN_LIGHT_CLIENT_SEAL.get()?;
@@ -74,6 +73,39 @@ impl TargetAParachainHandler {
 		let validator_accessor =
 			Arc::new(EnclaveValidatorAccessor::new(validator, light_client_seal));
 
+		Self::new::<WorkerModeProvider>(genesis_header, validator_accessor)
+	}
+
+	/// Initializes a light client that only accepts parachain headers whose inclusion in a
+	/// finalized relay chain block has been proven.
+	pub fn init_relay_chain_backed<WorkerModeProvider: ProvideWorkerMode>(
+		_base_path: PathBuf,
+		params: RelayChainBackedParachainParams,
+	) -> Result<Self> {
+		let ocall_api = GLOBAL_OCALL_API_COMPONENT.get()?;
+		let genesis_header = params.genesis_header.clone();
+
+		let light_client_seal = GLOBAL_TARGET_A_PARENTCHAIN_LIGHT_CLIENT_SEAL.get()?;
+		let validator =
+			itc_parentchain::light_client::io::read_or_init_relay_chain_backed_validator::<
+				ParachainBlock,
+				EnclaveOCallApi,
+				_,
+			>(params, ocall_api, &*light_client_seal, ParentchainId::TargetA)?;
+		let validator_accessor =
+			Arc::new(EnclaveValidatorAccessor::new(validator, light_client_seal));
+
+		Self::new::<WorkerModeProvider>(genesis_header, validator_accessor)
+	}
+
+	fn new<WorkerModeProvider: ProvideWorkerMode>(
+		genesis_header: ParachainHeader,
+		validator_accessor: Arc<EnclaveValidatorAccessor>,
+	) -> Result<Self> {
+		let ocall_api = GLOBAL_OCALL_API_COMPONENT.get()?;
+		let state_handler = GLOBAL_STATE_HANDLER_COMPONENT.get()?;
+		let node_metadata_repository = Arc::new(EnclaveNodeMetadataRepository::default());
+
 		let genesis_hash = validator_accessor.execute_on_validator(|v| v.genesis_hash())?;
 
 		let extrinsics_factory = create_extrinsics_factory(
@@ -33,14 +33,14 @@ use crate::{
 	error::{Error, Result},
 	initialization::global_components::{
 		GLOBAL_INTEGRITEE_PARACHAIN_HANDLER_COMPONENT, GLOBAL_INTEGRITEE_PARENTCHAIN_NONCE_CACHE,
-		GLOBAL_INTEGRITEE_SOLOCHAIN_HANDLER_COMPONENT, GLOBAL_SHIELDING_KEY_REPOSITORY_COMPONENT,
-		GLOBAL_SIDECHAIN_IMPORT_QUEUE_COMPONENT, GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT,
-		GLOBAL_STATE_HANDLER_COMPONENT, GLOBAL_TARGET_A_PARACHAIN_HANDLER_COMPONENT,
-		GLOBAL_TARGET_A_PARENTCHAIN_NONCE_CACHE, GLOBAL_TARGET_A_SOLOCHAIN_HANDLER_COMPONENT,
-		GLOBAL_TARGET_B_PARACHAIN_HANDLER_COMPONENT, GLOBAL_TARGET_B_PARENTCHAIN_NONCE_CACHE,
-		GLOBAL_TARGET_B_SOLOCHAIN_HANDLER_COMPONENT,
+		GLOBAL_INTEGRITEE_SOLOCHAIN_HANDLER_COMPONENT, GLOBAL_PERSONHOOD_SOURCES_COMPONENT,
+		GLOBAL_SHIELDING_KEY_REPOSITORY_COMPONENT, GLOBAL_SIDECHAIN_IMPORT_QUEUE_COMPONENT,
+		GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT, GLOBAL_STATE_HANDLER_COMPONENT,
+		GLOBAL_TARGET_A_PARACHAIN_HANDLER_COMPONENT, GLOBAL_TARGET_A_PARENTCHAIN_NONCE_CACHE,
+		GLOBAL_TARGET_A_SOLOCHAIN_HANDLER_COMPONENT, GLOBAL_TARGET_B_PARACHAIN_HANDLER_COMPONENT,
+		GLOBAL_TARGET_B_PARENTCHAIN_NONCE_CACHE, GLOBAL_TARGET_B_SOLOCHAIN_HANDLER_COMPONENT,
 	},
-	rpc::worker_api_direct::sidechain_io_handler,
+	rpc::{personhood_sources::ReputationSource, worker_api_direct::sidechain_io_handler},
 	utils::{
 		get_node_metadata_repository_from_integritee_solo_or_parachain,
 		get_node_metadata_repository_from_target_a_solo_or_parachain,
@@ -54,14 +54,14 @@ use itc_parentchain::{
 	},
 	primitives::ParentchainId,
 };
-use itp_component_container::ComponentGetter;
+use itp_component_container::{ComponentGetter, ComponentInitializer};
 use itp_import_queue::PushToQueue;
 use itp_node_api::metadata::NodeMetadata;
 use itp_nonce_cache::{MutateNonce, Nonce};
 use itp_settings::worker_mode::{ProvideWorkerMode, WorkerMode, WorkerModeProvider};
 use itp_sgx_crypto::key_repository::AccessPubkey;
 use itp_storage::{StorageProof, StorageProofChecker};
-use itp_types::{ShardIdentifier, SignedBlock};
+use itp_types::{personhood::PersonhoodSourceConfig, ShardIdentifier, SignedBlock};
 use itp_utils::write_slice_and_whitespace_pad;
 use log::*;
 use once_cell::sync::OnceCell;
@@ -72,6 +72,10 @@ use std::{
 	path::PathBuf,
 	slice,
 	string::{String, ToString},
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
 	vec::Vec,
 };
 
@@ -79,14 +83,19 @@ mod attestation;
 mod empty_impls;
 mod initialization;
 mod ipfs;
+mod light_client_backup;
 mod ocall;
 mod utils;
 
 pub mod error;
 pub mod rpc;
+mod secret_migration;
+mod sidechain_auditor;
+mod state_snapshot;
 mod sync;
 mod tls_ra;
 pub mod top_pool_execution;
+mod top_pool_persistence;
 
 #[cfg(feature = "teeracle")]
 pub mod teeracle;
@@ -99,6 +108,9 @@ pub type AuthorityPair = sp_core::ed25519::Pair;
 
 static BASE_PATH: OnceCell<PathBuf> = OnceCell::new();
 
+/// Whether the personhood sources have been configured since the enclave started.
+static PERSONHOOD_SOURCES_CONFIGURED: AtomicBool = AtomicBool::new(false);
+
 fn get_base_path() -> Result<PathBuf> {
 	let base_path = BASE_PATH.get().ok_or_else(|| {
 		Error::Other("BASE_PATH not initialized. Broken enclave init flow!".to_string().into())
@@ -296,6 +308,51 @@ pub unsafe extern "C" fn set_node_metadata(
 	sgx_status_t::SGX_SUCCESS
 }
 
+/// Configures the target parentchains the personhood oracle reads from, replacing the default
+/// TargetA source. The sources can only be configured once per enclave start, and not be empty.
+#[no_mangle]
+pub unsafe extern "C" fn set_personhood_sources(
+	personhood_sources: *const u8,
+	personhood_sources_size: u32,
+) -> sgx_status_t {
+	let configs = match Vec::<PersonhoodSourceConfig>::decode_raw(
+		personhood_sources,
+		personhood_sources_size as usize,
+	) {
+		Err(e) => {
+			error!("Failed to decode personhood sources: {:?}", e);
+			return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+		},
+		Ok(configs) => configs,
+	};
+
+	if configs.is_empty() {
+		error!("At least one personhood source is required");
+		return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+	}
+
+	info!("Setting personhood sources: {:?}", configs);
+
+	let sources = match configs.into_iter().map(ReputationSource::new).collect::<Result<Vec<_>>>() {
+		Err(e) => {
+			error!("Invalid personhood source: {:?}", e);
+			return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+		},
+		Ok(sources) => sources,
+	};
+
+	if PERSONHOOD_SOURCES_CONFIGURED
+		.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+		.is_err()
+	{
+		error!("Personhood sources have already been set");
+		return sgx_status_t::SGX_ERROR_INVALID_STATE
+	}
+	GLOBAL_PERSONHOOD_SOURCES_COMPONENT.initialize(Arc::new(sources));
+
+	sgx_status_t::SGX_SUCCESS
+}
+
 /// This is reduced to the sidechain block import RPC interface (i.e. worker-worker communication).
 /// The entire rest of the RPC server is run inside the enclave and does not use this e-call function anymore.
 #[no_mangle]
@@ -0,0 +1,167 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! ECALLs to inspect the rotating backups of the sealed light client states and to roll
+//! back to one of them. Only to be used while the worker is not running.
+//!
+//! A backup is only restored if the shard states have not imported any parentchain block
+//! after the latest finalized block of the backup, as those blocks would be imported twice.
+
+use crate::{
+	error::{Error, Result},
+	initialization::global_components::{
+		EnclaveLightClientSeal, GLOBAL_INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_SEAL,
+		GLOBAL_STATE_HANDLER_COMPONENT, GLOBAL_TARGET_A_PARENTCHAIN_LIGHT_CLIENT_SEAL,
+		GLOBAL_TARGET_B_PARENTCHAIN_LIGHT_CLIENT_SEAL,
+	},
+	state_snapshot::parentchain_block_number,
+	utils::DecodeRaw,
+};
+use codec::Encode;
+use itc_parentchain::{
+	light_client::backup::{LightClientBackupInfo, LightClientBackupStatus},
+	primitives::ParentchainId,
+};
+use itp_component_container::ComponentGetter;
+use itp_stf_state_handler::{handle_state::HandleState, query_shard_state::QueryShardState};
+use itp_types::BlockNumber;
+use itp_utils::write_slice_and_whitespace_pad;
+use log::*;
+use sgx_types::sgx_status_t;
+use std::{format, slice, sync::Arc, vec::Vec};
+
+/// Writes the encoded `Vec<LightClientBackupInfo>` of the light client of the given
+/// parentchain to `backups`.
+#[no_mangle]
+pub unsafe extern "C" fn get_light_client_backups(
+	parentchain_id: *const u8,
+	parentchain_id_size: u32,
+	backups: *mut u8,
+	backups_size: u32,
+) -> sgx_status_t {
+	let parentchain_id =
+		match ParentchainId::decode_raw(parentchain_id, parentchain_id_size as usize) {
+			Ok(id) => id,
+			Err(e) => {
+				error!("Could not decode parentchain id: {:?}", e);
+				return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+			},
+		};
+
+	let backup_infos = match light_client_backups(parentchain_id) {
+		Ok(infos) => infos,
+		Err(e) => {
+			error!("[{:?}] Failed to read the light client backups: {:?}", parentchain_id, e);
+			return e.into()
+		},
+	};
+
+	let backups_slice = slice::from_raw_parts_mut(backups, backups_size as usize);
+	if let Err(e) = write_slice_and_whitespace_pad(backups_slice, backup_infos.encode()) {
+		error!("Failed to write the light client backups: {:?}", e);
+		return sgx_status_t::SGX_ERROR_UNEXPECTED
+	}
+
+	sgx_status_t::SGX_SUCCESS
+}
+
+/// Replaces the light client db of the given parentchain with the backup of `generation`.
+#[no_mangle]
+pub unsafe extern "C" fn restore_light_client_backup(
+	parentchain_id: *const u8,
+	parentchain_id_size: u32,
+	generation: u64,
+) -> sgx_status_t {
+	let parentchain_id =
+		match ParentchainId::decode_raw(parentchain_id, parentchain_id_size as usize) {
+			Ok(id) => id,
+			Err(e) => {
+				error!("Could not decode parentchain id: {:?}", e);
+				return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+			},
+		};
+
+	if let Err(e) = restore_backup(parentchain_id, generation) {
+		error!(
+			"[{:?}] Failed to restore light client backup generation {}: {:?}",
+			parentchain_id, generation, e
+		);
+		return e.into()
+	}
+
+	sgx_status_t::SGX_SUCCESS
+}
+
+fn light_client_backups(parentchain_id: ParentchainId) -> Result<Vec<LightClientBackupInfo>> {
+	let backups = light_client_seal(parentchain_id)?.backups()?;
+
+	Ok(backups
+		.into_iter()
+		.map(|(slot, backup)| {
+			let status = match backup {
+				Some(backup) => LightClientBackupStatus::Valid {
+					generation: backup.generation,
+					latest_finalized_block: backup.latest_finalized_block,
+				},
+				None => LightClientBackupStatus::Corrupted,
+			};
+			LightClientBackupInfo { slot, status }
+		})
+		.collect())
+}
+
+fn restore_backup(parentchain_id: ParentchainId, generation: u64) -> Result<()> {
+	let seal = light_client_seal(parentchain_id)?;
+	let backup = seal.backup_of_generation(generation)?;
+
+	if let Some(imported_block) = last_imported_parentchain_block(parentchain_id)? {
+		if backup.latest_finalized_block < imported_block {
+			return Err(Error::Other(
+				format!(
+					"Backup is at block {}, but the shards have already imported block {}",
+					backup.latest_finalized_block, imported_block
+				)
+				.into(),
+			))
+		}
+	}
+	Ok(seal.restore_backup(generation)?)
+}
+
+/// Highest parentchain block any shard state has imported. Only the blocks of the Integritee
+/// parentchain are recorded in the shard states.
+fn last_imported_parentchain_block(parentchain_id: ParentchainId) -> Result<Option<BlockNumber>> {
+	if parentchain_id != ParentchainId::Integritee {
+		return Ok(None)
+	}
+	let state_handler = GLOBAL_STATE_HANDLER_COMPONENT.get()?;
+	let mut last_imported_block = None;
+	for shard in state_handler.list_shards()? {
+		let (state, _) = state_handler.load_cloned(&shard)?;
+		last_imported_block = last_imported_block.max(parentchain_block_number(&state));
+	}
+	Ok(last_imported_block)
+}
+
+fn light_client_seal(parentchain_id: ParentchainId) -> Result<Arc<EnclaveLightClientSeal>> {
+	let seal = match parentchain_id {
+		ParentchainId::Integritee => GLOBAL_INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_SEAL.get()?,
+		ParentchainId::TargetA => GLOBAL_TARGET_A_PARENTCHAIN_LIGHT_CLIENT_SEAL.get()?,
+		ParentchainId::TargetB => GLOBAL_TARGET_B_PARENTCHAIN_LIGHT_CLIENT_SEAL.get()?,
+	};
+	Ok(seal)
+}
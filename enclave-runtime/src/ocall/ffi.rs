@@ -107,6 +107,14 @@ extern "C" {
 		sidechain_blocks_size: u32,
 	) -> sgx_status_t;
 
+	pub fn ocall_finalize_sidechain_block(
+		ret_val: *mut sgx_status_t,
+		shard_identifier: *const u8,
+		shard_identifier_size: u32,
+		finalized_block: *const u8,
+		finalized_block_size: u32,
+	) -> sgx_status_t;
+
 	pub fn ocall_send_to_parentchain(
 		ret_val: *mut sgx_status_t,
 		extrinsics: *const u8,
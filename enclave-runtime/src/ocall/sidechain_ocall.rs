@@ -20,7 +20,7 @@ use crate::ocall::{ffi, OcallApi};
 use codec::{Decode, Encode};
 use frame_support::ensure;
 use itp_ocall_api::EnclaveSidechainOCallApi;
-use itp_types::{BlockHash, ShardIdentifier};
+use itp_types::{BlockHash, ShardIdentifier, SidechainBlockConfirmation};
 use log::*;
 use sgx_types::{sgx_status_t, SgxResult};
 use std::vec::Vec;
@@ -109,4 +109,29 @@ impl EnclaveSidechainOCallApi for OcallApi {
 
 		Ok(decoded_signed_blocks)
 	}
+
+	fn finalize_sidechain_block(
+		&self,
+		shard_identifier: ShardIdentifier,
+		finalized_block: SidechainBlockConfirmation,
+	) -> SgxResult<()> {
+		let mut rt: sgx_status_t = sgx_status_t::SGX_ERROR_UNEXPECTED;
+		let shard_identifier_encoded = shard_identifier.encode();
+		let finalized_block_encoded = finalized_block.encode();
+
+		let res = unsafe {
+			ffi::ocall_finalize_sidechain_block(
+				&mut rt as *mut sgx_status_t,
+				shard_identifier_encoded.as_ptr(),
+				shard_identifier_encoded.len() as u32,
+				finalized_block_encoded.as_ptr(),
+				finalized_block_encoded.len() as u32,
+			)
+		};
+
+		ensure!(rt == sgx_status_t::SGX_SUCCESS, rt);
+		ensure!(res == sgx_status_t::SGX_SUCCESS, res);
+
+		Ok(())
+	}
 }
@@ -0,0 +1,372 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Enclave-side cache of verified Encointer state.
+//!
+//! Each personhood source has its own cache. Only values that have been read with a storage
+//! proof against a finalized header of the source's parentchain are cached. Reputations of
+//! past ceremonies hardly ever change, so a cached entry is kept until the parentchain's
+//! [`EncointerWatcher`] reports a change that could affect it. A new ceremony phase clears
+//! the whole cache.
+//!
+//! Both maps are bounded, the least recently used entry is evicted first. Lookups only take
+//! the read lock: they stamp the entry they hit atomically, and the recency order is brought
+//! up to date with these stamps when an entry has to be evicted.
+
+use crate::rpc::encointer_watcher::{EncointerWatcher, TargetBlockNumber};
+use encointer_primitives::{
+	ceremonies::Reputation,
+	communities::{CommunityIdentifier, CommunityMetadata},
+	scheduler::CeremonyIndexType,
+};
+use itp_settings::personhood_oracle::{COMMUNITY_CACHE_SIZE, REPUTATION_CACHE_SIZE};
+use itp_stf_primitives::types::AccountId;
+use log::*;
+use std::{
+	collections::BTreeMap,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc, SgxRwLock as RwLock,
+	},
+};
+
+type ReputationKey = (CommunityIdentifier, CeremonyIndexType, AccountId);
+
+struct CachedEntry<V> {
+	value: V,
+	verified_at: TargetBlockNumber,
+	/// Stamp of the last access, updated by lookups under the read lock.
+	last_access: AtomicU64,
+	/// Stamp under which the entry is listed in the recency order.
+	ordered_at: u64,
+}
+
+/// Map with a capacity, evicting the least recently used entry.
+struct LruMap<K, V> {
+	entries: BTreeMap<K, CachedEntry<V>>,
+	/// Keys in the order of their `ordered_at` stamps. An entry that has been looked up since
+	/// is listed under an older stamp than its `last_access`.
+	recency: BTreeMap<u64, K>,
+	capacity: usize,
+}
+
+impl<K: Ord + Clone, V: Clone> LruMap<K, V> {
+	fn new(capacity: usize) -> Self {
+		LruMap { entries: BTreeMap::new(), recency: BTreeMap::new(), capacity }
+	}
+
+	/// Returns the value unless it `is_outdated` for the block it has been verified at.
+	///
+	/// An outdated entry is left in place, it is replaced by the next insert or evicted.
+	fn get(
+		&self,
+		key: &K,
+		access: u64,
+		is_outdated: impl FnOnce(TargetBlockNumber) -> bool,
+	) -> Option<V> {
+		let entry = self.entries.get(key)?;
+		if is_outdated(entry.verified_at) {
+			return None
+		}
+		entry.last_access.fetch_max(access, Ordering::Relaxed);
+		Some(entry.value.clone())
+	}
+
+	fn insert(&mut self, key: K, value: V, verified_at: TargetBlockNumber, access: u64) {
+		if self.capacity == 0 {
+			return
+		}
+		match self.entries.get(&key) {
+			Some(entry) => {
+				self.recency.remove(&entry.ordered_at);
+			},
+			None =>
+				while self.entries.len() >= self.capacity && self.evict_least_recently_used() {},
+		}
+		let last_access = AtomicU64::new(access);
+		self.entries.insert(
+			key.clone(),
+			CachedEntry { value, verified_at, last_access, ordered_at: access },
+		);
+		self.recency.insert(access, key);
+	}
+
+	/// Evicts the first entry in the recency order that has not been looked up since it has
+	/// been ordered. Entries that have been looked up are moved to their actual place.
+	///
+	/// Returns false if there was nothing to evict.
+	fn evict_least_recently_used(&mut self) -> bool {
+		while let Some((ordered_at, key)) =
+			self.recency.iter().next().map(|(stamp, key)| (*stamp, key.clone()))
+		{
+			self.recency.remove(&ordered_at);
+			let entry = match self.entries.get_mut(&key) {
+				Some(entry) => entry,
+				None => continue,
+			};
+			let last_access = *entry.last_access.get_mut();
+			if last_access > ordered_at {
+				entry.ordered_at = last_access;
+				self.recency.insert(last_access, key);
+			} else {
+				self.entries.remove(&key);
+				return true
+			}
+		}
+		false
+	}
+
+	fn clear(&mut self) {
+		self.entries.clear();
+		self.recency.clear();
+	}
+
+	fn len(&self) -> usize {
+		self.entries.len()
+	}
+}
+
+struct CachedEncointerState {
+	/// Block of the last phase change the cache has been cleared for.
+	phase_changed_at: Option<TargetBlockNumber>,
+	reputations: LruMap<ReputationKey, Reputation>,
+	communities: LruMap<CommunityIdentifier, Option<CommunityMetadata>>,
+}
+
+impl CachedEncointerState {
+	fn is_current(&self, watcher: &EncointerWatcher) -> bool {
+		self.phase_changed_at == watcher.ceremony_phase().map(|(_, block_number)| block_number)
+	}
+
+	fn clear_on_phase_change(&mut self, watcher: &EncointerWatcher) {
+		let phase_changed_at = watcher.ceremony_phase().map(|(_, block_number)| block_number);
+		if phase_changed_at != self.phase_changed_at {
+			debug!(
+				"Encointer ceremony phase changed, clearing {} cached reputations",
+				self.reputations.len()
+			);
+			self.reputations.clear();
+			self.communities.clear();
+			self.phase_changed_at = phase_changed_at;
+		}
+	}
+}
+
+pub struct EncointerCache {
+	state: RwLock<CachedEncointerState>,
+	watcher: Arc<EncointerWatcher>,
+	/// Source of the access stamps, unique per lookup or insert.
+	access_counter: AtomicU64,
+}
+
+impl EncointerCache {
+	pub fn new(
+		watcher: Arc<EncointerWatcher>,
+		reputation_capacity: usize,
+		community_capacity: usize,
+	) -> Self {
+		EncointerCache {
+			state: RwLock::new(CachedEncointerState {
+				phase_changed_at: None,
+				reputations: LruMap::new(reputation_capacity),
+				communities: LruMap::new(community_capacity),
+			}),
+			watcher,
+			access_counter: AtomicU64::new(0),
+		}
+	}
+
+	/// Cache with the capacities of the personhood oracle settings.
+	pub fn with_default_capacity(watcher: Arc<EncointerWatcher>) -> Self {
+		Self::new(watcher, REPUTATION_CACHE_SIZE, COMMUNITY_CACHE_SIZE)
+	}
+
+	/// Cached reputation of `account` in the given ceremony, if it is still up to date.
+	pub fn reputation(
+		&self,
+		cid: CommunityIdentifier,
+		cindex: CeremonyIndexType,
+		account: &AccountId,
+	) -> Option<Reputation> {
+		let state = self.state.read().ok()?;
+		if !state.is_current(&self.watcher) {
+			// Cleared by the next insert.
+			return None
+		}
+		state
+			.reputations
+			.get(&(cid, cindex, account.clone()), self.next_access(), |verified_at| {
+				self.watcher.reputation_changed_since(&cid, account, verified_at)
+			})
+	}
+
+	/// Cache a reputation that has been verified against the block `verified_at`.
+	pub fn insert_reputation(
+		&self,
+		cid: CommunityIdentifier,
+		cindex: CeremonyIndexType,
+		account: AccountId,
+		reputation: Reputation,
+		verified_at: TargetBlockNumber,
+	) {
+		if let Ok(mut state) = self.state.write() {
+			state.clear_on_phase_change(&self.watcher);
+			let access = self.next_access();
+			state
+				.reputations
+				.insert((cid, cindex, account), reputation, verified_at, access);
+		}
+	}
+
+	/// Cached metadata of the community, if it is still up to date.
+	///
+	/// Returns `Some(None)` if the community has been verified to not exist.
+	pub fn community_metadata(
+		&self,
+		cid: CommunityIdentifier,
+	) -> Option<Option<CommunityMetadata>> {
+		let state = self.state.read().ok()?;
+		if !state.is_current(&self.watcher) {
+			return None
+		}
+		state.communities.get(&cid, self.next_access(), |verified_at| {
+			self.watcher.community_changed_since(&cid, verified_at)
+		})
+	}
+
+	/// Cache community metadata that has been verified against the block `verified_at`.
+	///
+	/// `None` records that the community does not exist.
+	pub fn insert_community_metadata(
+		&self,
+		cid: CommunityIdentifier,
+		metadata: Option<CommunityMetadata>,
+		verified_at: TargetBlockNumber,
+	) {
+		if let Ok(mut state) = self.state.write() {
+			state.clear_on_phase_change(&self.watcher);
+			let access = self.next_access();
+			state.communities.insert(cid, metadata, verified_at, access);
+		}
+	}
+
+	fn next_access(&self) -> u64 {
+		self.access_counter.fetch_add(1, Ordering::Relaxed) + 1
+	}
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+	use super::*;
+	use encointer_primitives::scheduler::CeremonyPhaseType;
+	use itc_parentchain::indirect_calls_executor::{EncointerEvent, HandleEncointerEvents};
+	use itp_types::H256;
+
+	pub fn encointer_cache_returns_reputation_until_it_changes() {
+		let watcher = Arc::new(EncointerWatcher::default());
+		let cache = EncointerCache::new(watcher.clone(), 10, 10);
+		let cid = CommunityIdentifier::default();
+		let account = AccountId::new([1u8; 32]);
+
+		assert_eq!(cache.reputation(cid, 3, &account), None);
+
+		cache.insert_reputation(cid, 3, account.clone(), Reputation::VerifiedUnlinked, 10);
+		assert_eq!(cache.reputation(cid, 3, &account), Some(Reputation::VerifiedUnlinked));
+		assert_eq!(cache.reputation(cid, 2, &account), None);
+
+		watcher.handle_encointer_events(
+			11,
+			H256::default(),
+			vec![EncointerEvent::ReputationChanged(cid, Some(account.clone()))],
+		);
+		assert_eq!(cache.reputation(cid, 3, &account), None);
+	}
+
+	pub fn encointer_cache_is_cleared_on_phase_change() {
+		let watcher = Arc::new(EncointerWatcher::default());
+		let cache = EncointerCache::new(watcher.clone(), 10, 10);
+		let cid = CommunityIdentifier::default();
+		let account = AccountId::new([1u8; 32]);
+
+		cache.insert_reputation(cid, 3, account.clone(), Reputation::VerifiedUnlinked, 10);
+		cache.insert_community_metadata(cid, Some(CommunityMetadata::default()), 10);
+
+		watcher.handle_encointer_events(
+			12,
+			H256::default(),
+			vec![EncointerEvent::PhaseChanged(CeremonyPhaseType::Assigning)],
+		);
+
+		assert_eq!(cache.reputation(cid, 3, &account), None);
+		assert_eq!(cache.community_metadata(cid), None);
+	}
+
+	pub fn encointer_cache_evicts_least_recently_used_reputation() {
+		let watcher = Arc::new(EncointerWatcher::default());
+		let cache = EncointerCache::new(watcher, 2, 2);
+		let cid = CommunityIdentifier::default();
+		let account = AccountId::new([1u8; 32]);
+
+		cache.insert_reputation(cid, 1, account.clone(), Reputation::VerifiedUnlinked, 10);
+		cache.insert_reputation(cid, 2, account.clone(), Reputation::UnverifiedReputable, 10);
+		assert!(cache.reputation(cid, 1, &account).is_some());
+
+		cache.insert_reputation(cid, 3, account.clone(), Reputation::Unverified, 10);
+
+		assert_eq!(cache.reputation(cid, 1, &account), Some(Reputation::VerifiedUnlinked));
+		assert_eq!(cache.reputation(cid, 2, &account), None);
+		assert_eq!(cache.reputation(cid, 3, &account), Some(Reputation::Unverified));
+	}
+
+	pub fn encointer_cache_keeps_recently_used_entries_over_several_evictions() {
+		let watcher = Arc::new(EncointerWatcher::default());
+		let cache = EncointerCache::new(watcher, 3, 3);
+		let cid = CommunityIdentifier::default();
+		let account = AccountId::new([1u8; 32]);
+
+		for cindex in 1..=3 {
+			cache.insert_reputation(cid, cindex, account.clone(), Reputation::Unverified, 10);
+		}
+		// Order of use: 2, 3, 1.
+		assert!(cache.reputation(cid, 3, &account).is_some());
+		assert!(cache.reputation(cid, 1, &account).is_some());
+
+		cache.insert_reputation(cid, 4, account.clone(), Reputation::Unverified, 10);
+		cache.insert_reputation(cid, 5, account.clone(), Reputation::Unverified, 10);
+
+		let cached: Vec<_> = (1..=5)
+			.filter(|cindex| cache.reputation(cid, *cindex, &account).is_some())
+			.collect();
+		assert_eq!(cached, vec![1, 4, 5]);
+	}
+
+	pub fn encointer_cache_remembers_unknown_communities() {
+		let watcher = Arc::new(EncointerWatcher::default());
+		let cache = EncointerCache::new(watcher.clone(), 10, 10);
+		let cid = CommunityIdentifier::default();
+
+		cache.insert_community_metadata(cid, None, 10);
+		assert_eq!(cache.community_metadata(cid), Some(None));
+
+		watcher.handle_encointer_events(
+			11,
+			H256::default(),
+			vec![EncointerEvent::CommunityChanged(cid)],
+		);
+		assert_eq!(cache.community_metadata(cid), None);
+	}
+}
@@ -14,96 +14,214 @@
 	limitations under the License.
 
 */
-use crate::{initialization::global_components::GLOBAL_OCALL_API_COMPONENT, Vec};
-use codec::Decode;
+use crate::{
+	error::Result,
+	initialization::global_components::GLOBAL_PERSONHOOD_SOURCES_COMPONENT,
+	rpc::{
+		personhood_metrics::update_personhood_metric,
+		personhood_sources::{select_source, ReputationSource},
+	},
+	Vec,
+};
 use encointer_primitives::{
-	ceremonies::Reputation, communities::CommunityIdentifier, scheduler::CeremonyIndexType,
+	ceremonies::Reputation,
+	communities::{CommunityIdentifier, CommunityMetadata},
+	scheduler::CeremonyIndexType,
 };
+use ita_personhood::{EncointerEvidence, EncointerSubject, PersonhoodSubject, VerifiedStorage};
 use itp_component_container::ComponentGetter;
-use itp_ocall_api::EnclaveOnChainOCallApi;
+use itp_enclave_metrics::{PersonhoodOracleMetric, ReputationLookupCounts, ReputationLookupResult};
 use itp_stf_primitives::types::AccountId;
-use itp_storage::{storage_double_map_key, StorageHasher};
-use itp_types::{WorkerRequest, WorkerResponse, parentchain::ParentchainId};
+use itp_types::H256;
 use log::*;
-use std::cmp::min;
 
 pub fn fetch_reputation(
 	cid: CommunityIdentifier,
 	cindex: CeremonyIndexType,
 	account: AccountId,
 ) -> Vec<Reputation> {
-	let number_of_reputations = min(5, cindex);
-	query_last_n_reputations(&account, cid, cindex, number_of_reputations)
+	let subject = EncointerSubject { cid, cindex, account };
+	let sources = match GLOBAL_PERSONHOOD_SOURCES_COMPONENT.get() {
+		Ok(sources) => sources,
+		Err(e) => {
+			error!("Personhood sources are not available: {:?}", e);
+			return report_reputations(&subject, None)
+		},
+	};
+
+	match select_source(sources.as_slice(), &PersonhoodSubject::Encointer(subject.clone())) {
+		Some(source) => fetch_evidence(source, &subject)
+			.reputations
+			.into_iter()
+			.map(|(_, reputation)| reputation)
+			.collect(),
+		// A community that does not exist has no reputations, no need to look them up.
+		None => {
+			debug!("community {} is not registered", subject.cid);
+			report_reputations(&subject, Some(Reputation::Unverified))
+		},
+	}
 }
 
-fn query_last_n_reputations(
-	prover: &AccountId,
-	cid: CommunityIdentifier,
-	current_cindex: CeremonyIndexType,
-	n: CeremonyIndexType,
+/// Evidence of the subject's personhood on the source. Reputations that could not be read are
+/// reported as unverified.
+///
+/// The lookups of the request are reported with a single metric update.
+pub fn fetch_evidence(source: &ReputationSource, subject: &EncointerSubject) -> EncointerEvidence {
+	let mut lookups = ReputationLookupCounts::default();
+	let evidence = read_evidence(source, subject, &mut lookups);
+	update_personhood_metric(PersonhoodOracleMetric::ReputationLookups(lookups));
+	evidence
+}
+
+/// Reports the same reputation for each of the subject's ceremonies, for a subject that has not
+/// been looked up on any source. `None` if the lookup failed.
+fn report_reputations(
+	subject: &EncointerSubject,
+	reputation: Option<Reputation>,
 ) -> Vec<Reputation> {
-	(1..=n)
-		.map(|i| get_reputation_ocall_api(prover, cid, current_cindex - i))
-		.collect()
+	let mut lookups = ReputationLookupCounts::default();
+	let reputations = subject
+		.ceremony_indexes()
+		.iter()
+		.map(|_| record_reputation_lookup(reputation, &mut lookups))
+		.collect();
+	update_personhood_metric(PersonhoodOracleMetric::ReputationLookups(lookups));
+	reputations
 }
 
-fn get_reputation_ocall_api(
-	prover: &AccountId,
-	cid: CommunityIdentifier,
-	cindex: CeremonyIndexType,
-) -> Reputation {
-	println!(
-		"requesting reputation for {:?}: cid is :{}, cindex is: {}",
-		prover,
-		cid,
-		cindex.clone()
-	);
-	let unverified_reputation = Reputation::Unverified;
-
-	let ocall_api = GLOBAL_OCALL_API_COMPONENT.get();
-	if let Err(e) = ocall_api {
-		error!("failed to get OCALL API, error: {:#?}", e);
-		return unverified_reputation
+fn read_evidence(
+	source: &ReputationSource,
+	subject: &EncointerSubject,
+	lookups: &mut ReputationLookupCounts,
+) -> EncointerEvidence {
+	let cindexes = subject.ceremony_indexes();
+
+	// A community that does not exist has no reputations, no need to look them up.
+	if let Ok(None) = fetch_community_metadata(source, subject.cid) {
+		debug!("community {} is not registered", subject.cid);
+		let reputations = cindexes.iter().map(|_| Some(Reputation::Unverified)).collect();
+		return to_evidence(None, false, &cindexes, reputations, lookups)
 	}
-	let ocall_api = ocall_api.expect("Failed to get OCALL API, but it should have succeded.");
-	let storage_hash = storage_double_map_key(
-		"EncointerCeremonies",
-		"ParticipantReputation",
-		&(cid, cindex),
-		&StorageHasher::Blake2_128Concat,
-		prover,
-		&StorageHasher::Blake2_128Concat,
-	);
-	trace!("storage_hash is : {}", hex::encode(storage_hash.clone()));
-
-	let requests = vec![WorkerRequest::ChainStorage(storage_hash, None)];
-	let mut resp: Vec<WorkerResponse<Vec<u8>>> = match ocall_api.worker_request(requests, &ParentchainId::TargetA) {
-		Ok(response) => response,
+
+	let genesis_hash = match source.genesis_hash() {
+		Ok(genesis_hash) => genesis_hash,
 		Err(e) => {
-			error!("Worker response decode failed. Error: {:?}", e);
-			return unverified_reputation
+			error!(
+				"Failed to read the genesis hash of the {:?} parentchain: {:?}",
+				source.parentchain_id(),
+				e
+			);
+			let reputations = cindexes.iter().map(|_| None).collect();
+			return to_evidence(None, true, &cindexes, reputations, lookups)
 		},
 	};
+	let reputations =
+		query_reputations(source, &subject.account, subject.cid, &cindexes, lookups);
+	to_evidence(Some(genesis_hash), true, &cindexes, reputations, lookups)
+}
 
-	let first = match resp.pop() {
-		None => {
-			error!("Worker should have responded, but it did not.");
-			return unverified_reputation
-		},
-		Some(response) => response,
-	};
-	trace!("Worker response: {:?}", first);
+fn to_evidence(
+	source: Option<H256>,
+	community_registered: bool,
+	cindexes: &[CeremonyIndexType],
+	reputations: Vec<Option<Reputation>>,
+	lookups: &mut ReputationLookupCounts,
+) -> EncointerEvidence {
+	EncointerEvidence {
+		source,
+		community_registered,
+		reputations: cindexes
+			.iter()
+			.copied()
+			.zip(reputations.into_iter().map(|r| record_reputation_lookup(r, lookups)))
+			.collect(),
+	}
+}
+
+/// Fetch the metadata of a community, `None` if the community is not registered on the source.
+pub fn fetch_community_metadata(
+	source: &ReputationSource,
+	cid: CommunityIdentifier,
+) -> Result<Option<CommunityMetadata>> {
+	let cache = source.cache();
+	if let Some(metadata) = cache.and_then(|c| c.community_metadata(cid)) {
+		return Ok(metadata)
+	}
+
+	let (metadata, verified_at) = source.ceremonies().read_community_metadata(source, &cid)?;
+
+	if let Some(cache) = cache {
+		cache.insert_community_metadata(cid, metadata.clone(), verified_at);
+	}
+	Ok(metadata)
+}
 
-	let (_key, value, _proof) = match first {
-		WorkerResponse::ChainStorage(storage_key, value, proof) => (storage_key, value, proof),
+fn record_reputation_lookup(
+	reputation: Option<Reputation>,
+	lookups: &mut ReputationLookupCounts,
+) -> Reputation {
+	let (reputation, lookup_result) = match reputation {
+		Some(reputation) if reputation.is_verified() =>
+			(reputation, ReputationLookupResult::Verified),
+		Some(reputation) => (reputation, ReputationLookupResult::Unverified),
+		None => (Reputation::Unverified, ReputationLookupResult::Failed),
 	};
+	lookups.record_lookup(lookup_result);
+	reputation
+}
 
-	match value {
-		None => Reputation::Unverified,
-		Some(v) => {
-			let reputation: Reputation =
-				Decode::decode(&mut v.as_slice()).expect("Failed to decode value after fetching");
-			reputation
-		},
+/// Query the reputations of `prover` in the given ceremonies, served from the cache where
+/// possible. An entry is `None` if the reputation could not be read.
+fn query_reputations(
+	source: &ReputationSource,
+	prover: &AccountId,
+	cid: CommunityIdentifier,
+	cindexes: &[CeremonyIndexType],
+	lookups: &mut ReputationLookupCounts,
+) -> Vec<Option<Reputation>> {
+	let cache = source.cache();
+	let mut reputations: Vec<Option<Reputation>> = cindexes
+		.iter()
+		.map(|cindex| {
+			let cached = cache.and_then(|c| c.reputation(cid, *cindex, prover));
+			lookups.record_cache_lookup(cached.is_some());
+			cached
+		})
+		.collect();
+
+	let missing: Vec<CeremonyIndexType> = cindexes
+		.iter()
+		.zip(reputations.iter())
+		.filter(|(_, reputation)| reputation.is_none())
+		.map(|(cindex, _)| *cindex)
+		.collect();
+	if missing.is_empty() {
+		return reputations
+	}
+
+	trace!("requesting reputation for {:?}: cid is :{}, cindexes are: {:?}", prover, cid, missing);
+	let (fetched, verified_at) =
+		match source.ceremonies().read_reputations(source, cid, &missing, prover) {
+			Ok(fetched) => fetched,
+			Err(e) => {
+				error!(
+					"Failed to read reputations from the {:?} parentchain: {:?}",
+					source.parentchain_id(),
+					e
+				);
+				return reputations
+			},
+		};
+
+	let mut fetched = missing.into_iter().zip(fetched).map(|(cindex, reputation)| {
+		if let Some(cache) = cache {
+			cache.insert_reputation(cid, cindex, prover.clone(), reputation, verified_at);
+		}
+		reputation
+	});
+	for reputation in reputations.iter_mut().filter(|reputation| reputation.is_none()) {
+		*reputation = fetched.next();
 	}
+	reputations
 }
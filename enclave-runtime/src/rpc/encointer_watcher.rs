@@ -0,0 +1,322 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Keeps track of the Encointer changes on a target parentchain that affect the
+//! personhood oracle.
+//!
+//! There is one watcher per target parentchain, fed by its indirect calls executor with the
+//! events of every imported block. The oracle uses it to decide whether previously looked up reputations, community
+//! data or issued badges are still up to date.
+//!
+//! Changes are only tracked since the last ceremony phase change, and at most
+//! [`MAX_TRACKED_ENCOINTER_CHANGES`] of them. Anything verified before the tracked changes
+//! is considered outdated.
+
+use encointer_primitives::{communities::CommunityIdentifier, scheduler::CeremonyPhaseType};
+use itc_parentchain::indirect_calls_executor::{EncointerEvent, HandleEncointerEvents};
+use itp_settings::personhood_oracle::MAX_TRACKED_ENCOINTER_CHANGES;
+use itp_stf_primitives::types::AccountId;
+use itp_types::H256;
+use log::*;
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	sync::SgxRwLock as RwLock,
+	vec::Vec,
+};
+
+/// Block number of the target parentchain a watcher is attached to.
+pub type TargetBlockNumber = u64;
+
+#[derive(Default)]
+struct WatchedEncointerState {
+	last_block_number: Option<TargetBlockNumber>,
+	phase: Option<(CeremonyPhaseType, TargetBlockNumber)>,
+	ceremony_started_at: Option<TargetBlockNumber>,
+	/// Changes before this block have been forgotten.
+	tracked_since: Option<TargetBlockNumber>,
+	community_reputation_changes: BTreeMap<CommunityIdentifier, TargetBlockNumber>,
+	account_reputation_changes: BTreeMap<(CommunityIdentifier, AccountId), TargetBlockNumber>,
+	community_changes: BTreeMap<CommunityIdentifier, TargetBlockNumber>,
+	purged_communities: BTreeSet<CommunityIdentifier>,
+}
+
+impl WatchedEncointerState {
+	fn is_tracked_since(&self, block_number: TargetBlockNumber) -> bool {
+		self.tracked_since.map_or(true, |b| block_number >= b)
+	}
+
+	/// Forget the changes before `block_number`, which are then all considered as changed.
+	fn forget_changes(&mut self, block_number: TargetBlockNumber) {
+		self.community_reputation_changes.clear();
+		self.account_reputation_changes.clear();
+		self.community_changes.clear();
+		self.tracked_since = Some(block_number);
+	}
+
+	fn tracks_too_many_changes(&self) -> bool {
+		self.community_reputation_changes.len() > MAX_TRACKED_ENCOINTER_CHANGES
+			|| self.account_reputation_changes.len() > MAX_TRACKED_ENCOINTER_CHANGES
+			|| self.community_changes.len() > MAX_TRACKED_ENCOINTER_CHANGES
+	}
+}
+
+#[derive(Default)]
+pub struct EncointerWatcher {
+	state: RwLock<WatchedEncointerState>,
+}
+
+impl EncointerWatcher {
+	/// Last imported target parentchain block the watcher has seen Encointer events of.
+	pub fn last_block_number(&self) -> Option<TargetBlockNumber> {
+		self.state.read().ok()?.last_block_number
+	}
+
+	/// Current ceremony phase and the block in which it started, if a phase change
+	/// has been observed since the enclave started.
+	pub fn ceremony_phase(&self) -> Option<(CeremonyPhaseType, TargetBlockNumber)> {
+		self.state.read().ok()?.phase
+	}
+
+	/// Returns true if the reputation of `account` in `cid` has been written after `block_number`,
+	/// or if a new ceremony has started since then, which moves the window of relevant reputations.
+	///
+	/// Returns true as well if the watcher's state cannot be read, so that callers rather
+	/// refresh too often than rely on outdated data.
+	pub fn reputation_changed_since(
+		&self,
+		cid: &CommunityIdentifier,
+		account: &AccountId,
+		block_number: TargetBlockNumber,
+	) -> bool {
+		let state = match self.state.read() {
+			Ok(state) => state,
+			Err(_) => return true,
+		};
+		let changed_after = |changed_at: Option<&TargetBlockNumber>| {
+			changed_at.map_or(false, |b| *b > block_number)
+		};
+		!state.is_tracked_since(block_number)
+			|| state.purged_communities.contains(cid)
+			|| changed_after(state.community_reputation_changes.get(cid))
+			|| changed_after(state.account_reputation_changes.get(&(*cid, account.clone())))
+			|| changed_after(state.ceremony_started_at.as_ref())
+	}
+
+	/// Returns true if the community has been registered, updated or purged after `block_number`.
+	pub fn community_changed_since(
+		&self,
+		cid: &CommunityIdentifier,
+		block_number: TargetBlockNumber,
+	) -> bool {
+		let state = match self.state.read() {
+			Ok(state) => state,
+			Err(_) => return true,
+		};
+		!state.is_tracked_since(block_number)
+			|| state.purged_communities.contains(cid)
+			|| state.community_changes.get(cid).map_or(false, |b| *b > block_number)
+	}
+
+	pub fn is_purged(&self, cid: &CommunityIdentifier) -> bool {
+		self.state
+			.read()
+			.map(|s| s.purged_communities.contains(cid))
+			.unwrap_or_default()
+	}
+}
+
+impl HandleEncointerEvents for EncointerWatcher {
+	fn handle_encointer_events(
+		&self,
+		block_number: TargetBlockNumber,
+		block_hash: H256,
+		events: Vec<EncointerEvent>,
+	) {
+		let mut state = match self.state.write() {
+			Ok(state) => state,
+			Err(e) => {
+				error!("Failed to acquire Encointer watcher lock: {:?}", e);
+				return
+			},
+		};
+
+		for event in events {
+			debug!(
+				"Encointer event in target parentchain block {} ({:?}): {:?}",
+				block_number, block_hash, event
+			);
+			match event {
+				EncointerEvent::PhaseChanged(phase) => {
+					info!("Encointer ceremony phase changed to {:?}", phase);
+					// Everything verified before is outdated by the phase change anyway.
+					state.forget_changes(block_number);
+					if phase == CeremonyPhaseType::Registering {
+						state.ceremony_started_at = Some(block_number);
+					}
+					state.phase = Some((phase, block_number));
+				},
+				EncointerEvent::ReputationChanged(cid, Some(account)) => {
+					state.account_reputation_changes.insert((cid, account), block_number);
+				},
+				EncointerEvent::ReputationChanged(cid, None) => {
+					state.community_reputation_changes.insert(cid, block_number);
+				},
+				EncointerEvent::CommunityChanged(cid) => {
+					state.purged_communities.remove(&cid);
+					state.community_changes.insert(cid, block_number);
+				},
+				EncointerEvent::CommunityPurged(cid) => {
+					state.purged_communities.insert(cid);
+					state.community_changes.insert(cid, block_number);
+					state.community_reputation_changes.remove(&cid);
+					state.account_reputation_changes.retain(|(c, _), _| c != &cid);
+				},
+			}
+		}
+		if state.tracks_too_many_changes() {
+			warn!("Tracking too many Encointer changes, outdating everything verified so far");
+			state.forget_changes(block_number);
+		}
+		state.last_block_number = Some(block_number);
+	}
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+	use super::*;
+
+	pub fn encointer_watcher_tracks_reputation_changes() {
+		let watcher = EncointerWatcher::default();
+		let cid = CommunityIdentifier::default();
+		let account = AccountId::new([1u8; 32]);
+		let other_account = AccountId::new([2u8; 32]);
+
+		watcher.handle_encointer_events(
+			10,
+			H256::default(),
+			vec![EncointerEvent::ReputationChanged(cid, Some(account.clone()))],
+		);
+
+		assert_eq!(watcher.last_block_number(), Some(10));
+		assert!(watcher.reputation_changed_since(&cid, &account, 9));
+		assert!(!watcher.reputation_changed_since(&cid, &account, 10));
+		assert!(!watcher.reputation_changed_since(&cid, &other_account, 9));
+
+		watcher.handle_encointer_events(
+			12,
+			H256::default(),
+			vec![EncointerEvent::ReputationChanged(cid, None)],
+		);
+
+		assert!(watcher.reputation_changed_since(&cid, &other_account, 11));
+	}
+
+	pub fn encointer_watcher_new_ceremony_outdates_reputations() {
+		let watcher = EncointerWatcher::default();
+		let cid = CommunityIdentifier::default();
+		let account = AccountId::new([1u8; 32]);
+
+		watcher.handle_encointer_events(
+			20,
+			H256::default(),
+			vec![EncointerEvent::PhaseChanged(CeremonyPhaseType::Registering)],
+		);
+
+		assert_eq!(watcher.ceremony_phase(), Some((CeremonyPhaseType::Registering, 20)));
+		assert!(watcher.reputation_changed_since(&cid, &account, 19));
+		assert!(!watcher.reputation_changed_since(&cid, &account, 20));
+
+		watcher.handle_encointer_events(
+			25,
+			H256::default(),
+			vec![EncointerEvent::PhaseChanged(CeremonyPhaseType::Assigning)],
+		);
+
+		assert_eq!(watcher.ceremony_phase(), Some((CeremonyPhaseType::Assigning, 25)));
+		// Changes before a phase change are forgotten.
+		assert!(watcher.reputation_changed_since(&cid, &account, 20));
+		assert!(!watcher.reputation_changed_since(&cid, &account, 25));
+	}
+
+	pub fn encointer_watcher_forgets_changes_on_phase_change() {
+		let watcher = EncointerWatcher::default();
+		let cid = CommunityIdentifier::default();
+		let account = AccountId::new([1u8; 32]);
+
+		watcher.handle_encointer_events(
+			30,
+			H256::default(),
+			vec![
+				EncointerEvent::ReputationChanged(cid, Some(account.clone())),
+				EncointerEvent::CommunityChanged(cid),
+			],
+		);
+		watcher.handle_encointer_events(
+			31,
+			H256::default(),
+			vec![EncointerEvent::PhaseChanged(CeremonyPhaseType::Attesting)],
+		);
+
+		let state = watcher.state.read().unwrap();
+		assert!(state.account_reputation_changes.is_empty());
+		assert!(state.community_changes.is_empty());
+		drop(state);
+		assert!(watcher.community_changed_since(&cid, 30));
+		assert!(!watcher.community_changed_since(&cid, 31));
+	}
+
+	pub fn encointer_watcher_forgets_changes_beyond_capacity() {
+		let watcher = EncointerWatcher::default();
+		let cid = CommunityIdentifier::default();
+		let events: Vec<_> = (0..=MAX_TRACKED_ENCOINTER_CHANGES as u32)
+			.map(|i| {
+				let mut account = [0u8; 32];
+				account[..4].copy_from_slice(&i.to_le_bytes());
+				EncointerEvent::ReputationChanged(cid, Some(AccountId::new(account)))
+			})
+			.collect();
+
+		watcher.handle_encointer_events(40, H256::default(), events);
+
+		assert!(watcher.state.read().unwrap().account_reputation_changes.is_empty());
+		let unrelated_account = AccountId::new([0xffu8; 32]);
+		assert!(watcher.reputation_changed_since(&cid, &unrelated_account, 39));
+		assert!(!watcher.reputation_changed_since(&cid, &unrelated_account, 40));
+	}
+
+	pub fn encointer_watcher_tracks_purged_communities() {
+		let watcher = EncointerWatcher::default();
+		let cid = CommunityIdentifier::default();
+
+		watcher.handle_encointer_events(
+			30,
+			H256::default(),
+			vec![EncointerEvent::CommunityPurged(cid)],
+		);
+		assert!(watcher.is_purged(&cid));
+		assert!(watcher.community_changed_since(&cid, 40));
+
+		watcher.handle_encointer_events(
+			31,
+			H256::default(),
+			vec![EncointerEvent::CommunityChanged(cid)],
+		);
+		assert!(!watcher.is_purged(&cid));
+		assert!(watcher.community_changed_since(&cid, 30));
+		assert!(!watcher.community_changed_since(&cid, 31));
+	}
+}
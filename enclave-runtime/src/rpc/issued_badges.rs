@@ -0,0 +1,331 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Keeps the Nostr badges issued by the personhood oracle up to date with the Encointer
+//! pallets of the parentchain their holders have been attested on.
+//!
+//! The target parentchains are only synced up to their finalized head, so the updates are
+//! triggered by finalized blocks:
+//! * With every ceremony phase change, the holders are assessed again. A badge whose holder
+//!   is no longer attested is deleted (NIP-09), one with a changed number of attestations is
+//!   re-issued. A new ceremony renews all badges.
+//! * All badges of a purged community are deleted.
+//!
+//! Badge awards expire after [`BADGE_VALIDITY`] (NIP-40), so that the badges issued before a
+//! restart of the enclave, which are not sealed, or beyond [`MAX_MAINTAINED_BADGES`] are not
+//! valid forever.
+//!
+//! [`BADGE_VALIDITY`]: itp_settings::personhood_oracle::BADGE_VALIDITY
+
+use crate::rpc::encointer_watcher::{EncointerWatcher, TargetBlockNumber};
+use encointer_primitives::scheduler::CeremonyPhaseType;
+use ita_personhood::EncointerSubject;
+use itc_parentchain::indirect_calls_executor::{EncointerEvent, HandleEncointerEvents};
+use itp_settings::personhood_oracle::MAX_MAINTAINED_BADGES;
+use itp_types::{parentchain::ParentchainId, H256};
+use log::*;
+use nostr::{prelude::XOnlyPublicKey, EventId};
+use std::{
+	string::String,
+	sync::{Arc, SgxRwLock as RwLock},
+	vec::Vec,
+};
+
+/// A badge award the oracle has published.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IssuedBadge {
+	pub subject: EncointerSubject,
+	/// Parentchain the holder's attestations have been verified on.
+	pub parentchain_id: ParentchainId,
+	pub nostr_pub_key: XOnlyPublicKey,
+	pub relay_url: String,
+	pub attestations: u32,
+	pub award_id: EventId,
+}
+
+impl IssuedBadge {
+	fn is_replaced_by(&self, other: &IssuedBadge) -> bool {
+		self.subject.cid == other.subject.cid
+			&& self.subject.account == other.subject.account
+			&& self.nostr_pub_key == other.nostr_pub_key
+	}
+}
+
+/// The badges the oracle keeps up to date, oldest first.
+#[derive(Default)]
+pub struct IssuedBadges {
+	badges: RwLock<Vec<IssuedBadge>>,
+}
+
+impl IssuedBadges {
+	/// Register an issued badge. It replaces an earlier badge of the same Encointer account
+	/// for the same Nostr key.
+	pub fn register(&self, badge: IssuedBadge) {
+		let mut badges = match self.badges.write() {
+			Ok(badges) => badges,
+			Err(e) => {
+				error!("Failed to acquire issued badges lock: {:?}", e);
+				return
+			},
+		};
+		badges.retain(|b| !b.is_replaced_by(&badge));
+		badges.push(badge);
+		if badges.len() > MAX_MAINTAINED_BADGES {
+			let dropped = badges.remove(0);
+			warn!("Too many issued badges, leaving badge {:?} to expire", dropped.award_id);
+		}
+	}
+
+	/// Remove the badges verified on `parentchain_id` that match `filter` and return them.
+	pub fn take(
+		&self,
+		parentchain_id: ParentchainId,
+		filter: impl Fn(&IssuedBadge) -> bool,
+	) -> Vec<IssuedBadge> {
+		let mut badges = match self.badges.write() {
+			Ok(badges) => badges,
+			Err(e) => {
+				error!("Failed to acquire issued badges lock: {:?}", e);
+				return Vec::new()
+			},
+		};
+		let (taken, kept): (Vec<_>, Vec<_>) =
+			badges.drain(..).partition(|b| b.parentchain_id == parentchain_id && filter(b));
+		*badges = kept;
+		taken
+	}
+
+	pub fn count(&self) -> usize {
+		self.badges.read().map(|b| b.len()).unwrap_or_default()
+	}
+}
+
+/// Publishes the updates of issued badges.
+pub trait PublishBadgeUpdates {
+	/// Assess the holder of `badge` again. A badge whose holder is no longer attested is
+	/// deleted. It is re-issued if the number of attestations changed or if `renew` is set.
+	///
+	/// Returns the badge that remains issued, `None` if it has been deleted.
+	fn refresh(&self, badge: IssuedBadge, renew: bool) -> Option<IssuedBadge>;
+
+	/// Delete the badges.
+	fn delete(&self, badges: &[IssuedBadge], reason: &str);
+}
+
+/// Handles the Encointer events of a target parentchain for the personhood oracle.
+///
+/// The events are passed on to the parentchain's [`EncointerWatcher`] first, then the badges
+/// verified on that parentchain are updated.
+pub struct PersonhoodOracleEventHandler<Publisher> {
+	parentchain_id: ParentchainId,
+	watcher: Arc<EncointerWatcher>,
+	issued_badges: Arc<IssuedBadges>,
+	publisher: Publisher,
+}
+
+impl<Publisher> PersonhoodOracleEventHandler<Publisher> {
+	pub fn new(
+		parentchain_id: ParentchainId,
+		watcher: Arc<EncointerWatcher>,
+		issued_badges: Arc<IssuedBadges>,
+		publisher: Publisher,
+	) -> Self {
+		PersonhoodOracleEventHandler { parentchain_id, watcher, issued_badges, publisher }
+	}
+}
+
+impl<Publisher: PublishBadgeUpdates> HandleEncointerEvents
+	for PersonhoodOracleEventHandler<Publisher>
+{
+	fn handle_encointer_events(
+		&self,
+		block_number: TargetBlockNumber,
+		block_hash: H256,
+		events: Vec<EncointerEvent>,
+	) {
+		let mut purged_communities = Vec::new();
+		let mut phase_changed = false;
+		let mut ceremony_started = false;
+		for event in events.iter() {
+			match event {
+				EncointerEvent::CommunityPurged(cid) => purged_communities.push(*cid),
+				EncointerEvent::PhaseChanged(phase) => {
+					phase_changed = true;
+					ceremony_started |= *phase == CeremonyPhaseType::Registering;
+				},
+				_ => {},
+			}
+		}
+
+		self.watcher.handle_encointer_events(block_number, block_hash, events);
+
+		for cid in purged_communities {
+			let badges = self.issued_badges.take(self.parentchain_id, |b| b.subject.cid == cid);
+			if !badges.is_empty() {
+				info!("Deleting {} badges of purged community {}", badges.len(), cid);
+				self.publisher.delete(&badges, "The community has been purged");
+			}
+		}
+
+		if phase_changed {
+			let badges = self.issued_badges.take(self.parentchain_id, |_| true);
+			info!("Refreshing {} badges after a ceremony phase change", badges.len());
+			for mut badge in badges {
+				if ceremony_started {
+					badge.subject.cindex += 1;
+				}
+				if let Some(badge) = self.publisher.refresh(badge, ceremony_started) {
+					self.issued_badges.register(badge);
+				}
+			}
+		}
+	}
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+	use super::*;
+	use crate::rpc::nostr_utils::nostr_issuer_keys;
+	use core::str::FromStr;
+	use encointer_primitives::communities::CommunityIdentifier;
+	use itp_stf_primitives::types::AccountId;
+	use sp_core::{ed25519, Pair};
+	use std::{string::ToString, sync::SgxMutex as Mutex, vec};
+
+	#[derive(Default)]
+	struct PublisherMock {
+		refreshed: Mutex<Vec<(IssuedBadge, bool)>>,
+		deleted: Mutex<Vec<IssuedBadge>>,
+	}
+
+	impl PublishBadgeUpdates for Arc<PublisherMock> {
+		/// Deletes the badges without attestations and keeps all others.
+		fn refresh(&self, badge: IssuedBadge, renew: bool) -> Option<IssuedBadge> {
+			self.refreshed.lock().unwrap().push((badge.clone(), renew));
+			if badge.attestations == 0 {
+				self.delete(&[badge], "no attestations");
+				return None
+			}
+			Some(badge)
+		}
+
+		fn delete(&self, badges: &[IssuedBadge], _reason: &str) {
+			self.deleted.lock().unwrap().extend_from_slice(badges);
+		}
+	}
+
+	pub fn issued_badge_replaces_earlier_badge_of_the_same_holder() {
+		let issued_badges = IssuedBadges::default();
+		let cid = CommunityIdentifier::default();
+
+		issued_badges.register(badge(cid, 1, ParentchainId::TargetA, 3));
+		issued_badges.register(badge(cid, 2, ParentchainId::TargetA, 3));
+		issued_badges.register(badge(cid, 1, ParentchainId::TargetA, 4));
+
+		let badges = issued_badges.take(ParentchainId::TargetA, |_| true);
+		assert_eq!(badges.len(), 2);
+		assert_eq!(badges[1].attestations, 4);
+		assert_eq!(issued_badges.count(), 0);
+	}
+
+	pub fn badges_of_purged_community_are_deleted() {
+		let (handler, issued_badges, publisher) = handler(ParentchainId::TargetA);
+		let purged_cid = CommunityIdentifier::default();
+		let other_cid = CommunityIdentifier::from_str("gbsuv7YXq9G").unwrap();
+		issued_badges.register(badge(purged_cid, 1, ParentchainId::TargetA, 3));
+		issued_badges.register(badge(purged_cid, 2, ParentchainId::TargetB, 3));
+		issued_badges.register(badge(other_cid, 3, ParentchainId::TargetA, 3));
+
+		handler.handle_encointer_events(
+			10,
+			H256::default(),
+			vec![EncointerEvent::CommunityPurged(purged_cid)],
+		);
+
+		let deleted = publisher.deleted.lock().unwrap();
+		assert_eq!(deleted.len(), 1);
+		assert_eq!(deleted[0].subject.cid, purged_cid);
+		assert_eq!(deleted[0].parentchain_id, ParentchainId::TargetA);
+		assert_eq!(issued_badges.count(), 2);
+		assert!(publisher.refreshed.lock().unwrap().is_empty());
+	}
+
+	pub fn badges_are_refreshed_on_phase_change_and_renewed_with_a_new_ceremony() {
+		let (handler, issued_badges, publisher) = handler(ParentchainId::TargetA);
+		let cid = CommunityIdentifier::default();
+		issued_badges.register(badge(cid, 1, ParentchainId::TargetA, 3));
+		issued_badges.register(badge(cid, 2, ParentchainId::TargetA, 0));
+		issued_badges.register(badge(cid, 3, ParentchainId::TargetB, 3));
+
+		handler.handle_encointer_events(
+			10,
+			H256::default(),
+			vec![EncointerEvent::PhaseChanged(CeremonyPhaseType::Attesting)],
+		);
+
+		let refreshed: Vec<_> = publisher.refreshed.lock().unwrap().drain(..).collect();
+		assert_eq!(refreshed.len(), 2);
+		assert!(refreshed.iter().all(|(badge, renew)| badge.subject.cindex == 7 && !renew));
+		assert_eq!(publisher.deleted.lock().unwrap().len(), 1);
+		assert_eq!(issued_badges.count(), 2);
+		assert_eq!(handler.watcher.ceremony_phase(), Some((CeremonyPhaseType::Attesting, 10)));
+
+		handler.handle_encointer_events(
+			20,
+			H256::default(),
+			vec![EncointerEvent::PhaseChanged(CeremonyPhaseType::Registering)],
+		);
+
+		let refreshed = publisher.refreshed.lock().unwrap();
+		assert_eq!(refreshed.len(), 1);
+		assert_eq!(refreshed[0].0.subject.cindex, 8);
+		assert!(refreshed[0].1);
+		assert_eq!(issued_badges.count(), 2);
+	}
+
+	fn handler(
+		parentchain_id: ParentchainId,
+	) -> (PersonhoodOracleEventHandler<Arc<PublisherMock>>, Arc<IssuedBadges>, Arc<PublisherMock>) {
+		let issued_badges = Arc::new(IssuedBadges::default());
+		let publisher = Arc::new(PublisherMock::default());
+		let handler = PersonhoodOracleEventHandler::new(
+			parentchain_id,
+			Arc::new(EncointerWatcher::default()),
+			issued_badges.clone(),
+			publisher.clone(),
+		);
+		(handler, issued_badges, publisher)
+	}
+
+	fn badge(
+		cid: CommunityIdentifier,
+		holder: u8,
+		parentchain_id: ParentchainId,
+		attestations: u32,
+	) -> IssuedBadge {
+		IssuedBadge {
+			subject: EncointerSubject { cid, cindex: 7, account: AccountId::new([holder; 32]) },
+			parentchain_id,
+			nostr_pub_key: nostr_issuer_keys(&ed25519::Pair::from_seed(&[holder; 32]))
+				.unwrap()
+				.public_key(),
+			relay_url: "wss://relay.example".to_string(),
+			attestations,
+			award_id: EventId::from_slice(&[holder; 32]).unwrap(),
+		}
+	}
+}
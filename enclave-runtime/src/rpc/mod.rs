@@ -15,7 +15,12 @@
 
 */
 
+pub mod encointer_cache;
 pub mod encointer_utils;
+pub mod encointer_watcher;
+pub mod issued_badges;
 pub mod nostr_utils;
+pub mod personhood_metrics;
+pub mod personhood_sources;
 pub mod rpc_response_channel;
 pub mod worker_api_direct;
@@ -15,8 +15,11 @@
 
 */
 use crate::{String, Vec};
+use codec::Encode;
 use itp_time_utils::{duration_now, now_as_secs, Duration};
-use nostr::{prelude::*, types::time::TimeProvider, Event, Timestamp};
+use itp_utils::hex::hex_encode;
+use nostr::{key::FromSkStr, prelude::*, types::time::TimeProvider, Event, Keys, Timestamp};
+use sp_core::{blake2_256, ed25519, Pair};
 use tungstenite_sgx as tungstenite;
 
 use nostr::ClientMessage;
@@ -79,6 +82,21 @@ pub fn get_ts() -> Timestamp {
 	time_provider.to_timestamp(now)
 }
 
+/// Derivation context of the Nostr issuer key, so it is independent of other keys derived
+/// from the enclave signing key.
+const NOSTR_ISSUER_KEY_CONTEXT: &[u8] = b"integritee/nostr-issuer-key";
+
+/// Keys with which the enclave signs the Nostr events it issues.
+///
+/// The secret key is derived from the seed of the sealed `enclave_signer`, so it never leaves
+/// the enclave and is the same across restarts.
+pub fn nostr_issuer_keys(enclave_signer: &ed25519::Pair) -> Result<Keys, String> {
+	let secret_key = blake2_256(&(NOSTR_ISSUER_KEY_CONTEXT, enclave_signer.seed()).encode());
+
+	let secp = Secp256k1::new();
+	Keys::from_sk_str(&hex_encode(&secret_key), &secp).map_err(|e| format!("{:?}", e))
+}
+
 pub fn send_nostr_events(events_to_send: Vec<Event>, relay: &str) -> Result<(), String> {
 	// Connect to relay
 	let (mut socket, _response) = tungstenite::connect(relay)
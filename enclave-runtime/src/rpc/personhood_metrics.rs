@@ -0,0 +1,106 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Export of personhood oracle metrics to the untrusted worker.
+//!
+//! Metrics leave the enclave in plain text. Only ever export aggregate information,
+//! never anything that can be linked to a user (account ids, cids, Nostr keys).
+
+use crate::initialization::global_components::GLOBAL_OCALL_API_COMPONENT;
+use itp_component_container::ComponentGetter;
+use itp_enclave_metrics::{EnclaveMetric, PersonhoodOracleMetric};
+use itp_ocall_api::EnclaveMetricsOCallApi;
+use log::*;
+use std::{
+	string::{String, ToString},
+	time::Instant,
+};
+
+pub fn update_personhood_metric(metric: PersonhoodOracleMetric) {
+	let ocall_api = match GLOBAL_OCALL_API_COMPONENT.get() {
+		Ok(api) => api,
+		Err(e) => {
+			error!("failed to get OCALL API, error: {:#?}", e);
+			return
+		},
+	};
+
+	if let Err(e) = ocall_api.update_metric(EnclaveMetric::PersonhoodOracle(metric)) {
+		error!("Failed to update enclave metric, sgx_status_t: {}", e)
+	}
+}
+
+pub fn record_rpc_latency(rpc_method: &str, timer: Instant) {
+	update_personhood_metric(PersonhoodOracleMetric::RpcLatency(
+		rpc_method.to_string(),
+		timer.elapsed().as_millis(),
+	));
+}
+
+/// Relays that are reported by their host in metrics. The relay url is chosen by the caller, so
+/// any other relay is reported as [`OTHER_RELAY`] to keep the number of label values bounded.
+const KNOWN_RELAYS: &[&str] = &[
+	"nos.lol",
+	"nostr.wine",
+	"relay.damus.io",
+	"relay.nostr.band",
+	"relay.primal.net",
+	"relay.snort.social",
+];
+
+const OTHER_RELAY: &str = "other";
+
+/// Metric label of a relay url: the host of a well known relay, "other" for any other relay.
+pub fn relay_label(relay_url: &str) -> &'static str {
+	let host = relay_host(relay_url);
+	KNOWN_RELAYS
+		.iter()
+		.find(|known| **known == host)
+		.copied()
+		.unwrap_or(OTHER_RELAY)
+}
+
+/// Reduce a relay url to its host.
+///
+/// Drops the scheme, credentials, port, path and query, as they may be user specific.
+fn relay_host(relay_url: &str) -> String {
+	let without_scheme = relay_url.split("://").last().unwrap_or_default();
+	let authority = without_scheme.split(&['/', '?', '#'][..]).next().unwrap_or_default();
+	let host_and_port = authority.rsplit('@').next().unwrap_or_default();
+	let host = host_and_port.split(':').next().unwrap_or_default();
+	host.to_lowercase()
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+	use super::*;
+
+	pub fn relay_host_strips_everything_but_the_host() {
+		assert_eq!(relay_host("wss://relay.damus.io"), "relay.damus.io");
+		assert_eq!(relay_host("wss://Relay.Example.com:443/path?token=abc"), "relay.example.com");
+		assert_eq!(relay_host("ws://user:secret@127.0.0.1:7000"), "127.0.0.1");
+		assert_eq!(relay_host("relay.example.com/"), "relay.example.com");
+		assert_eq!(relay_host(""), "");
+	}
+
+	pub fn relay_label_reports_unknown_relays_as_other() {
+		assert_eq!(relay_label("wss://Relay.Damus.io:443/?token=abc"), "relay.damus.io");
+		assert_eq!(relay_label("wss://relay.example.com"), "other");
+		assert_eq!(relay_label("ws://user:secret@127.0.0.1:7000"), "other");
+		assert_eq!(relay_label(""), "other");
+	}
+}
@@ -0,0 +1,202 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! The target parentchains the personhood oracle reads verified personhood data from.
+//!
+//! By default, the Encointer pallets of the TargetA parentchain are read. The untrusted worker
+//! can configure the oracle to read from TargetB instead, or from both, each with the storage
+//! layout of its runtime.
+
+use crate::{
+	error::{Error, Result},
+	initialization::global_components::{
+		EnclaveValidatorAccessor, GLOBAL_OCALL_API_COMPONENT,
+		GLOBAL_TARGET_A_ENCOINTER_WATCHER_COMPONENT, GLOBAL_TARGET_B_ENCOINTER_WATCHER_COMPONENT,
+	},
+	rpc::{
+		encointer_cache::EncointerCache,
+		encointer_utils::{fetch_community_metadata, fetch_evidence},
+		encointer_watcher::{EncointerWatcher, TargetBlockNumber},
+	},
+	utils::{
+		get_validator_accessor_from_solo_or_parachain,
+		get_validator_accessor_from_target_a_solo_or_parachain,
+		get_validator_accessor_from_target_b_solo_or_parachain,
+	},
+};
+use codec::Decode;
+use ita_personhood::{
+	EncointerCeremonies, Error as PersonhoodError, PersonhoodAssessment, PersonhoodSource,
+	PersonhoodSubject, VerifiedStorage,
+};
+use itc_parentchain::light_client::{concurrent_access::ValidatorAccess, LightClientState};
+use itp_component_container::ComponentGetter;
+use itp_ocall_api::EnclaveOnChainOCallApi;
+use itp_types::{
+	parentchain::ParentchainId,
+	personhood::{PersonhoodSourceConfig, PersonhoodStorageLayout},
+	H256,
+};
+use log::*;
+use std::{format, string::String, sync::Arc, vec::Vec};
+
+/// A target parentchain the personhood oracle reads reputations and communities from.
+pub struct ReputationSource {
+	parentchain_id: ParentchainId,
+	ceremonies: EncointerCeremonies,
+	/// The watcher only recognizes the events of the Encointer pallets under their default
+	/// names. Values read with other layouts could not be invalidated, so they are not cached.
+	cache: Option<EncointerCache>,
+}
+
+impl ReputationSource {
+	pub fn new(config: PersonhoodSourceConfig) -> Result<Self> {
+		let watcher = encointer_watcher(config.parentchain_id)?;
+		let (communities_pallet, ceremonies_pallet, scheduler_pallet) =
+			config.layout.pallet_names();
+		let pallet_name = |name: &[u8]| {
+			String::from_utf8(name.to_vec())
+				.map_err(|e| Error::Other(format!("Invalid pallet name: {:?}", e).into()))
+		};
+
+		Ok(ReputationSource {
+			parentchain_id: config.parentchain_id,
+			ceremonies: EncointerCeremonies::new(
+				pallet_name(communities_pallet)?,
+				pallet_name(ceremonies_pallet)?,
+				pallet_name(scheduler_pallet)?,
+			),
+			cache: match config.layout {
+				PersonhoodStorageLayout::Encointer =>
+					Some(EncointerCache::with_default_capacity(watcher)),
+				PersonhoodStorageLayout::RenamedEncointerPallets { .. } => None,
+			},
+		})
+	}
+
+	pub fn parentchain_id(&self) -> ParentchainId {
+		self.parentchain_id
+	}
+
+	pub fn cache(&self) -> Option<&EncointerCache> {
+		self.cache.as_ref()
+	}
+
+	pub fn ceremonies(&self) -> &EncointerCeremonies {
+		&self.ceremonies
+	}
+}
+
+impl VerifiedStorage for ReputationSource {
+	type Error = Error;
+
+	/// Read storage values of the source's parentchain, checked against the storage proof of
+	/// its latest finalized header. Returns the values together with the number of that header.
+	fn read_verified<V: Decode>(
+		&self,
+		storage_keys: Vec<Vec<u8>>,
+	) -> Result<(Vec<Option<V>>, TargetBlockNumber)> {
+		let ocall_api = GLOBAL_OCALL_API_COMPONENT.get()?;
+		let header = self
+			.validator_accessor()?
+			.execute_on_validator(|v| v.latest_finalized_header())?;
+
+		let entries = ocall_api.get_multiple_storages_verified(
+			storage_keys,
+			&header,
+			&self.parentchain_id,
+		)?;
+		Ok((entries.into_iter().map(|entry| entry.value).collect(), header.number.into()))
+	}
+
+	fn genesis_hash(&self) -> Result<H256> {
+		Ok(self.validator_accessor()?.execute_on_validator(|v| v.genesis_hash())?)
+	}
+}
+
+impl ReputationSource {
+	fn validator_accessor(&self) -> Result<Arc<EnclaveValidatorAccessor>> {
+		match self.parentchain_id {
+			ParentchainId::Integritee => get_validator_accessor_from_solo_or_parachain(),
+			ParentchainId::TargetA => get_validator_accessor_from_target_a_solo_or_parachain(),
+			ParentchainId::TargetB => get_validator_accessor_from_target_b_solo_or_parachain(),
+		}
+	}
+}
+
+impl PersonhoodSource for ReputationSource {
+	fn knows(&self, subject: &PersonhoodSubject) -> core::result::Result<bool, PersonhoodError> {
+		match subject {
+			PersonhoodSubject::Encointer(subject) => fetch_community_metadata(self, subject.cid)
+				.map(|metadata| metadata.is_some())
+				.map_err(|e| PersonhoodError::Storage(format!("{:?}", e))),
+		}
+	}
+
+	/// Assesses the subject at the current ceremony of the source, regardless of the ceremony
+	/// index the subject has been requested with.
+	fn assess(
+		&self,
+		subject: &PersonhoodSubject,
+	) -> core::result::Result<PersonhoodAssessment, PersonhoodError> {
+		match subject {
+			PersonhoodSubject::Encointer(subject) => {
+				let (cindex, _) = self
+					.ceremonies
+					.read_current_ceremony_index(self)
+					.map_err(|e| PersonhoodError::Storage(format!("{:?}", e)))?;
+				let cindex = cindex.ok_or_else(|| {
+					PersonhoodError::Storage("The scheduler has no current ceremony index".into())
+				})?;
+				let subject = subject.at_ceremony(cindex);
+				Ok(self.ceremonies.assess(&subject, &fetch_evidence(self, &subject)))
+			},
+		}
+	}
+}
+
+/// The first of the sources that knows the subject.
+///
+/// If the subject could not be looked up on some source, that source is returned, so that the
+/// assessment fails instead of reporting the subject as unknown.
+pub fn select_source<'a, S: PersonhoodSource>(
+	sources: &'a [S],
+	subject: &PersonhoodSubject,
+) -> Option<&'a S> {
+	let mut unavailable = None;
+	for source in sources {
+		match source.knows(subject) {
+			Ok(true) => return Some(source),
+			Ok(false) => {},
+			Err(e) => {
+				warn!("Could not look up {:?} on a personhood source: {:?}", subject, e);
+				unavailable = unavailable.or(Some(source));
+			},
+		}
+	}
+	unavailable
+}
+
+/// The Encointer watcher of a target parentchain.
+pub fn encointer_watcher(parentchain_id: ParentchainId) -> Result<Arc<EncointerWatcher>> {
+	match parentchain_id {
+		ParentchainId::TargetA => Ok(GLOBAL_TARGET_A_ENCOINTER_WATCHER_COMPONENT.get()?),
+		ParentchainId::TargetB => Ok(GLOBAL_TARGET_B_ENCOINTER_WATCHER_COMPONENT.get()?),
+		ParentchainId::Integritee =>
+			Err(Error::Other("The Integritee parentchain is no personhood source".into())),
+	}
+}
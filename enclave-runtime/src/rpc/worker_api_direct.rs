@@ -20,9 +20,17 @@ use crate::{
 		generate_dcap_ra_extrinsic_from_quote_internal,
 		generate_ias_ra_extrinsic_from_der_cert_internal,
 	},
+	initialization::global_components::{
+		GLOBAL_ATTESTATION_HANDLER_COMPONENT, GLOBAL_ISSUED_BADGES_COMPONENT,
+		GLOBAL_PERSONHOOD_SOURCES_COMPONENT, GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT,
+		GLOBAL_TOP_POOL_PERSISTENCE_COMPONENT,
+	},
 	rpc::{
 		encointer_utils::fetch_reputation,
-		nostr_utils::{get_ts, send_nostr_events},
+		issued_badges::{IssuedBadge, PublishBadgeUpdates},
+		nostr_utils::{get_ts, nostr_issuer_keys, send_nostr_events},
+		personhood_metrics::{record_rpc_latency, relay_label, update_personhood_metric},
+		personhood_sources::select_source,
 	},
 	utils::get_validator_accessor_from_solo_or_parachain,
 };
@@ -31,36 +39,56 @@ use core::result::Result;
 use encointer_primitives::{
 	ceremonies::Reputation, communities::CommunityIdentifier, scheduler::CeremonyIndexType,
 };
+use ita_personhood::{EncointerSubject, PersonhoodSource, PersonhoodSubject};
 use ita_sgx_runtime::Runtime;
+use ita_stf::helpers::is_public_state_key;
 use itc_parentchain::light_client::{concurrent_access::ValidatorAccess, ExtrinsicSender};
+use itp_attestation_handler::{AttestationHandler, EnclaveAttestation};
+use itp_component_container::ComponentGetter;
+use itp_enclave_metrics::{PersonhoodOracleMetric, VerificationFailureReason};
 use itp_primitives_cache::{GetPrimitives, GLOBAL_PRIMITIVES_CACHE};
 use itp_rpc::RpcReturnValue;
-use itp_sgx_crypto::key_repository::AccessPubkey;
+use itp_settings::personhood_oracle::BADGE_VALIDITY;
+use itp_sgx_crypto::key_repository::{AccessKey, AccessPubkey};
 use itp_stf_executor::getter_executor::ExecuteGetter;
 use itp_stf_primitives::types::AccountId;
-use itp_top_pool_author::traits::AuthorApi;
-use itp_types::{DirectRequestStatus, Request, ShardIdentifier, H256};
-use itp_utils::{FromHexPrefixed, ToHexPrefixed};
-use its_primitives::types::block::SignedBlock;
-use its_sidechain::rpc_handler::{direct_top_pool_api, import_block_api};
+use itp_stf_state_observer::traits::ObserveState;
+use itp_top_pool_author::{persistence::PersistTopPool, traits::AuthorApi};
+use itp_types::{
+	parentchain::ParentchainId, DirectRequestStatus, Request, ShardIdentifier,
+	TrustedOperationStatus, H256,
+};
+use itp_utils::{hex::hex_encode, FromHexPrefixed, ToHexPrefixed};
+use its_primitives::{
+	traits::{Block as BlockTrait, Header as HeaderTrait},
+	types::{
+		block::{Block as SidechainBlock, SignedBlock},
+		read_proof::ReadProof,
+	},
+};
+use its_sidechain::{
+	rpc_handler::{direct_top_pool_api, import_block_api, read_proof_api},
+	state::{LastBlockExt, StateRoot, StateTrie},
+};
 use jsonrpc_core::{serde_json::json, IoHandler, Params, Value};
 use log::*;
 use nostr::{
-	key::FromSkStr,
 	nips::{
 		nip58,
 		nip58::{BadgeAward, BadgeDefinition, ImageDimensions},
 	},
 	prelude::{FromBech32, Secp256k1, XOnlyPublicKey},
-	Keys, Tag,
+	EventBuilder, Keys, Tag, Timestamp,
 };
 use sgx_crypto_helper::rsa3072::Rsa3072PubKey;
 use sp_runtime::OpaqueExtrinsic;
 use std::{
 	borrow::ToOwned,
-	format, str,
+	collections::{BTreeMap, HashMap},
+	format, slice, str,
 	string::{String, ToString},
-	sync::Arc,
+	sync::{Arc, SgxMutex as Mutex},
+	time::Instant,
 	vec::Vec,
 };
 
@@ -78,20 +106,25 @@ fn get_all_rpc_methods_string(io_handler: &IoHandler) -> String {
 	format!("methods: [{}]", method_string)
 }
 
-pub fn public_api_rpc_handler<Author, GetterExecutor, AccessShieldingKey>(
+pub fn public_api_rpc_handler<Author, GetterExecutor, AccessShieldingKey, ProveFn>(
 	top_pool_author: Arc<Author>,
 	getter_executor: Arc<GetterExecutor>,
 	shielding_key: Arc<AccessShieldingKey>,
+	prove_read: ProveFn,
 ) -> IoHandler
 where
 	Author: AuthorApi<H256, H256> + Send + Sync + 'static,
 	GetterExecutor: ExecuteGetter + Send + Sync + 'static,
 	AccessShieldingKey: AccessPubkey<KeyType = Rsa3072PubKey> + Send + Sync + 'static,
+	ProveFn: Fn(&ShardIdentifier, &[Vec<u8>]) -> Result<ReadProof, String> + Sync + Send + 'static,
 {
 	let io = IoHandler::new();
 
 	// Add direct TOP pool rpc methods
-	let mut io = direct_top_pool_api::add_top_pool_direct_rpc_methods(top_pool_author, io);
+	let io = direct_top_pool_api::add_top_pool_direct_rpc_methods(top_pool_author, io);
+
+	// state_getReadProof
+	let mut io = read_proof_api::add_read_proof_rpc_method(prove_read, io);
 
 	// author_getShieldingKey
 	let rsa_pubkey_name: &str = "author_getShieldingKey";
@@ -182,6 +215,21 @@ where
 		Ok(json!(json_value))
 	});
 
+	// author_getUnrestoredOperationStatus
+	let author_get_unrestored_operation_status: &str = "author_getUnrestoredOperationStatus";
+	io.add_sync_method(author_get_unrestored_operation_status, |params: Params| {
+		let json_value = match get_unrestored_operation_status_inner(params) {
+			Ok(status) => RpcReturnValue {
+				do_watch: false,
+				value: status.encode(),
+				status: DirectRequestStatus::Ok,
+			}
+			.to_hex(),
+			Err(error) => compute_hex_encoded_return_error(error.as_str()),
+		};
+		Ok(json!(json_value))
+	});
+
 	// attesteer_forward_dcap_quote
 	let attesteer_forward_dcap_quote: &str = "attesteer_forwardDcapQuote";
 	io.add_sync_method(attesteer_forward_dcap_quote, move |params: Params| {
@@ -214,9 +262,26 @@ where
 		Ok(json!(json_value))
 	});
 
+	// attesteer_getEnclaveAttestation
+	let attesteer_get_enclave_attestation: &str = "attesteer_getEnclaveAttestation";
+	io.add_sync_method(attesteer_get_enclave_attestation, |_: Params| {
+		let json_value = match get_enclave_attestation_inner() {
+			Ok(attestation) => RpcReturnValue {
+				do_watch: false,
+				value: attestation.encode(),
+				status: DirectRequestStatus::Ok,
+			}
+			.to_hex(),
+			Err(error) => compute_hex_encoded_return_error(error.as_str()),
+		};
+
+		Ok(json!(json_value))
+	});
+
 	// personhoodoracle_issueNostrBadge
 	let personhoodoracle_issue_nostr_badge: &str = "personhoodoracle_issueNostrBadge";
 	io.add_sync_method(personhoodoracle_issue_nostr_badge, move |params: Params| {
+		let timer_start = Instant::now();
 		let json_value = match issue_nostr_badge_inner(params) {
 			Ok(id) => RpcReturnValue {
 				do_watch: false,
@@ -226,6 +291,7 @@ where
 			.to_hex(),
 			Err(error) => compute_hex_encoded_return_error(error.as_str()),
 		};
+		record_rpc_latency(personhoodoracle_issue_nostr_badge, timer_start);
 
 		Ok(json!(json_value))
 	});
@@ -233,6 +299,7 @@ where
 	// personhoodoracle_fetchReputation
 	let personhoodoracle_fetch_reputation: &str = "personhoodoracle_fetchReputation";
 	io.add_sync_method(personhoodoracle_fetch_reputation, move |params: Params| {
+		let timer_start = Instant::now();
 		let json_value = match fetch_reputation_inner(params) {
 			Ok(val) => RpcReturnValue {
 				do_watch: false,
@@ -242,6 +309,7 @@ where
 			.to_hex(),
 			Err(error) => compute_hex_encoded_return_error(error.as_str()),
 		};
+		record_rpc_latency(personhoodoracle_fetch_reputation, timer_start);
 
 		Ok(json!(json_value))
 	});
@@ -295,6 +363,79 @@ fn execute_getter_inner<G: ExecuteGetter>(
 	Ok(getter_result)
 }
 
+/// State tries of the last sidechain block of each shard, so that the trie is only built once
+/// per block for all the read proofs requested.
+#[derive(Default)]
+pub struct StateTrieCache {
+	tries: Mutex<HashMap<ShardIdentifier, (H256, Arc<StateTrie>)>>,
+}
+
+impl StateTrieCache {
+	fn get(&self, shard: &ShardIdentifier, block_hash: &H256) -> Option<Arc<StateTrie>> {
+		let tries = self.tries.lock().ok()?;
+		tries
+			.get(shard)
+			.filter(|(cached_block_hash, _)| cached_block_hash == block_hash)
+			.map(|(_, trie)| trie.clone())
+	}
+
+	fn insert(&self, shard: ShardIdentifier, block_hash: H256, trie: Arc<StateTrie>) {
+		if let Ok(mut tries) = self.tries.lock() {
+			tries.insert(shard, (block_hash, trie));
+		}
+	}
+}
+
+/// Proves `keys` against the state root of the last sidechain block of `shard`.
+///
+/// Only public state can be proven, see [`is_public_state_key`], everything else has to be
+/// queried with trusted getters. Fails if the trie of the last block is not cached yet and the
+/// observed state no longer matches its root, i.e. the state was updated after the last block
+/// was produced or imported. The client should retry in that case.
+pub fn prove_read_inner<Observer>(
+	state_observer: &Observer,
+	trie_cache: &StateTrieCache,
+	shard: &ShardIdentifier,
+	keys: &[Vec<u8>],
+) -> Result<ReadProof, String>
+where
+	Observer: ObserveState,
+	Observer::StateType: StateRoot + LastBlockExt<SidechainBlock>,
+{
+	if let Some(key) = keys.iter().find(|key| !is_public_state_key(key)) {
+		return Err(format!(
+			"State key {} is not public, query it with a trusted getter",
+			hex_encode(key)
+		))
+	}
+
+	let (last_block, trie) = state_observer
+		.observe_state(shard, |state| {
+			let last_block = state
+				.get_last_block()
+				.ok_or_else(|| "No sidechain block has been imported yet".to_owned())?;
+			if let Some(trie) = trie_cache.get(shard, &last_block.hash()) {
+				return Ok((last_block, trie))
+			}
+
+			let trie = state.state_trie().map_err(|e| format!("{:?}", e))?;
+			if trie.root() != last_block.header().state_root() {
+				return Err("State changed since the last sidechain block, retry".to_owned())
+			}
+			let trie = Arc::new(trie);
+			trie_cache.insert(*shard, last_block.hash(), trie.clone());
+			Ok((last_block, trie))
+		})
+		.map_err(|e| format!("{:?}", e))??;
+
+	Ok(ReadProof {
+		block_hash: last_block.hash(),
+		block_number: last_block.header().block_number(),
+		state_root: trie.root(),
+		proof: trie.read_proof(keys).map_err(|e| format!("{:?}", e))?,
+	})
+}
+
 fn forward_dcap_quote_inner(params: Params) -> Result<OpaqueExtrinsic, String> {
 	let hex_encoded_params = params.parse::<Vec<String>>().map_err(|e| format!("{:?}", e))?;
 
@@ -349,6 +490,33 @@ fn attesteer_forward_ias_attestation_report_inner(
 	Ok(ext)
 }
 
+/// Final status of an operation that was pending when the enclave was shut down and could not
+/// be restored on startup. Its watcher's connection did not survive the restart.
+fn get_unrestored_operation_status_inner(
+	params: Params,
+) -> Result<Option<TrustedOperationStatus>, String> {
+	let hex_encoded_params = params.parse::<Vec<String>>().map_err(|e| format!("{:?}", e))?;
+	let hash = H256::from_hex(
+		hex_encoded_params.get(0).ok_or_else(|| "Missing operation hash".to_owned())?,
+	)
+	.map_err(|e| format!("{:?}", e))?;
+
+	Ok(GLOBAL_TOP_POOL_PERSISTENCE_COMPONENT
+		.get()
+		.map_err(|e| format!("{:?}", e))?
+		.status_of_unrestored_operation(&hash))
+}
+
+/// Our MRENCLAVE, the public keys we control and the evidence of our latest remote attestation,
+/// which binds these keys in its report data.
+fn get_enclave_attestation_inner() -> Result<EnclaveAttestation, String> {
+	GLOBAL_ATTESTATION_HANDLER_COMPONENT
+		.get()
+		.map_err(|e| format!("{:?}", e))?
+		.enclave_attestation()
+		.map_err(|e| format!("{:?}", e))
+}
+
 fn personhoodoracle_parse_params(
 	params: Params,
 ) -> Result<(CommunityIdentifier, CeremonyIndexType, AccountId), String> {
@@ -386,74 +554,227 @@ fn fetch_reputation_inner(params: Params) -> Result<Vec<Reputation>, String> {
 }
 
 fn issue_nostr_badge_inner(params: Params) -> Result<nostr::EventId, String> {
+	use VerificationFailureReason::*;
+
 	trace!("evaluating reputation to maybe issue a nostr badge");
 	// Check reputation first - will be change later to have the user submit their `ProofOfAttendance`
 
-	let reputations = fetch_reputation_inner(params.clone())?;
-	let verified_reputations = reputations.iter().filter(|rep| rep.is_verified()).count();
+	let (cid, cindex, account) = personhoodoracle_parse_params(params.clone())
+		.map_err(|e| verification_failure(InvalidParameters, e))?;
+	let subject = EncointerSubject { cid, cindex, account };
+	let attested = assess_badge_holder(&subject)?.ok_or_else(|| {
+		verification_failure(NoReputation, "The user does not have any reputation".to_string())
+	})?;
 
-	if verified_reputations == 0 {
-		return Err("The user does not have any reputation".to_string())
-	}
-
-	let hex_encoded_params =
-		params.clone().parse::<Vec<String>>().map_err(|e| format!("{:?}", e))?;
+	let hex_encoded_params = params
+		.parse::<Vec<String>>()
+		.map_err(|e| verification_failure(InvalidParameters, format!("{:?}", e)))?;
 
 	if hex_encoded_params.len() < 5 {
-		return Err(format!(
-			"Wrong number of arguments for Nostr badge request: {}, expected: {}",
-			hex_encoded_params.len(),
-			6
+		return Err(verification_failure(
+			InvalidParameters,
+			format!(
+				"Wrong number of arguments for Nostr badge request: {}, expected: {}",
+				hex_encoded_params.len(),
+				6
+			),
 		))
 	}
-	let (cid, cindex, account) = personhoodoracle_parse_params(params)?;
-
-	let nostr_pub_key =
-		itp_utils::hex::decode_hex(&hex_encoded_params[3]).map_err(|e| format!("{:?}", e))?;
-	let nostr_pub_key_str: String =
-		Decode::decode(&mut nostr_pub_key.as_slice()).map_err(|e| format!("{:?}", e))?;
-	let nostr_pub_key =
-		XOnlyPublicKey::from_bech32(&nostr_pub_key_str).map_err(|e| format!("{:?}", e))?;
+	let nostr_pub_key = itp_utils::hex::decode_hex(&hex_encoded_params[3])
+		.map_err(|e| verification_failure(InvalidParameters, format!("{:?}", e)))?;
+	let nostr_pub_key_str: String = Decode::decode(&mut nostr_pub_key.as_slice())
+		.map_err(|e| verification_failure(InvalidParameters, format!("{:?}", e)))?;
+	let nostr_pub_key = XOnlyPublicKey::from_bech32(&nostr_pub_key_str)
+		.map_err(|e| verification_failure(InvalidNostrKey, format!("{:?}", e)))?;
+
+	let nostr_relay_url = itp_utils::hex::decode_hex(&hex_encoded_params[4])
+		.map_err(|e| verification_failure(InvalidParameters, format!("{:?}", e)))?;
+	let nostr_relay_url: String = Decode::decode(&mut nostr_relay_url.as_slice())
+		.map_err(|e| verification_failure(InvalidParameters, format!("{:?}", e)))?;
+
+	let award_id = publish_nostr_badge(&attested, nostr_pub_key, &nostr_relay_url)
+		.map_err(|e| verification_failure(PublishFailed, e))?;
+
+	GLOBAL_ISSUED_BADGES_COMPONENT
+		.get()
+		.map_err(|e| format!("{:?}", e))?
+		.register(IssuedBadge {
+			subject,
+			parentchain_id: attested.parentchain_id,
+			nostr_pub_key,
+			relay_url: nostr_relay_url,
+			attestations: attested.attestations,
+			award_id,
+		});
+
+	Ok(award_id)
+}
 
-	let nostr_relay_url =
-		itp_utils::hex::decode_hex(&hex_encoded_params[4]).map_err(|e| format!("{:?}", e))?;
-	let nostr_relay_url: String =
-		Decode::decode(&mut nostr_relay_url.as_slice()).map_err(|e| format!("{:?}", e))?;
+/// A badge holder whose personhood a personhood source attested.
+struct AttestedPersonhood {
+	parentchain_id: ParentchainId,
+	attestations: u32,
+	/// Genesis hash of the parentchain the attestations have been verified on.
+	source: H256,
+}
 
-	// todo: derive from enclave signing key
-	let nostr_issuers_private_key =
-		"nsec1punh8ys9ewzjhuuzh6dtna2d72l8d2pc2cyuuh93g9h73uedg5nq9z4p7l".to_string();
+/// Assess the subject with the first personhood source that knows it.
+///
+/// Returns `None` if the subject's personhood is not attested, an error if it could not be
+/// assessed.
+fn assess_badge_holder(subject: &EncointerSubject) -> Result<Option<AttestedPersonhood>, String> {
+	let subject = PersonhoodSubject::Encointer(subject.clone());
+	let sources = GLOBAL_PERSONHOOD_SOURCES_COMPONENT.get().map_err(|e| format!("{:?}", e))?;
+	let personhood_source = match select_source(sources.as_slice(), &subject) {
+		Some(personhood_source) => personhood_source,
+		None => return Ok(None),
+	};
+	let assessment = personhood_source.assess(&subject).map_err(|e| format!("{:?}", e))?;
+	debug!("personhood assessment of {:?}: {:?}", subject.account(), assessment);
+
+	// The personhood has been attested on a known chain.
+	Ok(match assessment.source {
+		Some(source) if assessment.is_attested => Some(AttestedPersonhood {
+			parentchain_id: personhood_source.parentchain_id(),
+			attestations: assessment.attestations,
+			source,
+		}),
+		_ => None,
+	})
+}
 
-	let secp = Secp256k1::new();
-	let signer_key =
-		Keys::from_sk_str(&nostr_issuers_private_key, &secp).map_err(|e| format!("{:?}", e))?;
+/// Publish a badge for the attested personhood and award it to `nostr_pub_key`.
+fn publish_nostr_badge(
+	attested: &AttestedPersonhood,
+	nostr_pub_key: XOnlyPublicKey,
+	nostr_relay_url: &str,
+) -> Result<nostr::EventId, String> {
+	let signer_key = nostr_signer_key()?;
 
-	let badge_def = create_nostr_badge_definition(&signer_key, verified_reputations as u32);
+	let badge_def =
+		create_nostr_badge_definition(&signer_key, attested.attestations, &attested.source);
 	println!("prepared nostr badge definition");
 	debug!("  {:?}", badge_def);
-	// todo: set NIP-40 expiry timestamp
 	let award = create_nostr_badge_award(badge_def.clone(), nostr_pub_key, &signer_key);
-	println!("prepared nostr badge award for {}", nostr_pub_key_str);
+	println!("prepared nostr badge award for {}", nostr_pub_key);
 	debug!("  {:?}", award);
 	let badge_def = badge_def.into_event();
 	let award = award.into_event();
 	println!("sending to nostr relay at {}", nostr_relay_url);
 	let nostr_events = vec![badge_def, award.clone()];
-	send_nostr_events(nostr_events, &nostr_relay_url)
-		.map_err(|e| format!("Failed to send nostr events: {:?}", e))?;
-
-	let _temp_tuple = (cid, cindex, account);
+	let publish_result = send_nostr_events(nostr_events, nostr_relay_url);
+	update_personhood_metric(PersonhoodOracleMetric::RelayPublish(
+		relay_label(nostr_relay_url).to_string(),
+		publish_result.is_ok(),
+	));
+	publish_result.map_err(|e| format!("Failed to send nostr events: {:?}", e))?;
+	update_personhood_metric(PersonhoodOracleMetric::BadgeIssued(attested.attestations));
 
 	Ok(award.id)
 }
 
+fn nostr_signer_key() -> Result<Keys, String> {
+	let enclave_signer = GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT
+		.get()
+		.map_err(|e| format!("{:?}", e))?
+		.retrieve_key()
+		.map_err(|e| format!("Failed to retrieve the enclave signing key: {:?}", e))?;
+	nostr_issuer_keys(&enclave_signer)
+}
+
+/// Record a failed personhood verification and pass on the error message.
+fn verification_failure(reason: VerificationFailureReason, error_msg: String) -> String {
+	update_personhood_metric(PersonhoodOracleMetric::VerificationFailure(reason));
+	error_msg
+}
+
+/// Publishes the badge updates on the Nostr relays the badges have been issued to.
+pub struct NostrBadgePublisher;
+
+impl PublishBadgeUpdates for NostrBadgePublisher {
+	fn refresh(&self, badge: IssuedBadge, renew: bool) -> Option<IssuedBadge> {
+		let attested = match assess_badge_holder(&badge.subject) {
+			Ok(Some(attested)) => attested,
+			Ok(None) => {
+				self.delete(slice::from_ref(&badge), "The holder is no longer attested");
+				return None
+			},
+			Err(e) => {
+				warn!(
+					"Could not assess the holder of badge {:?}, keeping it: {}",
+					badge.award_id, e
+				);
+				return Some(badge)
+			},
+		};
+		if !renew && attested.attestations == badge.attestations {
+			return Some(badge)
+		}
+		match publish_nostr_badge(&attested, badge.nostr_pub_key, &badge.relay_url) {
+			Ok(award_id) => {
+				self.delete(slice::from_ref(&badge), "The badge has been renewed");
+				Some(IssuedBadge {
+					parentchain_id: attested.parentchain_id,
+					attestations: attested.attestations,
+					award_id,
+					..badge
+				})
+			},
+			Err(e) => {
+				warn!("Could not re-issue badge {:?}, keeping it: {}", badge.award_id, e);
+				Some(badge)
+			},
+		}
+	}
+
+	fn delete(&self, badges: &[IssuedBadge], reason: &str) {
+		let signer_key = match nostr_signer_key() {
+			Ok(signer_key) => signer_key,
+			Err(e) => {
+				error!("Could not delete {} badges: {}", badges.len(), e);
+				return
+			},
+		};
+		let mut award_ids_per_relay = BTreeMap::<&str, Vec<nostr::EventId>>::new();
+		for badge in badges {
+			award_ids_per_relay.entry(&badge.relay_url).or_default().push(badge.award_id);
+		}
+		for (relay_url, award_ids) in award_ids_per_relay {
+			let publish_result = create_nostr_deletion(award_ids, reason, &signer_key)
+				.and_then(|deletion| send_nostr_events(vec![deletion], relay_url));
+			update_personhood_metric(PersonhoodOracleMetric::RelayPublish(
+				relay_label(relay_url).to_string(),
+				publish_result.is_ok(),
+			));
+			if let Err(e) = publish_result {
+				// The awards expire anyway.
+				warn!("Could not delete badges on nostr relay {}: {}", relay_url, e);
+			}
+		}
+	}
+}
+
+/// Deletion request (NIP-09) for the given badge awards.
+fn create_nostr_deletion(
+	award_ids: Vec<nostr::EventId>,
+	reason: &str,
+	signer_key: &Keys,
+) -> Result<nostr::Event, String> {
+	let secp = Secp256k1::new();
+	EventBuilder::delete(award_ids, Some(reason))
+		.to_event_with_timestamp_with_secp(signer_key, get_ts(), &secp)
+		.map_err(|e| format!("{:?}", e))
+}
+
+/// Badge award that expires after [`BADGE_VALIDITY`] (NIP-40).
 fn create_nostr_badge_award(
 	badge_definition: BadgeDefinition,
 	awarded_pub_key: XOnlyPublicKey,
 	signer_key: &Keys,
 ) -> BadgeAward {
 	let badge_definition_event = badge_definition.into_event();
-	let awarded_keys = vec![Tag::PubKey(awarded_pub_key, None)];
+	let expiration = Timestamp::from(get_ts().as_u64() + BADGE_VALIDITY.as_secs());
+	let awarded_keys = vec![Tag::PubKey(awarded_pub_key, None), Tag::Expiration(expiration)];
 
 	let secp = Secp256k1::new();
 	let ts = get_ts();
@@ -461,17 +782,25 @@ fn create_nostr_badge_award(
 	nip58::BadgeAward::new(&badge_definition_event, awarded_keys, signer_key, ts, &secp).unwrap()
 }
 
-fn create_nostr_badge_definition(signer_key: &Keys, reputation: u32) -> BadgeDefinition {
+/// Badge definition for the given reputation, on the Encointer chain with the `source` genesis
+/// hash, as community identifiers are only unique per chain.
+fn create_nostr_badge_definition(
+	signer_key: &Keys,
+	reputation: u32,
+	source: &H256,
+) -> BadgeDefinition {
+	let source = hex_encode(source.as_bytes());
 	// Just for demo purposes, should be reworked
 	let builder =
-		nip58::BadgeDefinitionBuilder::new(format!("personhood_{}", reputation).to_owned());
+		nip58::BadgeDefinitionBuilder::new(format!("personhood_{}_{}", reputation, source));
 	let builder = builder
 		.name(format!("Personhood Confidence {}/5 Verified by Encointer (TESTING)", reputation))
 		.description(format!("This badge is only issued once every 10 days for reputables on the Encointer network who have attended proof of personhood cycles (see https://encointer.org). \
 			Each person owning an account bearing this badge has verifiably attended (in person) {} of the last 5 cycles. \
 			There can always ever be maximally as many 5-of-5 badges issued as there are human participants in encointer communities.\
 			For enhanced privacy, this badge is unlinkable to the account used on Encointer Network (Unlinked by Integritee's trusted execution environment oracle SDK: https://integritee.network).\
-			THE ISSUER OF THIS BADGE IS STILL RUNNING IN TEE TESTING MODE. DO NOT TRUST THIS BADGE JUST YET", reputation))
+			The attendance has been verified on the Encointer chain with genesis hash {}.\
+			THE ISSUER OF THIS BADGE IS STILL RUNNING IN TEE TESTING MODE. DO NOT TRUST THIS BADGE JUST YET", reputation, source))
 		.image("https://cdn.nostr.build/i/1e779aabd6fe190ca26f5211bab84a8f3642ed1d74f7cda70b73d96779b80f34.png".to_owned())
 		.image_dimensions(ImageDimensions(1024, 1024))
 		.thumbs(vec![
@@ -0,0 +1,523 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! ECALLs to hand over the sealed secrets of this enclave to its successor, i.e. an
+//! enclave build with a different MRENCLAVE.
+//!
+//! The successor writes a signed [`StateImportKey`], like for a state snapshot import. We
+//! only export our shielding key, state key and signing key to it, if the signer is a
+//! registered (i.e. remote attested) enclave, and its fingerprint is authorized for the
+//! shard on the Integritee parentchain, as the active or the pending (upgrade) enclave in
+//! the `EnclaveBridge::ShardConfigRegistry`.
+//!
+//! We sign the export with our enclave signing key. The successor in turn only imports
+//! secrets signed by a registered enclave, whose fingerprint is authorized for the shard.
+//!
+//! The successor needs to be restarted after the import. It can then read the state files
+//! of the predecessor, which are encrypted with the migrated state key, and it has to
+//! register again, as its enclave account is derived from the migrated signing key.
+
+use crate::{
+	error::{Error, Result},
+	initialization::global_components::{
+		GLOBAL_INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_SEAL, GLOBAL_OCALL_API_COMPONENT,
+		GLOBAL_SHIELDING_KEY_REPOSITORY_COMPONENT, GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT,
+		GLOBAL_STATE_KEY_REPOSITORY_COMPONENT,
+	},
+	utils::{random_aes_key, utf8_str_from_raw},
+};
+use codec::{Decode, Encode};
+use itc_parentchain::{
+	light_client::{LightClientSealing, LightClientState},
+	primitives::ParentchainId,
+};
+use itp_component_container::ComponentGetter;
+use itp_enclave_bridge_storage::{EnclaveBridgeStorage, EnclaveBridgeStorageKeys};
+use itp_ocall_api::{EnclaveAttestationOCallApi, EnclaveOnChainOCallApi};
+use itp_sgx_crypto::{
+	key_repository::{AccessKey, MutateKey},
+	Aes, ShieldingCryptoDecrypt, ShieldingCryptoEncrypt, StateCrypto,
+};
+use itp_stf_state_handler::state_snapshot_export::StateImportKey;
+use itp_teerex_storage::{TeeRexStorage, TeerexStorageKeys};
+use itp_types::{
+	AccountId, Enclave, EnclaveFingerprint, ShardIdentifier, UpgradableShardConfig, H256,
+};
+use log::*;
+use sgx_crypto_helper::rsa3072::{Rsa3072KeyPair, Rsa3072PubKey};
+use sgx_types::sgx_status_t;
+use sp_core::{blake2_256, ed25519, Pair};
+use sp_runtime::traits::Header as HeaderTrait;
+use std::{format, slice, string::ToString, vec::Vec};
+
+/// Sealed secrets of an enclave, as handed over to its successor.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct EnclaveSecrets {
+	/// JSON encoded RSA shielding key pair.
+	pub shielding_key: Vec<u8>,
+	pub state_key: Aes,
+	/// Seed of the ed25519 enclave signing key.
+	pub signing_key_seed: Vec<u8>,
+}
+
+/// Signing context of an [`EnclaveSecretsExport`].
+const ENCLAVE_SECRETS_EXPORT_CONTEXT: &[u8] = b"integritee/enclave-secrets-export";
+
+/// Encrypted [`EnclaveSecrets`], as written to the export file.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct EnclaveSecretsExport {
+	/// Shard for which the target enclave has been authorized.
+	pub shard: ShardIdentifier,
+	/// Signing key of the enclave the secrets are encrypted for.
+	pub target_enclave_signer: ed25519::Public,
+	/// Transport key, encrypted with the shielding key of the target enclave.
+	pub encrypted_transport_key: Vec<u8>,
+	/// Encoded secrets, encrypted with the transport key.
+	pub encrypted_secrets: Vec<u8>,
+	/// Signing key of the exporting (predecessor) enclave, as registered on the parentchain.
+	pub exporter_enclave_signer: ed25519::Public,
+	/// Signature of the `exporter_enclave_signer` over all the other fields.
+	pub signature: ed25519::Signature,
+}
+
+impl EnclaveSecretsExport {
+	/// Verify that the export was signed by the exporter enclave signer.
+	///
+	/// Does not check whether the signer is an authorized enclave, that is up to the caller.
+	pub fn verify_signature(&self) -> bool {
+		ed25519::Pair::verify(
+			&self.signature,
+			self.signing_payload(),
+			&self.exporter_enclave_signer,
+		)
+	}
+
+	fn signing_payload(&self) -> Vec<u8> {
+		(
+			ENCLAVE_SECRETS_EXPORT_CONTEXT,
+			self.shard,
+			self.target_enclave_signer,
+			blake2_256(&self.encrypted_transport_key),
+			blake2_256(&self.encrypted_secrets),
+			self.exporter_enclave_signer,
+		)
+			.encode()
+	}
+}
+
+/// Export our sealed secrets to the enclave of the import key at `import_key_path`, if it is
+/// authorized for `shard` on the Integritee parentchain, and write them to `export_path`.
+#[no_mangle]
+pub unsafe extern "C" fn export_sealed_secrets_to_file(
+	shard: *const u8,
+	shard_size: u32,
+	import_key_path: *const u8,
+	import_key_path_size: u32,
+	export_path: *const u8,
+	export_path_size: u32,
+) -> sgx_status_t {
+	if shard_size as usize != ShardIdentifier::len_bytes() {
+		error!("Invalid shard size: {}", shard_size);
+		return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+	}
+	let shard = ShardIdentifier::from_slice(slice::from_raw_parts(shard, shard_size as usize));
+
+	let paths = utf8_str_from_raw(import_key_path, import_key_path_size as usize).and_then(
+		|import_key_path| {
+			utf8_str_from_raw(export_path, export_path_size as usize)
+				.map(|export_path| (import_key_path, export_path))
+		},
+	);
+	let (import_key_path, export_path) = match paths {
+		Ok(p) => p,
+		Err(e) => {
+			error!("Invalid file path: {:?}", e);
+			return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+		},
+	};
+
+	if let Err(e) = export_sealed_secrets_internal(&shard, import_key_path, export_path) {
+		error!("Failed to export the sealed secrets for shard {:?}: {:?}", shard, e);
+		return e.into()
+	}
+
+	sgx_status_t::SGX_SUCCESS
+}
+
+/// Import and seal the secrets at `secrets_path`, which must have been exported to this enclave.
+#[no_mangle]
+pub unsafe extern "C" fn import_sealed_secrets_from_file(
+	secrets_path: *const u8,
+	secrets_path_size: u32,
+) -> sgx_status_t {
+	let secrets_path = match utf8_str_from_raw(secrets_path, secrets_path_size as usize) {
+		Ok(p) => p,
+		Err(e) => {
+			error!("Invalid secrets path: {:?}", e);
+			return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+		},
+	};
+
+	if let Err(e) = import_sealed_secrets_internal(secrets_path) {
+		error!("Failed to import the sealed secrets: {:?}", e);
+		return e.into()
+	}
+
+	sgx_status_t::SGX_SUCCESS
+}
+
+fn export_sealed_secrets_internal(
+	shard: &ShardIdentifier,
+	import_key_path: &str,
+	export_path: &str,
+) -> Result<()> {
+	let import_key = StateImportKey::decode(&mut itp_sgx_io::read(import_key_path)?.as_slice())?;
+	if !import_key.verify_signature() {
+		return Err(Error::Other("Invalid signature of the state import key".to_string().into()))
+	}
+	let authorization = fetch_upgrade_authorization(&import_key.enclave_signer, shard)?;
+	ensure_upgrade_is_authorized(
+		&authorization.shard_config,
+		&authorization.self_fingerprint,
+		&authorization.enclave_fingerprint,
+	)?;
+
+	let target_shielding_key: Rsa3072PubKey =
+		serde_json::from_slice(&import_key.shielding_key).map_err(|e| Error::Other(e.into()))?;
+
+	let shielding_key = GLOBAL_SHIELDING_KEY_REPOSITORY_COMPONENT.get()?.retrieve_key()?;
+	let signer = GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT.get()?.retrieve_key()?;
+	let secrets = EnclaveSecrets {
+		shielding_key: serde_json::to_vec(&shielding_key).map_err(|e| Error::Other(e.into()))?,
+		state_key: GLOBAL_STATE_KEY_REPOSITORY_COMPONENT.get()?.retrieve_key()?,
+		signing_key_seed: signer.seed().to_vec(),
+	};
+
+	let export = encrypt_secrets(
+		&secrets,
+		*shard,
+		&signer,
+		import_key.enclave_signer,
+		&target_shielding_key,
+		random_aes_key()?,
+	)?;
+
+	itp_sgx_io::write(&export.encode(), export_path)?;
+	info!(
+		"Exported the sealed secrets to enclave {:?}, authorized for shard {:?}",
+		import_key.enclave_signer, shard
+	);
+	Ok(())
+}
+
+fn import_sealed_secrets_internal(secrets_path: &str) -> Result<()> {
+	let export = EnclaveSecretsExport::decode(&mut itp_sgx_io::read(secrets_path)?.as_slice())?;
+
+	let signing_key_repository = GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT.get()?;
+	let signer = signing_key_repository.retrieve_key()?;
+	if export.target_enclave_signer != signer.public() {
+		return Err(Error::Other(
+			format!(
+				"Secrets were exported for enclave {:?}, not for us",
+				export.target_enclave_signer
+			)
+			.into(),
+		))
+	}
+
+	if !export.verify_signature() {
+		return Err(Error::Other("Invalid signature of the secrets export".to_string().into()))
+	}
+	// The predecessor must still be authorized for the shard, otherwise anyone with our
+	// public shielding key could make us replace our keys with ones they know.
+	let authorization =
+		fetch_upgrade_authorization(&export.exporter_enclave_signer, &export.shard)?;
+	ensure_upgrade_is_authorized(
+		&authorization.shard_config,
+		&authorization.enclave_fingerprint,
+		&authorization.self_fingerprint,
+	)?;
+
+	let shielding_key_repository = GLOBAL_SHIELDING_KEY_REPOSITORY_COMPONENT.get()?;
+	let secrets = decrypt_secrets(export, &shielding_key_repository.retrieve_key()?)?;
+
+	let shielding_key: Rsa3072KeyPair =
+		serde_json::from_slice(&secrets.shielding_key).map_err(|e| Error::Other(e.into()))?;
+	let signing_key = ed25519::Pair::from_seed_slice(&secrets.signing_key_seed)
+		.map_err(|e| Error::Other(format!("Invalid signing key seed: {:?}", e).into()))?;
+
+	GLOBAL_STATE_KEY_REPOSITORY_COMPONENT.get()?.update_key(secrets.state_key)?;
+	shielding_key_repository.update_key(shielding_key)?;
+	signing_key_repository.update_key(signing_key.clone())?;
+
+	info!(
+		"Imported the sealed secrets of enclave {:?}. Restart the worker to use them",
+		signing_key.public()
+	);
+	Ok(())
+}
+
+/// Encrypt `secrets` with `transport_key`, which in turn is encrypted with the (previously
+/// verified) shielding key of the target enclave. The export is signed with `exporter_signer`.
+pub fn encrypt_secrets<ShieldingKey: ShieldingCryptoEncrypt>(
+	secrets: &EnclaveSecrets,
+	shard: ShardIdentifier,
+	exporter_signer: &ed25519::Pair,
+	target_enclave_signer: ed25519::Public,
+	target_shielding_key: &ShieldingKey,
+	transport_key: Aes,
+) -> Result<EnclaveSecretsExport> {
+	let mut encrypted_secrets = secrets.encode();
+	transport_key.encrypt(&mut encrypted_secrets)?;
+
+	let encrypted_transport_key = target_shielding_key
+		.encrypt(&transport_key.encode())
+		.map_err(|e| Error::Other(format!("{:?}", e).into()))?;
+
+	let mut export = EnclaveSecretsExport {
+		shard,
+		target_enclave_signer,
+		encrypted_transport_key,
+		encrypted_secrets,
+		exporter_enclave_signer: exporter_signer.public(),
+		signature: ed25519::Signature::from_raw([0u8; 64]),
+	};
+	export.signature = exporter_signer.sign(&export.signing_payload());
+	Ok(export)
+}
+
+/// Decrypt the secrets of an export with our own `shielding_key`, if it is signed by its
+/// exporter.
+pub fn decrypt_secrets<ShieldingKey: ShieldingCryptoDecrypt>(
+	export: EnclaveSecretsExport,
+	shielding_key: &ShieldingKey,
+) -> Result<EnclaveSecrets> {
+	if !export.verify_signature() {
+		return Err(Error::Other("Invalid signature of the secrets export".to_string().into()))
+	}
+
+	let transport_key = shielding_key
+		.decrypt(&export.encrypted_transport_key)
+		.map_err(|e| Error::Other(format!("{:?}", e).into()))?;
+	let transport_key = Aes::decode(&mut transport_key.as_slice())?;
+
+	let mut secrets = export.encrypted_secrets;
+	transport_key.decrypt(&mut secrets)?;
+	Ok(EnclaveSecrets::decode(&mut secrets.as_slice())?)
+}
+
+/// Fingerprints and shard config, to check whether an upgrade is authorized.
+struct UpgradeAuthorization {
+	shard_config: UpgradableShardConfig,
+	self_fingerprint: EnclaveFingerprint,
+	/// Fingerprint of the registered peer enclave.
+	enclave_fingerprint: EnclaveFingerprint,
+}
+
+/// Fetch the fingerprint of the registered `enclave_signer` and the config of `shard` from the
+/// Integritee parentchain, as of our latest finalized header.
+fn fetch_upgrade_authorization(
+	enclave_signer: &ed25519::Public,
+	shard: &ShardIdentifier,
+) -> Result<UpgradeAuthorization> {
+	let ocall_api = GLOBAL_OCALL_API_COMPONENT.get()?;
+	let latest_header = GLOBAL_INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_SEAL
+		.get()?
+		.unseal()?
+		.latest_finalized_header()?;
+
+	let registered_enclave: Enclave = ocall_api
+		.get_storage_verified(
+			TeeRexStorage::sovereign_enclaves(AccountId::from(*enclave_signer)),
+			&latest_header,
+			&ParentchainId::Integritee,
+		)?
+		.into_tuple()
+		.1
+		.ok_or_else(|| {
+			Error::Other(format!("Enclave {:?} is not registered", enclave_signer).into())
+		})?;
+
+	let shard_config = fetch_shard_config(&*ocall_api, &latest_header, shard)?;
+
+	Ok(UpgradeAuthorization {
+		shard_config,
+		self_fingerprint: EnclaveFingerprint::from(ocall_api.get_mrenclave_of_self()?.m),
+		enclave_fingerprint: registered_enclave.fingerprint(),
+	})
+}
+
+/// Fetch the MRENCLAVEs of the enclaves authorized for `shard` from the Integritee
+/// parentchain, as of our latest finalized header.
+pub fn fetch_authorized_mrenclaves(shard: &ShardIdentifier) -> Result<Vec<[u8; 32]>> {
+	let ocall_api = GLOBAL_OCALL_API_COMPONENT.get()?;
+	let latest_header = GLOBAL_INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_SEAL
+		.get()?
+		.unseal()?
+		.latest_finalized_header()?;
+
+	let shard_config = fetch_shard_config(&*ocall_api, &latest_header, shard)?;
+	Ok(authorized_fingerprints(&shard_config).into_iter().map(Into::into).collect())
+}
+
+fn fetch_shard_config<OCallApi: EnclaveOnChainOCallApi, Header: HeaderTrait<Hash = H256>>(
+	ocall_api: &OCallApi,
+	latest_header: &Header,
+	shard: &ShardIdentifier,
+) -> Result<UpgradableShardConfig> {
+	ocall_api
+		.get_storage_verified(
+			EnclaveBridgeStorage::shard_config_registry(shard),
+			latest_header,
+			&ParentchainId::Integritee,
+		)?
+		.into_tuple()
+		.1
+		.ok_or_else(|| {
+			Error::Other(format!("No shard config registered for shard {:?}", shard).into())
+		})
+}
+
+/// Fingerprints of the authorized enclaves of the shard config, i.e. of the active one, and of
+/// the pending upgrade.
+fn authorized_fingerprints(shard_config: &UpgradableShardConfig) -> Vec<EnclaveFingerprint> {
+	core::iter::once(&shard_config.active_config)
+		.chain(shard_config.pending_upgrade.iter())
+		.map(|config| config.enclave_fingerprint)
+		.collect()
+}
+
+/// Ensure both the exporting enclave and the target enclave are authorized enclaves of the
+/// shard config, i.e. either the active one, or the pending upgrade.
+pub fn ensure_upgrade_is_authorized(
+	shard_config: &UpgradableShardConfig,
+	exporter_fingerprint: &EnclaveFingerprint,
+	target_fingerprint: &EnclaveFingerprint,
+) -> Result<()> {
+	let authorized_fingerprints = authorized_fingerprints(shard_config);
+
+	if !authorized_fingerprints.contains(exporter_fingerprint) {
+		return Err(Error::Other(
+			format!("Enclave {:?} is not authorized for the shard anymore", exporter_fingerprint)
+				.into(),
+		))
+	}
+	if !authorized_fingerprints.contains(target_fingerprint) {
+		return Err(Error::Other(
+			format!("Enclave {:?} is not an authorized upgrade", target_fingerprint).into(),
+		))
+	}
+	Ok(())
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+	use super::*;
+	use itp_types::ShardConfig;
+
+	fn shard_config(
+		active: EnclaveFingerprint,
+		pending: Option<EnclaveFingerprint>,
+	) -> UpgradableShardConfig {
+		let config = |enclave_fingerprint| ShardConfig {
+			enclave_fingerprint,
+			max_instances: None,
+			authorities: None,
+			maintenance_mode: false,
+		};
+		UpgradableShardConfig {
+			active_config: config(active),
+			pending_upgrade: pending.map(config),
+			upgrade_at: pending.map(|_| 42),
+		}
+	}
+
+	pub fn upgrade_to_pending_enclave_is_authorized() {
+		let old = EnclaveFingerprint::repeat_byte(1);
+		let new = EnclaveFingerprint::repeat_byte(2);
+
+		assert!(ensure_upgrade_is_authorized(&shard_config(old, Some(new)), &old, &new).is_ok());
+		// The upgrade has already been enacted.
+		assert!(ensure_upgrade_is_authorized(&shard_config(new, None), &new, &new).is_ok());
+	}
+
+	pub fn upgrade_to_unauthorized_enclave_fails() {
+		let old = EnclaveFingerprint::repeat_byte(1);
+		let new = EnclaveFingerprint::repeat_byte(2);
+		let rogue = EnclaveFingerprint::repeat_byte(3);
+
+		assert!(ensure_upgrade_is_authorized(&shard_config(old, Some(new)), &old, &rogue).is_err());
+		assert!(ensure_upgrade_is_authorized(&shard_config(old, None), &old, &new).is_err());
+		// An enclave that has been replaced must not hand out its secrets anymore.
+		assert!(ensure_upgrade_is_authorized(&shard_config(new, None), &rogue, &new).is_err());
+	}
+
+	pub fn encrypted_secrets_can_only_be_decrypted_by_target() {
+		let target_shielding_key = Rsa3072KeyPair::new().unwrap();
+		let other_shielding_key = Rsa3072KeyPair::new().unwrap();
+		let secrets = EnclaveSecrets {
+			shielding_key: serde_json::to_vec(&Rsa3072KeyPair::new().unwrap()).unwrap(),
+			state_key: Aes::new([3u8; 16], [0u8; 16]),
+			signing_key_seed: [7u8; 32].to_vec(),
+		};
+
+		let export = encrypt_secrets(
+			&secrets,
+			ShardIdentifier::default(),
+			&ed25519::Pair::from_seed(&[2u8; 32]),
+			ed25519::Public::from_raw([1u8; 32]),
+			&target_shielding_key,
+			Aes::new([5u8; 16], [6u8; 16]),
+		)
+		.unwrap();
+
+		assert!(!export.encrypted_secrets.is_empty());
+		assert_ne!(export.encrypted_secrets, secrets.encode());
+		assert!(decrypt_secrets(export.clone(), &other_shielding_key).is_err());
+		assert_eq!(decrypt_secrets(export, &target_shielding_key).unwrap(), secrets);
+	}
+
+	pub fn secrets_with_invalid_signature_are_rejected() {
+		let target_shielding_key = Rsa3072KeyPair::new().unwrap();
+		let secrets = EnclaveSecrets {
+			shielding_key: serde_json::to_vec(&Rsa3072KeyPair::new().unwrap()).unwrap(),
+			state_key: Aes::new([3u8; 16], [0u8; 16]),
+			signing_key_seed: [7u8; 32].to_vec(),
+		};
+		let export = encrypt_secrets(
+			&secrets,
+			ShardIdentifier::default(),
+			&ed25519::Pair::from_seed(&[2u8; 32]),
+			ed25519::Public::from_raw([1u8; 32]),
+			&target_shielding_key,
+			Aes::new([5u8; 16], [6u8; 16]),
+		)
+		.unwrap();
+		assert!(export.verify_signature());
+
+		// Secrets encrypted by someone else, e.g. the host, to our public shielding key.
+		let forged = EnclaveSecretsExport {
+			exporter_enclave_signer: ed25519::Public::from_raw([9u8; 32]),
+			..export.clone()
+		};
+		assert!(decrypt_secrets(forged, &target_shielding_key).is_err());
+
+		let mut tampered = export;
+		tampered.encrypted_secrets[0] ^= 1;
+		assert!(decrypt_secrets(tampered, &target_shielding_key).is_err());
+	}
+}
@@ -0,0 +1,87 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Encryption of sidechain block state diffs for the auditor registered on the parentchain.
+
+use crate::utils::random_aes_key;
+use itp_enclave_bridge_storage::{EnclaveBridgeStorage, EnclaveBridgeStorageKeys};
+use itp_ocall_api::EnclaveOnChainOCallApi;
+use itp_types::{parentchain::ParentchainId, ShardIdentifier, H256};
+use its_sidechain::{
+	block_composer::{
+		auditor::{encrypt_state_payload_for_auditor, EncryptForAuditor},
+		error::{Error, Result},
+	},
+	primitives::types::block_data::AuditorStateDiff,
+};
+use log::*;
+use sgx_crypto_helper::rsa3072::Rsa3072PubKey;
+use sp_runtime::traits::Header as HeaderTrait;
+use std::{format, sync::Arc, vec::Vec};
+
+/// Looks up the auditor key of a shard in the verified parentchain storage and encrypts
+/// the state payload for it.
+///
+/// The auditor key is stored as JSON encoded RSA 3072 public key.
+pub struct ParentchainAuditorEncryption<OCallApi> {
+	ocall_api: Arc<OCallApi>,
+}
+
+impl<OCallApi> ParentchainAuditorEncryption<OCallApi> {
+	pub fn new(ocall_api: Arc<OCallApi>) -> Self {
+		Self { ocall_api }
+	}
+}
+
+impl<OCallApi, ParentchainHeader> EncryptForAuditor<ParentchainHeader>
+	for ParentchainAuditorEncryption<OCallApi>
+where
+	OCallApi: EnclaveOnChainOCallApi,
+	ParentchainHeader: HeaderTrait<Hash = H256>,
+{
+	fn encrypt_for_auditor(
+		&self,
+		shard: &ShardIdentifier,
+		parentchain_header: &ParentchainHeader,
+		state_payload: &[u8],
+	) -> Result<Option<AuditorStateDiff>> {
+		let maybe_auditor_key: Option<Vec<u8>> = self
+			.ocall_api
+			.get_storage_verified(
+				EnclaveBridgeStorage::shard_auditor(shard),
+				parentchain_header,
+				&ParentchainId::Integritee,
+			)
+			.map_err(|e| Error::Other(format!("Failed to fetch shard auditor: {:?}", e).into()))?
+			.into_tuple()
+			.1;
+
+		let auditor_key_json = match maybe_auditor_key {
+			Some(key) => key,
+			None => return Ok(None),
+		};
+
+		let auditor_key: Rsa3072PubKey = serde_json::from_slice(&auditor_key_json)
+			.map_err(|e| Error::Other(format!("Invalid shard auditor key: {:?}", e).into()))?;
+
+		let state_key = random_aes_key()
+			.map_err(|e| Error::Other(format!("Failed to create state key: {:?}", e).into()))?;
+
+		trace!("Encrypting state diff of shard {:?} for auditor", shard);
+		encrypt_state_payload_for_auditor(&auditor_key, state_key, state_payload).map(Some)
+	}
+}
@@ -0,0 +1,307 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! ECALLs to export and import encrypted shard state snapshots, without a mutual-RA
+//! connection to a live peer.
+//!
+//! The target enclave writes a signed [`StateImportKey`]. The exporting enclave only
+//! encrypts a snapshot to it, if the signer is a registered (i.e. remote attested)
+//! enclave with our MRENCLAVE on the Integritee parentchain. Vice versa, the target enclave
+//! only imports a snapshot signed by a registered enclave with its MRENCLAVE.
+
+use crate::{
+	error::{Error, Result},
+	initialization::global_components::{
+		GLOBAL_INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_SEAL, GLOBAL_OCALL_API_COMPONENT,
+		GLOBAL_SHIELDING_KEY_REPOSITORY_COMPONENT, GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT,
+		GLOBAL_STATE_HANDLER_COMPONENT,
+	},
+	utils::{random_aes_key, utf8_str_from_raw, DecodeRaw},
+};
+use codec::{Decode, Encode};
+use ita_stf::State as StfState;
+use itc_parentchain::{
+	light_client::{LightClientSealing, LightClientState},
+	primitives::ParentchainId,
+};
+use itp_component_container::ComponentGetter;
+use itp_ocall_api::{EnclaveAttestationOCallApi, EnclaveOnChainOCallApi};
+use itp_sgx_crypto::key_repository::{AccessKey, AccessPubkey};
+use itp_sgx_externalities::SgxExternalitiesTrait;
+use itp_stf_state_handler::{
+	error::{Error as StateHandlerError, Result as StateHandlerResult},
+	handle_state::HandleState,
+	state_snapshot_export::{
+		export_state_snapshot, import_state_snapshot, StateImportKey, StateSnapshotExport,
+	},
+};
+use itp_storage::storage_value_key;
+use itp_teerex_storage::{TeeRexStorage, TeerexStorageKeys};
+use itp_types::{AccountId, BlockNumber, Enclave, EnclaveFingerprint, ShardIdentifier, H256};
+use its_primitives::{
+	traits::{Block as BlockTrait, Header as HeaderTrait},
+	types::block::Block as SidechainBlock,
+};
+use its_sidechain::state::LastBlockExt;
+use log::*;
+use sgx_crypto_helper::rsa3072::Rsa3072PubKey;
+use sgx_types::sgx_status_t;
+use sp_core::{ed25519, Pair};
+use std::{format, slice, string::ToString};
+
+/// Write our signed shielding key to `import_key_path`, so that a peer can export a state
+/// snapshot to this enclave.
+#[no_mangle]
+pub unsafe extern "C" fn write_state_import_key(
+	import_key_path: *const u8,
+	import_key_path_size: u32,
+) -> sgx_status_t {
+	let import_key_path = match utf8_str_from_raw(import_key_path, import_key_path_size as usize) {
+		Ok(p) => p,
+		Err(e) => {
+			error!("Invalid import key path: {:?}", e);
+			return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+		},
+	};
+
+	if let Err(e) = write_state_import_key_internal(import_key_path) {
+		error!("Failed to write the state import key: {:?}", e);
+		return e.into()
+	}
+
+	sgx_status_t::SGX_SUCCESS
+}
+
+/// Export the state of `shard` at the encoded `Option<H256>` state hash (the current state,
+/// if `None`), encrypted to the enclave of the import key
+/// at `import_key_path`, and write it to `export_path`.
+#[no_mangle]
+pub unsafe extern "C" fn export_state_snapshot_to_file(
+	shard: *const u8,
+	shard_size: u32,
+	maybe_state_hash: *const u8,
+	maybe_state_hash_size: u32,
+	import_key_path: *const u8,
+	import_key_path_size: u32,
+	export_path: *const u8,
+	export_path_size: u32,
+) -> sgx_status_t {
+	if shard_size as usize != ShardIdentifier::len_bytes() {
+		error!("Invalid shard size: {}", shard_size);
+		return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+	}
+	let shard = ShardIdentifier::from_slice(slice::from_raw_parts(shard, shard_size as usize));
+	let maybe_state_hash =
+		match Option::<H256>::decode_raw(maybe_state_hash, maybe_state_hash_size as usize) {
+			Ok(h) => h,
+			Err(e) => {
+				error!("Failed to decode state hash: {:?}", e);
+				return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+			},
+		};
+
+	let paths = utf8_str_from_raw(import_key_path, import_key_path_size as usize).and_then(
+		|import_key_path| {
+			utf8_str_from_raw(export_path, export_path_size as usize)
+				.map(|export_path| (import_key_path, export_path))
+		},
+	);
+	let (import_key_path, export_path) = match paths {
+		Ok(p) => p,
+		Err(e) => {
+			error!("Invalid file path: {:?}", e);
+			return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+		},
+	};
+
+	if let Err(e) =
+		export_state_snapshot_internal(&shard, maybe_state_hash, import_key_path, export_path)
+	{
+		error!("Failed to export state snapshot of shard {:?}: {:?}", shard, e);
+		return e.into()
+	}
+
+	sgx_status_t::SGX_SUCCESS
+}
+
+/// Import the state snapshot at `snapshot_path`, which must have been exported to this enclave.
+#[no_mangle]
+pub unsafe extern "C" fn import_state_snapshot_from_file(
+	snapshot_path: *const u8,
+	snapshot_path_size: u32,
+) -> sgx_status_t {
+	let snapshot_path = match utf8_str_from_raw(snapshot_path, snapshot_path_size as usize) {
+		Ok(p) => p,
+		Err(e) => {
+			error!("Invalid snapshot path: {:?}", e);
+			return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+		},
+	};
+
+	if let Err(e) = import_state_snapshot_internal(snapshot_path) {
+		error!("Failed to import state snapshot: {:?}", e);
+		return e.into()
+	}
+
+	sgx_status_t::SGX_SUCCESS
+}
+
+fn write_state_import_key_internal(import_key_path: &str) -> Result<()> {
+	let signer = GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT.get()?.retrieve_key()?;
+	let shielding_pubkey = GLOBAL_SHIELDING_KEY_REPOSITORY_COMPONENT.get()?.retrieve_pubkey()?;
+	let shielding_pubkey_json =
+		serde_json::to_vec(&shielding_pubkey).map_err(|e| Error::Other(e.into()))?;
+
+	let import_key = StateImportKey::new_signed(&signer, shielding_pubkey_json);
+	itp_sgx_io::write(&import_key.encode(), import_key_path)?;
+	info!("Wrote state import key of enclave {:?} to {}", signer.public(), import_key_path);
+	Ok(())
+}
+
+fn export_state_snapshot_internal(
+	shard: &ShardIdentifier,
+	maybe_state_hash: Option<H256>,
+	import_key_path: &str,
+	export_path: &str,
+) -> Result<()> {
+	let import_key = StateImportKey::decode(&mut itp_sgx_io::read(import_key_path)?.as_slice())?;
+	if !import_key.verify_signature() {
+		return Err(Error::Other("Invalid signature of the state import key".to_string().into()))
+	}
+	ensure_is_registered_with_our_mrenclave(&import_key.enclave_signer)?;
+
+	let target_shielding_key: Rsa3072PubKey =
+		serde_json::from_slice(&import_key.shielding_key).map_err(|e| Error::Other(e.into()))?;
+
+	let signer = GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT.get()?.retrieve_key()?;
+	let state_handler = GLOBAL_STATE_HANDLER_COMPONENT.get()?;
+	let state_hash = match maybe_state_hash {
+		Some(state_hash) => state_hash,
+		None => state_handler.execute_on_current(shard, |_, state_hash| state_hash)?,
+	};
+	let snapshot = export_state_snapshot(
+		state_handler.as_ref(),
+		shard,
+		&state_hash,
+		&signer,
+		import_key.enclave_signer,
+		&target_shielding_key,
+		random_aes_key()?,
+	)?;
+
+	itp_sgx_io::write(&snapshot.encode(), export_path)?;
+	Ok(())
+}
+
+fn import_state_snapshot_internal(snapshot_path: &str) -> Result<()> {
+	let snapshot = StateSnapshotExport::decode(&mut itp_sgx_io::read(snapshot_path)?.as_slice())?;
+
+	let signer = GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT.get()?.retrieve_key()?;
+	if snapshot.target_enclave_signer != signer.public() {
+		return Err(Error::Other(
+			format!(
+				"State snapshot was exported for enclave {:?}, not for us",
+				snapshot.target_enclave_signer
+			)
+			.into(),
+		))
+	}
+
+	// The signature itself is checked on import, before anything is decrypted.
+	ensure_is_registered_with_our_mrenclave(&snapshot.exporter_enclave_signer)?;
+
+	let shielding_key = GLOBAL_SHIELDING_KEY_REPOSITORY_COMPONENT.get()?.retrieve_key()?;
+	let state_handler = GLOBAL_STATE_HANDLER_COMPONENT.get()?;
+	import_state_snapshot(
+		state_handler.as_ref(),
+		snapshot,
+		&shielding_key,
+		ensure_is_ahead_of_current_state,
+	)?;
+	Ok(())
+}
+
+/// Ensure a snapshot state is ahead of the current state of its shard, in both its last
+/// sidechain block and its last imported parentchain block. Anything is ahead of what the
+/// current state does not have yet, so an empty shard can always be restored.
+fn ensure_is_ahead_of_current_state(
+	snapshot_state: &StfState,
+	current_state: &StfState,
+) -> StateHandlerResult<()> {
+	let sidechain_block_number = |state: &StfState| {
+		state
+			.get_last_block()
+			.map(|block: SidechainBlock| block.header().block_number())
+	};
+	if let Some(current_number) = sidechain_block_number(current_state) {
+		let snapshot_number = sidechain_block_number(snapshot_state);
+		if snapshot_number <= Some(current_number) {
+			return Err(StateHandlerError::OutdatedSnapshot(format!(
+				"sidechain block {:?} is not after the current block {}",
+				snapshot_number, current_number
+			)))
+		}
+	}
+
+	if let Some(current_number) = parentchain_block_number(current_state) {
+		let snapshot_number = parentchain_block_number(snapshot_state);
+		if snapshot_number <= Some(current_number) {
+			return Err(StateHandlerError::OutdatedSnapshot(format!(
+				"parentchain block {:?} is not after the current block {}",
+				snapshot_number, current_number
+			)))
+		}
+	}
+	Ok(())
+}
+
+/// Number of the last parentchain block imported into `state`.
+pub(crate) fn parentchain_block_number(state: &StfState) -> Option<BlockNumber> {
+	state
+		.get(&storage_value_key("Parentchain", "Number"))
+		.and_then(|number| BlockNumber::decode(&mut number.as_slice()).ok())
+}
+
+/// Ensure `enclave_signer` is an enclave that is registered on the Integritee parentchain
+/// (as of our latest finalized header) and runs the same code as we do.
+fn ensure_is_registered_with_our_mrenclave(enclave_signer: &ed25519::Public) -> Result<()> {
+	let ocall_api = GLOBAL_OCALL_API_COMPONENT.get()?;
+	let latest_header = GLOBAL_INTEGRITEE_PARENTCHAIN_LIGHT_CLIENT_SEAL
+		.get()?
+		.unseal()?
+		.latest_finalized_header()?;
+
+	let registered_enclave: Enclave = ocall_api
+		.get_storage_verified(
+			TeeRexStorage::sovereign_enclaves(AccountId::from(*enclave_signer)),
+			&latest_header,
+			&ParentchainId::Integritee,
+		)?
+		.into_tuple()
+		.1
+		.ok_or_else(|| {
+			Error::Other(format!("Enclave {:?} is not registered", enclave_signer).into())
+		})?;
+
+	let self_mrenclave = ocall_api.get_mrenclave_of_self()?;
+	if registered_enclave.fingerprint() != EnclaveFingerprint::from(self_mrenclave.m) {
+		return Err(Error::Other(
+			format!("Enclave {:?} has a different MRENCLAVE than we do", enclave_signer).into(),
+		))
+	}
+	Ok(())
+}
@@ -0,0 +1,271 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Feed configuration of the teeracle, governed by the Teeracle pallet.
+//!
+//! The untrusted worker only proposes candidate data sources, read from the pallet's
+//! `Whitelists`. Every candidate is checked against verified parentchain storage before the
+//! teeracle serves it. The trading pairs of a data source are read from the pallet's
+//! `TradingPairs` configuration, so governance can add a pair before any rate was reported.
+
+use crate::error::Result;
+use ita_oracle::types::TradingPair;
+use itp_ocall_api::{EnclaveAttestationOCallApi, EnclaveOnChainOCallApi};
+use itp_settings::teeracle::MAX_EXCHANGE_RATE_FEEDS;
+use itp_teeracle_storage::{
+	DataSource, ExchangeRateFeed, MrEnclave, TeeracleStorage, TeeracleStorageKeys,
+	TradingPairString,
+};
+use itp_types::parentchain::ParentchainId;
+use lazy_static::lazy_static;
+use log::*;
+use sp_core::H256;
+use sp_runtime::traits::Header as HeaderTrait;
+use std::{collections::BTreeSet, string::String, sync::SgxRwLock as RwLock, vec::Vec};
+
+lazy_static! {
+	static ref ACTIVE_FEED_CONFIG: RwLock<FeedConfig> = Default::default();
+}
+
+/// The (trading pair, data source) combinations the teeracle reports exchange rates for.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FeedConfig {
+	pub feeds: BTreeSet<ExchangeRateFeed>,
+}
+
+impl FeedConfig {
+	pub fn data_sources(&self) -> BTreeSet<DataSource> {
+		self.feeds.iter().map(|(_, data_source)| data_source.clone()).collect()
+	}
+
+	/// Feeds of `self` that are not part of `other`.
+	pub fn difference<'a>(&'a self, other: &'a FeedConfig) -> Vec<&'a ExchangeRateFeed> {
+		self.feeds.difference(&other.feeds).collect()
+	}
+}
+
+/// Reads the feed configuration from verified parentchain storage at `header`.
+///
+/// A candidate data source is served if the enclave supports it and its whitelist contains our
+/// MRENCLAVE, with the trading pairs configured for it. At most `MAX_EXCHANGE_RATE_FEEDS` feeds
+/// are served.
+pub fn read_feed_config<OCallApi, Header>(
+	ocall_api: &OCallApi,
+	header: &Header,
+	supported_sources: &[DataSource],
+	candidate_sources: &[DataSource],
+) -> Result<FeedConfig>
+where
+	OCallApi: EnclaveOnChainOCallApi + EnclaveAttestationOCallApi,
+	Header: HeaderTrait<Hash = H256>,
+{
+	let mrenclave: MrEnclave = ocall_api.get_mrenclave_of_self()?.m;
+
+	let mut feeds = BTreeSet::new();
+	for data_source in candidate_sources {
+		if !supported_sources.contains(data_source) {
+			debug!("Data source {} is not supported by the teeracle", display(data_source));
+			continue
+		}
+
+		let whitelist: Vec<MrEnclave> = ocall_api
+			.get_storage_verified(
+				TeeracleStorage::whitelist(data_source),
+				header,
+				&ParentchainId::Integritee,
+			)?
+			.into_tuple()
+			.1
+			.unwrap_or_default();
+
+		if !whitelist.contains(&mrenclave) {
+			debug!("Data source {} is not whitelisted for us", display(data_source));
+			continue
+		}
+
+		let trading_pairs: Vec<TradingPairString> = ocall_api
+			.get_storage_verified(
+				TeeracleStorage::trading_pairs(data_source),
+				header,
+				&ParentchainId::Integritee,
+			)?
+			.into_tuple()
+			.1
+			.unwrap_or_default();
+
+		if trading_pairs.is_empty() {
+			warn!("No trading pairs configured for data source {}", display(data_source));
+		}
+
+		for trading_pair in trading_pairs {
+			if feeds.len() >= MAX_EXCHANGE_RATE_FEEDS {
+				warn!(
+					"Ignoring {} from {}, at most {} feeds are served",
+					display(&trading_pair),
+					display(data_source),
+					MAX_EXCHANGE_RATE_FEEDS
+				);
+				continue
+			}
+			feeds.insert((trading_pair, data_source.clone()));
+		}
+	}
+
+	Ok(FeedConfig { feeds })
+}
+
+/// Makes `feed_config` the active configuration and logs the feeds that changed.
+pub fn update_active_feed_config(feed_config: FeedConfig) {
+	let mut active_config = match ACTIVE_FEED_CONFIG.write() {
+		Ok(config) => config,
+		Err(e) => {
+			error!("Failed to acquire lock on the active feed configuration: {:?}", e);
+			return
+		},
+	};
+
+	if *active_config == feed_config {
+		return
+	}
+
+	for (trading_pair, data_source) in feed_config.difference(&active_config) {
+		info!("Teeracle now serves {} from {}", display(trading_pair), display(data_source));
+	}
+	for (trading_pair, data_source) in active_config.difference(&feed_config) {
+		info!("Teeracle stops serving {} from {}", display(trading_pair), display(data_source));
+	}
+
+	*active_config = feed_config;
+}
+
+/// Decodes a trading pair from its on-chain representation.
+pub fn trading_pair_from_storage(trading_pair: &[u8]) -> Option<TradingPair> {
+	TradingPair::from_key(core::str::from_utf8(trading_pair).ok()?)
+}
+
+fn display(bytes: &[u8]) -> String {
+	String::from_utf8_lossy(bytes).into()
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+	use super::*;
+	use codec::Encode;
+	use itc_parentchain_test::ParentchainHeaderBuilder;
+	use itp_teeracle_storage::encode_trading_pair;
+	use itp_test::mock::onchain_mock::OnchainMock;
+	use std::{format, vec};
+
+	const OUR_MRENCLAVE: MrEnclave = [1u8; 32];
+	const OTHER_MRENCLAVE: MrEnclave = [2u8; 32];
+
+	pub fn read_feed_config_only_serves_whitelisted_sources() {
+		let header = ParentchainHeaderBuilder::default().build();
+		let coin_gecko = b"https://api.coingecko.com/".to_vec();
+		let coin_market_cap = b"https://pro-api.coinmarketcap.com/".to_vec();
+		let unsupported = b"https://api.example.com/".to_vec();
+		let dot_usd = encode_trading_pair("DOT", "USD");
+		let mut ocall_api = OnchainMock::default()
+			.with_mr_enclave(OUR_MRENCLAVE)
+			.with_storage_entries_at_header(
+				&header,
+				vec![
+					(TeeracleStorage::whitelist(&coin_gecko), vec![OTHER_MRENCLAVE, OUR_MRENCLAVE]),
+					(TeeracleStorage::whitelist(&coin_market_cap), vec![OTHER_MRENCLAVE]),
+					(TeeracleStorage::whitelist(&unsupported), vec![OUR_MRENCLAVE]),
+				],
+			);
+		for data_source in [&coin_gecko, &coin_market_cap, &unsupported] {
+			ocall_api.insert_at_header(
+				&header,
+				TeeracleStorage::trading_pairs(data_source),
+				vec![dot_usd.clone()].encode(),
+			);
+		}
+
+		let feed_config = read_feed_config(
+			&ocall_api,
+			&header,
+			&[coin_gecko.clone(), coin_market_cap.clone()],
+			&[coin_gecko.clone(), coin_market_cap, unsupported],
+		)
+		.unwrap();
+
+		assert_eq!(feed_config.data_sources(), BTreeSet::from([coin_gecko.clone()]));
+		assert_eq!(feed_config.feeds, BTreeSet::from([(dot_usd, coin_gecko)]));
+	}
+
+	pub fn read_feed_config_serves_configured_pairs_without_on_chain_rate() {
+		let header = ParentchainHeaderBuilder::default().build();
+		let coin_gecko = b"https://api.coingecko.com/".to_vec();
+		let ksm_usd = encode_trading_pair("KSM", "USD");
+		let btc_usd = encode_trading_pair("BTC", "USD");
+		let mut ocall_api = OnchainMock::default().with_mr_enclave(OUR_MRENCLAVE);
+		ocall_api.insert_at_header(
+			&header,
+			TeeracleStorage::whitelist(&coin_gecko),
+			vec![OUR_MRENCLAVE].encode(),
+		);
+		ocall_api.insert_at_header(
+			&header,
+			TeeracleStorage::trading_pairs(&coin_gecko),
+			vec![ksm_usd.clone(), btc_usd.clone()].encode(),
+		);
+
+		let feed_config =
+			read_feed_config(&ocall_api, &header, &[coin_gecko.clone()], &[coin_gecko.clone()])
+				.unwrap();
+
+		assert_eq!(
+			feed_config.feeds,
+			BTreeSet::from([(ksm_usd, coin_gecko.clone()), (btc_usd, coin_gecko)])
+		);
+	}
+
+	pub fn read_feed_config_caps_number_of_feeds() {
+		let header = ParentchainHeaderBuilder::default().build();
+		let coin_gecko = b"https://api.coingecko.com/".to_vec();
+		let trading_pairs: Vec<TradingPairString> = (0..MAX_EXCHANGE_RATE_FEEDS + 1)
+			.map(|i| encode_trading_pair(&format!("C{}", i), "USD"))
+			.collect();
+		let mut ocall_api = OnchainMock::default().with_mr_enclave(OUR_MRENCLAVE);
+		ocall_api.insert_at_header(
+			&header,
+			TeeracleStorage::whitelist(&coin_gecko),
+			vec![OUR_MRENCLAVE].encode(),
+		);
+		ocall_api.insert_at_header(
+			&header,
+			TeeracleStorage::trading_pairs(&coin_gecko),
+			trading_pairs.encode(),
+		);
+
+		let feed_config =
+			read_feed_config(&ocall_api, &header, &[coin_gecko.clone()], &[coin_gecko]).unwrap();
+
+		assert_eq!(feed_config.feeds.len(), MAX_EXCHANGE_RATE_FEEDS);
+	}
+
+	pub fn trading_pair_from_storage_works() {
+		assert_eq!(
+			trading_pair_from_storage(b"DOT/USD"),
+			Some(TradingPair { crypto_currency: "DOT".into(), fiat_currency: "USD".into() })
+		);
+		assert_eq!(trading_pair_from_storage(b"DOTUSD"), None);
+		assert_eq!(trading_pair_from_storage(b"DOT/USD/CHF"), None);
+	}
+}
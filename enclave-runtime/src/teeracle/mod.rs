@@ -17,10 +17,17 @@
 
 use crate::{
 	error::{Error, Result},
-	initialization::global_components::GLOBAL_OCALL_API_COMPONENT,
+	initialization::global_components::{
+		GLOBAL_OCALL_API_COMPONENT, GLOBAL_ORACLE_CONNECTION_POOL_COMPONENT,
+		GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT,
+	},
+	teeracle::feed_config::{
+		read_feed_config, trading_pair_from_storage, update_active_feed_config,
+	},
 	utils::{
 		get_extrinsic_factory_from_solo_or_parachain,
 		get_node_metadata_repository_from_integritee_solo_or_parachain,
+		get_validator_accessor_from_solo_or_parachain,
 	},
 };
 use codec::{Decode, Encode};
@@ -30,57 +37,148 @@ use ita_oracle::{
 	metrics_exporter::ExportMetrics,
 	oracles::{
 		exchange_rate_oracle::{ExchangeRateOracle, GetExchangeRate},
-		weather_oracle::{GetLongitude, WeatherOracle},
+		weather_oracle::{GetWeather, WeatherOracle},
 	},
 	traits::OracleSource,
-	types::{TradingInfo, TradingPair, WeatherInfo, WeatherQuery},
+	types::{
+		ResponseTranscript, TradingInfo, TradingPair, WeatherInfo, WeatherQuery, WeatherReport,
+	},
 };
+use itc_parentchain::light_client::{concurrent_access::ValidatorAccess, LightClientState};
 use itp_component_container::ComponentGetter;
 use itp_extrinsics_factory::CreateExtrinsics;
 use itp_node_api::metadata::{pallet_teeracle::TeeracleCallIndexes, provider::AccessNodeMetadata};
-use itp_types::OpaqueCall;
+use itp_ocall_api::{EnclaveIpfsOCallApi, IpfsCid};
+use itp_sgx_crypto::key_repository::AccessKey;
+use itp_teeracle_storage::DataSource;
+use itp_types::{OpaqueCall, H256};
 use itp_utils::write_slice_and_whitespace_pad;
 use log::*;
 use sgx_types::sgx_status_t;
+use sp_core::{blake2_256, ed25519, Pair};
 use sp_runtime::OpaqueExtrinsic;
 use std::{string::String, vec::Vec};
 
+pub mod feed_config;
+
+/// Transcript of the HTTPS response an oracle result was derived from, signed by the enclave.
+///
+/// Published to IPFS along with each oracle update, so anyone can check which server
+/// (i.e. TLS certificate) and which response body the submitted value is based on.
+/// The signature covers the parentchain call carrying that value as well, so a transcript
+/// cannot be presented as the source of a different update.
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct SignedResponseTranscript {
+	pub transcript: ResponseTranscript,
+	/// Encoded call that submitted the value derived from the transcript.
+	pub call: Vec<u8>,
+	pub signer: ed25519::Public,
+	pub signature: ed25519::Signature,
+}
+
+impl SignedResponseTranscript {
+	pub fn signed_payload(transcript: &ResponseTranscript, call: &[u8]) -> Vec<u8> {
+		(transcript, call).encode()
+	}
+
+	pub fn verify(&self) -> bool {
+		ed25519::Pair::verify(
+			&self.signature,
+			Self::signed_payload(&self.transcript, &self.call),
+			&self.signer,
+		)
+	}
+
+	/// Whether the transcript is signed, and is the source of the value submitted with `call`.
+	pub fn verify_for_call(&self, call: &OpaqueCall) -> bool {
+		self.call == call.encode() && self.verify()
+	}
+}
+
+fn sign_response_transcript(
+	transcript: ResponseTranscript,
+	call: &OpaqueCall,
+) -> Result<SignedResponseTranscript> {
+	let signer = GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT.get()?.retrieve_key()?;
+	let call = call.encode();
+	let signature = signer.sign(&SignedResponseTranscript::signed_payload(&transcript, &call));
+	Ok(SignedResponseTranscript { transcript, call, signer: signer.public(), signature })
+}
+
+/// Signs the transcript together with the `call` submitting the value derived from it, and
+/// publishes it to IPFS. Failing to do so does not prevent the oracle update itself, the
+/// error is only logged.
+fn publish_response_transcript(maybe_transcript: Option<ResponseTranscript>, call: &OpaqueCall) {
+	let transcript = match maybe_transcript {
+		Some(transcript) => transcript,
+		None => {
+			warn!("No response transcript available for the oracle update");
+			return
+		},
+	};
+	let url = transcript.url.clone();
+
+	let publish = || -> Result<IpfsCid> {
+		let signed_transcript = sign_response_transcript(transcript, call)?;
+		let cid = GLOBAL_OCALL_API_COMPONENT.get()?.write_ipfs(&signed_transcript.encode())?;
+		Ok(cid)
+	};
+
+	match publish() {
+		Ok(cid) => info!(
+			"Published response transcript for {} to IPFS, cid: {}, call hash: {:?}",
+			url,
+			String::from_utf8_lossy(&cid.0),
+			H256::from(blake2_256(&call.encode()))
+		),
+		Err(e) => error!("Failed to publish response transcript for {}: {:?}", url, e),
+	}
+}
+
 fn update_weather_data_internal(weather_info: WeatherInfo) -> Result<Vec<OpaqueExtrinsic>> {
 	let extrinsics_factory = get_extrinsic_factory_from_solo_or_parachain()?;
 	let ocall_api = GLOBAL_OCALL_API_COMPONENT.get()?;
 
 	let mut extrinsic_calls: Vec<OpaqueCall> = Vec::new();
 
-	let open_meteo_weather_oracle = create_open_meteo_weather_oracle(ocall_api);
+	let connection_pool = GLOBAL_ORACLE_CONNECTION_POOL_COMPONENT.get()?;
+
+	let open_meteo_weather_oracle = create_open_meteo_weather_oracle(ocall_api, connection_pool);
 
-	match get_longitude(weather_info, open_meteo_weather_oracle) {
+	match get_weather(weather_info, open_meteo_weather_oracle) {
 		Ok(opaque_call) => extrinsic_calls.push(opaque_call),
 		Err(e) => {
-			error!("[-] Failed to get the newest longitude from OpenMeteo. {:?}", e);
+			error!("[-] Failed to get the newest weather data from OpenMeteo. {:?}", e);
 		},
 	};
 	let extrinsics = extrinsics_factory.create_extrinsics(extrinsic_calls.as_slice(), None)?;
 	Ok(extrinsics)
 }
 
-fn get_longitude<OracleSourceType, MetricsExporter>(
+fn get_weather<OracleSourceType, MetricsExporter>(
 	weather_info: WeatherInfo,
 	oracle: WeatherOracle<OracleSourceType, MetricsExporter>,
 ) -> Result<OpaqueCall>
 where
 	OracleSourceType: OracleSource<
 		WeatherInfo,
-		OracleRequestResult = std::result::Result<f32, ita_oracle::error::Error>,
+		OracleRequestResult = std::result::Result<WeatherReport, ita_oracle::error::Error>,
 	>,
 	MetricsExporter: ExportMetrics<WeatherInfo>,
 {
-	let longitude =
-		oracle.get_longitude(weather_info.clone()).map_err(|e| Error::Other(e.into()))?;
+	let (report, transcript) =
+		oracle.get_weather(weather_info.clone()).map_err(|e| Error::Other(e.into()))?;
 
 	let base_url = oracle.get_base_url().map_err(|e| Error::Other(e.into()))?;
 	let source_base_url = base_url.as_str();
 
-	println!("Update the longitude:  {}, for source {}", longitude, source_base_url);
+	println!(
+		"Update the weather data: {} = {} ({} samples), for source {}",
+		weather_info.weather_query.clone().key(),
+		report.value,
+		report.number_of_samples,
+		source_base_url
+	);
 
 	let node_metadata_repository =
 		get_node_metadata_repository_from_integritee_solo_or_parachain()?;
@@ -94,42 +192,35 @@ where
 		call_ids,
 		weather_info.weather_query.key().as_bytes().to_vec(),
 		source_base_url.as_bytes().to_vec(),
-		longitude.encode(),
+		report.encode(),
 	));
+	publish_response_transcript(transcript, &call);
 
 	Ok(call)
 }
 
+/// Get the weather data for a SCALE encoded `WeatherQuery` from Open-Meteo.
 #[no_mangle]
 pub unsafe extern "C" fn update_weather_data_xt(
-	weather_info_longitude: *const u8,
-	weather_info_longitude_size: u32,
-	weather_info_latitude: *const u8,
-	weather_info_latitude_size: u32,
+	weather_query: *const u8,
+	weather_query_size: u32,
 	unchecked_extrinsic: *mut u8,
 	unchecked_extrinsic_size: u32,
 ) -> sgx_status_t {
-	let mut weather_info_longitude_slice =
-		slice::from_raw_parts(weather_info_longitude, weather_info_longitude_size as usize);
-	let longitude = match String::decode(&mut weather_info_longitude_slice) {
+	let mut weather_query_slice = slice::from_raw_parts(weather_query, weather_query_size as usize);
+	let weather_query = match WeatherQuery::decode(&mut weather_query_slice) {
 		Ok(val) => val,
 		Err(e) => {
-			error!("Could not decode longitude: {:?}", e);
+			error!("Could not decode weather query: {:?}", e);
 			return sgx_status_t::SGX_ERROR_UNEXPECTED
 		},
 	};
 
-	let mut weather_info_latitude_slice =
-		slice::from_raw_parts(weather_info_latitude, weather_info_latitude_size as usize);
-	let latitude = match String::decode(&mut weather_info_latitude_slice) {
-		Ok(val) => val,
-		Err(e) => {
-			error!("Could not decode latitude: {:?}", e);
-			return sgx_status_t::SGX_ERROR_UNEXPECTED
-		},
-	};
+	if let Err(e) = weather_query.validate() {
+		error!("Invalid weather query: {:?}", e);
+		return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+	}
 
-	let weather_query = WeatherQuery { longitude, latitude, hourly: " ".into() };
 	let weather_info = WeatherInfo { weather_query };
 
 	let extrinsics = match update_weather_data_internal(weather_info) {
@@ -152,25 +243,28 @@ pub unsafe extern "C" fn update_weather_data_xt(
 	sgx_status_t::SGX_SUCCESS
 }
 
-/// For now get the crypto/fiat currency exchange rate from coingecko and CoinMarketCap.
+/// Get the exchange rates of all feeds the Teeracle pallet governs, from CoinGecko and CoinMarketCap.
+///
+/// `candidates` is a SCALE encoded `Vec<DataSource>`, the whitelisted data sources found in the
+/// parentchain storage by the untrusted worker. Each of them is verified against the light
+/// client's state, and their trading pairs are read from the pallet's configuration.
 #[no_mangle]
 pub unsafe extern "C" fn update_market_data_xt(
-	crypto_currency_ptr: *const u8,
-	crypto_currency_size: u32,
-	fiat_currency_ptr: *const u8,
-	fiat_currency_size: u32,
+	candidates: *const u8,
+	candidates_size: u32,
 	unchecked_extrinsic: *mut u8,
 	unchecked_extrinsic_size: u32,
 ) -> sgx_status_t {
-	let mut crypto_currency_slice =
-		slice::from_raw_parts(crypto_currency_ptr, crypto_currency_size as usize);
-	let crypto_currency: String = Decode::decode(&mut crypto_currency_slice).unwrap();
-
-	let mut fiat_currency_slice =
-		slice::from_raw_parts(fiat_currency_ptr, fiat_currency_size as usize);
-	let fiat_currency: String = Decode::decode(&mut fiat_currency_slice).unwrap();
+	let mut candidates_slice = slice::from_raw_parts(candidates, candidates_size as usize);
+	let candidate_sources: Vec<DataSource> = match Decode::decode(&mut candidates_slice) {
+		Ok(candidates) => candidates,
+		Err(e) => {
+			error!("Could not decode candidate data sources: {:?}", e);
+			return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+		},
+	};
 
-	let extrinsics = match update_market_data_internal(crypto_currency, fiat_currency) {
+	let extrinsics = match update_market_data_internal(&candidate_sources) {
 		Ok(xts) => xts,
 		Err(e) => {
 			error!("Update market data failed: {:?}", e);
@@ -179,8 +273,7 @@ pub unsafe extern "C" fn update_market_data_xt(
 	};
 
 	if extrinsics.is_empty() {
-		error!("Updating market data yielded no extrinsics");
-		return sgx_status_t::SGX_ERROR_UNEXPECTED
+		warn!("Updating market data yielded no extrinsics");
 	}
 	let extrinsic_slice =
 		slice::from_raw_parts_mut(unchecked_extrinsic, unchecked_extrinsic_size as usize);
@@ -194,48 +287,81 @@ pub unsafe extern "C" fn update_market_data_xt(
 	sgx_status_t::SGX_SUCCESS
 }
 
-fn update_market_data_internal(
-	crypto_currency: String,
-	fiat_currency: String,
-) -> Result<Vec<OpaqueExtrinsic>> {
+fn update_market_data_internal(candidate_sources: &[DataSource]) -> Result<Vec<OpaqueExtrinsic>> {
 	let extrinsics_factory = get_extrinsic_factory_from_solo_or_parachain()?;
 	let ocall_api = GLOBAL_OCALL_API_COMPONENT.get()?;
+	let latest_header = get_validator_accessor_from_solo_or_parachain()?
+		.execute_on_validator(|v| v.latest_finalized_header())?;
 
-	let mut extrinsic_calls: Vec<OpaqueCall> = Vec::new();
+	let connection_pool = GLOBAL_ORACLE_CONNECTION_POOL_COMPONENT.get()?;
 
-	// Get the exchange rate
-	let trading_pair = TradingPair { crypto_currency, fiat_currency };
+	let coin_gecko_oracle = create_coin_gecko_oracle(ocall_api.clone(), connection_pool.clone());
+	let coin_market_cap_oracle = create_coin_market_cap_oracle(ocall_api.clone(), connection_pool);
+	let coin_gecko_source = data_source(&coin_gecko_oracle)?;
+	let coin_market_cap_source = data_source(&coin_market_cap_oracle)?;
 
-	let coin_gecko_oracle = create_coin_gecko_oracle(ocall_api.clone());
+	let feed_config = read_feed_config(
+		ocall_api.as_ref(),
+		&latest_header,
+		&[coin_gecko_source.clone(), coin_market_cap_source.clone()],
+		candidate_sources,
+	)?;
+	update_active_feed_config(feed_config.clone());
 
-	match get_exchange_rate(trading_pair.clone(), coin_gecko_oracle) {
-		Ok(opaque_call) => extrinsic_calls.push(opaque_call),
-		Err(e) => {
-			error!("[-] Failed to get the newest exchange rate from CoinGecko. {:?}", e);
-		},
-	};
+	let mut extrinsic_calls: Vec<OpaqueCall> = Vec::new();
 
-	let coin_market_cap_oracle = create_coin_market_cap_oracle(ocall_api);
-	match get_exchange_rate(trading_pair, coin_market_cap_oracle) {
-		Ok(oc) => extrinsic_calls.push(oc),
-		Err(e) => {
-			error!("[-] Failed to get the newest exchange rate from CoinMarketCap. {:?}", e);
-		},
-	};
+	for (trading_pair, source) in feed_config.feeds {
+		let trading_pair = match trading_pair_from_storage(&trading_pair) {
+			Some(trading_pair) => trading_pair,
+			None => {
+				error!("[-] Invalid trading pair: {}", String::from_utf8_lossy(&trading_pair));
+				continue
+			},
+		};
+
+		let result = if source == coin_gecko_source {
+			get_exchange_rate(trading_pair.clone(), &coin_gecko_oracle)
+		} else {
+			get_exchange_rate(trading_pair.clone(), &coin_market_cap_oracle)
+		};
+
+		match result {
+			Ok(opaque_call) => extrinsic_calls.push(opaque_call),
+			Err(e) => {
+				error!(
+					"[-] Failed to get the newest exchange rate of {:?} from {}. {:?}",
+					trading_pair,
+					String::from_utf8_lossy(&source),
+					e
+				);
+			},
+		};
+	}
 
 	let extrinsics = extrinsics_factory.create_extrinsics(extrinsic_calls.as_slice(), None)?;
 	Ok(extrinsics)
 }
 
+/// The data source an oracle is whitelisted under, i.e. its base URL.
+fn data_source<OracleSourceType, MetricsExporter>(
+	oracle: &ExchangeRateOracle<OracleSourceType, MetricsExporter>,
+) -> Result<DataSource>
+where
+	OracleSourceType: OracleSource<TradingInfo>,
+{
+	let base_url = oracle.get_base_url().map_err(|e| Error::Other(e.into()))?;
+	Ok(base_url.as_str().as_bytes().to_vec())
+}
+
 fn get_exchange_rate<OracleSourceType, MetricsExporter>(
 	trading_pair: TradingPair,
-	oracle: ExchangeRateOracle<OracleSourceType, MetricsExporter>,
+	oracle: &ExchangeRateOracle<OracleSourceType, MetricsExporter>,
 ) -> Result<OpaqueCall>
 where
 	OracleSourceType: OracleSource<TradingInfo>,
 	MetricsExporter: ExportMetrics<TradingInfo>,
 {
-	let (rate, base_url) = oracle
+	let (rate, base_url, transcript) = oracle
 		.get_exchange_rate(trading_pair.clone())
 		.map_err(|e| Error::Other(e.into()))?;
 
@@ -262,6 +388,7 @@ where
 		trading_pair.key().as_bytes().to_vec(),
 		Some(rate),
 	));
+	publish_response_transcript(transcript, &call);
 
 	Ok(call)
 }
@@ -51,8 +51,12 @@ pub fn get_state_request_works() {
 		Arc::new(GetterExecutor::<_, GetStateMock<TestState>>::new(state_observer));
 	let top_pool_author = Arc::new(AuthorApiMock::default());
 
-	let io_handler =
-		public_api_rpc_handler(top_pool_author, getter_executor, Arc::new(rsa_repository));
+	let io_handler = public_api_rpc_handler(
+		top_pool_author,
+		getter_executor,
+		Arc::new(rsa_repository),
+		|_: &ShardIdentifier, _: &[Vec<u8>]| Err("read proofs are not tested here".to_string()),
+	);
 	let rpc_handler = Arc::new(RpcWsHandler::new(io_handler, watch_extractor, connection_registry));
 
 	let getter = Getter::trusted(TrustedGetterSigned::new(
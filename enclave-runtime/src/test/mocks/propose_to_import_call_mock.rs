@@ -22,7 +22,7 @@ use itc_parentchain::primitives::ParentchainId;
 use itp_ocall_api::{EnclaveOnChainOCallApi, EnclaveSidechainOCallApi, Result};
 use itp_types::{
 	storage::StorageEntryVerified, BlockHash, Header as ParentchainHeader, ShardIdentifier,
-	WorkerRequest, WorkerResponse, H256,
+	SidechainBlockConfirmation, WorkerRequest, WorkerResponse, H256,
 };
 use its_primitives::types::block::SignedBlock as SignedSidechainBlockType;
 use its_sidechain::consensus_common::BlockImport;
@@ -117,4 +117,12 @@ impl EnclaveSidechainOCallApi for ProposeToImportOCallApi {
 	) -> SgxResult<Vec<SignedSidechainBlock>> {
 		Ok(Vec::new())
 	}
+
+	fn finalize_sidechain_block(
+		&self,
+		_shard_identifier: ShardIdentifier,
+		_finalized_block: SidechainBlockConfirmation,
+	) -> SgxResult<()> {
+		Ok(())
+	}
 }
@@ -34,7 +34,7 @@ use itc_parentchain_test::ParentchainHeaderBuilder;
 use itp_extrinsics_factory::mock::ExtrinsicsFactoryMock;
 use itp_node_api::metadata::{metadata_mocks::NodeMetadataMock, provider::NodeMetadataRepository};
 use itp_settings::{
-	sidechain::SLOT_DURATION,
+	sidechain::{MAX_FORK_DEPTH, SLOT_DURATION},
 	worker_mode::{ProvideWorkerMode, WorkerMode, WorkerModeProvider},
 };
 use itp_sgx_externalities::SgxExternalitiesTrait;
@@ -46,7 +46,11 @@ use itp_top_pool_author::top_filter::AllowAllTopsFilter;
 use itp_types::Block as ParentchainBlock;
 use its_block_verification::slot::slot_from_timestamp_and_duration;
 use its_primitives::types::SignedBlock as SignedSidechainBlock;
-use its_sidechain::{aura::proposer_factory::ProposerFactory, slots::SlotInfo};
+use its_sidechain::{
+	aura::proposer_factory::ProposerFactory,
+	consensus_common::{AppliedSidechainBlocks, SidechainBlockStates, SidechainFinalityTracker},
+	slots::SlotInfo,
+};
 use log::*;
 use primitive_types::H256;
 use sgx_crypto_helper::RsaKeyPair;
@@ -97,16 +101,25 @@ pub fn ensure_events_get_reset_upon_block_proposal() {
 		Arc::new(MetricsOCallMock::default()),
 	));
 	let parentchain_block_import_trigger = Arc::new(TestParentchainBlockImportTrigger::default());
+	let block_states = Arc::new(SidechainBlockStates::new(MAX_FORK_DEPTH));
+	let applied_blocks = Arc::new(AppliedSidechainBlocks::new());
 	let block_importer = Arc::new(TestBlockImporter::new(
 		state_handler.clone(),
 		state_key_repo.clone(),
 		top_pool_author.clone(),
 		parentchain_block_import_trigger.clone(),
 		ocall_api.clone(),
+		block_states.clone(),
+		Arc::new(SidechainFinalityTracker::new(ocall_api.clone(), applied_blocks.clone())),
 	));
 	let block_composer = Arc::new(TestBlockComposer::new(signer.clone(), state_key_repo.clone()));
-	let proposer_environment =
-		ProposerFactory::new(top_pool_author.clone(), stf_executor.clone(), block_composer);
+	let proposer_environment = ProposerFactory::new(
+		top_pool_author.clone(),
+		stf_executor.clone(),
+		block_composer,
+		block_states,
+		applied_blocks,
+	);
 	let extrinsics_factory = ExtrinsicsFactoryMock::default();
 	let validator_access = ValidatorAccessMock::default();
 
@@ -16,12 +16,17 @@
 
 */
 
-use codec::alloc::string::ToString;
+use crate::teeracle::SignedResponseTranscript;
+use codec::{alloc::string::ToString, Encode};
 use ita_oracle::{
 	create_coin_gecko_oracle, create_coin_market_cap_oracle,
-	oracles::exchange_rate_oracle::GetExchangeRate, types::TradingPair,
+	oracles::exchange_rate_oracle::GetExchangeRate,
+	types::{ResponseTranscript, TradingPair},
+	ConnectionPool,
 };
 use itp_test::mock::metrics_ocall_mock::MetricsOCallMock;
+use itp_types::OpaqueCall;
+use sp_core::{ed25519, Pair};
 use std::sync::Arc;
 
 pub(super) fn test_verify_get_exchange_rate_from_coin_gecko_works() {
@@ -29,10 +34,16 @@ pub(super) fn test_verify_get_exchange_rate_from_coin_gecko_works() {
 	let trading_pair =
 		TradingPair { crypto_currency: "DOT".to_string(), fiat_currency: "USD".to_string() };
 
-	let coin_gecko_oracle = create_coin_gecko_oracle(Arc::new(MetricsOCallMock::default()));
+	let coin_gecko_oracle = create_coin_gecko_oracle(
+		Arc::new(MetricsOCallMock::default()),
+		Arc::new(ConnectionPool::default()),
+	);
 
 	let result = coin_gecko_oracle.get_exchange_rate(trading_pair.clone());
 	assert!(result.is_ok());
+
+	let (_, _, transcript) = result.unwrap();
+	assert!(transcript.unwrap().server_certificate_fingerprint.is_some());
 }
 
 /// Get exchange rate from coin market cap. Requires API key (therefore not suited for unit testing).
@@ -42,9 +53,42 @@ pub(super) fn test_verify_get_exchange_rate_from_coin_market_cap_works() {
 	let trading_pair =
 		TradingPair { crypto_currency: "DOT".to_string(), fiat_currency: "USD".to_string() };
 
-	let coin_market_cap_oracle =
-		create_coin_market_cap_oracle(Arc::new(MetricsOCallMock::default()));
+	let coin_market_cap_oracle = create_coin_market_cap_oracle(
+		Arc::new(MetricsOCallMock::default()),
+		Arc::new(ConnectionPool::default()),
+	);
 
 	let result = coin_market_cap_oracle.get_exchange_rate(trading_pair.clone());
 	assert!(result.is_ok());
 }
+
+pub(super) fn test_signed_response_transcript_verification_detects_tampering() {
+	let signer = ed25519::Pair::from_seed(&[7u8; 32]);
+	let transcript = ResponseTranscript::new(
+		"https://api.coingecko.com/api/v3/coins/polkadot".to_string(),
+		1_700_000_000_000,
+		200,
+		Some(b"certificate"),
+		b"{\"usd\":4.2}",
+	);
+	let call = OpaqueCall::from_tuple(&([1u8, 2u8], Some(42u64)));
+	let other_call = OpaqueCall::from_tuple(&([1u8, 2u8], Some(43u64)));
+	let signature =
+		signer.sign(&SignedResponseTranscript::signed_payload(&transcript, &call.encode()));
+	let mut signed_transcript = SignedResponseTranscript {
+		transcript,
+		call: call.encode(),
+		signer: signer.public(),
+		signature,
+	};
+
+	assert!(signed_transcript.verify_for_call(&call));
+	assert!(!signed_transcript.verify_for_call(&other_call));
+
+	signed_transcript.call = other_call.encode();
+	assert!(!signed_transcript.verify());
+
+	signed_transcript.call = call.encode();
+	signed_transcript.transcript.body_hash = [0u8; 32];
+	assert!(!signed_transcript.verify());
+}
@@ -113,6 +113,21 @@ pub extern "C" fn test_main_entrance() -> size_t {
 		test_retrieve_event_count,
 		test_reset_events,
 		rpc::worker_api_direct::tests::test_given_io_handler_methods_then_retrieve_all_names_as_string,
+		rpc::personhood_metrics::tests::relay_host_strips_everything_but_the_host,
+		rpc::personhood_metrics::tests::relay_label_reports_unknown_relays_as_other,
+		rpc::encointer_cache::tests::encointer_cache_returns_reputation_until_it_changes,
+		rpc::encointer_cache::tests::encointer_cache_is_cleared_on_phase_change,
+		rpc::encointer_cache::tests::encointer_cache_evicts_least_recently_used_reputation,
+		rpc::encointer_cache::tests::encointer_cache_keeps_recently_used_entries_over_several_evictions,
+		rpc::encointer_cache::tests::encointer_cache_remembers_unknown_communities,
+		rpc::encointer_watcher::tests::encointer_watcher_tracks_reputation_changes,
+		rpc::encointer_watcher::tests::encointer_watcher_new_ceremony_outdates_reputations,
+		rpc::encointer_watcher::tests::encointer_watcher_tracks_purged_communities,
+		rpc::encointer_watcher::tests::encointer_watcher_forgets_changes_on_phase_change,
+		rpc::encointer_watcher::tests::encointer_watcher_forgets_changes_beyond_capacity,
+		rpc::issued_badges::tests::issued_badge_replaces_earlier_badge_of_the_same_holder,
+		rpc::issued_badges::tests::badges_of_purged_community_are_deleted,
+		rpc::issued_badges::tests::badges_are_refreshed_on_phase_change_and_renewed_with_a_new_ceremony,
 		handle_state_mock::tests::initialized_shards_list_is_empty,
 		handle_state_mock::tests::shard_exists_after_inserting,
 		handle_state_mock::tests::from_shard_works,
@@ -122,6 +137,7 @@ pub extern "C" fn test_main_entrance() -> size_t {
 		handle_state_mock::tests::ensure_encode_and_encrypt_does_not_affect_state_hash,
 		// mra cert tests
 		test_verify_mra_cert_should_work,
+		test_verify_mra_cert_of_accepted_mrenclave_works,
 		test_verify_wrong_cert_is_err,
 		test_given_wrong_platform_info_when_verifying_attestation_report_then_return_error,
 		// sync tests
@@ -132,6 +148,9 @@ pub extern "C" fn test_main_entrance() -> size_t {
 		stf_executor_tests::propose_state_update_executes_no_trusted_calls_given_no_time,
 		stf_executor_tests::propose_state_update_executes_only_one_trusted_call_given_not_enough_time,
 		stf_executor_tests::propose_state_update_executes_all_calls_given_enough_time,
+		stf_executor_tests::propose_state_update_in_parallel_results_in_same_state_as_sequential_execution,
+		stf_executor_tests::propose_state_update_in_parallel_with_conflicting_calls_results_in_same_state_as_sequential_execution,
+		stf_executor_tests::propose_state_update_in_parallel_follows_the_state_across_updates,
 		enclave_signer_tests::enclave_signer_signatures_are_valid,
 		enclave_signer_tests::derive_key_is_deterministic,
 		enclave_signer_tests::nonce_is_computed_correctly,
@@ -153,6 +172,17 @@ pub extern "C" fn test_main_entrance() -> size_t {
 		tls_ra::seal_handler::test::unseal_seal_state_works,
 		tls_ra::tests::test_tls_ra_server_client_networking,
 		tls_ra::tests::test_state_and_key_provisioning,
+		tls_ra::tests::test_tls_ra_provisions_only_requested_items,
+		tls_ra::tests::test_tls_ra_legacy_framing_seals_only_requested_items,
+		tls_ra::protocol::tests::version_negotiation_picks_highest_common_version,
+		tls_ra::protocol::tests::version_negotiation_rejects_newer_client,
+		tls_ra::protocol::tests::granted_items_are_requested_and_provisionable,
+		tls_ra::protocol::tests::state_key_is_only_provisioned_with_the_state,
+		tls_ra::protocol::tests::handshake_magic_is_distinguishable_from_legacy_opcodes,
+		secret_migration::tests::upgrade_to_pending_enclave_is_authorized,
+		secret_migration::tests::upgrade_to_unauthorized_enclave_fails,
+		secret_migration::tests::encrypted_secrets_can_only_be_decrypted_by_target,
+		secret_migration::tests::secrets_with_invalid_signature_are_rejected,
 		// RPC tests
 		direct_rpc_tests::get_state_request_works,
 
@@ -162,6 +192,12 @@ pub extern "C" fn test_main_entrance() -> size_t {
 		// light-client-test
 		itc_parentchain::light_client::io::sgx_tests::init_parachain_light_client_works,
 		itc_parentchain::light_client::io::sgx_tests::sealing_creates_backup,
+		itc_parentchain::light_client::io::sgx_tests::backups_are_created_at_block_interval,
+		itc_parentchain::light_client::io::sgx_tests::backups_rotate_through_slots,
+		itc_parentchain::light_client::io::sgx_tests::corrupted_db_falls_back_to_newest_backup,
+		itc_parentchain::light_client::io::sgx_tests::restore_backup_rolls_back_state,
+		itc_parentchain::light_client::io::sgx_tests::relay_chain_backed_light_client_refuses_another_relay_chain,
+		itc_parentchain::light_client::io::sgx_tests::relay_chain_authorities_are_only_initialized_once,
 
 		// these unit test (?) need an ipfs node running..
 		// ipfs::test_creates_ipfs_content_struct_works,
@@ -177,7 +213,13 @@ pub extern "C" fn test_main_entrance() -> size_t {
 #[cfg(feature = "teeracle")]
 fn run_teeracle_tests() {
 	use super::teeracle_tests::*;
+	use crate::teeracle::feed_config::tests::*;
 	test_verify_get_exchange_rate_from_coin_gecko_works();
+	test_signed_response_transcript_verification_detects_tampering();
+	read_feed_config_only_serves_whitelisted_sources();
+	read_feed_config_serves_configured_pairs_without_on_chain_rate();
+	read_feed_config_caps_number_of_feeds();
+	trading_pair_from_storage_works();
 	// Disabled - requires API key, cannot run locally
 	//test_verify_get_exchange_rate_from_coin_market_cap_works();
 }
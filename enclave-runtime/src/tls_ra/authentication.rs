@@ -21,17 +21,25 @@ use itp_attestation_handler::cert;
 use itp_ocall_api::EnclaveAttestationOCallApi;
 use log::*;
 use sgx_types::*;
+use std::vec::Vec;
 use webpki::DNSName;
 
 pub struct ClientAuth<A> {
 	outdated_ok: bool,
 	skip_ra: bool,
+	/// MRENCLAVEs of peers that are accepted besides our own.
+	accepted_mrenclaves: Vec<[u8; 32]>,
 	attestation_ocall: A,
 }
 
 impl<A> ClientAuth<A> {
-	pub fn new(outdated_ok: bool, skip_ra: bool, attestation_ocall: A) -> Self {
-		ClientAuth { outdated_ok, skip_ra, attestation_ocall }
+	pub fn new(
+		outdated_ok: bool,
+		skip_ra: bool,
+		accepted_mrenclaves: Vec<[u8; 32]>,
+		attestation_ocall: A,
+	) -> Self {
+		ClientAuth { outdated_ok, skip_ra, accepted_mrenclaves, attestation_ocall }
 	}
 }
 
@@ -66,7 +74,13 @@ where
 		let is_dcap = true;
 		#[cfg(not(feature = "dcap"))]
 		let is_dcap = false;
-		match cert::verify_mra_cert(&certs[0].0, true, is_dcap, &self.attestation_ocall) {
+		match cert::verify_mra_cert(
+			&certs[0].0,
+			true,
+			is_dcap,
+			&self.accepted_mrenclaves,
+			&self.attestation_ocall,
+		) {
 			Ok(()) => Ok(rustls::ClientCertVerified::assertion()),
 			Err(sgx_status_t::SGX_ERROR_UPDATE_NEEDED) =>
 				if self.outdated_ok {
@@ -83,12 +97,19 @@ where
 pub struct ServerAuth<A> {
 	outdated_ok: bool,
 	skip_ra: bool,
+	/// MRENCLAVEs of peers that are accepted besides our own.
+	accepted_mrenclaves: Vec<[u8; 32]>,
 	attestation_ocall: A,
 }
 
 impl<A> ServerAuth<A> {
-	pub fn new(outdated_ok: bool, skip_ra: bool, attestation_ocall: A) -> Self {
-		ServerAuth { outdated_ok, skip_ra, attestation_ocall }
+	pub fn new(
+		outdated_ok: bool,
+		skip_ra: bool,
+		accepted_mrenclaves: Vec<[u8; 32]>,
+		attestation_ocall: A,
+	) -> Self {
+		ServerAuth { outdated_ok, skip_ra, accepted_mrenclaves, attestation_ocall }
 	}
 }
 
@@ -119,7 +140,13 @@ where
 		#[cfg(not(feature = "dcap"))]
 		let is_dcap = false;
 		// This call will automatically verify cert is properly signed
-		match cert::verify_mra_cert(&certs[0].0, true, is_dcap, &self.attestation_ocall) {
+		match cert::verify_mra_cert(
+			&certs[0].0,
+			true,
+			is_dcap,
+			&self.accepted_mrenclaves,
+			&self.attestation_ocall,
+		) {
 			Ok(()) => Ok(rustls::ServerCertVerified::assertion()),
 			Err(sgx_status_t::SGX_ERROR_UPDATE_NEEDED) =>
 				if self.outdated_ok {
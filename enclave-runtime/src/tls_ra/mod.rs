@@ -18,9 +18,15 @@
 //! Contains all logic of the state provisioning mechanism
 //! including the remote attestation and tls / tcp connection part.
 
+use crate::secret_migration::fetch_authorized_mrenclaves;
 use codec::{Decode, Encode, MaxEncodedLen};
+use itp_types::ShardIdentifier;
+use log::*;
+use protocol::ProvisioningError;
+use std::vec::Vec;
 
 mod authentication;
+pub mod protocol;
 pub mod seal_handler;
 mod tls_ra_client;
 mod tls_ra_server;
@@ -31,6 +37,27 @@ pub mod tests;
 #[cfg(feature = "test")]
 pub mod mocks;
 
+/// MRENCLAVEs a provisioning peer may have besides our own: the enclaves authorized for any of
+/// the `shards` in the `ShardConfigRegistry`, so the active enclave and its pending upgrade can
+/// provision each other.
+///
+/// If the authorizations can't be fetched, only peers with our own MRENCLAVE are accepted.
+fn accepted_mrenclaves(shards: &[ShardIdentifier]) -> Vec<[u8; 32]> {
+	let mut accepted = Vec::new();
+	for shard in shards {
+		match fetch_authorized_mrenclaves(shard) {
+			Ok(mrenclaves) => accepted.extend(mrenclaves),
+			Err(e) => warn!(
+				"Could not fetch the enclaves authorized for shard {:?}, only accepting our own MRENCLAVE: {:?}",
+				shard, e
+			),
+		}
+	}
+	accepted.sort_unstable();
+	accepted.dedup();
+	accepted
+}
+
 /// Header of an accompanied payload. Indicates the
 /// length an the type (opcode) of the following payload.
 #[derive(Clone, Debug, Decode, Encode, MaxEncodedLen)]
@@ -54,19 +81,25 @@ pub enum Opcode {
 	LightClient,
 }
 
-impl From<u8> for Opcode {
-	fn from(item: u8) -> Self {
+impl TryFrom<u8> for Opcode {
+	type Error = ProvisioningError;
+
+	fn try_from(item: u8) -> Result<Self, Self::Error> {
 		match item {
-			0 => Opcode::ShieldingKey,
-			1 => Opcode::StateKey,
-			2 => Opcode::State,
-			3 => Opcode::LightClient,
-			_ => unimplemented!("Unsupported/unknown Opcode for MU-RA exchange"),
+			0 => Ok(Opcode::ShieldingKey),
+			1 => Ok(Opcode::StateKey),
+			2 => Ok(Opcode::State),
+			3 => Ok(Opcode::LightClient),
+			_ => Err(ProvisioningError::UnknownOpcode(item)),
 		}
 	}
 }
 
 impl Opcode {
+	/// All items, in the order they are provisioned.
+	pub const ALL: [Opcode; 4] =
+		[Opcode::ShieldingKey, Opcode::StateKey, Opcode::State, Opcode::LightClient];
+
 	pub fn to_bytes(self) -> [u8; 1] {
 		(self as u8).to_be_bytes()
 	}
@@ -0,0 +1,257 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Versioned handshake of the state provisioning protocol.
+//!
+//! Legacy clients start a session by sending the 32 bytes of the requested shard. A versioned
+//! client sends [`HANDSHAKE_MAGIC`] instead, which is not a valid shard, followed by a
+//! length-prefixed [`ClientHello`]. The server answers with a frame starting with
+//! [`SERVER_HELLO_MARKER`], which is not a valid [`Opcode`], so a versioned client detects a
+//! legacy server and aborts before sealing anything. Then the server sends exactly the granted
+//! items.
+//!
+//! The state key and the states are provisioned together or not at all: sealing a provisioned
+//! state key makes the locally sealed states undecryptable.
+//!
+//! A client that detected a legacy server reconnects with [`Framing::Legacy`].
+
+use super::Opcode;
+use codec::{Decode, Encode};
+use itp_types::ShardIdentifier;
+use std::{string::String, vec::Vec};
+
+/// Latest version of the provisioning protocol.
+pub const PROVISIONING_PROTOCOL_VERSION: u16 = 1;
+
+/// Oldest version of the provisioning protocol we still speak.
+pub const MIN_PROVISIONING_PROTOCOL_VERSION: u16 = 1;
+
+/// First bytes of a versioned client, instead of the shard of the legacy protocol.
+pub const HANDSHAKE_MAGIC: [u8; 32] = *b"integritee/state-provisioning/v1";
+
+/// First byte of the server's answer to a [`ClientHello`].
+pub const SERVER_HELLO_MARKER: u8 = 0xA5;
+
+/// Upper bound of an encoded hello, to not allocate whatever a peer announces.
+pub const MAX_HELLO_LENGTH: u64 = 4096;
+
+/// How a client opens a provisioning session.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Framing {
+	/// [`HANDSHAKE_MAGIC`] followed by a [`ClientHello`].
+	Versioned,
+	/// The shard only. The server sends everything its worker mode allows.
+	Legacy,
+}
+
+/// Optional protocol features, negotiated as the intersection of both peers' capabilities.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Encode, Decode)]
+pub struct Capabilities(u32);
+
+impl Capabilities {
+	/// The server only provides the items requested by the client. Without it, the server
+	/// provides everything its worker mode allows.
+	pub const ITEM_SELECTION: Capabilities = Capabilities(1 << 0);
+
+	/// Capabilities supported by this enclave release.
+	pub fn supported() -> Self {
+		Self::ITEM_SELECTION
+	}
+
+	pub fn contains(&self, other: Capabilities) -> bool {
+		self.0 & other.0 == other.0
+	}
+
+	pub fn intersection(&self, other: Capabilities) -> Self {
+		Capabilities(self.0 & other.0)
+	}
+}
+
+impl core::ops::BitOr for Capabilities {
+	type Output = Self;
+
+	fn bitor(self, rhs: Self) -> Self {
+		Capabilities(self.0 | rhs.0)
+	}
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Encode, Decode)]
+pub struct ClientHello {
+	pub min_version: u16,
+	pub max_version: u16,
+	pub capabilities: Capabilities,
+	pub shard: ShardIdentifier,
+	/// Items the client wants to receive. Only considered with [`Capabilities::ITEM_SELECTION`].
+	pub requested_items: Vec<Opcode>,
+}
+
+impl ClientHello {
+	pub fn new(shard: ShardIdentifier, requested_items: Vec<Opcode>) -> Self {
+		Self {
+			min_version: MIN_PROVISIONING_PROTOCOL_VERSION,
+			max_version: PROVISIONING_PROTOCOL_VERSION,
+			capabilities: Capabilities::supported(),
+			shard,
+			requested_items,
+		}
+	}
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Encode, Decode)]
+pub enum ServerHello {
+	Accepted {
+		version: u16,
+		capabilities: Capabilities,
+		/// Items the server is going to send, in this order.
+		items: Vec<Opcode>,
+	},
+	Rejected(RejectReason),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Encode, Decode)]
+pub enum RejectReason {
+	/// No common protocol version, contains the versions supported by the server.
+	UnsupportedVersion { min_version: u16, max_version: u16 },
+	/// The state key has been requested without the state, or the other way around.
+	StateKeyWithoutState,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ProvisioningError {
+	/// The server only speaks the unversioned protocol.
+	LegacyServer,
+	Rejected(RejectReason),
+	UnknownOpcode(u8),
+	HelloTooLarge(u64),
+	/// The server negotiated a version or capabilities we did not offer.
+	InvalidServerHello(String),
+	UnexpectedItem {
+		expected: Opcode,
+		received: Opcode,
+	},
+	/// The state key is only provisioned together with the state.
+	StateKeyWithoutState(Vec<Opcode>),
+	/// The connection has been closed before all announced items have been received.
+	MissingItems(Vec<Opcode>),
+}
+
+impl core::fmt::Display for ProvisioningError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		write!(f, "{:?}", self)
+	}
+}
+
+impl std::error::Error for ProvisioningError {}
+
+/// Picks the highest protocol version both peers support.
+pub fn negotiate_version(hello: &ClientHello) -> Result<u16, RejectReason> {
+	let version = hello.max_version.min(PROVISIONING_PROTOCOL_VERSION);
+	if version < hello.min_version.max(MIN_PROVISIONING_PROTOCOL_VERSION) {
+		return Err(RejectReason::UnsupportedVersion {
+			min_version: MIN_PROVISIONING_PROTOCOL_VERSION,
+			max_version: PROVISIONING_PROTOCOL_VERSION,
+		})
+	}
+	Ok(version)
+}
+
+/// Items to provide: the requested ones out of the `provisionable` ones, if the client
+/// selects items, in the order of the `provisionable` items.
+pub fn granted_items(
+	hello: &ClientHello,
+	capabilities: Capabilities,
+	provisionable: &[Opcode],
+) -> Vec<Opcode> {
+	if !capabilities.contains(Capabilities::ITEM_SELECTION) {
+		return provisionable.to_vec()
+	}
+	provisionable
+		.iter()
+		.filter(|item| hello.requested_items.contains(item))
+		.copied()
+		.collect()
+}
+
+/// Ensures the `items` contain either both the state key and the state or none of them.
+///
+/// The provisioned state key replaces ours, so our sealed states could no longer be decrypted
+/// without also provisioning the state. A state without its key can't be decrypted either.
+pub fn ensure_state_key_with_state(items: &[Opcode]) -> Result<(), ProvisioningError> {
+	if items.contains(&Opcode::StateKey) != items.contains(&Opcode::State) {
+		return Err(ProvisioningError::StateKeyWithoutState(items.to_vec()))
+	}
+	Ok(())
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+	use super::*;
+
+	pub fn version_negotiation_picks_highest_common_version() {
+		let mut hello = ClientHello::new(ShardIdentifier::default(), vec![]);
+		hello.max_version = PROVISIONING_PROTOCOL_VERSION + 3;
+
+		assert_eq!(negotiate_version(&hello), Ok(PROVISIONING_PROTOCOL_VERSION));
+	}
+
+	pub fn version_negotiation_rejects_newer_client() {
+		let mut hello = ClientHello::new(ShardIdentifier::default(), vec![]);
+		hello.min_version = PROVISIONING_PROTOCOL_VERSION + 1;
+		hello.max_version = PROVISIONING_PROTOCOL_VERSION + 2;
+
+		assert_eq!(
+			negotiate_version(&hello),
+			Err(RejectReason::UnsupportedVersion {
+				min_version: MIN_PROVISIONING_PROTOCOL_VERSION,
+				max_version: PROVISIONING_PROTOCOL_VERSION,
+			})
+		);
+	}
+
+	pub fn granted_items_are_requested_and_provisionable() {
+		let hello =
+			ClientHello::new(ShardIdentifier::default(), vec![Opcode::State, Opcode::ShieldingKey]);
+		let provisionable = [Opcode::ShieldingKey, Opcode::LightClient];
+
+		assert_eq!(
+			granted_items(&hello, Capabilities::supported(), &provisionable),
+			vec![Opcode::ShieldingKey]
+		);
+		assert_eq!(
+			granted_items(&hello, Capabilities::default(), &provisionable),
+			provisionable.to_vec()
+		);
+	}
+
+	pub fn state_key_is_only_provisioned_with_the_state() {
+		assert!(ensure_state_key_with_state(&Opcode::ALL).is_ok());
+		assert!(ensure_state_key_with_state(&[Opcode::ShieldingKey, Opcode::LightClient]).is_ok());
+		assert_eq!(
+			ensure_state_key_with_state(&[Opcode::StateKey]),
+			Err(ProvisioningError::StateKeyWithoutState(vec![Opcode::StateKey]))
+		);
+		assert_eq!(
+			ensure_state_key_with_state(&[Opcode::ShieldingKey, Opcode::State]),
+			Err(ProvisioningError::StateKeyWithoutState(vec![Opcode::ShieldingKey, Opcode::State]))
+		);
+	}
+
+	pub fn handshake_magic_is_distinguishable_from_legacy_opcodes() {
+		assert!(Opcode::try_from(SERVER_HELLO_MARKER).is_err());
+		assert_eq!(HANDSHAKE_MAGIC.len(), ShardIdentifier::default().as_bytes().len());
+	}
+}
@@ -18,8 +18,8 @@
 //! Tests of tls-ra client / server communication.
 
 use super::{
-	mocks::SealHandlerMock, tls_ra_client::request_state_provisioning_internal,
-	tls_ra_server::run_state_provisioning_server_internal,
+	mocks::SealHandlerMock, protocol::Framing, tls_ra_client::request_state_provisioning_internal,
+	tls_ra_server::run_state_provisioning_server_internal, Opcode,
 };
 use crate::{
 	initialization::global_components::EnclaveStf,
@@ -61,6 +61,7 @@ fn run_state_provisioning_server(seal_handler: impl UnsealStateAndKeys, port: u1
 		Some(&sgx_target_info),
 		Some(&QUOTE_SIZE),
 		SKIP_RA,
+		vec![],
 		seal_handler,
 	)
 	.unwrap();
@@ -116,7 +117,10 @@ pub fn test_tls_ra_server_client_networking() {
 		Some(&sgx_target_info),
 		Some(&QUOTE_SIZE),
 		shard,
+		Opcode::ALL.to_vec(),
+		Framing::Versioned,
 		SKIP_RA,
+		vec![],
 		client_seal_handler.clone(),
 	);
 
@@ -137,6 +141,116 @@ pub fn test_tls_ra_server_client_networking() {
 	}
 }
 
+pub fn test_tls_ra_provisions_only_requested_items() {
+	let shard = ShardIdentifier::default();
+	let shielding_key_encoded = vec![1, 2, 3];
+	let light_client_state_encoded = vec![4, 5, 6];
+
+	let server_seal_handler = SealHandlerMock::new(
+		Arc::new(RwLock::new(shielding_key_encoded.clone())),
+		Arc::new(RwLock::new(vec![5, 2, 3, 7])),
+		Arc::new(RwLock::new(vec![1u8; 100])),
+		Arc::new(RwLock::new(light_client_state_encoded)),
+	);
+	let initial_client_light_client_state = vec![0, 0, 3];
+	let client_shielding_key = Arc::new(RwLock::new(Vec::new()));
+	let client_light_client_state =
+		Arc::new(RwLock::new(initial_client_light_client_state.clone()));
+
+	let client_seal_handler = SealHandlerMock::new(
+		client_shielding_key.clone(),
+		Arc::new(RwLock::new(Vec::new())),
+		Arc::new(RwLock::new(Vec::new())),
+		client_light_client_state.clone(),
+	);
+
+	let port: u16 = 3151;
+
+	// Start server.
+	let server_thread_handle = thread::spawn(move || {
+		run_state_provisioning_server(server_seal_handler, port);
+	});
+	thread::sleep(Duration::from_secs(1));
+
+	// Start client, only asking for the shielding key.
+	let socket = TcpStream::connect(server_addr(port)).unwrap();
+	let sgx_target_info: sgx_target_info_t = sgx_target_info_t::default();
+	let result = request_state_provisioning_internal(
+		socket.as_raw_fd(),
+		SIGN_TYPE,
+		Some(&sgx_target_info),
+		Some(&QUOTE_SIZE),
+		shard,
+		vec![Opcode::ShieldingKey],
+		Framing::Versioned,
+		SKIP_RA,
+		vec![],
+		client_seal_handler,
+	);
+
+	// Ensure server thread has finished.
+	server_thread_handle.join().unwrap();
+
+	assert!(result.is_ok());
+	assert_eq!(*client_shielding_key.read().unwrap(), shielding_key_encoded);
+	assert_eq!(*client_light_client_state.read().unwrap(), initial_client_light_client_state);
+}
+
+pub fn test_tls_ra_legacy_framing_seals_only_requested_items() {
+	let shard = ShardIdentifier::default();
+	let shielding_key_encoded = vec![1, 2, 3];
+	let light_client_state_encoded = vec![4, 5, 6];
+
+	let server_seal_handler = SealHandlerMock::new(
+		Arc::new(RwLock::new(shielding_key_encoded.clone())),
+		Arc::new(RwLock::new(vec![5, 2, 3, 7])),
+		Arc::new(RwLock::new(vec![1u8; 100])),
+		Arc::new(RwLock::new(light_client_state_encoded)),
+	);
+	let initial_client_light_client_state = vec![0, 0, 3];
+	let client_shielding_key = Arc::new(RwLock::new(Vec::new()));
+	let client_light_client_state =
+		Arc::new(RwLock::new(initial_client_light_client_state.clone()));
+
+	let client_seal_handler = SealHandlerMock::new(
+		client_shielding_key.clone(),
+		Arc::new(RwLock::new(Vec::new())),
+		Arc::new(RwLock::new(Vec::new())),
+		client_light_client_state.clone(),
+	);
+
+	let port: u16 = 3152;
+
+	// Start server.
+	let server_thread_handle = thread::spawn(move || {
+		run_state_provisioning_server(server_seal_handler, port);
+	});
+	thread::sleep(Duration::from_secs(1));
+
+	// Start client with the framing of the legacy protocol, only asking for the shielding key.
+	let socket = TcpStream::connect(server_addr(port)).unwrap();
+	let sgx_target_info: sgx_target_info_t = sgx_target_info_t::default();
+	let result = request_state_provisioning_internal(
+		socket.as_raw_fd(),
+		SIGN_TYPE,
+		Some(&sgx_target_info),
+		Some(&QUOTE_SIZE),
+		shard,
+		vec![Opcode::ShieldingKey],
+		Framing::Legacy,
+		SKIP_RA,
+		vec![],
+		client_seal_handler,
+	);
+
+	// Ensure server thread has finished.
+	server_thread_handle.join().unwrap();
+
+	assert!(result.is_ok());
+	assert_eq!(*client_shielding_key.read().unwrap(), shielding_key_encoded);
+	assert_eq!(*client_light_client_state.read().unwrap(), initial_client_light_client_state);
+}
+
 // Test state and key provisioning with 'real' data structures.
 pub fn test_state_and_key_provisioning() {
 	let state_key = Aes::new([3u8; 16], [0u8; 16]);
@@ -166,7 +280,10 @@ pub fn test_state_and_key_provisioning() {
 		Some(&sgx_target_info),
 		Some(&QUOTE_SIZE),
 		shard,
+		Opcode::ALL.to_vec(),
+		Framing::Versioned,
 		SKIP_RA,
+		vec![],
 		client_seal_handler,
 	);
 
@@ -17,7 +17,16 @@
 
 //! Implementation of the client part of the state provisioning.
 
-use super::{authentication::ServerAuth, Opcode, TcpHeader};
+use super::{
+	accepted_mrenclaves,
+	authentication::ServerAuth,
+	protocol::{
+		ensure_state_key_with_state, Capabilities, ClientHello, Framing, ProvisioningError,
+		ServerHello, HANDSHAKE_MAGIC, MAX_HELLO_LENGTH, MIN_PROVISIONING_PROTOCOL_VERSION,
+		PROVISIONING_PROTOCOL_VERSION, SERVER_HELLO_MARKER,
+	},
+	Opcode, TcpHeader,
+};
 use crate::{
 	attestation::create_ra_report_and_signature,
 	error::{Error as EnclaveError, Result as EnclaveResult},
@@ -29,6 +38,7 @@ use crate::{
 	tls_ra::seal_handler::SealStateAndKeys,
 	GLOBAL_STATE_HANDLER_COMPONENT,
 };
+use codec::{Decode, Encode};
 use itp_attestation_handler::{RemoteAttestationType, DEV_HOSTNAME};
 use itp_component_container::ComponentGetter;
 use itp_ocall_api::EnclaveAttestationOCallApi;
@@ -55,6 +65,7 @@ where
 	tls_stream: Stream<'a, ClientSession, TcpStream>,
 	seal_handler: StateAndKeySealer,
 	shard: ShardIdentifier,
+	requested_items: Vec<Opcode>,
 }
 
 impl<'a, StateAndKeySealer> TlsClient<'a, StateAndKeySealer>
@@ -65,8 +76,9 @@ where
 		tls_stream: Stream<'a, ClientSession, TcpStream>,
 		seal_handler: StateAndKeySealer,
 		shard: ShardIdentifier,
+		requested_items: Vec<Opcode>,
 	) -> TlsClient<StateAndKeySealer> {
-		TlsClient { tls_stream, seal_handler, shard }
+		TlsClient { tls_stream, seal_handler, shard, requested_items }
 	}
 
 	/// Read all data sent by the server of the specific shard.
@@ -74,32 +86,146 @@ where
 	/// We trust here that the server sends us the correct data, as
 	/// we do not have any way to test it.
 	fn read_shard(&mut self) -> EnclaveResult<()> {
-		debug!("read_shard called, about to call self.write_shard().");
-		self.write_shard()?;
-		debug!("self.write_shard() succeeded.");
-		self.read_and_seal_all()
+		debug!("read_shard called, about to call self.write_client_hello().");
+		self.write_client_hello()?;
+		let (capabilities, items) = self.read_server_hello()?;
+		debug!("Server announced items {:?} with capabilities {:?}", items, capabilities);
+
+		// Nothing is sealed before all items have been received and checked.
+		let payloads = self.read_items(&items)?;
+		self.seal_all(payloads)
 	}
 
-	/// Send the shard of the state we want to receive to the provisioning server.
-	fn write_shard(&mut self) -> EnclaveResult<()> {
-		debug!("self.write_shard() called.");
+	/// Read the data sent by a server of the legacy protocol, which only expects the shard.
+	///
+	/// The server sends everything its worker mode allows until it closes the connection. Items
+	/// that have not been requested are dropped.
+	fn read_shard_legacy(&mut self) -> EnclaveResult<()> {
+		debug!("read_shard_legacy called, writing the shard.");
 		self.tls_stream.write_all(self.shard.as_bytes())?;
-		debug!("write_all succeeded.");
+
+		let mut payloads = Vec::new();
+		loop {
+			let mut start_byte = [0u8; 1];
+			// If we're reading but there's no data: EOF.
+			if self.tls_stream.read(&mut start_byte)? == 0 {
+				break
+			}
+			let header = self.read_header(start_byte[0])?;
+			let bytes = self.read_until(header.payload_length as usize)?;
+			if self.requested_items.contains(&header.opcode) {
+				payloads.push((header.opcode, bytes));
+			} else {
+				debug!("Dropping {:?}, which has not been requested", header.opcode);
+			}
+		}
+		self.seal_all(payloads)
+	}
+
+	/// Send the handshake, including the shard of the state we want to receive.
+	fn write_client_hello(&mut self) -> EnclaveResult<()> {
+		let hello = ClientHello::new(self.shard, self.requested_items.clone()).encode();
+		self.tls_stream.write_all(&HANDSHAKE_MAGIC)?;
+		self.tls_stream.write_all(&(hello.len() as u64).to_be_bytes())?;
+		self.tls_stream.write_all(&hello)?;
+		debug!("write_client_hello succeeded.");
 		Ok(())
 	}
 
-	/// Read and seal all relevant data sent by the server.
-	fn read_and_seal_all(&mut self) -> EnclaveResult<()> {
-		let mut received_payloads: Vec<Opcode> = Vec::new();
+	/// Reads the server hello and returns the negotiated capabilities and the announced items.
+	fn read_server_hello(&mut self) -> EnclaveResult<(Capabilities, Vec<Opcode>)> {
+		let mut start_byte = [0u8; 1];
+		self.tls_stream.read_exact(&mut start_byte)?;
+		if start_byte[0] != SERVER_HELLO_MARKER {
+			// A legacy server took our handshake for a shard and started sending payloads.
+			return match Opcode::try_from(start_byte[0]) {
+				Ok(_) => Err(ProvisioningError::LegacyServer.into()),
+				Err(e) => Err(e.into()),
+			}
+		}
+
+		let mut length_buffer = [0u8; std::mem::size_of::<u64>()];
+		self.tls_stream.read_exact(&mut length_buffer)?;
+		let length = u64::from_be_bytes(length_buffer);
+		if length > MAX_HELLO_LENGTH {
+			return Err(ProvisioningError::HelloTooLarge(length).into())
+		}
+		let encoded_hello = self.read_until(length as usize)?;
+
+		match ServerHello::decode(&mut encoded_hello.as_slice())? {
+			ServerHello::Rejected(reason) => Err(ProvisioningError::Rejected(reason).into()),
+			ServerHello::Accepted { version, capabilities, items } => {
+				self.check_server_hello(version, capabilities, &items)?;
+				info!("Negotiated state provisioning protocol version {}", version);
+				Ok((capabilities, items))
+			},
+		}
+	}
 
-		loop {
-			let maybe_opcode = self.read_and_seal()?;
-			match maybe_opcode {
-				None => break,
-				Some(o) => {
-					received_payloads.push(o);
-				},
+	fn check_server_hello(
+		&self,
+		version: u16,
+		capabilities: Capabilities,
+		items: &[Opcode],
+	) -> Result<(), ProvisioningError> {
+		if !(MIN_PROVISIONING_PROTOCOL_VERSION..=PROVISIONING_PROTOCOL_VERSION).contains(&version) {
+			return Err(ProvisioningError::InvalidServerHello(format!(
+				"Unsupported version {}",
+				version
+			)))
+		}
+		if capabilities.intersection(Capabilities::supported()) != capabilities {
+			return Err(ProvisioningError::InvalidServerHello(format!(
+				"Capabilities {:?} have not been offered",
+				capabilities
+			)))
+		}
+		if capabilities.contains(Capabilities::ITEM_SELECTION)
+			&& items.iter().any(|item| !self.requested_items.contains(item))
+		{
+			return Err(ProvisioningError::InvalidServerHello(format!(
+				"Items {:?} have not been requested",
+				items
+			)))
+		}
+		ensure_state_key_with_state(items)
+	}
+
+	/// Reads the announced items in order.
+	fn read_items(&mut self, items: &[Opcode]) -> EnclaveResult<Vec<(Opcode, Vec<u8>)>> {
+		let mut payloads = Vec::with_capacity(items.len());
+
+		for (index, expected) in items.iter().enumerate() {
+			let mut start_byte = [0u8; 1];
+			if self.tls_stream.read(&mut start_byte)? == 0 {
+				return Err(ProvisioningError::MissingItems(items[index..].to_vec()).into())
 			}
+			let header = self.read_header(start_byte[0])?;
+			if header.opcode != *expected {
+				return Err(ProvisioningError::UnexpectedItem {
+					expected: *expected,
+					received: header.opcode,
+				}
+				.into())
+			}
+			let bytes = self.read_until(header.payload_length as usize)?;
+			payloads.push((header.opcode, bytes));
+		}
+		Ok(payloads)
+	}
+
+	/// Seal all received items.
+	fn seal_all(&mut self, payloads: Vec<(Opcode, Vec<u8>)>) -> EnclaveResult<()> {
+		let received_payloads: Vec<Opcode> = payloads.iter().map(|(opcode, _)| *opcode).collect();
+		ensure_state_key_with_state(&received_payloads)?;
+
+		for (opcode, bytes) in payloads {
+			match opcode {
+				Opcode::ShieldingKey => self.seal_handler.seal_shielding_key(&bytes)?,
+				Opcode::StateKey => self.seal_handler.seal_state_key(&bytes)?,
+				Opcode::State => self.seal_handler.seal_state(&bytes, &self.shard)?,
+				Opcode::LightClient => self.seal_handler.seal_light_client_state(&bytes)?,
+			};
 		}
 		info!("Successfully read and sealed all data sent by the state provisioning server.");
 
@@ -114,30 +240,11 @@ where
 		Ok(())
 	}
 
-	/// Read a server header / payload pair and directly seal the received data.
-	fn read_and_seal(&mut self) -> EnclaveResult<Option<Opcode>> {
-		let mut start_byte = [0u8; 1];
-		let read_size = self.tls_stream.read(&mut start_byte)?;
-		// If we're reading but there's no data: EOF.
-		if read_size == 0 {
-			return Ok(None)
-		}
-		let header = self.read_header(start_byte[0])?;
-		let bytes = self.read_until(header.payload_length as usize)?;
-		match header.opcode {
-			Opcode::ShieldingKey => self.seal_handler.seal_shielding_key(&bytes)?,
-			Opcode::StateKey => self.seal_handler.seal_state_key(&bytes)?,
-			Opcode::State => self.seal_handler.seal_state(&bytes, &self.shard)?,
-			Opcode::LightClient => self.seal_handler.seal_light_client_state(&bytes)?,
-		};
-		Ok(Some(header.opcode))
-	}
-
 	/// Reads the payload header, indicating the sent payload length and type.
 	fn read_header(&mut self, start_byte: u8) -> EnclaveResult<TcpHeader> {
 		debug!("Read first byte: {:?}", start_byte);
 		// The first sent byte indicates the payload type.
-		let opcode: Opcode = start_byte.into();
+		let opcode = Opcode::try_from(start_byte)?;
 		debug!("Read header opcode: {:?}", opcode);
 		// The following bytes contain the payload length, which is a u64.
 		let mut payload_length_buffer = [0u8; std::mem::size_of::<u64>()];
@@ -156,7 +263,14 @@ where
 	}
 }
 
+/// Returned by [`request_state_provisioning`] if the server only speaks the legacy protocol.
+///
+/// The untrusted side is then expected to reconnect and request again with the legacy framing.
+pub const LEGACY_SERVER_STATUS: sgx_status_t = sgx_status_t::SGX_ERROR_UNSUPPORTED_FEATURE;
+
+/// Request the `items` (opcodes, all items if empty) of the `shard` from a provisioning server.
 #[no_mangle]
+#[allow(clippy::too_many_arguments)]
 pub unsafe extern "C" fn request_state_provisioning(
 	socket_fd: c_int,
 	sign_type: sgx_quote_sign_type_t,
@@ -164,11 +278,34 @@ pub unsafe extern "C" fn request_state_provisioning(
 	quote_size: Option<&u32>,
 	shard: *const u8,
 	shard_size: u32,
+	items: *const u8,
+	items_size: u32,
+	legacy_framing: c_int,
 	skip_ra: c_int,
 ) -> sgx_status_t {
 	let _ = backtrace::enable_backtrace("enclave.signed.so", PrintFormat::Short);
 	let shard = ShardIdentifier::from_slice(slice::from_raw_parts(shard, shard_size as usize));
 
+	let items: &[u8] =
+		if items.is_null() { &[] } else { slice::from_raw_parts(items, items_size as usize) };
+	let requested_items = match items
+		.iter()
+		.map(|opcode| Opcode::try_from(*opcode))
+		.collect::<Result<Vec<_>, _>>()
+	{
+		Ok(items) if items.is_empty() => Opcode::ALL.to_vec(),
+		Ok(items) => items,
+		Err(e) => {
+			error!("Invalid provisioning items: {:?}", e);
+			return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+		},
+	};
+	if let Err(e) = ensure_state_key_with_state(&requested_items) {
+		error!("Invalid provisioning items: {:?}", e);
+		return sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+	}
+	let framing = if legacy_framing == 1 { Framing::Legacy } else { Framing::Versioned };
+
 	let state_handler = match GLOBAL_STATE_HANDLER_COMPONENT.get() {
 		Ok(s) => s,
 		Err(e) => {
@@ -214,24 +351,34 @@ pub unsafe extern "C" fn request_state_provisioning(
 		quoting_enclave_target_info,
 		quote_size,
 		shard,
+		requested_items,
+		framing,
 		skip_ra,
+		accepted_mrenclaves(&[shard]),
 		seal_handler,
 	) {
 		error!("Failed to sync state due to: {:?}", e);
-		return e.into()
+		return match e {
+			EnclaveError::Provisioning(ProvisioningError::LegacyServer) => LEGACY_SERVER_STATUS,
+			e => e.into(),
+		}
 	};
 
 	sgx_status_t::SGX_SUCCESS
 }
 
 /// Internal [`request_state_provisioning`] function to be able to use the handy `?` operator.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn request_state_provisioning_internal<StateAndKeySealer: SealStateAndKeys>(
 	socket_fd: c_int,
 	sign_type: sgx_quote_sign_type_t,
 	quoting_enclave_target_info: Option<&sgx_target_info_t>,
 	quote_size: Option<&u32>,
 	shard: ShardIdentifier,
+	requested_items: Vec<Opcode>,
+	framing: Framing,
 	skip_ra: c_int,
+	accepted_mrenclaves: Vec<[u8; 32]>,
 	seal_handler: StateAndKeySealer,
 ) -> EnclaveResult<()> {
 	debug!("Client config generate...");
@@ -241,6 +388,7 @@ pub(crate) fn request_state_provisioning_internal<StateAndKeySealer: SealStateAn
 		quote_size,
 		OcallApi,
 		skip_ra == 1,
+		accepted_mrenclaves,
 	)?;
 	debug!("Client config retrieved");
 	let (mut client_session, mut tcp_stream) = tls_client_session_stream(socket_fd, client_config)?;
@@ -250,10 +398,14 @@ pub(crate) fn request_state_provisioning_internal<StateAndKeySealer: SealStateAn
 		rustls::Stream::new(&mut client_session, &mut tcp_stream),
 		seal_handler,
 		shard,
+		requested_items,
 	);
 
 	info!("Requesting keys and state from mu-ra server of fellow validateer");
-	client.read_shard()
+	match framing {
+		Framing::Versioned => client.read_shard(),
+		Framing::Legacy => client.read_shard_legacy(),
+	}
 }
 
 fn tls_client_config<A: EnclaveAttestationOCallApi + 'static>(
@@ -262,6 +414,7 @@ fn tls_client_config<A: EnclaveAttestationOCallApi + 'static>(
 	quote_size: Option<&u32>,
 	ocall_api: A,
 	skip_ra: bool,
+	accepted_mrenclaves: Vec<[u8; 32]>,
 ) -> EnclaveResult<ClientConfig> {
 	#[cfg(not(feature = "dcap"))]
 	let attestation_type = RemoteAttestationType::Epid;
@@ -282,8 +435,12 @@ fn tls_client_config<A: EnclaveAttestationOCallApi + 'static>(
 	let privkey = rustls::PrivateKey(key_der);
 
 	cfg.set_single_client_cert(certs, privkey).unwrap();
-	cfg.dangerous()
-		.set_certificate_verifier(Arc::new(ServerAuth::new(true, skip_ra, ocall_api)));
+	cfg.dangerous().set_certificate_verifier(Arc::new(ServerAuth::new(
+		true,
+		skip_ra,
+		accepted_mrenclaves,
+		ocall_api,
+	)));
 	cfg.versions.clear();
 	cfg.versions.push(rustls::ProtocolVersion::TLSv1_2);
 	Ok(cfg)
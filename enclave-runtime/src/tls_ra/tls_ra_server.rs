@@ -17,7 +17,16 @@
 
 //! Implementation of the server part of the state provisioning.
 
-use super::{authentication::ClientAuth, Opcode, TcpHeader};
+use super::{
+	accepted_mrenclaves,
+	authentication::ClientAuth,
+	protocol::{
+		ensure_state_key_with_state, granted_items, negotiate_version, Capabilities, ClientHello,
+		ProvisioningError, RejectReason, ServerHello, HANDSHAKE_MAGIC, MAX_HELLO_LENGTH,
+		SERVER_HELLO_MARKER,
+	},
+	Opcode, TcpHeader,
+};
 use crate::{
 	attestation::create_ra_report_and_signature,
 	error::{Error as EnclaveError, Result as EnclaveResult},
@@ -29,10 +38,12 @@ use crate::{
 	tls_ra::seal_handler::UnsealStateAndKeys,
 	GLOBAL_STATE_HANDLER_COMPONENT,
 };
+use codec::{Decode, Encode};
 use itp_attestation_handler::RemoteAttestationType;
 use itp_component_container::ComponentGetter;
 use itp_ocall_api::EnclaveAttestationOCallApi;
 use itp_settings::worker_mode::{ProvideWorkerMode, WorkerMode, WorkerModeProvider};
+use itp_stf_state_handler::query_shard_state::QueryShardState;
 use itp_types::ShardIdentifier;
 use log::*;
 use rustls::{ServerConfig, ServerSession, StreamOwned};
@@ -42,6 +53,7 @@ use std::{
 	io::{Read, Write},
 	net::TcpStream,
 	sync::Arc,
+	vec::Vec,
 };
 
 #[derive(Clone, Eq, PartialEq, Debug)]
@@ -50,6 +62,17 @@ enum ProvisioningPayload {
 	ShieldingKeyAndLightClient,
 }
 
+impl ProvisioningPayload {
+	/// Items we are allowed to provide, in the order they are sent.
+	fn items(&self) -> Vec<Opcode> {
+		match self {
+			ProvisioningPayload::Everything => Opcode::ALL.to_vec(),
+			ProvisioningPayload::ShieldingKeyAndLightClient =>
+				vec![Opcode::ShieldingKey, Opcode::LightClient],
+		}
+	}
+}
+
 impl From<WorkerMode> for ProvisioningPayload {
 	fn from(m: WorkerMode) -> Self {
 		match m {
@@ -82,65 +105,98 @@ where
 	}
 
 	/// Sends all relevant data of the specific shard to the client.
+	///
+	/// Clients of the versioned protocol start with the [`HANDSHAKE_MAGIC`], legacy clients
+	/// send the shard right away.
 	fn write_shard(&mut self) -> EnclaveResult<()> {
-		println!("    [Enclave] (MU-RA-Server) write_shard, calling read_shard()");
-		let shard = self.read_shard()?;
-		println!("    [Enclave] (MU-RA-Server) write_shard, read_shard() OK");
-		println!("    [Enclave] (MU-RA-Server) write_shard, write_all()");
-		self.write_all(&shard)
+		let mut first_bytes = [0u8; 32];
+		println!("    [Enclave] (MU-RA-Server) write_shard, calling read_exact()");
+		self.tls_stream.read_exact(&mut first_bytes)?;
+
+		if first_bytes == HANDSHAKE_MAGIC {
+			self.write_negotiated_items()
+		} else {
+			info!("Provisioning a client of the legacy protocol");
+			let shard = ShardIdentifier::from(first_bytes);
+			let items = self.provisioning_payload.items();
+			for (opcode, payload) in self.unseal_items(&items, &shard)? {
+				self.write(opcode, &payload)?;
+			}
+			debug!("Successfully provisioned all payloads to peer");
+			Ok(())
+		}
 	}
 
-	/// Read the shard of the state the client wants to receive.
-	fn read_shard(&mut self) -> EnclaveResult<ShardIdentifier> {
-		let mut shard_holder = ShardIdentifier::default();
-		let shard = shard_holder.as_fixed_bytes_mut();
-		println!("    [Enclave] (MU-RA-Server) read_shard, calling read_exact()");
-		self.tls_stream.read_exact(shard)?;
-		Ok(shard.into())
-	}
+	/// Answers the client hello and sends the negotiated items.
+	fn write_negotiated_items(&mut self) -> EnclaveResult<()> {
+		let hello = self.read_client_hello()?;
+		debug!("Received client hello: {:?}", hello);
 
-	/// Sends all relevant data to the client.
-	fn write_all(&mut self, shard: &ShardIdentifier) -> EnclaveResult<()> {
-		debug!("Provisioning is set to: {:?}", self.provisioning_payload);
-		match self.provisioning_payload {
-			ProvisioningPayload::Everything => {
-				self.write_shielding_key()?;
-				self.write_state_key()?;
-				self.write_state(shard)?;
-				self.write_light_client_state()?;
-			},
-			ProvisioningPayload::ShieldingKeyAndLightClient => {
-				self.write_shielding_key()?;
-				self.write_light_client_state()?;
+		let version = match negotiate_version(&hello) {
+			Ok(version) => version,
+			Err(reason) => {
+				self.write_server_hello(&ServerHello::Rejected(reason.clone()))?;
+				return Err(ProvisioningError::Rejected(reason).into())
 			},
+		};
+		let capabilities = hello.capabilities.intersection(Capabilities::supported());
+		let items = granted_items(&hello, capabilities, &self.provisioning_payload.items());
+		if let Err(e) = ensure_state_key_with_state(&items) {
+			self.write_server_hello(&ServerHello::Rejected(RejectReason::StateKeyWithoutState))?;
+			return Err(e.into())
 		}
 
-		debug!("Successfully provisioned all payloads to peer");
-		Ok(())
-	}
+		// Unseal everything before announcing it, so we never announce what we can't send.
+		let payloads = self.unseal_items(&items, &hello.shard)?;
+		self.write_server_hello(&ServerHello::Accepted { version, capabilities, items })?;
+
+		for (opcode, payload) in payloads {
+			self.write(opcode, &payload)?;
+		}
 
-	fn write_shielding_key(&mut self) -> EnclaveResult<()> {
-		let shielding_key = self.seal_handler.unseal_shielding_key()?;
-		self.write(Opcode::ShieldingKey, &shielding_key)?;
+		debug!("Successfully provisioned {:?} with protocol version {}", capabilities, version);
 		Ok(())
 	}
 
-	fn write_state_key(&mut self) -> EnclaveResult<()> {
-		let state_key = self.seal_handler.unseal_state_key()?;
-		self.write(Opcode::StateKey, &state_key)?;
-		Ok(())
+	fn read_client_hello(&mut self) -> EnclaveResult<ClientHello> {
+		let mut length_buffer = [0u8; std::mem::size_of::<u64>()];
+		self.tls_stream.read_exact(&mut length_buffer)?;
+		let length = u64::from_be_bytes(length_buffer);
+		if length > MAX_HELLO_LENGTH {
+			return Err(ProvisioningError::HelloTooLarge(length).into())
+		}
+
+		let mut encoded_hello = vec![0u8; length as usize];
+		self.tls_stream.read_exact(&mut encoded_hello)?;
+		Ok(ClientHello::decode(&mut encoded_hello.as_slice())?)
 	}
 
-	fn write_state(&mut self, shard: &ShardIdentifier) -> EnclaveResult<()> {
-		let state = self.seal_handler.unseal_state(shard)?;
-		self.write(Opcode::State, &state)?;
+	fn write_server_hello(&mut self, hello: &ServerHello) -> EnclaveResult<()> {
+		let encoded_hello = hello.encode();
+		self.tls_stream.write_all(&[SERVER_HELLO_MARKER])?;
+		self.tls_stream.write_all(&(encoded_hello.len() as u64).to_be_bytes())?;
+		self.tls_stream.write_all(&encoded_hello)?;
 		Ok(())
 	}
 
-	fn write_light_client_state(&mut self) -> EnclaveResult<()> {
-		let state = self.seal_handler.unseal_light_client_state()?;
-		self.write(Opcode::LightClient, &state)?;
-		Ok(())
+	fn unseal_items(
+		&self,
+		items: &[Opcode],
+		shard: &ShardIdentifier,
+	) -> EnclaveResult<Vec<(Opcode, Vec<u8>)>> {
+		debug!("Unsealing provisioning items {:?}", items);
+		items
+			.iter()
+			.map(|opcode| {
+				let payload = match opcode {
+					Opcode::ShieldingKey => self.seal_handler.unseal_shielding_key()?,
+					Opcode::StateKey => self.seal_handler.unseal_state_key()?,
+					Opcode::State => self.seal_handler.unseal_state(shard)?,
+					Opcode::LightClient => self.seal_handler.unseal_light_client_state()?,
+				};
+				Ok((*opcode, payload))
+			})
+			.collect()
 	}
 
 	/// Sends the header followed by the payload.
@@ -206,6 +262,16 @@ pub unsafe extern "C" fn run_state_provisioning_server(
 		},
 	};
 
+	// The shard is only known after the handshake, so we accept the enclaves authorized for
+	// any of our shards.
+	let accepted_mrenclaves = match state_handler.list_shards() {
+		Ok(shards) => accepted_mrenclaves(&shards),
+		Err(e) => {
+			error!("{:?}", e);
+			return sgx_status_t::SGX_ERROR_UNEXPECTED
+		},
+	};
+
 	let seal_handler = EnclaveSealHandler::new(
 		state_handler,
 		state_key_repository,
@@ -219,6 +285,7 @@ pub unsafe extern "C" fn run_state_provisioning_server(
 		quoting_enclave_target_info,
 		quote_size,
 		skip_ra,
+		accepted_mrenclaves,
 		seal_handler,
 	) {
 		error!("Failed to provision state due to: {:?}", e);
@@ -238,6 +305,7 @@ pub(crate) fn run_state_provisioning_server_internal<
 	quoting_enclave_target_info: Option<&sgx_target_info_t>,
 	quote_size: Option<&u32>,
 	skip_ra: c_int,
+	accepted_mrenclaves: Vec<[u8; 32]>,
 	seal_handler: StateAndKeyUnsealer,
 ) -> EnclaveResult<()> {
 	let server_config = tls_server_config(
@@ -246,6 +314,7 @@ pub(crate) fn run_state_provisioning_server_internal<
 		quote_size,
 		OcallApi,
 		skip_ra == 1,
+		accepted_mrenclaves,
 	)?;
 	let (server_session, tcp_stream) = tls_server_session_stream(socket_fd, server_config)?;
 	let provisioning = ProvisioningPayload::from(WorkerModeProvider::worker_mode());
@@ -273,6 +342,7 @@ fn tls_server_config<A: EnclaveAttestationOCallApi + 'static>(
 	quote_size: Option<&u32>,
 	ocall_api: A,
 	skip_ra: bool,
+	accepted_mrenclaves: Vec<[u8; 32]>,
 ) -> EnclaveResult<ServerConfig> {
 	#[cfg(not(feature = "dcap"))]
 	let attestation_type = RemoteAttestationType::Epid;
@@ -287,7 +357,12 @@ fn tls_server_config<A: EnclaveAttestationOCallApi + 'static>(
 		quote_size,
 	)?;
 
-	let mut cfg = rustls::ServerConfig::new(Arc::new(ClientAuth::new(true, skip_ra, ocall_api)));
+	let mut cfg = rustls::ServerConfig::new(Arc::new(ClientAuth::new(
+		true,
+		skip_ra,
+		accepted_mrenclaves,
+		ocall_api,
+	)));
 	let certs = vec![rustls::Certificate(cert_der)];
 	let privkey = rustls::PrivateKey(key_der);
 	cfg.set_single_cert_with_ocsp_and_sct(certs, privkey, vec![], vec![])
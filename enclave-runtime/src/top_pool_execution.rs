@@ -18,7 +18,9 @@
 use crate::{
 	error::Result,
 	initialization::global_components::{
-		GLOBAL_OCALL_API_COMPONENT, GLOBAL_SIDECHAIN_BLOCK_COMPOSER_COMPONENT,
+		GLOBAL_APPLIED_SIDECHAIN_BLOCKS_COMPONENT, GLOBAL_OCALL_API_COMPONENT,
+		GLOBAL_SIDECHAIN_BLOCK_COMPOSER_COMPONENT, GLOBAL_SIDECHAIN_BLOCK_STATES_COMPONENT,
+		GLOBAL_SIDECHAIN_FINALITY_TRACKER_COMPONENT,
 		GLOBAL_SIDECHAIN_IMPORT_QUEUE_WORKER_COMPONENT, GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT,
 		GLOBAL_STATE_HANDLER_COMPONENT, GLOBAL_TOP_POOL_AUTHOR_COMPONENT,
 	},
@@ -53,7 +55,9 @@ use its_primitives::{
 };
 use its_sidechain::{
 	aura::{proposer_factory::ProposerFactory, Aura, SlotClaimStrategy},
-	consensus_common::{Environment, Error as ConsensusError, ProcessBlockImportQueue},
+	consensus_common::{
+		Environment, Error as ConsensusError, ProcessBlockImportQueue, TrackSidechainFinality,
+	},
 	slots::{yield_next_slot, LastSlot, PerShardSlotWorkerScheduler, SlotInfo},
 	validateer_fetch::ValidateerFetch,
 };
@@ -122,6 +126,8 @@ fn execute_top_pool_trusted_calls_internal() -> Result<()> {
 	let top_pool_author = GLOBAL_TOP_POOL_AUTHOR_COMPONENT.get()?;
 
 	let block_composer = GLOBAL_SIDECHAIN_BLOCK_COMPOSER_COMPONENT.get()?;
+	let block_states = GLOBAL_SIDECHAIN_BLOCK_STATES_COMPONENT.get()?;
+	let applied_blocks = GLOBAL_APPLIED_SIDECHAIN_BLOCKS_COMPONENT.get()?;
 
 	let extrinsics_factory = get_extrinsic_factory_from_solo_or_parachain()?;
 
@@ -131,6 +137,17 @@ fn execute_top_pool_trusted_calls_internal() -> Result<()> {
 
 	let authority = GLOBAL_SIGNING_KEY_REPOSITORY_COMPONENT.get()?.retrieve_key()?;
 
+	let shards = state_handler.list_shards()?;
+
+	// Sidechain blocks become final once their confirmation is included in a finalized
+	// parentchain block.
+	let finality_tracker = GLOBAL_SIDECHAIN_FINALITY_TRACKER_COMPONENT.get()?;
+	for shard in shards.iter() {
+		if let Err(e) = finality_tracker.update_finality(shard, &current_parentchain_header) {
+			warn!("Failed to update sidechain finality of shard {:?}: {:?}", shard, e);
+		}
+	}
+
 	match yield_next_slot(
 		slot_beginning_timestamp,
 		SLOT_DURATION,
@@ -145,11 +162,12 @@ fn execute_top_pool_trusted_calls_internal() -> Result<()> {
 
 			log_remaining_slot_duration(&slot, "Before AURA");
 
-			let shards = state_handler.list_shards()?;
 			let env = ProposerFactory::<Block, _, _, _>::new(
 				top_pool_author,
 				stf_executor,
 				block_composer,
+				block_states,
+				applied_blocks,
 			);
 
 			let (blocks, opaque_calls) = exec_aura_on_slot::<_, _, SignedSidechainBlock, _, _, _>(
@@ -0,0 +1,44 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! ECALL to seal the pending trusted operations, so they survive a restart of the enclave.
+//! They are restored during the enclave initialization.
+
+use crate::{
+	error::Result, initialization::global_components::GLOBAL_TOP_POOL_PERSISTENCE_COMPONENT,
+};
+use itp_component_container::ComponentGetter;
+use itp_top_pool_author::persistence::PersistTopPool;
+use log::*;
+use sgx_types::sgx_status_t;
+
+#[no_mangle]
+pub unsafe extern "C" fn seal_top_pool() -> sgx_status_t {
+	if let Err(e) = seal_top_pool_internal() {
+		error!("Failed to seal the trusted operation pool: {:?}", e);
+		return e.into()
+	}
+
+	sgx_status_t::SGX_SUCCESS
+}
+
+fn seal_top_pool_internal() -> Result<()> {
+	let top_pool_persistence = GLOBAL_TOP_POOL_PERSISTENCE_COMPONENT.get()?;
+	let sealed = top_pool_persistence.seal_pending_operations()?;
+	trace!("Sealed {} pending trusted operations", sealed);
+	Ok(())
+}
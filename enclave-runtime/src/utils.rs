@@ -28,6 +28,8 @@ use crate::{
 };
 use codec::{Decode, Input};
 use itp_component_container::ComponentGetter;
+use itp_sgx_crypto::Aes;
+use sgx_rand::{Rng, StdRng};
 use std::{result::Result as StdResult, slice, sync::Arc};
 
 /// Helper trait to transform the sgx-ffi pointers to any type that implements
@@ -108,6 +110,32 @@ pub(crate) fn get_validator_accessor_from_solo_or_parachain(
 	Ok(validator_accessor)
 }
 
+pub(crate) fn get_validator_accessor_from_target_a_solo_or_parachain(
+) -> Result<Arc<EnclaveValidatorAccessor>> {
+	let validator_accessor =
+		if let Ok(solochain_handler) = GLOBAL_TARGET_A_SOLOCHAIN_HANDLER_COMPONENT.get() {
+			solochain_handler.validator_accessor.clone()
+		} else if let Ok(parachain_handler) = GLOBAL_TARGET_A_PARACHAIN_HANDLER_COMPONENT.get() {
+			parachain_handler.validator_accessor.clone()
+		} else {
+			return Err(Error::NoTargetAParentchainAssigned)
+		};
+	Ok(validator_accessor)
+}
+
+pub(crate) fn get_validator_accessor_from_target_b_solo_or_parachain(
+) -> Result<Arc<EnclaveValidatorAccessor>> {
+	let validator_accessor =
+		if let Ok(solochain_handler) = GLOBAL_TARGET_B_SOLOCHAIN_HANDLER_COMPONENT.get() {
+			solochain_handler.validator_accessor.clone()
+		} else if let Ok(parachain_handler) = GLOBAL_TARGET_B_PARACHAIN_HANDLER_COMPONENT.get() {
+			parachain_handler.validator_accessor.clone()
+		} else {
+			return Err(Error::NoTargetBParentchainAssigned)
+		};
+	Ok(validator_accessor)
+}
+
 pub(crate) fn get_node_metadata_repository_from_integritee_solo_or_parachain(
 ) -> Result<Arc<EnclaveNodeMetadataRepository>> {
 	let metadata_repository =
@@ -171,3 +199,14 @@ pub(crate) fn get_stf_executor_from_solo_or_parachain() -> Result<Arc<EnclaveStf
 		};
 	Ok(stf_executor)
 }
+
+/// Generates a fresh AES key, e.g. to encrypt a single payload for a third party.
+pub(crate) fn random_aes_key() -> Result<Aes> {
+	let mut key = [0u8; 16];
+	let mut init_vec = [0u8; 16];
+	let mut rand = StdRng::new()?;
+
+	rand.fill_bytes(&mut key);
+	rand.fill_bytes(&mut init_vec);
+	Ok(Aes::new(key, init_vec))
+}
@@ -17,12 +17,19 @@
 
 use clap::ArgMatches;
 use itc_rest_client::rest_client::Url;
+use itp_enclave_api::remote_attestation::ProvisioningItem;
 use itp_settings::teeracle::{DEFAULT_MARKET_DATA_UPDATE_INTERVAL, ONE_DAY, THIRTY_MINUTES};
+use itp_types::{
+	parentchain::ParentchainId,
+	personhood::{PersonhoodSourceConfig, PersonhoodStorageLayout},
+};
+use its_storage::PruningPolicy;
 use parse_duration::parse;
 use serde::{Deserialize, Serialize};
 use std::{
 	fs,
 	path::{Path, PathBuf},
+	str::FromStr,
 	time::Duration,
 };
 
@@ -40,6 +47,10 @@ pub struct Config {
 	integritee_rpc_port: String,
 	target_a_parentchain_rpc_url: Option<String>,
 	target_a_parentchain_rpc_port: Option<String>,
+	/// Relay chain of the Target A parachain. If set, the Target A light client follows the
+	/// relay chain finality instead of trusting the parachain headers.
+	target_a_relay_chain_rpc_url: Option<String>,
+	target_a_relay_chain_rpc_port: Option<String>,
 	target_b_parentchain_rpc_url: Option<String>,
 	target_b_parentchain_rpc_port: Option<String>,
 	worker_ip: String,
@@ -74,6 +85,8 @@ impl Config {
 		integritee_rpc_port: String,
 		target_a_parentchain_rpc_url: Option<String>,
 		target_a_parentchain_rpc_port: Option<String>,
+		target_a_relay_chain_rpc_url: Option<String>,
+		target_a_relay_chain_rpc_port: Option<String>,
 		target_b_parentchain_rpc_url: Option<String>,
 		target_b_parentchain_rpc_port: Option<String>,
 		worker_ip: String,
@@ -94,6 +107,8 @@ impl Config {
 			integritee_rpc_port,
 			target_a_parentchain_rpc_url,
 			target_a_parentchain_rpc_port,
+			target_a_relay_chain_rpc_url,
+			target_a_relay_chain_rpc_port,
 			target_b_parentchain_rpc_url,
 			target_b_parentchain_rpc_port,
 			worker_ip,
@@ -131,6 +146,20 @@ impl Config {
 		None
 	}
 
+	pub fn target_a_relay_chain_rpc_endpoint(&self) -> Option<String> {
+		if self.target_a_relay_chain_rpc_url.is_some()
+			&& self.target_a_relay_chain_rpc_port.is_some()
+		{
+			return Some(format!(
+				"{}:{}",
+				self.target_a_relay_chain_rpc_url.clone().unwrap(),
+				self.target_a_relay_chain_rpc_port.clone().unwrap()
+			))
+		};
+
+		None
+	}
+
 	pub fn target_b_parentchain_rpc_endpoint(&self) -> Option<String> {
 		if self.target_b_parentchain_rpc_url.is_some()
 			&& self.target_b_parentchain_rpc_port.is_some()
@@ -238,6 +267,8 @@ impl From<&ArgMatches<'_>> for Config {
 			m.value_of("integritee-rpc-port").unwrap_or(DEFAULT_INTEGRITEE_RPC_PORT).into(),
 			m.value_of("target-a-parentchain-rpc-url").map(Into::into),
 			m.value_of("target-a-parentchain-rpc-port").map(Into::into),
+			m.value_of("target-a-relay-chain-rpc-url").map(Into::into),
+			m.value_of("target-a-relay-chain-rpc-port").map(Into::into),
 			m.value_of("target-b-parentchain-rpc-url").map(Into::into),
 			m.value_of("target-b-parentchain-rpc-port").map(Into::into),
 			if m.is_present("ws-external") { "0.0.0.0".into() } else { "127.0.0.1".into() },
@@ -267,6 +298,8 @@ pub struct RunConfig {
 	dev: bool,
 	/// Request key and state provisioning from a peer worker.
 	request_state: bool,
+	/// Optional items to request from the peer worker, all by default
+	provisioning_items: Option<String>,
 	/// Shard identifier base58 encoded. Defines the shard that this worker operates on. Default is mrenclave.
 	shard: Option<String>,
 	/// Optional teeracle update interval
@@ -275,6 +308,10 @@ pub struct RunConfig {
 	reregister_teeracle_interval: Option<Duration>,
 	/// Marblerun's Prometheus endpoint base URL
 	marblerun_base_url: Option<String>,
+	/// Optional pruning policy of the sidechain block storage
+	sidechain_pruning_policy: Option<String>,
+	/// Optional target parentchains the personhood oracle reads from
+	personhood_sources: Option<String>,
 }
 
 impl RunConfig {
@@ -290,6 +327,16 @@ impl RunConfig {
 		self.request_state
 	}
 
+	/// Items to request with [`Self::request_state`], empty to request all of them.
+	pub fn provisioning_items(&self) -> Vec<ProvisioningItem> {
+		self.provisioning_items
+			.as_deref()
+			.map(|i| {
+				parse_provisioning_items(i).expect("Provisioning items are validated on parsing")
+			})
+			.unwrap_or_default()
+	}
+
 	pub fn shard(&self) -> Option<&str> {
 		self.shard.as_deref()
 	}
@@ -313,6 +360,20 @@ impl RunConfig {
 		// https://github.com/edgelesssys/marblerun/blob/master/docs/docs/workflows/monitoring.md?plain=1#L26
 		self.marblerun_base_url.as_deref().unwrap_or("http://localhost:9944")
 	}
+
+	pub fn sidechain_pruning_policy(&self) -> PruningPolicy {
+		self.sidechain_pruning_policy
+			.as_deref()
+			.map(|p| PruningPolicy::from_str(p).expect("Pruning policy is validated on parsing"))
+			.unwrap_or_default()
+	}
+
+	/// Personhood sources to configure the enclave with, `None` to keep the enclave's default.
+	pub fn personhood_sources(&self) -> Option<Vec<PersonhoodSourceConfig>> {
+		self.personhood_sources.as_deref().map(|s| {
+			parse_personhood_sources(s).expect("Personhood sources are validated on parsing")
+		})
+	}
 }
 
 impl From<&ArgMatches<'_>> for RunConfig {
@@ -320,6 +381,11 @@ impl From<&ArgMatches<'_>> for RunConfig {
 		let skip_ra = m.is_present("skip-ra");
 		let dev = m.is_present("dev");
 		let request_state = m.is_present("request-state");
+		let provisioning_items = m.value_of("provisioning-items").map(|i| {
+			parse_provisioning_items(i)
+				.unwrap_or_else(|e| panic!("provisioning-items parsing error: {}", e));
+			i.to_string()
+		});
 		let shard = m.value_of("shard").map(|s| s.to_string());
 		let teeracle_update_interval = m.value_of("teeracle-interval").map(|i| {
 			parse(i).unwrap_or_else(|e| panic!("teeracle-interval parsing error {:?}", e))
@@ -334,18 +400,78 @@ impl From<&ArgMatches<'_>> for RunConfig {
 				.to_string()
 		});
 
+		let sidechain_pruning_policy = m.value_of("sidechain-pruning").map(|p| {
+			PruningPolicy::from_str(p)
+				.unwrap_or_else(|e| panic!("sidechain-pruning parsing error: {:?}", e));
+			p.to_string()
+		});
+
+		let personhood_sources = m.value_of("personhood-sources").map(|s| {
+			parse_personhood_sources(s)
+				.unwrap_or_else(|e| panic!("personhood-sources parsing error: {}", e));
+			s.to_string()
+		});
+
 		Self {
 			skip_ra,
 			dev,
 			request_state,
+			provisioning_items,
 			shard,
 			teeracle_update_interval,
 			reregister_teeracle_interval,
 			marblerun_base_url,
+			sidechain_pruning_policy,
+			personhood_sources,
 		}
 	}
 }
 
+/// Parses a comma separated list of
+/// `<parentchain>[:<communities pallet>:<ceremonies pallet>:<scheduler pallet>]`,
+/// where the parentchain is `target-a` or `target-b`. Without pallet names, the Encointer
+/// pallets are read under their default names.
+fn parse_personhood_sources(sources: &str) -> Result<Vec<PersonhoodSourceConfig>, String> {
+	sources
+		.split(',')
+		.map(|source| {
+			let mut parts = source.trim().split(':');
+			let parentchain_id = match parts.next() {
+				Some("target-a") => ParentchainId::TargetA,
+				Some("target-b") => ParentchainId::TargetB,
+				other => return Err(format!("Unsupported personhood parentchain {:?}", other)),
+			};
+			let layout = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+				(None, ..) => PersonhoodStorageLayout::Encointer,
+				(
+					Some(communities_pallet),
+					Some(ceremonies_pallet),
+					Some(scheduler_pallet),
+					None,
+				) => PersonhoodStorageLayout::RenamedEncointerPallets {
+					communities_pallet: communities_pallet.as_bytes().to_vec(),
+					ceremonies_pallet: ceremonies_pallet.as_bytes().to_vec(),
+					scheduler_pallet: scheduler_pallet.as_bytes().to_vec(),
+				},
+				_ => return Err(format!("Invalid pallet names in personhood source {:?}", source)),
+			};
+			Ok(PersonhoodSourceConfig { parentchain_id, layout })
+		})
+		.collect()
+}
+
+/// Parses a comma separated list of provisioning items, e.g. `shielding-key,light-client`.
+/// The enclave only provisions the state key together with the state, so they can't be
+/// requested separately.
+pub(crate) fn parse_provisioning_items(items: &str) -> Result<Vec<ProvisioningItem>, String> {
+	let items: Vec<ProvisioningItem> =
+		items.split(',').map(|item| item.trim().parse()).collect::<Result<_, _>>()?;
+	if items.contains(&ProvisioningItem::StateKey) != items.contains(&ProvisioningItem::State) {
+		return Err("<state-key> and <state> can only be requested together".into())
+	}
+	Ok(items)
+}
+
 fn add_port_if_necessary(url: &str, port: &str) -> String {
 	// [Option("ws(s)"), ip, Option(port)]
 	match url.split(':').count() {
@@ -383,6 +509,8 @@ mod test {
 		assert_eq!(config.integritee_rpc_port, DEFAULT_INTEGRITEE_RPC_PORT);
 		assert_eq!(config.target_a_parentchain_rpc_url, None);
 		assert_eq!(config.target_a_parentchain_rpc_port, None);
+		assert_eq!(config.target_a_relay_chain_rpc_url, None);
+		assert_eq!(config.target_a_relay_chain_rpc_port, None);
 		assert_eq!(config.target_b_parentchain_rpc_url, None);
 		assert_eq!(config.target_b_parentchain_rpc_port, None);
 		assert_eq!(config.trusted_worker_port, DEFAULT_TRUSTED_PORT);
@@ -469,6 +597,9 @@ mod test {
 		assert_eq!(run_config.skip_ra, false);
 		assert!(run_config.shard.is_none());
 		assert!(run_config.teeracle_update_interval.is_none());
+		assert_eq!(run_config.sidechain_pruning_policy(), PruningPolicy::default());
+		assert!(run_config.personhood_sources().is_none());
+		assert!(run_config.provisioning_items().is_empty());
 	}
 
 	#[test]
@@ -482,10 +613,12 @@ mod test {
 			("skip-ra", Default::default()),
 			("shard", Default::default()),
 			("teeracle-interval", Default::default()),
+			("sidechain-pruning", Default::default()),
 		]);
 		// Workaround because MatchedArg is private.
 		args.args.get_mut("shard").unwrap().vals = vec![shard_identifier.into()];
 		args.args.get_mut("teeracle-interval").unwrap().vals = vec!["42s".into()];
+		args.args.get_mut("sidechain-pruning").unwrap().vals = vec!["archive".into()];
 
 		let run_config = RunConfig::from(&args);
 
@@ -494,6 +627,57 @@ mod test {
 		assert_eq!(run_config.skip_ra, true);
 		assert_eq!(run_config.shard.unwrap(), shard_identifier.to_string());
 		assert_eq!(run_config.teeracle_update_interval.unwrap(), Duration::from_secs(42));
+		assert_eq!(run_config.sidechain_pruning_policy(), PruningPolicy::Archive);
+	}
+
+	#[test]
+	fn personhood_sources_parsing_works() {
+		let sources =
+			parse_personhood_sources("target-a, target-b:Communities:Ceremonies:Scheduler")
+				.unwrap();
+
+		assert_eq!(
+			sources,
+			vec![
+				PersonhoodSourceConfig::encointer(ParentchainId::TargetA),
+				PersonhoodSourceConfig {
+					parentchain_id: ParentchainId::TargetB,
+					layout: PersonhoodStorageLayout::RenamedEncointerPallets {
+						communities_pallet: b"Communities".to_vec(),
+						ceremonies_pallet: b"Ceremonies".to_vec(),
+						scheduler_pallet: b"Scheduler".to_vec(),
+					},
+				},
+			]
+		);
+	}
+
+	#[test]
+	fn invalid_personhood_sources_are_rejected() {
+		assert!(parse_personhood_sources("").is_err());
+		assert!(parse_personhood_sources("integritee").is_err());
+		assert!(parse_personhood_sources("target-a,").is_err());
+		assert!(parse_personhood_sources("target-b:Communities").is_err());
+		assert!(parse_personhood_sources("target-b:Communities:Ceremonies").is_err());
+	}
+
+	#[test]
+	fn provisioning_items_parsing_works() {
+		assert_eq!(
+			parse_provisioning_items("shielding-key, light-client").unwrap(),
+			vec![ProvisioningItem::ShieldingKey, ProvisioningItem::LightClient]
+		);
+		assert!(parse_provisioning_items("shielding-key,keys").is_err());
+	}
+
+	#[test]
+	fn state_key_is_only_requested_with_the_state() {
+		assert_eq!(
+			parse_provisioning_items("state-key,state").unwrap(),
+			vec![ProvisioningItem::StateKey, ProvisioningItem::State]
+		);
+		assert!(parse_provisioning_items("shielding-key,state-key").is_err());
+		assert!(parse_provisioning_items("state").is_err());
 	}
 
 	#[test]
@@ -538,6 +722,16 @@ mod test {
 		assert!(result.is_err());
 	}
 
+	#[test]
+	fn sidechain_pruning_parsing_panics_if_format_is_invalid() {
+		let mut args = ArgMatches::default();
+		args.args = HashMap::from([("sidechain-pruning", Default::default())]);
+		args.args.get_mut("sidechain-pruning").unwrap().vals = vec!["blocks:many".into()];
+
+		let result = std::panic::catch_unwind(|| RunConfig::from(&args));
+		assert!(result.is_err());
+	}
+
 	#[test]
 	fn external_addresses_are_returned_correctly_if_set() {
 		let trusted_ext_addr = "wss://1.1.1.2:700";
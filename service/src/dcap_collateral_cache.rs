@@ -0,0 +1,209 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Caches the DCAP quote verification collateral, i.e. the PCK CRL, TCB info and QE
+//! identity, per FMSPC of the attested platforms.
+//!
+//! Without cache, the quote verification enclave fetches the collateral from the PCCS for
+//! each quote. With it, quotes are verified against the cached collateral, which is refreshed
+//! periodically. If the PCCS is unreachable, the cached collateral is used until its next
+//! update date, after which the enclave reports it as expired. The collateral is signed by
+//! Intel, so the cache does not need to be trusted. The PCK certificates themselves are part
+//! of the quotes.
+
+use crate::error::ServiceResult;
+use codec::{Decode, Encode};
+use itp_attestation_handler::{Fmspc, SgxQlQveCollateral};
+use itp_settings::files::DCAP_COLLATERAL_REFRESH_INTERVAL;
+use log::*;
+use parking_lot::RwLock;
+use std::{
+	collections::HashMap,
+	fs,
+	path::{Path, PathBuf},
+};
+
+const CACHE_FILE_EXTENSION: &str = "bin";
+
+#[derive(Encode, Decode, Clone, Debug, PartialEq, Eq)]
+pub struct CachedCollateral {
+	pub collateral: SgxQlQveCollateral,
+	/// Unix time in seconds, at which the collateral has been fetched.
+	pub fetched_at: u64,
+	/// Unix time in seconds, after which the collateral is expired.
+	pub next_update: u64,
+}
+
+impl CachedCollateral {
+	pub fn is_expired(&self, now: u64) -> bool {
+		now >= self.next_update
+	}
+
+	fn needs_refresh(&self, now: u64) -> bool {
+		self.is_expired(now) || now >= self.fetched_at + DCAP_COLLATERAL_REFRESH_INTERVAL
+	}
+}
+
+pub struct DcapCollateralCache {
+	cache_dir: PathBuf,
+	entries: RwLock<HashMap<Fmspc, CachedCollateral>>,
+}
+
+impl DcapCollateralCache {
+	/// Creates the cache and loads the collateral persisted in `cache_dir`.
+	pub fn new(cache_dir: &Path) -> ServiceResult<Self> {
+		fs::create_dir_all(cache_dir)?;
+
+		let mut entries = HashMap::new();
+		for entry in fs::read_dir(cache_dir)? {
+			let path = entry?.path();
+			let fmspc = match path
+				.file_stem()
+				.and_then(|stem| hex::decode(stem.to_string_lossy().as_ref()).ok())
+				.and_then(|fmspc| Fmspc::try_from(fmspc).ok())
+			{
+				Some(fmspc) => fmspc,
+				None => continue,
+			};
+			match CachedCollateral::decode(&mut fs::read(&path)?.as_slice()) {
+				Ok(cached) => {
+					entries.insert(fmspc, cached);
+				},
+				Err(e) => warn!("Ignoring corrupt DCAP collateral cache file {:?}: {:?}", path, e),
+			}
+		}
+		info!("Loaded DCAP collateral of {} platform(s) from {:?}", entries.len(), cache_dir);
+
+		Ok(Self { cache_dir: cache_dir.to_path_buf(), entries: RwLock::new(entries) })
+	}
+
+	/// Returns the collateral for `fmspc`. Fetches it, if it is not cached or due for a
+	/// refresh. If fetching fails, falls back to the cached collateral, even if expired.
+	pub fn get_or_fetch<F>(&self, fmspc: Fmspc, now: u64, fetch: F) -> Option<CachedCollateral>
+	where
+		F: FnOnce(Fmspc) -> Result<SgxQlQveCollateral, String>,
+	{
+		let cached = self.entries.read().get(&fmspc).cloned();
+		if let Some(cached) = cached.as_ref().filter(|cached| !cached.needs_refresh(now)) {
+			return Some(cached.clone())
+		}
+
+		match fetch(fmspc) {
+			Ok(collateral) => {
+				let next_update = collateral.next_update().unwrap_or_else(|| {
+					warn!("DCAP collateral lacks a next update date, caching it for one interval");
+					now + DCAP_COLLATERAL_REFRESH_INTERVAL
+				});
+				let fetched = CachedCollateral { collateral, fetched_at: now, next_update };
+				if let Err(e) = self.persist(&fmspc, &fetched) {
+					warn!(
+						"Failed to persist DCAP collateral of FMSPC {}: {:?}",
+						hex::encode(fmspc),
+						e
+					);
+				}
+				self.entries.write().insert(fmspc, fetched.clone());
+				Some(fetched)
+			},
+			Err(e) => {
+				let cached = cached?;
+				if cached.is_expired(now) {
+					error!(
+						"Fetching DCAP collateral of FMSPC {} failed: {}. Cached collateral expired at {}",
+						hex::encode(fmspc),
+						e,
+						cached.next_update
+					);
+				} else {
+					warn!(
+						"Fetching DCAP collateral of FMSPC {} failed: {}. Using cached collateral, valid until {}",
+						hex::encode(fmspc),
+						e,
+						cached.next_update
+					);
+				}
+				Some(cached)
+			},
+		}
+	}
+
+	fn persist(&self, fmspc: &Fmspc, cached: &CachedCollateral) -> ServiceResult<()> {
+		let path = self.cache_dir.join(hex::encode(fmspc)).with_extension(CACHE_FILE_EXTENSION);
+		fs::write(path, cached.encode())?;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const FMSPC: Fmspc = [0x00, 0x90, 0x6e, 0xa1, 0x00, 0x00];
+
+	fn collateral(next_update: &str) -> SgxQlQveCollateral {
+		SgxQlQveCollateral {
+			version: 3,
+			tcb_info: format!(
+				r#"{{"tcbInfo":{{"nextUpdate":"{}"}},"signature":"00"}}"#,
+				next_update
+			)
+			.into_bytes(),
+			qe_identity: format!(
+				r#"{{"enclaveIdentity":{{"nextUpdate":"{}"}},"signature":"00"}}"#,
+				next_update
+			)
+			.into_bytes(),
+			..Default::default()
+		}
+	}
+
+	fn cache_dir(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("dcap_collateral_cache_{}", name));
+		let _ = fs::remove_dir_all(&dir);
+		dir
+	}
+
+	#[test]
+	fn fetched_collateral_is_cached_and_persisted() {
+		let dir = cache_dir("persisted");
+		let cache = DcapCollateralCache::new(&dir).unwrap();
+		let fetched = cache.get_or_fetch(FMSPC, 0, |_| Ok(collateral("2023-06-13T00:00:00Z")));
+		assert_eq!(fetched.as_ref().unwrap().next_update, 1686614400);
+
+		let cached = cache.get_or_fetch(FMSPC, 10, |_| panic!("must not fetch a fresh entry"));
+		assert_eq!(cached, fetched);
+
+		let reloaded = DcapCollateralCache::new(&dir).unwrap();
+		assert_eq!(reloaded.get_or_fetch(FMSPC, 10, |_| Err("offline".into())), fetched);
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn cached_collateral_is_used_if_fetching_fails() {
+		let dir = cache_dir("outage");
+		let cache = DcapCollateralCache::new(&dir).unwrap();
+		cache.get_or_fetch(FMSPC, 0, |_| Ok(collateral("2023-06-13T00:00:00Z")));
+
+		let now = DCAP_COLLATERAL_REFRESH_INTERVAL + 1;
+		let cached = cache.get_or_fetch(FMSPC, now, |_| Err("PCCS unreachable".into())).unwrap();
+		assert!(!cached.is_expired(now));
+		assert!(cached.is_expired(1686614400));
+
+		assert_eq!(cache.get_or_fetch([0u8; 6], now, |_| Err("PCCS unreachable".into())), None);
+		fs::remove_dir_all(dir).unwrap();
+	}
+}
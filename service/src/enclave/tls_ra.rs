@@ -16,7 +16,10 @@
 */
 use itp_enclave_api::{
 	error::Error,
-	remote_attestation::{RemoteAttestation, TlsRemoteAttestation},
+	remote_attestation::{
+		ProvisioningItem, RemoteAttestation, TlsRemoteAttestation,
+		LEGACY_PROVISIONING_SERVER_STATUS,
+	},
 	EnclaveResult,
 };
 use itp_types::ShardIdentifier;
@@ -70,17 +73,17 @@ pub fn enclave_run_state_provisioning_server<E: TlsRemoteAttestation>(
 	}
 }
 
+/// Request the `items` (all if empty) of the `shard` from the provisioning server at `addr`.
+///
+/// Reconnects with the legacy framing if the server only speaks the legacy protocol.
 pub fn enclave_request_state_provisioning<E: TlsRemoteAttestation + RemoteAttestation>(
 	enclave_api: &E,
 	sign_type: sgx_quote_sign_type_t,
 	addr: &str,
 	shard: &ShardIdentifier,
+	items: &[ProvisioningItem],
 	skip_ra: bool,
 ) -> EnclaveResult<()> {
-	info!("[MU-RA-Client] Requesting key provisioning from {}", addr);
-
-	let stream = TcpStream::connect(addr).map_err(|e| Error::Other(Box::new(e)))?;
-
 	let quoting_enclave_target_info = if !skip_ra {
 		match enclave_api.qe_get_target_info() {
 			Ok(quote_size) => Some(quote_size),
@@ -99,12 +102,26 @@ pub fn enclave_request_state_provisioning<E: TlsRemoteAttestation + RemoteAttest
 		None
 	};
 
-	enclave_api.request_state_provisioning(
-		stream.as_raw_fd(),
-		sign_type,
-		quoting_enclave_target_info.as_ref(),
-		quote_size.as_ref(),
-		shard,
-		skip_ra,
-	)
+	let request = |legacy_framing: bool| {
+		info!("[MU-RA-Client] Requesting key provisioning from {}", addr);
+		let stream = TcpStream::connect(addr).map_err(|e| Error::Other(Box::new(e)))?;
+		enclave_api.request_state_provisioning(
+			stream.as_raw_fd(),
+			sign_type,
+			quoting_enclave_target_info.as_ref(),
+			quote_size.as_ref(),
+			shard,
+			items,
+			legacy_framing,
+			skip_ra,
+		)
+	};
+
+	match request(false) {
+		Err(Error::Sgx(status)) if status == LEGACY_PROVISIONING_SERVER_STATUS => {
+			warn!("[MU-RA-Client] {} only speaks the legacy protocol, reconnecting", addr);
+			request(true)
+		},
+		result => result,
+	}
 }
@@ -50,6 +50,14 @@ pub enum Error {
 	MissingGenesisHeader,
 	#[error("Could not find last finalized block of the parentchain")]
 	MissingLastFinalizedBlock,
+	#[error("Could not find block {0} of the relay chain")]
+	MissingRelayChainBlock(u32),
+	#[error("Relay chain does not contain a head of parachain {0}")]
+	MissingParaHead(u32),
+	#[error("Parentchain is not a parachain, can't follow its relay chain")]
+	MissingParachainId,
+	#[error("{0}")]
+	Io(#[from] std::io::Error),
 	#[error("{0}")]
 	Custom(Box<dyn std::error::Error + Sync + Send + 'static>),
 }
@@ -0,0 +1,78 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Inspection and rollback of the rotating light client backups. The worker must not be
+//! running, it picks up the restored light client state on its next start.
+
+use itc_parentchain::{light_client::backup::LightClientBackupStatus, primitives::ParentchainId};
+use itp_enclave_api::light_client_backup::LightClientBackups;
+use log::*;
+
+pub(crate) fn parse_parentchain_id(parentchain: &str) -> Option<ParentchainId> {
+	match parentchain {
+		"integritee" => Some(ParentchainId::Integritee),
+		"target-a" => Some(ParentchainId::TargetA),
+		"target-b" => Some(ParentchainId::TargetB),
+		_ => None,
+	}
+}
+
+pub(crate) fn list_light_client_backups<E: LightClientBackups>(
+	enclave: &E,
+	parentchain_id: &ParentchainId,
+) {
+	let backups = match enclave.list_light_client_backups(parentchain_id) {
+		Ok(backups) => backups,
+		Err(e) => {
+			error!("[-] Failed to read the {:?} light client backups: {:?}", parentchain_id, e);
+			return
+		},
+	};
+
+	if backups.is_empty() {
+		println!("[+] No {:?} light client backups found", parentchain_id);
+		return
+	}
+
+	println!("[+] {:?} light client backups:", parentchain_id);
+	for backup in backups {
+		match backup.status {
+			LightClientBackupStatus::Valid { generation, latest_finalized_block } => println!(
+				"    slot {}: generation {}, latest finalized block {}",
+				backup.slot, generation, latest_finalized_block
+			),
+			LightClientBackupStatus::Corrupted => println!("    slot {}: corrupted", backup.slot),
+		}
+	}
+}
+
+pub(crate) fn restore_light_client_backup<E: LightClientBackups>(
+	enclave: &E,
+	parentchain_id: &ParentchainId,
+	generation: u64,
+) {
+	match enclave.restore_light_client_backup(parentchain_id, generation) {
+		Err(e) => error!(
+			"[-] Failed to restore the {:?} light client backup generation {}: {:?}",
+			parentchain_id, generation, e
+		),
+		Ok(_) => println!(
+			"[+] Restored the {:?} light client backup generation {}. Restart the worker to use it",
+			parentchain_id, generation
+		),
+	}
+}
@@ -18,13 +18,16 @@
 #![cfg_attr(test, feature(assert_matches))]
 
 #[cfg(feature = "teeracle")]
-use crate::teeracle::{schedule_periodic_reregistration_thread};
+use crate::teeracle::{
+	schedule_periodic_market_data_update_thread, schedule_periodic_reregistration_thread,
+};
 
 #[cfg(not(feature = "dcap"))]
 use crate::utils::check_files;
 
 use crate::{
 	account_funding::{setup_account_funding, EnclaveAccountInfoProvider},
+	dcap_collateral_cache::DcapCollateralCache,
 	error::Error,
 	globals::tokio_handle::{GetTokioHandle, GlobalTokioHandle},
 	initialized_service::{
@@ -37,6 +40,7 @@ use crate::{
 	prometheus_metrics::{start_metrics_server, EnclaveMetricsReceiver, MetricsHandler},
 	sidechain_setup::{sidechain_init_block_production, sidechain_start_untrusted_rpc_server},
 	sync_block_broadcaster::SyncBlockBroadcaster,
+	top_pool_sealing::{listen_for_shutdown_signal, seal_top_pool, start_top_pool_sealing},
 	utils::extract_shard,
 	worker::Worker,
 	worker_peers_updater::WorkerPeersUpdater,
@@ -44,7 +48,7 @@ use crate::{
 use base58::ToBase58;
 use clap::{load_yaml, App};
 use codec::Encode;
-use config::Config;
+use config::{parse_provisioning_items, Config};
 use enclave::{
 	api::enclave_init,
 	tls_ra::{enclave_request_state_provisioning, enclave_run_state_provisioning_server},
@@ -62,7 +66,13 @@ use itp_node_api::{
 	metadata::NodeMetadata,
 	node_api_factory::{CreateNodeApi, NodeApiFactory},
 };
-use itp_settings::worker_mode::{ProvideWorkerMode, WorkerMode, WorkerModeProvider};
+use itp_settings::{
+	files::{
+		DCAP_COLLATERAL_CACHE_PATH, SECRETS_EXPORT_FILE, STATE_IMPORT_KEY_FILE,
+		STATE_SNAPSHOT_EXPORT_FILE,
+	},
+	worker_mode::{ProvideWorkerMode, WorkerMode, WorkerModeProvider},
+};
 use its_peer_fetch::{
 	block_fetch_client::BlockFetcher, untrusted_peer_fetch::UntrustedPeerFetcher,
 };
@@ -86,24 +96,31 @@ use itc_parentchain::primitives::ParentchainId;
 use sp_core::crypto::{AccountId32, Ss58Codec};
 use sp_keyring::AccountKeyring;
 use sp_runtime::MultiSigner;
-use std::{str, sync::Arc, thread, time::Duration};
+use std::{path::Path, str, sync::Arc, thread, time::Duration};
 
 mod account_funding;
 mod config;
+mod dcap_collateral_cache;
 mod enclave;
 mod error;
 mod globals;
 mod initialized_service;
+mod light_client_backup;
 mod ocall_bridge;
 mod parentchain_handler;
 mod prometheus_metrics;
+mod relay_chain_follower;
+mod secret_migration;
 mod setup;
 mod sidechain_setup;
+mod sidechain_storage;
+mod state_snapshot;
 mod sync_block_broadcaster;
 mod sync_state;
 #[cfg(feature = "teeracle")]
 mod teeracle;
 mod tests;
+mod top_pool_sealing;
 mod utils;
 mod worker;
 mod worker_peers_updater;
@@ -167,6 +184,9 @@ fn main() {
 	let peer_sidechain_block_fetcher =
 		Arc::new(BlockFetcher::<SignedSidechainBlock, _>::new(untrusted_peer_fetcher));
 	let enclave_metrics_receiver = Arc::new(EnclaveMetricsReceiver {});
+	let collateral_cache = Arc::new(
+		DcapCollateralCache::new(&config.data_dir().join(DCAP_COLLATERAL_CACHE_PATH)).unwrap(),
+	);
 
 	let maybe_target_a_parentchain_api_factory = config
 		.target_a_parentchain_rpc_endpoint()
@@ -188,6 +208,7 @@ fn main() {
 		peer_sidechain_block_fetcher,
 		tokio_handle.clone(),
 		enclave_metrics_receiver,
+		collateral_cache,
 	)));
 
 	let quoting_enclave_target_info = match enclave.qe_get_target_info() {
@@ -222,6 +243,7 @@ fn main() {
 				&node_api,
 				&shard,
 				enclave.as_ref(),
+				&run_config.provisioning_items(),
 				run_config.skip_ra(),
 			);
 		}
@@ -245,8 +267,68 @@ fn main() {
 			&node_api,
 			&extract_shard(smatches.value_of("shard"), enclave.as_ref()),
 			enclave.as_ref(),
+			&smatches
+				.value_of("provisioning-items")
+				.map(|i| {
+					parse_provisioning_items(i)
+						.unwrap_or_else(|e| panic!("provisioning-items parsing error: {}", e))
+				})
+				.unwrap_or_default(),
 			smatches.is_present("skip-ra"),
 		);
+	} else if let Some(sub_matches) = matches.subcommand_matches("state-import-key") {
+		state_snapshot::write_state_import_key(
+			enclave.as_ref(),
+			sub_matches.value_of("out").unwrap_or(STATE_IMPORT_KEY_FILE),
+		);
+	} else if let Some(sub_matches) = matches.subcommand_matches("export-state") {
+		state_snapshot::export_state_snapshot(
+			enclave.as_ref(),
+			&extract_shard(sub_matches.value_of("shard"), enclave.as_ref()),
+			sub_matches.value_of("state-hash"),
+			sub_matches.value_of("import-key").unwrap_or(STATE_IMPORT_KEY_FILE),
+			sub_matches.value_of("out").unwrap_or(STATE_SNAPSHOT_EXPORT_FILE),
+		);
+	} else if let Some(sub_matches) = matches.subcommand_matches("import-state") {
+		state_snapshot::import_state_snapshot(
+			enclave.as_ref(),
+			sub_matches.value_of("snapshot").unwrap_or(STATE_SNAPSHOT_EXPORT_FILE),
+		);
+	} else if let Some(sub_matches) = matches.subcommand_matches("export-secrets") {
+		secret_migration::export_sealed_secrets(
+			enclave.as_ref(),
+			&extract_shard(sub_matches.value_of("shard"), enclave.as_ref()),
+			sub_matches.value_of("import-key").unwrap_or(STATE_IMPORT_KEY_FILE),
+			sub_matches.value_of("out").unwrap_or(SECRETS_EXPORT_FILE),
+		);
+	} else if let Some(sub_matches) = matches.subcommand_matches("import-secrets") {
+		secret_migration::import_sealed_secrets(
+			enclave.as_ref(),
+			sub_matches.value_of("secrets").unwrap_or(SECRETS_EXPORT_FILE),
+		);
+	} else if let Some(sub_matches) = matches.subcommand_matches("light-client-backups") {
+		let parentchain_id = light_client_backup::parse_parentchain_id(
+			sub_matches.value_of("parentchain").unwrap_or("integritee"),
+		)
+		.expect("parentchain is validated by clap");
+		match sub_matches.value_of("restore") {
+			Some(generation) => light_client_backup::restore_light_client_backup(
+				enclave.as_ref(),
+				&parentchain_id,
+				generation.parse().expect("generation must be a number"),
+			),
+			None => light_client_backup::list_light_client_backups(
+				enclave.as_ref(),
+				&parentchain_id,
+			),
+		}
+	} else if let Some(sub_matches) = matches.subcommand_matches("compact-sidechain-storage") {
+		sidechain_storage::compact_sidechain_storage(
+			sidechain_blockstorage.as_ref(),
+			sub_matches.value_of("pruning"),
+		);
+	} else if matches.is_present("sidechain-storage-info") {
+		sidechain_storage::print_sidechain_storage_info(sidechain_blockstorage.as_ref());
 	} else if matches.is_present("shielding-key") {
 		setup::generate_shielding_key_file(enclave.as_ref());
 	} else if matches.is_present("signing-key") {
@@ -290,6 +372,7 @@ fn main() {
 				sgx_quote_sign_type_t::SGX_UNLINKABLE_SIGNATURE,
 				&config.mu_ra_url_external(),
 				&shard,
+				&[],
 				sub_matches.is_present("skip-ra"),
 			)
 			.unwrap();
@@ -322,7 +405,10 @@ fn start_worker<E, T, D, InitializationHandler, WorkerModeProvider>(
 		+ RemoteAttestation
 		+ TlsRemoteAttestation
 		+ TeeracleApi
-		+ Clone,
+		+ Clone
+		+ Send
+		+ Sync
+		+ 'static,
 	D: BlockPruner + FetchBlocks<SignedSidechainBlock> + Sync + Send + 'static,
 	InitializationHandler: TrackInitialization + IsInitialized + Sync + Send + 'static,
 	WorkerModeProvider: ProvideWorkerMode,
@@ -448,6 +534,15 @@ fn start_worker<E, T, D, InitializationHandler, WorkerModeProvider>(
 		});
 	}
 
+	// ------------------------------------------------------------------------
+	// Seal the pending trusted operations periodically and on shutdown.
+	if WorkerModeProvider::worker_mode() == WorkerMode::Sidechain
+		|| WorkerModeProvider::worker_mode() == WorkerMode::OffChainWorker
+	{
+		start_top_pool_sealing(enclave.clone()).unwrap();
+	}
+	let shutdown_receiver = listen_for_shutdown_signal(&tokio_handle);
+
 	// ------------------------------------------------------------------------
 	// Start untrusted worker rpc server.
 	// i.e move sidechain block importing to trusted worker.
@@ -463,8 +558,13 @@ fn start_worker<E, T, D, InitializationHandler, WorkerModeProvider>(
 	// ------------------------------------------------------------------------
 	// Init parentchain specific stuff. Needed for parentchain communication.
 
-	let (parentchain_handler, last_synced_header) =
-		init_parentchain(&enclave, &integritee_rpc_api, &tee_accountid, ParentchainId::Integritee);
+	let (parentchain_handler, last_synced_header) = init_parentchain(
+		&enclave,
+		&integritee_rpc_api,
+		None,
+		&tee_accountid,
+		ParentchainId::Integritee,
+	);
 
 	#[cfg(feature = "dcap")]
 	register_collateral(
@@ -547,6 +647,13 @@ fn start_worker<E, T, D, InitializationHandler, WorkerModeProvider>(
 			send_register_xt,
 			run_config.reregister_teeracle_interval(),
 		);
+		schedule_periodic_market_data_update_thread(
+			integritee_rpc_api.clone(),
+			enclave.clone(),
+			parentchain_handler.clone(),
+			last_synced_header.clone(),
+			run_config.teeracle_update_interval(),
+		);
 	}
 
 	if WorkerModeProvider::worker_mode() != WorkerMode::Teeracle {
@@ -566,6 +673,7 @@ fn start_worker<E, T, D, InitializationHandler, WorkerModeProvider>(
 				parentchain_handler.clone(),
 				sidechain_storage,
 				&last_synced_header,
+				run_config.sidechain_pruning_policy(),
 			)
 			.unwrap();
 		}
@@ -590,13 +698,32 @@ fn start_worker<E, T, D, InitializationHandler, WorkerModeProvider>(
 		spawn_worker_for_shard_polling(shard, integritee_rpc_api.clone(), initialization_handler);
 	}
 
+	if let Some(sources) = run_config.personhood_sources() {
+		for source in sources.iter() {
+			let is_configured = match source.parentchain_id {
+				ParentchainId::TargetB => config.target_b_parentchain_rpc_endpoint().is_some(),
+				_ => config.target_a_parentchain_rpc_endpoint().is_some(),
+			};
+			if !is_configured {
+				warn!(
+					"Personhood source {:?} has no parentchain rpc endpoint",
+					source.parentchain_id
+				);
+			}
+		}
+		enclave.set_personhood_sources(&sources).unwrap();
+		println!("[+] Set personhood sources: {:?}", sources);
+	}
+
 	if let Some(url) = config.target_a_parentchain_rpc_endpoint() {
 		init_target_parentchain(
 			&enclave,
 			&tee_accountid,
 			url,
+			config.target_a_relay_chain_rpc_endpoint(),
 			ParentchainId::TargetA,
 			is_development_mode,
+			config.data_dir(),
 		)
 	}
 
@@ -605,8 +732,10 @@ fn start_worker<E, T, D, InitializationHandler, WorkerModeProvider>(
 			&enclave,
 			&tee_accountid,
 			url,
+			None,
 			ParentchainId::TargetB,
 			is_development_mode,
+			config.data_dir(),
 		)
 	}
 
@@ -615,19 +744,37 @@ fn start_worker<E, T, D, InitializationHandler, WorkerModeProvider>(
 	println!("*** [{:?}] Subscribing to events", ParentchainId::Integritee);
 	let mut subscription = integritee_rpc_api.subscribe_events().unwrap();
 	println!("[+] [{:?}] Subscribed to events. waiting...", ParentchainId::Integritee);
-	loop {
-		if let Some(Ok(events)) = subscription.next_event::<RuntimeEvent, Hash>() {
-			print_events(events)
-		}
+	thread::Builder::new()
+		.name(format!("{:?}_parentchain_event_subscription", ParentchainId::Integritee))
+		.spawn(move || loop {
+			if let Some(Ok(events)) = subscription.next_event::<RuntimeEvent, Hash>() {
+				print_events(events)
+			}
+		})
+		.unwrap();
+
+	// ------------------------------------------------------------------------
+	// Run until Ctrl-C or SIGTERM, then seal the pending trusted operations before returning.
+	if shutdown_receiver.recv().is_err() {
+		error!("Shutdown signal listener terminated unexpectedly");
+	}
+	if WorkerModeProvider::worker_mode() == WorkerMode::Sidechain
+		|| WorkerModeProvider::worker_mode() == WorkerMode::OffChainWorker
+	{
+		println!("[+] Shutting down, sealing pending trusted operations");
+		seal_top_pool(enclave.as_ref());
 	}
+	println!("[+] Worker shut down");
 }
 
 fn init_target_parentchain<E>(
 	enclave: &Arc<E>,
 	tee_account_id: &AccountId32,
 	url: String,
+	relay_chain_url: Option<String>,
 	parentchain_id: ParentchainId,
 	is_development_mode: bool,
+	data_dir: &Path,
 ) where
 	E: EnclaveBase + Sidechain,
 {
@@ -642,8 +789,20 @@ fn init_target_parentchain<E>(
 			panic!("[{:?}] Could not fund parentchain enclave account", parentchain_id)
 		});
 
-	let (parentchain_handler, last_synched_header) =
-		init_parentchain(enclave, &node_api, tee_account_id, parentchain_id);
+	let relay_chain_api = relay_chain_url.map(|url| {
+		println!("[{:?}] Following relay chain finality with url: {}", parentchain_id, url);
+		NodeApiFactory::new(url, AccountKeyring::Alice.pair()).create_api().unwrap_or_else(|_| {
+			panic!("[{:?}] Failed to create relay chain node API", parentchain_id)
+		})
+	});
+
+	let (parentchain_handler, last_synched_header) = init_parentchain(
+		enclave,
+		&node_api,
+		relay_chain_api.map(|api| (api, data_dir)),
+		tee_account_id,
+		parentchain_id,
+	);
 
 	if WorkerModeProvider::worker_mode() != WorkerMode::Teeracle {
 		println!(
@@ -690,6 +849,7 @@ fn init_target_parentchain<E>(
 fn init_parentchain<E>(
 	enclave: &Arc<E>,
 	node_api: &ParentchainApi,
+	relay_chain: Option<(ParentchainApi, &Path)>,
 	tee_account_id: &AccountId32,
 	parentchain_id: ParentchainId,
 ) -> (Arc<ParentchainHandler<ParentchainApi, E>>, Header)
@@ -697,11 +857,20 @@ where
 	E: EnclaveBase + Sidechain,
 {
 	let parentchain_handler = Arc::new(
-		ParentchainHandler::new_with_automatic_light_client_allocation(
-			node_api.clone(),
-			enclave.clone(),
-			parentchain_id,
-		)
+		match relay_chain {
+			Some((relay_chain_api, data_dir)) => ParentchainHandler::new_relay_chain_backed(
+				node_api.clone(),
+				relay_chain_api,
+				enclave.clone(),
+				parentchain_id,
+				data_dir,
+			),
+			None => ParentchainHandler::new_with_automatic_light_client_allocation(
+				node_api.clone(),
+				enclave.clone(),
+				parentchain_id,
+			),
+		}
 		.unwrap(),
 	);
 	let last_synced_header = parentchain_handler.init_parentchain_components().unwrap();
@@ -131,6 +131,8 @@ pub enum OCallBridgeError {
 	ProposeSidechainBlock(String),
 	#[error("Failed to fetch sidechain blocks from peer: {0}")]
 	FetchSidechainBlocksFromPeer(String),
+	#[error("Failed to finalize sidechain block: {0}")]
+	FinalizeSidechainBlock(String),
 	#[error("Sending extrinsics to parentchain failed: {0}")]
 	SendExtrinsicsToParentchain(String),
 	#[error("IPFS Error: {0}")]
@@ -235,6 +237,12 @@ pub trait SidechainBridge {
 		maybe_until_block_hash_encoded: Vec<u8>,
 		shard_identifier_encoded: Vec<u8>,
 	) -> OCallBridgeResult<Vec<u8>>;
+
+	fn finalize_sidechain_block(
+		&self,
+		shard_identifier_encoded: Vec<u8>,
+		finalized_block_encoded: Vec<u8>,
+	) -> OCallBridgeResult<()>;
 }
 
 /// type for IPFS
@@ -17,6 +17,7 @@
 */
 
 use crate::{
+	dcap_collateral_cache::DcapCollateralCache,
 	ocall_bridge::{
 		bridge_api::{
 			GetOCallBridgeComponents, IpfsBridge, MetricsBridge, RemoteAttestationBridge,
@@ -33,7 +34,7 @@ use crate::{
 	worker_peers_updater::UpdateWorkerPeers,
 	GetTokioHandle,
 };
-use itp_enclave_api::remote_attestation::RemoteAttestationCallBacks;
+use itp_enclave_api::remote_attestation::{RemoteAttestation, RemoteAttestationCallBacks};
 use itp_node_api::node_api_factory::CreateNodeApi;
 use its_peer_fetch::FetchBlocksFromPeer;
 use its_primitives::types::block::SignedBlock as SignedSidechainBlock;
@@ -63,6 +64,7 @@ pub struct OCallBridgeComponentFactory<
 	peer_block_fetcher: Arc<PeerBlockFetcher>,
 	tokio_handle: Arc<TokioHandle>,
 	metrics_receiver: Arc<MetricsReceiver>,
+	collateral_cache: Arc<DcapCollateralCache>,
 }
 
 impl<
@@ -98,6 +100,7 @@ impl<
 		peer_block_fetcher: Arc<PeerBlockFetcher>,
 		tokio_handle: Arc<TokioHandle>,
 		metrics_receiver: Arc<MetricsReceiver>,
+		collateral_cache: Arc<DcapCollateralCache>,
 	) -> Self {
 		OCallBridgeComponentFactory {
 			integritee_rpc_api_factory,
@@ -110,6 +113,7 @@ impl<
 			peer_block_fetcher,
 			tokio_handle,
 			metrics_receiver,
+			collateral_cache,
 		}
 	}
 }
@@ -136,7 +140,7 @@ impl<
 	> where
 	NodeApi: CreateNodeApi + 'static,
 	Broadcaster: BroadcastBlocks + 'static,
-	EnclaveApi: RemoteAttestationCallBacks + 'static,
+	EnclaveApi: RemoteAttestationCallBacks + RemoteAttestation + 'static,
 	Storage: BlockStorage<SignedSidechainBlock> + 'static,
 	PeerUpdater: UpdateWorkerPeers + 'static,
 	PeerBlockFetcher: FetchBlocksFromPeer<SignedBlockType = SignedSidechainBlock> + 'static,
@@ -144,7 +148,10 @@ impl<
 	MetricsReceiver: ReceiveEnclaveMetrics + 'static,
 {
 	fn get_ra_api(&self) -> Arc<dyn RemoteAttestationBridge> {
-		Arc::new(RemoteAttestationOCall::new(self.enclave_api.clone()))
+		Arc::new(RemoteAttestationOCall::new(
+			self.enclave_api.clone(),
+			self.collateral_cache.clone(),
+		))
 	}
 
 	fn get_sidechain_api(&self) -> Arc<dyn SidechainBridge> {
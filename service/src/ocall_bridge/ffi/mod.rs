@@ -22,6 +22,7 @@
 //! actual implementation of the OCalls (using the traits defined in the bridge_api).
 
 pub mod fetch_sidechain_blocks_from_peer;
+pub mod finalize_sidechain_block;
 pub mod get_ias_socket;
 pub mod get_quote;
 pub mod get_qve_report_on_quote;
@@ -16,30 +16,70 @@
 
 */
 
-use crate::ocall_bridge::bridge_api::{
-	OCallBridgeError, OCallBridgeResult, RemoteAttestationBridge,
+use crate::{
+	dcap_collateral_cache::DcapCollateralCache,
+	ocall_bridge::bridge_api::{OCallBridgeError, OCallBridgeResult, RemoteAttestationBridge},
 };
-use itp_enclave_api::remote_attestation::{QveReport, RemoteAttestationCallBacks};
+use itp_attestation_handler::{fmspc_from_quote, SgxQlQveCollateral};
+use itp_enclave_api::remote_attestation::{
+	QveReport, RemoteAttestation, RemoteAttestationCallBacks,
+};
+use log::*;
 use sgx_types::*;
 use std::{
 	net::{SocketAddr, TcpStream},
 	os::unix::io::IntoRawFd,
 	sync::Arc,
+	time::{SystemTime, UNIX_EPOCH},
 };
 
 pub struct RemoteAttestationOCall<E> {
 	enclave_api: Arc<E>,
+	collateral_cache: Arc<DcapCollateralCache>,
 }
 
 impl<E> RemoteAttestationOCall<E> {
-	pub fn new(enclave_api: Arc<E>) -> Self {
-		RemoteAttestationOCall { enclave_api }
+	pub fn new(enclave_api: Arc<E>, collateral_cache: Arc<DcapCollateralCache>) -> Self {
+		RemoteAttestationOCall { enclave_api, collateral_cache }
+	}
+}
+
+impl<E> RemoteAttestationOCall<E>
+where
+	E: RemoteAttestation,
+{
+	/// Collateral to verify the quote with, if the cache can provide it.
+	fn cached_collateral(&self, quote: &[u8]) -> Option<SgxQlQveCollateral> {
+		let fmspc = match fmspc_from_quote(quote) {
+			Some(fmspc) => fmspc,
+			None => {
+				warn!("Quote does not contain a PCK certificate with FMSPC, not using the collateral cache");
+				return None
+			},
+		};
+		let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+
+		self.collateral_cache
+			.get_or_fetch(fmspc, now, |fmspc| {
+				let collateral_ptr =
+					self.enclave_api.get_dcap_collateral(fmspc).map_err(|e| format!("{:?}", e))?;
+				// SAFETY: `get_dcap_collateral` checks for null, and the quote library
+				// initializes all members.
+				let collateral = unsafe { SgxQlQveCollateral::from_c_type(&*collateral_ptr) };
+				let free_status =
+					unsafe { sgx_ql_free_quote_verification_collateral(collateral_ptr) };
+				if free_status != sgx_quote3_error_t::SGX_QL_SUCCESS {
+					warn!("Failed to free the DCAP collateral: {:?}", free_status);
+				}
+				Ok(collateral)
+			})
+			.map(|cached| cached.collateral)
 	}
 }
 
 impl<E> RemoteAttestationBridge for RemoteAttestationOCall<E>
 where
-	E: RemoteAttestationCallBacks,
+	E: RemoteAttestationCallBacks + RemoteAttestation,
 {
 	fn init_quote(&self) -> OCallBridgeResult<(sgx_target_info_t, sgx_epid_group_id_t)> {
 		self.enclave_api.init_quote().map_err(|e| match e {
@@ -98,6 +138,12 @@ where
 		qve_report_info: sgx_ql_qe_report_info_t,
 		supplemental_data_size: u32,
 	) -> OCallBridgeResult<QveReport> {
+		// The enclave passes an empty collateral, if the collateral is to be fetched on our side.
+		let cached_collateral =
+			if quote_collateral.version == 0 { self.cached_collateral(&quote) } else { None };
+		let cached_collateral_c_type = cached_collateral.as_ref().map(|c| c.as_c_type());
+		let quote_collateral = cached_collateral_c_type.as_ref().unwrap_or(quote_collateral);
+
 		self.enclave_api
 			.get_qve_report_on_quote(
 				quote,
@@ -23,7 +23,7 @@ use crate::{
 	GetTokioHandle,
 };
 use codec::{Decode, Encode};
-use itp_types::{BlockHash, ShardIdentifier};
+use itp_types::{BlockHash, ShardIdentifier, SidechainBlockConfirmation};
 use its_peer_fetch::FetchBlocksFromPeer;
 use its_primitives::{traits::Block, types::SignedBlock as SignedSidechainBlock};
 use its_storage::BlockStorage;
@@ -187,6 +187,39 @@ where
 
 		Ok(signed_sidechain_blocks.encode())
 	}
+
+	fn finalize_sidechain_block(
+		&self,
+		shard_identifier_encoded: Vec<u8>,
+		finalized_block_encoded: Vec<u8>,
+	) -> OCallBridgeResult<()> {
+		let shard_identifier: ShardIdentifier =
+			Decode::decode(&mut shard_identifier_encoded.as_slice()).map_err(|_| {
+				OCallBridgeError::FinalizeSidechainBlock(
+					"Failed to decode shard identifier".to_string(),
+				)
+			})?;
+
+		let finalized_block: SidechainBlockConfirmation =
+			Decode::decode(&mut finalized_block_encoded.as_slice()).map_err(|_| {
+				OCallBridgeError::FinalizeSidechainBlock(
+					"Failed to decode finalized block".to_string(),
+				)
+			})?;
+
+		info!(
+			"[O-call] sidechain block {} of shard {:?} is final",
+			finalized_block.block_number, shard_identifier
+		);
+
+		self.block_storage
+			.finalize_block(
+				&shard_identifier,
+				finalized_block.block_number,
+				finalized_block.block_header_hash,
+			)
+			.map_err(|e| OCallBridgeError::FinalizeSidechainBlock(format!("{:?}", e)))
+	}
 }
 
 #[cfg(test)]
@@ -212,6 +245,15 @@ mod tests {
 		fn store_blocks(&self, _blocks: Vec<SignedSidechainBlock>) -> StorageResult<()> {
 			Ok(())
 		}
+
+		fn finalize_block(
+			&self,
+			_shard_identifier: &ShardIdentifier,
+			_block_number: u64,
+			_block_hash: BlockHash,
+		) -> StorageResult<()> {
+			Ok(())
+		}
 	}
 
 	type TestSidechainOCall = SidechainOCall<
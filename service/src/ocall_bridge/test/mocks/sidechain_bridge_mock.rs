@@ -47,4 +47,12 @@ impl SidechainBridge for SidechainBridgeMock {
 	) -> OCallBridgeResult<Vec<u8>> {
 		Ok(self.peer_blocks_encoded.clone())
 	}
+
+	fn finalize_sidechain_block(
+		&self,
+		_shard_identifier_encoded: Vec<u8>,
+		_finalized_block_encoded: Vec<u8>,
+	) -> OCallBridgeResult<()> {
+		Ok(())
+	}
 }
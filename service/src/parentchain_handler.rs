@@ -16,19 +16,25 @@
 
 */
 
-use crate::error::{Error, ServiceResult};
+use crate::{
+	error::{Error, ServiceResult},
+	relay_chain_follower::RelayChainFollower,
+};
 use itc_parentchain::{
-	light_client::light_client_init_params::{GrandpaParams, SimpleParams},
+	light_client::{
+		light_client_init_params::{GrandpaParams, SimpleParams},
+		parachain_inclusion::ParachainInclusionProof,
+	},
 	primitives::{ParentchainId, ParentchainInitParams},
 };
 use itp_enclave_api::{enclave_base::EnclaveBase, sidechain::Sidechain};
-use itp_node_api::api_client::ChainApi;
+use itp_node_api::api_client::{ChainApi, SignedBlock};
 use itp_storage::StorageProof;
 use log::*;
-use my_node_runtime::Header;
+use my_node_runtime::{Hash, Header};
 use sp_consensus_grandpa::VersionedAuthorityList;
 use sp_runtime::traits::Header as HeaderTrait;
-use std::{cmp::min, sync::Arc};
+use std::{cmp::min, path::Path, sync::Arc};
 
 const BLOCK_SYNC_BATCH_SIZE: u32 = 1000;
 
@@ -58,6 +64,8 @@ pub(crate) struct ParentchainHandler<ParentchainApi, EnclaveApi> {
 	parentchain_api: ParentchainApi,
 	enclave_api: Arc<EnclaveApi>,
 	parentchain_init_params: ParentchainInitParams,
+	/// Relay chain of the parentchain, if the light client follows its finality.
+	relay_chain: Option<RelayChainFollower<ParentchainApi>>,
 }
 
 impl<ParentchainApi, EnclaveApi> ParentchainHandler<ParentchainApi, EnclaveApi>
@@ -70,7 +78,32 @@ where
 		enclave_api: Arc<EnclaveApi>,
 		parentchain_init_params: ParentchainInitParams,
 	) -> Self {
-		Self { parentchain_api, enclave_api, parentchain_init_params }
+		Self { parentchain_api, enclave_api, parentchain_init_params, relay_chain: None }
+	}
+
+	/// Handler of a parachain whose light client only accepts blocks proven to be included
+	/// in a relay chain block finalized by GRANDPA.
+	pub fn new_relay_chain_backed(
+		parentchain_api: ParentchainApi,
+		relay_chain_api: ParentchainApi,
+		enclave_api: Arc<EnclaveApi>,
+		id: ParentchainId,
+		data_dir: &Path,
+	) -> ServiceResult<Self> {
+		let genesis_hash = parentchain_api.get_genesis_hash()?;
+		let genesis_header =
+			parentchain_api.header(Some(genesis_hash))?.ok_or(Error::MissingGenesisHeader)?;
+		let para_id = parentchain_api.parachain_id()?.ok_or(Error::MissingParachainId)?;
+
+		let relay_chain = RelayChainFollower::new(relay_chain_api, para_id, data_dir, id)?;
+		let parentchain_init_params = (id, relay_chain.init_params(genesis_header)?).into();
+
+		Ok(Self {
+			parentchain_api,
+			enclave_api,
+			parentchain_init_params,
+			relay_chain: Some(relay_chain),
+		})
 	}
 
 	// FIXME: Necessary in the future? Fix with #1080
@@ -125,11 +158,19 @@ where
 	fn sync_parentchain(&self, last_synced_header: Header) -> ServiceResult<Header> {
 		let id = self.parentchain_id();
 		trace!("[{:?}] Getting current head", id);
-		let curr_block = self
-			.parentchain_api
-			.last_finalized_block()?
-			.ok_or(Error::MissingLastFinalizedBlock)?;
-		let curr_block_number = curr_block.block.header.number;
+		let (curr_block_number, mut inclusion_proof) = match &self.relay_chain {
+			Some(relay_chain) => match relay_chain.prove_latest_para_head()? {
+				Some((para_head, proof)) => (para_head.number, Some((para_head.hash(), proof))),
+				None => return Ok(last_synced_header),
+			},
+			None => {
+				let curr_block = self
+					.parentchain_api
+					.last_finalized_block()?
+					.ok_or(Error::MissingLastFinalizedBlock)?;
+				(curr_block.block.header.number, None)
+			},
+		};
 
 		println!(
 			"[{:?}] Syncing blocks from {} to {}",
@@ -138,7 +179,7 @@ where
 
 		let mut until_synced_header = last_synced_header;
 		loop {
-			let block_chunk_to_sync = self.parentchain_api.get_blocks(
+			let mut block_chunk_to_sync = self.parentchain_api.get_blocks(
 				until_synced_header.number + 1,
 				min(until_synced_header.number + BLOCK_SYNC_BATCH_SIZE, curr_block_number),
 			)?;
@@ -163,6 +204,9 @@ where
 				})
 				.collect::<Result<Vec<_>, _>>()?;
 
+			let attached_proof =
+				attach_inclusion_proof(&mut inclusion_proof, &mut block_chunk_to_sync);
+
 			self.enclave_api.sync_parentchain(
 				block_chunk_to_sync.as_slice(),
 				events_chunk_to_sync.as_slice(),
@@ -170,6 +214,12 @@ where
 				self.parentchain_id(),
 			)?;
 
+			if let (Some(relay_chain), Some(relay_block_number)) =
+				(&self.relay_chain, attached_proof)
+			{
+				relay_chain.set_last_proven_block(relay_block_number)?;
+			}
+
 			until_synced_header = block_chunk_to_sync
 				.last()
 				.map(|b| b.block.header.clone())
@@ -210,3 +260,17 @@ where
 		Ok(last_synced_header)
 	}
 }
+
+/// Attaches the inclusion proof to the justifications of the parachain block it proves, if
+/// that block is part of the chunk. Returns the number of the proven relay chain block then.
+fn attach_inclusion_proof(
+	inclusion_proof: &mut Option<(Hash, ParachainInclusionProof<Header>)>,
+	blocks: &mut [SignedBlock],
+) -> Option<u32> {
+	let proven_hash = inclusion_proof.as_ref()?.0;
+	let block = blocks.iter_mut().find(|b| b.block.header.hash() == proven_hash)?;
+	let (_, proof) = inclusion_proof.take()?;
+
+	block.justifications = Some(proof.append_to(block.justifications.take()));
+	Some(proof.relay_header.number)
+}
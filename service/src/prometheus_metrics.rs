@@ -35,10 +35,13 @@ use itc_rest_client::{
 	rest_client::{RestClient, Url as URL},
 	RestGet, RestPath,
 };
-use itp_enclave_metrics::EnclaveMetric;
+use itp_enclave_metrics::{badge_tier_label, EnclaveMetric, PersonhoodOracleMetric};
 use lazy_static::lazy_static;
 use log::*;
-use prometheus::{proto::MetricFamily, register_int_gauge, IntGauge};
+use prometheus::{
+	proto::MetricFamily, register_histogram_vec, register_int_counter_vec, register_int_gauge,
+	HistogramVec, IntCounterVec, IntGauge,
+};
 use serde::{Deserialize, Serialize};
 use std::{net::SocketAddr, sync::Arc};
 use warp::{Filter, Rejection, Reply};
@@ -55,6 +58,27 @@ lazy_static! {
 	static ref ENCLAVE_SIDECHAIN_TOP_POOL_SIZE: IntGauge =
 		register_int_gauge!("integritee_worker_enclave_sidechain_top_pool_size", "Enclave sidechain top pool size")
 			.unwrap();
+
+	/// Personhood oracle metrics. Labels must never contain account ids or Nostr keys.
+
+	static ref PERSONHOOD_REPUTATION_LOOKUPS: IntCounterVec =
+		register_int_counter_vec!("integritee_worker_enclave_personhood_oracle_reputation_lookups", "Number of reputation lookups, partitioned into the lookup result", &["result"])
+			.unwrap();
+	static ref PERSONHOOD_REPUTATION_CACHE_LOOKUPS: IntCounterVec =
+		register_int_counter_vec!("integritee_worker_enclave_personhood_oracle_reputation_cache_lookups", "Number of lookups in the enclave's reputation cache, partitioned into hits and misses", &["outcome"])
+			.unwrap();
+	static ref PERSONHOOD_VERIFICATION_FAILURES: IntCounterVec =
+		register_int_counter_vec!("integritee_worker_enclave_personhood_oracle_verification_failures", "Number of failed personhood verifications, partitioned into the failure reason", &["reason"])
+			.unwrap();
+	static ref PERSONHOOD_BADGES_ISSUED: IntCounterVec =
+		register_int_counter_vec!("integritee_worker_enclave_personhood_oracle_badges_issued", "Number of issued badges per tier (number of verified reputations)", &["tier"])
+			.unwrap();
+	static ref PERSONHOOD_RELAY_PUBLISHES: IntCounterVec =
+		register_int_counter_vec!("integritee_worker_enclave_personhood_oracle_relay_publishes", "Number of attempts to publish to a Nostr relay, partitioned into relay host and outcome", &["relay", "outcome"])
+			.unwrap();
+	static ref PERSONHOOD_RPC_LATENCY: HistogramVec =
+		register_histogram_vec!("integritee_worker_enclave_personhood_oracle_rpc_latency_seconds", "Latency of the personhood oracle RPC calls", &["method"])
+			.unwrap();
 }
 
 pub async fn start_metrics_server<MetricsHandler>(
@@ -177,11 +201,55 @@ impl ReceiveEnclaveMetrics for EnclaveMetricsReceiver {
 			EnclaveMetric::ExchangeRateOracle(_) => {
 				error!("Received Teeracle metric, but Teeracle feature is not enabled, ignoring metric item.")
 			},
+			EnclaveMetric::PersonhoodOracle(m) => update_personhood_oracle_metrics(m)?,
 		}
 		Ok(())
 	}
 }
 
+fn update_personhood_oracle_metrics(metric: PersonhoodOracleMetric) -> ServiceResult<()> {
+	match metric {
+		PersonhoodOracleMetric::ReputationLookups(counts) => {
+			for (result, count) in counts.lookups() {
+				PERSONHOOD_REPUTATION_LOOKUPS
+					.get_metric_with_label_values(&[result.label()])
+					.map(|m| m.inc_by(count.into()))
+					.map_err(|e| Error::Custom(e.into()))?
+			}
+			for (outcome, count) in [("hit", counts.cache_hits), ("miss", counts.cache_misses)] {
+				PERSONHOOD_REPUTATION_CACHE_LOOKUPS
+					.get_metric_with_label_values(&[outcome])
+					.map(|m| m.inc_by(count.into()))
+					.map_err(|e| Error::Custom(e.into()))?
+			}
+		},
+
+		PersonhoodOracleMetric::VerificationFailure(reason) => PERSONHOOD_VERIFICATION_FAILURES
+			.get_metric_with_label_values(&[reason.label()])
+			.map(|m| m.inc())
+			.map_err(|e| Error::Custom(e.into()))?,
+
+		PersonhoodOracleMetric::BadgeIssued(tier) => PERSONHOOD_BADGES_ISSUED
+			.get_metric_with_label_values(&[badge_tier_label(tier)])
+			.map(|m| m.inc())
+			.map_err(|e| Error::Custom(e.into()))?,
+
+		PersonhoodOracleMetric::RelayPublish(relay, success) => {
+			let outcome = if success { "success" } else { "failure" };
+			PERSONHOOD_RELAY_PUBLISHES
+				.get_metric_with_label_values(&[relay.as_str(), outcome])
+				.map(|m| m.inc())
+				.map_err(|e| Error::Custom(e.into()))?
+		},
+
+		PersonhoodOracleMetric::RpcLatency(method, millis) => PERSONHOOD_RPC_LATENCY
+			.get_metric_with_label_values(&[method.as_str()])
+			.map(|m| m.observe(millis as f64 / 1000.0))
+			.map_err(|e| Error::Custom(e.into()))?,
+	};
+	Ok(())
+}
+
 // Data structure that matches with REST API JSON
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -0,0 +1,179 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Follows the GRANDPA finality of the relay chain of a parachain and fetches the proofs
+//! the enclave needs to accept parachain blocks as final.
+//!
+//! The relay chain block up to which the enclave knows the relay chain authority set is
+//! persisted in the data dir. When starting without it, the latest finalized relay chain
+//! block is used as checkpoint.
+
+use crate::error::{Error, ServiceResult};
+use codec::{Decode, Encode};
+use itc_parentchain::{
+	light_client::parachain_inclusion::ParachainInclusionProof,
+	primitives::{ParentchainId, RelayChainBackedParachainParams},
+};
+use itp_node_api::api_client::{ChainApi, ParaId};
+use itp_types::parentchain::BlockNumber;
+use log::*;
+use my_node_runtime::Header;
+use sp_runtime::traits::Header as HeaderTrait;
+use std::{
+	fs,
+	path::{Path, PathBuf},
+	sync::Mutex,
+};
+
+const LAST_PROVEN_RELAY_BLOCK_FILE_SUFFIX: &str = "last_proven_relay_chain_block.bin";
+
+pub(crate) struct RelayChainFollower<RelayChainApi> {
+	relay_chain_api: RelayChainApi,
+	para_id: ParaId,
+	last_proven_block: Mutex<BlockNumber>,
+	last_proven_block_path: PathBuf,
+}
+
+impl<RelayChainApi: ChainApi> RelayChainFollower<RelayChainApi> {
+	pub fn new(
+		relay_chain_api: RelayChainApi,
+		para_id: ParaId,
+		data_dir: &Path,
+		id: ParentchainId,
+	) -> ServiceResult<Self> {
+		let last_proven_block_path =
+			data_dir.join(format!("{:?}_{}", id, LAST_PROVEN_RELAY_BLOCK_FILE_SUFFIX));
+
+		let last_proven_block = match fs::read(&last_proven_block_path) {
+			Ok(encoded) => BlockNumber::decode(&mut encoded.as_slice())?,
+			Err(_) => {
+				let checkpoint = relay_chain_api
+					.last_finalized_block()?
+					.ok_or(Error::MissingLastFinalizedBlock)?
+					.block
+					.header
+					.number;
+				info!("[{:?}] Following relay chain from checkpoint block {}", id, checkpoint);
+				checkpoint
+			},
+		};
+
+		Ok(Self {
+			relay_chain_api,
+			para_id,
+			last_proven_block: Mutex::new(last_proven_block),
+			last_proven_block_path,
+		})
+	}
+
+	/// Light client parameters with the relay chain authorities at the last proven relay block.
+	///
+	/// The enclave only initializes the relay chain authorities from them once, and refuses
+	/// them for another relay chain afterwards.
+	pub fn init_params(
+		&self,
+		genesis_header: Header,
+	) -> ServiceResult<RelayChainBackedParachainParams> {
+		let relay_header = self.relay_header(*self.last_proven_block.lock().unwrap())?;
+		let relay_hash = Some(relay_header.hash());
+
+		let authorities = self.relay_chain_api.grandpa_authorities(relay_hash)?;
+		let set_id = self.relay_chain_api.grandpa_set_id(relay_hash)?;
+		let proof = self.relay_chain_api.grandpa_authorities_and_set_id_proof(relay_hash)?;
+
+		Ok(RelayChainBackedParachainParams::new(
+			genesis_header,
+			self.para_id,
+			self.relay_chain_api.get_genesis_hash()?,
+			relay_header,
+			authorities,
+			set_id,
+			proof,
+		))
+	}
+
+	/// Returns the parachain head of the latest finalized relay chain block, together with
+	/// the proof of its inclusion. Returns `None` if no relay chain block has been finalized
+	/// since the last proven one.
+	pub fn prove_latest_para_head(
+		&self,
+	) -> ServiceResult<Option<(Header, ParachainInclusionProof<Header>)>> {
+		let finalized_block = self
+			.relay_chain_api
+			.last_finalized_block()?
+			.ok_or(Error::MissingLastFinalizedBlock)?
+			.block
+			.header
+			.number;
+
+		let mut justified_headers = Vec::new();
+		let mut next_block = *self.last_proven_block.lock().unwrap() + 1;
+
+		// Each finality proof justifies either the last block of an authority set or the latest
+		// justified block, so the walk yields all authority set changes on the way.
+		while next_block <= finalized_block {
+			let finality_proof = match self.relay_chain_api.grandpa_finality_proof(next_block)? {
+				Some(finality_proof) => finality_proof,
+				None => break,
+			};
+			let header = self
+				.relay_chain_api
+				.header(Some(finality_proof.block))?
+				.ok_or(Error::MissingRelayChainBlock(next_block))?;
+			next_block = header.number + 1;
+			justified_headers.push((header, finality_proof.justification));
+		}
+
+		let (relay_header, relay_justification) = match justified_headers.pop() {
+			Some(justified_header) => justified_header,
+			None => return Ok(None),
+		};
+
+		let relay_hash = Some(relay_header.hash());
+		let encoded_para_head = self
+			.relay_chain_api
+			.para_head(self.para_id, relay_hash)?
+			.ok_or(Error::MissingParaHead(self.para_id))?;
+		let para_head = Header::decode(&mut encoded_para_head.as_slice())?;
+		let para_head_proof = self.relay_chain_api.para_head_proof(self.para_id, relay_hash)?;
+
+		Ok(Some((
+			para_head,
+			ParachainInclusionProof {
+				authority_set_changes: justified_headers,
+				relay_header,
+				relay_justification,
+				para_head_proof,
+			},
+		)))
+	}
+
+	/// Records that the enclave has received the proof up to the given relay chain block.
+	pub fn set_last_proven_block(&self, block_number: BlockNumber) -> ServiceResult<()> {
+		*self.last_proven_block.lock().unwrap() = block_number;
+		fs::write(&self.last_proven_block_path, block_number.encode())?;
+		Ok(())
+	}
+
+	fn relay_header(&self, block_number: BlockNumber) -> ServiceResult<Header> {
+		self.relay_chain_api
+			.get_blocks(block_number, block_number)?
+			.pop()
+			.map(|signed_block| signed_block.block.header)
+			.ok_or(Error::MissingRelayChainBlock(block_number))
+	}
+}
@@ -0,0 +1,53 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Hand-over of the sealed secrets from a worker to its upgraded successor.
+//!
+//! The successor writes its import key with `state-import-key`, the predecessor exports its
+//! secrets to it with `export-secrets`, and the successor imports them with `import-secrets`.
+
+use base58::ToBase58;
+use itp_enclave_api::secret_migration::SecretMigration;
+use itp_types::ShardIdentifier;
+use log::*;
+
+pub(crate) fn export_sealed_secrets<E: SecretMigration>(
+	enclave: &E,
+	shard: &ShardIdentifier,
+	import_key_path: &str,
+	export_path: &str,
+) {
+	match enclave.export_sealed_secrets(shard, import_key_path, export_path) {
+		Err(e) =>
+			error!("[-] Failed to export sealed secrets for shard {}: {:?}", shard.0.to_base58(), e),
+		Ok(_) => println!(
+			"[+] Exported sealed secrets for shard {} to '{}'",
+			shard.0.to_base58(),
+			export_path
+		),
+	}
+}
+
+pub(crate) fn import_sealed_secrets<E: SecretMigration>(enclave: &E, secrets_path: &str) {
+	match enclave.import_sealed_secrets(secrets_path) {
+		Err(e) => error!("[-] Failed to import sealed secrets '{}': {:?}", secrets_path, e),
+		Ok(_) => println!(
+			"[+] Imported sealed secrets '{}'. Restart the worker to use them",
+			secrets_path
+		),
+	}
+}
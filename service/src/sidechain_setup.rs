@@ -24,14 +24,14 @@ use futures::executor::block_on;
 use itp_enclave_api::{
 	direct_request::DirectRequest, enclave_base::EnclaveBase, sidechain::Sidechain,
 };
-use itp_settings::{
-	files::{SIDECHAIN_PURGE_INTERVAL, SIDECHAIN_PURGE_LIMIT},
-	sidechain::SLOT_DURATION,
-};
+use itp_settings::{files::SIDECHAIN_PURGE_INTERVAL, sidechain::SLOT_DURATION};
 use itp_types::Header;
 use its_consensus_slots::start_slot_worker;
 use its_primitives::types::block::SignedBlock as SignedSidechainBlock;
-use its_storage::{interface::FetchBlocks, start_sidechain_pruning_loop, BlockPruner};
+use its_storage::{
+	interface::FetchBlocks, start_sidechain_pruning_loop, BlockPruner, PruningPolicy,
+	SubscribeHeads,
+};
 use log::*;
 use std::{sync::Arc, thread};
 use tokio::runtime::Handle;
@@ -43,7 +43,12 @@ pub(crate) fn sidechain_start_untrusted_rpc_server<Enclave, SidechainStorage>(
 	tokio_handle: Handle,
 ) where
 	Enclave: DirectRequest + Clone,
-	SidechainStorage: BlockPruner + FetchBlocks<SignedSidechainBlock> + Sync + Send + 'static,
+	SidechainStorage: BlockPruner
+		+ FetchBlocks<SignedSidechainBlock>
+		+ SubscribeHeads<SignedSidechainBlock>
+		+ Sync
+		+ Send
+		+ 'static,
 {
 	let untrusted_url = config.untrusted_worker_url();
 	println!("[+] Untrusted RPC server listening on {}", &untrusted_url);
@@ -61,6 +66,7 @@ pub(crate) fn sidechain_init_block_production<Enclave, SidechainStorage, Parentc
 	parentchain_handler: Arc<ParentchainHandler>,
 	sidechain_storage: Arc<SidechainStorage>,
 	last_synced_header: &Header,
+	sidechain_pruning_policy: PruningPolicy,
 ) -> ServiceResult<Header>
 where
 	Enclave: EnclaveBase + Sidechain,
@@ -109,7 +115,7 @@ where
 			start_sidechain_pruning_loop(
 				&sidechain_storage,
 				SIDECHAIN_PURGE_INTERVAL,
-				SIDECHAIN_PURGE_LIMIT,
+				sidechain_pruning_policy,
 			);
 		})
 		.map_err(|e| Error::Custom(Box::new(e)))?;
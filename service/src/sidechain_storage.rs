@@ -0,0 +1,69 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+	Copyright (C) 2017-2019 Baidu, Inc. All Rights Reserved.
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Maintenance of the sidechain block storage: one-off pruning with compaction and size reports.
+
+use base58::ToBase58;
+use its_primitives::types::SignedBlock as SignedSidechainBlock;
+use its_storage::{BlockPruner, PruningPolicy, ShardStorageReport};
+use log::*;
+use std::str::FromStr;
+
+/// Prune the sidechain storage once according to the pruning policy and compact it.
+pub(crate) fn compact_sidechain_storage<S>(
+	sidechain_storage: &S,
+	maybe_pruning_policy: Option<&str>,
+) where
+	S: BlockPruner + ShardStorageReport<SignedSidechainBlock>,
+{
+	let pruning_policy = maybe_pruning_policy
+		.map(|p| PruningPolicy::from_str(p).expect("invalid pruning policy"))
+		.unwrap_or_default();
+
+	println!("[+] Pruning sidechain storage with policy {:?}", pruning_policy);
+	sidechain_storage.prune_blocks(&pruning_policy);
+	sidechain_storage.compact();
+	println!("[+] Compacted sidechain storage");
+	print_sidechain_storage_info(sidechain_storage);
+}
+
+/// Print the storage usage of every shard in the sidechain storage.
+pub(crate) fn print_sidechain_storage_info<S>(sidechain_storage: &S)
+where
+	S: ShardStorageReport<SignedSidechainBlock>,
+{
+	let shard_infos = match sidechain_storage.shard_storage_infos() {
+		Ok(infos) => infos,
+		Err(e) => {
+			error!("[-] Failed to read sidechain storage info: {:?}", e);
+			return
+		},
+	};
+
+	if shard_infos.is_empty() {
+		println!("Sidechain storage is empty");
+	}
+	for (shard, info) in shard_infos {
+		println!("shard {}:", shard.0.to_base58());
+		println!("  blocks:          {}", info.number_of_blocks);
+		println!("  first block:     {:?}", info.first_block_number);
+		println!("  last block:      {:?}", info.last_block_number);
+		println!("  finalized block: {:?}", info.finalized_block_number);
+		println!("  size:            {} bytes", info.size_in_bytes);
+	}
+}
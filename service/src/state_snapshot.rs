@@ -0,0 +1,66 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Export and import of encrypted shard state snapshots, for backups, shard migration and
+//! cold restores without an online peer.
+
+use base58::ToBase58;
+use itp_enclave_api::state_snapshot::StateSnapshot;
+use itp_types::{ShardIdentifier, H256};
+use log::*;
+use std::str::FromStr;
+
+/// Write the signed shielding key of our enclave, to be handed to the exporting worker.
+pub(crate) fn write_state_import_key<E: StateSnapshot>(enclave: &E, import_key_path: &str) {
+	match enclave.write_state_import_key(import_key_path) {
+		Err(e) => error!("[-] Failed to write state import key '{}': {:?}", import_key_path, e),
+		Ok(_) => println!("[+] File '{}' written successfully", import_key_path),
+	}
+}
+
+pub(crate) fn export_state_snapshot<E: StateSnapshot>(
+	enclave: &E,
+	shard: &ShardIdentifier,
+	maybe_state_hash: Option<&str>,
+	import_key_path: &str,
+	export_path: &str,
+) {
+	let maybe_state_hash = match maybe_state_hash.map(H256::from_str).transpose() {
+		Ok(maybe_state_hash) => maybe_state_hash,
+		Err(e) => {
+			error!("[-] State hash must be a hex encoded 32 byte hash: {:?}", e);
+			return
+		},
+	};
+
+	match enclave.export_state_snapshot(shard, maybe_state_hash, import_key_path, export_path) {
+		Err(e) =>
+			error!("[-] Failed to export state snapshot of shard {}: {:?}", shard.0.to_base58(), e),
+		Ok(_) => println!(
+			"[+] Exported state snapshot of shard {} to '{}'",
+			shard.0.to_base58(),
+			export_path
+		),
+	}
+}
+
+pub(crate) fn import_state_snapshot<E: StateSnapshot>(enclave: &E, snapshot_path: &str) {
+	match enclave.import_state_snapshot(snapshot_path) {
+		Err(e) => error!("[-] Failed to import state snapshot '{}': {:?}", snapshot_path, e),
+		Ok(_) => println!("[+] Imported state snapshot '{}'", snapshot_path),
+	}
+}
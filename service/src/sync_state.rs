@@ -24,7 +24,7 @@ use futures::executor;
 use itc_rpc_client::direct_client::{DirectApi, DirectClient as DirectWorkerApi};
 use itp_enclave_api::{
 	enclave_base::EnclaveBase,
-	remote_attestation::{RemoteAttestation, TlsRemoteAttestation},
+	remote_attestation::{ProvisioningItem, RemoteAttestation, TlsRemoteAttestation},
 };
 use itp_node_api::api_client::PalletTeerexApi;
 use itp_settings::worker_mode::{ProvideWorkerMode, WorkerMode};
@@ -40,6 +40,7 @@ pub(crate) fn sync_state<
 	node_api: &NodeApi,
 	shard: &ShardIdentifier,
 	enclave_api: &E,
+	items: &[ProvisioningItem],
 	skip_ra: bool,
 ) {
 	// FIXME: we now assume that keys are equal for all shards.
@@ -58,6 +59,7 @@ pub(crate) fn sync_state<
 		sgx_quote_sign_type_t::SGX_UNLINKABLE_SIGNATURE,
 		&provider_url,
 		shard,
+		items,
 		skip_ra,
 	)
 	.unwrap();
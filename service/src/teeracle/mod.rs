@@ -15,11 +15,21 @@
 
 */
 
-use crate::teeracle::schedule_periodic::schedule_periodic;
-
+use crate::{
+	parentchain_handler::HandleParentchain, teeracle::schedule_periodic::schedule_periodic,
+};
+use codec::{Decode, Encode};
+use itp_enclave_api::teeracle_api::TeeracleApi;
+use itp_node_api::api_client::{PalletTeeracleApi, ParentchainApi};
 use itp_types::parentchain::Hash;
 use log::*;
-use std::time::Duration;
+use my_node_runtime::Header;
+use sp_runtime::OpaqueExtrinsic;
+use std::{
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+use substrate_api_client::SubmitExtrinsic;
 
 pub(crate) mod schedule_periodic;
 pub(crate) mod teeracle_metrics;
@@ -57,3 +67,77 @@ pub(crate) fn schedule_periodic_reregistration_thread(
 		})
 		.unwrap();
 }
+
+/// Schedule periodic market data updates.
+///
+/// Which exchange rate feeds are served is governed by the teeracle pallet. We hand the feeds
+/// currently present on-chain to the enclave as candidates; the enclave verifies them against
+/// the parentchain state with read proofs. Hence, the light client is synced before every update.
+pub(crate) fn schedule_periodic_market_data_update_thread<E, ParentchainHandler>(
+	api: ParentchainApi,
+	enclave: Arc<E>,
+	parentchain_handler: Arc<ParentchainHandler>,
+	last_synced_header: Header,
+	period: Duration,
+) where
+	E: TeeracleApi + Send + Sync + 'static,
+	ParentchainHandler: HandleParentchain + Send + Sync + 'static,
+{
+	println!("Schedule periodic market data update every: {:?}", period);
+	let last_synced_header = Mutex::new(last_synced_header);
+
+	std::thread::Builder::new()
+		.name("market_data_update_thread".to_owned())
+		.spawn(move || {
+			schedule_periodic(
+				|| {
+					let mut last_synced_header = last_synced_header.lock().unwrap();
+					match parentchain_handler.sync_parentchain(last_synced_header.clone()) {
+						Ok(header) => *last_synced_header = header,
+						Err(e) => {
+							error!(
+								"Could not sync the parentchain, skipping market data update: {:?}",
+								e
+							);
+							return
+						},
+					}
+					execute_market_data_update(&api, enclave.as_ref());
+				},
+				period,
+			);
+		})
+		.unwrap();
+}
+
+fn execute_market_data_update<E: TeeracleApi>(api: &ParentchainApi, enclave: &E) {
+	trace!("Executing market data update");
+	let candidate_sources = api.whitelisted_data_sources(None).unwrap_or_else(|e| {
+		warn!("Could not fetch the whitelisted data sources from the parentchain: {:?}", e);
+		Vec::new()
+	});
+	debug!("Candidate data sources: {}", candidate_sources.len());
+
+	let updated_extrinsics = match enclave.update_market_data_xt(&candidate_sources) {
+		Ok(xts) => xts,
+		Err(e) => {
+			error!("Updating market data failed: {:?}", e);
+			return
+		},
+	};
+
+	let extrinsics = match <Vec<OpaqueExtrinsic>>::decode(&mut updated_extrinsics.as_slice()) {
+		Ok(xts) => xts,
+		Err(e) => {
+			error!("Failed to decode market data update extrinsics: {:?}", e);
+			return
+		},
+	};
+
+	for xt in extrinsics {
+		match api.submit_opaque_extrinsic(xt.encode().into()) {
+			Ok(hash) => println!("[>] Market data update sent. Extrinsic hash: {:?}", hash),
+			Err(e) => error!("Failed to send market data update extrinsic: {:?}", e),
+		}
+	}
+}
@@ -21,11 +21,12 @@ use enclave_bridge_primitives::EnclaveFingerprint;
 use frame_support::sp_runtime::traits::Block as ParentchainBlockTrait;
 use itc_parentchain::primitives::{
 	ParentchainId, ParentchainInitParams,
-	ParentchainInitParams::{Parachain, Solochain},
+	ParentchainInitParams::{Parachain, RelayChainBackedParachain, Solochain},
 };
 use itp_enclave_api::{enclave_base::EnclaveBase, sidechain::Sidechain, EnclaveResult};
 use itp_settings::worker::MR_ENCLAVE_SIZE;
 use itp_storage::StorageProof;
+use itp_types::personhood::PersonhoodSourceConfig;
 use sgx_crypto_helper::rsa3072::Rsa3072PubKey;
 use sp_core::ed25519;
 
@@ -52,6 +53,7 @@ impl EnclaveBase for EnclaveMock {
 		let genesis_header_encoded = match params {
 			Solochain { params, .. } => params.genesis_header.encode(),
 			Parachain { params, .. } => params.genesis_header.encode(),
+			RelayChainBackedParachain { params, .. } => params.genesis_header.encode(),
 		};
 		let header = Header::decode(&mut genesis_header_encoded.as_slice())?;
 		Ok(header)
@@ -73,6 +75,10 @@ impl EnclaveBase for EnclaveMock {
 		todo!()
 	}
 
+	fn set_personhood_sources(&self, _: &[PersonhoodSourceConfig]) -> EnclaveResult<()> {
+		todo!()
+	}
+
 	fn get_rsa_shielding_pubkey(&self) -> EnclaveResult<Rsa3072PubKey> {
 		unreachable!()
 	}
@@ -100,4 +106,8 @@ impl Sidechain for EnclaveMock {
 	fn execute_trusted_calls(&self) -> EnclaveResult<()> {
 		todo!()
 	}
+
+	fn seal_top_pool(&self) -> EnclaveResult<()> {
+		Ok(())
+	}
 }
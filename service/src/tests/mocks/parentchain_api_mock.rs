@@ -16,12 +16,12 @@
 */
 
 use itc_parentchain_test::{ParentchainBlockBuilder, ParentchainHeaderBuilder};
-use itp_node_api::api_client::{ApiResult, ChainApi, SignedBlock};
+use itp_node_api::api_client::{ApiResult, ChainApi, GrandpaFinalityProof, ParaId, SignedBlock};
 use itp_types::{
 	parentchain::{Hash, Header, StorageProof},
 	H256,
 };
-use sp_consensus_grandpa::AuthorityList;
+use sp_consensus_grandpa::{AuthorityList, SetId};
 
 pub struct ParentchainApiMock {
 	parentchain: Vec<SignedBlock>,
@@ -92,4 +92,31 @@ impl ChainApi for ParentchainApiMock {
 	fn get_events_for_block(&self, _block_hash: Option<H256>) -> ApiResult<Vec<u8>> {
 		Ok(Default::default())
 	}
+
+	fn grandpa_set_id(&self, _hash: Option<H256>) -> ApiResult<SetId> {
+		todo!()
+	}
+
+	fn grandpa_authorities_and_set_id_proof(&self, _hash: Option<H256>) -> ApiResult<StorageProof> {
+		todo!()
+	}
+
+	fn grandpa_finality_proof(
+		&self,
+		_block_number: u32,
+	) -> ApiResult<Option<GrandpaFinalityProof>> {
+		todo!()
+	}
+
+	fn parachain_id(&self) -> ApiResult<Option<ParaId>> {
+		todo!()
+	}
+
+	fn para_head(&self, _para_id: ParaId, _hash: Option<H256>) -> ApiResult<Option<Vec<u8>>> {
+		todo!()
+	}
+
+	fn para_head_proof(&self, _para_id: ParaId, _hash: Option<H256>) -> ApiResult<StorageProof> {
+		todo!()
+	}
 }
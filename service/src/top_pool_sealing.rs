@@ -0,0 +1,83 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Seals the pending trusted operations of the enclave periodically and on shutdown,
+//! so they are restored when the worker is restarted.
+
+use crate::error::{Error, ServiceResult};
+use itp_enclave_api::sidechain::Sidechain;
+use itp_settings::worker::TOP_POOL_SEAL_INTERVAL;
+use log::*;
+use std::{
+	sync::{
+		mpsc::{channel, Receiver},
+		Arc,
+	},
+	thread,
+	time::Duration,
+};
+use tokio::{
+	runtime::Handle,
+	signal::unix::{signal, SignalKind},
+};
+
+pub(crate) fn start_top_pool_sealing<Enclave>(enclave: Arc<Enclave>) -> ServiceResult<()>
+where
+	Enclave: Sidechain,
+{
+	println!("[+] Spawning thread for periodic sealing of the trusted operation pool");
+	thread::Builder::new()
+		.name("top_pool_sealing_loop".to_owned())
+		.spawn(move || loop {
+			thread::sleep(Duration::from_secs(TOP_POOL_SEAL_INTERVAL));
+			seal_top_pool(enclave.as_ref());
+		})
+		.map_err(|e| Error::Custom(Box::new(e)))?;
+
+	Ok(())
+}
+
+/// Returns a receiver that is notified once the worker is asked to shut down,
+/// so the main loop can seal the trusted operation pool and return.
+pub(crate) fn listen_for_shutdown_signal(tokio_handle: &Handle) -> Receiver<()> {
+	let (shutdown_sender, shutdown_receiver) = channel();
+	tokio_handle.spawn(async move {
+		if let Err(e) = shutdown_signal().await {
+			error!("Failed to listen for the shutdown signal: {:?}", e);
+			return
+		}
+		if shutdown_sender.send(()).is_err() {
+			warn!("Main loop is no longer waiting for the shutdown signal");
+		}
+	});
+	shutdown_receiver
+}
+
+/// Resolves on Ctrl-C (SIGINT) or SIGTERM, the latter being sent by docker and systemd.
+async fn shutdown_signal() -> std::io::Result<()> {
+	let mut terminate = signal(SignalKind::terminate())?;
+	tokio::select! {
+		result = tokio::signal::ctrl_c() => result,
+		_ = terminate.recv() => Ok(()),
+	}
+}
+
+pub(crate) fn seal_top_pool<E: Sidechain>(enclave_api: &E) {
+	if let Err(e) = enclave_api.seal_top_pool() {
+		error!("Failed to seal the trusted operation pool: {:?}", e);
+	}
+}
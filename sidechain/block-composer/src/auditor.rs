@@ -0,0 +1,124 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Encryption of block state diffs for the auditor of a shard.
+//!
+//! A shard may have an auditor public key registered on the parentchain. Each block's
+//! state payload is then additionally encrypted with a fresh state key, which in turn is
+//! encrypted with the auditor key, so that the auditor can read the state diffs block by block.
+
+use crate::error::{Error, Result};
+use codec::Encode;
+use itp_sgx_crypto::{Aes, ShieldingCryptoEncrypt, StateCrypto};
+use itp_types::ShardIdentifier;
+use its_primitives::types::block_data::AuditorStateDiff;
+use std::{format, vec::Vec};
+
+/// Encrypt the state payload of a block for the auditor of a shard.
+pub trait EncryptForAuditor<ParentchainHeader> {
+	/// Returns `None` if no auditor is registered for `shard` at `parentchain_header`.
+	fn encrypt_for_auditor(
+		&self,
+		shard: &ShardIdentifier,
+		parentchain_header: &ParentchainHeader,
+		state_payload: &[u8],
+	) -> Result<Option<AuditorStateDiff>>;
+}
+
+/// Does not encrypt state diffs for any auditor.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct NoAuditor;
+
+impl<ParentchainHeader> EncryptForAuditor<ParentchainHeader> for NoAuditor {
+	fn encrypt_for_auditor(
+		&self,
+		_shard: &ShardIdentifier,
+		_parentchain_header: &ParentchainHeader,
+		_state_payload: &[u8],
+	) -> Result<Option<AuditorStateDiff>> {
+		Ok(None)
+	}
+}
+
+/// Encrypt `state_payload` with `state_key` and the state key with `auditor_key`.
+///
+/// The state key must be freshly generated for each block.
+pub fn encrypt_state_payload_for_auditor<AuditorKey: ShieldingCryptoEncrypt>(
+	auditor_key: &AuditorKey,
+	state_key: Aes,
+	state_payload: &[u8],
+) -> Result<AuditorStateDiff> {
+	let mut encrypted_state_diff: Vec<u8> = state_payload.to_vec();
+	state_key.encrypt(&mut encrypted_state_diff).map_err(|e| {
+		Error::Other(format!("Failed to encrypt state payload for auditor: {:?}", e).into())
+	})?;
+
+	let encrypted_key = auditor_key.encrypt(&state_key.encode()).map_err(|e| {
+		Error::Other(format!("Failed to encrypt state key for auditor: {:?}", e).into())
+	})?;
+
+	Ok(AuditorStateDiff { encrypted_key, encrypted_state_diff })
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Decode;
+
+	/// Reverses the data, which is good enough to check what was encrypted.
+	struct ReversingAuditorKey;
+
+	impl ShieldingCryptoEncrypt for ReversingAuditorKey {
+		type Error = itp_sgx_crypto::Error;
+
+		fn encrypt(&self, data: &[u8]) -> core::result::Result<Vec<u8>, Self::Error> {
+			Ok(data.iter().rev().copied().collect())
+		}
+	}
+
+	#[test]
+	fn auditor_can_decrypt_state_payload_with_encrypted_state_key() {
+		let state_key = Aes::new([3u8; 16], [7u8; 16]);
+		let state_payload = vec![1u8, 2, 3, 4, 5, 42];
+
+		let auditor_diff =
+			encrypt_state_payload_for_auditor(&ReversingAuditorKey, state_key, &state_payload)
+				.unwrap();
+		assert_ne!(auditor_diff.encrypted_state_diff, state_payload);
+
+		let decrypted_key_bytes: Vec<u8> =
+			auditor_diff.encrypted_key.iter().rev().copied().collect();
+		let decrypted_key = Aes::decode(&mut decrypted_key_bytes.as_slice()).unwrap();
+		assert_eq!(decrypted_key, state_key);
+
+		let mut decrypted_payload = auditor_diff.encrypted_state_diff;
+		decrypted_key.decrypt(&mut decrypted_payload).unwrap();
+		assert_eq!(decrypted_payload, state_payload);
+	}
+
+	#[test]
+	fn no_auditor_does_not_encrypt() {
+		let result = EncryptForAuditor::<()>::encrypt_for_auditor(
+			&NoAuditor,
+			&ShardIdentifier::default(),
+			&(),
+			&[1u8, 2, 3],
+		)
+		.unwrap();
+		assert!(result.is_none());
+	}
+}
@@ -15,7 +15,10 @@
 
 */
 
-use crate::error::{Error, Result};
+use crate::{
+	auditor::{EncryptForAuditor, NoAuditor},
+	error::{Error, Result},
+};
 use codec::Encode;
 use ita_stf::StatePayload;
 use itp_settings::worker::BLOCK_NUMBER_FINALIZATION_DIFF;
@@ -27,7 +30,7 @@ use its_primitives::traits::{
 	Block as SidechainBlockTrait, BlockData, Header as HeaderTrait, SignBlock,
 	SignedBlock as SignedSidechainBlockTrait,
 };
-use its_state::{LastBlockExt, SidechainState, SidechainSystemExt};
+use its_state::{LastBlockExt, SidechainState, SidechainSystemExt, StateRoot};
 use log::*;
 use sp_core::Pair;
 use sp_runtime::{
@@ -52,9 +55,16 @@ pub trait ComposeBlock<Externalities, ParentchainBlock: ParentchainBlockTrait> {
 }
 
 /// Block composer implementation for the sidechain
-pub struct BlockComposer<ParentchainBlock, SignedSidechainBlock, Signer, StateKeyRepository> {
+pub struct BlockComposer<
+	ParentchainBlock,
+	SignedSidechainBlock,
+	Signer,
+	StateKeyRepository,
+	AuditorEncryption = NoAuditor,
+> {
 	signer: Signer,
 	state_key_repository: Arc<StateKeyRepository>,
+	auditor_encryption: AuditorEncryption,
 	_phantom: PhantomData<(ParentchainBlock, SignedSidechainBlock)>,
 }
 
@@ -74,7 +84,25 @@ where
 	<StateKeyRepository as AccessKey>::KeyType: StateCrypto,
 {
 	pub fn new(signer: Signer, state_key_repository: Arc<StateKeyRepository>) -> Self {
-		BlockComposer { signer, state_key_repository, _phantom: Default::default() }
+		Self::with_auditor_encryption(signer, state_key_repository, NoAuditor)
+	}
+}
+
+impl<ParentchainBlock, SignedSidechainBlock, Signer, StateKeyRepository, AuditorEncryption>
+	BlockComposer<ParentchainBlock, SignedSidechainBlock, Signer, StateKeyRepository, AuditorEncryption>
+{
+	/// Creates a block composer that additionally encrypts the state diffs for the shard auditor.
+	pub fn with_auditor_encryption(
+		signer: Signer,
+		state_key_repository: Arc<StateKeyRepository>,
+		auditor_encryption: AuditorEncryption,
+	) -> Self {
+		BlockComposer {
+			signer,
+			state_key_repository,
+			auditor_encryption,
+			_phantom: Default::default(),
+		}
 	}
 }
 
@@ -82,10 +110,21 @@ type HeaderTypeOf<T> = <<T as SignedSidechainBlockTrait>::Block as SidechainBloc
 type BlockDataTypeOf<T> =
 	<<T as SignedSidechainBlockTrait>::Block as SidechainBlockTrait>::BlockDataType;
 
-impl<ParentchainBlock, SignedSidechainBlock, Signer, StateKeyRepository, Externalities>
-	ComposeBlock<Externalities, ParentchainBlock>
-	for BlockComposer<ParentchainBlock, SignedSidechainBlock, Signer, StateKeyRepository>
-where
+impl<
+		ParentchainBlock,
+		SignedSidechainBlock,
+		Signer,
+		StateKeyRepository,
+		AuditorEncryption,
+		Externalities,
+	> ComposeBlock<Externalities, ParentchainBlock>
+	for BlockComposer<
+		ParentchainBlock,
+		SignedSidechainBlock,
+		Signer,
+		StateKeyRepository,
+		AuditorEncryption,
+	> where
 	ParentchainBlock: ParentchainBlockTrait<Hash = H256>,
 	SignedSidechainBlock:
 		SignedSidechainBlockTrait<Public = Signer::Public, Signature = MultiSignature>,
@@ -97,6 +136,7 @@ where
 		+ SidechainState
 		+ SidechainSystemExt
 		+ StateHash
+		+ StateRoot
 		+ LastBlockExt<SignedSidechainBlock::Block>
 		+ Encode,
 	<Externalities as SgxExternalitiesTrait>::SgxExternalitiesType: Encode,
@@ -105,6 +145,7 @@ where
 	Signer::Public: Encode,
 	StateKeyRepository: AccessKey,
 	<StateKeyRepository as AccessKey>::KeyType: StateCrypto,
+	AuditorEncryption: EncryptForAuditor<ParentchainBlock::Header>,
 {
 	type SignedSidechainBlock = SignedSidechainBlock;
 
@@ -142,6 +183,16 @@ where
 			StatePayload::new(state_hash_apriori, state_hash_new, aposteriori_state.state_diff())
 				.encode();
 
+		// The auditor copy is an addition to the block, failing to create it must not stop
+		// block production.
+		let auditor_state_diff = self
+			.auditor_encryption
+			.encrypt_for_auditor(&shard, latest_parentchain_header, &payload)
+			.unwrap_or_else(|e| {
+				error!("Failed to encrypt state diff for the shard auditor: {:?}", e);
+				None
+			});
+
 		let state_key = self
 			.state_key_repository
 			.retrieve_key()
@@ -157,7 +208,8 @@ where
 			top_call_hashes,
 			payload,
 			now_as_millis(),
-		);
+		)
+		.with_auditor_state_diff(auditor_state_diff);
 
 		let mut finalization_candidate = next_finalization_block_number;
 		if block_number == 1 {
@@ -171,6 +223,7 @@ where
 			parent_hash,
 			shard,
 			block_data.hash(),
+			aposteriori_state.state_root(),
 			finalization_candidate,
 		);
 
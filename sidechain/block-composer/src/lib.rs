@@ -30,6 +30,7 @@ pub mod sgx_reexport_prelude {
 	pub use thiserror_sgx as thiserror;
 }
 
+pub mod auditor;
 pub mod block_composer;
 pub mod error;
 
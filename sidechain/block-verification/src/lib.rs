@@ -36,7 +36,7 @@ use its_primitives::{
 		Block as SidechainBlockTrait, BlockData, Header as HeaderTrait,
 		SignedBlock as SignedSidechainBlockTrait, SignedBlock,
 	},
-	types::block::BlockHash,
+	types::{block::BlockHash, header::SIDECHAIN_HEADER_VERSION},
 };
 use log::*;
 pub use sp_consensus_slots::Slot;
@@ -70,6 +70,18 @@ where
 		ConsensusError::BadSidechainBlock(signed_block.block().hash(), "bad signature".into())
 	);
 
+	ensure!(
+		signed_block.block().header().version() == SIDECHAIN_HEADER_VERSION,
+		ConsensusError::BadSidechainBlock(
+			signed_block.block().hash(),
+			format!(
+				"Unsupported header version {}, expected {}",
+				signed_block.block().header().version(),
+				SIDECHAIN_HEADER_VERSION
+			)
+		)
+	);
+
 	let slot = slot_from_timestamp_and_duration(
 		Duration::from_millis(signed_block.block().block_data().timestamp()),
 		slot_duration,
@@ -352,6 +364,32 @@ mod tests {
 		));
 	}
 
+	#[test]
+	fn verify_errs_on_unsupported_header_version() {
+		let signer = Keyring::Alice;
+		let signer_account: AccountId = signer.public().into();
+		let authorities = [AuthorityId::<Pair>::from_slice(signer_account.as_ref()).unwrap()];
+
+		let parentchain_header = ParentchainHeaderBuilder::default().build();
+		let header = SidechainHeaderBuilder::default()
+			.with_version(SIDECHAIN_HEADER_VERSION + 1)
+			.with_block_number(1)
+			.build();
+		let curr_block = block(signer, header);
+
+		assert_matches!(
+			verify_sidechain_block::<Pair, ParentchainBlock, _>(
+				curr_block,
+				SLOT_DURATION,
+				&None,
+				&parentchain_header,
+				&authorities,
+			)
+			.unwrap_err(),
+			ConsensusError::BadSidechainBlock(_, _)
+		);
+	}
+
 	#[test]
 	fn verify_errs_on_wrong_authority() {
 		let signer = Keyring::Alice;
@@ -20,21 +20,33 @@
 // Reexport BlockImport trait which implements fn block_import()
 pub use its_consensus_common::BlockImport;
 
+#[cfg(feature = "sgx")]
+use std::sync::SgxRwLock as RwLock;
+
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+
 use crate::{AuraVerifier, EnclaveOnChainOCallApi, SidechainBlockTrait};
-use ita_stf::hash::TrustedOperationOrHash;
+use ita_stf::{hash::TrustedOperationOrHash, TrustedOperation};
 use itc_parentchain_block_import_dispatcher::triggered_dispatcher::TriggerParentchainBlockImport;
 use itp_enclave_metrics::EnclaveMetric;
 use itp_ocall_api::{EnclaveMetricsOCallApi, EnclaveSidechainOCallApi};
-use itp_settings::sidechain::SLOT_DURATION;
+use itp_settings::sidechain::{MAX_FORK_DEPTH, SLOT_DURATION};
 use itp_sgx_crypto::{key_repository::AccessKey, StateCrypto};
 use itp_sgx_externalities::SgxExternalities;
 use itp_stf_state_handler::handle_state::HandleState;
 use itp_top_pool_author::traits::{AuthorApi, OnBlockImported};
 use itp_types::H256;
-use its_consensus_common::Error as ConsensusError;
-use its_primitives::traits::{
-	BlockData, Header as HeaderTrait, ShardIdentifierFor, SignedBlock as SignedBlockTrait,
+use its_consensus_common::{
+	Error as ConsensusError, SidechainBlockStates, SidechainFinalityTracker, SidechainForks,
+};
+use its_primitives::{
+	traits::{
+		BlockData, Header as HeaderTrait, ShardIdentifierFor, SignedBlock as SignedBlockTrait,
+	},
+	types::{BlockHash, BlockNumber},
 };
+use its_state::LastBlockExt;
 use its_validateer_fetch::ValidateerFetch;
 use log::*;
 use sp_core::{crypto::UncheckedFrom, Pair};
@@ -42,7 +54,7 @@ use sp_runtime::{
 	generic::SignedBlock as SignedParentchainBlock,
 	traits::{Block as ParentchainBlockTrait, Header},
 };
-use std::{marker::PhantomData, sync::Arc};
+use std::{collections::HashMap, format, marker::PhantomData, sync::Arc, vec::Vec};
 
 /// Implements `BlockImport`.
 #[derive(Clone)]
@@ -61,6 +73,13 @@ pub struct BlockImporter<
 	top_pool_author: Arc<TopPoolAuthor>,
 	parentchain_block_importer: Arc<ParentchainBlockImporter>,
 	ocall_api: Arc<OCallApi>,
+	forks: Arc<SidechainForks<SignedSidechainBlock>>,
+	/// States after recent blocks, shared with the block proposer.
+	block_states: Arc<SidechainBlockStates<SgxExternalities>>,
+	finality_tracker: Arc<SidechainFinalityTracker<OCallApi>>,
+	/// Trusted operations of the blocks in the fork trees, as far as they were in our TOP pool,
+	/// by shard and block hash. Resubmitted if their block is retracted.
+	recent_operations: Arc<RwLock<HashMap<(H256, BlockHash), Vec<TrustedOperation>>>>,
 	_phantom: PhantomData<(Authority, ParentchainBlock, SignedSidechainBlock)>,
 }
 
@@ -96,7 +115,7 @@ impl<
 		+ EnclaveMetricsOCallApi
 		+ Send
 		+ Sync,
-	StateHandler: HandleState<StateT = SgxExternalities>,
+	StateHandler: HandleState<StateT = SgxExternalities, HashType = H256>,
 	StateKeyRepository: AccessKey,
 	<StateKeyRepository as AccessKey>::KeyType: StateCrypto,
 	TopPoolAuthor: AuthorApi<H256, H256> + OnBlockImported<Hash = H256>,
@@ -110,6 +129,8 @@ impl<
 		top_pool_author: Arc<TopPoolAuthor>,
 		parentchain_block_importer: Arc<ParentchainBlockImporter>,
 		ocall_api: Arc<OCallApi>,
+		block_states: Arc<SidechainBlockStates<SgxExternalities>>,
+		finality_tracker: Arc<SidechainFinalityTracker<OCallApi>>,
 	) -> Self {
 		Self {
 			state_handler,
@@ -117,11 +138,19 @@ impl<
 			top_pool_author,
 			parentchain_block_importer,
 			ocall_api,
+			forks: Arc::new(SidechainForks::new(MAX_FORK_DEPTH)),
+			block_states,
+			finality_tracker,
+			recent_operations: Default::default(),
 			_phantom: Default::default(),
 		}
 	}
 
 	fn update_top_pool(&self, sidechain_block: &SignedSidechainBlock::Block) {
+		// Keep the calls, so we can resubmit them if the block is retracted in a re-org.
+		// Calls that were not submitted to our own TOP pool are not known to us.
+		self.keep_operations(sidechain_block);
+
 		// Notify pool about imported block for status updates of the calls.
 		self.top_pool_author.on_block_imported(
 			sidechain_block.block_data().signed_top_hashes(),
@@ -147,6 +176,27 @@ impl<
 		// 	error!("Could not remove call {:?} from top pool", call_failed_to_remove);
 		// }
 	}
+
+	fn keep_operations(&self, sidechain_block: &SignedSidechainBlock::Block) {
+		let shard = sidechain_block.header().shard_id();
+		let top_hashes = sidechain_block.block_data().signed_top_hashes();
+		let operations = self
+			.top_pool_author
+			.get_pending_trusted_calls(shard)
+			.into_iter()
+			.filter(|operation| top_hashes.contains(&self.top_pool_author.hash_of(operation)))
+			.collect::<Vec<_>>();
+
+		match self.recent_operations.write() {
+			Ok(mut recent_operations) => {
+				recent_operations.insert((shard, sidechain_block.hash()), operations);
+				recent_operations.retain(|(shard_id, hash), _| {
+					self.forks.contains(shard_id, hash).unwrap_or(false)
+				});
+			},
+			Err(e) => error!("Failed to lock recent operations, cannot keep them: {:?}", e),
+		}
+	}
 }
 
 impl<
@@ -181,7 +231,7 @@ impl<
 		+ EnclaveMetricsOCallApi
 		+ Send
 		+ Sync,
-	StateHandler: HandleState<StateT = SgxExternalities>,
+	StateHandler: HandleState<StateT = SgxExternalities, HashType = H256>,
 	StateKeyRepository: AccessKey,
 	<StateKeyRepository as AccessKey>::KeyType: StateCrypto,
 	TopPoolAuthor: AuthorApi<H256, H256> + OnBlockImported<Hash = H256>,
@@ -333,4 +383,137 @@ impl<
 
 		Ok(())
 	}
+
+	fn forks(&self) -> &SidechainForks<SignedSidechainBlock> {
+		&self.forks
+	}
+
+	fn last_block(
+		&self,
+		shard: &ShardIdentifierFor<SignedSidechainBlock>,
+	) -> Result<Option<SignedSidechainBlock::Block>, ConsensusError> {
+		self.state_handler
+			.execute_on_current(shard, |state, _| state.get_last_block())
+			.map_err(|e| ConsensusError::Other(format!("{:?}", e).into()))
+	}
+
+	fn block_state(
+		&self,
+		shard: &ShardIdentifierFor<SignedSidechainBlock>,
+		block_hash: &BlockHash,
+	) -> Result<Self::SidechainState, ConsensusError> {
+		self.block_states.get(shard, block_hash)?.ok_or_else(|| {
+			ConsensusError::ChainLookup(format!(
+				"State after block {:?} is not available",
+				block_hash
+			))
+		})
+	}
+
+	fn keep_block_state(
+		&self,
+		shard: &ShardIdentifierFor<SignedSidechainBlock>,
+		block: &SignedSidechainBlock::Block,
+		state: Self::SidechainState,
+	) -> Result<(), ConsensusError> {
+		let header = block.header();
+		self.block_states.keep(*shard, block.hash(), header.block_number(), state)?;
+		self.finality_tracker.note_applied_block(
+			*shard,
+			header.block_number(),
+			block.hash(),
+			header.parent_hash(),
+		)
+	}
+
+	fn finalized_block_number(
+		&self,
+		shard: &ShardIdentifierFor<SignedSidechainBlock>,
+	) -> Result<Option<BlockNumber>, ConsensusError> {
+		Ok(self
+			.finality_tracker
+			.finalized_block(shard)?
+			.map(|confirmation| confirmation.block_number))
+	}
+
+	fn current_state(
+		&self,
+		shard: &ShardIdentifierFor<SignedSidechainBlock>,
+	) -> Result<Self::SidechainState, ConsensusError> {
+		self.state_handler
+			.load_cloned(shard)
+			.map(|(state, _)| state)
+			.map_err(|e| ConsensusError::Other(format!("{:?}", e).into()))
+	}
+
+	fn reset_state(
+		&self,
+		shard: &ShardIdentifierFor<SignedSidechainBlock>,
+		state: Self::SidechainState,
+	) -> Result<(), ConsensusError> {
+		self.state_handler
+			.reset(state, shard)
+			.map_err(|e| ConsensusError::Other(format!("{:?}", e).into()))?;
+		Ok(())
+	}
+
+	fn reapply_parentchain_blocks(
+		&self,
+		applied_parentchain_head: &H256,
+		target_parentchain_head: Option<&H256>,
+		last_imported_parentchain_header: &ParentchainBlock::Header,
+	) -> Result<ParentchainBlock::Header, ConsensusError> {
+		let target = target_parentchain_head
+			.copied()
+			.unwrap_or_else(|| last_imported_parentchain_header.hash());
+		if target == *applied_parentchain_head {
+			return Ok(last_imported_parentchain_header.clone())
+		}
+
+		let maybe_latest_imported_block = self
+			.parentchain_block_importer
+			.reapply_until(
+				|signed_parentchain_block| {
+					signed_parentchain_block.block.hash() == *applied_parentchain_head
+				},
+				|signed_parentchain_block| signed_parentchain_block.block.hash() == target,
+			)
+			.map_err(|e| ConsensusError::Other(format!("{:?}", e).into()))?;
+
+		Ok(maybe_latest_imported_block
+			.map(|b| b.block.header().clone())
+			.unwrap_or_else(|| last_imported_parentchain_header.clone()))
+	}
+
+	fn resubmit_operations(
+		&self,
+		shard: &ShardIdentifierFor<SignedSidechainBlock>,
+		retracted: &[BlockHash],
+		enacted: &[SignedSidechainBlock],
+	) {
+		let enacted_operation_hashes = enacted
+			.iter()
+			.flat_map(|b| b.block().block_data().signed_top_hashes().to_vec())
+			.collect::<Vec<_>>();
+
+		let operations = match self.recent_operations.read() {
+			Ok(recent_operations) => retracted
+				.iter()
+				.filter_map(|hash| recent_operations.get(&(*shard, *hash)))
+				.flat_map(|operations| operations.iter().cloned())
+				.filter(|operation| {
+					!enacted_operation_hashes.contains(&self.top_pool_author.hash_of(operation))
+				})
+				.collect::<Vec<_>>(),
+			Err(e) => {
+				error!("Failed to lock recent operations, cannot resubmit them: {:?}", e);
+				return
+			},
+		};
+
+		if !operations.is_empty() {
+			info!("Resubmitting {} trusted operations of retracted blocks", operations.len());
+			self.top_pool_author.resubmit_operations(*shard, operations);
+		}
+	}
 }
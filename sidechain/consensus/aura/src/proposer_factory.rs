@@ -23,7 +23,9 @@ use itp_stf_executor::traits::StateUpdateProposer;
 use itp_top_pool_author::traits::AuthorApi;
 use itp_types::H256;
 use its_block_composer::ComposeBlock;
-use its_consensus_common::{Environment, Error as ConsensusError};
+use its_consensus_common::{
+	AppliedSidechainBlocks, Environment, Error as ConsensusError, SidechainBlockStates,
+};
 use its_primitives::traits::{
 	Block as SidechainBlockTrait, Header as HeaderTrait, ShardIdentifierFor,
 	SignedBlock as SignedSidechainBlockTrait,
@@ -37,25 +39,36 @@ use std::{marker::PhantomData, sync::Arc};
 
 ///! `ProposerFactory` instance containing all the data to create the `SlotProposer` for the
 /// next `Slot`.
-pub struct ProposerFactory<ParentchainBlock: Block, TopPoolAuthor, StfExecutor, BlockComposer> {
+pub struct ProposerFactory<ParentchainBlock: Block, TopPoolAuthor, StfExecutor, BlockComposer>
+where
+	StfExecutor: StateUpdateProposer,
+{
 	top_pool_author: Arc<TopPoolAuthor>,
 	stf_executor: Arc<StfExecutor>,
 	block_composer: Arc<BlockComposer>,
+	block_states: Arc<SidechainBlockStates<ExternalitiesFor<StfExecutor>>>,
+	applied_blocks: Arc<AppliedSidechainBlocks>,
 	_phantom: PhantomData<ParentchainBlock>,
 }
 
 impl<ParentchainBlock: Block, TopPoolAuthor, StfExecutor, BlockComposer>
 	ProposerFactory<ParentchainBlock, TopPoolAuthor, StfExecutor, BlockComposer>
+where
+	StfExecutor: StateUpdateProposer,
 {
 	pub fn new(
 		top_pool_executor: Arc<TopPoolAuthor>,
 		stf_executor: Arc<StfExecutor>,
 		block_composer: Arc<BlockComposer>,
+		block_states: Arc<SidechainBlockStates<ExternalitiesFor<StfExecutor>>>,
+		applied_blocks: Arc<AppliedSidechainBlocks>,
 	) -> Self {
 		Self {
 			top_pool_author: top_pool_executor,
 			stf_executor,
 			block_composer,
+			block_states,
+			applied_blocks,
 			_phantom: Default::default(),
 		}
 	}
@@ -79,7 +92,7 @@ where
 	TopPoolAuthor: AuthorApi<H256, ParentchainBlock::Hash> + Send + Sync + 'static,
 	StfExecutor: StateUpdateProposer + Send + Sync + 'static,
 	ExternalitiesFor<StfExecutor>:
-		SgxExternalitiesTrait + SidechainState + SidechainSystemExt + StateHash,
+		SgxExternalitiesTrait + SidechainState + SidechainSystemExt + StateHash + Clone,
 	<ExternalitiesFor<StfExecutor> as SgxExternalitiesTrait>::SgxExternalitiesType: Encode,
 	BlockComposer: ComposeBlock<
 			ExternalitiesFor<StfExecutor>,
@@ -107,6 +120,8 @@ where
 			top_pool_author: self.top_pool_author.clone(),
 			stf_executor: self.stf_executor.clone(),
 			block_composer: self.block_composer.clone(),
+			block_states: self.block_states.clone(),
+			applied_blocks: self.applied_blocks.clone(),
 			parentchain_header: parent_header,
 			shard,
 			_phantom: PhantomData,
@@ -23,12 +23,14 @@ use itp_time_utils::now_as_millis;
 use itp_top_pool_author::traits::AuthorApi;
 use itp_types::H256;
 use its_block_composer::ComposeBlock;
-use its_consensus_common::{Error as ConsensusError, Proposal, Proposer};
+use its_consensus_common::{
+	AppliedSidechainBlocks, Error as ConsensusError, Proposal, Proposer, SidechainBlockStates,
+};
 use its_primitives::traits::{
 	Block as SidechainBlockTrait, Header as HeaderTrait, ShardIdentifierFor,
 	SignedBlock as SignedSidechainBlockTrait,
 };
-use its_state::{SidechainState, SidechainSystemExt};
+use its_state::{LastBlockExt, SidechainState, SidechainSystemExt};
 use log::*;
 use sp_runtime::{
 	traits::{Block, NumberFor},
@@ -44,10 +46,14 @@ pub struct SlotProposer<
 	TopPoolAuthor,
 	StfExecutor,
 	BlockComposer,
-> {
+> where
+	StfExecutor: StateUpdateProposer,
+{
 	pub(crate) top_pool_author: Arc<TopPoolAuthor>,
 	pub(crate) stf_executor: Arc<StfExecutor>,
 	pub(crate) block_composer: Arc<BlockComposer>,
+	pub(crate) block_states: Arc<SidechainBlockStates<ExternalitiesFor<StfExecutor>>>,
+	pub(crate) applied_blocks: Arc<AppliedSidechainBlocks>,
 	pub(crate) parentchain_header: ParentchainBlock::Header,
 	pub(crate) shard: ShardIdentifierFor<SignedSidechainBlock>,
 	pub(crate) _phantom: PhantomData<ParentchainBlock>,
@@ -66,7 +72,7 @@ where
 		HeaderTrait<ShardIdentifier = H256>,
 	StfExecutor: StateUpdateProposer,
 	ExternalitiesFor<StfExecutor>:
-		SgxExternalitiesTrait + SidechainState + SidechainSystemExt + StateHash,
+		SgxExternalitiesTrait + SidechainState + SidechainSystemExt + StateHash + Clone,
 	<ExternalitiesFor<StfExecutor> as SgxExternalitiesTrait>::SgxExternalitiesType: Encode,
 	TopPoolAuthor: AuthorApi<H256, ParentchainBlock::Hash> + Send + Sync + 'static,
 	BlockComposer: ComposeBlock<
@@ -145,6 +151,28 @@ where
 			)
 			.map_err(|e| ConsensusError::Other(e.to_string().into()))?;
 
+		// Keep the state after our own block, so we can re-org on top of it like on top of an
+		// imported block.
+		let mut block_state = batch_execution_result.state_after_execution;
+		block_state.set_last_block(sidechain_block.block());
+		if let Err(e) = self.block_states.keep(
+			self.shard,
+			sidechain_block.hash(),
+			sidechain_block.block().header().block_number(),
+			block_state,
+		) {
+			warn!("Failed to keep the state after the proposed block: {:?}", e);
+		}
+		let header = sidechain_block.block().header();
+		if let Err(e) = self.applied_blocks.note_applied_block(
+			self.shard,
+			header.block_number(),
+			sidechain_block.hash(),
+			header.parent_hash(),
+		) {
+			warn!("Failed to note the proposed block as applied: {:?}", e);
+		}
+
 		info!(
 			"Queue/Timeslot/Transactions: {:?};{};{}",
 			trusted_calls.len(),
@@ -20,6 +20,7 @@ use codec::Encode;
 use core::assert_matches::assert_matches;
 use itc_parentchain_block_import_dispatcher::trigger_parentchain_block_import_mock::TriggerParentchainBlockImportMock;
 use itc_parentchain_test::{ParentchainBlockBuilder, ParentchainHeaderBuilder};
+use itp_settings::sidechain::MAX_FORK_DEPTH;
 use itp_sgx_crypto::{aes::Aes, mocks::KeyRepositoryMock, StateCrypto};
 use itp_sgx_externalities::SgxExternalitiesDiffType;
 use itp_stf_state_handler::handle_state::HandleState;
@@ -27,12 +28,15 @@ use itp_test::mock::{handle_state_mock::HandleStateMock, onchain_mock::OnchainMo
 use itp_time_utils::{duration_now, now_as_millis};
 use itp_top_pool_author::mocks::AuthorApiMock;
 use itp_types::{Block as ParentchainBlock, Header as ParentchainHeader, H256};
-use its_consensus_common::{BlockImport, Error as ConsensusError};
+use its_consensus_common::{
+	AppliedSidechainBlocks, BlockImport, Error as ConsensusError, SidechainBlockStates,
+	SidechainFinalityTracker,
+};
 use its_primitives::{
 	traits::{SignBlock, SignedBlock},
 	types::SignedBlock as SignedSidechainBlock,
 };
-use its_state::StateUpdate;
+use its_state::{StateRoot, StateUpdate};
 use its_test::{
 	sidechain_block_builder::{SidechainBlockBuilder, SidechainBlockBuilderTrait},
 	sidechain_block_data_builder::SidechainBlockDataBuilder,
@@ -88,7 +92,9 @@ fn test_fixtures(
 		state_key_repository,
 		top_pool_author.clone(),
 		parentchain_block_import_trigger,
-		ocall_api,
+		ocall_api.clone(),
+		Arc::new(SidechainBlockStates::new(MAX_FORK_DEPTH)),
+		Arc::new(SidechainFinalityTracker::new(ocall_api, Arc::new(AppliedSidechainBlocks::new()))),
 	);
 
 	(block_importer, state_handler, top_pool_author)
@@ -115,10 +121,12 @@ fn signed_block(
 	signer: Pair,
 ) -> SignedSidechainBlock {
 	let state_update = empty_encrypted_state_update(state_handler);
+	let (state, _) = state_handler.load_cloned(&shard()).unwrap();
 
 	let header = SidechainHeaderBuilder::default()
 		.with_parent_hash(H256::default())
 		.with_shard(shard())
+		.with_state_root(state.state_root())
 		.build();
 
 	let block_data = SidechainBlockDataBuilder::default()
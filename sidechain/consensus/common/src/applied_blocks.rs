@@ -0,0 +1,279 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! The chain of sidechain blocks applied to the state of each shard, from its latest finalized
+//! block on.
+//!
+//! Shared by the block importer and the block proposer, so the finality tracker can check
+//! that a newly finalized block descends from the previous one, no matter who produced it.
+
+#[cfg(feature = "sgx")]
+use std::sync::SgxRwLock as RwLock;
+
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+
+use crate::error::{Error, Result};
+use itp_types::SidechainBlockConfirmation;
+use its_primitives::types::{BlockHash, BlockNumber, ShardIdentifier};
+use log::*;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Default)]
+struct AppliedChain {
+	/// Hashes of the applied blocks by number. Every block is the parent of the next one.
+	blocks: BTreeMap<BlockNumber, BlockHash>,
+	finalized: Option<SidechainBlockConfirmation>,
+}
+
+impl AppliedChain {
+	fn contains(&self, number: BlockNumber, hash: &BlockHash) -> bool {
+		self.blocks.get(&number) == Some(hash)
+	}
+}
+
+#[derive(Default)]
+pub struct AppliedSidechainBlocks {
+	chains: RwLock<HashMap<ShardIdentifier, AppliedChain>>,
+}
+
+impl AppliedSidechainBlocks {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Note a block that was applied to the state of `shard`, on top of `parent_hash`.
+	///
+	/// Blocks of the same or a higher number were retracted by a re-org and are forgotten.
+	/// If the block does not build on the previously applied block, the state jumped, e.g.
+	/// with an imported state snapshot. The ancestry to the finalized block is unknown then,
+	/// so the chain starts over and finality is re-established with the next confirmation.
+	pub fn note_applied_block(
+		&self,
+		shard: ShardIdentifier,
+		number: BlockNumber,
+		hash: BlockHash,
+		parent_hash: BlockHash,
+	) -> Result<()> {
+		let mut chains = self.chains.write().map_err(|_| Error::LockPoisoning)?;
+		let chain = chains.entry(shard).or_default();
+
+		chain.blocks.split_off(&number);
+		let builds_on_chain = number
+			.checked_sub(1)
+			.map(|parent_number| chain.contains(parent_number, &parent_hash))
+			.unwrap_or(false);
+		if !builds_on_chain {
+			if chain.finalized.is_some() {
+				warn!(
+					"Block {} of shard {:?} does not build on the applied chain, forgetting its finalized block",
+					number, shard
+				);
+			}
+			*chain = AppliedChain::default();
+		}
+		chain.blocks.insert(number, hash);
+		Ok(())
+	}
+
+	/// The latest finalized block of `shard`.
+	pub fn finalized_block(
+		&self,
+		shard: &ShardIdentifier,
+	) -> Result<Option<SidechainBlockConfirmation>> {
+		Ok(self
+			.chains
+			.read()
+			.map_err(|_| Error::LockPoisoning)?
+			.get(shard)
+			.and_then(|chain| chain.finalized))
+	}
+
+	/// Whether `confirmation` advances finality of `shard`.
+	///
+	/// That is the case, if the confirmed block is newer than the finalized block and has
+	/// been applied already. It must then descend from the finalized block.
+	pub fn advances_finality(
+		&self,
+		shard: &ShardIdentifier,
+		confirmation: &SidechainBlockConfirmation,
+	) -> Result<bool> {
+		let chains = self.chains.read().map_err(|_| Error::LockPoisoning)?;
+		let chain = match chains.get(shard) {
+			Some(chain) => chain,
+			None => return Ok(false),
+		};
+		Self::check_advances_finality(chain, confirmation)
+	}
+
+	/// Finalize the confirmed block and forget the applied blocks before it.
+	///
+	/// Returns false if `confirmation` does not advance finality, see [`Self::advances_finality`].
+	pub fn finalize(
+		&self,
+		shard: &ShardIdentifier,
+		confirmation: SidechainBlockConfirmation,
+	) -> Result<bool> {
+		let mut chains = self.chains.write().map_err(|_| Error::LockPoisoning)?;
+		let chain = match chains.get_mut(shard) {
+			Some(chain) => chain,
+			None => return Ok(false),
+		};
+		if !Self::check_advances_finality(chain, &confirmation)? {
+			return Ok(false)
+		}
+		chain.blocks = chain.blocks.split_off(&confirmation.block_number);
+		chain.finalized = Some(confirmation);
+		Ok(true)
+	}
+
+	fn check_advances_finality(
+		chain: &AppliedChain,
+		confirmation: &SidechainBlockConfirmation,
+	) -> Result<bool> {
+		let number = confirmation.block_number;
+		if let Some(finalized) = chain.finalized {
+			if finalized.block_number >= number {
+				return Ok(false)
+			}
+		}
+
+		let (first_applied, last_applied) =
+			match (chain.blocks.keys().next(), chain.blocks.keys().next_back()) {
+				(Some(first), Some(last)) => (*first, *last),
+				_ => return Ok(false),
+			};
+		// We can only tell the ancestry of blocks we applied. Wait for a later confirmation.
+		if number < first_applied || number > last_applied {
+			return Ok(false)
+		}
+
+		let (ancestor_number, ancestor_hash) = match chain.finalized {
+			Some(finalized) => (finalized.block_number, finalized.block_header_hash),
+			None => (first_applied, chain.blocks[&first_applied]),
+		};
+		// The applied blocks form a chain, so a block on it descends from all blocks before it.
+		if !chain.contains(number, &confirmation.block_header_hash)
+			|| !chain.contains(ancestor_number, &ancestor_hash)
+		{
+			return Err(Error::FinalizedBlockNotDescendant(
+				number,
+				confirmation.block_header_hash,
+				ancestor_number,
+				ancestor_hash,
+			))
+		}
+		Ok(true)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use core::ops::RangeInclusive;
+	use sp_core::H256;
+
+	fn hash(number: BlockNumber, fork: u64) -> BlockHash {
+		H256::from_low_u64_be(number * 100 + fork)
+	}
+
+	fn confirmation(number: BlockNumber, fork: u64) -> SidechainBlockConfirmation {
+		SidechainBlockConfirmation { block_number: number, block_header_hash: hash(number, fork) }
+	}
+
+	/// Apply the blocks `numbers` of `fork`, on top of the block before them of `parent_fork`.
+	fn apply_blocks(
+		applied_blocks: &AppliedSidechainBlocks,
+		shard: ShardIdentifier,
+		parent_fork: u64,
+		numbers: RangeInclusive<BlockNumber>,
+		fork: u64,
+	) {
+		let first = *numbers.start();
+		let mut parent_hash =
+			if first == 1 { H256::default() } else { hash(first - 1, parent_fork) };
+		for number in numbers {
+			applied_blocks
+				.note_applied_block(shard, number, hash(number, fork), parent_hash)
+				.unwrap();
+			parent_hash = hash(number, fork);
+		}
+	}
+
+	#[test]
+	fn finality_advances_along_the_applied_chain() {
+		let applied_blocks = AppliedSidechainBlocks::new();
+		let shard = ShardIdentifier::repeat_byte(1);
+		apply_blocks(&applied_blocks, shard, 0, 1..=5, 0);
+
+		assert!(applied_blocks.finalize(&shard, confirmation(2, 0)).unwrap());
+		assert!(applied_blocks.finalize(&shard, confirmation(4, 0)).unwrap());
+		assert!(!applied_blocks.finalize(&shard, confirmation(3, 0)).unwrap());
+		assert_eq!(applied_blocks.finalized_block(&shard).unwrap(), Some(confirmation(4, 0)));
+	}
+
+	#[test]
+	fn blocks_that_have_not_been_applied_yet_are_not_finalized() {
+		let applied_blocks = AppliedSidechainBlocks::new();
+		let shard = ShardIdentifier::repeat_byte(1);
+		apply_blocks(&applied_blocks, shard, 0, 1..=3, 0);
+
+		assert!(!applied_blocks.finalize(&shard, confirmation(4, 0)).unwrap());
+		assert!(applied_blocks.finalized_block(&shard).unwrap().is_none());
+	}
+
+	#[test]
+	fn block_of_another_fork_is_not_finalized() {
+		let applied_blocks = AppliedSidechainBlocks::new();
+		let shard = ShardIdentifier::repeat_byte(1);
+		apply_blocks(&applied_blocks, shard, 0, 1..=3, 0);
+		assert!(applied_blocks.finalize(&shard, confirmation(2, 0)).unwrap());
+
+		assert!(matches!(
+			applied_blocks.finalize(&shard, confirmation(3, 1)),
+			Err(Error::FinalizedBlockNotDescendant(3, _, 2, _))
+		));
+		assert_eq!(applied_blocks.finalized_block(&shard).unwrap(), Some(confirmation(2, 0)));
+	}
+
+	#[test]
+	fn retracted_blocks_are_not_finalized() {
+		let applied_blocks = AppliedSidechainBlocks::new();
+		let shard = ShardIdentifier::repeat_byte(1);
+		apply_blocks(&applied_blocks, shard, 0, 1..=4, 0);
+		assert!(applied_blocks.finalize(&shard, confirmation(2, 0)).unwrap());
+
+		// Re-org on top of block 2, replacing blocks 3 and 4.
+		apply_blocks(&applied_blocks, shard, 0, 3..=4, 1);
+
+		assert!(applied_blocks.finalize(&shard, confirmation(4, 0)).is_err());
+		assert!(applied_blocks.finalize(&shard, confirmation(4, 1)).unwrap());
+	}
+
+	#[test]
+	fn jump_of_the_state_forgets_the_finalized_block() {
+		let applied_blocks = AppliedSidechainBlocks::new();
+		let shard = ShardIdentifier::repeat_byte(1);
+		apply_blocks(&applied_blocks, shard, 0, 1..=3, 0);
+		assert!(applied_blocks.finalize(&shard, confirmation(2, 0)).unwrap());
+
+		applied_blocks.note_applied_block(shard, 10, hash(10, 0), hash(9, 0)).unwrap();
+
+		assert!(applied_blocks.finalized_block(&shard).unwrap().is_none());
+		assert!(applied_blocks.finalize(&shard, confirmation(10, 0)).unwrap());
+	}
+}
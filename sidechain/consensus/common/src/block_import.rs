@@ -17,18 +17,22 @@
 
 //! Abstraction around block import
 
-use crate::{Error, Verifier};
+use crate::{Error, ForkBlock, ReorgRoute, SidechainForks, Verifier};
 use codec::Decode;
 use itp_ocall_api::EnclaveSidechainOCallApi;
 use itp_sgx_crypto::StateCrypto;
-use its_primitives::traits::{
-	Block as SidechainBlockTrait, BlockData, Header as HeaderTrait, ShardIdentifierFor,
-	SignedBlock as SignedSidechainBlockTrait,
+use itp_types::H256;
+use its_primitives::{
+	traits::{
+		Block as SidechainBlockTrait, BlockData, Header as HeaderTrait, ShardIdentifierFor,
+		SignedBlock as SignedSidechainBlockTrait,
+	},
+	types::{BlockHash, BlockNumber},
 };
-use its_state::{LastBlockExt, SidechainState};
+use its_state::{LastBlockExt, SidechainState, StateRoot};
 use log::*;
 use sp_runtime::traits::Block as ParentchainBlockTrait;
-use std::{time::Instant, vec::Vec};
+use std::{format, time::Instant, vec::Vec};
 
 pub trait BlockImport<ParentchainBlock, SignedSidechainBlock>
 where
@@ -44,7 +48,10 @@ where
 	>;
 
 	/// Context needed to derive verifier relevant data.
-	type SidechainState: SidechainState + LastBlockExt<SignedSidechainBlock::Block>;
+	type SidechainState: SidechainState
+		+ LastBlockExt<SignedSidechainBlock::Block>
+		+ StateRoot
+		+ Clone;
 
 	/// Provides the cryptographic functions for our the state encryption.
 	type StateCrypto: StateCrypto;
@@ -105,7 +112,79 @@ where
 	/// Cleanup task after import is done.
 	fn cleanup(&self, signed_sidechain_block: &SignedSidechainBlock) -> Result<(), Error>;
 
+	/// Competing forks of all shards, used for fork choice.
+	fn forks(&self) -> &SidechainForks<SignedSidechainBlock>;
+
+	/// Last block that was applied to the state of a shard.
+	fn last_block(
+		&self,
+		shard: &ShardIdentifierFor<SignedSidechainBlock>,
+	) -> Result<Option<SignedSidechainBlock::Block>, Error>;
+
+	/// State of a shard right after a block was applied.
+	///
+	/// Kept for recent blocks we imported or produced, so we can re-org on top of any of them,
+	/// no matter how often the state has been written since.
+	fn block_state(
+		&self,
+		shard: &ShardIdentifierFor<SignedSidechainBlock>,
+		block_hash: &BlockHash,
+	) -> Result<Self::SidechainState, Error>;
+
+	/// Keep the state of a shard right after `block` was applied, see [`Self::block_state`],
+	/// and note the block as applied for finality tracking.
+	fn keep_block_state(
+		&self,
+		shard: &ShardIdentifierFor<SignedSidechainBlock>,
+		block: &SignedSidechainBlock::Block,
+		state: Self::SidechainState,
+	) -> Result<(), Error>;
+
+	/// Number of the latest finalized block of a shard, if any. We never re-org below it.
+	fn finalized_block_number(
+		&self,
+		shard: &ShardIdentifierFor<SignedSidechainBlock>,
+	) -> Result<Option<BlockNumber>, Error>;
+
+	/// Current state of a shard.
+	fn current_state(
+		&self,
+		shard: &ShardIdentifierFor<SignedSidechainBlock>,
+	) -> Result<Self::SidechainState, Error>;
+
+	/// Replace the current state of a shard.
+	fn reset_state(
+		&self,
+		shard: &ShardIdentifierFor<SignedSidechainBlock>,
+		state: Self::SidechainState,
+	) -> Result<(), Error>;
+
+	/// Re-apply the parentchain blocks after `applied_parentchain_head`, up to and including
+	/// `target_parentchain_head`, or `last_imported_parentchain_header` if there is no target.
+	/// Their effects on the state are lost when it is reset to an earlier block. Parentchain
+	/// blocks that are not imported yet are imported.
+	///
+	/// Returns the latest imported header, like [`Self::import_parentchain_block`].
+	fn reapply_parentchain_blocks(
+		&self,
+		applied_parentchain_head: &H256,
+		target_parentchain_head: Option<&H256>,
+		last_imported_parentchain_header: &ParentchainBlock::Header,
+	) -> Result<ParentchainBlock::Header, Error>;
+
+	/// Submit the trusted operations of retracted blocks to the pool again, unless they are
+	/// part of an enacted block as well.
+	fn resubmit_operations(
+		&self,
+		shard: &ShardIdentifierFor<SignedSidechainBlock>,
+		retracted: &[BlockHash],
+		enacted: &[SignedSidechainBlock],
+	);
+
 	/// Import a sidechain block and mutate state by `apply_state_update`.
+	///
+	/// A block that builds on a known fork instead of our best block is added to the fork tree,
+	/// and we re-org to it if it wins the fork choice.
 	fn import_block(
 		&self,
 		signed_sidechain_block: SignedSidechainBlock,
@@ -124,6 +203,20 @@ where
 			signed_sidechain_block.block().block_data().layer_one_head()
 		);
 
+		let last_block = self.last_block(&shard)?;
+		self.forks().sync_best_block(&shard, last_block.as_ref())?;
+
+		if self.forks().contains(&shard, &signed_sidechain_block.hash())? {
+			return Err(Error::BlockAlreadyImported(
+				block_number,
+				last_block.map(|b| b.header().block_number()).unwrap_or_default(),
+			))
+		}
+
+		if self.forks().is_fork_parent(&shard, &sidechain_block.header().parent_hash())? {
+			return self.import_fork_block(&shard, signed_sidechain_block, parentchain_header)
+		}
+
 		let peeked_parentchain_header =
 			self.peek_parentchain_header(&sidechain_block, parentchain_header)
 				.unwrap_or_else(|e| {
@@ -144,12 +237,34 @@ where
 		let latest_parentchain_header =
 			self.import_parentchain_block(&sidechain_block, parentchain_header)?;
 
+		self.apply_block_state(&shard, &block_import_params)?;
+
+		self.forks().import_best_block(&signed_sidechain_block)?;
+
+		self.cleanup(&signed_sidechain_block)?;
+
+		// Store block in storage.
+		self.get_context().store_sidechain_blocks(vec![signed_sidechain_block])?;
+
+		info!("Importing block {} took {} ms", block_number, start_time.elapsed().as_millis());
+
+		Ok(latest_parentchain_header)
+	}
+
+	/// Decrypt and apply the state diff of a verified block, and check the resulting state root.
+	fn apply_block_state(
+		&self,
+		shard: &ShardIdentifierFor<SignedSidechainBlock>,
+		signed_sidechain_block: &SignedSidechainBlock,
+	) -> Result<(), Error> {
+		let block_number = signed_sidechain_block.block().header().block_number();
 		let state_key = self.state_key()?;
 
 		let state_update_start_time = Instant::now();
-		self.apply_state_update(&shard, |mut state| {
+		let mut block_state = None;
+		self.apply_state_update(shard, |mut state| {
 			let encrypted_state_diff =
-				block_import_params.block().block_data().encrypted_state_diff();
+				signed_sidechain_block.block().block_data().encrypted_state_diff();
 
 			info!(
 				"Applying state diff for block {} of size {} bytes",
@@ -161,25 +276,189 @@ where
 
 			state.apply_state_update(&update).map_err(|e| Error::Other(e.into()))?;
 
-			state.set_last_block(block_import_params.block());
-
+			let header_state_root = signed_sidechain_block.block().header().state_root();
+			let state_root = state.state_root();
+			if state_root != header_state_root {
+				return Err(Error::BadSidechainBlock(
+					signed_sidechain_block.hash(),
+					format!(
+						"State root in header ({:?}) does not match the resulting state ({:?})",
+						header_state_root, state_root
+					),
+				))
+			}
+
+			state.set_last_block(signed_sidechain_block.block());
+
+			block_state = Some(state.clone());
 			Ok(state)
 		})?;
+		if let Some(state) = block_state {
+			self.keep_block_state(shard, signed_sidechain_block.block(), state)?;
+		}
 		info!(
 			"Applying state update from block {} took {} ms",
 			block_number,
 			state_update_start_time.elapsed().as_millis()
 		);
+		Ok(())
+	}
 
-		self.cleanup(&signed_sidechain_block)?;
+	/// Import a block that builds on a fork, and re-org to it if it becomes the best block.
+	fn import_fork_block(
+		&self,
+		shard: &ShardIdentifierFor<SignedSidechainBlock>,
+		signed_sidechain_block: SignedSidechainBlock,
+		parentchain_header: &ParentchainBlock::Header,
+	) -> Result<ParentchainBlock::Header, Error> {
+		let sidechain_block = signed_sidechain_block.block().clone();
+		let block_hash = signed_sidechain_block.hash();
+		let block_number = sidechain_block.header().block_number();
+		let parent_hash = sidechain_block.header().parent_hash();
+
+		let parent_block = self.forks().execute_on_fork_tree(shard, |tree| {
+			tree.block(&parent_hash).map(|b| b.block.clone()).ok_or_else(|| {
+				Error::ChainLookup(format!("Parent {:?} is not part of the fork tree", parent_hash))
+			})
+		})?;
 
-		// Store block in storage.
-		self.get_context().store_sidechain_blocks(vec![signed_sidechain_block])?;
+		let peeked_parentchain_header =
+			self.peek_parentchain_header(&sidechain_block, parentchain_header)
+				.unwrap_or_else(|e| {
+					warn!("Could not peek parentchain block, returning latest parentchain block ({:?})", e);
+					parentchain_header.clone()
+				});
 
-		info!("Importing block {} took {} ms", block_number, start_time.elapsed().as_millis());
+		let verified_block = self.verifier(Some(parent_block)).verify(
+			signed_sidechain_block,
+			&peeked_parentchain_header,
+			*shard,
+			self.get_context(),
+		)?;
+
+		let maybe_route = self.forks().execute_on_fork_tree(shard, |tree| {
+			tree.import(ForkBlock {
+				block: verified_block.block().clone(),
+				signed_block: Some(verified_block.clone()),
+			})?;
+			if tree.is_better_than_best(block_number, &block_hash) {
+				tree.route_to(&block_hash).map(Some)
+			} else {
+				Ok(None)
+			}
+		})?;
+
+		match maybe_route {
+			Some(route) => self.reorg(shard, route, parentchain_header),
+			None => {
+				info!(
+					"Added sidechain block {} ({:?}) to a fork, keeping the current best block",
+					block_number, block_hash
+				);
+				Ok(parentchain_header.clone())
+			},
+		}
+	}
+
+	/// Switch the best chain of a shard along a re-org route.
+	///
+	/// The state is reset to the one of the common ancestor, and the enacted blocks are applied
+	/// on top of it, together with the parentchain blocks they refer to. Parentchain blocks that
+	/// were imported before the re-org are re-applied as well. If any of this fails, the previous
+	/// state is restored, so the best chain stays as it was. Finalized blocks are never retracted.
+	fn reorg(
+		&self,
+		shard: &ShardIdentifierFor<SignedSidechainBlock>,
+		route: ReorgRoute<SignedSidechainBlock>,
+		parentchain_header: &ParentchainBlock::Header,
+	) -> Result<ParentchainBlock::Header, Error> {
+		let last_enacted_hash = route
+			.enacted
+			.last()
+			.map(|b| b.hash())
+			.ok_or_else(|| Error::ChainLookup("Re-org route without blocks to enact".into()))?;
+
+		let ancestor_state = self.block_state(shard, &route.common_ancestor)?;
+		let ancestor = ancestor_state.get_last_block().ok_or_else(|| {
+			Error::ChainLookup(format!(
+				"State of common ancestor {:?} has no last block",
+				route.common_ancestor
+			))
+		})?;
+		let ancestor_number = ancestor.header().block_number();
+		if let Some(finalized_number) = self.finalized_block_number(shard)? {
+			if ancestor_number < finalized_number {
+				return Err(Error::ReorgBelowFinalizedBlock(ancestor_number, finalized_number))
+			}
+		}
+		let previous_state = self.current_state(shard)?;
+
+		info!(
+			"Re-org of shard {:?}: retracting {} and enacting {} block(s) on top of {:?}",
+			shard,
+			route.retracted.len(),
+			route.enacted.len(),
+			route.common_ancestor
+		);
+
+		self.reset_state(shard, ancestor_state)?;
+		let latest_parentchain_header =
+			match self.enact_blocks(shard, ancestor, &route.enacted, parentchain_header) {
+				Ok(header) => header,
+				Err(e) => {
+					error!("Re-org of shard {:?} failed, keeping the best chain: {:?}", shard, e);
+					self.reset_state(shard, previous_state)?;
+					return Err(e)
+				},
+			};
+
+		self.forks()
+			.execute_on_fork_tree(shard, |tree| tree.set_best_block(&last_enacted_hash))?;
+
+		for signed_sidechain_block in route.enacted.iter() {
+			self.cleanup(signed_sidechain_block)?;
+		}
+		self.resubmit_operations(shard, &route.retracted, &route.enacted);
+		self.get_context().store_sidechain_blocks(route.enacted)?;
 
 		Ok(latest_parentchain_header)
 	}
+
+	/// Apply blocks on top of `ancestor`, whose state is the current one, and re-apply the
+	/// parentchain blocks up to `parentchain_header`, the latest imported one.
+	fn enact_blocks(
+		&self,
+		shard: &ShardIdentifierFor<SignedSidechainBlock>,
+		ancestor: SignedSidechainBlock::Block,
+		enacted: &[SignedSidechainBlock],
+		parentchain_header: &ParentchainBlock::Header,
+	) -> Result<ParentchainBlock::Header, Error> {
+		let mut applied_block = ancestor;
+		let mut latest_parentchain_header = parentchain_header.clone();
+		for signed_sidechain_block in enacted {
+			let sidechain_block = signed_sidechain_block.block();
+			let applied_head = applied_block.block_data().layer_one_head();
+			let head = sidechain_block.block_data().layer_one_head();
+			if head != applied_head {
+				latest_parentchain_header = self.reapply_parentchain_blocks(
+					&applied_head,
+					Some(&head),
+					&latest_parentchain_header,
+				)?;
+			}
+
+			self.apply_block_state(shard, signed_sidechain_block)?;
+			applied_block = sidechain_block.clone();
+		}
+
+		// The replaced state contained the effects of all imported parentchain blocks, including
+		// those after the ones the enacted blocks refer to.
+		self.reapply_parentchain_blocks(
+			&applied_block.block_data().layer_one_head(),
+			None,
+			&latest_parentchain_header,
+		)
+	}
 }
 
 fn state_update_from_encrypted<Key: StateCrypto, StateUpdate: Decode>(
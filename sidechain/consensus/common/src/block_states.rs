@@ -0,0 +1,113 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! States of a shard right after recent sidechain blocks were applied.
+//!
+//! Shared by the block importer and the block proposer, so we can re-org on top of any recent
+//! block, no matter whether we imported or produced it.
+
+#[cfg(feature = "sgx")]
+use std::sync::SgxRwLock as RwLock;
+
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+
+use crate::error::{Error, Result};
+use its_primitives::types::{BlockHash, BlockNumber, ShardIdentifier};
+use std::collections::HashMap;
+
+/// States after recent blocks, by shard and block hash.
+///
+/// States of blocks more than `max_depth` behind the newest kept block of a shard are dropped,
+/// like the blocks of the fork tree.
+pub struct SidechainBlockStates<State> {
+	states: RwLock<HashMap<(ShardIdentifier, BlockHash), (BlockNumber, State)>>,
+	max_depth: BlockNumber,
+}
+
+impl<State: Clone> SidechainBlockStates<State> {
+	pub fn new(max_depth: BlockNumber) -> Self {
+		SidechainBlockStates { states: Default::default(), max_depth }
+	}
+
+	/// Keep the state right after block `hash` with `number` was applied.
+	pub fn keep(
+		&self,
+		shard: ShardIdentifier,
+		hash: BlockHash,
+		number: BlockNumber,
+		state: State,
+	) -> Result<()> {
+		let mut states = self.states.write().map_err(|_| Error::LockPoisoning)?;
+		states.insert((shard, hash), (number, state));
+
+		let newest = states
+			.iter()
+			.filter(|((shard_id, _), _)| *shard_id == shard)
+			.map(|(_, (number, _))| *number)
+			.max()
+			.unwrap_or(number);
+		let threshold = newest.saturating_sub(self.max_depth);
+		states.retain(|(shard_id, _), (number, _)| *shard_id != shard || *number >= threshold);
+		Ok(())
+	}
+
+	/// State right after block `hash` was applied, if it is still kept.
+	pub fn get(&self, shard: &ShardIdentifier, hash: &BlockHash) -> Result<Option<State>> {
+		Ok(self
+			.states
+			.read()
+			.map_err(|_| Error::LockPoisoning)?
+			.get(&(*shard, *hash))
+			.map(|(_, state)| state.clone()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::H256;
+
+	#[test]
+	fn keeps_states_by_shard_and_hash() {
+		let states = SidechainBlockStates::new(4);
+		let shard = H256::from_low_u64_be(1);
+		let other_shard = H256::from_low_u64_be(2);
+		let hash = H256::from_low_u64_be(10);
+
+		states.keep(shard, hash, 1, 42u32).unwrap();
+
+		assert_eq!(states.get(&shard, &hash).unwrap(), Some(42));
+		assert_eq!(states.get(&other_shard, &hash).unwrap(), None);
+	}
+
+	#[test]
+	fn drops_states_too_far_behind_the_newest_block() {
+		let states = SidechainBlockStates::new(2);
+		let shard = H256::from_low_u64_be(1);
+		let other_shard = H256::from_low_u64_be(2);
+
+		for number in 1..=5u64 {
+			states.keep(shard, H256::from_low_u64_be(number), number, number).unwrap();
+		}
+		states.keep(other_shard, H256::from_low_u64_be(1), 1, 1).unwrap();
+
+		assert_eq!(states.get(&shard, &H256::from_low_u64_be(2)).unwrap(), None);
+		assert_eq!(states.get(&shard, &H256::from_low_u64_be(3)).unwrap(), Some(3));
+		assert_eq!(states.get(&other_shard, &H256::from_low_u64_be(1)).unwrap(), Some(1));
+	}
+}
@@ -60,6 +60,10 @@ pub enum Error {
 	InvalidFirstBlock(BlockNumber, String),
 	#[error("Could not import block (number: {0}). A block with this number is already imported (current state block number: {1})")]
 	BlockAlreadyImported(BlockNumber, BlockNumber),
+	#[error("Cannot re-org on top of block {0}, it is below the finalized block {1}")]
+	ReorgBelowFinalizedBlock(BlockNumber, BlockNumber),
+	#[error("Block {0} ({1:?}) does not descend from the finalized block {2} ({3:?})")]
+	FinalizedBlockNotDescendant(BlockNumber, SidechainBlockHash, BlockNumber, SidechainBlockHash),
 	#[error("Failed to pop from block import queue: {0}")]
 	FailedToPopBlockImportQueue(#[from] itp_import_queue::error::Error),
 	#[error("Verification Error: {0}")]
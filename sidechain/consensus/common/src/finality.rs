@@ -0,0 +1,233 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Sidechain finality, anchored to the parentchain.
+//!
+//! A sidechain block is final once its confirmation (or the confirmation of one of its
+//! descendants) is contained in a parentchain block that the light client has finalized.
+//! Finality only advances along the chain of blocks applied to our state, see
+//! [`AppliedSidechainBlocks`].
+
+use crate::{
+	applied_blocks::AppliedSidechainBlocks,
+	error::{Error, Result},
+};
+use itp_ocall_api::{EnclaveOnChainOCallApi, EnclaveSidechainOCallApi};
+use itp_storage::{storage_map_key, StorageHasher};
+use itp_types::{parentchain::ParentchainId, ShardIdentifier, SidechainBlockConfirmation, H256};
+use its_primitives::types::{BlockHash, BlockNumber};
+use log::*;
+use sp_runtime::traits::Header as ParentchainHeaderTrait;
+use std::{format, sync::Arc, vec::Vec};
+
+/// Storage key of the latest sidechain block confirmation of a shard in the sidechain pallet.
+pub fn latest_sidechain_block_confirmation_key(shard: &ShardIdentifier) -> Vec<u8> {
+	storage_map_key(
+		"Sidechain",
+		"LatestSidechainBlockConfirmation",
+		shard,
+		&StorageHasher::Blake2_128Concat,
+	)
+}
+
+/// Trait to track the finality of sidechain blocks.
+pub trait TrackSidechainFinality<ParentchainHeader> {
+	/// Update the finalized sidechain block of `shard`, based on a finalized parentchain header.
+	///
+	/// Returns the newly finalized sidechain block, if finality advanced.
+	fn update_finality(
+		&self,
+		shard: &ShardIdentifier,
+		finalized_parentchain_header: &ParentchainHeader,
+	) -> Result<Option<SidechainBlockConfirmation>>;
+}
+
+/// Reads the sidechain block confirmations from the finalized parentchain state and reports
+/// newly finalized sidechain blocks to the untrusted worker.
+pub struct SidechainFinalityTracker<OCallApi> {
+	ocall_api: Arc<OCallApi>,
+	applied_blocks: Arc<AppliedSidechainBlocks>,
+}
+
+impl<OCallApi> SidechainFinalityTracker<OCallApi> {
+	pub fn new(ocall_api: Arc<OCallApi>, applied_blocks: Arc<AppliedSidechainBlocks>) -> Self {
+		SidechainFinalityTracker { ocall_api, applied_blocks }
+	}
+
+	/// The latest finalized sidechain block of `shard` known to this tracker.
+	pub fn finalized_block(
+		&self,
+		shard: &ShardIdentifier,
+	) -> Result<Option<SidechainBlockConfirmation>> {
+		self.applied_blocks.finalized_block(shard)
+	}
+
+	/// Note a block that was applied to the state of `shard`, see [`AppliedSidechainBlocks`].
+	pub fn note_applied_block(
+		&self,
+		shard: ShardIdentifier,
+		number: BlockNumber,
+		hash: BlockHash,
+		parent_hash: BlockHash,
+	) -> Result<()> {
+		self.applied_blocks.note_applied_block(shard, number, hash, parent_hash)
+	}
+}
+
+impl<OCallApi, ParentchainHeader> TrackSidechainFinality<ParentchainHeader>
+	for SidechainFinalityTracker<OCallApi>
+where
+	OCallApi: EnclaveOnChainOCallApi + EnclaveSidechainOCallApi,
+	ParentchainHeader: ParentchainHeaderTrait<Hash = H256>,
+{
+	fn update_finality(
+		&self,
+		shard: &ShardIdentifier,
+		finalized_parentchain_header: &ParentchainHeader,
+	) -> Result<Option<SidechainBlockConfirmation>> {
+		let maybe_confirmation: Option<SidechainBlockConfirmation> = self
+			.ocall_api
+			.get_storage_verified(
+				latest_sidechain_block_confirmation_key(shard),
+				finalized_parentchain_header,
+				&ParentchainId::Integritee,
+			)
+			.map_err(|e| Error::Other(format!("{:?}", e).into()))?
+			.into_tuple()
+			.1;
+
+		let confirmation = match maybe_confirmation {
+			Some(c) => c,
+			None => return Ok(None),
+		};
+
+		if !self.applied_blocks.advances_finality(shard, &confirmation)? {
+			return Ok(None)
+		}
+
+		self.ocall_api.finalize_sidechain_block(*shard, confirmation)?;
+		self.applied_blocks.finalize(shard, confirmation)?;
+
+		debug!(
+			"Sidechain block {} of shard {:?} is final (parentchain block {:?})",
+			confirmation.block_number,
+			shard,
+			finalized_parentchain_header.number()
+		);
+		Ok(Some(confirmation))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use itc_parentchain_test::ParentchainHeaderBuilder;
+	use itp_test::mock::onchain_mock::OnchainMock;
+
+	fn confirmation(block_number: u64) -> SidechainBlockConfirmation {
+		SidechainBlockConfirmation {
+			block_number,
+			block_header_hash: H256::from_low_u64_be(block_number),
+		}
+	}
+
+	/// Applied blocks of `shard` 1 to `last`, the hash of each block being its number.
+	fn applied_blocks(shard: ShardIdentifier, last: u64) -> Arc<AppliedSidechainBlocks> {
+		let applied_blocks = Arc::new(AppliedSidechainBlocks::new());
+		for number in 1..=last {
+			applied_blocks
+				.note_applied_block(
+					shard,
+					number,
+					H256::from_low_u64_be(number),
+					H256::from_low_u64_be(number - 1),
+				)
+				.unwrap();
+		}
+		applied_blocks
+	}
+
+	#[test]
+	fn no_confirmation_on_chain_finalizes_nothing() {
+		let shard = ShardIdentifier::repeat_byte(1);
+		let header = ParentchainHeaderBuilder::default().build();
+		let tracker = SidechainFinalityTracker::new(
+			Arc::new(OnchainMock::default()),
+			applied_blocks(shard, 5),
+		);
+
+		assert!(tracker.update_finality(&shard, &header).unwrap().is_none());
+		assert!(tracker.finalized_block(&shard).unwrap().is_none());
+	}
+
+	#[test]
+	fn finality_advances_with_confirmations_and_never_goes_backwards() {
+		let shard = ShardIdentifier::repeat_byte(1);
+		let header_1 = ParentchainHeaderBuilder::default().with_number(1).build();
+		let header_2 = ParentchainHeaderBuilder::default().with_number(2).build();
+		let header_3 = ParentchainHeaderBuilder::default().with_number(3).build();
+		let key = latest_sidechain_block_confirmation_key(&shard);
+		let (confirmation_5, confirmation_3) = (confirmation(5), confirmation(3));
+
+		let ocall_api = OnchainMock::default()
+			.with_storage_entries_at_header(&header_1, vec![(key.clone(), confirmation_5)])
+			.with_storage_entries_at_header(&header_2, vec![(key.clone(), confirmation_5)])
+			.with_storage_entries_at_header(&header_3, vec![(key, confirmation_3)]);
+		let tracker = SidechainFinalityTracker::new(Arc::new(ocall_api), applied_blocks(shard, 5));
+
+		assert_eq!(tracker.update_finality(&shard, &header_1).unwrap(), Some(confirmation_5));
+		assert!(tracker.update_finality(&shard, &header_2).unwrap().is_none());
+		assert!(tracker.update_finality(&shard, &header_3).unwrap().is_none());
+		assert_eq!(tracker.finalized_block(&shard).unwrap(), Some(confirmation_5));
+	}
+
+	#[test]
+	fn confirmed_block_that_has_not_been_applied_yet_is_finalized_later() {
+		let shard = ShardIdentifier::repeat_byte(1);
+		let header = ParentchainHeaderBuilder::default().with_number(1).build();
+		let key = latest_sidechain_block_confirmation_key(&shard);
+		let ocall_api = OnchainMock::default()
+			.with_storage_entries_at_header(&header, vec![(key, confirmation(5))]);
+		let applied_blocks = applied_blocks(shard, 4);
+		let tracker = SidechainFinalityTracker::new(Arc::new(ocall_api), applied_blocks.clone());
+
+		assert!(tracker.update_finality(&shard, &header).unwrap().is_none());
+
+		tracker
+			.note_applied_block(shard, 5, H256::from_low_u64_be(5), H256::from_low_u64_be(4))
+			.unwrap();
+		assert_eq!(tracker.update_finality(&shard, &header).unwrap(), Some(confirmation(5)));
+	}
+
+	#[test]
+	fn confirmed_block_of_another_fork_is_not_finalized() {
+		let shard = ShardIdentifier::repeat_byte(1);
+		let header = ParentchainHeaderBuilder::default().with_number(1).build();
+		let key = latest_sidechain_block_confirmation_key(&shard);
+		let other_fork =
+			SidechainBlockConfirmation { block_number: 3, block_header_hash: H256::repeat_byte(3) };
+		let ocall_api =
+			OnchainMock::default().with_storage_entries_at_header(&header, vec![(key, other_fork)]);
+		let tracker = SidechainFinalityTracker::new(Arc::new(ocall_api), applied_blocks(shard, 5));
+
+		assert!(matches!(
+			tracker.update_finality(&shard, &header),
+			Err(Error::FinalizedBlockNotDescendant(3, _, _, _))
+		));
+		assert!(tracker.finalized_block(&shard).unwrap().is_none());
+	}
+}
@@ -0,0 +1,567 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Tracking of competing sidechain forks and the fork-choice rule.
+//!
+//! Validateers can briefly diverge, e.g. when two of them produce a block for the same height.
+//! Instead of rejecting every block that does not extend our best block, we keep the recent
+//! block tree of each shard and follow the fork that wins the fork choice.
+
+#[cfg(feature = "sgx")]
+use std::sync::SgxRwLock as RwLock;
+
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+
+use crate::{
+	header_db::{HeaderDb, HeaderDbTrait},
+	is_descendant_of_builder::IsDescendantOfBuilder,
+	Error, Result,
+};
+use core::fmt;
+use fork_tree::ForkTree;
+use its_primitives::{
+	traits::{
+		Block as SidechainBlockTrait, Header as HeaderTrait, ShardIdentifierFor,
+		SignedBlock as SignedSidechainBlockTrait,
+	},
+	types::{BlockHash, BlockNumber},
+};
+use std::{collections::HashMap, format, vec::Vec};
+
+type HeaderOf<SignedSidechainBlock> =
+	<<SignedSidechainBlock as SignedSidechainBlockTrait>::Block as SidechainBlockTrait>::HeaderType;
+
+/// Deterministic fork-choice rule.
+///
+/// The higher block wins. Of two blocks with the same number, the one with the lower hash wins,
+/// so all validateers agree on the best block, independent of the order they received blocks in.
+pub fn is_better_block(
+	candidate: (BlockNumber, &BlockHash),
+	current: (BlockNumber, &BlockHash),
+) -> bool {
+	candidate.0 > current.0 || (candidate.0 == current.0 && candidate.1 < current.1)
+}
+
+/// A block in the fork tree.
+#[derive(Clone, Debug)]
+pub struct ForkBlock<SignedSidechainBlock: SignedSidechainBlockTrait> {
+	pub block: SignedSidechainBlock::Block,
+	/// Only known for imported blocks. Blocks we produced ourselves are taken from the state,
+	/// which does not contain the signature.
+	pub signed_block: Option<SignedSidechainBlock>,
+}
+
+/// Blocks to retract and to enact, in order to switch the best chain to another fork.
+#[derive(Clone, Debug)]
+pub struct ReorgRoute<SignedSidechainBlock> {
+	/// Last block both chains have in common.
+	pub common_ancestor: BlockHash,
+	/// Blocks that are no longer on the best chain, newest first.
+	pub retracted: Vec<BlockHash>,
+	/// Blocks to apply on top of the common ancestor, oldest first.
+	pub enacted: Vec<SignedSidechainBlock>,
+}
+
+/// The recent block tree of a single shard.
+///
+/// Blocks more than `max_depth` behind the best block are pruned, together with all forks
+/// that branched off before them.
+pub struct SidechainForkTree<SignedSidechainBlock: SignedSidechainBlockTrait> {
+	/// The block number is kept as node data as well, so we can prune by it.
+	tree: ForkTree<BlockHash, BlockNumber, BlockNumber>,
+	header_db: HeaderDb<BlockHash, HeaderOf<SignedSidechainBlock>>,
+	blocks: HashMap<BlockHash, ForkBlock<SignedSidechainBlock>>,
+	best_block: (BlockNumber, BlockHash),
+	max_depth: BlockNumber,
+}
+
+impl<SignedSidechainBlock> SidechainForkTree<SignedSidechainBlock>
+where
+	SignedSidechainBlock: SignedSidechainBlockTrait,
+{
+	/// Create a tree with `root` as its only, and therefore best, block.
+	pub fn new(root: ForkBlock<SignedSidechainBlock>, max_depth: BlockNumber) -> Result<Self> {
+		let header = root.block.header();
+		let mut fork_tree = SidechainForkTree {
+			tree: ForkTree::new(),
+			header_db: HeaderDb(HashMap::new()),
+			blocks: HashMap::new(),
+			best_block: (header.block_number(), header.hash()),
+			max_depth,
+		};
+		fork_tree.insert(root)?;
+		Ok(fork_tree)
+	}
+
+	/// Number and hash of the best block.
+	pub fn best_block(&self) -> (BlockNumber, BlockHash) {
+		self.best_block
+	}
+
+	pub fn contains(&self, hash: &BlockHash) -> bool {
+		self.blocks.contains_key(hash)
+	}
+
+	pub fn block(&self, hash: &BlockHash) -> Option<&ForkBlock<SignedSidechainBlock>> {
+		self.blocks.get(hash)
+	}
+
+	/// Whether a block would win the fork choice against the current best block.
+	pub fn is_better_than_best(&self, number: BlockNumber, hash: &BlockHash) -> bool {
+		is_better_block((number, hash), (self.best_block.0, &self.best_block.1))
+	}
+
+	/// Add a block whose parent is in the tree. Does not change the best block.
+	pub fn import(&mut self, block: ForkBlock<SignedSidechainBlock>) -> Result<()> {
+		let parent_hash = block.block.header().parent_hash();
+		if !self.contains(&parent_hash) {
+			return Err(Error::BlockAncestryMismatch(
+				self.best_block.0,
+				self.best_block.1,
+				format!("Parent {:?} is not part of the fork tree", parent_hash),
+			))
+		}
+		self.insert(block)
+	}
+
+	/// Make `hash` the best block and prune what is too far behind it.
+	pub fn set_best_block(&mut self, hash: &BlockHash) -> Result<()> {
+		let number = self.lookup_header(hash)?.block_number();
+		self.best_block = (number, *hash);
+		self.prune()
+	}
+
+	/// Route from the current best block to `target`.
+	///
+	/// Fails if a block to enact is not available with its signature.
+	pub fn route_to(&self, target: &BlockHash) -> Result<ReorgRoute<SignedSidechainBlock>> {
+		let mut retracted = Vec::new();
+		let mut enacted = Vec::new();
+
+		let mut retract_hash = self.best_block.1;
+		let mut retract_header = self.lookup_header(&retract_hash)?;
+		let mut enact_hash = *target;
+		let mut enact_header = self.lookup_header(&enact_hash)?;
+
+		while retract_header.block_number() > enact_header.block_number() {
+			retracted.push(retract_hash);
+			retract_hash = retract_header.parent_hash();
+			retract_header = self.lookup_header(&retract_hash)?;
+		}
+
+		while enact_header.block_number() > retract_header.block_number() {
+			enacted.push(enact_hash);
+			enact_hash = enact_header.parent_hash();
+			enact_header = self.lookup_header(&enact_hash)?;
+		}
+
+		while retract_hash != enact_hash {
+			retracted.push(retract_hash);
+			enacted.push(enact_hash);
+			retract_hash = retract_header.parent_hash();
+			retract_header = self.lookup_header(&retract_hash)?;
+			enact_hash = enact_header.parent_hash();
+			enact_header = self.lookup_header(&enact_hash)?;
+		}
+
+		let enacted = enacted
+			.iter()
+			.rev()
+			.map(|hash| {
+				self.blocks.get(hash).and_then(|b| b.signed_block.clone()).ok_or_else(|| {
+					Error::ChainLookup(format!("Signed block {:?} is not available", hash))
+				})
+			})
+			.collect::<Result<Vec<_>>>()?;
+
+		Ok(ReorgRoute { common_ancestor: retract_hash, retracted, enacted })
+	}
+
+	fn insert(&mut self, block: ForkBlock<SignedSidechainBlock>) -> Result<()> {
+		let header = block.block.header().clone();
+		let hash = header.hash();
+		let number = header.block_number();
+
+		self.header_db.0.insert(hash, header);
+		let is_descendant_of = IsDescendantOfBuilder::<
+			BlockHash,
+			HeaderDb<BlockHash, HeaderOf<SignedSidechainBlock>>,
+			UnknownAncestry,
+		>::build_is_descendant_of(None, &self.header_db);
+
+		if let Err(e) = self.tree.import(hash, number, number, &is_descendant_of) {
+			self.header_db.0.remove(&hash);
+			return Err(Error::ChainLookup(format!("Failed to add block to fork tree: {:?}", e)))
+		}
+
+		self.blocks.insert(hash, block);
+		Ok(())
+	}
+
+	fn prune(&mut self) -> Result<()> {
+		let (best_number, best_hash) = self.best_block;
+		let threshold = match best_number.checked_sub(self.max_depth) {
+			Some(threshold) => threshold,
+			None => return Ok(()),
+		};
+
+		let removed = {
+			let is_descendant_of = IsDescendantOfBuilder::<
+				BlockHash,
+				HeaderDb<BlockHash, HeaderOf<SignedSidechainBlock>>,
+				UnknownAncestry,
+			>::build_is_descendant_of(None, &self.header_db);
+
+			self.tree
+				.prune(&best_hash, &best_number, &is_descendant_of, &|number| *number <= threshold)
+				.map_err(|e| Error::ChainLookup(format!("Failed to prune fork tree: {:?}", e)))?
+				.map(|(hash, _, _)| hash)
+				.collect::<Vec<_>>()
+		};
+
+		for hash in removed {
+			self.header_db.0.remove(&hash);
+			self.blocks.remove(&hash);
+		}
+		Ok(())
+	}
+
+	fn lookup_header(&self, hash: &BlockHash) -> Result<HeaderOf<SignedSidechainBlock>> {
+		self.header_db.header(hash).ok_or_else(|| {
+			Error::ChainLookup(format!("Block {:?} is not part of the fork tree", hash))
+		})
+	}
+}
+
+/// Fork trees of all shards.
+pub struct SidechainForks<SignedSidechainBlock: SignedSidechainBlockTrait> {
+	fork_trees: RwLock<
+		HashMap<ShardIdentifierFor<SignedSidechainBlock>, SidechainForkTree<SignedSidechainBlock>>,
+	>,
+	max_depth: BlockNumber,
+}
+
+impl<SignedSidechainBlock> SidechainForks<SignedSidechainBlock>
+where
+	SignedSidechainBlock: SignedSidechainBlockTrait,
+{
+	pub fn new(max_depth: BlockNumber) -> Self {
+		SidechainForks { fork_trees: RwLock::new(HashMap::new()), max_depth }
+	}
+
+	/// Align the fork tree of `shard` with the last block that was applied to its state.
+	///
+	/// The state is authoritative for the best block: blocks we produced ourselves are applied
+	/// without being imported, so this is the only place where they are added.
+	pub fn sync_best_block(
+		&self,
+		shard: &ShardIdentifierFor<SignedSidechainBlock>,
+		last_block: Option<&SignedSidechainBlock::Block>,
+	) -> Result<()> {
+		let mut fork_trees = self.fork_trees.write().map_err(|_| Error::LockPoisoning)?;
+		let last_block = match last_block {
+			Some(block) => block,
+			None => {
+				fork_trees.remove(shard);
+				return Ok(())
+			},
+		};
+
+		let hash = last_block.hash();
+		if let Some(tree) = fork_trees.get_mut(shard) {
+			if tree.best_block().1 == hash {
+				return Ok(())
+			}
+			if tree.contains(&hash) {
+				return tree.set_best_block(&hash)
+			}
+			if tree.contains(&last_block.header().parent_hash()) {
+				tree.import(ForkBlock { block: last_block.clone(), signed_block: None })?;
+				return tree.set_best_block(&hash)
+			}
+		}
+
+		let root = ForkBlock { block: last_block.clone(), signed_block: None };
+		fork_trees.insert(*shard, SidechainForkTree::new(root, self.max_depth)?);
+		Ok(())
+	}
+
+	/// Whether a block with `parent_hash` builds on a known fork, rather than on the best block.
+	pub fn is_fork_parent(
+		&self,
+		shard: &ShardIdentifierFor<SignedSidechainBlock>,
+		parent_hash: &BlockHash,
+	) -> Result<bool> {
+		let fork_trees = self.fork_trees.read().map_err(|_| Error::LockPoisoning)?;
+		Ok(fork_trees
+			.get(shard)
+			.map(|tree| tree.best_block().1 != *parent_hash && tree.contains(parent_hash))
+			.unwrap_or(false))
+	}
+
+	/// Whether a block is part of the fork tree of `shard`.
+	pub fn contains(
+		&self,
+		shard: &ShardIdentifierFor<SignedSidechainBlock>,
+		hash: &BlockHash,
+	) -> Result<bool> {
+		let fork_trees = self.fork_trees.read().map_err(|_| Error::LockPoisoning)?;
+		Ok(fork_trees.get(shard).map(|tree| tree.contains(hash)).unwrap_or(false))
+	}
+
+	/// Add a block that was applied on top of the best block, and make it the new best block.
+	pub fn import_best_block(&self, signed_block: &SignedSidechainBlock) -> Result<()> {
+		let shard = signed_block.block().header().shard_id();
+		let fork_block = ForkBlock {
+			block: signed_block.block().clone(),
+			signed_block: Some(signed_block.clone()),
+		};
+
+		let mut fork_trees = self.fork_trees.write().map_err(|_| Error::LockPoisoning)?;
+		match fork_trees.get_mut(&shard) {
+			Some(tree) if tree.contains(&fork_block.block.header().parent_hash()) => {
+				tree.import(fork_block)?;
+				tree.set_best_block(&signed_block.hash())
+			},
+			_ => {
+				fork_trees.insert(shard, SidechainForkTree::new(fork_block, self.max_depth)?);
+				Ok(())
+			},
+		}
+	}
+
+	/// Execute a function on the fork tree of `shard`.
+	pub fn execute_on_fork_tree<F, R>(
+		&self,
+		shard: &ShardIdentifierFor<SignedSidechainBlock>,
+		executing_function: F,
+	) -> Result<R>
+	where
+		F: FnOnce(&mut SidechainForkTree<SignedSidechainBlock>) -> Result<R>,
+	{
+		let mut fork_trees = self.fork_trees.write().map_err(|_| Error::LockPoisoning)?;
+		let tree = fork_trees
+			.get_mut(shard)
+			.ok_or_else(|| Error::ChainLookup(format!("No fork tree for shard {:?}", shard)))?;
+		executing_function(tree)
+	}
+}
+
+/// Ancestry lookup error of the fork tree, a block was not found in the header database.
+#[derive(Debug)]
+struct UnknownAncestry;
+
+impl From<()> for UnknownAncestry {
+	fn from(_: ()) -> Self {
+		UnknownAncestry
+	}
+}
+
+impl fmt::Display for UnknownAncestry {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "Unknown block ancestry")
+	}
+}
+
+impl std::error::Error for UnknownAncestry {}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use its_primitives::types::SignedBlock;
+	use its_test::{
+		sidechain_block_builder::{SidechainBlockBuilder, SidechainBlockBuilderTrait},
+		sidechain_header_builder::SidechainHeaderBuilder,
+	};
+	use sp_core::H256;
+
+	fn block_on(parent: Option<&SignedBlock>, fork_id: u64) -> SignedBlock {
+		let (parent_hash, number) = match parent {
+			Some(parent) => (parent.hash(), parent.block().header().block_number() + 1),
+			None => (H256::default(), 1),
+		};
+		let header = SidechainHeaderBuilder::default()
+			.with_parent_hash(parent_hash)
+			.with_block_number(number)
+			.with_block_data_hash(H256::from_low_u64_be(fork_id))
+			.build();
+		SidechainBlockBuilder::default().with_header(header).build_signed()
+	}
+
+	fn fork_block(signed_block: &SignedBlock) -> ForkBlock<SignedBlock> {
+		ForkBlock { block: signed_block.block().clone(), signed_block: Some(signed_block.clone()) }
+	}
+
+	fn fork_tree_with(blocks: &[&SignedBlock]) -> SidechainForkTree<SignedBlock> {
+		let mut tree = SidechainForkTree::new(fork_block(blocks[0]), 4).unwrap();
+		for block in &blocks[1..] {
+			tree.import(fork_block(block)).unwrap();
+		}
+		tree
+	}
+
+	#[test]
+	fn higher_block_number_is_better() {
+		let low_hash = H256::from_low_u64_be(1);
+		let high_hash = H256::from_low_u64_be(2);
+
+		assert!(is_better_block((3, &high_hash), (2, &low_hash)));
+		assert!(!is_better_block((2, &low_hash), (3, &high_hash)));
+	}
+
+	#[test]
+	fn lower_hash_breaks_tie() {
+		let low_hash = H256::from_low_u64_be(1);
+		let high_hash = H256::from_low_u64_be(2);
+
+		assert!(is_better_block((2, &low_hash), (2, &high_hash)));
+		assert!(!is_better_block((2, &high_hash), (2, &low_hash)));
+		assert!(!is_better_block((2, &low_hash), (2, &low_hash)));
+	}
+
+	#[test]
+	fn import_fails_for_unknown_parent() {
+		let block_1 = block_on(None, 0);
+		let block_2 = block_on(Some(&block_1), 0);
+		let block_3 = block_on(Some(&block_2), 0);
+
+		let mut tree = fork_tree_with(&[&block_1]);
+
+		assert!(tree.import(fork_block(&block_3)).is_err());
+		assert!(!tree.contains(&block_3.hash()));
+	}
+
+	#[test]
+	fn route_to_fork_retracts_and_enacts_from_common_ancestor() {
+		let block_1 = block_on(None, 0);
+		let block_2a = block_on(Some(&block_1), 0);
+		let block_3a = block_on(Some(&block_2a), 0);
+		let block_2b = block_on(Some(&block_1), 1);
+		let block_3b = block_on(Some(&block_2b), 1);
+		let block_4b = block_on(Some(&block_3b), 1);
+
+		let mut tree =
+			fork_tree_with(&[&block_1, &block_2a, &block_3a, &block_2b, &block_3b, &block_4b]);
+		tree.set_best_block(&block_3a.hash()).unwrap();
+
+		let route = tree.route_to(&block_4b.hash()).unwrap();
+
+		assert_eq!(route.common_ancestor, block_1.hash());
+		assert_eq!(route.retracted, vec![block_3a.hash(), block_2a.hash()]);
+		assert_eq!(route.enacted, vec![block_2b, block_3b, block_4b]);
+	}
+
+	#[test]
+	fn route_to_descendant_only_enacts() {
+		let block_1 = block_on(None, 0);
+		let block_2 = block_on(Some(&block_1), 0);
+		let block_3 = block_on(Some(&block_2), 0);
+
+		let tree = fork_tree_with(&[&block_1, &block_2, &block_3]);
+
+		let route = tree.route_to(&block_3.hash()).unwrap();
+
+		assert_eq!(route.common_ancestor, block_1.hash());
+		assert!(route.retracted.is_empty());
+		assert_eq!(route.enacted, vec![block_2, block_3]);
+	}
+
+	#[test]
+	fn route_fails_if_enacted_block_is_not_signed() {
+		let block_1 = block_on(None, 0);
+		let block_2 = block_on(Some(&block_1), 0);
+
+		let mut tree = fork_tree_with(&[&block_1]);
+		tree.import(ForkBlock { block: block_2.block().clone(), signed_block: None })
+			.unwrap();
+
+		assert!(tree.route_to(&block_2.hash()).is_err());
+	}
+
+	#[test]
+	fn setting_best_block_prunes_stale_forks() {
+		let block_1 = block_on(None, 0);
+		let block_2a = block_on(Some(&block_1), 0);
+		let block_2b = block_on(Some(&block_1), 1);
+		let block_3 = block_on(Some(&block_2a), 0);
+		let block_4 = block_on(Some(&block_3), 0);
+		let block_5 = block_on(Some(&block_4), 0);
+		let block_6 = block_on(Some(&block_5), 0);
+
+		let mut tree = fork_tree_with(&[
+			&block_1, &block_2a, &block_2b, &block_3, &block_4, &block_5, &block_6,
+		]);
+		assert!(tree.contains(&block_2b.hash()));
+
+		// Block 1 is max depth behind block 5, so forks branching off at block 1 are pruned.
+		tree.set_best_block(&block_5.hash()).unwrap();
+
+		assert!(tree.contains(&block_1.hash()));
+		assert!(!tree.contains(&block_2b.hash()));
+
+		tree.set_best_block(&block_6.hash()).unwrap();
+
+		assert!(!tree.contains(&block_1.hash()));
+		assert!(tree.contains(&block_2a.hash()));
+		assert_eq!(tree.best_block(), (6, block_6.hash()));
+	}
+
+	#[test]
+	fn sync_best_block_follows_own_produced_blocks() {
+		let block_1 = block_on(None, 0);
+		let block_2 = block_on(Some(&block_1), 0);
+		let shard = block_1.block().header().shard_id();
+		let forks = SidechainForks::<SignedBlock>::new(4);
+
+		forks.import_best_block(&block_1).unwrap();
+		forks.sync_best_block(&shard, Some(block_2.block())).unwrap();
+
+		let best = forks.execute_on_fork_tree(&shard, |tree| Ok(tree.best_block())).unwrap();
+		assert_eq!(best, (2, block_2.hash()));
+		assert!(forks.contains(&shard, &block_1.hash()).unwrap());
+	}
+
+	#[test]
+	fn is_fork_parent_only_for_non_best_blocks_in_tree() {
+		let block_1 = block_on(None, 0);
+		let block_2 = block_on(Some(&block_1), 0);
+		let shard = block_1.block().header().shard_id();
+		let forks = SidechainForks::<SignedBlock>::new(4);
+
+		assert!(!forks.is_fork_parent(&shard, &block_1.hash()).unwrap());
+
+		forks.import_best_block(&block_1).unwrap();
+		forks.import_best_block(&block_2).unwrap();
+
+		assert!(forks.is_fork_parent(&shard, &block_1.hash()).unwrap());
+		assert!(!forks.is_fork_parent(&shard, &block_2.hash()).unwrap());
+		assert!(!forks.is_fork_parent(&shard, &H256::from_low_u64_be(42)).unwrap());
+	}
+
+	#[test]
+	fn sync_without_last_block_removes_fork_tree() {
+		let block_1 = block_on(None, 0);
+		let shard = block_1.block().header().shard_id();
+		let forks = SidechainForks::<SignedBlock>::new(4);
+
+		forks.import_best_block(&block_1).unwrap();
+		forks.sync_best_block(&shard, None).unwrap();
+
+		assert!(!forks.contains(&shard, &block_1.hash()).unwrap());
+	}
+}
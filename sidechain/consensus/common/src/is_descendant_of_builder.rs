@@ -83,6 +83,8 @@ where
 		let mut blocknum_2 = header_2.block_number();
 		let mut parent_1 = Hash::from(header_1.parent_hash());
 		let mut parent_2 = Hash::from(header_2.parent_hash());
+		let mut node_1 = a.clone();
+		let mut node_2 = b.clone();
 
 		if *a == parent_2 {
 			// Then a is the common ancestor of b and it means it is itself the ancestor
@@ -97,37 +99,34 @@ where
 		while blocknum_1 > blocknum_2 {
 			// This means block 1 is further down in the tree than block 2
 			let new_parent = header_db.header(&parent_1.clone().into()).ok_or(())?;
-
-			if new_parent.block_number() >= blocknum_2 {
-				blocknum_1 = new_parent.block_number();
-				parent_1 = Hash::from(new_parent.parent_hash());
-			} else {
-				break
-			}
+			node_1 = parent_1;
+			blocknum_1 = new_parent.block_number();
+			parent_1 = Hash::from(new_parent.parent_hash());
 		}
 
 		while blocknum_2 > blocknum_1 {
 			// This means block 2 is further down in the tree than block 1
 			let new_parent = header_db.header(&parent_2.clone().into()).ok_or(())?;
-
-			if new_parent.block_number() >= blocknum_1 {
-				blocknum_2 = new_parent.block_number();
-				parent_2 = Hash::from(new_parent.parent_hash());
-			} else {
-				break
-			}
+			node_2 = parent_2;
+			blocknum_2 = new_parent.block_number();
+			parent_2 = Hash::from(new_parent.parent_hash());
 		}
 
-		// At this point will be at equal height
-		while parent_1 != parent_2 {
+		// At this point both nodes are at equal height. Compare the nodes themselves,
+		// not only their parents: one of them may be the ancestor of the other.
+		while node_1 != node_2 {
 			// go up on both nodes
-			let new_header_1 = header_db.header(&parent_1.into()).ok_or(())?;
-			let new_header_2 = header_db.header(&parent_2.into()).ok_or(())?;
+			node_1 = parent_1;
+			node_2 = parent_2;
+			if node_1 == node_2 {
+				break
+			}
+			let new_header_1 = header_db.header(&node_1.clone().into()).ok_or(())?;
+			let new_header_2 = header_db.header(&node_2.clone().into()).ok_or(())?;
 			parent_1 = Hash::from(new_header_1.parent_hash());
 			parent_2 = Hash::from(new_header_2.parent_hash());
 		}
 
-		// Return any Parent node Hash as in worst case scenario it is the root which is shared amongst all
-		Ok(parent_1)
+		Ok(node_1)
 	}
 }
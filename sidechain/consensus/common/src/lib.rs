@@ -32,24 +32,29 @@ use its_primitives::traits::{ShardIdentifierFor, SignedBlock as SignedSidechainB
 use sp_runtime::traits::Block as ParentchainBlockTrait;
 use std::{time::Duration, vec::Vec};
 
+mod applied_blocks;
 mod block_import;
 mod block_import_confirmation_handler;
 mod block_import_queue_worker;
+mod block_states;
 mod error;
+mod finality;
+mod fork_choice;
 mod header_db;
-mod peer_block_sync;
-
-// The feature flag will be removed once we use the module outside of tests.
-#[cfg(test)]
 mod is_descendant_of_builder;
+mod peer_block_sync;
 
 #[cfg(test)]
 mod test;
 
+pub use applied_blocks::*;
 pub use block_import::*;
 pub use block_import_confirmation_handler::*;
 pub use block_import_queue_worker::*;
+pub use block_states::*;
 pub use error::*;
+pub use finality::*;
+pub use fork_choice::*;
 pub use peer_block_sync::*;
 
 pub trait Verifier<ParentchainBlock, SignedSidechainBlock>: Send + Sync
@@ -15,13 +15,16 @@
 
 */
 
-use crate::{test::mocks::verifier_mock::VerifierMock, BlockImport, Error, Result};
+use crate::{test::mocks::verifier_mock::VerifierMock, BlockImport, Error, Result, SidechainForks};
 use core::marker::PhantomData;
 use itp_sgx_crypto::aes::Aes;
 use itp_sgx_externalities::SgxExternalities;
 use itp_test::mock::onchain_mock::OnchainMock;
 use itp_types::H256;
-use its_primitives::traits::{ShardIdentifierFor, SignedBlock as SignedSidechainBlockTrait};
+use its_primitives::{
+	traits::{ShardIdentifierFor, SignedBlock as SignedSidechainBlockTrait},
+	types::{BlockHash, BlockNumber},
+};
 use sp_core::Pair;
 use sp_runtime::traits::Block as ParentchainBlockTrait;
 use std::{collections::VecDeque, sync::RwLock};
@@ -153,6 +156,74 @@ where
 		todo!()
 	}
 
+	fn forks(&self) -> &SidechainForks<SignedSidechainBlock> {
+		todo!()
+	}
+
+	fn last_block(
+		&self,
+		_shard: &ShardIdentifierFor<SignedSidechainBlock>,
+	) -> Result<Option<SignedSidechainBlock::Block>> {
+		todo!()
+	}
+
+	fn block_state(
+		&self,
+		_shard: &ShardIdentifierFor<SignedSidechainBlock>,
+		_block_hash: &BlockHash,
+	) -> Result<Self::SidechainState> {
+		todo!()
+	}
+
+	fn keep_block_state(
+		&self,
+		_shard: &ShardIdentifierFor<SignedSidechainBlock>,
+		_block: &SignedSidechainBlock::Block,
+		_state: Self::SidechainState,
+	) -> Result<()> {
+		todo!()
+	}
+
+	fn finalized_block_number(
+		&self,
+		_shard: &ShardIdentifierFor<SignedSidechainBlock>,
+	) -> Result<Option<BlockNumber>> {
+		todo!()
+	}
+
+	fn current_state(
+		&self,
+		_shard: &ShardIdentifierFor<SignedSidechainBlock>,
+	) -> Result<Self::SidechainState> {
+		todo!()
+	}
+
+	fn reset_state(
+		&self,
+		_shard: &ShardIdentifierFor<SignedSidechainBlock>,
+		_state: Self::SidechainState,
+	) -> Result<()> {
+		todo!()
+	}
+
+	fn reapply_parentchain_blocks(
+		&self,
+		_applied_parentchain_head: &H256,
+		_target_parentchain_head: Option<&H256>,
+		_last_imported_parentchain_header: &ParentchainBlock::Header,
+	) -> Result<ParentchainBlock::Header> {
+		todo!()
+	}
+
+	fn resubmit_operations(
+		&self,
+		_shard: &ShardIdentifierFor<SignedSidechainBlock>,
+		_retracted: &[BlockHash],
+		_enacted: &[SignedSidechainBlock],
+	) {
+		todo!()
+	}
+
 	fn import_block(
 		&self,
 		signed_sidechain_block: SignedSidechainBlock,
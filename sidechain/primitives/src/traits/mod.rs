@@ -20,6 +20,7 @@
 //! Todo: This crate should be more generic and supply blanket implementations for
 //! some generic structs.
 
+use crate::types::block_data::AuditorStateDiff;
 use codec::{Decode, Encode};
 use sp_core::{crypto::Public, H256};
 use sp_runtime::traits::{BlakeTwo256, Hash, Member};
@@ -29,6 +30,8 @@ pub trait Header: Encode + Decode + Clone {
 	/// Identifier for the shards.
 	type ShardIdentifier: Encode + Decode + sp_std::hash::Hash + Copy + Member;
 
+	/// Get the header format version.
+	fn version(&self) -> u8;
 	/// Get block number.
 	fn block_number(&self) -> u64;
 	/// get parent hash of block
@@ -37,6 +40,8 @@ pub trait Header: Encode + Decode + Clone {
 	fn shard_id(&self) -> Self::ShardIdentifier;
 	/// get hash of the block's payload
 	fn block_data_hash(&self) -> H256;
+	/// get the merkle-trie root of the state after the block was applied
+	fn state_root(&self) -> H256;
 
 	/// get the `blake2_256` hash of the header.
 	fn hash(&self) -> H256 {
@@ -50,6 +55,7 @@ pub trait Header: Encode + Decode + Clone {
 		parent_hash: H256,
 		shard: Self::ShardIdentifier,
 		block_data_hash: H256,
+		state_root: H256,
 		next_finalization_block_number: u64,
 	) -> Self;
 }
@@ -68,6 +74,10 @@ pub trait BlockData: Encode + Decode + Send + Sync + Debug + Clone {
 	fn signed_top_hashes(&self) -> &[H256];
 	/// get encrypted payload
 	fn encrypted_state_diff(&self) -> &Vec<u8>;
+	/// get state payload encrypted for the shard auditor, if any
+	fn auditor_state_diff(&self) -> Option<&AuditorStateDiff>;
+	/// set the state payload encrypted for the shard auditor
+	fn with_auditor_state_diff(self, auditor_state_diff: Option<AuditorStateDiff>) -> Self;
 	/// get the `blake2_256` hash of the block
 	fn hash(&self) -> H256 {
 		self.using_encoded(BlakeTwo256::hash)
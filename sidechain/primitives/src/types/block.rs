@@ -121,7 +121,8 @@ mod tests {
 	}
 
 	fn test_block() -> Block {
-		let header = Header::new(0, H256::random(), H256::random(), Default::default(), 1);
+		let header =
+			Header::new(0, H256::random(), H256::random(), Default::default(), H256::random(), 1);
 		let block_data = BlockData::new(
 			ed25519::Pair::from_string("//Alice", None).unwrap().public(),
 			H256::random(),
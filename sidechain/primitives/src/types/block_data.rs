@@ -16,7 +16,7 @@
 */
 
 use crate::traits::BlockData as BlockDataTrait;
-use codec::{Decode, Encode};
+use codec::{Decode, Encode, EncodeLike, Error as CodecError, Input, Output};
 use sp_core::{ed25519, H256};
 use sp_std::vec::Vec;
 
@@ -25,7 +25,18 @@ pub type Timestamp = u64;
 #[cfg(feature = "std")]
 use serde::{Deserialize, Serialize};
 
-#[derive(PartialEq, Eq, Clone, Encode, Decode, Debug)]
+/// Marks block data encoded with a version, in place of the timestamp of the original format.
+///
+/// No block can have this timestamp, so block data in the original format, e.g. of blocks
+/// stored before the auditor state diff was introduced, can still be decoded.
+const VERSIONED_BLOCK_DATA_MARKER: u64 = u64::MAX;
+
+/// Version of the block data format that appends an [`AuditorStateDiff`].
+const BLOCK_DATA_VERSION_WITH_AUDITOR: u8 = 1;
+
+/// Block data without auditor state diff is encoded in the original format, block data with
+/// one as `VERSIONED_BLOCK_DATA_MARKER ++ version ++ original fields ++ auditor state diff`.
+#[derive(PartialEq, Eq, Clone, Debug)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub struct BlockData {
 	pub timestamp: u64,
@@ -37,6 +48,75 @@ pub struct BlockData {
 	pub signed_top_hashes: Vec<H256>,
 	/// Encrypted state payload.
 	pub encrypted_state_diff: Vec<u8>,
+	/// State payload encrypted for the auditor of the shard, if one is registered.
+	pub auditor_state_diff: Option<AuditorStateDiff>,
+}
+
+/// State payload of a block, readable by the auditor registered for the shard.
+///
+/// The payload is encrypted with a fresh per-block key, which is itself encrypted with
+/// the public key of the auditor.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Debug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct AuditorStateDiff {
+	/// Encoded per-block state key, encrypted with the auditor public key.
+	pub encrypted_key: Vec<u8>,
+	/// State payload, encrypted with the per-block state key.
+	pub encrypted_state_diff: Vec<u8>,
+}
+
+impl Encode for BlockData {
+	fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+		if let Some(auditor_state_diff) = &self.auditor_state_diff {
+			VERSIONED_BLOCK_DATA_MARKER.encode_to(dest);
+			BLOCK_DATA_VERSION_WITH_AUDITOR.encode_to(dest);
+			self.encode_original_fields_to(dest);
+			auditor_state_diff.encode_to(dest);
+		} else {
+			self.encode_original_fields_to(dest);
+		}
+	}
+}
+
+impl EncodeLike for BlockData {}
+
+impl Decode for BlockData {
+	fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+		let timestamp = u64::decode(input)?;
+		if timestamp != VERSIONED_BLOCK_DATA_MARKER {
+			return Self::decode_original_fields(timestamp, input)
+		}
+
+		match u8::decode(input)? {
+			BLOCK_DATA_VERSION_WITH_AUDITOR => {
+				let timestamp = u64::decode(input)?;
+				let block_data = Self::decode_original_fields(timestamp, input)?;
+				Ok(block_data.with_auditor_state_diff(Some(AuditorStateDiff::decode(input)?)))
+			},
+			_ => Err("Unknown block data version".into()),
+		}
+	}
+}
+
+impl BlockData {
+	fn encode_original_fields_to<T: Output + ?Sized>(&self, dest: &mut T) {
+		self.timestamp.encode_to(dest);
+		self.layer_one_head.encode_to(dest);
+		self.block_author.encode_to(dest);
+		self.signed_top_hashes.encode_to(dest);
+		self.encrypted_state_diff.encode_to(dest);
+	}
+
+	fn decode_original_fields<I: Input>(timestamp: u64, input: &mut I) -> Result<Self, CodecError> {
+		Ok(BlockData {
+			timestamp,
+			layer_one_head: Decode::decode(input)?,
+			block_author: Decode::decode(input)?,
+			signed_top_hashes: Decode::decode(input)?,
+			encrypted_state_diff: Decode::decode(input)?,
+			auditor_state_diff: None,
+		})
+	}
 }
 
 impl BlockDataTrait for BlockData {
@@ -62,6 +142,15 @@ impl BlockDataTrait for BlockData {
 	fn encrypted_state_diff(&self) -> &Vec<u8> {
 		&self.encrypted_state_diff
 	}
+	/// Get state payload encrypted for the shard auditor.
+	fn auditor_state_diff(&self) -> Option<&AuditorStateDiff> {
+		self.auditor_state_diff.as_ref()
+	}
+	/// Set state payload encrypted for the shard auditor.
+	fn with_auditor_state_diff(mut self, auditor_state_diff: Option<AuditorStateDiff>) -> Self {
+		self.auditor_state_diff = auditor_state_diff;
+		self
+	}
 	/// Constructs block data.
 	fn new(
 		block_author: Self::Public,
@@ -77,6 +166,58 @@ impl BlockDataTrait for BlockData {
 			signed_top_hashes,
 			block_author,
 			encrypted_state_diff,
+			auditor_state_diff: None,
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn block_data() -> BlockData {
+		BlockData::new(
+			ed25519::Public::from_raw([1u8; 32]),
+			H256::random(),
+			vec![H256::random()],
+			vec![4u8; 16],
+			1_700_000_000_000,
+		)
+	}
+
+	#[test]
+	fn block_data_without_auditor_state_diff_is_encoded_in_original_format() {
+		let block_data = block_data();
+
+		let original_encoding = (
+			block_data.timestamp,
+			block_data.layer_one_head,
+			block_data.block_author,
+			block_data.signed_top_hashes.clone(),
+			block_data.encrypted_state_diff.clone(),
+		)
+			.encode();
+
+		assert_eq!(block_data.encode(), original_encoding);
+		assert_eq!(BlockData::decode(&mut original_encoding.as_slice()).unwrap(), block_data);
+	}
+
+	#[test]
+	fn block_data_with_auditor_state_diff_round_trips() {
+		let block_data = block_data().with_auditor_state_diff(Some(AuditorStateDiff {
+			encrypted_key: vec![1u8; 8],
+			encrypted_state_diff: vec![2u8; 32],
+		}));
+
+		let encoded = block_data.encode();
+
+		assert_eq!(BlockData::decode(&mut encoded.as_slice()).unwrap(), block_data);
+	}
+
+	#[test]
+	fn decoding_unknown_block_data_version_fails() {
+		let encoded = (VERSIONED_BLOCK_DATA_MARKER, 2u8).encode();
+
+		assert!(BlockData::decode(&mut encoded.as_slice()).is_err());
+	}
+}
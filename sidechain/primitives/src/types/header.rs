@@ -28,9 +28,17 @@ use serde::{Deserialize, Serialize};
 
 pub use itp_types::ShardIdentifier;
 
-#[derive(PartialEq, Eq, Clone, Encode, Decode, Debug, Copy, Default, TypeInfo)]
+/// Version of the header format, bumped whenever the encoding of [`SidechainHeader`] changes.
+///
+/// Version 1 added the `state_root`. Headers without a version field predate it.
+pub const SIDECHAIN_HEADER_VERSION: u8 = 1;
+
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Debug, Copy, TypeInfo)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub struct SidechainHeader {
+	/// The header format version, see [`SIDECHAIN_HEADER_VERSION`].
+	pub version: u8,
+
 	/// The parent hash.
 	pub parent_hash: H256,
 
@@ -43,10 +51,27 @@ pub struct SidechainHeader {
 	/// The payload hash.
 	pub block_data_hash: H256,
 
+	/// The merkle-trie root of the shard state after this block was applied.
+	pub state_root: H256,
+
 	/// The latest finalized block number
 	pub next_finalization_block_number: u64,
 }
 
+impl Default for SidechainHeader {
+	fn default() -> Self {
+		SidechainHeader {
+			version: SIDECHAIN_HEADER_VERSION,
+			parent_hash: Default::default(),
+			block_number: Default::default(),
+			shard_id: Default::default(),
+			block_data_hash: Default::default(),
+			state_root: Default::default(),
+			next_finalization_block_number: Default::default(),
+		}
+	}
+}
+
 impl SidechainHeader {
 	/// get the `blake2_256` hash of the header.
 	pub fn hash(&self) -> H256 {
@@ -57,6 +82,9 @@ impl SidechainHeader {
 impl HeaderTrait for SidechainHeader {
 	type ShardIdentifier = H256;
 
+	fn version(&self) -> u8 {
+		self.version
+	}
 	fn block_number(&self) -> u64 {
 		self.block_number
 	}
@@ -69,6 +97,9 @@ impl HeaderTrait for SidechainHeader {
 	fn block_data_hash(&self) -> H256 {
 		self.block_data_hash
 	}
+	fn state_root(&self) -> H256 {
+		self.state_root
+	}
 	fn next_finalization_block_number(&self) -> u64 {
 		self.next_finalization_block_number
 	}
@@ -78,13 +109,16 @@ impl HeaderTrait for SidechainHeader {
 		parent_hash: H256,
 		shard: Self::ShardIdentifier,
 		block_data_hash: H256,
+		state_root: H256,
 		next_finalization_block_number: u64,
 	) -> SidechainHeader {
 		SidechainHeader {
+			version: SIDECHAIN_HEADER_VERSION,
 			block_number,
 			parent_hash,
 			shard_id: shard,
 			block_data_hash,
+			state_root,
 			next_finalization_block_number,
 		}
 	}
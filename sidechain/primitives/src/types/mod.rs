@@ -17,6 +17,8 @@
 
 pub mod block;
 pub mod block_data;
+pub mod finality;
 pub mod header;
+pub mod read_proof;
 
 pub use block::*;
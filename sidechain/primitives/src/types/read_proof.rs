@@ -0,0 +1,45 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Proof of state values against the state root of a sidechain header.
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_core::H256;
+use sp_std::prelude::*;
+
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+/// Merkle proof of a set of state keys, anchored in a sidechain block.
+///
+/// The `proof` can be checked against `state_root` with a `StorageProofChecker<BlakeTwo256>`.
+/// `state_root` equals the one in the header of the block with `block_hash`.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Debug, Default, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct ReadProof {
+	/// Hash of the block the proof is anchored in.
+	pub block_hash: H256,
+
+	/// Number of the block the proof is anchored in.
+	pub block_number: u64,
+
+	/// State root of the block's header.
+	pub state_root: H256,
+
+	/// Trie nodes needed to prove the requested keys.
+	pub proof: Vec<Vec<u8>>,
+}
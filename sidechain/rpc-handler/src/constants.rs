@@ -20,3 +20,13 @@
 // RPC method names.
 pub const RPC_METHOD_NAME_IMPORT_BLOCKS: &str = "sidechain_importBlock";
 pub const RPC_METHOD_NAME_FETCH_BLOCKS_FROM_PEER: &str = "sidechain_fetchBlocksFromPeer";
+pub const RPC_METHOD_NAME_STATE_GET_READ_PROOF: &str = "state_getReadProof";
+pub const RPC_METHOD_NAME_GET_BLOCK_BY_NUMBER: &str = "sidechain_getBlockByNumber";
+pub const RPC_METHOD_NAME_GET_BLOCK_BY_HASH: &str = "sidechain_getBlockByHash";
+pub const RPC_METHOD_NAME_GET_LATEST_HEADER: &str = "sidechain_getLatestHeader";
+pub const RPC_METHOD_NAME_SUBSCRIBE_NEW_HEADS: &str = "sidechain_subscribeNewHeads";
+pub const RPC_METHOD_NAME_UNSUBSCRIBE_NEW_HEADS: &str = "sidechain_unsubscribeNewHeads";
+pub const RPC_METHOD_NAME_GET_FINALIZED_HEADER: &str = "sidechain_getFinalizedHeader";
+pub const RPC_METHOD_NAME_GET_FINALITY_STATUS: &str = "sidechain_getFinalityStatus";
+pub const RPC_METHOD_NAME_SUBSCRIBE_FINALIZED_HEADS: &str = "sidechain_subscribeFinalizedHeads";
+pub const RPC_METHOD_NAME_UNSUBSCRIBE_FINALIZED_HEADS: &str = "sidechain_unsubscribeFinalizedHeads";
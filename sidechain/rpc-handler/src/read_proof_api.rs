@@ -0,0 +1,135 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+#[cfg(all(not(feature = "std"), feature = "sgx"))]
+use crate::sgx_reexport_prelude::*;
+
+use crate::constants::RPC_METHOD_NAME_STATE_GET_READ_PROOF;
+use codec::{Decode, Encode};
+use itp_rpc::RpcReturnValue;
+use itp_types::{DirectRequestStatus, Request, ShardIdentifier};
+use itp_utils::{FromHexPrefixed, ToHexPrefixed};
+use its_primitives::types::read_proof::ReadProof;
+use jsonrpc_core::{serde_json::json, IoHandler, Params};
+use log::*;
+use std::{borrow::ToOwned, format, string::String, vec::Vec};
+
+/// Adds `state_getReadProof`.
+///
+/// Expects a hex encoded `Request`, whose `cyphertext` is the (plain) encoded `Vec<Vec<u8>>` of
+/// storage keys to prove. Returns the encoded `ReadProof` in an `RpcReturnValue`.
+pub fn add_read_proof_rpc_method<ProveFn>(prove_fn: ProveFn, mut io_handler: IoHandler) -> IoHandler
+where
+	ProveFn: Fn(&ShardIdentifier, &[Vec<u8>]) -> Result<ReadProof, String> + Sync + Send + 'static,
+{
+	io_handler.add_sync_method(RPC_METHOD_NAME_STATE_GET_READ_PROOF, move |params: Params| {
+		let json_value = match get_read_proof_inner(&prove_fn, params) {
+			Ok(read_proof) => RpcReturnValue {
+				do_watch: false,
+				value: read_proof.encode(),
+				status: DirectRequestStatus::Ok,
+			}
+			.to_hex(),
+			Err(error) => {
+				warn!("{} failed: {}", RPC_METHOD_NAME_STATE_GET_READ_PROOF, error);
+				RpcReturnValue::from_error_message(error.as_str()).to_hex()
+			},
+		};
+		Ok(json!(json_value))
+	});
+
+	io_handler
+}
+
+fn get_read_proof_inner<ProveFn>(prove_fn: &ProveFn, params: Params) -> Result<ReadProof, String>
+where
+	ProveFn: Fn(&ShardIdentifier, &[Vec<u8>]) -> Result<ReadProof, String>,
+{
+	let hex_encoded_params = params.parse::<Vec<String>>().map_err(|e| format!("{:?}", e))?;
+	let request = Request::from_hex(
+		hex_encoded_params
+			.get(0)
+			.ok_or_else(|| "Missing request parameter".to_owned())?,
+	)
+	.map_err(|e| format!("{:?}", e))?;
+
+	let keys = Vec::<Vec<u8>>::decode(&mut request.cyphertext.as_slice())
+		.map_err(|e| format!("Could not decode storage keys: {:?}", e))?;
+
+	prove_fn(&request.shard, &keys)
+}
+
+#[cfg(test)]
+pub mod tests {
+
+	use super::*;
+	use itp_rpc::RpcRequest;
+	use sp_core::H256;
+	use std::string::ToString;
+
+	fn read_proof() -> ReadProof {
+		ReadProof {
+			block_hash: H256::from([1u8; 32]),
+			block_number: 7,
+			state_root: H256::from([2u8; 32]),
+			proof: vec![vec![3u8, 4u8]],
+		}
+	}
+
+	fn io_handler() -> IoHandler {
+		add_read_proof_rpc_method(
+			|_: &ShardIdentifier, keys: &[Vec<u8>]| {
+				if keys.is_empty() {
+					Err("no keys".to_string())
+				} else {
+					Ok(read_proof())
+				}
+			},
+			IoHandler::new(),
+		)
+	}
+
+	fn call(keys: Vec<Vec<u8>>) -> RpcReturnValue {
+		let request = Request { shard: ShardIdentifier::default(), cyphertext: keys.encode() };
+		let request_string = RpcRequest::compose_jsonrpc_call(
+			RPC_METHOD_NAME_STATE_GET_READ_PROOF.to_string(),
+			vec![request.to_hex()],
+		)
+		.unwrap();
+
+		let response_string = io_handler().handle_request_sync(&request_string).unwrap();
+		let response: jsonrpc_core::serde_json::Value =
+			jsonrpc_core::serde_json::from_str(&response_string).unwrap();
+		RpcReturnValue::from_hex(response["result"].as_str().unwrap()).unwrap()
+	}
+
+	#[test]
+	pub fn read_proof_is_returned() {
+		let return_value = call(vec![vec![5u8]]);
+
+		assert_eq!(return_value.status, DirectRequestStatus::Ok);
+		assert_eq!(ReadProof::decode(&mut return_value.value.as_slice()).unwrap(), read_proof());
+	}
+
+	#[test]
+	pub fn prove_error_is_returned_as_error_status() {
+		let return_value = call(vec![]);
+
+		assert_eq!(return_value.status, DirectRequestStatus::Error);
+		assert_eq!(String::decode(&mut return_value.value.as_slice()).unwrap(), "no keys");
+	}
+}
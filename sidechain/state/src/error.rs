@@ -28,4 +28,6 @@ pub enum Error {
 	InvalidStorageDiff,
 	#[error("Codec error when accessing module: {1}, storage: {2}. Error: {0:?}")]
 	DB(codec::Error, String, String),
+	#[error("State trie error: {0}")]
+	Trie(String),
 }
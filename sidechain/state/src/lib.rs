@@ -25,9 +25,11 @@ extern crate sgx_tstd as std;
 
 mod error;
 mod impls;
+mod state_trie;
 
 pub use error::*;
 pub use impls::*;
+pub use state_trie::*;
 
 #[cfg(all(not(feature = "std"), feature = "sgx"))]
 mod sgx_reexports {
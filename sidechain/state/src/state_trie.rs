@@ -0,0 +1,183 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Merkle-trie commitment to the sidechain state.
+//!
+//! The state root in the sidechain header is the root of a trie over the state entries, built
+//! like a substrate storage trie. A client can therefore check a read proof against the header
+//! with `itp_storage::StorageProofChecker<BlakeTwo256>`.
+
+use crate::Error;
+use itp_sgx_externalities::SgxExternalitiesTrait;
+use itp_storage::{keys::storage_value_key, StorageProof};
+use sp_core::H256;
+use sp_runtime::traits::BlakeTwo256;
+use sp_std::{collections::btree_map::BTreeMap, ops::Deref, vec::Vec};
+use sp_trie::{
+	LayoutV1, MemoryDB, Recorder, Trie, TrieConfiguration, TrieDBBuilder, TrieDBMutBuilder, TrieMut,
+};
+use std::format;
+
+pub type StateTrieLayout = LayoutV1<BlakeTwo256>;
+
+/// Merkle-trie commitment to the state.
+pub trait StateRoot {
+	/// Root of the trie over all committed state entries.
+	fn state_root(&self) -> H256;
+
+	/// Build the trie over all committed state entries, to create read proofs.
+	///
+	/// This is as expensive as computing the state root, so the trie should be kept as long
+	/// as the state it was built from is current, e.g. for the duration of a sidechain block.
+	fn state_trie(&self) -> Result<StateTrie, Error>;
+
+	/// Proof of the values (or absence) of `keys`, together with the root it is valid for.
+	fn read_proof(&self, keys: &[Vec<u8>]) -> Result<(H256, StorageProof), Error> {
+		let trie = self.state_trie()?;
+		Ok((trie.root(), trie.read_proof(keys)?))
+	}
+}
+
+impl<T> StateRoot for T
+where
+	T: SgxExternalitiesTrait,
+	T::SgxExternalitiesType: Deref<Target = BTreeMap<Vec<u8>, Vec<u8>>>,
+{
+	fn state_root(&self) -> H256 {
+		StateTrieLayout::trie_root(committed_entries(self.state()))
+	}
+
+	fn state_trie(&self) -> Result<StateTrie, Error> {
+		StateTrie::build(committed_entries(self.state()))
+	}
+}
+
+/// Trie over the committed entries of a state.
+pub struct StateTrie {
+	root: H256,
+	db: MemoryDB<BlakeTwo256>,
+}
+
+impl StateTrie {
+	fn build<'a>(entries: impl Iterator<Item = (&'a Vec<u8>, &'a Vec<u8>)>) -> Result<Self, Error> {
+		let mut db = MemoryDB::<BlakeTwo256>::default();
+		let mut root = H256::default();
+		{
+			let mut trie = TrieDBMutBuilder::<StateTrieLayout>::new(&mut db, &mut root).build();
+			for (key, value) in entries {
+				trie.insert(key, value).map_err(|e| Error::Trie(format!("{:?}", e)))?;
+			}
+		}
+		Ok(Self { root, db })
+	}
+
+	pub fn root(&self) -> H256 {
+		self.root
+	}
+
+	/// Proof of the values (or absence) of `keys`, valid for [`Self::root`].
+	pub fn read_proof(&self, keys: &[Vec<u8>]) -> Result<StorageProof, Error> {
+		let mut recorder = Recorder::<StateTrieLayout>::new();
+		{
+			let trie = TrieDBBuilder::<StateTrieLayout>::new(&self.db, &self.root)
+				.with_recorder(&mut recorder)
+				.build();
+			for key in keys {
+				trie.get(key).map_err(|e| Error::Trie(format!("{:?}", e)))?;
+			}
+		}
+
+		Ok(recorder.drain().into_iter().map(|record| record.data).collect())
+	}
+}
+
+/// All state entries, except the ones written when a block is imported.
+///
+/// The last block and its hash are set after the state root of that block has been computed,
+/// so they cannot be part of the commitment. The header chain contains them anyway.
+fn committed_entries(
+	state: &BTreeMap<Vec<u8>, Vec<u8>>,
+) -> impl Iterator<Item = (&Vec<u8>, &Vec<u8>)> {
+	let block_keys =
+		[storage_value_key("System", "LastBlock"), storage_value_key("System", "LastHash")];
+	state.iter().filter(move |(key, _)| !block_keys.contains(key))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::LastBlockExt;
+	use itp_sgx_externalities::SgxExternalities;
+	use itp_storage::{Error as StorageError, StorageProofChecker};
+	use its_test::sidechain_block_builder::{SidechainBlockBuilder, SidechainBlockBuilderTrait};
+
+	fn state() -> SgxExternalities {
+		let mut state = SgxExternalities::default();
+		state.insert(b"balance".to_vec(), 42u128.to_le_bytes().to_vec());
+		state.insert(b"issuance".to_vec(), vec![7u8; 64]);
+		state.insert(b"nonce".to_vec(), vec![1u8]);
+		state
+	}
+
+	#[test]
+	fn read_proof_can_be_checked_against_state_root() {
+		let state = state();
+
+		let (root, proof) = state
+			.read_proof(&[b"balance".to_vec(), b"issuance".to_vec(), b"missing".to_vec()])
+			.unwrap();
+
+		assert_eq!(root, state.state_root());
+		let checker = StorageProofChecker::<BlakeTwo256>::new(root, proof).unwrap();
+		assert_eq!(checker.read_value(b"balance"), Ok(Some(42u128.to_le_bytes().to_vec())));
+		assert_eq!(checker.read_value(b"issuance"), Ok(Some(vec![7u8; 64])));
+		assert_eq!(checker.read_value(b"missing"), Ok(None));
+		assert_eq!(checker.read_value(b"nonce"), Err(StorageError::StorageValueUnavailable));
+	}
+
+	#[test]
+	fn state_trie_can_be_reused_for_several_read_proofs() {
+		let state = state();
+		let trie = state.state_trie().unwrap();
+
+		assert_eq!(trie.root(), state.state_root());
+		for key in [b"balance".to_vec(), b"nonce".to_vec()] {
+			let proof = trie.read_proof(&[key.clone()]).unwrap();
+			let checker = StorageProofChecker::<BlakeTwo256>::new(trie.root(), proof).unwrap();
+			assert_eq!(checker.read_value(&key).unwrap(), state.get(&key).cloned());
+		}
+	}
+
+	#[test]
+	fn state_root_changes_with_any_entry() {
+		let state = state();
+		let mut changed_state = state.clone();
+		changed_state.insert(b"nonce".to_vec(), vec![2u8]);
+
+		assert_ne!(state.state_root(), changed_state.state_root());
+	}
+
+	#[test]
+	fn state_root_does_not_commit_to_last_block() {
+		let state = state();
+		let mut state_after_import = state.clone();
+
+		state_after_import.set_last_block(&SidechainBlockBuilder::default().build());
+
+		assert_eq!(state.state_root(), state_after_import.state_root());
+	}
+}
@@ -49,6 +49,11 @@ impl SidechainDB {
 		self.db.write(batch).map_err(Error::Operational)
 	}
 
+	/// compacts the whole key range, to release the disk space of deleted entries
+	pub fn compact(&self) {
+		self.db.compact_range(None::<&[u8]>, None::<&[u8]>)
+	}
+
 	/// adds a given key value pair to the batch
 	pub fn add_to_batch<K: Encode, V: Encode>(batch: &mut WriteBatch, key: K, value: V) {
 		batch.put(key.encode(), &value.encode())
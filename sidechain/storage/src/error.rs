@@ -15,6 +15,7 @@
 
 */
 
+use its_primitives::types::BlockNumber;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -31,4 +32,10 @@ pub enum Error {
 	Decode(#[from] codec::Error),
 	#[error("Given block is not a successor of the last known block")]
 	HeaderAncestryMismatch,
+	#[error("Given block would revert the finalized block {0}")]
+	RevertsFinalizedBlock(BlockNumber),
+	#[error("Block {0} does not descend from the finalized block {1}")]
+	FinalizedBlockNotDescendant(BlockNumber, BlockNumber),
+	#[error("Invalid pruning policy: {0}, expected archive, blocks:<n> or hours:<n>")]
+	InvalidPruningPolicy(String),
 }
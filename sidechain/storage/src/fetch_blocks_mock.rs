@@ -17,13 +17,15 @@
 
 use crate::{error::Result, interface::FetchBlocks};
 use its_primitives::{
-	traits::ShardIdentifierFor,
-	types::{BlockHash, SignedBlock},
+	traits::{Block as BlockTrait, Header as HeaderTrait, ShardIdentifierFor, SignedBlock as _},
+	types::{BlockHash, BlockNumber, SignedBlock},
 };
 
 #[derive(Default)]
 pub struct FetchBlocksMock {
 	blocks_to_be_fetched: Vec<SignedBlock>,
+	finalized_block_number: Option<BlockNumber>,
+	retracted_blocks: Vec<BlockHash>,
 }
 
 impl FetchBlocksMock {
@@ -31,6 +33,16 @@ impl FetchBlocksMock {
 		self.blocks_to_be_fetched = blocks;
 		self
 	}
+
+	pub fn with_finalized_block_number(mut self, block_number: BlockNumber) -> Self {
+		self.finalized_block_number = Some(block_number);
+		self
+	}
+
+	pub fn with_retracted_blocks(mut self, block_hashes: Vec<BlockHash>) -> Self {
+		self.retracted_blocks = block_hashes;
+		self
+	}
 }
 
 impl FetchBlocks<SignedBlock> for FetchBlocksMock {
@@ -50,4 +62,41 @@ impl FetchBlocks<SignedBlock> for FetchBlocksMock {
 	) -> Result<Vec<SignedBlock>> {
 		Ok(self.blocks_to_be_fetched.clone())
 	}
+
+	fn fetch_block_by_hash(&self, block_hash: &BlockHash) -> Result<Option<SignedBlock>> {
+		Ok(self.blocks_to_be_fetched.iter().find(|b| b.hash() == *block_hash).cloned())
+	}
+
+	fn fetch_block_by_number(
+		&self,
+		_shard_identifier: &ShardIdentifierFor<SignedBlock>,
+		block_number: BlockNumber,
+	) -> Result<Option<SignedBlock>> {
+		Ok(self
+			.blocks_to_be_fetched
+			.iter()
+			.find(|b| b.block().header().block_number() == block_number)
+			.cloned())
+	}
+
+	fn fetch_latest_block(
+		&self,
+		_shard_identifier: &ShardIdentifierFor<SignedBlock>,
+	) -> Result<Option<SignedBlock>> {
+		Ok(self.blocks_to_be_fetched.last().cloned())
+	}
+
+	fn fetch_finalized_block(
+		&self,
+		shard_identifier: &ShardIdentifierFor<SignedBlock>,
+	) -> Result<Option<SignedBlock>> {
+		match self.finalized_block_number {
+			Some(block_number) => self.fetch_block_by_number(shard_identifier, block_number),
+			None => Ok(None),
+		}
+	}
+
+	fn is_retracted_block(&self, block_hash: &BlockHash) -> Result<bool> {
+		Ok(self.retracted_blocks.contains(block_hash))
+	}
 }
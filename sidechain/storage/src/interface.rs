@@ -20,25 +20,66 @@ use mockall::predicate::*;
 #[cfg(test)]
 use mockall::*;
 
-use super::{storage::SidechainStorage, Result};
+use super::{
+	pruning::{PruningPolicy, ShardStorageInfo},
+	storage::{LastSidechainBlock, SidechainStorage},
+	Result,
+};
 use its_primitives::{
-	traits::{ShardIdentifierFor, SignedBlock as SignedBlockT},
+	traits::{Block as BlockT, Header as HeaderT, ShardIdentifierFor, SignedBlock as SignedBlockT},
 	types::{BlockHash, BlockNumber},
 };
-use parking_lot::RwLock;
-use std::path::PathBuf;
+use parking_lot::{Mutex, RwLock};
+use std::{
+	path::PathBuf,
+	sync::mpsc::{channel, Receiver, Sender},
+	time::{SystemTime, UNIX_EPOCH},
+};
 
 /// Lock wrapper around sidechain storage
 pub struct SidechainStorageLock<SignedBlock: SignedBlockT> {
 	storage: RwLock<SidechainStorage<SignedBlock>>,
+	head_subscribers: Mutex<Vec<Sender<HeadNotification<SignedBlock>>>>,
 }
 
 impl<SignedBlock: SignedBlockT> SidechainStorageLock<SignedBlock> {
 	pub fn from_base_path(path: PathBuf) -> Result<SidechainStorageLock<SignedBlock>> {
 		Ok(SidechainStorageLock {
 			storage: RwLock::new(SidechainStorage::<SignedBlock>::load_from_base_path(path)?),
+			head_subscribers: Mutex::new(Vec::new()),
 		})
 	}
+
+	#[cfg(test)]
+	pub(crate) fn head_subscriber_count(&self) -> usize {
+		self.head_subscribers.lock().len()
+	}
+
+	fn notify_heads(&self, notifications: Vec<HeadNotification<SignedBlock>>) {
+		if notifications.is_empty() {
+			return
+		}
+		self.head_subscribers.lock().retain(|subscriber| {
+			notifications
+				.iter()
+				.all(|notification| subscriber.send(notification.clone()).is_ok())
+		});
+	}
+}
+
+/// Change of the head of a shard, see [`SubscribeHeads`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HeadNotification<SignedBlock> {
+	/// Block that was stored on the best chain of its shard.
+	New(SignedBlock),
+	/// Block that became the finalized block of its shard.
+	Finalized(SignedBlock),
+}
+
+pub trait SubscribeHeads<SignedBlock: SignedBlockT> {
+	/// Receive a notification for every block that is stored on the best chain or finalized,
+	/// until the receiver is dropped.
+	fn subscribe_heads(&self) -> Receiver<HeadNotification<SignedBlock>>;
 }
 
 /// Storage interface Trait
@@ -48,11 +89,29 @@ pub trait BlockStorage<SignedBlock: SignedBlockT> {
 	// so it needs to be defined somewhere more global.
 	// type SignedBlock: SignedBlockT;
 	fn store_blocks(&self, blocks: Vec<SignedBlock>) -> Result<()>;
+
+	/// Mark a block and all its ancestors as final.
+	fn finalize_block(
+		&self,
+		shard_identifier: &ShardIdentifierFor<SignedBlock>,
+		block_number: BlockNumber,
+		block_hash: BlockHash,
+	) -> Result<()>;
 }
 
 pub trait BlockPruner {
-	/// Prune all blocks except the newest n, where n = `number_of_blocks_to_keep`.
-	fn prune_blocks_except(&self, number_of_blocks_to_keep: u64);
+	/// Prune blocks according to the pruning policy. Blocks that are not final are kept.
+	fn prune_blocks(&self, pruning_policy: &PruningPolicy);
+
+	/// Compact the storage, to release the disk space of pruned blocks.
+	fn compact(&self);
+}
+
+pub trait ShardStorageReport<SignedBlock: SignedBlockT> {
+	/// Storage usage of every shard.
+	fn shard_storage_infos(
+		&self,
+	) -> Result<Vec<(ShardIdentifierFor<SignedBlock>, ShardStorageInfo)>>;
 }
 
 #[cfg_attr(test, automock)]
@@ -77,17 +136,112 @@ pub trait FetchBlocks<SignedBlock: SignedBlockT> {
 		block_hash_until: &BlockHash,
 		shard_identifier: &ShardIdentifierFor<SignedBlock>,
 	) -> Result<Vec<SignedBlock>>;
+
+	/// Fetch a block by its hash.
+	fn fetch_block_by_hash(&self, block_hash: &BlockHash) -> Result<Option<SignedBlock>>;
+
+	/// Fetch the block of our best chain with the given number.
+	fn fetch_block_by_number(
+		&self,
+		shard_identifier: &ShardIdentifierFor<SignedBlock>,
+		block_number: BlockNumber,
+	) -> Result<Option<SignedBlock>>;
+
+	/// Fetch the last block of a shard.
+	fn fetch_latest_block(
+		&self,
+		shard_identifier: &ShardIdentifierFor<SignedBlock>,
+	) -> Result<Option<SignedBlock>>;
+
+	/// Fetch the latest finalized block of a shard.
+	fn fetch_finalized_block(
+		&self,
+		shard_identifier: &ShardIdentifierFor<SignedBlock>,
+	) -> Result<Option<SignedBlock>>;
+
+	/// Whether a block was on our chain, and dropped from it by a re-org.
+	///
+	/// Retracted blocks are deleted, only the latest re-orgs since startup are known.
+	fn is_retracted_block(&self, block_hash: &BlockHash) -> Result<bool>;
 }
 
 impl<SignedBlock: SignedBlockT> BlockStorage<SignedBlock> for SidechainStorageLock<SignedBlock> {
 	fn store_blocks(&self, blocks: Vec<SignedBlock>) -> Result<()> {
-		self.storage.write().store_blocks(blocks)
+		let new_heads = {
+			let mut storage = self.storage.write();
+			storage.store_blocks(blocks.clone())?;
+
+			// Blocks that could not be stored are not on our best chain.
+			blocks
+				.into_iter()
+				.filter(|block| {
+					let header = block.block().header();
+					storage
+						.get_block_hash(&header.shard_id(), header.block_number())
+						.map(|maybe_hash| maybe_hash == Some(block.hash()))
+						.unwrap_or(false)
+				})
+				.map(HeadNotification::New)
+				.collect()
+		};
+		self.notify_heads(new_heads);
+		Ok(())
+	}
+
+	fn finalize_block(
+		&self,
+		shard_identifier: &ShardIdentifierFor<SignedBlock>,
+		block_number: BlockNumber,
+		block_hash: BlockHash,
+	) -> Result<()> {
+		let finalized_head = {
+			let mut storage = self.storage.write();
+			storage.finalize_block(
+				shard_identifier,
+				LastSidechainBlock { hash: block_hash, number: block_number },
+			)?;
+			match storage.finalized_block_of_shard(shard_identifier) {
+				Some(finalized) if finalized.hash == block_hash =>
+					storage.get_block(&block_hash)?,
+				_ => None,
+			}
+		};
+		self.notify_heads(finalized_head.into_iter().map(HeadNotification::Finalized).collect());
+		Ok(())
+	}
+}
+
+impl<SignedBlock: SignedBlockT> SubscribeHeads<SignedBlock> for SidechainStorageLock<SignedBlock> {
+	fn subscribe_heads(&self) -> Receiver<HeadNotification<SignedBlock>> {
+		let (sender, receiver) = channel();
+		self.head_subscribers.lock().push(sender);
+		receiver
 	}
 }
 
 impl<SignedBlock: SignedBlockT> BlockPruner for SidechainStorageLock<SignedBlock> {
-	fn prune_blocks_except(&self, number_of_blocks_to_keep: BlockNumber) {
-		self.storage.write().prune_shards(number_of_blocks_to_keep);
+	fn prune_blocks(&self, pruning_policy: &PruningPolicy) {
+		let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+		self.storage.write().prune_shards(pruning_policy, now);
+	}
+
+	fn compact(&self) {
+		self.storage.read().compact();
+	}
+}
+
+impl<SignedBlock: SignedBlockT> ShardStorageReport<SignedBlock>
+	for SidechainStorageLock<SignedBlock>
+{
+	fn shard_storage_infos(
+		&self,
+	) -> Result<Vec<(ShardIdentifierFor<SignedBlock>, ShardStorageInfo)>> {
+		let storage = self.storage.read();
+		storage
+			.shards()
+			.iter()
+			.map(|shard| Ok((*shard, storage.shard_storage_info(shard)?)))
+			.collect()
 	}
 }
 
@@ -110,4 +264,46 @@ impl<SignedBlock: SignedBlockT> FetchBlocks<SignedBlock> for SidechainStorageLoc
 			.read()
 			.get_blocks_in_range(block_hash_from, block_hash_until, shard_identifier)
 	}
+
+	fn fetch_block_by_hash(&self, block_hash: &BlockHash) -> Result<Option<SignedBlock>> {
+		self.storage.read().get_block(block_hash)
+	}
+
+	fn fetch_block_by_number(
+		&self,
+		shard_identifier: &ShardIdentifierFor<SignedBlock>,
+		block_number: BlockNumber,
+	) -> Result<Option<SignedBlock>> {
+		let storage = self.storage.read();
+		match storage.get_block_hash(shard_identifier, block_number)? {
+			Some(block_hash) => storage.get_block(&block_hash),
+			None => Ok(None),
+		}
+	}
+
+	fn fetch_latest_block(
+		&self,
+		shard_identifier: &ShardIdentifierFor<SignedBlock>,
+	) -> Result<Option<SignedBlock>> {
+		let storage = self.storage.read();
+		match storage.last_block_of_shard(shard_identifier) {
+			Some(last_block) => storage.get_block(&last_block.hash),
+			None => Ok(None),
+		}
+	}
+
+	fn fetch_finalized_block(
+		&self,
+		shard_identifier: &ShardIdentifierFor<SignedBlock>,
+	) -> Result<Option<SignedBlock>> {
+		let storage = self.storage.read();
+		match storage.finalized_block_of_shard(shard_identifier) {
+			Some(finalized_block) => storage.get_block(&finalized_block.hash),
+			None => Ok(None),
+		}
+	}
+
+	fn is_retracted_block(&self, block_hash: &BlockHash) -> Result<bool> {
+		Ok(self.storage.read().is_retracted(block_hash))
+	}
 }
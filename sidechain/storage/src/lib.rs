@@ -17,7 +17,7 @@
 
 #![cfg_attr(test, feature(assert_matches))]
 
-use its_primitives::types::BlockNumber;
+use log::*;
 use std::{
 	sync::Arc,
 	thread,
@@ -27,8 +27,12 @@ use std::{
 mod db;
 mod error;
 pub mod interface;
+mod pruning;
 mod storage;
 
+#[cfg(test)]
+mod storage_tests_fetch_block;
+
 #[cfg(test)]
 mod storage_tests_get_blocks_after;
 
@@ -42,15 +46,24 @@ mod test_utils;
 pub mod fetch_blocks_mock;
 
 pub use error::{Error, Result};
-pub use interface::{BlockPruner, BlockStorage, SidechainStorageLock};
+pub use interface::{
+	BlockPruner, BlockStorage, HeadNotification, ShardStorageReport, SidechainStorageLock,
+	SubscribeHeads,
+};
+pub use pruning::{PruningPolicy, ShardStorageInfo};
 
 pub fn start_sidechain_pruning_loop<D>(
 	storage: &Arc<D>,
 	purge_interval: u64,
-	purge_limit: BlockNumber,
+	pruning_policy: PruningPolicy,
 ) where
 	D: BlockPruner,
 {
+	if pruning_policy == PruningPolicy::Archive {
+		info!("Sidechain storage runs in archive mode, blocks are never pruned");
+		return
+	}
+
 	let interval_time = Duration::from_secs(purge_interval);
 	let mut interval_start = SystemTime::now();
 	loop {
@@ -58,7 +71,7 @@ pub fn start_sidechain_pruning_loop<D>(
 			if elapsed >= interval_time {
 				// update interval time
 				interval_start = SystemTime::now();
-				storage.prune_blocks_except(purge_limit);
+				storage.prune_blocks(&pruning_policy);
 			} else {
 				// sleep for the rest of the interval
 				let sleep_time = interval_time - elapsed;
@@ -0,0 +1,120 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+//! Pruning policies of the sidechain storage.
+
+use super::Error;
+use itp_settings::files::SIDECHAIN_PURGE_LIMIT;
+use its_primitives::types::BlockNumber;
+use std::{str::FromStr, time::Duration};
+
+const SECONDS_PER_HOUR: u64 = 3600;
+
+/// Defines which blocks are removed when the sidechain storage is pruned.
+///
+/// Independent of the policy, blocks that are not final yet, and the last block of a shard,
+/// are never pruned. Shards without any finalized block are pruned by block count,
+/// see [`PruningPolicy::without_finality`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PruningPolicy {
+	/// Keep the newest n blocks of every shard.
+	KeepBlocks(BlockNumber),
+	/// Keep all blocks that are younger than the given duration.
+	KeepDuration(Duration),
+	/// Never prune, e.g. for block explorers.
+	Archive,
+}
+
+impl Default for PruningPolicy {
+	fn default() -> Self {
+		PruningPolicy::KeepBlocks(SIDECHAIN_PURGE_LIMIT)
+	}
+}
+
+impl PruningPolicy {
+	/// Policy for shards without a finalized block.
+	///
+	/// We fall back to pruning by block count then, a duration based policy keeps the default
+	/// number of blocks.
+	pub fn without_finality(&self) -> PruningPolicy {
+		match self {
+			PruningPolicy::KeepDuration(_) => PruningPolicy::default(),
+			policy => *policy,
+		}
+	}
+}
+
+/// Parses `archive`, `blocks:<n>` or `hours:<n>`.
+impl FromStr for PruningPolicy {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let invalid = || Error::InvalidPruningPolicy(s.to_string());
+
+		if s == "archive" {
+			return Ok(PruningPolicy::Archive)
+		}
+
+		let (kind, value) = s.split_once(':').ok_or_else(invalid)?;
+		let value: u64 = value.parse().map_err(|_| invalid())?;
+		match kind {
+			"blocks" => Ok(PruningPolicy::KeepBlocks(value)),
+			"hours" => Ok(PruningPolicy::KeepDuration(Duration::from_secs(
+				value.checked_mul(SECONDS_PER_HOUR).ok_or_else(invalid)?,
+			))),
+			_ => Err(invalid()),
+		}
+	}
+}
+
+/// Storage usage of a single shard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ShardStorageInfo {
+	/// Number of stored blocks.
+	pub number_of_blocks: u64,
+	/// Number of the oldest stored block.
+	pub first_block_number: Option<BlockNumber>,
+	/// Number of the newest stored block.
+	pub last_block_number: Option<BlockNumber>,
+	/// Number of the latest finalized block.
+	pub finalized_block_number: Option<BlockNumber>,
+	/// Encoded size of all stored blocks in bytes.
+	pub size_in_bytes: u64,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::assert_matches::assert_matches;
+
+	#[test]
+	fn parsing_pruning_policies_works() {
+		assert_eq!(PruningPolicy::from_str("archive").unwrap(), PruningPolicy::Archive);
+		assert_eq!(PruningPolicy::from_str("blocks:42").unwrap(), PruningPolicy::KeepBlocks(42));
+		assert_eq!(
+			PruningPolicy::from_str("hours:2").unwrap(),
+			PruningPolicy::KeepDuration(Duration::from_secs(7200))
+		);
+	}
+
+	#[test]
+	fn parsing_invalid_pruning_policies_fails() {
+		for invalid in ["", "blocks", "blocks:", "blocks:-1", "days:1", "hours:x"] {
+			assert_matches!(PruningPolicy::from_str(invalid), Err(Error::InvalidPruningPolicy(_)));
+		}
+	}
+}
@@ -15,22 +15,38 @@
 
 */
 
-use super::{db::SidechainDB, Error, Result};
+use super::{
+	db::SidechainDB,
+	pruning::{PruningPolicy, ShardStorageInfo},
+	Error, Result,
+};
 use codec::{Decode, Encode};
 use itp_settings::files::SIDECHAIN_STORAGE_PATH;
 use its_primitives::{
-	traits::{Block as BlockTrait, Header as HeaderTrait, SignedBlock as SignedBlockT},
+	traits::{
+		Block as BlockTrait, BlockData as BlockDataTrait, Header as HeaderTrait,
+		SignedBlock as SignedBlockT,
+	},
 	types::{BlockHash, BlockNumber},
 };
 use log::*;
 use rocksdb::WriteBatch;
 use sp_core::H256;
-use std::{collections::HashMap, fmt::Debug, path::PathBuf};
+use std::{
+	collections::{HashMap, VecDeque},
+	fmt::Debug,
+	path::PathBuf,
+	time::Duration,
+};
 
 /// key value of sidechain db of last block
 const LAST_BLOCK_KEY: &[u8] = b"last_sidechainblock";
 /// key value of the stored shards vector
 const STORED_SHARDS_KEY: &[u8] = b"stored_shards";
+/// key value of sidechain db of the finalized block
+const FINALIZED_BLOCK_KEY: &[u8] = b"finalized_sidechainblock";
+/// Number of retracted blocks we remember, over all shards.
+const MAX_RETRACTED_BLOCKS: usize = 1024;
 
 /// ShardIdentifier type
 type ShardIdentifierFor<B> =
@@ -55,6 +71,10 @@ pub struct SidechainStorage<SignedBlock: SignedBlockT> {
 	shards: Vec<ShardIdentifierFor<SignedBlock>>,
 	/// map to last sidechain block of every shard
 	last_blocks: HashMap<ShardIdentifierFor<SignedBlock>, LastSidechainBlock>,
+	/// map to the latest finalized sidechain block of every shard
+	finalized_blocks: HashMap<ShardIdentifierFor<SignedBlock>, LastSidechainBlock>,
+	/// blocks that were dropped from our chain by a re-org since the storage was loaded, oldest first
+	retracted_blocks: VecDeque<BlockHash>,
 }
 
 impl<SignedBlock: SignedBlockT> SidechainStorage<SignedBlock> {
@@ -64,7 +84,13 @@ impl<SignedBlock: SignedBlockT> SidechainStorage<SignedBlock> {
 	pub fn load_from_base_path(base_path: PathBuf) -> Result<SidechainStorage<SignedBlock>> {
 		// load db
 		let db = SidechainDB::open_default(base_path.join(SIDECHAIN_STORAGE_PATH))?;
-		let mut storage = SidechainStorage { db, shards: vec![], last_blocks: HashMap::new() };
+		let mut storage = SidechainStorage {
+			db,
+			shards: vec![],
+			last_blocks: HashMap::new(),
+			finalized_blocks: HashMap::new(),
+			retracted_blocks: VecDeque::new(),
+		};
 		storage.shards = storage.load_shards_from_db()?;
 		// get last block of each shard
 		for shard in storage.shards.iter() {
@@ -74,6 +100,9 @@ impl<SignedBlock: SignedBlockT> SidechainStorage<SignedBlock> {
 				// an empty shard sidechain storage should not exist. Consider deleting this shard from the shards list.
 				error!("Sidechain storage of shard {:?} is empty", shard);
 			}
+			if let Some(finalized_block) = storage.db.get((FINALIZED_BLOCK_KEY, *shard))? {
+				storage.finalized_blocks.insert(*shard, finalized_block);
+			}
 		}
 		Ok(storage)
 	}
@@ -91,6 +120,69 @@ impl<SignedBlock: SignedBlockT> SidechainStorage<SignedBlock> {
 		self.last_blocks.get(shard)
 	}
 
+	/// gets the latest finalized block of the given shard
+	pub fn finalized_block_of_shard(
+		&self,
+		shard: &ShardIdentifierFor<SignedBlock>,
+	) -> Option<&LastSidechainBlock> {
+		self.finalized_blocks.get(shard)
+	}
+
+	/// Whether the block was on our chain, and dropped from it by a re-org.
+	///
+	/// Only re-orgs since the storage was loaded are known, and only the latest ones.
+	pub fn is_retracted(&self, block_hash: &BlockHash) -> bool {
+		self.retracted_blocks.contains(block_hash)
+	}
+
+	/// Marks the given block, and with it all its ancestors, as final.
+	///
+	/// Finality never goes backwards, an older finalized block is ignored. The block must
+	/// descend from the current finalized block.
+	pub fn finalize_block(
+		&mut self,
+		shard: &ShardIdentifierFor<SignedBlock>,
+		finalized_block: LastSidechainBlock,
+	) -> Result<()> {
+		if let Some(current) = self.finalized_blocks.get(shard) {
+			if current.number >= finalized_block.number {
+				debug!(
+					"[Sidechain DB] Ignoring finalization of block {} in shard {:?}, block {} is already final",
+					finalized_block.number, shard, current.number
+				);
+				return Ok(())
+			}
+			if !self.is_descendant_of(&finalized_block, current)? {
+				return Err(Error::FinalizedBlockNotDescendant(
+					finalized_block.number,
+					current.number,
+				))
+			}
+		}
+		let mut batch = WriteBatch::default();
+		SidechainDB::add_to_batch(&mut batch, (FINALIZED_BLOCK_KEY, *shard), finalized_block);
+		self.db.write(batch)?;
+		self.finalized_blocks.insert(*shard, finalized_block);
+		Ok(())
+	}
+
+	/// Whether `ancestor` is found by following the parents of `block` in storage.
+	fn is_descendant_of(
+		&self,
+		block: &LastSidechainBlock,
+		ancestor: &LastSidechainBlock,
+	) -> Result<bool> {
+		let mut current = *block;
+		while current.number > ancestor.number {
+			let parent_hash = match self.get_block(&current.hash)? {
+				Some(signed_block) => signed_block.block().header().parent_hash(),
+				None => return Ok(false),
+			};
+			current = LastSidechainBlock { hash: parent_hash, number: current.number - 1 };
+		}
+		Ok(current == *ancestor)
+	}
+
 	/// gets the block hash of the sidechain block of the given shard and block number, if there is such a block
 	pub fn get_block_hash(
 		&self,
@@ -101,7 +193,6 @@ impl<SignedBlock: SignedBlockT> SidechainStorage<SignedBlock> {
 	}
 
 	/// gets the block of the given blockhash, if there is such a block
-	#[allow(unused)]
 	pub fn get_block(&self, block_hash: &BlockHash) -> Result<Option<SignedBlock>> {
 		self.db.get(block_hash)
 	}
@@ -199,6 +290,9 @@ impl<SignedBlock: SignedBlockT> SidechainStorage<SignedBlock> {
 			current_block_number = previous_block.number;
 			self.delete_block(&mut batch, &previous_block.hash, &current_block_number, shard);
 		}
+		// Remove the finalized block of the shard.
+		SidechainDB::delete_to_batch(&mut batch, (FINALIZED_BLOCK_KEY, *shard));
+		self.finalized_blocks.remove(shard);
 		// Remove shard from list.
 		// STORED_SHARDS_KEY -> Vec<(Shard)>
 		self.shards.retain(|&x| x != *shard);
@@ -233,19 +327,111 @@ impl<SignedBlock: SignedBlockT> SidechainStorage<SignedBlock> {
 		}
 	}
 
-	/// Prunes all shards except for the newest blocks (according to blocknumber).
-	pub fn prune_shards(&mut self, number_of_blocks_to_keep: BlockNumber) {
+	/// Prunes the blocks of all shards according to the pruning policy.
+	///
+	/// Blocks that are not final yet are never pruned, neither is the last block of a shard.
+	/// `now` is the duration since the unix epoch, used by time based policies.
+	pub fn prune_shards(&mut self, pruning_policy: &PruningPolicy, now: Duration) {
 		for shard in self.shards().clone() {
-			// get last block:
-			if let Some(last_block) = self.last_block_of_shard(&shard) {
-				let threshold_block = last_block.number - number_of_blocks_to_keep;
-				if let Err(e) = self.prune_shard_from_block_number(&shard, threshold_block) {
-					error!("Could not purge shard {:?} due to {:?}", shard, e);
-				}
-			} else {
-				error!("Last block not found in shard {:?}", shard);
+			match self.pruning_threshold(&shard, pruning_policy, now) {
+				Ok(Some(threshold_block)) => {
+					if let Err(e) = self.prune_shard_from_block_number(&shard, threshold_block) {
+						error!("Could not purge shard {:?} due to {:?}", shard, e);
+					}
+				},
+				Ok(None) => debug!("[Sidechain DB] Nothing to prune in shard {:?}", shard),
+				Err(e) =>
+					error!("Could not determine blocks to prune of shard {:?}: {:?}", shard, e),
+			}
+		}
+	}
+
+	/// Gathers the storage usage of the given shard.
+	pub fn shard_storage_info(
+		&self,
+		shard: &ShardIdentifierFor<SignedBlock>,
+	) -> Result<ShardStorageInfo> {
+		let mut info = ShardStorageInfo {
+			finalized_block_number: self.finalized_block_of_shard(shard).map(|b| b.number),
+			..Default::default()
+		};
+		let last_block = match self.last_block_of_shard(shard) {
+			Some(last_block) => *last_block,
+			None => return Ok(info),
+		};
+		info.last_block_number = Some(last_block.number);
+
+		let mut block_number = last_block.number;
+		while block_number > 0 {
+			let block = match self.get_block_hash(shard, block_number)? {
+				Some(block_hash) => self.get_block(&block_hash)?,
+				None => None,
+			};
+			match block {
+				Some(block) => {
+					info.number_of_blocks += 1;
+					info.size_in_bytes += block.encoded_size() as u64;
+					info.first_block_number = Some(block_number);
+				},
+				None => break,
+			}
+			block_number -= 1;
+		}
+		Ok(info)
+	}
+
+	/// Compacts the underlying database, which releases the disk space of pruned blocks.
+	pub fn compact(&self) {
+		self.db.compact()
+	}
+
+	/// Number of the newest block to prune. All blocks below are pruned as well.
+	fn pruning_threshold(
+		&self,
+		shard: &ShardIdentifierFor<SignedBlock>,
+		pruning_policy: &PruningPolicy,
+		now: Duration,
+	) -> Result<Option<BlockNumber>> {
+		let last_block = self.get_last_block_of_shard(shard)?;
+		let max_threshold = last_block.number.saturating_sub(1);
+		let (pruning_policy, max_threshold) = match self.finalized_block_of_shard(shard) {
+			Some(finalized_block) => (*pruning_policy, finalized_block.number.min(max_threshold)),
+			// Without finality we cannot tell which blocks are confirmed, so we do not let the
+			// storage grow without bounds and prune by block count.
+			None => (pruning_policy.without_finality(), max_threshold),
+		};
+
+		let threshold = match pruning_policy {
+			PruningPolicy::Archive => None,
+			PruningPolicy::KeepBlocks(number_of_blocks_to_keep) =>
+				last_block.number.checked_sub(*number_of_blocks_to_keep),
+			PruningPolicy::KeepDuration(duration) =>
+				self.newest_block_older_than(shard, max_threshold, now.saturating_sub(*duration))?,
+		};
+		Ok(threshold.map(|t| t.min(max_threshold)).filter(|t| *t > 0))
+	}
+
+	/// Number of the newest block, starting at `block_number`, with a timestamp before `time`.
+	fn newest_block_older_than(
+		&self,
+		shard: &ShardIdentifierFor<SignedBlock>,
+		mut block_number: BlockNumber,
+		time: Duration,
+	) -> Result<Option<BlockNumber>> {
+		let time_millis = time.as_millis() as u64;
+		while block_number > 0 {
+			let block = match self.get_block_hash(shard, block_number)? {
+				Some(block_hash) => self.get_block(&block_hash)?,
+				None => None,
+			};
+			match block {
+				Some(block) if block.block().block_data().timestamp() < time_millis =>
+					return Ok(Some(block_number)),
+				Some(_) => block_number -= 1,
+				None => break,
 			}
 		}
+		Ok(None)
 	}
 
 	fn add_block_to_batch(
@@ -256,7 +442,13 @@ impl<SignedBlock: SignedBlockT> SidechainStorage<SignedBlock> {
 	) -> Result<()> {
 		let shard = &signed_block.block().header().shard_id();
 		if self.shards.contains(shard) {
-			if !self.verify_block_ancestry(signed_block.block()) {
+			if let Some(parent_number) = self.reorg_parent(signed_block.block())? {
+				// Block of a fork that replaced our best chain, drop the retracted blocks.
+				let retracted_blocks = self.delete_blocks_after(batch, shard, parent_number)?;
+				self.retracted_blocks.extend(retracted_blocks);
+				let excess = self.retracted_blocks.len().saturating_sub(MAX_RETRACTED_BLOCKS);
+				self.retracted_blocks.drain(..excess);
+			} else if !self.verify_block_ancestry(signed_block.block()) {
 				// Do not include block if its not a direct ancestor of the last block in line.
 				return Err(Error::HeaderAncestryMismatch)
 			}
@@ -264,6 +456,9 @@ impl<SignedBlock: SignedBlockT> SidechainStorage<SignedBlock> {
 			self.shards.push(*shard);
 			*new_shard = true;
 		}
+		// A block of a fork we re-org back to is no longer retracted.
+		let block_hash = signed_block.hash();
+		self.retracted_blocks.retain(|hash| *hash != block_hash);
 		// Add block to DB batch.
 		self.add_last_block(batch, signed_block);
 		Ok(())
@@ -288,6 +483,67 @@ impl<SignedBlock: SignedBlockT> SidechainStorage<SignedBlock> {
 		true
 	}
 
+	/// Returns the number of the parent block, if the block is a re-org of our stored chain.
+	///
+	/// That is the case if its parent is part of the stored chain, but not the last block.
+	fn reorg_parent(
+		&self,
+		block: &<SignedBlock as SignedBlockT>::Block,
+	) -> Result<Option<BlockNumber>> {
+		let shard = &block.header().shard_id();
+		let block_number = block.header().block_number();
+		let last_block = match self.last_block_of_shard(shard) {
+			Some(last_block) => last_block,
+			None => return Ok(None),
+		};
+
+		if block_number == 0 || block_number > last_block.number {
+			return Ok(None)
+		}
+
+		let parent_number = block_number - 1;
+		let parent_hash = block.header().parent_hash();
+		let parent_is_stored = if parent_number == 0 {
+			parent_hash == BlockHash::default()
+		} else {
+			self.get_block_hash(shard, parent_number)? == Some(parent_hash)
+		};
+		let is_already_stored = self.get_block_hash(shard, block_number)? == Some(block.hash());
+
+		if parent_is_stored && !is_already_stored {
+			if let Some(finalized_block) = self.finalized_block_of_shard(shard) {
+				if finalized_block.number > parent_number {
+					return Err(Error::RevertsFinalizedBlock(finalized_block.number))
+				}
+			}
+			info!(
+				"[Sidechain DB] Re-org of shard {:?}: replacing blocks {} to {}",
+				*shard, block_number, last_block.number
+			);
+			Ok(Some(parent_number))
+		} else {
+			Ok(None)
+		}
+	}
+
+	/// Add delete commands for all blocks after the given block number to the WriteBatch.
+	fn delete_blocks_after(
+		&self,
+		batch: &mut WriteBatch,
+		shard: &ShardIdentifierFor<SignedBlock>,
+		block_number: BlockNumber,
+	) -> Result<Vec<BlockHash>> {
+		let last_block = self.get_last_block_of_shard(shard)?;
+		let mut deleted_blocks = Vec::new();
+		for number in (block_number + 1)..=last_block.number {
+			if let Some(block_hash) = self.get_block_hash(shard, number)? {
+				self.delete_block(batch, &block_hash, &number, shard);
+				deleted_blocks.push(block_hash);
+			}
+		}
+		Ok(deleted_blocks)
+	}
+
 	/// Implementations of helper functions, not meant for pub use
 	/// gets the previous block of given shard and block number, if there is one.
 	fn get_previous_block(
@@ -388,11 +644,13 @@ impl<SignedBlock: SignedBlockT> SidechainStorage<SignedBlock> {
 mod test {
 	use super::*;
 	use crate::test_utils::{
-		create_signed_block_with_shard as create_signed_block, create_temp_dir, get_storage,
+		create_signed_block_with_parenthash, create_signed_block_with_shard as create_signed_block,
+		create_signed_block_with_timestamp, create_temp_dir, get_storage,
 	};
 	use itp_types::ShardIdentifier;
 	use its_primitives::{traits::SignedBlock as SignedBlockT, types::SignedBlock};
 	use sp_core::H256;
+	use std::assert_matches::assert_matches;
 
 	#[test]
 	fn load_shards_from_db_works() {
@@ -776,6 +1034,152 @@ mod test {
 		}
 	}
 
+	#[test]
+	fn store_blocks_replaces_retracted_blocks_on_reorg() {
+		let temp_dir = create_temp_dir();
+		let block_1 = create_signed_block_with_parenthash(1, BlockHash::default());
+		let block_2a = create_signed_block_with_parenthash(2, block_1.hash());
+		let block_3a = create_signed_block_with_parenthash(3, block_2a.hash());
+		let block_2b = create_signed_block_with_parenthash(2, block_1.hash());
+		let block_3b = create_signed_block_with_parenthash(3, block_2b.hash());
+		let block_4b = create_signed_block_with_parenthash(4, block_3b.hash());
+		let shard = block_1.block().header().shard_id();
+
+		{
+			let mut sidechain_db = get_storage(temp_dir.path().to_path_buf());
+			sidechain_db
+				.store_blocks(vec![block_1.clone(), block_2a.clone(), block_3a.clone()])
+				.unwrap();
+			sidechain_db
+				.store_blocks(vec![block_2b.clone(), block_3b.clone(), block_4b.clone()])
+				.unwrap();
+
+			assert!(sidechain_db.is_retracted(&block_2a.hash()));
+			assert!(sidechain_db.is_retracted(&block_3a.hash()));
+			assert!(!sidechain_db.is_retracted(&block_2b.hash()));
+		}
+
+		{
+			let sidechain_db = get_storage(temp_dir.path().to_path_buf());
+			let last_block = sidechain_db.last_block_of_shard(&shard).unwrap();
+			assert_eq!(last_block.hash, block_4b.hash());
+			assert_eq!(last_block.number, 4);
+
+			assert_eq!(sidechain_db.get_block_hash(&shard, 2).unwrap(), Some(block_2b.hash()));
+			assert_eq!(sidechain_db.get_block_hash(&shard, 3).unwrap(), Some(block_3b.hash()));
+			assert!(sidechain_db.get_block(&block_2a.hash()).unwrap().is_none());
+			assert!(sidechain_db.get_block(&block_3a.hash()).unwrap().is_none());
+		}
+	}
+
+	#[test]
+	fn store_blocks_does_not_reorg_to_block_with_unknown_parent() {
+		let temp_dir = create_temp_dir();
+		let block_1 = create_signed_block_with_parenthash(1, BlockHash::default());
+		let block_2 = create_signed_block_with_parenthash(2, block_1.hash());
+		let block_3 = create_signed_block_with_parenthash(3, block_2.hash());
+		let unknown_parent_block = create_signed_block_with_parenthash(3, H256::random());
+		let shard = block_1.block().header().shard_id();
+
+		{
+			let mut sidechain_db = get_storage(temp_dir.path().to_path_buf());
+			sidechain_db
+				.store_blocks(vec![block_1.clone(), block_2.clone(), block_3.clone()])
+				.unwrap();
+			sidechain_db.store_blocks(vec![unknown_parent_block]).unwrap();
+		}
+
+		{
+			let sidechain_db = get_storage(temp_dir.path().to_path_buf());
+			let last_block = sidechain_db.last_block_of_shard(&shard).unwrap();
+			assert_eq!(last_block.hash, block_3.hash());
+			assert_eq!(sidechain_db.get_block_hash(&shard, 3).unwrap(), Some(block_3.hash()));
+		}
+	}
+
+	#[test]
+	fn store_blocks_does_not_reorg_finalized_blocks() {
+		let temp_dir = create_temp_dir();
+		let block_1 = create_signed_block_with_parenthash(1, BlockHash::default());
+		let block_2a = create_signed_block_with_parenthash(2, block_1.hash());
+		let block_3a = create_signed_block_with_parenthash(3, block_2a.hash());
+		let block_2b = create_signed_block_with_parenthash(2, block_1.hash());
+		let block_3b = create_signed_block_with_parenthash(3, block_2b.hash());
+		let shard = block_1.block().header().shard_id();
+
+		{
+			let mut sidechain_db = get_storage(temp_dir.path().to_path_buf());
+			sidechain_db
+				.store_blocks(vec![block_1.clone(), block_2a.clone(), block_3a.clone()])
+				.unwrap();
+			sidechain_db
+				.finalize_block(&shard, LastSidechainBlock { hash: block_2a.hash(), number: 2 })
+				.unwrap();
+			sidechain_db.store_blocks(vec![block_2b, block_3b]).unwrap();
+		}
+
+		{
+			let sidechain_db = get_storage(temp_dir.path().to_path_buf());
+			let last_block = sidechain_db.last_block_of_shard(&shard).unwrap();
+			assert_eq!(last_block.hash, block_3a.hash());
+			assert_eq!(sidechain_db.get_block_hash(&shard, 2).unwrap(), Some(block_2a.hash()));
+		}
+	}
+
+	#[test]
+	fn finalized_block_must_descend_from_the_previous_one() {
+		let temp_dir = create_temp_dir();
+		let block_1 = create_signed_block_with_parenthash(1, BlockHash::default());
+		let block_2a = create_signed_block_with_parenthash(2, block_1.hash());
+		let block_3a = create_signed_block_with_parenthash(3, block_2a.hash());
+		let block_2b = create_signed_block_with_parenthash(2, block_1.hash());
+		let block_3b = create_signed_block_with_parenthash(3, block_2b.hash());
+		let shard = block_1.block().header().shard_id();
+		let finalized_block_2a = LastSidechainBlock { hash: block_2a.hash(), number: 2 };
+
+		let mut sidechain_db = get_storage(temp_dir.path().to_path_buf());
+		sidechain_db
+			.store_blocks(vec![block_1, block_2a.clone(), block_3a.clone()])
+			.unwrap();
+		sidechain_db.finalize_block(&shard, finalized_block_2a).unwrap();
+
+		assert_matches!(
+			sidechain_db
+				.finalize_block(&shard, LastSidechainBlock { hash: block_3b.hash(), number: 3 }),
+			Err(Error::FinalizedBlockNotDescendant(3, 2))
+		);
+		assert_eq!(sidechain_db.finalized_block_of_shard(&shard), Some(&finalized_block_2a));
+
+		let finalized_block_3a = LastSidechainBlock { hash: block_3a.hash(), number: 3 };
+		sidechain_db.finalize_block(&shard, finalized_block_3a).unwrap();
+		assert_eq!(sidechain_db.finalized_block_of_shard(&shard), Some(&finalized_block_3a));
+	}
+
+	#[test]
+	fn finalized_block_is_persisted_and_never_goes_backwards() {
+		let temp_dir = create_temp_dir();
+		let block_1 = create_signed_block_with_parenthash(1, BlockHash::default());
+		let block_2 = create_signed_block_with_parenthash(2, block_1.hash());
+		let shard = block_1.block().header().shard_id();
+		let finalized_block_2 = LastSidechainBlock { hash: block_2.hash(), number: 2 };
+
+		{
+			let mut sidechain_db = get_storage(temp_dir.path().to_path_buf());
+			sidechain_db.store_blocks(vec![block_1.clone(), block_2]).unwrap();
+			assert!(sidechain_db.finalized_block_of_shard(&shard).is_none());
+
+			sidechain_db.finalize_block(&shard, finalized_block_2).unwrap();
+			sidechain_db
+				.finalize_block(&shard, LastSidechainBlock { hash: block_1.hash(), number: 1 })
+				.unwrap();
+		}
+
+		{
+			let sidechain_db = get_storage(temp_dir.path().to_path_buf());
+			assert_eq!(sidechain_db.finalized_block_of_shard(&shard), Some(&finalized_block_2));
+		}
+	}
+
 	#[test]
 	fn store_block_works() {
 		let temp_dir = create_temp_dir();
@@ -1116,8 +1520,10 @@ mod test {
 				.store_blocks(vec![block_three.clone(), block_three_s.clone()])
 				.unwrap();
 			sidechain_db.store_blocks(vec![block_four_s.clone()]).unwrap();
+			sidechain_db.finalize_block(&shard_one, last_block_one).unwrap();
+			sidechain_db.finalize_block(&shard_two, last_block_two).unwrap();
 
-			sidechain_db.prune_shards(2);
+			sidechain_db.prune_shards(&PruningPolicy::KeepBlocks(2), Duration::default());
 		}
 
 		{
@@ -1172,4 +1578,128 @@ mod test {
 			assert!(updated_sidechain_db.get_block(&block_two_s.hash()).unwrap().is_none());
 		}
 	}
+
+	#[test]
+	fn prune_shards_without_finalized_block_prunes_by_block_count() {
+		let temp_dir = create_temp_dir();
+		let shard = H256::from_low_u64_be(1);
+		let blocks: Vec<_> = (1..=4).map(|n| create_signed_block(n, shard)).collect();
+		let mut sidechain_db = get_storage(temp_dir.path().to_path_buf());
+		sidechain_db.store_blocks(blocks.clone()).unwrap();
+
+		sidechain_db.prune_shards(&PruningPolicy::KeepBlocks(2), Duration::default());
+
+		assert!(sidechain_db.get_block_hash(&shard, 1).unwrap().is_none());
+		assert!(sidechain_db.get_block_hash(&shard, 2).unwrap().is_none());
+		assert_eq!(sidechain_db.get_block_hash(&shard, 3).unwrap(), Some(blocks[2].hash()));
+		assert_eq!(sidechain_db.get_block_hash(&shard, 4).unwrap(), Some(blocks[3].hash()));
+	}
+
+	#[test]
+	fn prune_shards_never_prunes_blocks_that_are_not_final() {
+		let temp_dir = create_temp_dir();
+		let shard = H256::from_low_u64_be(1);
+		let blocks: Vec<_> = (1..=4).map(|n| create_signed_block(n, shard)).collect();
+		let mut sidechain_db = get_storage(temp_dir.path().to_path_buf());
+		sidechain_db.store_blocks(blocks.clone()).unwrap();
+
+		sidechain_db
+			.finalize_block(&shard, LastSidechainBlock { hash: blocks[1].hash(), number: 2 })
+			.unwrap();
+		sidechain_db.prune_shards(&PruningPolicy::KeepBlocks(1), Duration::default());
+
+		assert!(sidechain_db.get_block_hash(&shard, 1).unwrap().is_none());
+		assert!(sidechain_db.get_block_hash(&shard, 2).unwrap().is_none());
+		assert_eq!(sidechain_db.get_block_hash(&shard, 3).unwrap(), Some(blocks[2].hash()));
+		assert_eq!(sidechain_db.get_block_hash(&shard, 4).unwrap(), Some(blocks[3].hash()));
+	}
+
+	#[test]
+	fn prune_shards_keeps_the_last_block() {
+		let temp_dir = create_temp_dir();
+		let shard = H256::from_low_u64_be(1);
+		let blocks: Vec<_> = (1..=2).map(|n| create_signed_block(n, shard)).collect();
+		let mut sidechain_db = get_storage(temp_dir.path().to_path_buf());
+		sidechain_db.store_blocks(blocks.clone()).unwrap();
+		sidechain_db
+			.finalize_block(&shard, LastSidechainBlock { hash: blocks[1].hash(), number: 2 })
+			.unwrap();
+
+		sidechain_db.prune_shards(&PruningPolicy::KeepBlocks(0), Duration::default());
+
+		assert!(sidechain_db.get_block_hash(&shard, 1).unwrap().is_none());
+		assert_eq!(sidechain_db.get_block_hash(&shard, 2).unwrap(), Some(blocks[1].hash()));
+		assert_eq!(sidechain_db.last_block_of_shard(&shard).unwrap().number, 2);
+	}
+
+	#[test]
+	fn prune_shards_by_age_works() {
+		let temp_dir = create_temp_dir();
+		let shard = H256::from_low_u64_be(1);
+		let blocks: Vec<_> = (1..=4)
+			.map(|n| create_signed_block_with_timestamp(n, shard, n * 1000))
+			.collect();
+		let mut sidechain_db = get_storage(temp_dir.path().to_path_buf());
+		sidechain_db.store_blocks(blocks.clone()).unwrap();
+		sidechain_db
+			.finalize_block(&shard, LastSidechainBlock { hash: blocks[3].hash(), number: 4 })
+			.unwrap();
+
+		// Keep the blocks of the last 2.5 seconds, i.e. blocks 3 and 4.
+		sidechain_db.prune_shards(
+			&PruningPolicy::KeepDuration(Duration::from_millis(2500)),
+			Duration::from_millis(5000),
+		);
+
+		assert!(sidechain_db.get_block_hash(&shard, 1).unwrap().is_none());
+		assert!(sidechain_db.get_block_hash(&shard, 2).unwrap().is_none());
+		assert_eq!(sidechain_db.get_block_hash(&shard, 3).unwrap(), Some(blocks[2].hash()));
+		assert_eq!(sidechain_db.get_block_hash(&shard, 4).unwrap(), Some(blocks[3].hash()));
+	}
+
+	#[test]
+	fn archive_policy_never_prunes() {
+		let temp_dir = create_temp_dir();
+		let shard = H256::from_low_u64_be(1);
+		let blocks: Vec<_> = (1..=3).map(|n| create_signed_block(n, shard)).collect();
+		let mut sidechain_db = get_storage(temp_dir.path().to_path_buf());
+		sidechain_db.store_blocks(blocks.clone()).unwrap();
+		sidechain_db
+			.finalize_block(&shard, LastSidechainBlock { hash: blocks[2].hash(), number: 3 })
+			.unwrap();
+
+		sidechain_db.prune_shards(&PruningPolicy::Archive, Duration::default());
+
+		assert_eq!(sidechain_db.shard_storage_info(&shard).unwrap().number_of_blocks, 3);
+	}
+
+	#[test]
+	fn shard_storage_info_works() {
+		let temp_dir = create_temp_dir();
+		let shard = H256::from_low_u64_be(1);
+		let blocks: Vec<_> = (1..=3).map(|n| create_signed_block(n, shard)).collect();
+		let mut sidechain_db = get_storage(temp_dir.path().to_path_buf());
+		sidechain_db.store_blocks(blocks.clone()).unwrap();
+		sidechain_db
+			.finalize_block(&shard, LastSidechainBlock { hash: blocks[1].hash(), number: 2 })
+			.unwrap();
+		sidechain_db.prune_shards(&PruningPolicy::KeepBlocks(2), Duration::default());
+
+		let info = sidechain_db.shard_storage_info(&shard).unwrap();
+
+		assert_eq!(
+			info,
+			ShardStorageInfo {
+				number_of_blocks: 2,
+				first_block_number: Some(2),
+				last_block_number: Some(3),
+				finalized_block_number: Some(2),
+				size_in_bytes: (blocks[1].encoded_size() + blocks[2].encoded_size()) as u64,
+			}
+		);
+		assert_eq!(
+			sidechain_db.shard_storage_info(&H256::from_low_u64_be(2)).unwrap(),
+			ShardStorageInfo::default()
+		);
+	}
 }
@@ -0,0 +1,113 @@
+/*
+	Copyright 2021 Integritee AG and Supercomputing Systems AG
+
+	Licensed under the Apache License, Version 2.0 (the "License");
+	you may not use this file except in compliance with the License.
+	You may obtain a copy of the License at
+
+		http://www.apache.org/licenses/LICENSE-2.0
+
+	Unless required by applicable law or agreed to in writing, software
+	distributed under the License is distributed on an "AS IS" BASIS,
+	WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+	See the License for the specific language governing permissions and
+	limitations under the License.
+
+*/
+
+use crate::{
+	interface::{
+		BlockStorage, FetchBlocks, HeadNotification, SidechainStorageLock, SubscribeHeads,
+	},
+	test_utils::{
+		create_signed_block_with_parenthash as create_signed_block, create_temp_dir, default_shard,
+		fill_storage_with_blocks,
+	},
+};
+use its_primitives::{
+	traits::SignedBlock,
+	types::{BlockHash, SignedBlock as SignedSidechainBlock},
+};
+
+#[test]
+fn fetch_block_by_number_and_hash_works() {
+	let block_1 = create_signed_block(1, BlockHash::default());
+	let block_2 = create_signed_block(2, block_1.hash());
+
+	let temp_dir = fill_storage_with_blocks(vec![block_1.clone(), block_2.clone()]);
+
+	{
+		let storage =
+			SidechainStorageLock::<SignedSidechainBlock>::from_base_path(temp_dir.path().into())
+				.unwrap();
+
+		assert_eq!(storage.fetch_block_by_number(&default_shard(), 1).unwrap(), Some(block_1));
+		assert_eq!(storage.fetch_block_by_hash(&block_2.hash()).unwrap(), Some(block_2));
+		assert_eq!(storage.fetch_block_by_number(&default_shard(), 3).unwrap(), None);
+		assert_eq!(storage.fetch_block_by_hash(&BlockHash::from_low_u64_be(1)).unwrap(), None);
+	}
+}
+
+#[test]
+fn fetch_latest_block_returns_last_block_of_shard() {
+	let block_1 = create_signed_block(1, BlockHash::default());
+	let block_2 = create_signed_block(2, block_1.hash());
+
+	let temp_dir = fill_storage_with_blocks(vec![block_1, block_2.clone()]);
+
+	{
+		let storage =
+			SidechainStorageLock::<SignedSidechainBlock>::from_base_path(temp_dir.path().into())
+				.unwrap();
+
+		assert_eq!(storage.fetch_latest_block(&default_shard()).unwrap(), Some(block_2));
+		assert_eq!(storage.fetch_latest_block(&BlockHash::from_low_u64_be(1)).unwrap(), None);
+	}
+}
+
+#[test]
+fn head_subscribers_are_notified_of_stored_and_finalized_blocks() {
+	let block_1 = create_signed_block(1, BlockHash::default());
+	let block_2 = create_signed_block(2, block_1.hash());
+	let unknown_parent_block = create_signed_block(4, BlockHash::from_low_u64_be(3));
+
+	let temp_dir = create_temp_dir();
+	{
+		let storage =
+			SidechainStorageLock::<SignedSidechainBlock>::from_base_path(temp_dir.path().into())
+				.unwrap();
+		let head_notifications = storage.subscribe_heads();
+
+		storage.store_blocks(vec![block_1.clone(), block_2.clone()]).unwrap();
+		storage.store_blocks(vec![unknown_parent_block]).unwrap();
+		storage.finalize_block(&default_shard(), 1, block_1.hash()).unwrap();
+
+		assert_eq!(
+			head_notifications.try_iter().collect::<Vec<_>>(),
+			vec![
+				HeadNotification::New(block_1.clone()),
+				HeadNotification::New(block_2),
+				HeadNotification::Finalized(block_1),
+			]
+		);
+	}
+}
+
+#[test]
+fn dropped_head_subscribers_are_removed() {
+	let block_1 = create_signed_block(1, BlockHash::default());
+
+	let temp_dir = create_temp_dir();
+	{
+		let storage =
+			SidechainStorageLock::<SignedSidechainBlock>::from_base_path(temp_dir.path().into())
+				.unwrap();
+		drop(storage.subscribe_heads());
+		let head_notifications = storage.subscribe_heads();
+
+		storage.store_blocks(vec![block_1.clone()]).unwrap();
+
+		assert_eq!(head_notifications.recv().unwrap(), HeadNotification::New(block_1));
+		assert_eq!(storage.head_subscriber_count(), 1);
+	}
+}
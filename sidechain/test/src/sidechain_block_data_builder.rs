@@ -92,6 +92,7 @@ impl SidechainBlockDataBuilder {
 			layer_one_head: self.layer_one_head,
 			signed_top_hashes: self.signed_top_hashes,
 			encrypted_state_diff: self.encrypted_state_diff,
+			auditor_state_diff: None,
 		}
 	}
 }